@@ -0,0 +1,77 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Number of allocations made while decoding a long list, comparing the
+//! decoding with the collection preallocated from the length prefix against
+//! the collection growing by pushing the items. Run with
+//! `cargo bench --bench alloc`.
+
+#[path = "../src/test/counting_alloc.rs"]
+mod counting_alloc;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use amplify::confinement::{MediumVec, U24};
+use strict_encoding::{
+    ReadRaw, SliceReader, StrictDecode, StrictEncode, StrictReader, StrictWriter,
+};
+
+const ITEMS: u32 = 100_000;
+const MAX: usize = usize::MAX;
+
+/// Decodes the list by pushing the items into a growing vector.
+fn decode_pushing(data: &[u8]) -> Vec<u32> {
+    let mut raw = SliceReader::new::<MAX>(data);
+    let len = raw.read_raw_len::<U24>().expect("valid data");
+    let mut reader = StrictReader::with(raw);
+    let mut list = Vec::new();
+    for _ in 0..len {
+        list.push(u32::strict_decode(&mut reader).expect("valid data"));
+    }
+    list
+}
+
+fn decode_preallocated(data: &[u8]) -> Vec<u32> {
+    let mut reader = StrictReader::from_slice::<MAX>(data);
+    MediumVec::<u32>::strict_decode(&mut reader).expect("valid data").release()
+}
+
+fn run(name: &str, data: &[u8], decode: impl Fn(&[u8]) -> Vec<u32>) -> (usize, usize) {
+    let start = Instant::now();
+    let (list, allocs) = counting_alloc::allocations(|| black_box(decode(black_box(data))));
+    let elapsed = start.elapsed();
+    let counts = (allocs.count - allocs.reallocs, allocs.reallocs);
+    assert_eq!(list.len(), ITEMS as usize);
+    println!("{name:>12}: {} allocations, {} reallocations, {elapsed:?}", counts.0, counts.1);
+    counts
+}
+
+fn main() {
+    let list = MediumVec::try_from_iter(0..ITEMS).unwrap();
+    let writer = list.strict_encode(StrictWriter::in_memory::<MAX>()).unwrap();
+    let data = writer.unbox().unconfine();
+    println!("decoding list of {ITEMS} items from {} bytes", data.len());
+
+    let (_, reallocs) = run("pushing", &data, decode_pushing);
+    assert!(reallocs > 0);
+    assert_eq!(run("preallocated", &data, decode_preallocated), (1, 0));
+}
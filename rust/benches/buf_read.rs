@@ -0,0 +1,87 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of a file of records with byte blob fields through `BufReader`,
+//! comparing the generic stream reader against the reader taking the data out
+//! of the buffer of `BufRead`. Run with `cargo bench --bench buf_read`.
+
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{BufReader, BufWriter, Write};
+use std::time::Instant;
+
+use amplify::confinement::TinyBlob;
+use strict_encoding::{
+    ReadRaw, StreamReader, StrictDecode, StrictEncode, StrictReader, StrictType, StrictWriter,
+};
+
+const LIB: &str = "Bench";
+const RECORDS: u32 = 100_000;
+const MAX: usize = usize::MAX;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB)]
+struct Record {
+    id: u32,
+    key: [u8; 32],
+    blob: TinyBlob,
+}
+
+fn record(no: u32) -> Record {
+    Record {
+        id: no,
+        key: [no as u8; 32],
+        blob: TinyBlob::try_from(vec![(no >> 8) as u8; 64]).unwrap(),
+    }
+}
+
+fn run<R: ReadRaw>(name: &str, path: &str, reader: impl Fn(File) -> StrictReader<R>) {
+    let start = Instant::now();
+    let mut reader = reader(File::open(path).unwrap());
+    for no in 0..RECORDS {
+        let record = black_box(Record::strict_decode(&mut reader).expect("valid data"));
+        assert_eq!(record.id, no);
+    }
+    let elapsed = start.elapsed();
+    println!("{name:>8}: {elapsed:?} total, {:?} per record", elapsed / RECORDS);
+}
+
+fn main() {
+    let path = std::env::temp_dir().join(format!("strict-bench-{}.dat", std::process::id()));
+    let path = path.to_str().expect("temporary path is UTF-8");
+    let mut file = BufWriter::new(File::create(path).unwrap());
+    let mut len = 0;
+    for no in 0..RECORDS {
+        let writer = record(no).strict_encode(StrictWriter::in_memory::<MAX>()).unwrap();
+        let data = writer.unbox().unconfine();
+        len += data.len();
+        file.write_all(&data).unwrap();
+    }
+    file.flush().unwrap();
+    drop(file);
+    println!("decoding {RECORDS} records from {len}-byte file");
+
+    run("stream", path, |file| StrictReader::with(StreamReader::new::<MAX>(BufReader::new(file))));
+    run("buf read", path, |file| StrictReader::from_buf_read::<MAX>(BufReader::new(file)));
+
+    std::fs::remove_file(path).unwrap();
+}
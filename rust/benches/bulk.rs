@@ -0,0 +1,67 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding of a long list of integers, comparing the single write of the
+//! little-endian bytes of all the items against writing them one by one. Run
+//! with `cargo bench --bench bulk`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use amplify::confinement::MediumVec;
+use strict_encoding::{StrictEncode, StrictWriter};
+
+const ROUNDS: u32 = 20;
+const ITEMS: u64 = 1_000_000;
+const MAX: usize = usize::MAX;
+
+fn per_item(list: &MediumVec<u64>) -> Vec<u8> {
+    let mut writer = StrictWriter::in_memory::<MAX>();
+    for item in list {
+        writer = item.strict_encode(writer).unwrap();
+    }
+    writer.unbox().unconfine()
+}
+
+fn bulk(list: &MediumVec<u64>) -> Vec<u8> {
+    let writer = list.strict_encode(StrictWriter::in_memory::<MAX>()).unwrap();
+    writer.unbox().unconfine()
+}
+
+fn run(name: &str, list: &MediumVec<u64>, encode: impl Fn(&MediumVec<u64>) -> Vec<u8>) -> Vec<u8> {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(encode(black_box(list)));
+    }
+    let elapsed = start.elapsed();
+    println!("{name:>8}: {elapsed:?} total, {:?} per encode", elapsed / ROUNDS);
+    encode(list)
+}
+
+fn main() {
+    let list = MediumVec::try_from_iter((0..ITEMS).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+        .unwrap();
+    println!("encoding list of {ITEMS} u64 integers {ROUNDS} times");
+
+    let reference = run("per item", &list, per_item);
+    // the bulk encoding is prefixed with the list length
+    assert_eq!(run("bulk", &list, bulk)[3..], reference);
+}
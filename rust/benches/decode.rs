@@ -0,0 +1,83 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of decoding performance using in-memory readers. Run with
+//! `cargo bench --bench decode`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use amplify::confinement::SmallVec;
+use strict_encoding::{
+    ReadRaw, StrictDecode, StrictEncode, StrictReader, StrictSerialize, StrictType,
+};
+
+const LIB: &str = "Bench";
+const ROUNDS: u32 = 200_000;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB)]
+struct Medium {
+    id: u64,
+    flags: u8,
+    amount: i128,
+    hash: [u8; 32],
+    tag: Option<u16>,
+    items: SmallVec<u32>,
+    nested: (u16, u16, u32),
+}
+
+impl StrictSerialize for Medium {}
+
+fn run<'a, R: ReadRaw>(
+    name: &str,
+    data: &'a [u8],
+    reader: impl Fn(&'a [u8]) -> StrictReader<R>,
+) {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut reader = reader(black_box(data));
+        black_box(Medium::strict_decode(&mut reader).expect("valid data"));
+    }
+    let elapsed = start.elapsed();
+    let per_item = elapsed / ROUNDS;
+    println!("{name:>8}: {elapsed:?} total, {per_item:?} per decode");
+    assert!(per_item < Duration::from_secs(1));
+}
+
+fn main() {
+    let medium = Medium {
+        id: 0x0102030405060708,
+        flags: 0xA5,
+        amount: -1,
+        hash: [0x33; 32],
+        tag: Some(7),
+        items: SmallVec::try_from_iter(0..32).unwrap(),
+        nested: (1, 2, 3),
+    };
+    let data = medium.to_strict_serialized::<{ u16::MAX as usize }>().unwrap();
+    let data = data.as_slice();
+    println!("decoding {}-byte structure {ROUNDS} times", data.len());
+
+    run("cursor", data, StrictReader::in_memory::<{ u16::MAX as usize }>);
+    run("slice", data, StrictReader::from_slice::<{ u16::MAX as usize }>);
+}
@@ -0,0 +1,106 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Number of allocations made while decoding the same collection-heavy
+//! structure many times, comparing decoding of the new values against decoding
+//! into the existing one. Run with `cargo bench --bench decode_into`.
+
+#[path = "../src/test/counting_alloc.rs"]
+mod counting_alloc;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use amplify::confinement::{SmallBlob, SmallString, SmallVec, TinyVec};
+use strict_encoding::{
+    StrictDecode, StrictDecodeInto, StrictEncode, StrictReader, StrictSerialize, StrictType,
+};
+
+const LIB: &str = "Bench";
+const ROUNDS: usize = 100_000;
+const MAX: usize = u16::MAX as usize;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+#[strict_type(lib = LIB)]
+struct Entry {
+    key: SmallString,
+    value: SmallBlob,
+    refs: TinyVec<u64>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+#[strict_type(lib = LIB)]
+struct Message {
+    seq: u64,
+    sender: SmallString,
+    entries: SmallVec<Entry>,
+}
+
+impl StrictSerialize for Message {}
+
+fn run(name: &str, data: &[u8], mut decode: impl FnMut(&[u8])) -> usize {
+    let start = Instant::now();
+    let ((), allocs) = counting_alloc::allocations(|| {
+        for _ in 0..ROUNDS {
+            decode(black_box(data));
+        }
+    });
+    let elapsed = start.elapsed();
+    let allocs = allocs.count;
+    println!(
+        "{name:>8}: {allocs} allocations, {elapsed:?} total, {:?} per decode",
+        elapsed / ROUNDS as u32
+    );
+    allocs
+}
+
+fn main() {
+    let entry = |n: u64| Entry {
+        key: SmallString::try_from(format!("entry key #{n}")).unwrap(),
+        value: SmallBlob::try_from(vec![n as u8; 64]).unwrap(),
+        refs: TinyVec::try_from_iter(n..n + 8).unwrap(),
+    };
+    let message = Message {
+        seq: 1,
+        sender: SmallString::try_from("sender of the message".to_owned()).unwrap(),
+        entries: SmallVec::try_from_iter((0..16).map(entry)).unwrap(),
+    };
+    let data = message.to_strict_serialized::<MAX>().unwrap();
+    let data = data.as_slice();
+    println!("decoding {}-byte structure {ROUNDS} times", data.len());
+
+    let fresh = run("fresh", data, |data| {
+        let mut reader = StrictReader::from_slice::<MAX>(data);
+        black_box(Message::strict_decode(&mut reader).expect("valid data"));
+    });
+    assert!(fresh >= ROUNDS * 50);
+
+    let mut value = Message::default();
+    let reused = run("into", data, |data| {
+        let mut reader = StrictReader::from_slice::<MAX>(data);
+        value.strict_decode_into(&mut reader).expect("valid data");
+    });
+    assert_eq!(value, message);
+    // only the first decoding allocates the collections of the value
+    assert!(reused <= fresh / ROUNDS);
+}
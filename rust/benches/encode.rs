@@ -0,0 +1,113 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of encoding into a file with and without buffering. Run with
+//! `cargo bench --bench encode`.
+
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::Instant;
+
+use amplify::confinement::{Confined, U16};
+use strict_encoding::{StreamWriter, StrictEncode, StrictType, StrictWriter};
+
+const LIB: &str = "Bench";
+const ROUNDS: u32 = 20;
+const ITEMS: usize = 2500;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB)]
+struct Item {
+    id: u64,
+    value: u32,
+    index: u16,
+    flags: u8,
+}
+
+/// Structure with 10k primitive fields.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB)]
+struct Wide {
+    items: Confined<Vec<Item>, 0, U16>,
+}
+
+/// File counting the number of write calls, each of which is a syscall.
+struct CountingFile {
+    file: File,
+    writes: usize,
+}
+
+impl Write for CountingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.file.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { self.file.flush() }
+}
+
+fn run(name: &str, wide: &Wide, encode: impl Fn(&Wide, &mut CountingFile)) {
+    let path = std::env::temp_dir().join(format!("strict_encoding_bench_{name}"));
+    let mut writes = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut file = CountingFile {
+            file: File::create(&path).expect("temporary file"),
+            writes: 0,
+        };
+        encode(black_box(wide), &mut file);
+        writes = file.writes;
+    }
+    let elapsed = start.elapsed();
+    let per_item = elapsed / ROUNDS;
+    println!("{name:>10}: {elapsed:?} total, {per_item:?} per encode, {writes} writes");
+    std::fs::remove_file(path).ok();
+}
+
+fn main() {
+    let items = (0..ITEMS)
+        .map(|i| Item {
+            id: i as u64,
+            value: i as u32 * 3,
+            index: i as u16,
+            flags: i as u8,
+        })
+        .collect::<Vec<_>>();
+    let wide = Wide {
+        items: Confined::try_from(items).unwrap(),
+    };
+    println!("encoding {}-field structure into a file {ROUNDS} times", ITEMS * 4);
+
+    run("unbuffered", &wide, |wide, file| {
+        wide.strict_encode(StrictWriter::with(StreamWriter::new::<{ u32::MAX as usize }>(file)))
+            .unwrap();
+    });
+    run("buffered", &wide, |wide, file| {
+        let (_, count) = wide
+            .strict_encode(StrictWriter::buffered::<{ u32::MAX as usize }>(file))
+            .unwrap()
+            .finish()
+            .unwrap();
+        black_box(count);
+    });
+}
@@ -0,0 +1,110 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding and decoding of records consisting of nested structures and
+//! unions without an observer, with an observer doing nothing and with
+//! `StatsObserver`, measuring the overhead of the instrumentation. Run with
+//! `cargo bench --bench observer`.
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use strict_encoding::{
+    CodecObserver, StatsObserver, StrictDecode, StrictEncode, StrictReader, StrictType,
+    StrictWriter,
+};
+
+const LIB: &str = "Bench";
+const ROUNDS: u32 = 1_000_000;
+const MAX: usize = usize::MAX;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB, tags = order)]
+enum Kind {
+    #[default]
+    Plain,
+    Tagged(u16),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB)]
+struct Point(u32, u32);
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB)]
+struct Record {
+    id: u64,
+    kind: Kind,
+    from: Point,
+    to: Point,
+    memo: Option<u8>,
+}
+
+struct Noop;
+
+impl CodecObserver for Noop {}
+
+fn run(name: &str, observer: Option<Arc<dyn CodecObserver>>) {
+    let record = Record {
+        id: 7,
+        kind: Kind::Tagged(300),
+        from: Point(1, 2),
+        to: Point(3, 4),
+        memo: Some(5),
+    };
+
+    let start = Instant::now();
+    let mut data = vec![];
+    for _ in 0..ROUNDS {
+        let mut writer = StrictWriter::in_memory::<MAX>();
+        if let Some(observer) = &observer {
+            writer = writer.with_observer(observer.clone());
+        }
+        data = black_box(&record).strict_encode(writer).unwrap().unbox().unconfine();
+    }
+    let encoding = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut reader = StrictReader::from_slice::<MAX>(black_box(&data));
+        if let Some(observer) = &observer {
+            reader = reader.with_observer(observer.clone());
+        }
+        black_box(Record::strict_decode(&mut reader).unwrap());
+    }
+    let decoding = start.elapsed();
+
+    println!(
+        "{name:>9}: encoding {:?}, decoding {:?} per record",
+        encoding / ROUNDS,
+        decoding / ROUNDS
+    );
+}
+
+fn main() {
+    run("none", None);
+    run("noop", Some(Arc::new(Noop)));
+    run("stats", Some(Arc::new(StatsObserver::new())));
+}
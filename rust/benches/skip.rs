@@ -0,0 +1,96 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory allocated while indexing records with large blob fields, comparing
+//! the full decoding of the records against the partial decoding generated by
+//! `project(...)` attribute, which skips the blobs. Run with
+//! `cargo bench --bench skip`.
+
+#[path = "../src/test/counting_alloc.rs"]
+mod counting_alloc;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use amplify::confinement::{MediumBlob, TinyString};
+use strict_encoding::{
+    SliceReader, StrictDecode, StrictEncode, StrictReader, StrictType, StrictWriter,
+};
+
+const LIB: &str = "Bench";
+const RECORDS: u32 = 100;
+const BLOB_LEN: usize = 1 << 20;
+const MAX: usize = usize::MAX;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB, project(id, timestamp))]
+struct Record {
+    id: u64,
+    name: TinyString,
+    blob: MediumBlob,
+    timestamp: i64,
+}
+
+fn run(
+    name: &str,
+    data: &[u8],
+    index: impl Fn(&mut StrictReader<SliceReader>) -> (u64, i64),
+) -> usize {
+    let start = Instant::now();
+    let ((), allocs) = counting_alloc::allocations(|| {
+        let mut reader = StrictReader::from_slice::<MAX>(black_box(data));
+        for no in 0..RECORDS {
+            let (id, timestamp) = black_box(index(&mut reader));
+            assert_eq!((id, timestamp), (no as u64, -(no as i64)));
+        }
+    });
+    let elapsed = start.elapsed();
+    let allocated = allocs.bytes;
+    println!("{name:>8}: {allocated} bytes allocated, {elapsed:?}");
+    allocated
+}
+
+fn main() {
+    let mut writer = StrictWriter::in_memory::<MAX>();
+    for no in 0..RECORDS {
+        let record = Record {
+            id: no as u64,
+            name: TinyString::try_from(format!("record {no}")).unwrap(),
+            blob: MediumBlob::try_from(vec![no as u8; BLOB_LEN]).unwrap(),
+            timestamp: -(no as i64),
+        };
+        writer = record.strict_encode(writer).unwrap();
+    }
+    let data = writer.unbox().unconfine();
+    println!("indexing {RECORDS} records from {} bytes", data.len());
+
+    let full = run("full", &data, |reader| {
+        let record = Record::strict_decode(reader).expect("valid data");
+        (record.id, record.timestamp)
+    });
+    let partial = run("partial", &data, |reader| {
+        Record::strict_decode_projection(reader).expect("valid data")
+    });
+    // blobs are allocated only by the full decoding
+    assert!(full >= RECORDS as usize * BLOB_LEN);
+    assert!(partial < BLOB_LEN);
+}
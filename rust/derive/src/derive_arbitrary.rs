@@ -0,0 +1,162 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify_syn::{DeriveInner, Field, FieldKind, Fields, Items, NamedField, Variant};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+use crate::params::{FieldAttr, StrictDerive};
+
+struct DeriveArbitrary<'a>(&'a StrictDerive);
+
+impl StrictDerive {
+    /// Derives `StrictArbitrary` and `arbitrary::Arbitrary` implementations
+    /// for the types marked with `#[strict_type(arbitrary)]` attribute.
+    pub fn derive_arbitrary(&self) -> Result<TokenStream2> {
+        if self.conf.remote.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "arbitrary values can't be generated for remote type mirrors",
+            ));
+        }
+
+        let crate_name = &self.conf.strict_crate;
+        let data = self.bounded(Some(ident!(StrictArbitrary)), self.encoded_types()?, false);
+        let impl_strict =
+            data.derive(crate_name, &ident!(StrictArbitrary), &DeriveArbitrary(self))?;
+
+        let mut generics = data.generics.clone();
+        generics.params.insert(0, parse_quote! { '__a });
+        let (impl_generics, _, _) = generics.split_for_impl();
+        let (_, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        Ok(quote! {
+            #impl_strict
+
+            #[automatically_derived]
+            impl #impl_generics #crate_name::arbitrary::Arbitrary<'__a> for #type_name #ty_generics #where_clause {
+                fn arbitrary(
+                    u: &mut #crate_name::arbitrary::Unstructured<'__a>,
+                ) -> #crate_name::arbitrary::Result<Self> {
+                    <Self as #crate_name::StrictArbitrary>::strict_arbitrary(u)
+                }
+            }
+        })
+    }
+}
+
+impl DeriveArbitrary<'_> {
+    fn strict_arbitrary(&self, value: TokenStream2) -> TokenStream2 {
+        let crate_name = &self.0.conf.strict_crate;
+        quote! {
+            fn strict_arbitrary(
+                u: &mut #crate_name::arbitrary::Unstructured,
+            ) -> #crate_name::arbitrary::Result<Self> {
+                use #crate_name::StrictArbitrary;
+                Ok(#value)
+            }
+        }
+    }
+}
+
+/// Generates expression constructing the fields of a struct or an enum
+/// variant from the arbitrary data. Skipped fields are initialized with their
+/// [`Default`] values.
+fn construct(fields: &Fields) -> Result<TokenStream2> {
+    match fields {
+        Fields::Unit => Ok(TokenStream2::new()),
+        Fields::Named(fields) => construct_named(fields),
+        Fields::Unnamed(fields) => construct_unnamed(fields),
+    }
+}
+
+fn construct_named(fields: &Items<NamedField>) -> Result<TokenStream2> {
+    let mut items = Vec::with_capacity(fields.len());
+    for named in fields {
+        let name = &named.name;
+        let value = field_value(&named.field, FieldKind::Named)?;
+        items.push(quote! { #name: #value });
+    }
+    Ok(quote! { { #( #items ),* } })
+}
+
+fn construct_unnamed(fields: &Items<Field>) -> Result<TokenStream2> {
+    let mut items = Vec::with_capacity(fields.len());
+    for field in fields {
+        items.push(field_value(field, FieldKind::Unnamed)?);
+    }
+    Ok(quote! { ( #( #items ),* ) })
+}
+
+fn field_value(field: &Field, kind: FieldKind) -> Result<TokenStream2> {
+    let attr = FieldAttr::of(field, kind)?;
+    attr.forbid_with(field.ty.span(), "arbitrary types")?;
+    Ok(if attr.skip {
+        quote! { Default::default() }
+    } else {
+        quote! { StrictArbitrary::strict_arbitrary(u)? }
+    })
+}
+
+impl DeriveInner for DeriveArbitrary<'_> {
+    fn derive_unit_inner(&self) -> Result<TokenStream2> {
+        Ok(self.strict_arbitrary(quote! { Self {} }))
+    }
+
+    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
+        let fields = construct_named(fields)?;
+        Ok(self.strict_arbitrary(quote! { Self #fields }))
+    }
+
+    fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
+        let fields = construct_unnamed(fields)?;
+        Ok(self.strict_arbitrary(quote! { Self #fields }))
+    }
+
+    fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
+        let Some(last) = variants.len().checked_sub(1) else {
+            return Err(Error::new(
+                Span::call_site(),
+                "arbitrary values can't be generated for enums without variants",
+            ));
+        };
+        let last = u8::try_from(last).map_err(|_| {
+            Error::new(Span::call_site(), "strict enums can't have more than 256 variants")
+        })?;
+
+        let mut arms = Vec::with_capacity(variants.len());
+        for (index, variant) in variants.iter().enumerate() {
+            let index = index as u8;
+            let name = &variant.name;
+            let fields = construct(&variant.fields)?;
+            arms.push(quote! { #index => Self::#name #fields });
+        }
+
+        Ok(self.strict_arbitrary(quote! {
+            match u.int_in_range(0u8..=#last)? {
+                #( #arms, )*
+                _ => unreachable!("variant index is out of range"),
+            }
+        }))
+    }
+}
@@ -0,0 +1,156 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify_syn::{DataInner, FieldKind, Fields};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Error, GenericParam, Index, Lifetime, LifetimeDef, Result};
+
+use crate::params::{FieldAttr, StrictDerive};
+
+impl StrictDerive {
+    pub fn derive_decode_borrowed(&self) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let type_name = &self.data.name;
+
+        if self.conf.remote.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "StrictDecodeBorrowed can't be derived for remote type mirrors",
+            ));
+        }
+        if self.conf.encode_only {
+            return Err(Error::new(
+                type_name.span(),
+                format!(
+                    "`StrictDecodeBorrowed` can't be derived for `{type_name}` marked as \
+                     `encode_only`"
+                ),
+            ));
+        }
+
+        let decode_field = |attr: &FieldAttr, span: Span, segment: TokenStream2| -> Result<_> {
+            attr.forbid_with(span, "borrowed strict types")?;
+            attr.forbid_optional(span, "borrowed strict types")?;
+            attr.forbid_flatten(span, "borrowed strict types")?;
+            Ok(quote! {
+                reader.in_context(
+                    || #crate_name::PathSegment::#segment,
+                    #crate_name::StrictDecodeBorrowed::strict_decode_borrowed,
+                )?
+            })
+        };
+
+        let body = match &self.data.inner {
+            DataInner::Struct(Fields::Named(fields)) => {
+                let mut field_name = Vec::with_capacity(fields.len());
+                let mut field_read = Vec::with_capacity(fields.len());
+                let mut skipped = Vec::new();
+                for named_field in fields.iter() {
+                    let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+                    let name = &named_field.name;
+                    if attr.skip {
+                        skipped.push(quote! { #name });
+                        continue;
+                    }
+                    let rename = attr.field_name(name);
+                    field_name.push(quote! { #name });
+                    field_read.push(decode_field(
+                        &attr,
+                        named_field.field.ty.span(),
+                        quote! { Field(#crate_name::fname!(#rename)) },
+                    )?);
+                }
+                quote! {
+                    #( let #field_name = #field_read; )*
+                    Ok(Self {
+                        #(#field_name,)*
+                        #(#skipped: Default::default()),*
+                    })
+                }
+            }
+            DataInner::Struct(Fields::Unnamed(fields)) => {
+                let mut field_idx = Vec::with_capacity(fields.len());
+                let mut field_read = Vec::with_capacity(fields.len());
+                let mut field_vars = Vec::with_capacity(fields.len());
+                for (index, field) in fields.iter().enumerate() {
+                    let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                    if attr.skip {
+                        field_vars.push(quote! { Default::default() });
+                        continue;
+                    }
+                    let pos = Index::from(index);
+                    field_read.push(decode_field(&attr, field.ty.span(), quote! { Index(#pos) })?);
+                    let index = Ident::new(&format!("_{index}"), Span::call_site());
+                    field_idx.push(quote! { #index });
+                    field_vars.push(quote! { #index });
+                }
+                quote! {
+                    #( let #field_idx = #field_read; )*
+                    Ok(Self( #( #field_vars ),* ))
+                }
+            }
+            _ => {
+                return Err(Error::new(
+                    type_name.span(),
+                    "StrictDecodeBorrowed can be derived only for structures with fields",
+                ));
+            }
+        };
+
+        // Fields borrow the data for the lifetime of the structure; structures
+        // without lifetime parameters may be decoded from a slice of any
+        // lifetime.
+        let mut generics = self.data.generics.clone();
+        let mut lifetimes = generics.lifetimes();
+        let lifetime = match (lifetimes.next(), lifetimes.next()) {
+            (Some(def), None) => def.lifetime.clone(),
+            (None, _) => {
+                let lifetime = Lifetime::new("'strict", Span::call_site());
+                generics
+                    .params
+                    .insert(0, GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())));
+                lifetime
+            }
+            (Some(_), Some(def)) => {
+                return Err(Error::new(
+                    def.span(),
+                    "StrictDecodeBorrowed can be derived only for structures with at most one \
+                     lifetime parameter",
+                ));
+            }
+        };
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ty_generics, _) = self.data.generics.split_for_impl();
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictDecodeBorrowed<#lifetime> for #type_name #ty_generics #where_clause {
+                fn strict_decode_borrowed(
+                    reader: &mut #crate_name::StrictReader<#crate_name::SliceReader<#lifetime>>,
+                ) -> Result<Self, #crate_name::DecodeError> {
+                    use #crate_name::TypedRead;
+                    #body
+                }
+            }
+        })
+    }
+}
@@ -19,9 +19,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amplify_syn::{DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant};
+use amplify_syn::{
+    DataInner, DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant,
+};
 use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
-use syn::{Error, Result};
+use syn::spanned::Spanned;
+use syn::{Error, Result, Type};
 
 use crate::params::{FieldAttr, StrictDerive, VariantAttr};
 
@@ -29,37 +32,426 @@ struct DeriveDecode<'a>(&'a StrictDerive);
 
 impl StrictDerive {
     pub fn derive_decode(&self) -> Result<TokenStream2> {
-        let res = self.data.derive(
-            &self.conf.strict_crate,
-            &ident!(StrictDecode),
-            &DeriveDecode(self),
-        )?;
-        Ok(res)
+        let impl_decode = self.derive_decode_impl()?;
+        let tests = self.derive_tests()?;
+        Ok(quote! {
+            #impl_decode
+            #tests
+        })
+    }
+
+    fn derive_decode_impl(&self) -> Result<TokenStream2> {
+        self.forbid_encode_only("StrictDecode")?;
+
+        if self.conf.transparent {
+            return self.derive_transparent_decode();
+        }
+        if let Some(ref wire) = self.conf.try_from {
+            return self.derive_try_from_decode(wire);
+        }
+        if self.conf.into.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "StrictDecode of types with `into` attribute requires `try_from` attribute \
+                 specifying the wire type the value is converted from",
+            ));
+        }
+
+        let data = self.bounded(Some(ident!(StrictDecode)), self.decoded_types()?, true);
+        if let Some(ref remote) = self.conf.remote {
+            let impl_decode = self.derive_inherent(&data, &DeriveDecode(self))?;
+            let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+            let type_name = &data.name;
+            let convert =
+                self.convert_expr(quote! { mirror }, quote! { #type_name }, quote! { #remote });
+            return Ok(quote! {
+                #impl_decode
+
+                #[automatically_derived]
+                impl #impl_generics ::core::convert::From<#type_name #ty_generics> for #remote #where_clause {
+                    fn from(mirror: #type_name #ty_generics) -> Self {
+                        #convert
+                    }
+                }
+            });
+        }
+        self.check_flattenable()?;
+        let impl_decode =
+            data.derive(&self.conf.strict_crate, &ident!(StrictDecode), &DeriveDecode(self))?;
+        let DataInner::Struct(Fields::Named(fields)) = &self.data.inner else {
+            if self.conf.project.is_some() {
+                return Err(Error::new(
+                    self.data.name.span(),
+                    "projections are supported only for structures with named fields",
+                ));
+            }
+            return Ok(impl_decode);
+        };
+        let impl_project = match self.conf.project {
+            Some(ref project) => self.derive_projection(fields, project)?,
+            None => quote! {},
+        };
+
+        if !self.conf.flattenable {
+            return Ok(quote! {
+                #impl_decode
+
+                #impl_project
+            });
+        }
+
+        let crate_name = &self.conf.strict_crate;
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+        let read_fields = derive_struct_fields(Some(self), fields, quote! { Self })?;
+        // validated here, such that the invariants are checked also when the
+        // structure is flattened into another one
+        let validated_fields = self.validated(quote! {
+            #[allow(unused_imports)]
+            use #crate_name::{ReadStruct, fname};
+            #read_fields
+        });
+        Ok(quote! {
+            #impl_decode
+
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictDecodeFields for #type_name #ty_generics #where_clause {
+                fn strict_decode_fields(
+                    r: &mut impl #crate_name::ReadStruct,
+                ) -> Result<Self, #crate_name::DecodeError> {
+                    #validated_fields
+                }
+            }
+
+            #impl_project
+        })
+    }
+}
+
+impl StrictDerive {
+    /// Generates `strict_decode_projection` function decoding only the fields
+    /// listed in `project(...)` attribute and skipping the rest of them. The
+    /// fields are read by a local product type, which has the name and the
+    /// fields of the structure itself, such that the reader checks the fields
+    /// in the same way as for the structure.
+    fn derive_projection(
+        &self,
+        fields: &Items<NamedField>,
+        project: &[Ident],
+    ) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let type_name = &self.data.name;
+        if let Some(param) = self.data.generics.params.first() {
+            return Err(Error::new(
+                param.span(),
+                "projections are not supported for generic types",
+            ));
+        }
+
+        for name in project {
+            let Some(named) = fields.iter().find(|named| &named.name == name) else {
+                return Err(Error::new(name.span(), format!("no field `{name}` to project")));
+            };
+            if FieldAttr::of(&named.field, FieldKind::Named)?.skip {
+                return Err(Error::new(
+                    name.span(),
+                    format!("skipped field `{name}` is not encoded and can't be projected"),
+                ));
+            }
+        }
+
+        let mut adapters = vec![];
+        let mut steps = Vec::with_capacity(fields.len());
+        let mut projected = Vec::with_capacity(project.len());
+        let mut dumb = Vec::with_capacity(project.len());
+        let mut optional = false;
+        for named_field in fields {
+            let field = &named_field.field;
+            let attr = FieldAttr::of(field, FieldKind::Named)?;
+            check_optional(&attr, field, &mut optional)?;
+            attr.forbid_flatten(field.ty.span(), "projected structures")?;
+            if attr.skip {
+                continue;
+            }
+
+            let name = &named_field.name;
+            let rename = attr.field_name(name);
+            let ty = &field.ty;
+            if !project.contains(name) {
+                attr.forbid_with(ty.span(), "structures which are skipped by a projection")?;
+                attr.forbid_optional(ty.span(), "structures which are skipped by a projection")?;
+                steps.push(quote! { r.skip_field::<#ty>(fname!(#rename))?; });
+                continue;
+            }
+            let adapter = Ident::new(&format!("StrictWith_{name}"), Span::call_site());
+            let read = read_field(
+                Some(self),
+                &attr,
+                field,
+                adapter,
+                Some(quote! { fname!(#rename) }),
+                &mut adapters,
+            )?;
+            steps.push(quote! { let #name: #ty = #read; });
+            dumb.push(match attr.dumb {
+                Some(ref dumb) => quote! { #dumb },
+                None => quote! { <#ty as #crate_name::StrictDumb>::strict_dumb() },
+            });
+            projected.push((name, ty));
+        }
+        let (names, types): (Vec<_>, Vec<_>) = projected.iter().copied().unzip();
+        // the values are returned in the order of the projection list
+        projected.sort_by_key(|(name, _)| project.iter().position(|p| p == *name));
+        let (returned, returned_types): (Vec<_>, Vec<_>) = projected.into_iter().unzip();
+        let listed = returned.iter().map(|name| format!("`{name}`")).collect::<Vec<_>>();
+        let listed = listed.join(", ");
+        let doc = format!(
+            "Decodes {listed} fields of the structure, skipping the other fields without decoding \
+             them; see `StrictSkip::strict_skip`."
+        );
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #type_name {
+                #[doc = #doc]
+                pub fn strict_decode_projection(
+                    reader: &mut impl #crate_name::TypedRead,
+                ) -> Result<( #( #returned_types, )* ), #crate_name::DecodeError> {
+                    #[allow(unused_imports)]
+                    use #crate_name::{ReadStruct, TypedRead, fname};
+
+                    struct Projection( #( #types ),* );
+                    impl #crate_name::StrictType for Projection {
+                        const STRICT_LIB_NAME: &'static str =
+                            <#type_name as #crate_name::StrictType>::STRICT_LIB_NAME;
+                        fn strict_name() -> Option<#crate_name::TypeName> {
+                            <#type_name as #crate_name::StrictType>::strict_name()
+                        }
+                    }
+                    impl #crate_name::StrictDumb for Projection {
+                        fn strict_dumb() -> Self { Projection( #( #dumb ),* ) }
+                    }
+                    impl #crate_name::StrictProduct for Projection {}
+                    impl #crate_name::StrictStruct for Projection {
+                        const ALL_FIELDS: &'static [&'static str] =
+                            <#type_name as #crate_name::StrictStruct>::ALL_FIELDS;
+                    }
+
+                    #( #adapters )*
+                    let Projection( #( #names ),* ) = reader.read_struct(|r| {
+                        #( #steps )*
+                        Ok(Projection( #( #names ),* ))
+                    })?;
+                    Ok(( #( #returned, )* ))
+                }
+            }
+        })
+    }
+}
+
+impl StrictDerive {
+    fn derive_transparent_decode(&self) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let (member, ty, skipped) = self.transparent_field()?;
+        let data = self.bounded(Some(ident!(StrictDecode)), [ty], false);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+        let decoded = self.validated(quote! {
+            Ok(Self {
+                #member: #crate_name::StrictDecode::strict_decode(reader)?,
+                #( #skipped: Default::default(), )*
+            })
+        });
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictDecode for #type_name #ty_generics #where_clause {
+                fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
+                    #decoded
+                }
+
+                fn strict_display(&self) -> Option<#crate_name::__private::String> {
+                    #crate_name::StrictDecode::strict_display(&self.#member)
+                }
+            }
+        })
     }
 }
 
+impl StrictDerive {
+    fn derive_try_from_decode(&self, wire: &Type) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let data = self.bounded(Some(ident!(StrictDecode)), [wire], false);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+        let converted = self.validated(quote! {
+            ::core::convert::TryFrom::try_from(wire)
+                .map_err(#crate_name::DecodeError::conversion::<#wire, Self>)
+        });
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictDecode for #type_name #ty_generics #where_clause {
+                fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
+                    let wire = <#wire as #crate_name::StrictDecode>::strict_decode(reader)?;
+                    #converted
+                }
+            }
+        })
+    }
+}
+
+impl StrictDerive {
+    /// Checks the invariants of the value decoded by the `decode` expression
+    /// with the function given in `validate` attribute, if any.
+    fn validated(&self, decode: TokenStream2) -> TokenStream2 {
+        let Some(ref validate) = self.conf.validate else {
+            return decode;
+        };
+        let crate_name = &self.conf.strict_crate;
+        quote! {
+            let decoded: Result<Self, #crate_name::DecodeError> = { #decode };
+            let value = decoded?;
+            #validate(&value).map_err(#crate_name::DecodeError::invalid::<Self>)?;
+            Ok(value)
+        }
+    }
+}
+
+impl DeriveDecode<'_> {
+    /// Renders newtypes in the decoding errors as their only field, unless
+    /// the field is decoded with a custom codec.
+    fn newtype_display(&self, fields: &Items<Field>) -> Result<TokenStream2> {
+        let crate_name = &self.0.conf.strict_crate;
+        let mut iter = fields.iter();
+        let (Some(field), None) = (iter.next(), iter.next()) else {
+            return Ok(quote! {});
+        };
+        let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+        if self.0.conf.remote.is_some() || attr.skip || attr.optional || attr.has_codec() {
+            return Ok(quote! {});
+        }
+        Ok(quote! {
+            fn strict_display(&self) -> Option<#crate_name::__private::String> {
+                #crate_name::StrictDecode::strict_display(&self.0)
+            }
+        })
+    }
+
+    /// Wraps the decoding procedure body into a function, validating the
+    /// decoded value unless it is already validated by the `body`. For remote
+    /// type mirrors the decoded mirror value is converted into the remote type
+    /// with the derived [`From`] implementation.
+    fn wrap(&self, body: TokenStream2, validated: bool) -> TokenStream2 {
+        let crate_name = &self.0.conf.strict_crate;
+        let body = if validated { body } else { self.0.validated(body) };
+        match self.0.conf.remote {
+            None => quote! {
+                fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
+                    #body
+                }
+            },
+            Some(ref remote) => quote! {
+                pub fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<#remote, #crate_name::DecodeError> {
+                    let mirror: Self = { #body }?;
+                    Ok(::core::convert::Into::into(mirror))
+                }
+            },
+        }
+    }
+}
+
+/// Generates reading of a single field value, which uses a custom codec
+/// adapter if the field has `with` or `decode_with` attribute.
+fn read_field(
+    derive: Option<&StrictDerive>,
+    attr: &FieldAttr,
+    field: &Field,
+    adapter: Ident,
+    name: Option<TokenStream2>,
+    adapters: &mut Vec<TokenStream2>,
+) -> Result<TokenStream2> {
+    if derive.is_none() {
+        attr.forbid_with(field.ty.span(), "enum variants")?;
+        attr.forbid_optional(field.ty.span(), "enum variants")?;
+    }
+    if attr.optional {
+        attr.forbid_with(field.ty.span(), "optional fields")?;
+        return Ok(quote! { r.read_optional_field(#name)? });
+    }
+    Ok(match (&attr.decode_with, derive) {
+        (Some(with), Some(derive)) => {
+            adapters.push(derive.decode_adapter(&adapter, &field.ty, with)?);
+            quote! { r.read_field::<#adapter>(#name)?.0 }
+        }
+        _ => quote! { r.read_field(#name)? },
+    })
+}
+
+/// Checks that optional fields form a contiguous suffix of the encoded fields,
+/// since only the trailing fields may be absent from the data.
+fn check_optional(attr: &FieldAttr, field: &Field, optional: &mut bool) -> Result<()> {
+    if attr.skip {
+        return Ok(());
+    }
+    if *optional && !attr.optional {
+        return Err(Error::new(
+            field.ty.span(),
+            "fields following an optional field must be optional as well",
+        ));
+    }
+    *optional |= attr.optional;
+    Ok(())
+}
+
+/// Generates decoding of the struct fields from `r` struct reader. Custom field
+/// codecs and flattened fields are supported only if `derive` is provided,
+/// since they are not allowed in enum variants.
 fn derive_struct_fields(
+    derive: Option<&StrictDerive>,
     fields: &Items<NamedField>,
     self_name: TokenStream2,
 ) -> Result<TokenStream2> {
+    let mut adapters = vec![];
     let mut skipped = Vec::new();
     let mut field_name = Vec::with_capacity(fields.len());
-    let mut field_rename = Vec::with_capacity(fields.len());
+    let mut field_read = Vec::with_capacity(fields.len());
+    let mut optional = false;
     for named_field in fields {
-        let attr = FieldAttr::with(named_field.field.attr.clone(), FieldKind::Named)?;
+        let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+        check_optional(&attr, &named_field.field, &mut optional)?;
 
         let name = &named_field.name;
         let rename = attr.field_name(name);
 
         if attr.skip {
             skipped.push(quote! { #name })
+        } else if let (true, Some(derive)) = (attr.flatten, derive) {
+            let crate_name = &derive.conf.strict_crate;
+            let ty = &named_field.field.ty;
+            field_name.push(quote! { #name });
+            field_read.push(quote! {
+                <#ty as #crate_name::StrictDecodeFields>::strict_decode_fields(r)?
+            });
         } else {
+            if derive.is_none() {
+                attr.forbid_flatten(name.span(), "enum variants")?;
+            }
+            let adapter = Ident::new(&format!("StrictWith_{name}"), Span::call_site());
             field_name.push(quote! { #name });
-            field_rename.push(quote! { #rename });
+            field_read.push(read_field(
+                derive,
+                &attr,
+                &named_field.field,
+                adapter,
+                Some(quote! { fname!(#rename) }),
+                &mut adapters,
+            )?);
         }
     }
     Ok(quote! {
-        #( let #field_name = r.read_field(fname!(#field_rename))?; )*
+        #( #adapters )*
+        #( let #field_name = #field_read; )*
         Ok(#self_name {
             #(#field_name,)*
             #(#skipped: Default::default()),*
@@ -67,64 +459,98 @@ fn derive_struct_fields(
     })
 }
 
-fn derive_tuple_fields(fields: &Items<Field>, self_name: TokenStream2) -> Result<TokenStream2> {
+/// Generates decoding of the tuple fields. Custom field codecs are supported
+/// only if `derive` is provided, since they are not allowed in enum variants.
+fn derive_tuple_fields(
+    derive: Option<&StrictDerive>,
+    fields: &Items<Field>,
+    self_name: TokenStream2,
+) -> Result<TokenStream2> {
+    let mut adapters = vec![];
     let mut field_idx = Vec::with_capacity(fields.len());
+    let mut field_read = Vec::with_capacity(fields.len());
     let mut field_vars = Vec::with_capacity(fields.len());
+    let mut optional = false;
     for (index, field) in fields.iter().enumerate() {
-        let attr = FieldAttr::with(field.attr.clone(), FieldKind::Unnamed)?;
+        let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+        check_optional(&attr, field, &mut optional)?;
         if attr.skip {
             field_vars.push(quote! { Default::default() });
         } else {
+            let adapter = Ident::new(&format!("StrictWith_{index}"), Span::call_site());
+            field_read.push(read_field(derive, &attr, field, adapter, None, &mut adapters)?);
             let index = Ident::new(&format!("_{index}"), Span::call_site());
             field_idx.push(quote! { #index });
             field_vars.push(quote! { #index });
         }
     }
     Ok(quote! {
-        #( let #field_idx = r.read_field()?; )*
+        #( #adapters )*
+        #( let #field_idx = #field_read; )*
         Ok(#self_name( #( #field_vars ),* ))
     })
 }
 
 impl DeriveInner for DeriveDecode<'_> {
     fn derive_unit_inner(&self) -> Result<TokenStream2> {
-        Err(Error::new(
-            Span::call_site(),
-            "StrictDecode must not be derived on a unit types. Use just a unit type instead when \
-             encoding parent structure.",
+        let crate_name = &self.0.conf.strict_crate;
+        Ok(self.wrap(
+            quote! {
+                use #crate_name::TypedRead;
+                reader.read_tuple(|_| Ok(Self {}))
+            },
+            false,
         ))
     }
 
     fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
-        let inner = derive_struct_fields(fields, quote! { Self })?;
-        Ok(quote! {
-            fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
-                use #crate_name::{TypedRead, ReadStruct, fname};
+        // only flattenable structures implement `StrictDecodeFields`
+        let inner = match self.0.conf.flattenable {
+            true => quote! { #crate_name::StrictDecodeFields::strict_decode_fields(r) },
+            false => {
+                let inner = derive_struct_fields(Some(self.0), fields, quote! { Self })?;
+                quote! {
+                    use #crate_name::{ReadStruct, fname};
+                    #inner
+                }
+            }
+        };
+        Ok(self.wrap(
+            quote! {
+                use #crate_name::TypedRead;
                 reader.read_struct(|r| {
                     #inner
                 })
-            }
-        })
+            },
+            self.0.conf.flattenable,
+        ))
     }
 
     fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
-        let inner = derive_tuple_fields(fields, quote! { Self })?;
-        Ok(quote! {
-            fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
+        let inner = derive_tuple_fields(Some(self.0), fields, quote! { Self })?;
+        let decode = self.wrap(
+            quote! {
                 use #crate_name::{TypedRead, ReadTuple};
                 reader.read_tuple(|r| {
                     #inner
                 })
-            }
+            },
+            false,
+        );
+        let display = self.newtype_display(fields)?;
+        Ok(quote! {
+            #decode
+            #display
         })
     }
 
     fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
 
-        let inner = if variants.enum_kind() == EnumKind::Primitive {
+        let inner = if self.0.catch_all()?.is_some() || variants.enum_kind() == EnumKind::Primitive
+        {
             quote! {
                 reader.read_enum()
             }
@@ -151,17 +577,35 @@ impl DeriveInner for DeriveDecode<'_> {
                         });
                     }
                     Fields::Unnamed(fields) => {
-                        let inner = derive_tuple_fields(fields, quote! { Self::#var_name })?;
+                        let inner = derive_tuple_fields(None, fields, quote! { Self::#var_name })?;
+                        // newtype variants use the shorthand method, which
+                        // reduces the size of the generated code
+                        let encoded =
+                            |field| FieldAttr::of(field, FieldKind::Unnamed).is_ok_and(|a| !a.skip);
+                        if fields.len() == 1 && fields.iter().all(encoded) {
+                            read_variants.push(quote! {
+                                #name => r.read_newtype_variant(Self::#var_name),
+                            });
+                            continue;
+                        }
+                        let count = fields.iter().filter(|field| encoded(field)).count() as u8;
                         read_variants.push(quote! {
-                            #name => r.read_tuple(|r| {
+                            #name => r.read_tuple_checked(#count, |r| {
                                 #inner
                             }),
                         });
                     }
                     Fields::Named(fields) => {
-                        let inner = derive_struct_fields(fields, quote! { Self::#var_name })?;
+                        let inner = derive_struct_fields(None, fields, quote! { Self::#var_name })?;
+                        let mut names = Vec::with_capacity(fields.len());
+                        for named_field in fields {
+                            let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+                            if !attr.skip {
+                                names.push(attr.field_name(&named_field.name));
+                            }
+                        }
                         read_variants.push(quote! {
-                            #name => r.read_struct(|r| {
+                            #name => r.read_struct_checked(&[#( #names ),*], |r| {
                                 #inner
                             }),
                         });
@@ -181,11 +625,12 @@ impl DeriveInner for DeriveDecode<'_> {
             }
         };
 
-        Ok(quote! {
-            fn strict_decode(reader: &mut impl #crate_name::TypedRead) -> Result<Self, #crate_name::DecodeError> {
+        Ok(self.wrap(
+            quote! {
                 use #crate_name::TypedRead;
                 #inner
-            }
-        })
+            },
+            false,
+        ))
     }
 }
@@ -0,0 +1,169 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify_syn::{DataInner, FieldKind, Fields};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{Error, Index, Result};
+
+use crate::params::{FieldAttr, StrictDerive};
+
+impl StrictDerive {
+    pub fn derive_decode_into(&self) -> Result<TokenStream2> {
+        self.forbid_encode_only("StrictDecodeInto")?;
+        if self.conf.remote.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "StrictDecodeInto can't be derived for remote type mirrors",
+            ));
+        }
+
+        let crate_name = &self.conf.strict_crate;
+        // fields of the types not implementing `StrictDecodeInto` are decoded
+        // with `StrictDecode`, which is selected by the method resolution
+        let decode_field = |member: TokenStream2, segment: TokenStream2| {
+            quote! {
+                r.in_context(
+                    || #crate_name::PathSegment::#segment,
+                    |r| (&mut #crate_name::__private::FieldInto(&mut self.#member))
+                        .decode_field_into(r),
+                )?;
+            }
+        };
+
+        let body = match &self.data.inner {
+            _ if self.conf.transparent => {
+                let (member, _, skipped) = self.transparent_field()?;
+                quote! {
+                    use #crate_name::__private::{DecodeFieldInto as _, DecodeFieldReplacing as _};
+                    (&mut #crate_name::__private::FieldInto(&mut self.#member))
+                        .decode_field_into(reader)?;
+                    #( self.#skipped = Default::default(); )*
+                    Ok(())
+                }
+            }
+            DataInner::Struct(Fields::Named(fields))
+                if self.conf.try_from.is_none() && self.decodes_natively()? =>
+            {
+                let mut field_read = Vec::with_capacity(fields.len());
+                let mut skipped = Vec::new();
+                for named_field in fields {
+                    let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+                    let name = &named_field.name;
+                    if attr.skip {
+                        skipped.push(quote! { #name });
+                        continue;
+                    }
+                    let rename = attr.field_name(name);
+                    field_read.push(decode_field(
+                        quote! { #name },
+                        quote! { Field(#crate_name::fname!(#rename)) },
+                    ));
+                }
+                quote! {
+                    use #crate_name::TypedRead;
+                    use #crate_name::__private::{DecodeFieldInto as _, DecodeFieldReplacing as _};
+                    reader.in_depth(|r| {
+                        #( #field_read )*
+                        #( self.#skipped = Default::default(); )*
+                        Ok(())
+                    })
+                }
+            }
+            DataInner::Struct(Fields::Unnamed(fields))
+                if self.conf.try_from.is_none() && self.decodes_natively()? =>
+            {
+                let mut field_read = Vec::with_capacity(fields.len());
+                let mut skipped = Vec::new();
+                let mut read_fields = 0u8;
+                for (index, field) in fields.iter().enumerate() {
+                    let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                    let index = Index::from(index);
+                    if attr.skip {
+                        skipped.push(quote! { #index });
+                        continue;
+                    }
+                    field_read
+                        .push(decode_field(quote! { #index }, quote! { Index(#read_fields) }));
+                    read_fields += 1;
+                }
+                quote! {
+                    use #crate_name::TypedRead;
+                    use #crate_name::__private::{DecodeFieldInto as _, DecodeFieldReplacing as _};
+                    reader.in_depth(|r| {
+                        #( #field_read )*
+                        #( self.#skipped = Default::default(); )*
+                        Ok(())
+                    })
+                }
+            }
+            // types which fields can't be decoded into one by one are decoded
+            // as new values replacing the existing ones
+            _ => {
+                let data = self.bounded(Some(ident!(StrictDecode)), self.decoded_types()?, true);
+                let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+                let type_name = &data.name;
+                return Ok(quote! {
+                    #[automatically_derived]
+                    impl #impl_generics #crate_name::StrictDecodeInto for #type_name #ty_generics #where_clause {}
+                });
+            }
+        };
+
+        let data = self.bounded(Some(ident!(StrictDecodeInto)), self.decoded_types()?, true);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictDecodeInto for #type_name #ty_generics #where_clause {
+                fn strict_decode_into(
+                    &mut self,
+                    reader: &mut impl #crate_name::TypedRead,
+                ) -> Result<(), #crate_name::DecodeError> {
+                    #body
+                }
+            }
+        })
+    }
+
+    /// Checks that all the struct fields are decoded with their own
+    /// `StrictDecode` implementations, i.e. none of them is optional,
+    /// flattened or has a custom codec.
+    fn decodes_natively(&self) -> Result<bool> {
+        let DataInner::Struct(fields) = &self.data.inner else {
+            return Ok(false);
+        };
+        let attrs = match fields {
+            Fields::Unit => vec![],
+            Fields::Named(fields) => fields
+                .iter()
+                .map(|named| FieldAttr::of(&named.field, FieldKind::Named))
+                .collect::<Result<_>>()?,
+            Fields::Unnamed(fields) => fields
+                .iter()
+                .map(|field| FieldAttr::of(field, FieldKind::Unnamed))
+                .collect::<Result<_>>()?,
+        };
+        Ok(attrs.iter().all(|attr| {
+            attr.skip || (!attr.optional && !attr.flatten && attr.decode_with.is_none())
+        }))
+    }
+}
@@ -19,9 +19,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amplify_syn::{DeriveInner, Field, FieldKind, Items, NamedField, Variant};
+use amplify_syn::{DataInner, DeriveInner, Field, FieldKind, Fields, Items, NamedField, Variant};
 use proc_macro2::{Span, TokenStream as TokenStream2};
-use syn::{Error, Result};
+use syn::{Error, Path, Result, Type};
 
 use crate::params::{FieldAttr, StrictDerive, VariantAttr};
 
@@ -29,109 +29,202 @@ struct DeriveDumb<'a>(&'a StrictDerive);
 
 impl StrictDerive {
     pub fn derive_dumb(&self) -> Result<TokenStream2> {
-        self.data.derive(&self.conf.strict_crate, &ident!(StrictDumb), &DeriveDumb(self))
+        self.bounded(Some(ident!(StrictDumb)), self.dumb_types()?, false).derive(
+            &self.conf.strict_crate,
+            &ident!(StrictDumb),
+            &DeriveDumb(self),
+        )
     }
-}
 
-impl DeriveInner for DeriveDumb<'_> {
-    fn derive_unit_inner(&self) -> Result<TokenStream2> {
-        Ok(quote! {
-            fn strict_dumb() -> Self {
-                Self()
+    /// Collects types of the fields which dumb values are constructed with
+    /// [`StrictDumb`] implementation of the field type. Skipped fields use
+    /// [`Default`] instead, and fields with `with` codec use the dumb value
+    /// of the codec.
+    fn dumb_types(&self) -> Result<Vec<&Type>> {
+        let mut types = vec![];
+        if self.conf.dumb.is_some() {
+            return Ok(types);
+        }
+        match &self.data.inner {
+            DataInner::Struct(Fields::Named(fields)) => {
+                for named in fields {
+                    let attr = FieldAttr::of(&named.field, FieldKind::Named)?;
+                    if attr.dumb.is_none() && !attr.skip && dumb_codec(&attr).is_none() {
+                        types.push(&named.field.ty);
+                    }
+                }
+            }
+            DataInner::Struct(Fields::Unnamed(fields)) => {
+                for field in fields {
+                    let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                    if attr.dumb.is_none() && !attr.skip && dumb_codec(&attr).is_none() {
+                        types.push(&field.ty);
+                    }
+                }
+            }
+            DataInner::Enum(variants) => {
+                let fields = match self.dumb_variant(variants)?.map(|variant| &variant.fields) {
+                    Some(Fields::Named(fields)) => {
+                        fields.iter().map(|named| (&named.field, FieldKind::Named)).collect()
+                    }
+                    Some(Fields::Unnamed(fields)) => {
+                        fields.iter().map(|field| (field, FieldKind::Unnamed)).collect()
+                    }
+                    Some(Fields::Unit) | None => vec![],
+                };
+                for (field, kind) in fields {
+                    let attr = FieldAttr::of(field, kind)?;
+                    if attr.dumb.is_none() && !attr.skip && dumb_codec(&attr).is_none() {
+                        types.push(&field.ty);
+                    }
+                }
             }
-        })
+            _ => {}
+        }
+        Ok(types)
     }
 
-    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
-        if let Some(ref dumb_expr) = self.0.conf.dumb {
-            return Ok(quote! {
-                fn strict_dumb() -> Self {
-                    #dumb_expr
+    /// Selects the variant constructing the dumb value of an enum: the one
+    /// marked with `#[strict_type(dumb)]` attribute or, if none is marked, the
+    /// first variant which doesn't refer to the enum itself. Returns `None` if
+    /// all the variants are recursive.
+    fn dumb_variant<'a>(&self, variants: &'a Items<Variant>) -> Result<Option<&'a Variant>> {
+        let mut unmarked = None;
+        for variant in variants {
+            let attr = VariantAttr::try_from(variant.attr.clone())?;
+            if attr.dumb {
+                if self.is_recursive(variant)? {
+                    return Err(Error::new(
+                        variant.name.span(),
+                        "dumb variant can't refer to the enum itself, since its construction \
+                         would never terminate",
+                    ));
                 }
-            });
+                return Ok(Some(variant));
+            }
+            if unmarked.is_none() && !attr.other && !self.is_recursive(variant)? {
+                unmarked = Some(variant);
+            }
         }
+        Ok(unmarked)
+    }
+}
 
-        let crate_name = &self.0.conf.strict_crate;
-        let trait_name = quote!(#crate_name::StrictDumb);
+/// Codec constructing the dumb value of a field, which is the decoding codec
+/// of the fields having both encoding and decoding codecs, like the ones with
+/// `with` attribute.
+fn dumb_codec(attr: &FieldAttr) -> Option<&Path> {
+    match (&attr.encode_with, &attr.decode_with) {
+        (Some(_), Some(codec)) => Some(codec),
+        _ => None,
+    }
+}
 
-        let mut items = Vec::with_capacity(fields.len());
-        for named in fields {
-            let attr = FieldAttr::with(named.field.attr.clone(), FieldKind::Named)?;
-            let name = &named.name;
-            items.push(match attr.dumb {
-                None => quote! { #name: StrictDumb::strict_dumb() },
-                Some(dumb_value) => quote! { #name: #dumb_value },
-            });
-        }
+fn dumb_value(attr: &FieldAttr, ty: &Type) -> TokenStream2 {
+    match (&attr.dumb, dumb_codec(attr)) {
+        (Some(dumb_value), _) => quote! { #dumb_value },
+        (None, _) if attr.skip => quote! { Default::default() },
+        (None, Some(codec)) => quote! { ::core::convert::From::from(#codec::strict_dumb()) },
+        (None, None) => type_dumb(ty),
+    }
+}
 
-        Ok(quote! {
-            fn strict_dumb() -> Self {
-                use #trait_name;
-                Self {
-                    #( #items ),*
-                }
-            }
-        })
+/// Dumb value of a field type. Arrays are `StrictDumb` only when they are
+/// `Default`, i.e. for the lengths up to 32, thus their dumb values are made of
+/// the dumb values of their items, which also works for arrays with the length
+/// given by a const generic parameter.
+fn type_dumb(ty: &Type) -> TokenStream2 {
+    match ty {
+        Type::Array(array) => {
+            let item = type_dumb(&array.elem);
+            quote! { ::core::array::from_fn(|_| #item) }
+        }
+        Type::Group(group) => type_dumb(&group.elem),
+        Type::Paren(paren) => type_dumb(&paren.elem),
+        _ => quote! { StrictDumb::strict_dumb() },
     }
+}
 
-    fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
-        if let Some(ref dumb_expr) = self.0.conf.dumb {
-            return Ok(quote! {
+impl DeriveDumb<'_> {
+    /// Wraps the expression constructing the dumb value into a function,
+    /// unless the dumb value is given at the container level.
+    fn wrap(&self, body: TokenStream2) -> TokenStream2 {
+        let crate_name = &self.0.conf.strict_crate;
+        match self.0.conf.dumb {
+            Some(ref dumb_expr) => quote! {
                 fn strict_dumb() -> Self {
                     #dumb_expr
                 }
-            });
+            },
+            None => quote! {
+                fn strict_dumb() -> Self {
+                    #[allow(unused_imports)]
+                    use #crate_name::StrictDumb;
+                    #body
+                }
+            },
         }
+    }
+}
 
-        let crate_name = &self.0.conf.strict_crate;
-        let trait_name = quote!(#crate_name::StrictDumb);
+impl DeriveInner for DeriveDumb<'_> {
+    fn derive_unit_inner(&self) -> Result<TokenStream2> { Ok(self.wrap(quote! { Self {} })) }
 
+    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
         let mut items = Vec::with_capacity(fields.len());
-        for field in fields {
-            let attr = FieldAttr::with(field.attr.clone(), FieldKind::Unnamed)?;
-            items.push(match attr.dumb {
-                None => quote! { StrictDumb::strict_dumb() },
-                Some(dumb_value) => quote! { #dumb_value },
-            });
+        for named in fields {
+            let attr = FieldAttr::of(&named.field, FieldKind::Named)?;
+            let name = &named.name;
+            let value = dumb_value(&attr, &named.field.ty);
+            items.push(quote! { #name: #value });
         }
+        Ok(self.wrap(quote! { Self { #( #items ),* } }))
+    }
 
-        Ok(quote! {
-            fn strict_dumb() -> Self {
-                use #trait_name;
-                Self(#( #items ),*)
-            }
-        })
+    fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
+        let mut items = Vec::with_capacity(fields.len());
+        for field in fields {
+            let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+            items.push(dumb_value(&attr, &field.ty));
+        }
+        Ok(self.wrap(quote! { Self(#( #items ),*) }))
     }
 
     fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
-        if let Some(ref dumb_expr) = self.0.conf.dumb {
-            return Ok(quote! {
-                fn strict_dumb() -> Self {
-                    #dumb_expr
-                }
-            });
+        if self.0.conf.dumb.is_some() {
+            return Ok(self.wrap(quote! {}));
         }
 
-        let mut dumb_variant = None;
-        for variant in variants {
-            let attr = VariantAttr::try_from(variant.attr.clone())?;
-            let name = &variant.name;
-            if attr.dumb {
-                dumb_variant = Some(quote! { Self::#name });
-            }
-        }
-        let dumb_variant = dumb_variant.ok_or_else(|| {
-            Error::new(
+        let Some(variant) = self.0.dumb_variant(variants)? else {
+            return Err(Error::new(
                 Span::call_site(),
-                "enum must mark one of its variants with `#[strict_type(dumb)]` attribute, or \
-                 provide a dumb value in eponym attribute at container level",
-            )
-        })?;
-
-        Ok(quote! {
-            fn strict_dumb() -> Self {
-                #dumb_variant
+                "enum has no non-recursive variant which can be used as its dumb value; mark one \
+                 of the variants with `#[strict_type(dumb)]` attribute, or provide a dumb value \
+                 in eponym attribute at container level",
+            ));
+        };
+        let name = &variant.name;
+        let dumb_variant = match &variant.fields {
+            Fields::Unit => quote! { Self::#name },
+            Fields::Named(fields) => {
+                let mut items = Vec::with_capacity(fields.len());
+                for named in fields {
+                    let attr = FieldAttr::of(&named.field, FieldKind::Named)?;
+                    let field = &named.name;
+                    let value = dumb_value(&attr, &named.field.ty);
+                    items.push(quote! { #field: #value });
+                }
+                quote! { Self::#name { #( #items ),* } }
+            }
+            Fields::Unnamed(fields) => {
+                let mut items = Vec::with_capacity(fields.len());
+                for field in fields {
+                    let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                    items.push(dumb_value(&attr, &field.ty));
+                }
+                quote! { Self::#name(#( #items ),*) }
             }
-        })
+        };
+        Ok(self.wrap(dumb_variant))
     }
 }
@@ -19,49 +19,211 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amplify_syn::{DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant};
+use amplify_syn::{
+    DataInner, DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant,
+};
 use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
-use syn::{Error, Index, Result};
+use syn::spanned::Spanned;
+use syn::{Error, Index, Result, Type};
 
-use crate::params::{FieldAttr, StrictDerive, VariantAttr};
+use crate::params::{pointee, FieldAttr, StrictDerive, VariantAttr};
 
 struct DeriveEncode<'a>(&'a StrictDerive);
 
 impl StrictDerive {
     pub fn derive_encode(&self) -> Result<TokenStream2> {
-        self.data.derive(&self.conf.strict_crate, &ident!(StrictEncode), &DeriveEncode(self))
+        if self.conf.transparent {
+            return self.derive_transparent_encode();
+        }
+        if let Some(ref wire) = self.conf.into {
+            return self.derive_into_encode(wire);
+        }
+        if self.conf.try_from.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "StrictEncode of types with `try_from` attribute requires `into` attribute \
+                 specifying the wire type the value is converted into",
+            ));
+        }
+
+        let data = self.bounded(Some(ident!(StrictEncode)), self.encoded_types()?, true);
+        if self.conf.remote.is_some() {
+            return self.derive_inherent(&data, &DeriveEncode(self));
+        }
+        self.check_flattenable()?;
+        let impl_encode =
+            data.derive(&self.conf.strict_crate, &ident!(StrictEncode), &DeriveEncode(self))?;
+        let (true, DataInner::Struct(Fields::Named(fields))) =
+            (self.conf.flattenable, &self.data.inner)
+        else {
+            return Ok(impl_encode);
+        };
+
+        let crate_name = &self.conf.strict_crate;
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+        let write_fields = DeriveEncode(self).write_fields(fields)?;
+        Ok(quote! {
+            #impl_encode
+
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictEncodeFields for #type_name #ty_generics #where_clause {
+                fn strict_encode_fields<W: #crate_name::WriteStruct>(&self, w: W) -> #crate_name::io::Result<W> {
+                    #write_fields
+                    Ok(w)
+                }
+            }
+        })
     }
 }
 
-impl DeriveInner for DeriveEncode<'_> {
-    fn derive_unit_inner(&self) -> Result<TokenStream2> {
-        Err(Error::new(
-            Span::call_site(),
-            "StrictEncode must not be derived on a unit types. Use just a unit type instead when \
-             encoding parent structure.",
-        ))
+impl StrictDerive {
+    fn derive_transparent_encode(&self) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let (member, ty, _) = self.transparent_field()?;
+        let data = self.bounded(Some(ident!(StrictEncode)), [ty], false);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictEncode for #type_name #ty_generics #where_clause {
+                fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> #crate_name::io::Result<W> {
+                    #crate_name::StrictEncode::strict_encode(&self.#member, writer)
+                }
+            }
+        })
     }
+}
 
-    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
+impl StrictDerive {
+    fn derive_into_encode(&self, wire: &Type) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let data = self.bounded(Some(ident!(StrictEncode)), [wire], false);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictEncode for #type_name #ty_generics #where_clause {
+                fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> #crate_name::io::Result<W> {
+                    let wire: #wire = Clone::clone(self).into();
+                    #crate_name::StrictEncode::strict_encode(&wire, writer)
+                }
+            }
+        })
+    }
+}
+
+impl DeriveEncode<'_> {
+    /// Generates writing of the struct fields into `w` struct writer, which is
+    /// rebound to the writer after the fields. The fields of the flattened
+    /// structures are written in place of the flattened fields. The fields are
+    /// written through the `FieldsWriter` typestate, thus a field which is
+    /// missed or written twice fails the build.
+    fn write_fields(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
+        let subject = self.subject();
 
-        let mut orig_name = Vec::with_capacity(fields.len());
-        let mut field_name = Vec::with_capacity(fields.len());
+        let mut adapters = vec![];
+        let mut declared = Vec::with_capacity(fields.len());
+        let mut write = Vec::with_capacity(fields.len());
         for named_field in fields {
-            let attr = FieldAttr::with(named_field.field.attr.clone(), FieldKind::Named)?;
-            if !attr.skip {
-                orig_name.push(&named_field.name);
-                field_name.push(attr.field_name(&named_field.name));
+            let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+            let name = &named_field.name;
+            let access = quote_spanned! { name.span() => #subject.#name };
+            if attr.flatten {
+                declared.push(quote! { .. });
+                write.push(quote! { w.write_flattened(&#access)? });
+            } else if !attr.skip {
+                let adapter = Ident::new(&format!("StrictWith_{name}"), Span::call_site());
+                let value =
+                    self.field_value(&attr, &named_field.field, access, adapter, &mut adapters)?;
+                let field_name = attr.field_name(name);
+                declared.push(quote! { #field_name });
+                write.push(quote! { w.write_field(#crate_name::field!(#field_name), #value)? });
             }
         }
 
         Ok(quote! {
-            fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> ::std::io::Result<W> {
-                use #crate_name::{TypedWrite, WriteStruct, fname};
+            #( #adapters )*
+            let w = #crate_name::FieldsWriter::<_, #crate_name::fields![#( #declared ),*]>::new(w);
+            #( let w = #write; )*
+            let w = w.release();
+        })
+    }
+
+    /// Value which is encoded: either `self` or a remote type instance.
+    fn subject(&self) -> TokenStream2 {
+        match self.0.conf.remote {
+            None => quote! { self },
+            Some(_) => quote! { remote },
+        }
+    }
+
+    fn signature(&self) -> TokenStream2 {
+        let crate_name = &self.0.conf.strict_crate;
+        match self.0.conf.remote {
+            None => quote! {
+                fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> #crate_name::io::Result<W>
+            },
+            Some(ref remote) => quote! {
+                pub fn strict_encode<W: #crate_name::TypedWrite>(remote: &#remote, writer: W) -> #crate_name::io::Result<W>
+            },
+        }
+    }
+
+    /// Returns expression referencing a field value, which is wrapped into a
+    /// custom codec adapter if the field has `with` or `encode_with` attribute.
+    fn field_value(
+        &self,
+        attr: &FieldAttr,
+        field: &Field,
+        access: TokenStream2,
+        adapter: Ident,
+        adapters: &mut Vec<TokenStream2>,
+    ) -> Result<TokenStream2> {
+        let value = quote_spanned! { field.ty.span() => &#access };
+        Ok(match attr.encode_with {
+            None => value,
+            Some(ref with) => {
+                adapters.push(self.0.encode_adapter(&adapter, &field.ty, with)?);
+                quote! { &#adapter(#value) }
+            }
+        })
+    }
+}
+
+impl DeriveInner for DeriveEncode<'_> {
+    fn derive_unit_inner(&self) -> Result<TokenStream2> {
+        let crate_name = &self.0.conf.strict_crate;
+        let signature = self.signature();
+
+        Ok(quote! {
+            #signature {
+                use #crate_name::{TypedWrite, WriteTuple};
+                writer.write_tuple::<Self>(|w| Ok(w.complete()))
+            }
+        })
+    }
+
+    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
+        let crate_name = &self.0.conf.strict_crate;
+        let signature = self.signature();
+
+        // only flattenable structures implement `StrictEncodeFields`
+        let write_fields = match self.0.conf.flattenable {
+            true => quote! {
+                let w = #crate_name::StrictEncodeFields::strict_encode_fields(self, w)?;
+            },
+            false => self.write_fields(fields)?,
+        };
+        Ok(quote! {
+            #signature {
+                use #crate_name::{TypedWrite, WriteStruct};
                 writer.write_struct::<Self>(|w| {
-                    Ok(w
-                        #( .write_field(fname!(#field_name), &self.#orig_name)? )*
-                        .complete())
+                    #write_fields
+                    Ok(w.complete())
                 })
             }
         })
@@ -70,17 +232,28 @@ impl DeriveInner for DeriveEncode<'_> {
     fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
 
-        let no = fields.iter().enumerate().filter_map(|(index, field)| {
-            let attr = FieldAttr::with(field.attr.clone(), FieldKind::Unnamed).ok()?;
-            if attr.skip { None } else { Some(Index::from(index)) }
-        });
+        let signature = self.signature();
+        let subject = self.subject();
+
+        let mut adapters = vec![];
+        let mut field_value = Vec::with_capacity(fields.len());
+        for (index, field) in fields.iter().enumerate() {
+            let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+            if !attr.skip {
+                let no = Index::from(index);
+                let adapter = Ident::new(&format!("StrictWith_{index}"), Span::call_site());
+                let access = quote_spanned! { field.ty.span() => #subject.#no };
+                field_value.push(self.field_value(&attr, field, access, adapter, &mut adapters)?);
+            }
+        }
 
         Ok(quote! {
-            fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> ::std::io::Result<W> {
+            #signature {
                 use #crate_name::{TypedWrite, WriteTuple};
+                #( #adapters )*
                 writer.write_tuple::<Self>(|w| {
                     Ok(w
-                        #( .write_field(&self.#no)? )*
+                        #( .write_field(#field_value)? )*
                         .complete())
                 })
             }
@@ -89,10 +262,37 @@ impl DeriveInner for DeriveEncode<'_> {
 
     fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
+        let signature = self.signature();
+        let subject = self.subject();
+        let path = match self.0.conf.remote {
+            None => quote! { Self },
+            Some(ref remote) => quote! { #remote },
+        };
 
-        let inner = if variants.enum_kind() == EnumKind::Primitive {
+        let inner = if let Some(other) = self.0.catch_all()? {
+            let other = &other.name;
             quote! {
-                writer.write_enum(*self)
+                match *self {
+                    Self::#other(tag) => {
+                        if <Self as #crate_name::StrictSum>::ALL_VARIANTS
+                            .iter()
+                            .any(|(known, _)| *known == tag)
+                        {
+                            let kind = #crate_name::io::ErrorKind::InvalidInput;
+                            return Err(#crate_name::io::Error::from(kind));
+                        }
+                        #crate_name::StrictEncode::strict_encode(&tag, writer)
+                    }
+                    value => writer.write_enum(value),
+                }
+            }
+        } else if variants.enum_kind() == EnumKind::Primitive {
+            let value = match self.0.conf.remote {
+                None => quote! { *self },
+                Some(_) => self.0.convert_expr(subject, path, quote! { Self }),
+            };
+            quote! {
+                writer.write_enum(#value)
             }
         } else {
             let mut define_variants = Vec::with_capacity(variants.len());
@@ -107,7 +307,7 @@ impl DeriveInner for DeriveEncode<'_> {
                             .define_unit(vname!(#name))
                         });
                         write_variants.push(quote! {
-                            Self::#var_name => writer.write_unit(vname!(#name))?,
+                            #path::#var_name => writer.write_unit(vname!(#name))?,
                         });
                     }
                     Fields::Unnamed(fields) if fields.is_empty() => {
@@ -115,7 +315,7 @@ impl DeriveInner for DeriveEncode<'_> {
                             .define_unit(vname!(#name))
                         });
                         write_variants.push(quote! {
-                            Self::#var_name() => writer.write_unit(vname!(#name))?,
+                            #path::#var_name() => writer.write_unit(vname!(#name))?,
                         });
                     }
                     Fields::Named(fields) if fields.is_empty() => {
@@ -123,29 +323,45 @@ impl DeriveInner for DeriveEncode<'_> {
                             .define_unit(vname!(#name))
                         });
                         write_variants.push(quote! {
-                            Self::#var_name {} => writer.write_unit(vname!(#name))?,
+                            #path::#var_name {} => writer.write_unit(vname!(#name))?,
                         });
                     }
                     Fields::Unnamed(fields) => {
                         let mut field_ty = Vec::with_capacity(fields.len());
                         let mut field_idx = Vec::with_capacity(fields.len());
+                        let mut field_pat = Vec::with_capacity(fields.len());
                         for (index, field) in fields.iter().enumerate() {
-                            let attr = FieldAttr::with(field.attr.clone(), FieldKind::Unnamed)?;
+                            let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                            attr.forbid_with(field.ty.span(), "enum variants")?;
 
-                            if !attr.skip {
-                                let ty = &field.ty;
+                            if attr.skip {
+                                field_pat.push(quote! { _ });
+                            } else {
+                                let ty = pointee(&field.ty);
                                 let index = Ident::new(&format!("_{index}"), Span::call_site());
                                 field_ty.push(quote! { #ty });
                                 field_idx.push(quote! { #index });
+                                field_pat.push(quote! { #index });
                             }
                         }
+                        // newtype variants use the shorthand methods, which
+                        // reduces the size of the generated code
+                        if fields.len() == 1 && field_ty.len() == 1 {
+                            define_variants.push(quote! {
+                                .define_newtype::<#( #field_ty )*>(vname!(#name))
+                            });
+                            write_variants.push(quote! {
+                                #path::#var_name( #( #field_idx )* ) => writer.write_newtype(vname!(#name), #( #field_idx )*)?,
+                            });
+                            continue;
+                        }
                         define_variants.push(quote! {
                             .define_tuple(vname!(#name), |d| {
                                 d #( .define_field::<#field_ty>() )* .complete()
                             })
                         });
                         write_variants.push(quote! {
-                            Self::#var_name( #( #field_idx ),* ) => writer.write_tuple(vname!(#name), |w| {
+                            #path::#var_name( #( #field_pat ),* ) => writer.write_tuple(vname!(#name), |w| {
                                 Ok(w #( .write_field(#field_idx)? )* .complete())
                             })?,
                         });
@@ -155,10 +371,11 @@ impl DeriveInner for DeriveEncode<'_> {
                         let mut field_name = Vec::with_capacity(fields.len());
                         let mut field_rename = Vec::with_capacity(fields.len());
                         for named_field in fields {
-                            let attr =
-                                FieldAttr::with(named_field.field.attr.clone(), FieldKind::Named)?;
+                            let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+                            attr.forbid_with(named_field.name.span(), "enum variants")?;
+                            attr.forbid_flatten(named_field.name.span(), "enum variants")?;
 
-                            let ty = &named_field.field.ty;
+                            let ty = pointee(&named_field.field.ty);
                             let name = &named_field.name;
                             let rename = attr.field_name(name);
 
@@ -175,7 +392,7 @@ impl DeriveInner for DeriveEncode<'_> {
                             })
                         });
                         write_variants.push(quote! {
-                            Self::#var_name { #( #field_name ),* } => writer.write_struct(vname!(#name), |w| {
+                            #path::#var_name { #( #field_name, )* .. } => writer.write_struct(vname!(#name), |w| {
                                 Ok(w #( .write_field(#field_rename, #field_name)? )* .complete())
                             })?,
                         });
@@ -191,7 +408,7 @@ impl DeriveInner for DeriveEncode<'_> {
                         #( #define_variants )*
                         .complete();
 
-                    Ok(match self {
+                    Ok(match #subject {
                         #( #write_variants )*
                     }.complete())
                 })
@@ -199,7 +416,7 @@ impl DeriveInner for DeriveEncode<'_> {
         };
 
         Ok(quote! {
-            fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> ::std::io::Result<W> {
+            #signature {
                 use #crate_name::TypedWrite;
                 #inner
             }
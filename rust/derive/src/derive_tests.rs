@@ -0,0 +1,88 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use heck::ToSnakeCase;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Error, GenericParam, Result};
+
+use crate::params::StrictDerive;
+
+impl StrictDerive {
+    /// Generates the module with tests checking the encoding of the dumb value
+    /// of the type and of the value given in `test_value` attribute, if the
+    /// type has `derive_tests` attribute.
+    pub fn derive_tests(&self) -> Result<TokenStream2> {
+        if !self.conf.derive_tests {
+            if let Some(ref value) = self.conf.test_value {
+                return Err(Error::new(
+                    value.span(),
+                    "`test_value` attribute requires `derive_tests` attribute",
+                ));
+            }
+            return Ok(TokenStream2::new());
+        }
+        if self.conf.remote.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "tests can't be generated for remote type mirrors",
+            ));
+        }
+        if let Some(param) = self
+            .data
+            .generics
+            .params
+            .iter()
+            .find(|param| !matches!(param, GenericParam::Lifetime(_)))
+        {
+            return Err(Error::new(param.span(), "tests can't be generated for generic types"));
+        }
+
+        let crate_name = &self.conf.strict_crate;
+        let type_name = &self.data.name;
+        let module =
+            format_ident!("strict_encoding_tests_{}", type_name.to_string().to_snake_case());
+        let test_value = self.conf.test_value.as_ref().map(|value| {
+            quote! {
+                #[test]
+                fn test_value() {
+                    let value: #type_name = #value;
+                    #crate_name::test_helpers::assert_encoding(&value);
+                }
+            }
+        });
+
+        Ok(quote! {
+            #[cfg(test)]
+            mod #module {
+                use super::*;
+
+                #[test]
+                fn dumb() {
+                    let value = <#type_name as #crate_name::StrictDumb>::strict_dumb();
+                    #crate_name::test_helpers::assert_encoding(&value);
+                }
+
+                #test_value
+            }
+        })
+    }
+}
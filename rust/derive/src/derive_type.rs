@@ -22,17 +22,17 @@
 use amplify_syn::{
     DataInner, DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant,
 };
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::ToTokens;
-use syn::Result;
+use syn::{Error, GenericParam, LitStr, Result, Type};
 
 use crate::params::{EnumAttr, FieldAttr, StrictDerive, VariantAttr, VariantTags};
 
 struct DeriveType<'a>(&'a StrictDerive);
 struct DeriveProduct;
 struct DeriveTuple;
-struct DeriveStruct;
-struct DeriveSum(EnumAttr);
+struct DeriveStruct<'a>(&'a StrictDerive);
+struct DeriveSum(SumVariants);
 struct DeriveEnum;
 struct DeriveUnion;
 
@@ -41,17 +41,76 @@ impl StrictDerive {
         let trait_crate = &self.conf.strict_crate;
         let type_name = &self.data.name;
 
-        let impl_type = self.data.derive(trait_crate, &ident!(StrictType), &DeriveType(self))?;
+        let impl_arbitrary =
+            if self.conf.arbitrary { self.derive_arbitrary()? } else { TokenStream2::new() };
+
+        let wire_type = match self.conf.wire_type() {
+            Some(ty) => Some(ty),
+            None if self.conf.transparent => Some(self.transparent_field()?.1),
+            None => None,
+        };
+        if let Some(ty) = wire_type {
+            let impl_type = self.derive_forwarded_type(ty)?;
+            return Ok(quote! {
+                #impl_type
+                #impl_arbitrary
+            });
+        }
+
+        let data = self.bounded(None, None, false);
+        let data_dumb = self.bounded(None, None, true);
+
+        let impl_type = data.derive(trait_crate, &ident!(StrictType), &DeriveType(self))?;
 
         let impl_outer = match &self.data.inner {
             DataInner::Struct(_) => {
-                self.data.derive(trait_crate, &ident!(StrictProduct), &DeriveProduct)?
+                data_dumb.derive(trait_crate, &ident!(StrictProduct), &DeriveProduct)?
             }
             DataInner::Enum(variants) => {
                 let enum_attr = EnumAttr::with(self.data.attr.clone(), variants.enum_kind())?;
+                let sum = SumVariants::with(enum_attr.tags, variants, &self.discriminants)?;
+
+                let impl_try_from_u8 = if let Some((ref other, _)) = sum.other {
+                    if enum_attr.tags == VariantTags::Repr {
+                        return Err(Error::new(
+                            Span::call_site(),
+                            "enum with a catch-all `other` variant can't use `tags = repr`",
+                        ));
+                    }
+                    if enum_attr.try_from_u8 || enum_attr.into_u8 {
+                        return Err(Error::new(
+                            Span::call_site(),
+                            "enum with a catch-all `other` variant implements conversions from \
+                             and into `u8` by itself; remove `try_from_u8` and `into_u8` \
+                             attributes",
+                        ));
+                    }
+                    let SumVariants { idents, orders, .. } = &sum;
 
-                let impl_try_from_u8 = if enum_attr.try_from_u8 {
-                    let variant_name = variants.iter().map(|var| &var.name);
+                    quote! {
+                        #[automatically_derived]
+                        impl TryFrom<u8> for #type_name {
+                            type Error = #trait_crate::VariantError<u8>;
+                            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                                match value {
+                                    #( x if x == #orders as u8 => Ok(Self::#idents), )*
+                                    unknown => Ok(Self::#other(unknown)),
+                                }
+                            }
+                        }
+
+                        #[automatically_derived]
+                        impl From<#type_name> for u8 {
+                            #[inline]
+                            fn from(value: #type_name) -> u8 {
+                                #trait_crate::StrictSum::variant_ord(&value)
+                            }
+                        }
+                    }
+                } else if enum_attr.try_from_u8 {
+                    // conversions use the same tags as `ALL_VARIANTS` and thus
+                    // can't disagree with it
+                    let SumVariants { idents, orders, .. } = &sum;
 
                     quote! {
                         #[automatically_derived]
@@ -59,7 +118,7 @@ impl StrictDerive {
                             type Error = #trait_crate::VariantError<u8>;
                             fn try_from(value: u8) -> Result<Self, Self::Error> {
                                 match value {
-                                    #( x if x == Self::#variant_name as u8 => Ok(Self::#variant_name), )*
+                                    #( x if x == #orders as u8 => Ok(Self::#idents), )*
                                     wrong => Err(#trait_crate::VariantError::with::<Self>(wrong)),
                                 }
                             }
@@ -84,7 +143,7 @@ impl StrictDerive {
                 };
 
                 let impl_struct_enum =
-                    self.data.derive(trait_crate, &ident!(StrictSum), &DeriveSum(enum_attr))?;
+                    data.derive(trait_crate, &ident!(StrictSum), &DeriveSum(sum))?;
 
                 quote! {
                     #impl_into_u8
@@ -95,36 +154,119 @@ impl StrictDerive {
             _ => TokenStream2::new(),
         };
 
+        let catch_all = self.catch_all()?.is_some();
         let impl_inner = match &self.data.inner {
             DataInner::Struct(Fields::Named(_)) => {
-                self.data.derive(trait_crate, &ident!(StrictStruct), &DeriveStruct)?
+                data_dumb.derive(trait_crate, &ident!(StrictStruct), &DeriveStruct(self))?
             }
-            DataInner::Struct(Fields::Unnamed(_)) => {
-                self.data.derive(trait_crate, &ident!(StrictTuple), &DeriveTuple)?
+            // unit structs are tuples without fields
+            DataInner::Struct(Fields::Unnamed(_) | Fields::Unit) => {
+                data_dumb.derive(trait_crate, &ident!(StrictTuple), &DeriveTuple)?
             }
-            DataInner::Enum(variants) if variants.enum_kind() == EnumKind::Primitive => {
-                self.data.derive(trait_crate, &ident!(StrictEnum), &DeriveEnum)?
+            DataInner::Enum(variants)
+                if catch_all || variants.enum_kind() == EnumKind::Primitive =>
+            {
+                data.derive(trait_crate, &ident!(StrictEnum), &DeriveEnum)?
             }
             DataInner::Enum(_) => {
-                self.data.derive(trait_crate, &ident!(StrictUnion), &DeriveUnion)?
+                data_dumb.derive(trait_crate, &ident!(StrictUnion), &DeriveUnion)?
             }
             _ => TokenStream2::new(),
         };
 
+        // generic types can't be named in a constant, so they are checked at
+        // runtime only; lifetime parameters are elided
+        let generic = self
+            .data
+            .generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, GenericParam::Lifetime(_)));
+        let kind = match &self.data.inner {
+            _ if generic => None,
+            DataInner::Struct(Fields::Named(_)) => Some(quote!(struct)),
+            DataInner::Struct(Fields::Unnamed(_) | Fields::Unit) => Some(quote!(tuple)),
+            DataInner::Enum(variants)
+                if catch_all || variants.enum_kind() == EnumKind::Primitive =>
+            {
+                Some(quote!(enum))
+            }
+            DataInner::Enum(_) => Some(quote!(union)),
+            _ => None,
+        };
+        let sanity_check = match kind {
+            Some(kind) => quote! { #trait_crate::assert_type_sanity!(#kind #type_name); },
+            None => TokenStream2::new(),
+        };
+
         Ok(quote! {
             #impl_type
             #impl_outer
             #impl_inner
+            #impl_arbitrary
+            #sanity_check
+        })
+    }
+}
+
+impl StrictDerive {
+    /// Derives type information matching the one of the type `ty`, which is
+    /// either the wrapped type of a transparent type or a wire type of a
+    /// converted type.
+    fn derive_forwarded_type(&self, ty: &Type) -> Result<TokenStream2> {
+        let crate_name = &self.conf.strict_crate;
+        let data = self.bounded(Some(ident!(StrictType)), [ty], false);
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #crate_name::StrictType for #type_name #ty_generics #where_clause {
+                const STRICT_LIB_NAME: &'static str = <#ty as #crate_name::StrictType>::STRICT_LIB_NAME;
+
+                fn strict_name() -> Option<#crate_name::TypeName> {
+                    <#ty as #crate_name::StrictType>::strict_name()
+                }
+            }
         })
     }
 }
 
 impl DeriveType<'_> {
-    pub fn derive_type(&self) -> Result<TokenStream2> {
+    pub fn derive_type(&self) -> Result<TokenStream2> { self.derive_type_with(vec![]) }
+
+    /// Derives type information together with the documentation of the listed
+    /// members, which are the fields or variants in the order of their type
+    /// information.
+    fn derive_type_with(&self, members: Vec<Option<&String>>) -> Result<TokenStream2> {
         let crate_name = &self.0.conf.strict_crate;
         let lib_name = &self.0.conf.lib;
 
+        let generics = &self.0.data.generics;
+        let const_params = generics.const_params().map(|param| &param.ident).collect::<Vec<_>>();
         let strict_name = match self.0.conf.rename {
+            // the values of const generic parameters are added to the name,
+            // since otherwise all instantiations of the type would have the
+            // same name
+            Some(ref rename) if !const_params.is_empty() => quote! {
+                fn strict_name() -> Option<#crate_name::TypeName> {
+                    #crate_name::const_generic_name(#rename, &[], &[ #( &#const_params ),* ]).ok()
+                }
+            },
+            None if !const_params.is_empty() => {
+                let base = self.0.data.name.to_string();
+                let type_params = generics.type_params().map(|param| &param.ident);
+                quote! {
+                    fn strict_name() -> Option<#crate_name::TypeName> {
+                        #crate_name::const_generic_name(
+                            #base,
+                            &[ #( #crate_name::type_name::<#type_params>() ),* ],
+                            &[ #( &#const_params ),* ],
+                        )
+                        .ok()
+                    }
+                }
+            }
             Some(ref rename) => quote! {
                 fn strict_name() -> Option<#crate_name::TypeName> {
                     Some(tn!(#rename))
@@ -133,29 +275,79 @@ impl DeriveType<'_> {
             None => TokenStream2::new(),
         };
 
+        let strict_docs = if self.0.conf.docs {
+            let doc_str = |doc: Option<&String>| match doc {
+                Some(doc) => quote! { Some(#doc) },
+                None => quote! { None },
+            };
+            let doc = doc_str(self.0.docs.doc.as_ref());
+            let member = members.into_iter().map(doc_str);
+            quote! {
+                fn strict_docs() -> #crate_name::TypeDocs {
+                    #crate_name::TypeDocs {
+                        doc: #doc,
+                        members: &[ #( #member ),* ],
+                    }
+                }
+            }
+        } else {
+            TokenStream2::new()
+        };
+
         Ok(quote! {
             const STRICT_LIB_NAME: &'static str = #lib_name;
 
             #strict_name
+            #strict_docs
         })
     }
+
+    /// Doc comments of the fields which are not skipped.
+    fn field_docs<'f>(
+        &self,
+        fields: impl IntoIterator<Item = &'f Field>,
+        kind: FieldKind,
+    ) -> Result<Vec<Option<&String>>> {
+        let mut docs = vec![];
+        for (field, doc) in fields.into_iter().zip(&self.0.docs.members) {
+            if !FieldAttr::of(field, kind)?.skip {
+                docs.push(doc.as_ref());
+            }
+        }
+        Ok(docs)
+    }
 }
 
 impl DeriveInner for DeriveType<'_> {
     fn derive_unit_inner(&self) -> Result<TokenStream2> { self.derive_type() }
-    fn derive_struct_inner(&self, _fields: &Items<NamedField>) -> Result<TokenStream2> {
-        self.derive_type()
+    fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
+        if self.0.conf.docs && self.0.has_flatten()? {
+            return Err(Error::new(
+                Span::call_site(),
+                "`docs` attribute can't be used for structures with flattened fields, since the \
+                 fields of the flattened structures are not known to the derive macro",
+            ));
+        }
+        let docs = self.field_docs(fields.iter().map(|named| &named.field), FieldKind::Named)?;
+        self.derive_type_with(docs)
     }
-    fn derive_tuple_inner(&self, _fields: &Items<Field>) -> Result<TokenStream2> {
-        self.derive_type()
+    fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
+        let docs = self.field_docs(fields, FieldKind::Unnamed)?;
+        self.derive_type_with(docs)
     }
-    fn derive_enum_inner(&self, _variants: &Items<Variant>) -> Result<TokenStream2> {
-        self.derive_type()
+    fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
+        let mut docs = vec![];
+        for (variant, doc) in variants.iter().zip(&self.0.docs.members) {
+            if !VariantAttr::try_from(variant.attr.clone())?.other {
+                docs.push(doc.as_ref());
+            }
+        }
+        self.derive_type_with(docs)
     }
 }
 
 impl DeriveInner for DeriveProduct {
-    fn derive_unit_inner(&self) -> Result<TokenStream2> { unreachable!() }
+    fn derive_unit_inner(&self) -> Result<TokenStream2> { Ok(TokenStream2::new()) }
     fn derive_enum_inner(&self, _variants: &Items<Variant>) -> Result<TokenStream2> {
         unreachable!()
     }
@@ -175,48 +367,124 @@ impl DeriveInner for DeriveSum {
     }
     fn derive_tuple_inner(&self, _fields: &Items<Field>) -> Result<TokenStream2> { unreachable!() }
 
-    fn derive_enum_inner(&self, variants: &Items<Variant>) -> Result<TokenStream2> {
+    fn derive_enum_inner(&self, _variants: &Items<Variant>) -> Result<TokenStream2> {
+        let SumVariants {
+            orders,
+            idents,
+            renames,
+            other,
+        } = &self.0;
+
+        let (other_name, other_ord) = match other {
+            None => (TokenStream2::new(), TokenStream2::new()),
+            Some((other, other_rename)) => (
+                quote! { Self::#other(_) => #other_rename, },
+                quote! { Self::#other(tag) => *tag, },
+            ),
+        };
+
+        Ok(quote! {
+            const ALL_VARIANTS: &'static [(u8, &'static str)] = &[
+                #( (#orders as u8, #renames) ),*
+            ];
+
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    #( Self::#idents => #renames, )*
+                    #other_name
+                }
+            }
+
+            fn variant_ord(&self) -> u8 {
+                match self {
+                    #( Self::#idents => #orders as u8, )*
+                    #other_ord
+                }
+            }
+        })
+    }
+}
+
+/// Tags and names of the enum variants. The catch-all variant, marked with
+/// `other` attribute, has no tag of its own and is listed separately.
+struct SumVariants {
+    orders: Vec<TokenStream2>,
+    idents: Vec<TokenStream2>,
+    renames: Vec<LitStr>,
+    other: Option<(Ident, LitStr)>,
+}
+
+impl SumVariants {
+    fn with(tags: VariantTags, variants: &Items<Variant>, discriminants: &[Ident]) -> Result<Self> {
         let mut orders = Vec::with_capacity(variants.len());
         let mut idents = Vec::with_capacity(variants.len());
         let mut renames = Vec::with_capacity(variants.len());
-
-        for (index, variant) in variants.iter().enumerate() {
+        let mut other = None;
+        let primitive = variants.enum_kind() == EnumKind::Primitive;
+        for variant in variants {
             let attr = VariantAttr::try_from(variant.attr.clone())?;
             let name = &variant.name;
             let rename = attr.variant_name(name);
-            let tag = match (&self.0.tags, &attr.tag) {
+            if attr.other {
+                other = Some((name.clone(), rename));
+                continue;
+            }
+            let index = orders.len();
+            let tag = match (&tags, &attr.tag) {
+                // primitive enums are converted into `u8` by casting, thus the
+                // tags must be the discriminants
+                (_, Some(_)) if primitive => {
+                    return Err(Error::new(
+                        name.span(),
+                        "variants of primitive enum types can't override their tags; use the enum \
+                         discriminants instead",
+                    ));
+                }
                 (_, Some(tag)) => tag.to_token_stream(),
+                // an enum without any discriminants most likely relies on the
+                // declaration order, which should be requested explicitly
+                (VariantTags::Repr, None) if discriminants.is_empty() => {
+                    return Err(Error::new(
+                        name.span(),
+                        "`tags = repr` requires the enum to have explicit discriminants; add them \
+                         or use `tags = order`",
+                    ));
+                }
                 (VariantTags::Repr, None) => quote! { Self::#name },
                 (VariantTags::Order, None) => quote! { #index },
                 (VariantTags::Custom, None) => {
-                    panic!("tag is required for variant `{}`", variant.name)
+                    return Err(Error::new(
+                        name.span(),
+                        format!(
+                            "variant `{name}` has no `#[strict_type(tag = ...)]` attribute, which \
+                             is required by `tags = custom`"
+                        ),
+                    ));
                 }
             };
-            orders.push(quote!(#tag));
-            renames.push(quote!(#rename));
+            orders.push(tag);
+            renames.push(rename);
             idents.push(match variant.fields {
                 Fields::Unit => quote!(#name),
                 Fields::Named(_) => quote!(#name { .. }),
                 Fields::Unnamed(_) => quote!(#name(..)),
             });
         }
-
-        Ok(quote! {
-            const ALL_VARIANTS: &'static [(u8, &'static str)] = &[
-                #( (#orders as u8, #renames) ),*
-            ];
-
-            fn variant_name(&self) -> &'static str {
-                match self {
-                    #( Self::#idents => #renames, )*
-                }
-            }
+        Ok(SumVariants {
+            orders,
+            idents,
+            renames,
+            other,
         })
     }
 }
 
 impl DeriveInner for DeriveTuple {
-    fn derive_unit_inner(&self) -> Result<TokenStream2> { unreachable!() }
+    fn derive_unit_inner(&self) -> Result<TokenStream2> {
+        Ok(quote! {
+            const FIELD_COUNT: u8 = 0;
+        })
+    }
     fn derive_struct_inner(&self, _fields: &Items<NamedField>) -> Result<TokenStream2> {
         unreachable!()
     }
@@ -225,14 +493,19 @@ impl DeriveInner for DeriveTuple {
     }
 
     fn derive_tuple_inner(&self, fields: &Items<Field>) -> Result<TokenStream2> {
-        let field_count = fields.len();
+        let mut field_count = 0usize;
+        for field in fields {
+            if !FieldAttr::of(field, FieldKind::Unnamed)?.skip {
+                field_count += 1;
+            }
+        }
         Ok(quote! {
             const FIELD_COUNT: u8 = #field_count as u8;
         })
     }
 }
 
-impl DeriveInner for DeriveStruct {
+impl DeriveInner for DeriveStruct<'_> {
     fn derive_unit_inner(&self) -> Result<TokenStream2> { unreachable!() }
     fn derive_enum_inner(&self, _variants: &Items<Variant>) -> Result<TokenStream2> {
         unreachable!()
@@ -240,18 +513,45 @@ impl DeriveInner for DeriveStruct {
     fn derive_tuple_inner(&self, _fields: &Items<Field>) -> Result<TokenStream2> { unreachable!() }
 
     fn derive_struct_inner(&self, fields: &Items<NamedField>) -> Result<TokenStream2> {
-        let mut name = Vec::with_capacity(fields.len());
+        if !self.0.has_flatten()? {
+            let mut name = Vec::with_capacity(fields.len());
+            for named_field in fields {
+                let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+                if !attr.skip {
+                    name.push(attr.field_name(&named_field.name));
+                }
+            }
+
+            return Ok(quote! {
+                const ALL_FIELDS: &'static [&'static str] = &[
+                    #( #name ),*
+                ];
+            });
+        }
+
+        // the fields of the flattened structures are inserted in place of the
+        // flattened fields; repeated names are caught by the sanity check
+        let crate_name = &self.0.conf.strict_crate;
+        let mut parts = Vec::with_capacity(fields.len());
         for named_field in fields {
-            let attr = FieldAttr::with(named_field.field.attr.clone(), FieldKind::Named)?;
-            if !attr.skip {
-                name.push(attr.field_name(&named_field.name));
+            let attr = FieldAttr::of(&named_field.field, FieldKind::Named)?;
+            let ty = &named_field.field.ty;
+            if attr.flatten {
+                self.0.check_flatten(ty)?;
+                parts.push(quote! { <#ty as #crate_name::StrictStruct>::ALL_FIELDS });
+            } else if !attr.skip {
+                let name = attr.field_name(&named_field.name);
+                parts.push(quote! { &[#name] });
             }
         }
 
         Ok(quote! {
-            const ALL_FIELDS: &'static [&'static str] = &[
-                #( #name ),*
-            ];
+            const ALL_FIELDS: &'static [&'static str] = {
+                const PARTS: &[&[&str]] = &[ #( #parts ),* ];
+                const FIELDS: [&str; #crate_name::fields_len(PARTS)] =
+                    #crate_name::flatten_fields::<{ #crate_name::fields_len(PARTS) }>(PARTS);
+                &FIELDS
+            };
         })
     }
 }
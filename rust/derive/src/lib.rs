@@ -58,6 +58,10 @@ mod derive_dumb;
 mod derive_type;
 mod derive_encode;
 mod derive_decode;
+mod derive_borrowed;
+mod derive_decode_into;
+mod derive_arbitrary;
+mod derive_tests;
 
 use proc_macro::TokenStream;
 use syn::DeriveInput;
@@ -65,6 +69,24 @@ use syn::DeriveInput;
 use crate::params::StrictDerive;
 
 /// Derives [`StrictDumb`] implementation for the type.
+///
+/// The dumb value of an enum is its variant marked with
+/// `#[strict_type(dumb)]` attribute or, if none is marked, the first variant
+/// which doesn't refer to the enum itself; the fields of the variant get their
+/// dumb values. Recursive types, like expression trees storing the enum in
+/// `Box`, `Rc` or `Arc` fields, thus have a terminating dumb value and don't
+/// need to implement [`Default`]. Enums where all variants are recursive must
+/// provide a dumb value with `#[strict_type(dumb = ...)]` container attribute.
+///
+/// Array fields get arrays of the dumb values of their items, thus arrays of
+/// any length, including the ones sized with const generic parameters, don't
+/// need to implement [`Default`].
+///
+/// Fields with `#[strict_type(with = "codec")]` attribute get the dumb value
+/// returned by `codec::strict_dumb()` function, converted into the field type
+/// with [`From`]. Thus a mirror of a remote type, which is converted into the
+/// remote type with the [`From`] implementation derived by [`StrictDecode`],
+/// provides the dumb values of the remote type fields.
 #[proc_macro_derive(StrictDumb, attributes(strict_type))]
 pub fn derive_strict_dumb(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -75,6 +97,63 @@ pub fn derive_strict_dumb(input: TokenStream) -> TokenStream {
 }
 
 /// Derives [`StrictType`] implementation for the type.
+///
+/// For non-generic types the derivation also checks at compile time that the
+/// names and tags of the fields and variants are unique, like
+/// `strict_encoding::assert_type_sanity!` does.
+///
+/// The names of types with const generic parameters, including the renamed
+/// ones, end with the values of the parameters separated by underscores, like
+/// `Packet_32` for `Packet<32>`, such that each instantiation of the type has
+/// its own name.
+///
+/// Enums must state how their variants get tags with `tags` attribute:
+/// `tags = order` numbers the variants in their declaration order starting
+/// from zero; `tags = repr` uses the explicit enum discriminants, which the
+/// enum is required to have; `tags = custom` requires each variant to have
+/// `#[strict_type(tag = ...)]` attribute. With `order` and `repr` a variant may
+/// still override its tag with the `tag` attribute. Enums without fields are
+/// converted into `u8` by casting, thus they must use `tags = repr` and can't
+/// override the tags. Missing tag information is a compile error, as are
+/// repeated tags of non-generic enums.
+///
+/// Types marked with `#[strict_type(arbitrary)]` attribute additionally get
+/// `StrictArbitrary` and `arbitrary::Arbitrary` implementations generating
+/// random valid values; this requires `arbitrary` feature of
+/// `strict_encoding` crate.
+///
+/// Types marked with `#[strict_type(docs)]` attribute capture their doc
+/// comments and the ones of their fields and variants, returning them from
+/// `StrictType::strict_docs`; they are included into the type information
+/// exported with `TypeInfoOwned`.
+///
+/// Enums with unit variants marked with `#[strict_type(into_u8, try_from_u8)]`
+/// attribute get `From<Enum> for u8` and `TryFrom<u8>` implementations using
+/// the tags of `StrictSum::ALL_VARIANTS`, whichever of `tags = repr`, `order` or
+/// `custom` the enum uses, thus the conversions always agree with the encoding.
+///
+/// A field of a structure marked with `#[strict_type(flatten)]` attribute must
+/// be a structure with named fields deriving the strict encoding traits itself
+/// and marked with `#[strict_type(flattenable)]` attribute.
+/// Its fields are listed in `StrictStruct::ALL_FIELDS` in place of the
+/// flattened field, as if they were the fields of the outer structure, and are
+/// encoded and decoded as such with `StrictEncodeFields` and
+/// `StrictDecodeFields`; the encoding is the same as of the structure encoded
+/// as a field. Names of the flattened fields repeating the names of other
+/// fields are a compile error for non-generic structures. The type of a
+/// flattened field can't depend on generic parameters, and the field can't be
+/// skipped, optional, renamed or use custom codecs.
+///
+/// Unit structs are strict tuples without fields; they are encoded as zero
+/// bytes, while still having their own type name, and thus can be used as the
+/// payload of union variants which carry all their meaning in the tag.
+///
+/// An enum with unit variants may mark a single variant with a single `u8`
+/// field as `#[strict_type(other)]`. Such catch-all variant doesn't have a tag
+/// of its own and isn't listed among the known variants of the type; on
+/// decoding it receives all the tags unknown to the enum, which are written
+/// back as they are on encoding. Encoding a catch-all variant storing a tag of a
+/// known variant fails with [`std::io::ErrorKind::InvalidInput`] error.
 #[proc_macro_derive(StrictType, attributes(strict_type))]
 pub fn derive_strict_type(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -85,6 +164,15 @@ pub fn derive_strict_type(input: TokenStream) -> TokenStream {
 }
 
 /// Derives [`StrictEncode`] implementation for the type.
+///
+/// Types with `#[strict_type(into = "Wire")]` attribute are encoded by cloning
+/// and converting the value into the `Wire` type, which is then encoded; their
+/// derived [`StrictType`] information is the one of the `Wire` type.
+///
+/// Fields with `#[strict_type(with = "codec")]` or
+/// `#[strict_type(encode_with = "codec")]` attribute are encoded with
+/// `codec::strict_encode(&value, writer)` function instead of the
+/// [`StrictEncode`] implementation of the field type.
 #[proc_macro_derive(StrictEncode, attributes(strict_type))]
 pub fn derive_strict_encode(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -95,6 +183,59 @@ pub fn derive_strict_encode(input: TokenStream) -> TokenStream {
 }
 
 /// Derives [`StrictDecode`] implementation for the type.
+///
+/// Types with `#[strict_type(try_from = "Wire")]` attribute are decoded by
+/// decoding the `Wire` type and converting it with [`TryFrom`]; conversion
+/// errors are reported as `DecodeError::Conversion`.
+///
+/// Types with `#[strict_type(validate = "path::to::check")]` attribute check
+/// their invariants with `check(&value) -> Result<(), impl Display>` function
+/// once all their fields are decoded and the skipped fields are defaulted,
+/// also when they are flattened into another structure; validation errors are
+/// reported as `DecodeError::InvalidValue` with the type name and the message.
+///
+/// Types with `#[strict_type(derive_tests)]` attribute get a `#[cfg(test)]`
+/// module named `strict_encoding_tests_<type_name>` with a test checking that
+/// the dumb value round-trips and is encoded deterministically and canonically,
+/// using `strict_encoding::test_helpers::assert_encoding`, which requires
+/// `test_helpers` feature in the tests. `#[strict_type(test_value = expr)]`
+/// attribute adds the same test for the value of the expression. For types
+/// implementing [`Default`] the dumb value is the default one. The tests can't
+/// be generated for generic types and remote type mirrors, and the types must
+/// be declared in a module (not in a function), implement [`PartialEq`],
+/// [`Debug`](std::fmt::Debug) and [`Sync`].
+///
+/// Fields with `#[strict_type(with = "codec")]` or
+/// `#[strict_type(decode_with = "codec")]` attribute are decoded with
+/// `codec::strict_decode(reader)` function instead of the [`StrictDecode`]
+/// implementation of the field type.
+///
+/// Mirrors of remote types, marked with `#[strict_type(remote = "Remote")]`
+/// attribute, get `strict_decode` function returning the remote type instead
+/// of the [`StrictDecode`] implementation, and the [`From`] implementation
+/// converting the mirror into the remote type field by field.
+///
+/// Structures with named fields marked with `#[strict_type(project(a, b))]`
+/// attribute additionally get `strict_decode_projection` function, which
+/// decodes only the listed fields and returns them as a tuple in the order of
+/// the list. The other fields are skipped with `TypedRead::skip` without being
+/// decoded or allocated, and thus are checked only as much as needed to find
+/// their end; see `StrictSkip::strict_skip`. Projected types can't be
+/// generic or have flattened fields, and the skipped fields can't be optional
+/// or use custom codecs.
+///
+/// Types borrowing data in their fields, i.e. having fields with types
+/// parametrized by a lifetime of the type, and types marked with
+/// `#[strict_type(encode_only)]` attribute can only be encoded; deriving
+/// `StrictDecode` for them fails to compile.
+///
+/// Structs, enums and enum variants marked with `#[non_exhaustive]` are
+/// supported: the derived implementations belong to the defining crate, where
+/// the values can be constructed as usual, and may be used from other crates.
+/// The attribute doesn't change the encoding: decoding tags which don't belong
+/// to the declared variants fails as for any other enum. Mirrors of remote
+/// `#[non_exhaustive]` types from other crates can't construct them and thus
+/// can't derive `StrictDecode`.
 #[proc_macro_derive(StrictDecode, attributes(strict_type))]
 pub fn derive_strict_decode(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -103,3 +244,31 @@ pub fn derive_strict_decode(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
+
+/// Derives `StrictDecodeInto` implementation for the type, decoding the data
+/// into an existing value and reusing the memory allocated by it.
+///
+/// The fields of structures are decoded into one by one: the fields
+/// implementing `StrictDecodeInto` reuse their memory, while the other fields
+/// are replaced with their newly decoded values; skipped fields get their
+/// default values. Enums, unions
+/// and structures with optional fields, fields with custom codecs or
+/// `#[strict_type(try_from = "Wire")]` attribute are decoded as new values
+/// replacing the existing ones.
+#[proc_macro_derive(StrictDecodeInto, attributes(strict_type))]
+pub fn derive_strict_decode_into(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    StrictDerive::try_from(derive_input)
+        .and_then(|engine| engine.derive_decode_into())
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(StrictDecodeBorrowed, attributes(strict_type))]
+pub fn derive_strict_decode_borrowed(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    StrictDerive::try_from(derive_input)
+        .and_then(|engine| engine.derive_decode_borrowed())
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -22,13 +22,18 @@
 use std::collections::HashMap;
 
 use amplify_syn::{
-    ArgValueReq, AttrReq, DataType, EnumKind, FieldKind, ListReq, ParametrizedAttr, TypeClass,
-    ValueClass,
+    ArgValueReq, AttrReq, DataInner, DataType, DeriveInner, EnumKind, Field, FieldKind, Fields,
+    ListReq, ParametrizedAttr, TypeClass, ValueClass, Variant,
 };
 use heck::ToLowerCamelCase;
-use proc_macro2::{Ident, Span};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream as TokenStream2, TokenTree};
 use quote::ToTokens;
-use syn::{DeriveInput, Error, Expr, LitInt, LitStr, Path, Result};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, GenericArgument, Index, Lit, LitInt, LitStr, Member,
+    Meta, MetaNameValue, Path, PathArguments, Result, Token, Type, WherePredicate,
+};
 
 const ATTR: &str = "strict_type";
 const ATTR_CRATE: &str = "crate";
@@ -44,14 +49,50 @@ const ATTR_TAGS_REPR: &str = "repr";
 const ATTR_TAGS_CUSTOM: &str = "custom";
 const ATTR_TAG: &str = "tag";
 const ATTR_SKIP: &str = "skip";
+const ATTR_OPTIONAL: &str = "optional";
+const ATTR_FLATTEN: &str = "flatten";
 const ATTR_INTO_U8: &str = "into_u8";
 const ATTR_TRY_FROM_U8: &str = "try_from_u8";
+const ATTR_BOUND: &str = "bound";
+const ATTR_NO_BOUND: &str = "no_bound";
+const ATTR_PROJECT: &str = "project";
+const ATTR_REMOTE: &str = "remote";
+const ATTR_TRANSPARENT: &str = "transparent";
+const ATTR_ARBITRARY: &str = "arbitrary";
+const ATTR_ENCODE_ONLY: &str = "encode_only";
+const ATTR_DOCS: &str = "docs";
+const ATTR_OTHER: &str = "other";
+const ATTR_INTO: &str = "into";
+const ATTR_TRY_FROM: &str = "try_from";
+const ATTR_VALIDATE: &str = "validate";
+const ATTR_DERIVE_TESTS: &str = "derive_tests";
+const ATTR_FLATTENABLE: &str = "flattenable";
+const ATTR_TEST_VALUE: &str = "test_value";
 
 pub struct ContainerAttr {
     pub strict_crate: Path,
-    pub lib: Expr,
+    pub lib: Option<Expr>,
     pub rename: Option<LitStr>,
     pub dumb: Option<Expr>,
+    pub bound: Option<Vec<WherePredicate>>,
+    pub no_bound: Vec<Ident>,
+    /// Fields decoded by the partial decoder generated with `project(...)`.
+    pub project: Option<Vec<Ident>>,
+    pub remote: Option<Path>,
+    pub transparent: bool,
+    pub into: Option<Type>,
+    pub try_from: Option<Type>,
+    pub arbitrary: bool,
+    pub encode_only: bool,
+    pub docs: bool,
+    /// Structure can be flattened into other structures.
+    pub flattenable: bool,
+    /// Function checking invariants of the decoded value.
+    pub validate: Option<Path>,
+    /// Generate tests of the encoding of the type.
+    pub derive_tests: bool,
+    /// Value checked by the generated tests in addition to the dumb one.
+    pub test_value: Option<Expr>,
     #[allow(dead_code)]
     pub encode_with: Option<Path>,
     #[allow(dead_code)]
@@ -67,11 +108,16 @@ pub struct EnumAttr {
 pub struct FieldAttr {
     pub dumb: Option<Expr>,
     pub rename: Option<LitStr>,
+    pub encode_with: Option<Path>,
+    pub decode_with: Option<Path>,
     pub skip: bool,
+    pub optional: bool,
+    pub flatten: bool,
 }
 
 pub struct VariantAttr {
     pub dumb: bool,
+    pub other: bool,
     pub rename: Option<LitStr>,
     pub tag: Option<LitInt>,
 }
@@ -87,21 +133,54 @@ impl ContainerAttr {
     fn shared_attrs() -> Vec<(&'static str, ArgValueReq)> {
         vec![
             (ATTR_CRATE, ArgValueReq::optional(TypeClass::Path)),
-            (ATTR_LIB, ArgValueReq::required(ValueClass::Expr)),
+            (ATTR_LIB, ArgValueReq::optional(ValueClass::Expr)),
             (ATTR_RENAME, ArgValueReq::optional(ValueClass::str())),
             (ATTR_DUMB, ArgValueReq::optional(ValueClass::Expr)),
+            (ATTR_BOUND, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_REMOTE, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_INTO, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_TRY_FROM, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_VALIDATE, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_TEST_VALUE, ArgValueReq::optional(ValueClass::Expr)),
             (ATTR_ENCODE_WITH, ArgValueReq::optional(TypeClass::Path)),
             (ATTR_DECODE_WITH, ArgValueReq::optional(TypeClass::Path)),
         ]
     }
+
+    /// Type used as the encoded form of a type with `into` or `try_from`
+    /// conversion attributes.
+    pub fn wire_type(&self) -> Option<&Type> { self.into.as_ref().or(self.try_from.as_ref()) }
 }
 
 impl EnumAttr {
     fn attr_req(map: HashMap<&str, ArgValueReq>, kind: EnumKind) -> AttrReq {
         let mut req = AttrReq::with(map);
-        if kind == EnumKind::Primitive {
-            req.path_req = ListReq::any_of(vec![path!(try_from_u8), path!(into_u8)], false);
-        }
+        req.path_req = if kind == EnumKind::Primitive {
+            ListReq::any_of(
+                vec![
+                    path!(try_from_u8),
+                    path!(into_u8),
+                    path!(transparent),
+                    path!(arbitrary),
+                    path!(encode_only),
+                    path!(docs),
+                    path!(derive_tests),
+                    path!(flattenable),
+                ],
+                false,
+            )
+        } else {
+            ListReq::any_of(
+                vec![
+                    path!(transparent),
+                    path!(arbitrary),
+                    path!(encode_only),
+                    path!(docs),
+                    path!(derive_tests),
+                ],
+                false,
+            )
+        };
         req
     }
 }
@@ -116,11 +195,101 @@ impl TryFrom<ParametrizedAttr> for ContainerAttr {
 
         params.check(EnumAttr::attr_req(map, EnumKind::Primitive))?;
 
+        let bound = params
+            .arg_value::<LitStr>(ATTR_BOUND)
+            .ok()
+            .map(|lit| {
+                lit.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)
+                    .map(|list| list.into_iter().collect())
+            })
+            .transpose()?;
+        let remote = params
+            .arg_value::<LitStr>(ATTR_REMOTE)
+            .ok()
+            .map(|lit| lit.parse::<Path>())
+            .transpose()?;
+
+        let into = params
+            .arg_value::<LitStr>(ATTR_INTO)
+            .ok()
+            .map(|lit| lit.parse::<Type>())
+            .transpose()?;
+        let try_from = params
+            .arg_value::<LitStr>(ATTR_TRY_FROM)
+            .ok()
+            .map(|lit| lit.parse::<Type>())
+            .transpose()?;
+
+        let validate = params
+            .arg_value::<LitStr>(ATTR_VALIDATE)
+            .ok()
+            .map(|lit| lit.parse::<Path>())
+            .transpose()?;
+
+        let transparent = params.has_verbatim(ATTR_TRANSPARENT);
+        let docs = params.has_verbatim(ATTR_DOCS);
+        let lib = params.arg_value(ATTR_LIB).ok();
+        let rename = params.arg_value(ATTR_RENAME).ok();
+        if transparent && (lib.is_some() || rename.is_some() || remote.is_some() || docs) {
+            return Err(Error::new(
+                Span::call_site(),
+                "transparent types inherit library and type name from the wrapped type and can't \
+                 use `lib`, `rename`, `remote` or `docs` attributes",
+            ));
+        }
+        let converted = into.is_some() || try_from.is_some();
+        if converted
+            && (transparent || lib.is_some() || rename.is_some() || remote.is_some() || docs)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "types encoded with `into` and `try_from` conversions inherit library and type \
+                 name from the wire type and can't use `transparent`, `lib`, `rename`, `remote` \
+                 or `docs` attributes",
+            ));
+        }
+        if let (Some(into), Some(try_from)) = (&into, &try_from) {
+            if into.to_token_stream().to_string() != try_from.to_token_stream().to_string() {
+                return Err(Error::new(
+                    try_from.span(),
+                    "`into` and `try_from` attributes must specify the same wire type",
+                ));
+            }
+        }
+        let flattenable = params.has_verbatim(ATTR_FLATTENABLE);
+        if flattenable && (transparent || converted || remote.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`flattenable` attribute can't be used together with `transparent`, `into`, \
+                 `try_from` or `remote` attributes",
+            ));
+        }
+        if !transparent && !converted && lib.is_none() {
+            return Err(Error::new(
+                Span::call_site(),
+                "strict type must specify its library with `lib` attribute",
+            ));
+        }
+
         Ok(ContainerAttr {
             strict_crate: params.arg_value(ATTR_CRATE).unwrap_or_else(|_| path!(strict_encoding)),
-            lib: params.unwrap_arg_value(ATTR_LIB),
-            rename: params.arg_value(ATTR_RENAME).ok(),
+            lib,
+            rename,
             dumb: params.arg_value(ATTR_DUMB).ok(),
+            bound,
+            no_bound: vec![],
+            project: None,
+            remote,
+            transparent,
+            into,
+            try_from,
+            arbitrary: params.has_verbatim(ATTR_ARBITRARY),
+            encode_only: params.has_verbatim(ATTR_ENCODE_ONLY),
+            docs,
+            flattenable,
+            validate,
+            derive_tests: params.has_verbatim(ATTR_DERIVE_TESTS),
+            test_value: params.arg_value(ATTR_TEST_VALUE).ok(),
             encode_with: params
                 .arg_value(ATTR_ENCODE_WITH)
                 .or_else(|_| params.arg_value(ATTR_WITH))
@@ -182,24 +351,128 @@ impl EnumAttr {
 
 impl FieldAttr {
     pub fn with(mut params: ParametrizedAttr, kind: FieldKind) -> Result<Self> {
-        let mut map =
-            HashMap::from_iter(vec![(ATTR_DUMB, ArgValueReq::optional(ValueClass::Expr))]);
+        let mut map = HashMap::from_iter(vec![
+            (ATTR_DUMB, ArgValueReq::optional(ValueClass::Expr)),
+            (ATTR_WITH, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_ENCODE_WITH, ArgValueReq::optional(ValueClass::str())),
+            (ATTR_DECODE_WITH, ArgValueReq::optional(ValueClass::str())),
+        ]);
 
         if kind == FieldKind::Named {
             map.insert(ATTR_RENAME, ArgValueReq::optional(ValueClass::str()));
         }
 
+        let mut paths = vec![path!(skip), path!(optional)];
+        if kind == FieldKind::Named {
+            paths.push(path!(flatten));
+        }
         let mut attr_req = AttrReq::with(map);
-        attr_req.path_req = ListReq::maybe_one(path!(skip));
+        attr_req.path_req = ListReq::any_of(paths, false);
         params.check(attr_req)?;
 
+        let skip = params.has_verbatim(ATTR_SKIP);
+        let optional = params.has_verbatim(ATTR_OPTIONAL);
+        let flatten = params.has_verbatim(ATTR_FLATTEN);
+        if skip && optional {
+            return Err(Error::new(
+                Span::call_site(),
+                "skipped fields are not encoded and can't be optional",
+            ));
+        }
+
+        let codec = |name: &str| -> Result<Option<Path>> {
+            params.arg_value::<LitStr>(name).ok().map(|lit| lit.parse::<Path>()).transpose()
+        };
+        let with = codec(ATTR_WITH)?;
+        let encode_with = codec(ATTR_ENCODE_WITH)?;
+        let decode_with = codec(ATTR_DECODE_WITH)?;
+        if with.is_some() && (encode_with.is_some() || decode_with.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`with` attribute defines both field encoding and decoding and can't be combined \
+                 with `encode_with` or `decode_with` attributes",
+            ));
+        }
+        if flatten
+            && (skip
+                || optional
+                || with.is_some()
+                || encode_with.is_some()
+                || decode_with.is_some()
+                || params.arg_value::<LitStr>(ATTR_RENAME).is_ok())
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "flattened fields are encoded as the fields of the flattened structure and can't \
+                 be skipped, optional, renamed or use custom codecs",
+            ));
+        }
+
         Ok(FieldAttr {
             rename: params.arg_value(ATTR_RENAME).ok(),
             dumb: params.arg_value(ATTR_DUMB).ok(),
-            skip: params.has_verbatim(ATTR_SKIP),
+            encode_with: encode_with.or_else(|| with.clone()),
+            decode_with: decode_with.or(with),
+            skip,
+            optional,
+            flatten,
         })
     }
 
+    /// Detects whether the field is encoded or decoded with a custom codec.
+    pub fn has_codec(&self) -> bool { self.encode_with.is_some() || self.decode_with.is_some() }
+
+    /// Checks that the field doesn't use a custom codec in a context where it
+    /// is not supported.
+    pub fn forbid_with(&self, span: Span, context: &str) -> Result<()> {
+        if self.has_codec() {
+            return Err(Error::new(
+                span,
+                format!(
+                    "`with`, `encode_with` and `decode_with` attributes are not supported for \
+                     fields of {context}"
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses attributes of a field. Fields of [`PhantomData`] type are always
+    /// skipped, since they carry no data.
+    ///
+    /// [`PhantomData`]: std::marker::PhantomData
+    pub fn of(field: &Field, kind: FieldKind) -> Result<Self> {
+        let mut attr = FieldAttr::with(field.attr.clone(), kind)?;
+        if is_phantom(&field.ty) {
+            attr.skip = true;
+        }
+        Ok(attr)
+    }
+
+    /// Checks that the field is not flattened in a context where flattening is
+    /// not supported.
+    pub fn forbid_flatten(&self, span: Span, context: &str) -> Result<()> {
+        if self.flatten {
+            return Err(Error::new(
+                span,
+                format!("`flatten` attribute is not supported for fields of {context}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that the field is not marked as optional in a context where
+    /// optional fields are not supported.
+    pub fn forbid_optional(&self, span: Span, context: &str) -> Result<()> {
+        if self.optional {
+            return Err(Error::new(
+                span,
+                format!("`optional` attribute is not supported for fields of {context}"),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn field_name(&self, name: &Ident) -> LitStr {
         match self.rename {
             None => LitStr::new(&name.to_string().to_lower_camel_case(), name.span()),
@@ -218,13 +491,23 @@ impl TryFrom<ParametrizedAttr> for VariantAttr {
         ]);
 
         let mut req = AttrReq::with(map);
-        req.path_req = ListReq::maybe_one(path!(dumb));
+        req.path_req = ListReq::any_of(vec![path!(dumb), path!(other)], false);
         params.check(req)?;
 
+        let other = params.has_verbatim(ATTR_OTHER);
+        let tag = params.arg_value(ATTR_TAG).ok();
+        if other && tag.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "catch-all `other` variant stores its tag and can't have a `tag` attribute",
+            ));
+        }
+
         Ok(VariantAttr {
             rename: params.arg_value(ATTR_RENAME).ok(),
-            tag: params.arg_value(ATTR_TAG).ok(),
+            tag,
             dumb: params.has_verbatim("dumb"),
+            other,
         })
     }
 }
@@ -249,15 +532,681 @@ impl VariantAttr {
 pub struct StrictDerive {
     pub data: DataType,
     pub conf: ContainerAttr,
+    pub docs: DocComments,
+    /// Enum variants with explicit discriminants, which are used as their tags
+    /// by `tags = repr`.
+    pub discriminants: Vec<Ident>,
+}
+
+/// Doc comments of the type and of each of its fields or variants, in the order
+/// of their declaration, collected for the types with `docs` attribute.
+#[derive(Default)]
+pub struct DocComments {
+    pub doc: Option<String>,
+    pub members: Vec<Option<String>>,
+}
+
+impl DocComments {
+    fn with(input: &DeriveInput) -> Self {
+        let members = match &input.data {
+            Data::Struct(data) => {
+                data.fields.iter().map(|field| doc_comment(&field.attrs)).collect()
+            }
+            Data::Enum(data) => data.variants.iter().map(|var| doc_comment(&var.attrs)).collect(),
+            Data::Union(data) => {
+                data.fields.named.iter().map(|field| doc_comment(&field.attrs)).collect()
+            }
+        };
+        DocComments {
+            doc: doc_comment(&input.attrs),
+            members,
+        }
+    }
+}
+
+/// Joins the lines of `///` and `/** */` doc comments with newlines, trimming
+/// their leading whitespace.
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(lit), ..
+            })) => Some(lit.value()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let doc = lines.iter().flat_map(|lines| lines.lines()).map(str::trim_start).collect::<Vec<_>>();
+    let doc = doc.join("\n");
+    let doc = doc.trim();
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc.to_owned())
+    }
 }
 
 impl TryFrom<DeriveInput> for StrictDerive {
     type Error = Error;
 
-    fn try_from(input: DeriveInput) -> Result<Self> {
+    fn try_from(mut input: DeriveInput) -> Result<Self> {
+        let no_bound =
+            extract_list(&mut input.attrs, ATTR_NO_BOUND, "type parameters, like `no_bound(T)`")?
+                .unwrap_or_default();
+        let project = extract_list(&mut input.attrs, ATTR_PROJECT, "fields, like `project(id)`")?;
         let params = ParametrizedAttr::with(ATTR, &input.attrs)?;
-        let conf = ContainerAttr::try_from(params)?;
+        let mut conf = ContainerAttr::try_from(params)?;
+        for param in &no_bound {
+            if !input.generics.type_params().any(|p| &p.ident == param) {
+                return Err(Error::new(
+                    param.span(),
+                    format!("`{param}` in `no_bound` is not a type parameter of this type"),
+                ));
+            }
+        }
+        conf.no_bound = no_bound;
+        conf.project = project;
+        let docs = if conf.docs { DocComments::with(&input) } else { DocComments::default() };
+        let discriminants = match &input.data {
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .filter(|variant| variant.discriminant.is_some())
+                .map(|variant| variant.ident.clone())
+                .collect(),
+            _ => vec![],
+        };
         let data = DataType::with(input, ident!(strict_type))?;
-        Ok(Self { data, conf })
+        Ok(Self {
+            data,
+            conf,
+            docs,
+            discriminants,
+        })
     }
 }
+
+/// Removes `name(a, ...)` arguments, like `no_bound(T)` and `project(id)`,
+/// from the container `strict_type` attributes, returning the listed
+/// identifiers, or `None` if there is no such argument.
+///
+/// The generic attribute parser doesn't support nested argument lists, so such
+/// arguments have to be taken out before the rest of the attribute is parsed.
+fn extract_list(attrs: &mut [Attribute], name: &str, items: &str) -> Result<Option<Vec<Ident>>> {
+    let mut found: Option<Vec<Ident>> = None;
+    for attr in attrs.iter_mut().filter(|attr| attr.path.is_ident(ATTR)) {
+        let Some(TokenTree::Group(group)) = attr.tokens.clone().into_iter().next() else {
+            continue;
+        };
+        if group.delimiter() != Delimiter::Parenthesis {
+            continue;
+        }
+
+        let mut args = vec![vec![]];
+        for tt in group.stream() {
+            match tt {
+                TokenTree::Punct(ref punct) if punct.as_char() == ',' => args.push(vec![]),
+                tt => args.last_mut().expect("always non-empty").push(tt),
+            }
+        }
+
+        let mut retained = TokenStream2::new();
+        for arg in args.into_iter().filter(|arg| !arg.is_empty()) {
+            match arg.as_slice() {
+                [TokenTree::Ident(ident), TokenTree::Group(list)]
+                    if ident == name && list.delimiter() == Delimiter::Parenthesis =>
+                {
+                    let list = syn::parse::Parser::parse2(
+                        Punctuated::<Ident, Token![,]>::parse_terminated,
+                        list.stream(),
+                    )?;
+                    found.get_or_insert_with(Vec::new).extend(list);
+                }
+                [TokenTree::Ident(ident), ..] if ident == name => {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!("`{name}` must be given a list of {items}"),
+                    ));
+                }
+                _ => {
+                    if !retained.is_empty() {
+                        retained.extend(quote! { , });
+                    }
+                    retained.extend(arg);
+                }
+            }
+        }
+        attr.tokens = quote! { (#retained) };
+    }
+    Ok(found)
+}
+
+impl StrictDerive {
+    /// Returns a copy of the data type with its where-clause extended with the
+    /// predicates required by a derived trait implementation.
+    ///
+    /// If the container provides `bound = "..."` attribute, its predicates are
+    /// used instead of the inferred ones. Otherwise the predicates are
+    /// inferred by bounding each type parameter mentioned in `types` with
+    /// `trait_name`, skipping parameters listed in `no_bound(...)`. With
+    /// `self_dumb` set the type itself is additionally required to be
+    /// `StrictDumb`, as needed by product and union type traits.
+    pub fn bounded<'a>(
+        &self,
+        trait_name: Option<Ident>,
+        types: impl IntoIterator<Item = &'a Type>,
+        self_dumb: bool,
+    ) -> DataType {
+        let crate_name = &self.conf.strict_crate;
+        let mut data = self.data.clone();
+
+        let mut predicates: Vec<WherePredicate> = match (&self.conf.bound, trait_name) {
+            (Some(bound), _) => bound.clone(),
+            (None, None) => vec![],
+            (None, Some(trait_name)) => {
+                let types = types.into_iter().map(|ty| ty.to_token_stream()).collect::<Vec<_>>();
+                self.data
+                    .generics
+                    .type_params()
+                    .map(|param| &param.ident)
+                    .filter(|param| !self.conf.no_bound.contains(param))
+                    .filter(|param| types.iter().any(|ty| mentions(ty.clone(), param)))
+                    .map(|param| parse_quote! { #param: #crate_name::#trait_name })
+                    .collect()
+            }
+        };
+
+        if self_dumb
+            && self.conf.bound.is_none()
+            && self.data.generics.type_params().next().is_some()
+        {
+            let type_name = &self.data.name;
+            let (_, ty_generics, _) = self.data.generics.split_for_impl();
+            predicates.push(parse_quote! { #type_name #ty_generics: #crate_name::StrictDumb });
+        }
+
+        if !predicates.is_empty() {
+            data.generics.make_where_clause().predicates.extend(predicates);
+        }
+        data
+    }
+
+    /// Collects types of all fields which are encoded using their own strict
+    /// encoding implementations, i.e. the fields which are neither skipped nor
+    /// use a custom encoding codec.
+    pub fn encoded_types(&self) -> Result<Vec<&Type>> {
+        self.codec_types(|attr| attr.encode_with.is_none())
+    }
+
+    /// Collects types of all fields which are decoded using their own strict
+    /// encoding implementations, i.e. the fields which are neither skipped nor
+    /// use a custom decoding codec.
+    pub fn decoded_types(&self) -> Result<Vec<&Type>> {
+        self.codec_types(|attr| attr.decode_with.is_none())
+    }
+
+    fn codec_types(&self, native: fn(&FieldAttr) -> bool) -> Result<Vec<&Type>> {
+        let mut types = vec![];
+        match &self.data.inner {
+            DataInner::Struct(fields) => collect_native(fields, native, &mut types)?,
+            DataInner::Enum(variants) => {
+                for variant in variants {
+                    collect_native(&variant.fields, native, &mut types)?;
+                }
+            }
+            DataInner::Union(_) | DataInner::Uninhabited => {}
+        }
+        Ok(types)
+    }
+
+    /// Returns the catch-all variant marked with `other` attribute, which
+    /// stores the tags of the variants unknown to the enum. Checks that the
+    /// variant has a single unnamed field and all other variants are units.
+    pub fn catch_all(&self) -> Result<Option<&Variant>> {
+        let DataInner::Enum(variants) = &self.data.inner else {
+            return Ok(None);
+        };
+        let mut catch_all = None;
+        for variant in variants {
+            if !VariantAttr::try_from(variant.attr.clone())?.other {
+                continue;
+            }
+            if catch_all.is_some() {
+                return Err(Error::new(
+                    variant.name.span(),
+                    "enum can't have more than a single catch-all `other` variant",
+                ));
+            }
+            if !matches!(variant.fields, Fields::Unnamed(ref fields) if fields.len() == 1) {
+                return Err(Error::new(
+                    variant.name.span(),
+                    "catch-all `other` variant must have a single unnamed field storing the tag",
+                ));
+            }
+            catch_all = Some(variant);
+        }
+        let Some(catch_all) = catch_all else {
+            return Ok(None);
+        };
+        if let Some(variant) = variants.iter().find(|variant| {
+            variant.name != catch_all.name && !matches!(variant.fields, Fields::Unit)
+        }) {
+            return Err(Error::new(
+                variant.name.span(),
+                "enum with a catch-all `other` variant can have only unit variants besides it",
+            ));
+        }
+        if self.conf.remote.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "catch-all `other` variant is not supported for remote type mirrors",
+            ));
+        }
+        Ok(Some(catch_all))
+    }
+
+    /// Detects whether the variant stores a value of the type itself, directly
+    /// or behind a pointer or a collection, such that its dumb value can't be
+    /// constructed without recursion.
+    pub fn is_recursive(&self, variant: &Variant) -> Result<bool> {
+        let mut types = vec![];
+        collect_stored(&variant.fields, &mut types)?;
+        let name = &self.data.name;
+        let this = Ident::new("Self", Span::call_site());
+        Ok(types.into_iter().any(|ty| {
+            let ty = ty.to_token_stream();
+            mentions(ty.clone(), name) || mentions(ty, &this)
+        }))
+    }
+
+    /// Finds the first encoded field which type borrows data for one of the
+    /// lifetimes of the type.
+    fn borrowing_field(&self) -> Result<Option<&Type>> {
+        let lifetimes =
+            self.data.generics.lifetimes().map(|def| &def.lifetime.ident).collect::<Vec<_>>();
+        if lifetimes.is_empty() {
+            return Ok(None);
+        }
+        let mut types = vec![];
+        match &self.data.inner {
+            DataInner::Struct(fields) => collect_stored(fields, &mut types)?,
+            DataInner::Enum(variants) => {
+                for variant in variants {
+                    collect_stored(&variant.fields, &mut types)?;
+                }
+            }
+            DataInner::Union(_) | DataInner::Uninhabited => {}
+        }
+        Ok(types.into_iter().find(|ty| {
+            lifetimes.iter().any(|lifetime| mentions_lifetime(ty.to_token_stream(), lifetime))
+        }))
+    }
+
+    /// Checks that the type can be decoded, which is required to derive
+    /// `trait_name`. Types borrowing data in their fields and types marked with
+    /// `encode_only` attribute can only be encoded.
+    pub fn forbid_encode_only(&self, trait_name: &str) -> Result<()> {
+        let type_name = &self.data.name;
+        if let Some(ty) = self.borrowing_field()? {
+            return Err(Error::new(
+                ty.span(),
+                format!(
+                    "`{trait_name}` can't be derived for `{type_name}` since the type of this \
+                     field borrows data; types borrowing data are encode-only"
+                ),
+            ));
+        }
+        if self.conf.encode_only {
+            return Err(Error::new(
+                type_name.span(),
+                format!(
+                    "`{trait_name}` can't be derived for `{type_name}` marked as `encode_only`"
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Generates inherent implementation block for the type, used instead of a
+    /// trait implementation when the type is a mirror of a remote type.
+    pub fn derive_inherent<D: DeriveInner>(
+        &self,
+        data: &DataType,
+        inner: &D,
+    ) -> Result<TokenStream2> {
+        let (impl_generics, ty_generics, where_clause) = data.generics.split_for_impl();
+        let type_name = &data.name;
+
+        let inner = match &data.inner {
+            DataInner::Struct(Fields::Unit) => inner.derive_unit_inner()?,
+            DataInner::Struct(Fields::Unnamed(fields)) => inner.derive_tuple_inner(fields)?,
+            DataInner::Struct(Fields::Named(fields)) => inner.derive_struct_inner(fields)?,
+            DataInner::Enum(variants) => inner.derive_enum_inner(variants)?,
+            DataInner::Union(_) | DataInner::Uninhabited => {
+                return Err(Error::new(
+                    type_name.span(),
+                    "remote strict encoding is supported only for structs and enums",
+                ));
+            }
+        };
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #type_name #ty_generics #where_clause {
+                #inner
+            }
+        })
+    }
+
+    /// Generates expression moving `value` of the type `src` into the type
+    /// `dst` field by field. Both types must have the same fields (and
+    /// variants, for enums), which is the case for a remote type and its
+    /// mirror.
+    pub fn convert_expr(
+        &self,
+        value: TokenStream2,
+        src: TokenStream2,
+        dst: TokenStream2,
+    ) -> TokenStream2 {
+        fn arm(fields: &Fields, src: TokenStream2, dst: TokenStream2) -> TokenStream2 {
+            match fields {
+                Fields::Unit => quote! { #src => #dst },
+                Fields::Named(fields) => {
+                    let name = fields.iter().map(|named| &named.name).collect::<Vec<_>>();
+                    let dst_name = name.iter().map(|name| quote_spanned! { name.span() => #name });
+                    quote! { #src { #( #name ),* } => #dst { #( #dst_name ),* } }
+                }
+                Fields::Unnamed(fields) => {
+                    let var = (0..fields.len())
+                        .map(|index| Ident::new(&format!("_{index}"), Span::call_site()))
+                        .collect::<Vec<_>>();
+                    quote! { #src( #( #var ),* ) => #dst( #( #var ),* ) }
+                }
+            }
+        }
+
+        let arms = match &self.data.inner {
+            DataInner::Struct(fields) => vec![arm(fields, src, dst)],
+            DataInner::Enum(variants) => variants
+                .iter()
+                .map(|var| {
+                    let name = &var.name;
+                    arm(&var.fields, quote! { #src::#name }, quote! { #dst::#name })
+                })
+                .collect(),
+            DataInner::Union(_) | DataInner::Uninhabited => vec![],
+        };
+
+        quote! {
+            match #value {
+                #( #arms, )*
+            }
+        }
+    }
+
+    /// Generates type wrapping a reference to a field value which encodes it
+    /// with a custom codec provided in the field `with` attribute.
+    pub fn encode_adapter(&self, name: &Ident, ty: &Type, with: &Path) -> Result<TokenStream2> {
+        self.check_adapter(ty)?;
+        let crate_name = &self.conf.strict_crate;
+        let lib_name = &self.conf.lib;
+        Ok(quote! {
+            #[allow(non_camel_case_types)]
+            struct #name<'a>(&'a #ty);
+            impl #crate_name::StrictType for #name<'_> {
+                const STRICT_LIB_NAME: &'static str = #lib_name;
+                fn strict_name() -> Option<#crate_name::TypeName> { None }
+            }
+            impl #crate_name::StrictEncode for #name<'_> {
+                fn strict_encode<W: #crate_name::TypedWrite>(&self, writer: W) -> #crate_name::io::Result<W> {
+                    #with::strict_encode(self.0, writer)
+                }
+            }
+        })
+    }
+
+    /// Generates type wrapping a field value which decodes it with a custom
+    /// codec provided in the field `with` attribute.
+    pub fn decode_adapter(&self, name: &Ident, ty: &Type, with: &Path) -> Result<TokenStream2> {
+        self.check_adapter(ty)?;
+        let crate_name = &self.conf.strict_crate;
+        let lib_name = &self.conf.lib;
+        Ok(quote! {
+            #[allow(non_camel_case_types)]
+            struct #name(#ty);
+            impl #crate_name::StrictType for #name {
+                const STRICT_LIB_NAME: &'static str = #lib_name;
+                fn strict_name() -> Option<#crate_name::TypeName> { None }
+            }
+            impl #crate_name::StrictDecode for #name {
+                fn strict_decode(
+                    reader: &mut impl #crate_name::TypedRead,
+                ) -> Result<Self, #crate_name::DecodeError> {
+                    #with::strict_decode(reader).map(Self)
+                }
+            }
+        })
+    }
+
+    /// For transparent types returns the wrapped field together with the
+    /// list of the skipped fields, which are initialized with their default
+    /// values.
+    pub fn transparent_field(&self) -> Result<(Member, &Type, Vec<Member>)> {
+        let fields: Vec<(Member, &Field)> = match &self.data.inner {
+            DataInner::Struct(Fields::Named(fields)) => fields
+                .iter()
+                .map(|named| (Member::Named(named.name.clone()), &named.field))
+                .collect(),
+            DataInner::Struct(Fields::Unnamed(fields)) => fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| (Member::Unnamed(Index::from(index)), field))
+                .collect(),
+            _ => {
+                return Err(Error::new(
+                    self.data.name.span(),
+                    "only structures with fields can be transparent strict types",
+                ));
+            }
+        };
+
+        let mut wrapped = None;
+        let mut skipped = vec![];
+        for (member, field) in fields {
+            let kind = match member {
+                Member::Named(_) => FieldKind::Named,
+                Member::Unnamed(_) => FieldKind::Unnamed,
+            };
+            let attr = FieldAttr::of(field, kind)?;
+            attr.forbid_with(field.ty.span(), "transparent types")?;
+            attr.forbid_optional(field.ty.span(), "transparent types")?;
+            attr.forbid_flatten(field.ty.span(), "transparent types")?;
+            if attr.skip {
+                skipped.push(member);
+            } else if wrapped.is_some() {
+                return Err(Error::new(
+                    field.ty.span(),
+                    "transparent strict type must have exactly one non-skipped field",
+                ));
+            } else {
+                wrapped = Some((member, &field.ty));
+            }
+        }
+
+        let (member, ty) = wrapped.ok_or_else(|| {
+            Error::new(
+                self.data.name.span(),
+                "transparent strict type must have exactly one non-skipped field",
+            )
+        })?;
+        Ok((member, ty, skipped))
+    }
+
+    /// Checks the type of a flattened field, which fields are listed among the
+    /// fields of the structure at compile time; this is impossible for types
+    /// depending on generic parameters.
+    pub fn check_flatten(&self, ty: &Type) -> Result<()> {
+        if let Some(param) = self
+            .data
+            .generics
+            .type_params()
+            .find(|param| mentions(ty.to_token_stream(), &param.ident))
+        {
+            return Err(Error::new_spanned(
+                ty,
+                format!("flattened field type can't depend on generic parameter `{}`", param.ident),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that a type with `flattenable` attribute is a structure with
+    /// named fields, which are the only types which can be flattened.
+    pub fn check_flattenable(&self) -> Result<()> {
+        if self.conf.flattenable && !matches!(self.data.inner, DataInner::Struct(Fields::Named(_)))
+        {
+            return Err(Error::new(
+                self.data.name.span(),
+                "`flattenable` attribute can be used only with structures with named fields",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Detects whether the structure has flattened fields.
+    pub fn has_flatten(&self) -> Result<bool> {
+        let DataInner::Struct(Fields::Named(fields)) = &self.data.inner else {
+            return Ok(false);
+        };
+        for named in fields {
+            if FieldAttr::of(&named.field, FieldKind::Named)?.flatten {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn check_adapter(&self, ty: &Type) -> Result<()> {
+        if let Some(param) = self
+            .data
+            .generics
+            .type_params()
+            .find(|param| mentions(ty.to_token_stream(), &param.ident))
+        {
+            return Err(Error::new_spanned(
+                ty,
+                format!(
+                    "custom codecs can't be used on a field which type depends on generic \
+                     parameter `{}`",
+                    param.ident
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn collect_native<'a>(
+    fields: &'a Fields,
+    native: fn(&FieldAttr) -> bool,
+    types: &mut Vec<&'a Type>,
+) -> Result<()> {
+    match fields {
+        Fields::Unit => {}
+        Fields::Named(fields) => {
+            for named in fields {
+                let attr = FieldAttr::of(&named.field, FieldKind::Named)?;
+                if !attr.skip && native(&attr) {
+                    types.push(&named.field.ty);
+                }
+            }
+        }
+        Fields::Unnamed(fields) => {
+            for field in fields {
+                let attr = FieldAttr::of(field, FieldKind::Unnamed)?;
+                if !attr.skip && native(&attr) {
+                    types.push(&field.ty);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_stored<'a>(fields: &'a Fields, types: &mut Vec<&'a Type>) -> Result<()> {
+    match fields {
+        Fields::Unit => {}
+        Fields::Named(fields) => {
+            for named in fields {
+                if !FieldAttr::of(&named.field, FieldKind::Named)?.skip {
+                    types.push(&named.field.ty);
+                }
+            }
+        }
+        Fields::Unnamed(fields) => {
+            for field in fields {
+                if !FieldAttr::of(field, FieldKind::Unnamed)?.skip {
+                    types.push(&field.ty);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Strips `Box`, `Rc` and `Arc` smart pointers from the field type. Pointers
+/// are encoded exactly as the value they point to, and the type definitions
+/// use the pointee, which doesn't require the pointer to be `StrictDumb` (i.e.
+/// the pointee to be `Default`) for recursive types.
+pub fn pointee(ty: &Type) -> &Type {
+    let Type::Path(path) = ty else {
+        return ty;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return ty;
+    };
+    if path.qself.is_some() || !["Box", "Rc", "Arc"].iter().any(|ptr| segment.ident == ptr) {
+        return ty;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => pointee(inner),
+            _ => ty,
+        },
+        _ => ty,
+    }
+}
+
+fn is_phantom(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "PhantomData")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ref i) => i == ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+fn mentions_lifetime(tokens: TokenStream2, lifetime: &Ident) -> bool {
+    let mut after_quote = false;
+    tokens.into_iter().any(|tt| {
+        let found = match tt {
+            TokenTree::Ident(ref i) => after_quote && i == lifetime,
+            TokenTree::Group(ref group) => mentions_lifetime(group.stream(), lifetime),
+            _ => false,
+        };
+        after_quote = matches!(tt, TokenTree::Punct(ref p) if p.as_char() == '\'');
+        found
+    })
+}
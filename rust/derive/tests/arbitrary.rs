@@ -0,0 +1,71 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Caused by an imperfection of rust compiler in parsing proc macro args
+#![allow(unused_braces)]
+
+#[macro_use]
+extern crate amplify;
+
+mod common;
+
+use amplify::confinement::{TinyString, TinyVec};
+use strict_encoding::arbitrary::{Arbitrary, Unstructured};
+use strict_encoding::{StrictArbitrary, StrictDecode, StrictDumb, StrictEncode, StrictType};
+
+const TEST_LIB: &str = "TestLib";
+
+#[test]
+fn transparent() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(transparent, arbitrary)]
+    struct Name(TinyString);
+
+    let data = [b'a', 0, 0, 0, b'b', 0, 0, 0, b'c', 0, 0, 0, 0x3];
+    let name = Name::arbitrary(&mut Unstructured::new(&data)).unwrap();
+    let string = TinyString::strict_arbitrary(&mut Unstructured::new(&data)).unwrap();
+    assert_eq!(name, Name(string));
+
+    Ok(())
+}
+
+#[test]
+fn generic() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, arbitrary)]
+    struct Pair<A: StrictDumb + StrictEncode + StrictDecode, B>
+    where B: StrictDumb + StrictEncode + StrictDecode
+    {
+        first: A,
+        second: TinyVec<B>,
+        #[strict_type(skip)]
+        cache: Option<u64>,
+    }
+
+    let data = [1, 2, 3, 4, 5, 6, 7, 8];
+    let pair = Pair::<u8, u16>::strict_arbitrary(&mut Unstructured::new(&data)).unwrap();
+    assert_eq!(pair.first, 1);
+    assert_eq!(pair.cache, None);
+
+    Ok(())
+}
@@ -27,13 +27,39 @@ extern crate strict_encoding_derive;
 mod common;
 
 use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
+use amplify::confinement::{Confined, TinyString};
 use strict_encoding::{
-    StrictDecode, StrictDumb, StrictEncode, StrictSerialize, StrictSum, VariantError,
+    fname, tn, DecodeError, KnownVariants, SliceReader, StrictDecode, StrictDumb, StrictEncode,
+    StrictEnum, StrictReader, StrictSerialize, StrictStruct, StrictSum, StrictTuple,
+    StrictTypeInfo, StrictWriter, TypeClassOwned, VariantError, VariantName,
 };
 
 const TEST_LIB: &str = "TestLib";
 
+/// Field type satisfying the bounds of the generic types in the tests below.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB)]
+struct Label(u8);
+
+impl From<&str> for Label {
+    fn from(s: &str) -> Self { Label(s.len() as u8) }
+}
+
+impl From<String> for Label {
+    fn from(s: String) -> Self { Label::from(s.as_str()) }
+}
+
+impl Default for &'static Label {
+    fn default() -> Self {
+        static LABEL: Label = Label(0);
+        &LABEL
+    }
+}
+
 #[test]
 fn wrapper_base() -> common::Result {
     #[derive(Clone, PartialEq, Eq, Debug)]
@@ -73,6 +99,10 @@ fn tuple_generics() -> common::Result {
         <A as TryFrom<u8>>::Error: From<Infallible>,
         B: StrictDumb + StrictEncode + StrictDecode;
 
+    strict_encoding_test::test_encoding_roundtrip::<_, 3>(&Pair(1u8, 0x0302u16), vec![1, 2, 3])?;
+    let value = WhereConstraint(7u8, Label::from(s!("label")));
+    strict_encoding_test::test_encoding_roundtrip::<_, 2>(&value, vec![7, 5])?;
+
     Ok(())
 }
 
@@ -98,6 +128,361 @@ fn struct_generics() -> common::Result {
         value: &'a V,
     }
 
+    let field = Field {
+        tag: 1,
+        value: 0x0302u16,
+    };
+    strict_encoding_test::test_encoding_roundtrip::<_, 3>(&field, vec![1, 2, 3])?;
+    static LABEL: Label = Label(5);
+    let complex = ComplexField {
+        tag: 1,
+        value: &LABEL,
+    };
+    let writer = complex.strict_encode(StrictWriter::in_memory::<2>()).unwrap();
+    assert_eq!(writer.unbox().unconfine(), vec![1, 5]);
+
+    Ok(())
+}
+
+#[test]
+fn generics_unused_in_fields() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct Marker;
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Tagged<T, M> {
+        value: T,
+        #[strict_type(skip)]
+        marker: PhantomData<M>,
+    }
+
+    let tagged = Tagged::<u16, Marker> {
+        value: 0xCAFE,
+        marker: PhantomData,
+    };
+    strict_encoding_test::test_encoding_roundtrip::<_, 2>(&tagged, vec![0xFE, 0xCA])?;
+
+    Ok(())
+}
+
+#[test]
+fn generics_no_bound() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct Marker;
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Handle<M>(u8, #[strict_type(skip)] PhantomData<M>);
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, no_bound(M))]
+    struct Wrapper<T, M> {
+        value: T,
+        handle: Handle<M>,
+    }
+
+    let wrapper = Wrapper::<u8, Marker> {
+        value: 1,
+        handle: Handle(2, PhantomData),
+    };
+    strict_encoding_test::test_encoding_roundtrip::<_, 2>(&wrapper, vec![1, 2])?;
+
+    Ok(())
+}
+
+#[test]
+fn phantom_fields() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct Marker;
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Tagged<M> {
+        value: u8,
+        marker: PhantomData<M>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct TaggedTuple<M>(PhantomData<fn() -> M>, u8);
+
+    impl StrictSerialize for Tagged<Marker> {}
+
+    assert_eq!(Tagged::<Marker>::ALL_FIELDS, &["value"]);
+    assert_eq!(TaggedTuple::<Marker>::FIELD_COUNT, 1);
+
+    let tagged = Tagged::<Marker> {
+        value: 0xAD,
+        marker: PhantomData,
+    };
+    assert_eq!(tagged.to_strict_serialized::<1>().unwrap().as_slice(), &[0xAD]);
+    strict_encoding_test::test_encoding_roundtrip::<_, 1>(&tagged, vec![0xAD])?;
+    let tuple = TaggedTuple::<Marker>(PhantomData, 0xAD);
+    strict_encoding_test::test_encoding_roundtrip::<_, 1>(&tuple, vec![0xAD])?;
+    strict_encoding_test::test_encoding_roundtrip::<_, 1>(&(0xADu8, PhantomData::<u16>), vec![
+        0xAD,
+    ])?;
+
+    Ok(())
+}
+
+#[test]
+fn generics_custom_bound() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, bound = "T: StrictDumb + StrictEncode + StrictDecode + Copy")]
+    struct Wrapper<T>(T);
+
+    impl<T: Copy> Wrapper<T> {
+        fn get(&self) -> T { self.0 }
+    }
+
+    let wrapper = Wrapper(0xABu8);
+    assert_eq!(wrapper.get(), 0xAB);
+    strict_encoding_test::test_encoding_roundtrip::<_, 1>(&wrapper, vec![0xAB])?;
+
+    Ok(())
+}
+
+#[test]
+fn const_generics() -> common::Result {
+    use strict_encoding::StrictType as _;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Header(u8);
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Packet<const LEN: usize> {
+        header: Header,
+        payload: [u8; LEN],
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, bound = "T: StrictDumb + StrictEncode + StrictDecode")]
+    struct Frame<T, const LEN: usize, const SHIFT: i8>([T; LEN], u16);
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, rename = "Digest")]
+    struct Hash<const LEN: usize>([u8; LEN]);
+
+    assert_eq!(Packet::<4>::strict_name(), Some(tn!("Packet_4")));
+    assert_eq!(Packet::<32>::strict_name(), Some(tn!("Packet_32")));
+    assert_eq!(Frame::<u16, 12, 3>::strict_name(), Some(tn!("Frameu16_12_3")));
+    assert_eq!(Frame::<u16, 1, 23>::strict_name(), Some(tn!("Frameu16_1_23")));
+    assert_eq!(Frame::<u8, 1, -1>::strict_name(), Some(tn!("Frameu8_1_Neg1")));
+    // names which are not valid type names leave the type unnamed
+    assert_eq!(Frame::<[u8; 2], 1, 0>::strict_name(), None);
+    assert_eq!(Hash::<32>::strict_name(), Some(tn!("Digest_32")));
+
+    assert_eq!(Packet::<33>::strict_dumb().payload, [0u8; 33]);
+    assert_eq!(Frame::<u16, 40, 0>::strict_dumb(), Frame([0u16; 40], 0));
+
+    strict_encoding_test::test_encoding_roundtrip::<_, 5>(
+        &Packet {
+            header: Header(1),
+            payload: [2u8; 4],
+        },
+        vec![1, 2, 2, 2, 2],
+    )?;
+    let mut data = vec![7];
+    data.extend([0xA5; 32]);
+    strict_encoding_test::test_encoding_roundtrip::<_, 33>(
+        &Packet {
+            header: Header(7),
+            payload: [0xA5u8; 32],
+        },
+        data,
+    )?;
+    strict_encoding_test::test_encoding_roundtrip::<_, 4>(
+        &Frame::<u8, 2, 0>([1, 2], 0x0403),
+        vec![1, 2, 3, 4],
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn transparent() -> common::Result {
+    fn serialize(value: &impl StrictEncode) -> Vec<u8> {
+        value.strict_encode(StrictWriter::in_memory::<256>()).unwrap().unbox().unconfine()
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(transparent)]
+    struct Amount(u64);
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(transparent)]
+    struct Name<M> {
+        inner: Confined<String, 1, 32>,
+        marker: PhantomData<M>,
+    }
+
+    use strict_encoding::StrictType as _;
+    assert_eq!(Amount::STRICT_LIB_NAME, u64::STRICT_LIB_NAME);
+    assert_eq!(Amount::strict_name(), u64::strict_name());
+
+    let amount = Amount(0x0102030405060708);
+    let serialized = serialize(&amount);
+    assert_eq!(serialized, serialize(&amount.0));
+    strict_encoding_test::test_encoding_roundtrip::<_, 8>(&amount, serialized)?;
+
+    let name = Name::<u8> {
+        inner: Confined::try_from(s!("satoshi")).unwrap(),
+        marker: PhantomData,
+    };
+    let serialized = serialize(&name);
+    assert_eq!(serialized, serialize(&name.inner));
+    assert_eq!(serialized, b"\x07satoshi");
+    strict_encoding_test::test_encoding_roundtrip::<_, 8>(&name, serialized)?;
+
+    Ok(())
+}
+
+#[test]
+fn converted() -> common::Result {
+    /// Range with its cached length, encoded as a tuple of its bounds.
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(into = "(u16, u16)", try_from = "(u16, u16)")]
+    struct Span {
+        start: u16,
+        end: u16,
+        len: u16,
+    }
+
+    impl From<Span> for (u16, u16) {
+        fn from(span: Span) -> Self { (span.start, span.end) }
+    }
+
+    impl TryFrom<(u16, u16)> for Span {
+        type Error = String;
+
+        fn try_from((start, end): (u16, u16)) -> Result<Self, Self::Error> {
+            if start > end {
+                return Err(format!("span start {start} is after its end {end}"));
+            }
+            Ok(Span {
+                start,
+                end,
+                len: end - start,
+            })
+        }
+    }
+
+    use strict_encoding::StrictType as _;
+    assert_eq!(Span::STRICT_LIB_NAME, <(u16, u16)>::STRICT_LIB_NAME);
+    assert_eq!(Span::strict_name(), <(u16, u16)>::strict_name());
+
+    let span = Span::try_from((0x0102, 0x0304)).unwrap();
+    assert_eq!(span.len, 0x0202);
+    strict_encoding_test::test_encoding_roundtrip::<_, 4>(&span, vec![0x02, 0x01, 0x04, 0x03])?;
+
+    let mut reader = StrictReader::in_memory::<4>([0x04, 0x03, 0x02, 0x01]);
+    let err = Span::strict_decode(&mut reader).unwrap_err();
+    assert_eq!(err, DecodeError::Conversion {
+        from: s!("u16u16"),
+        into: s!("Span"),
+        message: s!("span start 772 is after its end 258"),
+    });
+    assert_eq!(
+        err.to_string(),
+        "decoded value of type `u16u16` can't be converted into `Span`: span start 772 is after \
+         its end 258"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validated() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, validate = "Range::check", flattenable)]
+    struct Range {
+        start: u16,
+        end: u16,
+        #[strict_type(skip)]
+        checked: bool,
+    }
+
+    impl Range {
+        fn check(&self) -> Result<(), String> {
+            // skipped fields are already defaulted during the validation
+            assert!(!self.checked);
+            if self.end < self.start {
+                return Err(format!("range end {} is before its start {}", self.end, self.start));
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Window {
+        id: u8,
+        #[strict_type(flatten)]
+        range: Range,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, validate = "Even::check")]
+    struct Even(u8);
+
+    impl Even {
+        fn check(&self) -> Result<(), &'static str> {
+            if self.0 % 2 != 0 {
+                return Err("number is odd");
+            }
+            Ok(())
+        }
+    }
+
+    let range = Range {
+        start: 0x0102,
+        end: 0x0304,
+        checked: false,
+    };
+    strict_encoding_test::test_encoding_roundtrip::<_, 4>(&range, vec![0x02, 0x01, 0x04, 0x03])?;
+    let window = Window { id: 7, range };
+    strict_encoding_test::test_encoding_roundtrip::<_, 5>(&window, vec![7, 2, 1, 4, 3])?;
+    strict_encoding_test::test_encoding_roundtrip::<_, 1>(&Even(4), vec![4])?;
+
+    let invalid = DecodeError::InvalidValue {
+        type_name: s!("Range"),
+        message: s!("range end 258 is before its start 772"),
+    };
+    let mut reader = StrictReader::in_memory::<4>([0x04, 0x03, 0x02, 0x01]);
+    assert_eq!(Range::strict_decode(&mut reader).unwrap_err(), invalid);
+    let mut reader = StrictReader::in_memory::<5>([7, 0x04, 0x03, 0x02, 0x01]);
+    assert_eq!(Window::strict_decode(&mut reader).unwrap_err(), invalid);
+    assert_eq!(
+        invalid.to_string(),
+        "decoded value of type `Range` is invalid: range end 258 is before its start 772"
+    );
+
+    let mut reader = StrictReader::in_memory::<1>([3]);
+    assert_eq!(Even::strict_decode(&mut reader).unwrap_err(), DecodeError::InvalidValue {
+        type_name: s!("Even"),
+        message: s!("number is odd"),
+    });
+
     Ok(())
 }
 
@@ -142,6 +527,40 @@ fn enum_repr() -> common::Result {
     Ok(())
 }
 
+#[test]
+fn enum_tag_conversions() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
+    #[repr(u8)]
+    enum Level {
+        #[strict_type(dumb)]
+        Low = 0,
+        #[strict_type(rename = "average")]
+        Medium = 10,
+        High = 2,
+    }
+
+    impl StrictSerialize for Level {}
+
+    assert_eq!(Level::ALL_VARIANTS, &[(0, "low"), (10, "average"), (2, "high")]);
+    for (tag, name) in Level::ALL_VARIANTS {
+        let level = Level::try_from(*tag).unwrap();
+        assert_eq!(u8::from(level), *tag);
+        assert_eq!(level.variant_name(), *name);
+        assert_eq!(level.to_strict_serialized::<1>().unwrap().as_slice(), &[*tag]);
+    }
+    assert_eq!(Level::try_from(1), Err(VariantError(Some(s!("Level")), 1)));
+    assert_eq!(Level::from_variant_name(&VariantName::from("average")), Ok(Level::Medium));
+    assert_eq!(Level::from_variant_name(&VariantName::from("high")), Ok(Level::High));
+    assert_eq!(
+        Level::from_variant_name(&VariantName::from("medium")),
+        Err(VariantError(Some(s!("Level")), &VariantName::from("medium")))
+    );
+
+    Ok(())
+}
+
 #[test]
 fn enum_associated() -> common::Result {
     #[allow(dead_code)]
@@ -213,3 +632,434 @@ fn enum_custom_tags() -> common::Result {
 
     Ok(())
 }
+
+#[test]
+fn enum_unknown_tags() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
+    #[repr(u8)]
+    enum Variants {
+        #[strict_type(dumb)]
+        One = 5,
+        Two = 6,
+        Three = 7,
+    }
+
+    let variants = KnownVariants(vec![
+        (5, VariantName::from("one")),
+        (6, VariantName::from("two")),
+        (7, VariantName::from("three")),
+    ]);
+    for tag in (0..=u8::MAX).filter(|tag| !(5..=7).contains(tag)) {
+        let mut reader = StrictReader::in_memory::<1>([tag]);
+        assert_eq!(
+            Variants::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::EnumTagNotKnown {
+                type_name: s!("Variants"),
+                tag,
+                variants: variants.clone(),
+            }
+        );
+    }
+
+    let mut reader = StrictReader::in_memory::<1>([17]);
+    assert_eq!(
+        Variants::strict_decode(&mut reader).unwrap_err().to_string(),
+        "unexpected variant 17 for enum `Variants`; known variants: one=5, two=6, three=7"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn union_unknown_tags() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = custom)]
+    enum Assoc {
+        #[strict_type(tag = 1)]
+        One(u8),
+        #[strict_type(tag = 3, dumb)]
+        Two,
+    }
+
+    for tag in (0..=u8::MAX).filter(|tag| *tag != 1 && *tag != 3) {
+        let mut reader = StrictReader::in_memory::<2>([tag, 0]);
+        assert_eq!(Assoc::strict_decode(&mut reader).unwrap_err(), DecodeError::UnionTagNotKnown {
+            type_name: s!("Assoc"),
+            tag,
+            variants: KnownVariants(vec![
+                (1, VariantName::from("one")),
+                (3, VariantName::from("two"))
+            ]),
+        });
+    }
+
+    let mut reader = StrictReader::in_memory::<2>([2, 0]);
+    assert_eq!(
+        Assoc::strict_decode(&mut reader).unwrap_err().to_string(),
+        "unexpected variant 2 for union `Assoc`; known variants: one=1, two=3"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn enum_other() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = custom)]
+    enum Method {
+        #[strict_type(tag = 1, dumb)]
+        Get,
+        #[strict_type(tag = 2)]
+        Put,
+        #[strict_type(other)]
+        Unknown(u8),
+    }
+
+    impl StrictSerialize for Method {}
+
+    assert_eq!(Method::ALL_VARIANTS, &[(1, "get"), (2, "put")]);
+    assert_eq!(Method::Put.variant_ord(), 2);
+    assert_eq!(Method::Unknown(7).variant_ord(), 7);
+    assert_eq!(Method::Unknown(7).variant_name(), "unknown");
+    assert_eq!(u8::from(Method::Unknown(7)), 7);
+    assert_eq!(Method::try_from(1), Ok(Method::Get));
+
+    for tag in 0..=u8::MAX {
+        let mut reader = StrictReader::in_memory::<1>([tag]);
+        let method = Method::strict_decode(&mut reader).unwrap();
+        match tag {
+            1 => assert_eq!(method, Method::Get),
+            2 => assert_eq!(method, Method::Put),
+            _ => assert_eq!(method, Method::Unknown(tag)),
+        }
+        assert_eq!(method.to_strict_serialized::<1>().unwrap().as_slice(), &[tag]);
+    }
+
+    assert!(Method::Unknown(2).to_strict_serialized::<1>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn unit_types() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Ping;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Pong;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = order)]
+    enum Signal {
+        #[strict_type(dumb)]
+        Ping(Ping),
+        Pong(Pong),
+        Close(()),
+    }
+
+    impl StrictSerialize for Ping {}
+    impl StrictSerialize for Signal {}
+
+    assert_eq!(Ping::FIELD_COUNT, 0);
+    assert_eq!(Ping::strict_dumb(), Ping);
+    assert_eq!(Ping.to_strict_serialized::<0>().unwrap().as_slice(), b"");
+    assert_eq!(Ping::strict_decode(&mut StrictReader::in_memory::<0>([])).unwrap(), Ping);
+    strict_encoding_test::test_encoding_roundtrip::<_, 0>(&Pong, vec![])?;
+    strict_encoding_test::test_encoding_roundtrip::<_, 0>(&(), vec![])?;
+
+    for (signal, tag) in [(Signal::Ping(Ping), 0), (Signal::Pong(Pong), 1), (Signal::Close(()), 2)]
+    {
+        assert_eq!(signal.to_strict_serialized::<1>().unwrap().as_slice(), &[tag]);
+        strict_encoding_test::test_encoding_roundtrip::<_, 1>(&signal, vec![tag])?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn recursive() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, tags = order)]
+    enum Expr {
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+        Scale { factor: u8, expr: Arc<Expr> },
+        Const(u64),
+    }
+
+    impl StrictSerialize for Expr {}
+
+    impl Expr {
+        fn with_nodes(count: u64) -> Self {
+            match count {
+                1 => Expr::Const(count),
+                2 => Expr::Neg(Box::new(Expr::Const(count))),
+                3 => Expr::Scale {
+                    factor: 3,
+                    expr: Arc::new(Expr::with_nodes(2)),
+                },
+                _ => {
+                    let left = (count - 1) / 2;
+                    let right = count - 1 - left;
+                    Expr::Add(Box::new(Expr::with_nodes(left)), Box::new(Expr::with_nodes(right)))
+                }
+            }
+        }
+
+        fn nodes(&self) -> usize {
+            match self {
+                Expr::Neg(expr) => 1 + expr.nodes(),
+                Expr::Add(left, right) => 1 + left.nodes() + right.nodes(),
+                Expr::Scale { expr, .. } => 1 + expr.nodes(),
+                Expr::Const(_) => 1,
+            }
+        }
+    }
+
+    assert_eq!(Expr::strict_dumb(), Expr::Const(0));
+
+    let expr = Expr::with_nodes(1000);
+    assert_eq!(expr.nodes(), 1000);
+    let data = expr.to_strict_serialized::<{ usize::MAX }>().unwrap();
+    let mut reader = StrictReader::in_memory::<{ usize::MAX }>(data.as_slice());
+    assert_eq!(Expr::strict_decode(&mut reader).unwrap(), expr);
+
+    Ok(())
+}
+
+#[test]
+fn optional_fields() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct ConfigV1 {
+        id: u8,
+        port: u16,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct ConfigV2 {
+        id: u8,
+        port: u16,
+        #[strict_type(optional)]
+        name: TinyString,
+        #[strict_type(skip)]
+        cache: Option<u8>,
+        #[strict_type(optional)]
+        flags: u8,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Pair(u8, #[strict_type(optional)] u16);
+
+    impl StrictSerialize for ConfigV1 {}
+    impl StrictSerialize for ConfigV2 {}
+
+    fn decode<T: StrictDecode>(data: &[u8]) -> Result<T, DecodeError> {
+        T::strict_decode(&mut StrictReader::in_memory::<32>(data))
+    }
+
+    let old = ConfigV1 { id: 1, port: 8080 };
+    let old_data = old.to_strict_serialized::<32>().unwrap();
+    assert_eq!(decode::<ConfigV2>(&old_data).unwrap(), ConfigV2 {
+        id: 1,
+        port: 8080,
+        ..default!()
+    });
+
+    let new = ConfigV2 {
+        id: 1,
+        port: 8080,
+        name: tiny_s!("node"),
+        cache: None,
+        flags: 0x80,
+    };
+    let new_data = new.to_strict_serialized::<32>().unwrap();
+    assert_eq!(new_data.as_slice(), b"\x01\x90\x1F\x04node\x80");
+    assert_eq!(decode::<ConfigV2>(&new_data).unwrap(), new);
+    assert_eq!(decode::<ConfigV2>(&new_data[..8]).unwrap(), ConfigV2 { flags: 0, ..new });
+
+    // data ending in the middle of an optional field are invalid
+    assert!(matches!(decode::<ConfigV2>(&new_data[..5]), Err(DecodeError::Io(_))));
+    // as well as data missing mandatory fields
+    assert!(matches!(decode::<ConfigV2>(&old_data[..2]), Err(DecodeError::Io(_))));
+
+    assert_eq!(decode::<Pair>(&[7]).unwrap(), Pair(7, 0));
+    assert_eq!(decode::<Pair>(&[7, 1, 2]).unwrap(), Pair(7, 0x0201));
+    assert!(matches!(decode::<Pair>(&[7, 1]), Err(DecodeError::Io(_))));
+    strict_encoding_test::test_encoding_roundtrip::<_, 3>(&Pair(7, 0x0201), vec![7, 1, 2])?;
+
+    Ok(())
+}
+
+#[test]
+fn flatten() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, flattenable)]
+    struct Common {
+        version: u16,
+        flags: u8,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, rename = "Message", flattenable)]
+    struct Message {
+        #[strict_type(flatten)]
+        common: Common,
+        id: u32,
+        payload: TinyString,
+    }
+
+    // the same message as it was defined by the legacy format
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, rename = "Message")]
+    struct InlineMessage {
+        version: u16,
+        flags: u8,
+        id: u32,
+        payload: TinyString,
+    }
+
+    // flattened structures may have flattened fields themselves
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Envelope<T: StrictDumb + StrictEncode + StrictDecode> {
+        #[strict_type(skip)]
+        cache: Option<u8>,
+        #[strict_type(flatten)]
+        message: Message,
+        #[strict_type(rename = "nonce")]
+        salt: u8,
+        body: T,
+    }
+
+    let message = Message {
+        common: Common {
+            version: 2,
+            flags: 0x80,
+        },
+        id: 7,
+        payload: tiny_s!("ping"),
+    };
+    let inline = InlineMessage {
+        version: 2,
+        flags: 0x80,
+        id: 7,
+        payload: tiny_s!("ping"),
+    };
+    let data = b"\x02\x00\x80\x07\x00\x00\x00\x04ping".to_vec();
+    strict_encoding_test::test_encoding_roundtrip::<_, 16>(&message, data.clone())?;
+    strict_encoding_test::test_encoding_roundtrip::<_, 16>(&inline, data.clone())?;
+
+    assert_eq!(Message::ALL_FIELDS, &["version", "flags", "id", "payload"]);
+    assert_eq!(Message::ALL_FIELDS, InlineMessage::ALL_FIELDS);
+    assert_eq!(Message::type_info(), InlineMessage::type_info());
+    let TypeClassOwned::Struct { fields } = Message::type_info().class else {
+        panic!("flattened fields must be listed in the struct type information")
+    };
+    let fields = fields.iter().map(|field| (field.name.clone(), field.ord)).collect::<Vec<_>>();
+    assert_eq!(fields, [
+        (Some(fname!("version")), 0),
+        (Some(fname!("flags")), 1),
+        (Some(fname!("id")), 2),
+        (Some(fname!("payload")), 3),
+    ]);
+
+    let envelope = Envelope {
+        cache: None,
+        message,
+        salt: 0xFF,
+        body: 0x0201u16,
+    };
+    let mut envelope_data = data;
+    envelope_data.extend([0xFF, 1, 2]);
+    strict_encoding_test::test_encoding_roundtrip::<_, 32>(&envelope, envelope_data)?;
+    assert_eq!(Envelope::<u16>::ALL_FIELDS, &[
+        "version", "flags", "id", "payload", "nonce", "body"
+    ]);
+
+    Ok(())
+}
+
+#[test]
+fn projection() {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB, project(timestamp, id, flags))]
+    struct Entry {
+        id: u32,
+        #[strict_type(rename = "body")]
+        payload: Confined<Vec<u8>, 0, 0xFFFF>,
+        tags: Confined<Vec<TinyString>, 0, 8>,
+        #[strict_type(skip)]
+        cache: Option<u8>,
+        timestamp: i64,
+        #[strict_type(optional)]
+        flags: u8,
+    }
+
+    impl StrictSerialize for Entry {}
+
+    fn reader(data: &[u8]) -> StrictReader<SliceReader<'_>> {
+        StrictReader::from_slice::<{ usize::MAX }>(data)
+    }
+
+    let entry = Entry {
+        id: 7,
+        payload: Confined::try_from(vec![0xAB; 1000]).unwrap(),
+        tags: Confined::try_from(vec![tiny_s!("a"), tiny_s!("bc")]).unwrap(),
+        cache: None,
+        timestamp: -2,
+        flags: 0x80,
+    };
+    let data = entry.to_strict_serialized::<{ usize::MAX }>().unwrap();
+
+    // partial decoding agrees with the full one and consumes the same data
+    let mut r = reader(&data);
+    assert_eq!(Entry::strict_decode_projection(&mut r).unwrap(), (-2, 7, 0x80));
+    r.finish().unwrap();
+    let full = Entry::strict_decode(&mut reader(&data)).unwrap();
+    assert_eq!((full.timestamp, full.id, full.flags), (-2, 7, 0x80));
+
+    // skipped fields are not allocated
+    let mut r = reader(&data).with_alloc_limit(0);
+    assert_eq!(Entry::strict_decode_projection(&mut r).unwrap(), (-2, 7, 0x80));
+    let mut r = reader(&data).with_alloc_limit(0);
+    assert!(matches!(
+        Entry::strict_decode(&mut r),
+        Err(DecodeError::AllocationBudgetExceeded { .. })
+    ));
+
+    // optional fields may be absent
+    let mut r = reader(&data[..data.len() - 1]);
+    assert_eq!(Entry::strict_decode_projection(&mut r).unwrap(), (-2, 7, 0));
+
+    // invalid data fail both decodings in the same way
+    let mut truncated = reader(&data[..500]);
+    let err = Entry::strict_decode_projection(&mut truncated).unwrap_err();
+    assert_eq!(err, Entry::strict_decode(&mut reader(&data[..500])).unwrap_err());
+}
+
+#[test]
+fn compile_fail() { common::compile_test("compile-fail"); }
+
+#[test]
+fn run_pass() { common::compile_test("run-pass"); }
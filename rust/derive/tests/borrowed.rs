@@ -0,0 +1,263 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate strict_encoding_derive;
+
+mod common;
+
+#[path = "../../src/test/counting_alloc.rs"]
+mod counting_alloc;
+
+use std::marker::PhantomData;
+
+use amplify::confinement::{LargeBlob, TinyBlob, TinyString};
+use counting_alloc::allocations;
+use strict_encoding::{
+    ConfinedRef, DecodeError, DeserializeError, LargeBlobRef, StrictDecode, StrictDecodeBorrowed,
+    StrictDeserialize, StrictEncode, StrictSerialize, TinyBlobRef,
+};
+
+const TEST_LIB: &str = "TestLib";
+
+/// Allocations of this size or above are counted as large ones.
+const LARGE_ALLOC: usize = 1024 * 1024;
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB)]
+struct Record {
+    id: u32,
+    payload: LargeBlob,
+    tag: [u8; 4],
+    label: TinyString,
+}
+
+impl StrictSerialize for Record {}
+impl StrictDeserialize for Record {}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictDecodeBorrowed)]
+#[strict_type(lib = TEST_LIB)]
+struct RecordRef<'a> {
+    id: u32,
+    payload: LargeBlobRef<'a>,
+    tag: &'a [u8; 4],
+    label: TinyString,
+    #[strict_type(skip)]
+    marker: PhantomData<&'a ()>,
+}
+
+#[test]
+fn borrowed_struct() -> common::Result {
+    let record = Record {
+        id: 0xDEADBEEF,
+        payload: LargeBlob::try_from(vec![0xAA; 16]).unwrap(),
+        tag: *b"test",
+        label: tiny_s!("record"),
+    };
+    let data = record.to_strict_serialized::<1024>().unwrap().release();
+
+    let borrowed = RecordRef::from_strict_slice_borrowed::<1024>(&data).unwrap();
+    assert_eq!(borrowed.id, record.id);
+    assert_eq!(borrowed.payload.to_confined(), record.payload);
+    assert_eq!(borrowed.payload.as_ptr(), data[8..].as_ptr());
+    assert_eq!(borrowed.tag, &record.tag);
+    assert_eq!(borrowed.label, record.label);
+
+    let mut extended = data.to_vec();
+    extended.push(0);
+    let err = RecordRef::from_strict_slice_borrowed::<1024>(&extended).unwrap_err();
+    assert!(matches!(err, DeserializeError::DataNotEntirelyConsumed(1)));
+
+    let err = RecordRef::from_strict_slice_borrowed::<16>(&data).unwrap_err();
+    assert!(matches!(err, DeserializeError::Decode(DecodeError::Io(_))));
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_tuple() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDecodeBorrowed)]
+    #[strict_type(lib = TEST_LIB)]
+    struct PairRef<'a>(u8, TinyBlobRef<'a>);
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDecodeBorrowed)]
+    #[strict_type(lib = TEST_LIB)]
+    struct PairOwned(u8, TinyBlob);
+
+    let data = [7, 3, 1, 2, 3];
+    let pair = PairRef::from_strict_slice_borrowed::<8>(&data).unwrap();
+    assert_eq!(pair.0, 7);
+    assert_eq!(pair.1.as_slice(), &[1, 2, 3]);
+
+    let pair = PairOwned::from_strict_slice_borrowed::<8>(&data).unwrap();
+    assert_eq!(pair, PairOwned(7, tiny_blob!(1, 2, 3)));
+
+    let err = PairRef::from_strict_slice_borrowed::<8>(&data[..4]).unwrap_err();
+    assert!(matches!(err, DeserializeError::Decode(DecodeError::Io(_))));
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_confinement() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDecodeBorrowed)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Limited<'a> {
+        blob: strict_encoding::BlobRef<'a, 1, 2>,
+    }
+
+    let err = Limited::from_strict_slice_borrowed::<8>(&[3, 1, 2, 3]).unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Decode(DecodeError::Oversize {
+            declared_len: 3,
+            max_len: 2,
+            ..
+        })
+    ));
+
+    let err = Limited::from_strict_slice_borrowed::<8>(&[0]).unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Decode(DecodeError::Undersize {
+            declared_len: 0,
+            min_len: 1,
+            max_len: 2,
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_large_blob() -> common::Result {
+    const LEN: usize = 100 * 1024 * 1024;
+    const MAX: usize = LEN + 32;
+
+    let mut data = Vec::with_capacity(MAX);
+    data.extend(0x01020304u32.to_le_bytes());
+    data.extend((LEN as u32).to_le_bytes());
+    data.resize(data.len() + LEN, 0x5A);
+    data.extend(b"blob");
+    data.extend([4]);
+    data.extend(b"huge");
+
+    let (record, allocs) =
+        allocations(|| RecordRef::from_strict_slice_borrowed::<MAX>(&data).unwrap());
+    assert!(allocs.largest < LARGE_ALLOC);
+
+    assert_eq!(record.id, 0x01020304);
+    assert_eq!(record.payload.len(), LEN);
+    assert_eq!(record.payload.as_ptr(), data[8..].as_ptr());
+    assert_eq!(record.tag, b"blob");
+    assert_eq!(record.label, tiny_s!("huge"));
+
+    // copying the blob is noticed by the allocator
+    let (owned, allocs) = allocations(|| record.payload.to_confined());
+    assert!(allocs.largest >= LARGE_ALLOC);
+    assert_eq!(owned.len(), LEN);
+
+    Ok(())
+}
+
+#[test]
+fn borrowing_encode_only() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Entry {
+        name: TinyString,
+        id: u32,
+    }
+    impl StrictSerialize for Entry {}
+    impl StrictDeserialize for Entry {}
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDumb)]
+    #[strict_type(lib = TEST_LIB, dumb = EntryView::empty())]
+    struct EntryView<'a> {
+        name: ConfinedRef<'a, str, 0, 255>,
+        id: &'a u32,
+    }
+    impl StrictSerialize for EntryView<'_> {}
+
+    impl EntryView<'_> {
+        fn empty() -> Self {
+            EntryView {
+                name: ConfinedRef::try_from("").unwrap(),
+                id: &0,
+            }
+        }
+    }
+
+    let id = 0x0A0B0C0D;
+    let view = EntryView {
+        name: ConfinedRef::try_from("entry").unwrap(),
+        id: &id,
+    };
+    let data = view.to_strict_serialized::<64>().unwrap();
+    let entry = Entry::from_strict_serialized::<64>(data.clone()).unwrap();
+    assert_eq!(entry, Entry {
+        name: tiny_s!("entry"),
+        id,
+    });
+    assert_eq!(entry.to_strict_serialized::<64>().unwrap(), data);
+
+    Ok(())
+}
+
+#[test]
+fn lifetime_generic() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Wrapper<'a, T: 'a> {
+        value: T,
+        marker: PhantomData<&'a T>,
+    }
+    impl<T: StrictEncode + Default> StrictSerialize for Wrapper<'_, T> {}
+    impl<T: StrictDecode + Default> StrictDeserialize for Wrapper<'_, T> {}
+
+    let wrapper = Wrapper {
+        value: tiny_s!("wrapped"),
+        marker: PhantomData,
+    };
+    let data = wrapper.to_strict_serialized::<64>().unwrap();
+    assert_eq!(data.as_slice(), b"\x07wrapped");
+    assert_eq!(Wrapper::from_strict_serialized::<64>(data).unwrap(), wrapper);
+
+    let wrapper = Wrapper {
+        value: 0xBEEFu16,
+        marker: PhantomData,
+    };
+    let data = wrapper.to_strict_serialized::<64>().unwrap();
+    assert_eq!(Wrapper::from_strict_serialized::<64>(data).unwrap(), wrapper);
+
+    Ok(())
+}
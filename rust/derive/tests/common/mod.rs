@@ -23,22 +23,48 @@ extern crate compiletest_rs as compiletest;
 
 use std::fmt::{Debug, Display, Formatter};
 use std::path::PathBuf;
+use std::process::Command;
 
 use strict_encoding::{StrictDecode, StrictEncode};
 use strict_encoding_test::DataEncodingTestFailure;
 
 #[allow(dead_code)]
 pub fn compile_test(mode: &'static str) {
-    let mut config = compiletest::Config {
+    let config = compiletest::Config {
         mode: mode.parse().expect("Invalid mode"),
         src_base: PathBuf::from(format!("tests/{}", mode)),
+        target_rustcflags: Some(build_deps()),
         ..default!()
     };
-    config.link_deps();
-    config.clean_rmeta();
     compiletest::run_tests(&config);
 }
 
+/// Builds the crates used by the compile tests into a dedicated target
+/// directory and returns the compiler flags passing them to the tests.
+///
+/// The dependency directory of the test executable may contain builds of the
+/// crates with different features, which are ambiguous for the compiler; the
+/// dedicated directory contains a single build of each crate.
+fn build_deps() -> String {
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("compiletest");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "build",
+            "--quiet",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+        ])
+        .args(["-p", "strict_encoding", "-p", "strict_encoding_derive"])
+        .args(["--features", "strict_encoding/arbitrary,strict_encoding/test_helpers"])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cargo is not available");
+    assert!(status.success(), "crates used by the compile tests can't be built");
+    let deps = target_dir.join("debug").join("deps");
+    format!("-L {} --extern strict_encoding --extern strict_encoding_derive", deps.display())
+}
+
 #[derive(Display)]
 #[display(inner)]
 pub struct Error(pub Box<dyn std::error::Error>);
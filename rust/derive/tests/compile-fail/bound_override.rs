@@ -0,0 +1,21 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+extern crate strict_encoding;
+
+use std::marker::PhantomData;
+
+use strict_encoding::{StrictDumb, StrictEncode, StrictWriter};
+
+struct Marker;
+
+// the bounds given explicitly replace the inferred ones
+#[derive(StrictDumb, StrictType, StrictEncode)]
+#[strict_type(lib = "Test", bound = "M: StrictDumb")]
+struct Handle<M>(u8, PhantomData<M>);
+
+fn main() {
+    let handle = Handle::<Marker>(1, PhantomData);
+    handle.strict_encode(StrictWriter::in_memory::<1>()); //~ ERROR trait bounds were not satisfied
+}
@@ -0,0 +1,35 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+use strict_encoding::ConfinedRef;
+
+#[derive(Clone, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = "Test", dumb = View::empty())]
+struct View<'a> {
+    id: &'a u32, //~ ERROR borrows data
+}
+
+impl View<'_> {
+    fn empty() -> Self { View { id: &0 } }
+}
+
+#[derive(Clone, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = "Test", dumb = Name::empty())]
+struct Name<'a>(ConfinedRef<'a, str, 0, 255>); //~ ERROR borrows data
+
+impl Name<'_> {
+    fn empty() -> Self { Name(ConfinedRef::try_from("").unwrap()) }
+}
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", encode_only)]
+struct Report { //~ ERROR marked as `encode_only`
+    total: u64,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Copy, Clone, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = "Test", tags = order)]
+enum Named {
+    #[strict_type(dumb)]
+    Known,
+    #[strict_type(other)]
+    Unknown { tag: u8 }, //~ ERROR single unnamed field
+}
+
+#[derive(Copy, Clone, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = "Test", tags = order)]
+enum Mixed {
+    #[strict_type(dumb)]
+    Known,
+    Data(u16), //~ ERROR only unit variants
+    #[strict_type(other)]
+    Unknown(u8),
+}
+
+fn main() {}
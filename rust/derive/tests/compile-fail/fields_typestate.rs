@@ -0,0 +1,44 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+use strict_encoding::{field, fields, io, FieldsWriter, StrictEncode, TypedWrite};
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Missing {
+    base: u32,
+    quote: u32,
+}
+
+impl StrictEncode for Missing {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<Self>(|w| {
+            let w = FieldsWriter::<_, fields!["base", "quote"]>::new(w);
+            Ok(w.write_field(field!("base"), &self.base)?.complete()) //~ ERROR no method named `complete`
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Repeated {
+    base: u32,
+    quote: u32,
+}
+
+impl StrictEncode for Repeated {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<Self>(|w| {
+            let w = FieldsWriter::<_, fields!["base", "quote"]>::new(w);
+            Ok(w.write_field(field!("base"), &self.base)?
+                .write_field(field!("base"), &self.base)? //~ ERROR mismatched types
+                .complete())
+        })
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", flattenable)]
+struct Common {
+    version: u16,
+    flags: u8,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)] //~ ERROR contains repeated field names
+#[strict_type(lib = "Test")]
+struct Message {
+    #[strict_type(flatten)]
+    common: Common,
+    flags: u8,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", flattenable)]
+struct Pair(u16, u8); //~ ERROR can be used only with structures with named fields
+
+fn main() {}
@@ -0,0 +1,19 @@
+// compile-flags: --edition 2021
+
+use strict_encoding::{StrictSum, StrictType};
+
+#[derive(Default)]
+struct Method;
+
+impl StrictType for Method {
+    const STRICT_LIB_NAME: &'static str = "Test";
+}
+
+impl StrictSum for Method {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(1, "get"), (2, "post"), (2, "delete")];
+    fn variant_name(&self) -> &'static str { "get" }
+}
+
+strict_encoding::assert_type_sanity!(enum Method); //~ ERROR contains repeated variant tags
+
+fn main() {}
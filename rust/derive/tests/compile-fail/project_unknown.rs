@@ -0,0 +1,14 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", project(id, stamp))] //~ ERROR no field `stamp` to project
+struct Entry {
+    id: u32,
+    timestamp: i64,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug)]
+#[derive(StrictDumb)] //~ ERROR no non-recursive variant
+#[strict_type(lib = "Test", tags = order)]
+enum Infinite {
+    Neg(Box<Infinite>),
+    Pair(Box<Self>, Box<Self>),
+}
+
+#[derive(Clone, Debug)]
+#[derive(StrictDumb)]
+#[strict_type(lib = "Test", tags = order)]
+enum Marked {
+    #[strict_type(dumb)]
+    Neg(Box<Marked>), //~ ERROR can't refer to the enum itself
+    Const(u64),
+}
+
+fn main() {}
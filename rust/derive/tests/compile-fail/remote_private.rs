@@ -0,0 +1,22 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+mod vendor {
+    #[derive(Clone, Debug)]
+    pub struct Config {
+        pub port: u16,
+        secret: u64,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", remote = "vendor::Config")]
+struct ConfigDef {
+    port: u16,
+    secret: u64, //~ ERROR field `secret` of struct `Config` is private
+}
+
+fn main() {}
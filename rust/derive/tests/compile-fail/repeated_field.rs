@@ -0,0 +1,15 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)] //~ ERROR contains repeated field names
+#[strict_type(lib = "Test")]
+struct Header {
+    version: u8,
+    #[strict_type(rename = "version")]
+    flags: u8,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)] //~ ERROR contains repeated variant tags
+#[strict_type(lib = "Test", tags = custom)]
+enum Request {
+    #[strict_type(tag = 1, dumb)]
+    Get,
+    #[strict_type(tag = 2)]
+    Post(u8),
+    #[strict_type(tag = 2)]
+    Delete(u8),
+}
+
+fn main() {}
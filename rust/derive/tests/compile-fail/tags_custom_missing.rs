@@ -0,0 +1,15 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(StrictDumb, StrictType)]
+#[strict_type(lib = "Test", tags = custom)]
+enum Request {
+    #[strict_type(tag = 1, dumb)]
+    Get,
+    Post(u8), //~ ERROR variant `Post` has no `#[strict_type(tag = ...)]` attribute
+}
+
+fn main() {}
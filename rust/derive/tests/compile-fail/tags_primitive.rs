@@ -0,0 +1,27 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType)] //~ ERROR primitive enum types must always use `tags = repr`
+#[strict_type(lib = "Test", tags = order, into_u8, try_from_u8)]
+#[repr(u8)]
+enum Ordered {
+    #[default]
+    One,
+    Two,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType)]
+#[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8)]
+#[repr(u8)]
+enum Shifted {
+    #[default]
+    One = 1,
+    #[strict_type(tag = 20)]
+    Two = 2, //~ ERROR variants of primitive enum types can't override their tags
+}
+
+fn main() {}
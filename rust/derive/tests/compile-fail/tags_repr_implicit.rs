@@ -0,0 +1,17 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType)]
+#[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8)]
+#[repr(u8)]
+enum Prime {
+    #[default]
+    Two, //~ ERROR `tags = repr` requires the enum to have explicit discriminants
+    Three,
+    Five,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", test_value = Entry::default())] //~ ERROR `test_value` attribute requires `derive_tests` attribute
+struct Entry {
+    id: u32,
+}
+
+fn main() {}
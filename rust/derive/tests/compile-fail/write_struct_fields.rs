@@ -0,0 +1,48 @@
+// compile-flags: --edition 2021
+
+#[macro_use]
+extern crate strict_encoding_derive;
+
+use strict_encoding::{io, StrictEncode, StrictWriter, TypedWrite};
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Missing {
+    base: u32,
+    quote: u32,
+    spread: u16,
+    expiry: u64,
+}
+
+impl StrictEncode for Missing {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        strict_encoding::write_struct!(writer, Self { //~ ERROR are not the declared fields
+            "base" => &self.base,
+            "quote" => &self.quote,
+            "spread" => &self.spread,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[derive(StrictType, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Reordered {
+    base: u32,
+    quote: u32,
+}
+
+impl StrictEncode for Reordered {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        strict_encoding::write_struct!(writer, Self { //~ ERROR are not the declared fields
+            "quote" => &self.quote,
+            "base" => &self.base,
+        })
+    }
+}
+
+fn main() {
+    let _ = Missing::default().strict_encode(StrictWriter::in_memory::<32>());
+    let _ = Reordered::default().strict_encode(StrictWriter::in_memory::<32>());
+}
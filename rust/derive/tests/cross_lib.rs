@@ -0,0 +1,146 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate strict_encoding_derive;
+
+mod common;
+
+use strict_encoding::{
+    libname, tn, StrictType, StrictTypeInfo, TypeClassOwned, TypeLib, TypeLibError, TypeRef,
+};
+// types of the lower-level library are defined in another crate
+use strict_encoding_cross_lib_test as base;
+
+/// Protocol composed of the types of `BaseTypes` library.
+mod app {
+    use amplify::confinement::TinyVec;
+
+    use super::base;
+
+    pub const LIB_NAME: &str = "AppProto";
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME, tags = order)]
+    pub enum Message {
+        #[default]
+        Ping,
+        Transfer {
+            amount: base::Amount,
+            to: base::Address,
+        },
+        Batch(TinyVec<base::Amount>),
+        Note(Option<base::Address>),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME)]
+    pub struct Packet {
+        pub id: u32,
+        pub message: Message,
+    }
+}
+
+fn named(lib: &'static str, name: &'static str) -> TypeRef {
+    TypeRef::Named {
+        lib: libname!(lib),
+        name: tn!(name),
+    }
+}
+
+fn libs() -> (TypeLib, TypeLib) {
+    let mut base = TypeLib::new(libname!(base::LIB_NAME));
+    base.insert(base::Amount::type_info()).unwrap();
+    base.insert(base::Address::type_info()).unwrap();
+    let mut app = TypeLib::new(libname!(app::LIB_NAME));
+    app.insert(app::Message::type_info()).unwrap();
+    app.insert(app::Packet::type_info()).unwrap();
+    (base, app)
+}
+
+#[test]
+fn variant_refs() -> common::Result {
+    assert_eq!(app::Message::STRICT_LIB_NAME, app::LIB_NAME);
+
+    let info = app::Message::type_info();
+    assert_eq!(info.lib, libname!(app::LIB_NAME));
+    let TypeClassOwned::Union { variants } = &info.class else {
+        panic!("{info} is not a union")
+    };
+    let types = variants
+        .iter()
+        .map(|variant| {
+            variant.fields.iter().map(|field| field.ty.clone().unwrap()).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(types[0], []);
+    assert_eq!(types[1], [named(base::LIB_NAME, "Amount"), named(base::LIB_NAME, "Address")]);
+    assert_eq!(types[2][0].to_string(), "[BaseTypes.Amount ^ ..0xff]");
+    assert_eq!(types[3][0].to_string(), "BaseTypes.Address?");
+
+    assert_eq!(info.named_refs(), bset![
+        (libname!(base::LIB_NAME), tn!("Address")),
+        (libname!(base::LIB_NAME), tn!("Amount")),
+    ]);
+    assert_eq!(app::Packet::type_info().named_refs(), bset![(
+        libname!(app::LIB_NAME),
+        tn!("Message")
+    )]);
+
+    Ok(())
+}
+
+#[test]
+fn library_export() -> common::Result {
+    let (base, app) = libs();
+    assert_eq!(app.dependencies(), bset![libname!(base::LIB_NAME)]);
+    assert!(app.unresolved().is_empty());
+    assert!(base.dependencies().is_empty());
+
+    // all the references resolve to the types of the library they name
+    for (_, info) in &app {
+        for (lib, name) in info.named_refs() {
+            let lib = if lib == *app.name() { &app } else { &base };
+            assert!(lib.get(&name).is_some(), "{name} is not found in {}", lib.name());
+        }
+    }
+
+    let mut partial = TypeLib::new(libname!(app::LIB_NAME));
+    partial.insert(app::Packet::type_info()).unwrap();
+    assert_eq!(partial.unresolved(), bset![tn!("Message")]);
+
+    // the types of the inner library are not a part of the outer one
+    let mut app = app;
+    assert!(matches!(
+        app.insert(base::Amount::type_info()).unwrap_err(),
+        TypeLibError::ForeignLib { .. }
+    ));
+    assert!(matches!(
+        app.register::<base::Address>().unwrap_err(),
+        TypeLibError::ForeignLib { .. }
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate strict_encoding_derive;
+
+mod common;
+
+#[path = "../../src/test/counting_alloc.rs"]
+mod counting_alloc;
+
+use amplify::confinement::{TinyBlob, TinyString, TinyVec};
+use counting_alloc::allocations;
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDecodeInto, StrictDumb, StrictEncode, StrictReader,
+    StrictSerialize,
+};
+
+const TEST_LIB: &str = "TestLib";
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+#[strict_type(lib = TEST_LIB)]
+struct Item {
+    name: TinyString,
+    values: TinyVec<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+#[strict_type(lib = TEST_LIB)]
+struct Header(u8, TinyBlob);
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+#[strict_type(lib = TEST_LIB)]
+struct Message {
+    id: u64,
+    header: Header,
+    items: TinyVec<Item>,
+    #[strict_type(skip)]
+    cache: u32,
+}
+
+impl StrictSerialize for Message {}
+
+/// Messages of the same shape, which differ only in their data.
+fn message(seq: u8) -> Message {
+    let item = |n: u8| Item {
+        name: TinyString::try_from(format!("item {seq:02}-{n}")).unwrap(),
+        values: TinyVec::try_from_iter((0..n as u32).map(|v| v * seq as u32)).unwrap(),
+    };
+    Message {
+        id: seq as u64 * 1000,
+        header: Header(seq, TinyBlob::try_from(vec![seq; 12]).unwrap()),
+        items: TinyVec::try_from_iter((0..4).map(item)).unwrap(),
+        cache: 0,
+    }
+}
+
+fn decode_into<T: StrictDecodeInto>(value: &mut T, data: &[u8]) -> Result<(), DecodeError> {
+    let mut reader = StrictReader::from_slice::<1024>(data);
+    value.strict_decode_into(&mut reader)
+}
+
+#[test]
+fn struct_reuse() -> common::Result {
+    let data = (0..16u8)
+        .map(|seq| message(seq).to_strict_serialized::<1024>().unwrap().release())
+        .collect::<Vec<_>>();
+
+    let mut value = Message {
+        cache: 7,
+        ..default!()
+    };
+    for (seq, data) in data.iter().enumerate() {
+        decode_into(&mut value, data).unwrap();
+        assert_eq!(value, message(seq as u8));
+        assert_eq!(
+            value,
+            Message::strict_decode(&mut StrictReader::from_slice::<1024>(data)).unwrap()
+        );
+    }
+
+    // all the collections already have the capacity to hold any of the
+    // messages, thus the repeated decoding doesn't allocate
+    let ((), allocs) = allocations(|| {
+        for data in data.iter().rev() {
+            decode_into(&mut value, data).unwrap();
+        }
+    });
+    assert_eq!(allocs.count, 0);
+    assert_eq!(value, message(0));
+
+    let ((), fresh) = allocations(|| {
+        for data in &data {
+            Message::strict_decode(&mut StrictReader::from_slice::<1024>(data)).unwrap();
+        }
+    });
+    assert!(fresh.count > data.len() * 3);
+
+    Ok(())
+}
+
+#[test]
+fn failure_state() -> common::Result {
+    let data = message(3).to_strict_serialized::<1024>().unwrap().release();
+    let mut value = message(2);
+    let err = decode_into(&mut value, &data[..data.len() - 3]).unwrap_err();
+    assert!(matches!(err, DecodeError::Io(_)));
+
+    // the value is still usable and is fully overwritten by the next decoding
+    assert_eq!(value.items.len(), 4);
+    decode_into(&mut value, &data).unwrap();
+    assert_eq!(value, message(3));
+
+    Ok(())
+}
+
+#[test]
+fn replacing() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+    #[strict_type(lib = TEST_LIB, tags = order)]
+    enum Payload {
+        #[strict_type(dumb)]
+        Empty,
+        Text(TinyString),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Extensible {
+        version: u8,
+        #[strict_type(optional)]
+        label: TinyString,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+    #[strict_type(transparent)]
+    struct Wrapped(TinyVec<u16>);
+
+    let mut payload = Payload::Empty;
+    decode_into(&mut payload, &[1, 2, b'h', b'i']).unwrap();
+    assert_eq!(payload, Payload::Text(tiny_s!("hi")));
+    decode_into(&mut payload, &[0]).unwrap();
+    assert_eq!(payload, Payload::Empty);
+
+    let mut extensible = Extensible {
+        version: 1,
+        label: tiny_s!("old"),
+    };
+    decode_into(&mut extensible, &[2]).unwrap();
+    assert_eq!(extensible, Extensible {
+        version: 2,
+        label: tiny_s!(""),
+    });
+
+    let mut wrapped = Wrapped(tiny_vec![1, 2, 3]);
+    let ptr = wrapped.0.as_ptr();
+    decode_into(&mut wrapped, &[2, 5, 0, 6, 0]).unwrap();
+    assert_eq!(wrapped, Wrapped(tiny_vec![5, 6]));
+    assert_eq!(wrapped.0.as_ptr(), ptr);
+
+    Ok(())
+}
+
+#[test]
+fn field_fallback() -> common::Result {
+    /// Type which doesn't implement `StrictDecodeInto`.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Plain(TinyString);
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode, StrictDecodeInto)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Mixed {
+        plain: Plain,
+        reused: TinyBlob,
+    }
+
+    let mut mixed = Mixed {
+        plain: Plain(tiny_s!("old")),
+        reused: tiny_blob!(1, 2, 3),
+    };
+    let ptr = mixed.reused.as_ptr();
+    decode_into(&mut mixed, &[2, b'n', b'w', 2, 5, 6]).unwrap();
+    assert_eq!(mixed, Mixed {
+        plain: Plain(tiny_s!("nw")),
+        reused: tiny_blob!(5, 6),
+    });
+    assert_eq!(mixed.reused.as_ptr(), ptr);
+
+    Ok(())
+}
@@ -28,7 +28,7 @@ extern crate amplify;
 mod common;
 
 use amplify::confinement::Confined;
-use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictType};
+use strict_encoding::{ConfinedDumb, StrictDecode, StrictDumb, StrictEncode, StrictType};
 
 const TEST_LIB: &str = "TestLib";
 
@@ -57,7 +57,7 @@ fn enum_default() -> common::Result {
     #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
     #[repr(u8)]
     enum Variants {
-        One,
+        One = 0,
         Two,
         #[default]
         Three,
@@ -87,6 +87,41 @@ fn enum_explicit() -> common::Result {
     Ok(())
 }
 
+#[test]
+fn enum_fields() -> common::Result {
+    #[allow(dead_code)]
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb)]
+    #[strict_type(lib = TEST_LIB)]
+    enum Shape {
+        Group(Vec<Shape>),
+        Circle(u16),
+        #[strict_type(dumb)]
+        Rect {
+            width: u16,
+            #[strict_type(dumb = 1)]
+            height: u16,
+        },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb)]
+    #[strict_type(lib = TEST_LIB)]
+    enum Tree<T> {
+        Node(Box<Tree<T>>, Box<Tree<T>>),
+        Leaf(T),
+    }
+
+    assert_eq!(Shape::strict_dumb(), Shape::Rect {
+        width: 0,
+        height: 1
+    });
+    assert_eq!(Tree::<u8>::strict_dumb(), Tree::Leaf(0));
+
+    Ok(())
+}
+
 #[test]
 fn dumb_wrapper_container() -> common::Result {
     #[derive(Clone, PartialEq, Eq, Debug)]
@@ -172,5 +207,25 @@ fn dumb_ultra_complex() -> common::Result {
         Confined<Vec<T>, 1, { u8::MAX as usize }>,
     );
 
+    assert_eq!(NamedFields::<u8>::strict_dumb().0.as_slice(), &[0]);
+
+    Ok(())
+}
+
+#[test]
+fn struct_confined_dumb() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Inputs {
+        #[strict_type(dumb = ConfinedDumb::confined_dumb())]
+        items: Confined<Vec<u16>, 1, 10>,
+        label: Confined<String, 0, 10>,
+    }
+
+    let dumb = Inputs::strict_dumb();
+    assert_eq!(dumb.items.as_slice(), &[0]);
+    assert!(dumb.label.is_empty());
+
     Ok(())
 }
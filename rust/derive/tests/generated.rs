@@ -0,0 +1,97 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types with tests generated by `#[strict_type(derive_tests)]` attribute;
+//! the tests are run as `strict_encoding_tests_*` modules of this crate.
+
+#[macro_use]
+extern crate amplify;
+
+use amplify::confinement::{TinyOrdMap, TinyString, TinyVec};
+use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictType};
+
+const TEST_LIB: &str = "TestLib";
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, derive_tests, test_value = Header::sample())]
+pub struct Header {
+    version: u16,
+    name: TinyString,
+    extra: TinyOrdMap<u8, TinyVec<u8>>,
+}
+
+impl Header {
+    fn sample() -> Self {
+        Header {
+            version: 3,
+            name: tiny_s!("sample"),
+            extra: tiny_bmap!(1 => tiny_vec![0xAB], 7 => tiny_vec![]),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, derive_tests, test_value = { Pair(-1, u32::MAX) })]
+pub struct Pair(i8, u32);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8, derive_tests)]
+#[repr(u8)]
+pub enum Level {
+    #[default]
+    Low = 1,
+    High = 0x10,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, tags = order, derive_tests, test_value = Message::sample())]
+pub enum Message {
+    #[strict_type(dumb)]
+    Ping,
+    Pair(Pair),
+    Data {
+        level: Level,
+        body: TinyVec<u8>,
+    },
+}
+
+impl Message {
+    fn sample() -> Self {
+        Message::Data {
+            level: Level::High,
+            body: tiny_vec![1, 2, 3],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, derive_tests)]
+pub struct Envelope {
+    header: Header,
+    messages: TinyVec<Message>,
+    #[strict_type(skip)]
+    cache: Option<u64>,
+}
@@ -0,0 +1,259 @@
+// Derivation macro library for strict encoding.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Caused by an imperfection of rust compiler in parsing proc macro args
+#![allow(unused_braces)]
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate strict_encoding_derive;
+
+mod common;
+
+use strict_encoding::{
+    io, DecodeError, StrictDecode, StrictDumb, StrictEncode, StrictReader, StrictStruct,
+    StrictWriter, TypedRead, TypedWrite,
+};
+
+const TEST_LIB: &str = "TestLib";
+
+mod vendor {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum Network {
+        Main,
+        Test,
+        Regtest,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub struct ChainParams {
+        pub magic: u32,
+        pub port: u16,
+        pub network: Network,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct Amount(pub u64);
+}
+
+/// Legacy LEB128 variable-length encoding of amounts.
+mod varint {
+    use super::*;
+
+    pub fn strict_dumb() -> vendor::Amount { vendor::Amount(0) }
+
+    pub fn strict_encode<W: TypedWrite>(amount: &vendor::Amount, mut writer: W) -> io::Result<W> {
+        let mut value = amount.0;
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                return byte.strict_encode(writer);
+            }
+            writer = (byte | 0x80).strict_encode(writer)?;
+        }
+    }
+
+    pub fn strict_decode(reader: &mut impl TypedRead) -> Result<vendor::Amount, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = u8::strict_decode(reader)?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(vendor::Amount(value));
+            }
+        }
+        Err(DecodeError::DataIntegrityError(s!("varint is too long")))
+    }
+}
+
+/// Decoding of legacy flags, which used any non-zero byte as `true`.
+mod lenient_bool {
+    use super::*;
+
+    pub fn strict_decode(reader: &mut impl TypedRead) -> Result<bool, DecodeError> {
+        u8::strict_decode(reader).map(|byte| byte != 0)
+    }
+}
+
+/// Encoding of limits, which are capped by the protocol maximum.
+mod clamped {
+    use super::*;
+
+    pub const MAX_LIMIT: u16 = 1000;
+
+    pub fn strict_encode<W: TypedWrite>(limit: &u16, writer: W) -> io::Result<W> {
+        (*limit).min(MAX_LIMIT).strict_encode(writer)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8, remote = "vendor::Network")]
+#[repr(u8)]
+enum NetworkDef {
+    #[default]
+    Main = 0,
+    Test = 1,
+    Regtest = 0x10,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = TEST_LIB, remote = "vendor::ChainParams")]
+struct ChainParamsDef {
+    magic: u32,
+    port: u16,
+    #[strict_type(with = "NetworkDef")]
+    network: vendor::Network,
+}
+
+#[test]
+fn remote_enum() -> common::Result {
+    for (network, tag) in [
+        (vendor::Network::Main, 0x00u8),
+        (vendor::Network::Test, 0x01),
+        (vendor::Network::Regtest, 0x10),
+    ] {
+        let writer = NetworkDef::strict_encode(&network, StrictWriter::in_memory::<1>()).unwrap();
+        let data = writer.unbox().unconfine();
+        assert_eq!(data, vec![tag]);
+
+        let mut reader = StrictReader::in_memory::<1>(data);
+        assert_eq!(NetworkDef::strict_decode(&mut reader).unwrap(), network);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn remote_struct() -> common::Result {
+    let params = vendor::ChainParams {
+        magic: 0xD9B4BEF9,
+        port: 8333,
+        network: vendor::Network::Regtest,
+    };
+
+    assert_eq!(ChainParamsDef::ALL_FIELDS, &["magic", "port", "network"]);
+
+    assert_eq!(vendor::ChainParams::from(ChainParamsDef::strict_dumb()), vendor::ChainParams {
+        magic: 0,
+        port: 0,
+        network: vendor::Network::Main,
+    });
+
+    let writer = ChainParamsDef::strict_encode(&params, StrictWriter::in_memory::<7>()).unwrap();
+    let data = writer.unbox().unconfine();
+    assert_eq!(data, vec![0xF9, 0xBE, 0xB4, 0xD9, 0x8D, 0x20, 0x10]);
+
+    let mut reader = StrictReader::in_memory::<7>(data);
+    assert_eq!(ChainParamsDef::strict_decode(&mut reader).unwrap(), params);
+
+    Ok(())
+}
+
+#[test]
+fn remote_field() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Node {
+        version: u8,
+        #[strict_type(with = "ChainParamsDef")]
+        params: vendor::ChainParams,
+    }
+
+    assert_eq!(Node::strict_dumb().params, ChainParamsDef::strict_dumb().into());
+
+    let node = Node {
+        version: 1,
+        params: vendor::ChainParams {
+            magic: 0x0709110B,
+            port: 18333,
+            network: vendor::Network::Test,
+        },
+    };
+    strict_encoding_test::test_encoding_roundtrip::<_, 8>(&node, vec![
+        0x01, 0x0B, 0x11, 0x09, 0x07, 0x9D, 0x47, 0x01,
+    ])?;
+
+    Ok(())
+}
+
+#[test]
+fn module_codec() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Payment {
+        version: u8,
+        #[strict_type(with = "varint")]
+        amount: vendor::Amount,
+        fee: u16,
+    }
+
+    assert_eq!(Payment::strict_dumb().amount, vendor::Amount(0));
+
+    for (amount, encoded) in [
+        (0, vec![0x00]),
+        (0x7F, vec![0x7F]),
+        (300, vec![0xAC, 0x02]),
+        (u64::MAX, vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+    ] {
+        let payment = Payment {
+            version: 2,
+            amount: vendor::Amount(amount),
+            fee: 0x0102,
+        };
+        let mut data = vec![0x02];
+        data.extend(encoded);
+        data.extend([0x02, 0x01]);
+        strict_encoding_test::test_encoding_roundtrip::<_, 16>(&payment, data)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn one_directional_codec() -> common::Result {
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = TEST_LIB)]
+    struct Settings {
+        #[strict_type(decode_with = "lenient_bool")]
+        enabled: bool,
+        #[strict_type(encode_with = "clamped")]
+        limit: u16,
+    }
+
+    let settings = Settings {
+        enabled: true,
+        limit: 5000,
+    };
+    let writer = settings.strict_encode(StrictWriter::in_memory::<16>()).unwrap();
+    assert_eq!(writer.unbox().unconfine(), vec![0x01, 0xE8, 0x03]);
+
+    let mut reader = StrictReader::in_memory::<16>(vec![0x05, 0x88, 0x13]);
+    assert_eq!(Settings::strict_decode(&mut reader).unwrap(), settings);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// compile-flags: --edition 2021
+// no-prefer-dynamic
+
+//! Protocol types marked `#[non_exhaustive]`, which are consumed by the tests
+//! from another crate.
+
+#![crate_type = "rlib"]
+
+#[macro_use]
+extern crate strict_encoding_derive;
+extern crate strict_encoding;
+
+pub const LIB: &str = "Protocol";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB)]
+#[non_exhaustive]
+pub struct Header {
+    pub version: u8,
+    pub flags: u16,
+}
+
+impl Header {
+    pub fn new(version: u8, flags: u16) -> Self { Header { version, flags } }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB)]
+#[non_exhaustive]
+pub struct Nonce(pub u32);
+
+impl Nonce {
+    pub fn new(nonce: u32) -> Self { Nonce(nonce) }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB, tags = repr, into_u8, try_from_u8)]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum Command {
+    #[strict_type(dumb)]
+    Ping = 1,
+    Pong = 2,
+    Close = 3,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB, tags = order)]
+#[non_exhaustive]
+pub enum Message {
+    Hello(Header),
+    #[non_exhaustive]
+    Data {
+        nonce: Nonce,
+        command: Command,
+    },
+    Bye,
+}
+
+impl Message {
+    pub fn data(nonce: Nonce, command: Command) -> Self { Message::Data { nonce, command } }
+}
@@ -0,0 +1,45 @@
+// compile-flags: --edition 2021
+
+//! Generic types over a marker type, which is not a strict type, derive the
+//! strict encoding without bounds on the marker.
+
+#[macro_use]
+extern crate strict_encoding_derive;
+extern crate strict_encoding;
+
+use std::marker::PhantomData;
+
+use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictReader, StrictWriter};
+
+struct Marker;
+
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Wrapper<T>(T, PhantomData<fn() -> T>);
+
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test")]
+struct Handle<M>(u8, PhantomData<M>);
+
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", no_bound(M))]
+struct Owned<M> {
+    handle: Handle<M>,
+}
+
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "Test", bound = "T: StrictDumb + StrictEncode + StrictDecode + Clone")]
+struct Cloned<T>(T);
+
+fn roundtrip<T: StrictEncode + StrictDecode>(value: T, data: &[u8]) {
+    let writer = value.strict_encode(StrictWriter::in_memory::<8>()).unwrap();
+    assert_eq!(writer.unbox().unconfine(), data);
+    T::strict_decode(&mut StrictReader::in_memory::<8>(data)).unwrap();
+}
+
+fn main() {
+    roundtrip(Wrapper(7u8, PhantomData), &[7]);
+    roundtrip(Handle::<Marker>(2, PhantomData), &[2]);
+    roundtrip(Owned::<Marker>::strict_dumb(), &[0]);
+    roundtrip(Cloned(0x0102u16), &[2, 1]);
+}
@@ -0,0 +1,64 @@
+// compile-flags: --edition 2021
+// aux-build:protocol.rs
+
+//! `#[non_exhaustive]` types deriving strict encoding are used from a crate
+//! other than the one defining them.
+
+extern crate protocol;
+extern crate strict_encoding;
+
+use protocol::{Command, Header, Message, Nonce};
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDumb, StrictEncode, StrictReader, StrictWriter,
+};
+
+fn encode(value: &impl StrictEncode) -> Vec<u8> {
+    let writer = StrictWriter::in_memory::<256>();
+    value.strict_encode(writer).unwrap().unbox().unconfine()
+}
+
+fn decode<T: StrictDecode>(data: &[u8]) -> Result<T, DecodeError> {
+    T::strict_decode(&mut StrictReader::in_memory::<256>(data))
+}
+
+fn roundtrip<T: StrictEncode + StrictDecode + PartialEq + std::fmt::Debug>(value: T, data: &[u8]) {
+    assert_eq!(encode(&value), data);
+    assert_eq!(decode::<T>(data).unwrap(), value);
+}
+
+fn main() {
+    roundtrip(Header::new(2, 0x0180), &[2, 0x80, 0x01]);
+    roundtrip(Nonce::new(0x0403_0201), &[1, 2, 3, 4]);
+    roundtrip(Command::Close, &[3]);
+    roundtrip(Message::Hello(Header::new(1, 0)), &[0, 1, 0, 0]);
+    roundtrip(Message::data(Nonce::new(7), Command::Pong), &[1, 7, 0, 0, 0, 2]);
+    roundtrip(Message::Bye, &[2]);
+
+    assert_eq!(Header::strict_dumb(), Header::new(0, 0));
+    assert_eq!(Nonce::strict_dumb(), Nonce::new(0));
+    assert_eq!(Command::strict_dumb(), Command::Ping);
+    assert_eq!(Message::strict_dumb(), Message::Hello(Header::new(0, 0)));
+
+    // the attribute changes API semantics, not wire semantics: tags beyond the
+    // declared variants are still rejected
+    assert!(matches!(
+        decode::<Command>(&[4]),
+        Err(DecodeError::EnumTagNotKnown { tag: 4, .. })
+    ));
+    assert!(matches!(
+        decode::<Command>(&[0]),
+        Err(DecodeError::EnumTagNotKnown { tag: 0, .. })
+    ));
+    assert!(matches!(
+        decode::<Message>(&[3]),
+        Err(DecodeError::UnionTagNotKnown { tag: 3, .. })
+    ));
+
+    // matching the foreign types requires wildcards
+    match decode::<Message>(&[1, 7, 0, 0, 0, 1]).unwrap() {
+        Message::Data { nonce, command: Command::Ping, .. } => assert_eq!(nonce, Nonce::new(7)),
+        _ => unreachable!(),
+    }
+    let Header { version, .. } = Header::new(3, 0);
+    assert_eq!(version, 3);
+}
@@ -81,7 +81,7 @@ fn variants() -> common::Result {
     #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
     enum Enum {
         #[default]
-        MustCamelize,
+        MustCamelize = 0,
     }
 
     #[allow(unused_braces)]
@@ -152,13 +152,59 @@ fn rename_variant() -> common::Result {
     #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
     enum Enum {
         #[default]
-        MustCamelize,
+        MustCamelize = 0,
 
         #[strict_type(rename = "correctName")]
-        WrongName,
+        WrongName = 1,
     }
 
     assert_eq!(Enum::ALL_VARIANTS, &[(0, "mustCamelize"), (1, "correctName")]);
 
     Ok(())
 }
+
+#[test]
+fn tag_strategies() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType)]
+    #[strict_type(lib = TEST_LIB, tags = order, dumb = Order::Third)]
+    enum Order {
+        Second(u8),
+        First(u16),
+        Third,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType)]
+    #[strict_type(lib = TEST_LIB, tags = repr, into_u8, try_from_u8)]
+    #[repr(u8)]
+    enum Repr {
+        #[default]
+        One = 1,
+        Two,
+        Five = 5,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType)]
+    #[strict_type(lib = TEST_LIB, tags = custom, dumb = Custom::Zero)]
+    enum Custom {
+        #[strict_type(tag = 7)]
+        Seven(u8),
+        #[strict_type(tag = 0)]
+        Zero,
+    }
+
+    assert_eq!(Order::ALL_VARIANTS, &[(0, "second"), (1, "first"), (2, "third")]);
+    assert_eq!(Order::Second(0).variant_ord(), 0);
+    assert_eq!(Order::First(0).variant_ord(), 1);
+
+    assert_eq!(Repr::ALL_VARIANTS, &[(1, "one"), (2, "two"), (5, "five")]);
+    assert_eq!(u8::from(Repr::Two), 2);
+    assert!(Repr::try_from(3).is_err());
+
+    assert_eq!(Custom::ALL_VARIANTS, &[(7, "seven"), (0, "zero")]);
+    assert_eq!(Custom::Seven(0).variant_ord(), 7);
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shim crate checking that strict encoding works without `std`.
+//!
+//! The crate itself is `no_std` (except for the test harness) and depends on
+//! strict encoding with `alloc` feature only, such that all the code below is
+//! compiled against the `no_std` version of the library.
+//!
+//! The crate is checked for a bare-metal target with
+//! `cargo check --target thumbv7em-none-eabihf`. Note that `amplify` 4.x
+//! depends on `ascii` crate with its default `std` feature, which fails to
+//! build for such targets; the check requires `ascii` to be patched with a
+//! copy which has `alloc` as its only default feature.
+
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use amplify::confinement::{TinyOrdMap, TinyString, TinyVec};
+use strict_encoding::{
+    DecodeError, DeserializeError, StrictDecode, StrictDeserialize, StrictDumb, StrictEncode,
+    StrictReader, StrictSerialize, StrictType, StrictWriter,
+};
+
+pub const LIB_NAME_NO_STD: &str = "NoStd";
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_NO_STD, tags = repr, into_u8, try_from_u8)]
+#[repr(u8)]
+pub enum Network {
+    #[default]
+    Mainnet = 0,
+    Testnet = 1,
+    Regtest = 0x80,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_NO_STD, tags = custom)]
+pub enum Command {
+    #[strict_type(tag = 0, dumb)]
+    Ping,
+    #[strict_type(tag = 1)]
+    Sign { key: u32, message: TinyVec<u8> },
+    #[strict_type(tag = 2)]
+    Label(TinyString),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_NO_STD)]
+pub struct Request {
+    pub network: Network,
+    pub nonce: [u8; 8],
+    pub timeout: Option<Duration>,
+    pub commands: TinyVec<Command>,
+    pub meta: TinyOrdMap<u8, TinyString>,
+}
+
+impl StrictSerialize for Request {}
+impl StrictDeserialize for Request {}
+
+impl Request {
+    pub fn sample() -> Self {
+        let mut meta = BTreeMap::new();
+        meta.insert(1, TinyString::try_from(String::from("signer")).unwrap());
+        Request {
+            network: Network::Regtest,
+            nonce: [0xA5; 8],
+            timeout: Some(Duration::new(30, 500)),
+            commands: TinyVec::try_from(vec![
+                Command::Ping,
+                Command::Sign {
+                    key: 7,
+                    message: TinyVec::try_from(vec![0xDE, 0xAD, 0xBE, 0xEF]).unwrap(),
+                },
+                Command::Label(TinyString::try_from(String::from("tx")).unwrap()),
+            ])
+            .unwrap(),
+            meta: TinyOrdMap::try_from(meta).unwrap(),
+        }
+    }
+}
+
+/// Encodes the value into a vector of bytes not exceeding `u16::MAX` length.
+pub fn encode(value: &impl StrictEncode) -> Vec<u8> {
+    value
+        .strict_encode(StrictWriter::in_memory::<{ u16::MAX as usize }>())
+        .expect("in-memory encoding")
+        .unbox()
+        .unconfine()
+}
+
+/// Decodes the value from the slice of bytes, not checking that all the bytes
+/// are consumed.
+pub fn decode<T: StrictDecode>(data: &[u8]) -> Result<T, DecodeError> {
+    T::strict_decode(&mut StrictReader::in_memory::<{ u16::MAX as usize }>(data))
+}
+
+/// Serializes and deserializes the request back, checking that all the data
+/// are consumed.
+pub fn roundtrip(request: &Request) -> Result<Request, DeserializeError> {
+    let data = request
+        .to_strict_serialized::<{ u16::MAX as usize }>()
+        .expect("request doesn't exceed the limit");
+    Request::from_strict_serialized::<{ u16::MAX as usize }>(data)
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::io;
+
+    use super::*;
+
+    #[test]
+    fn enum_tags() {
+        assert_eq!(encode(&Network::Regtest), [0x80]);
+        assert_eq!(decode::<Network>(&[1]).unwrap(), Network::Testnet);
+        assert!(matches!(
+            decode::<Network>(&[2]),
+            Err(DecodeError::EnumTagNotKnown { tag: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn union_variants() {
+        let cmd = Command::Sign {
+            key: 7,
+            message: TinyVec::try_from(vec![1, 2]).unwrap(),
+        };
+        let data = encode(&cmd);
+        assert_eq!(data, [1, 7, 0, 0, 0, 2, 1, 2]);
+        assert_eq!(decode::<Command>(&data).unwrap(), cmd);
+        assert!(matches!(
+            decode::<Command>(&[3]),
+            Err(DecodeError::UnionTagNotKnown { tag: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn request_roundtrip() {
+        let request = Request::sample();
+        assert_eq!(roundtrip(&request).unwrap(), request);
+        assert_eq!(roundtrip(&Request::default()).unwrap(), Request::default());
+    }
+
+    #[test]
+    fn request_bytes() {
+        let data = encode(&Request::sample());
+        assert_eq!(&data[..12], &[0x80, 0xA5, 0xA5, 0xA5, 0xA5, 0xA5, 0xA5, 0xA5, 0xA5, 1, 30, 0]);
+        assert_eq!(
+            Request::from_strict_slice::<{ u16::MAX as usize }>(&data).unwrap(),
+            Request::sample()
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let data = encode(&Request::sample());
+        for len in 0..data.len() {
+            let err = decode::<Request>(&data[..len]).unwrap_err();
+            let DecodeError::Io(err) = err else {
+                panic!("unexpected error {err:?}");
+            };
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+}
@@ -0,0 +1,555 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of arbitrary values of strict types for fuzzing, available with
+//! `arbitrary` feature.
+//!
+//! Values produced by [`StrictArbitrary`] always satisfy the invariants of
+//! their types: confined strings and collections stay within their length
+//! bounds, restricted strings use only the characters of their alphabets, and
+//! sets and maps never repeat their keys. Thus for any generated value `x` the
+//! encoding is canonical, i.e. `encode(decode(encode(x))) == encode(x)`.
+//!
+//! [`Arbitrary`] can't be implemented for the confined collections, since both
+//! the trait and the collections are defined in other crates, so the
+//! generation goes through [`StrictArbitrary`]. The types defined in this
+//! crate implement both traits; a strict type gets both implementations from
+//! `#[derive(StrictType)]` when it is marked with `#[strict_type(arbitrary)]`
+//! attribute.
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+    NonZeroU32, NonZeroU64, NonZeroU8,
+};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+pub use ::arbitrary::{Arbitrary, Error, Result, Unstructured};
+use amplify::ascii::{AsciiChar, AsciiString};
+use amplify::confinement::Confined;
+#[cfg(feature = "float")]
+use amplify::num::apfloat::{ieee, Float};
+use amplify::num::{
+    i1024, i256, i512, u1, u1024, u2, u24, u256, u3, u4, u40, u48, u5, u512, u56, u6, u7,
+};
+
+use crate::stl::{
+    Alpha, AlphaCaps, AlphaCapsLodash, AlphaCapsNum, AlphaLodash, AlphaNum, AlphaNumDash,
+    AlphaNumLodash, AlphaSmall, AlphaSmallLodash, AlphaSmallNum, AsciiPrintable, AsciiSym, Base58,
+    Dec, HexDecCaps, HexDecSmall,
+};
+use crate::{
+    i24, i40, i48, i56, Bool, Byte, ByteArray, FieldName, Ident, LibName, RString,
+    RestrictedCharSet, StrictSum, TypeName, VariantName, U1, U2, U3, U4, U5, U6, U7,
+};
+
+/// Generation of arbitrary values which are valid strict types.
+pub trait StrictArbitrary: Sized {
+    /// Constructs a value of the type from the raw data. The value must
+    /// satisfy all the invariants checked during the strict decoding.
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self>;
+}
+
+/// Picks the length of a collection with items of type `T`, limited both by
+/// the amount of the remaining data and by the `MIN_LEN..=MAX_LEN` bounds.
+fn arbitrary_len<T, const MIN_LEN: usize, const MAX_LEN: usize>(
+    u: &mut Unstructured,
+) -> Result<usize> {
+    let len = u.arbitrary_len::<u8>()? / mem::size_of::<T>().max(1);
+    Ok(len.clamp(MIN_LEN, MAX_LEN))
+}
+
+/// Characters of the restricted character set.
+fn alphabet<C: RestrictedCharSet>() -> Vec<u8> {
+    (0..0x80).filter(|byte| C::try_from(*byte).is_ok()).collect()
+}
+
+// Every value produced by `Arbitrary` implementation of these types is valid
+macro_rules! arbitrary_any {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> { u.arbitrary() }
+        } )+
+    };
+}
+
+macro_rules! arbitrary_num {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                u.arbitrary().map(Self::from_le_bytes)
+            }
+        } )+
+    };
+}
+
+#[cfg(feature = "float")]
+macro_rules! arbitrary_float {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                const BYTES: usize = <$ty>::BITS / 8;
+                let mut bits = [0u8; 32];
+                u.fill_buffer(&mut bits[..BYTES])?;
+                Ok(Self::from_bits(u256::from_le_bytes(bits)))
+            }
+        } )+
+    };
+}
+
+macro_rules! arbitrary_enum {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                let (tag, _) = u.choose(Self::ALL_VARIANTS)?;
+                Ok(Self::try_from(*tag).expect("variant tag"))
+            }
+        } )+
+        impl_arbitrary!($($ty),+);
+    };
+}
+
+macro_rules! arbitrary_uint {
+    ($($ty:ty => $enum:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                <$enum>::strict_arbitrary(u).map(|val| <$ty>::with(val as u8))
+            }
+        } )+
+    };
+}
+
+macro_rules! arbitrary_ident {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictArbitrary for $ty {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                RString::strict_arbitrary(u).map(Self::from)
+            }
+        } )+
+        impl_arbitrary!($($ty),+);
+    };
+}
+
+// Implements `Arbitrary` for the types of this crate
+macro_rules! impl_arbitrary {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl<'a> Arbitrary<'a> for $ty {
+            fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> { Self::strict_arbitrary(u) }
+        } )+
+    };
+}
+
+macro_rules! arbitrary_tuple {
+    ($($ty:ident),+) => {
+        impl<$($ty: StrictArbitrary),+> StrictArbitrary for ($($ty,)+) {
+            fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+                Ok(($($ty::strict_arbitrary(u)?,)+))
+            }
+        }
+    };
+}
+
+arbitrary_any!((), bool, char, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+arbitrary_any!(NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128);
+arbitrary_any!(NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128);
+arbitrary_num!(u24, u40, u48, u56, u256, u512, u1024, i24, i40, i48, i56, i256, i512, i1024);
+#[cfg(feature = "float")]
+arbitrary_float!(
+    ieee::Half,
+    ieee::Single,
+    ieee::Double,
+    ieee::X87DoubleExtended,
+    ieee::Quad,
+    ieee::Oct
+);
+
+#[cfg(feature = "float")]
+impl StrictArbitrary for half::f16 {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> { u.arbitrary().map(Self::from_bits) }
+}
+#[cfg(feature = "float")]
+impl StrictArbitrary for half::bf16 {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> { u.arbitrary().map(Self::from_bits) }
+}
+
+arbitrary_enum!(Bool, U1, U2, U3, U4, U5, U6, U7, AsciiSym, AsciiPrintable);
+arbitrary_enum!(Alpha, AlphaCaps, AlphaCapsLodash, AlphaCapsNum, AlphaLodash, AlphaNum);
+arbitrary_enum!(AlphaNumDash, AlphaNumLodash, AlphaSmall, AlphaSmallLodash, AlphaSmallNum);
+arbitrary_enum!(Base58, Dec, HexDecCaps, HexDecSmall);
+arbitrary_uint!(u1 => U1, u2 => U2, u3 => U3, u4 => U4, u5 => U5, u6 => U6, u7 => U7);
+arbitrary_ident!(Ident, TypeName, FieldName, VariantName, LibName);
+
+arbitrary_tuple!(A, B);
+arbitrary_tuple!(A, B, C);
+arbitrary_tuple!(A, B, C, D);
+arbitrary_tuple!(A, B, C, D, E);
+arbitrary_tuple!(A, B, C, D, E, F);
+arbitrary_tuple!(A, B, C, D, E, F, G);
+arbitrary_tuple!(A, B, C, D, E, F, G, H);
+arbitrary_tuple!(A, B, C, D, E, F, G, H, I);
+arbitrary_tuple!(A, B, C, D, E, F, G, H, I, J);
+arbitrary_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+arbitrary_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+impl StrictArbitrary for Byte {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> { u8::arbitrary(u).map(Self::from) }
+}
+impl_arbitrary!(Byte);
+
+impl<const N: usize> StrictArbitrary for ByteArray<N> {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        u.arbitrary().map(Self::from_byte_array)
+    }
+}
+impl<'a, const N: usize> Arbitrary<'a> for ByteArray<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> { Self::strict_arbitrary(u) }
+}
+
+#[cfg(feature = "bytes")]
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for crate::SharedBlob<MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        Confined::<Vec<u8>, MIN_LEN, MAX_LEN>::strict_arbitrary(u).map(Self::from)
+    }
+}
+#[cfg(feature = "bytes")]
+impl<'a, const MIN_LEN: usize, const MAX_LEN: usize> Arbitrary<'a>
+    for crate::SharedBlob<MIN_LEN, MAX_LEN>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> { Self::strict_arbitrary(u) }
+}
+
+impl<C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: usize>
+    StrictArbitrary for RString<C1, C, MIN, MAX>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<u8, MIN, MAX>(u)?;
+        let mut s = Vec::with_capacity(len);
+        if len > 0 {
+            s.push(*u.choose(&alphabet::<C1>())?);
+            let rest = alphabet::<C>();
+            for _ in 1..len {
+                s.push(*u.choose(&rest)?);
+            }
+        }
+        Ok(Self::try_from(s).expect("characters and length of the string are valid"))
+    }
+}
+impl<'a, C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: usize>
+    Arbitrary<'a> for RString<C1, C, MIN, MAX>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> { Self::strict_arbitrary(u) }
+}
+
+impl<T: StrictArbitrary> StrictArbitrary for Option<T> {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        Ok(if u.arbitrary()? { Some(T::strict_arbitrary(u)?) } else { None })
+    }
+}
+
+impl<T: StrictArbitrary> StrictArbitrary for Box<T> {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        T::strict_arbitrary(u).map(Box::new)
+    }
+}
+
+impl<T: StrictArbitrary, const LEN: usize> StrictArbitrary for [T; LEN] {
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let mut items = Vec::with_capacity(LEN);
+        for _ in 0..LEN {
+            items.push(T::strict_arbitrary(u)?);
+        }
+        Ok(items.try_into().unwrap_or_else(|_| unreachable!("array of {LEN} items")))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<String, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        // The length of a string is measured in bytes
+        let len = arbitrary_len::<u8, MIN_LEN, MAX_LEN>(u)?;
+        let mut s = String::with_capacity(len);
+        while s.len() < len {
+            let ch = char::arbitrary(u)?;
+            if s.len() + ch.len_utf8() <= len {
+                s.push(ch);
+            } else {
+                s.push(char::from(u8::arbitrary(u)? & 0x7F));
+            }
+        }
+        Ok(Confined::try_from(s).expect("length of the string is within the bounds"))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<AsciiString, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<u8, MIN_LEN, MAX_LEN>(u)?;
+        let mut s = AsciiString::with_capacity(len);
+        for _ in 0..len {
+            s.push(AsciiChar::from_ascii(u8::arbitrary(u)? & 0x7F).expect("ASCII character"));
+        }
+        Ok(Confined::try_from(s).expect("length of the string is within the bounds"))
+    }
+}
+
+impl<T: StrictArbitrary, const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<Vec<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<T, MIN_LEN, MAX_LEN>(u)?;
+        let mut col = Vec::with_capacity(len);
+        for _ in 0..len {
+            col.push(T::strict_arbitrary(u)?);
+        }
+        Ok(Confined::try_from(col).expect("length of the collection is within the bounds"))
+    }
+}
+
+impl<T: StrictArbitrary, const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        Confined::<Vec<T>, MIN_LEN, MAX_LEN>::strict_arbitrary(u).map(|col| {
+            Confined::try_from(VecDeque::from(col.release()))
+                .expect("length of the collection is within the bounds")
+        })
+    }
+}
+
+// Sets and maps drop the repeated items, so the collection may end up being
+// shorter than its required minimum length; in this case the data are rejected.
+
+impl<T: StrictArbitrary + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<T, MIN_LEN, MAX_LEN>(u)?;
+        let mut col = BTreeSet::new();
+        for _ in 0..len {
+            col.insert(T::strict_arbitrary(u)?);
+        }
+        Confined::try_from(col).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<
+        K: StrictArbitrary + Ord + Hash,
+        V: StrictArbitrary,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictArbitrary for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<(K, V), MIN_LEN, MAX_LEN>(u)?;
+        let mut col = BTreeMap::new();
+        for _ in 0..len {
+            col.insert(K::strict_arbitrary(u)?, V::strict_arbitrary(u)?);
+        }
+        Confined::try_from(col).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: StrictArbitrary + Eq + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictArbitrary
+    for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<T, MIN_LEN, MAX_LEN>(u)?;
+        let mut col = HashSet::new();
+        for _ in 0..len {
+            col.insert(T::strict_arbitrary(u)?);
+        }
+        Confined::try_from(col).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<
+        K: StrictArbitrary + Eq + Hash,
+        V: StrictArbitrary,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictArbitrary for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn strict_arbitrary(u: &mut Unstructured) -> Result<Self> {
+        let len = arbitrary_len::<(K, V), MIN_LEN, MAX_LEN>(u)?;
+        let mut col = HashMap::new();
+        for _ in 0..len {
+            col.insert(K::strict_arbitrary(u)?, V::strict_arbitrary(u)?);
+        }
+        Confined::try_from(col).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(dead_code)]
+
+    use amplify::confinement::{TinyAscii, TinyOrdMap, TinyOrdSet, TinyString, TinyVec};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::{Arbitrary, *};
+    use crate::{StrictDecode, StrictDumb, StrictEncode, StrictReader, StrictType, StrictWriter};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", arbitrary, crate = crate)]
+    struct Record {
+        name: TinyString,
+        code: RString<AlphaCaps, AlphaCapsNum, 2, 4>,
+        value: Option<u24>,
+        flags: TinyVec<Bool>,
+        index: TinyOrdMap<u8, FieldName>,
+        #[strict_type(skip)]
+        cache: u64,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, arbitrary, crate = crate)]
+    enum Message {
+        #[strict_type(dumb)]
+        Ping,
+        Data(u16, TinyVec<u8>),
+        Text {
+            body: TinyAscii,
+            lang: Option<RString<AlphaSmall, AlphaSmall, 2, 2>>,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", arbitrary, crate = crate)]
+    struct Tree {
+        value: u8,
+        children: TinyVec<Tree>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", arbitrary, crate = crate)]
+    struct Wrapper<T: StrictDumb + StrictEncode + StrictDecode>(T, TinyOrdSet<T>)
+    where T: Ord + Hash;
+
+    fn encode<T: StrictEncode>(val: &T) -> Vec<u8> {
+        let writer = val.strict_encode(StrictWriter::in_memory::<{ usize::MAX }>()).unwrap();
+        writer.unbox().unconfine()
+    }
+
+    /// Generates a value from the data, if possible, and checks that it is
+    /// decodable and is encoded canonically.
+    fn check<T: StrictArbitrary + StrictEncode + StrictDecode>(data: &[u8]) {
+        let Ok(val) = T::strict_arbitrary(&mut Unstructured::new(data)) else {
+            return;
+        };
+        let encoded = encode(&val);
+        // Generated recursive values may nest deeper than the default limit
+        let mut reader =
+            StrictReader::from_slice::<{ usize::MAX }>(&encoded).with_depth_limit(usize::MAX);
+        let decoded = T::strict_decode(&mut reader).unwrap_or_else(|err| {
+            panic!("value of {} is not decodable: {err}", core::any::type_name::<T>())
+        });
+        assert!(reader.remaining().is_empty());
+        assert_eq!(encode(&decoded), encoded, "{} is not canonical", core::any::type_name::<T>());
+    }
+
+    macro_rules! check {
+        ($data:ident; $($ty:ty),+ $(,)?) => { $( check::<$ty>(&$data); )+ };
+    }
+
+    proptest! {
+        #[test]
+        fn canonical(data in vec(any::<u8>(), 0..256)) {
+            check!(data; (), bool, char, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+            check!(data; u24, u40, u48, u56, u256, u512, u1024, i256, i512, i1024);
+            check!(data; i24, i40, i48, i56);
+            check!(data; NonZeroU8, NonZeroU32, NonZeroI64, NonZeroI128, f32, f64);
+            check!(data; u1, u2, u3, u4, u5, u6, u7, Bool, U3, U7);
+            check!(data; AsciiSym, AsciiPrintable, Base58, HexDecSmall, AlphaNumLodash);
+            check!(data; Byte, ByteArray<4>, [u16; 3], Box<i32>, Option<u8>, (u8, i16, char));
+            check!(data; Confined<String, 2, 10>, Confined<AsciiString, 0, 8>);
+            check!(data; Confined<Vec<i64>, 0, 16>, Confined<VecDeque<u8>, 1, 8>);
+            check!(data; Confined<BTreeSet<u16>, 2, 8>, Confined<BTreeMap<u8, Ident>, 0, 8>);
+            check!(data; Confined<HashSet<u32>, 0, 8>, Confined<HashMap<u8, TinyString>, 1, 8>);
+            check!(data; RString<AlphaCaps, AlphaNumDash, 1, 8>, Ident, TypeName, FieldName);
+            check!(data; VariantName, LibName, Record, Message, Tree, Wrapper<u16>);
+        }
+
+        #[test]
+        #[cfg(feature = "float")]
+        fn canonical_float(data in vec(any::<u8>(), 0..256)) {
+            check!(data; half::f16, half::bf16, ieee::Half, ieee::Single, ieee::Double);
+            check!(data; ieee::X87DoubleExtended, ieee::Quad, ieee::Oct);
+        }
+
+        #[test]
+        #[cfg(feature = "bytes")]
+        fn canonical_bytes(data in vec(any::<u8>(), 0..256)) {
+            check!(data; crate::TinySharedBlob, crate::SharedBlob<4, 16>);
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let data = (0..=255u8).cycle().take(4096).collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..64 {
+            let s = RString::<AlphaCaps, Dec, 3, 5>::arbitrary(&mut u).unwrap();
+            assert!((3..=5).contains(&s.len()));
+            assert!(s.as_str().starts_with(|ch: char| ch.is_ascii_uppercase()));
+            assert!(s.as_str()[1..].chars().all(|ch| ch.is_ascii_digit()));
+
+            let s = Confined::<String, 3, 5>::strict_arbitrary(&mut u).unwrap();
+            assert!((3..=5).contains(&s.len()));
+        }
+
+        // not enough data: required minimum lengths are still satisfied
+        let mut u = Unstructured::new(&[]);
+        assert_eq!(Confined::<Vec<u8>, 3, 5>::strict_arbitrary(&mut u).unwrap().len(), 3);
+        assert_eq!(TypeName::strict_arbitrary(&mut u).unwrap().as_str(), "A");
+        // a set of three distinct items can't be generated from two values
+        assert!(Confined::<BTreeSet<Bool>, 3, 5>::strict_arbitrary(&mut u).is_err());
+    }
+
+    #[test]
+    fn derive() {
+        let data = [0xFF; 64];
+        let record = Record::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(record.cache, 0);
+        let variants = (0..=255u8)
+            .map(|byte| Message::arbitrary(&mut Unstructured::new(&[byte])).unwrap())
+            .map(|message| mem::discriminant(&message))
+            .collect::<HashSet<_>>();
+        assert_eq!(variants.len(), 3);
+        assert_eq!(Message::arbitrary(&mut Unstructured::new(&[])).unwrap(), Message::Ping);
+    }
+}
@@ -0,0 +1,398 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ASCII armor for strict-encoded data, which survives copying through email,
+//! forums and other text channels.
+//!
+//! Armored data look like
+//!
+//! ```text
+//! -----BEGIN CONSIGNMENT-----
+//! Version: 2
+//! Checksum: fa1fe775
+//!
+//! BmZvb2JhcgIB
+//! -----END CONSIGNMENT-----
+//! ```
+//!
+//! The `BEGIN` and `END` lines carry the label identifying the kind of the
+//! data, which are followed by optional `Key: Value` headers and the mandatory
+//! `Checksum` header with CRC-32 of the data in hex (see [`Crc32`]). After an
+//! empty line comes the [`Base64`] encoding of the data wrapped at
+//! [`ARMOR_LINE_WIDTH`] columns.
+//!
+//! Parsing ignores surrounding whitespace and accepts both LF and CRLF line
+//! ends.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use crate::reader::decode_slice;
+use crate::text::{Base16, Base64, TextEncoding, TextError};
+use crate::{Checksum, Crc32, DeserializeError, StrictDecode, StrictEncode, StrictWriter};
+
+/// Number of characters in each line of the armored data.
+pub const ARMOR_LINE_WIDTH: usize = 76;
+
+const CHECKSUM_HEADER: &str = "Checksum";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum ArmorError {
+    /// armored data must start with `-----BEGIN <LABEL>-----` line.
+    NoBegin,
+
+    /// armored data must end with `-----END {0}-----` line.
+    NoEnd(String),
+
+    /// armored data are labeled as `{found}` while `{expected}` was expected.
+    LabelMismatch { expected: String, found: String },
+
+    /// armor header line '{0}' is not in `Key: Value` format.
+    InvalidHeader(String),
+
+    /// armored data must have the `Checksum` header.
+    NoChecksum,
+
+    /// armored data checksum {actual} doesn't match the checksum {expected}
+    /// from the header; the data are corrupted.
+    ChecksumMismatch { expected: String, actual: String },
+
+    /// armored data body is invalid: {0}
+    #[from]
+    Body(TextError),
+
+    /// armored data contain {len} bytes, exceeding the limit of {max_len}
+    /// bytes.
+    Oversize { len: usize, max_len: usize },
+
+    #[display(inner)]
+    #[from]
+    Decode(DeserializeError),
+}
+
+impl_error!(ArmorError);
+
+/// Labeled data in the ASCII armor.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Armor {
+    /// Label like `CONSIGNMENT`, which must not contain dashes or line breaks.
+    pub label: String,
+    /// Headers other than the `Checksum`, which is computed from the data.
+    pub headers: Vec<(String, String)>,
+    pub data: Vec<u8>,
+}
+
+fn checksum(data: &[u8]) -> String {
+    let mut crc = Crc32::default();
+    crc.update(data);
+    Base16::encode(&crc.finalize())
+}
+
+impl Armor {
+    pub fn new(label: impl ToString, data: Vec<u8>) -> Self {
+        Armor {
+            label: label.to_string(),
+            headers: empty!(),
+            data,
+        }
+    }
+
+    /// Adds `key: value` header, where `key` must not contain whitespaces or
+    /// colons and `value` must not contain line breaks.
+    pub fn with_header(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the value of the first header with the `key`.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the armor, checking the label against the `expected` one, if
+    /// given, and the length of the body against `max_len` bytes before
+    /// decoding it.
+    fn parse(s: &str, expected: Option<&str>, max_len: usize) -> Result<Self, ArmorError> {
+        let mut lines = s.trim().lines().map(str::trim);
+        let label = lines
+            .next()
+            .and_then(|line| line.strip_prefix("-----BEGIN "))
+            .and_then(|line| line.strip_suffix("-----"))
+            .ok_or(ArmorError::NoBegin)?
+            .to_string();
+        if let Some(expected) = expected.filter(|expected| *expected != label) {
+            return Err(ArmorError::LabelMismatch {
+                expected: expected.to_string(),
+                found: label,
+            });
+        }
+
+        let mut headers = Vec::new();
+        let mut checksum_header = None;
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(ArmorError::InvalidHeader(line.to_string()));
+            };
+            let (key, value) = (key.trim_end(), value.trim_start());
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(ArmorError::InvalidHeader(line.to_string()));
+            }
+            if key == CHECKSUM_HEADER {
+                checksum_header = Some(value.to_string());
+            } else {
+                headers.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        let end = format!("-----END {label}-----");
+        let mut body = String::with_capacity(s.len());
+        let mut complete = false;
+        for line in lines {
+            if line == end {
+                complete = true;
+                break;
+            }
+            body.push_str(line);
+        }
+        if !complete {
+            return Err(ArmorError::NoEnd(label));
+        }
+
+        let expected = checksum_header.ok_or(ArmorError::NoChecksum)?;
+        let len = body.trim_end_matches('=').len() * 3 / 4;
+        if len > max_len {
+            return Err(ArmorError::Oversize { len, max_len });
+        }
+        let data = Base64::decode(&body)?;
+        let actual = checksum(&data);
+        if !actual.eq_ignore_ascii_case(&expected) {
+            return Err(ArmorError::ChecksumMismatch { expected, actual });
+        }
+        Ok(Armor {
+            label,
+            headers,
+            data,
+        })
+    }
+}
+
+impl Display for Armor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-----BEGIN {}-----", self.label)?;
+        for (key, value) in &self.headers {
+            writeln!(f, "{key}: {value}")?;
+        }
+        writeln!(f, "{CHECKSUM_HEADER}: {}", checksum(&self.data))?;
+        writeln!(f)?;
+        let body = Base64::encode(&self.data);
+        // base64 consists of ASCII characters only, so it can be split at any byte
+        for line in body.as_bytes().chunks(ARMOR_LINE_WIDTH) {
+            writeln!(f, "{}", core::str::from_utf8(line).expect("base64 is ASCII"))?;
+        }
+        write!(f, "-----END {}-----", self.label)
+    }
+}
+
+impl FromStr for Armor {
+    type Err = ArmorError;
+
+    /// Parses the armor, checking the label of the `END` line and the
+    /// checksum of the data.
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Armor::parse(s, None, usize::MAX) }
+}
+
+/// Conversion of strict-encodable values into the ASCII [`Armor`] and back.
+pub trait StrictArmor: StrictEncode + StrictDecode {
+    /// Returns the value in the ASCII armor with the `label`.
+    fn to_armored_string(&self, label: &str) -> String {
+        let writer = StrictWriter::in_memory::<{ usize::MAX }>();
+        let data = self
+            .strict_encode(writer)
+            .expect("in-memory strict encoding doesn't fail")
+            .unbox()
+            .unconfine();
+        Armor::new(label, data).to_string()
+    }
+
+    /// Parses the value from the ASCII armor with the `label`, which must not
+    /// contain more than `MAX_LEN` bytes of data.
+    fn from_armored_str<const MAX_LEN: usize>(s: &str, label: &str) -> Result<Self, ArmorError> {
+        let armor = Armor::parse(s, Some(label), MAX_LEN)?;
+        Ok(decode_slice::<Self, MAX_LEN>(&armor.data)?)
+    }
+}
+
+impl<T: StrictEncode + StrictDecode> StrictArmor for T {}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, TinyString};
+
+    use super::*;
+    use crate::StrictType;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        name: TinyString,
+        version: u16,
+    }
+
+    fn header() -> Header {
+        Header {
+            name: TinyString::try_from(s!("foobar")).unwrap(),
+            version: 0x0102,
+        }
+    }
+
+    const ARMORED: &str = "-----BEGIN HEADER-----
+Checksum: fa1fe775
+
+BmZvb2JhcgIB
+-----END HEADER-----";
+
+    fn parse(s: &str) -> Result<Header, ArmorError> {
+        Header::from_armored_str::<1024>(s, "HEADER")
+    }
+
+    #[test]
+    fn roundtrip() {
+        let armored = header().to_armored_string("HEADER");
+        assert_eq!(armored, ARMORED);
+        assert_eq!(parse(&armored).unwrap(), header());
+
+        let crlf = format!("\r\n  {}  \r\n\r\n", ARMORED.replace('\n', " \r\n"));
+        assert_eq!(parse(&crlf).unwrap(), header());
+
+        let armor = Armor::new("HEADER", vec![1, 2, 3]).with_header("Version", 2);
+        let parsed = Armor::from_str(&armor.to_string()).unwrap();
+        assert_eq!(parsed, armor);
+        assert_eq!(parsed.header("Version"), Some("2"));
+        assert_eq!(parsed.header("Checksum"), None);
+    }
+
+    #[test]
+    fn large() {
+        type Blob = Confined<Vec<u8>, 0, { u32::MAX as usize }>;
+
+        let data = (0..300_000u32).map(|i| (i * 7 + i / 256) as u8).collect::<Vec<_>>();
+        let blob = Blob::try_from(data).unwrap();
+        let armored = blob.to_armored_string("BLOB");
+        let lines = armored.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "-----BEGIN BLOB-----");
+        assert_eq!(lines[2], "");
+        assert_eq!(lines.last(), Some(&"-----END BLOB-----"));
+        let body = &lines[3..lines.len() - 1];
+        assert_eq!(body.len(), (300_004usize * 4 / 3).div_ceil(ARMOR_LINE_WIDTH));
+        assert!(body[..body.len() - 1].iter().all(|line| line.len() == ARMOR_LINE_WIDTH));
+        assert_eq!(Blob::from_armored_str::<400_000>(&armored, "BLOB").unwrap(), blob);
+        assert_eq!(
+            Blob::from_armored_str::<300_000>(&armored, "BLOB").unwrap_err(),
+            ArmorError::Oversize {
+                len: 300_004,
+                max_len: 300_000
+            }
+        );
+        // the limit is checked before the body is decoded
+        let pos = armored.find("\n\n").unwrap() + 2;
+        let corrupted = format!("{}-{}", &armored[..pos], &armored[pos + 1..]);
+        assert_eq!(
+            Blob::from_armored_str::<300_000>(&corrupted, "BLOB").unwrap_err(),
+            ArmorError::Oversize {
+                len: 300_004,
+                max_len: 300_000
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Header::from_armored_str::<1024>(ARMORED, "CONFIG").unwrap_err(),
+            ArmorError::LabelMismatch {
+                expected: s!("CONFIG"),
+                found: s!("HEADER")
+            }
+        );
+        assert_eq!(parse(&ARMORED[1..]).unwrap_err(), ArmorError::NoBegin);
+        assert_eq!(
+            parse(&ARMORED.replace("END HEADER", "END CONFIG")).unwrap_err(),
+            ArmorError::NoEnd(s!("HEADER"))
+        );
+        assert_eq!(
+            parse(&ARMORED[..ARMORED.len() - 1]).unwrap_err(),
+            ArmorError::NoEnd(s!("HEADER"))
+        );
+        assert_eq!(
+            parse(&ARMORED.replace("Checksum: fa1fe775\n", "")).unwrap_err(),
+            ArmorError::NoChecksum
+        );
+        assert_eq!(
+            parse(&ARMORED.replace("Checksum", "Check sum")).unwrap_err(),
+            ArmorError::InvalidHeader(s!("Check sum: fa1fe775"))
+        );
+        assert_eq!(
+            parse(&ARMORED.replace("Checksum:", "Checksum")).unwrap_err(),
+            ArmorError::InvalidHeader(s!("Checksum fa1fe775"))
+        );
+
+        // corrupted body
+        let err = parse(&ARMORED.replace("BmZvb2Jh", "BmZvb2Ji")).unwrap_err();
+        assert_eq!(err, ArmorError::ChecksumMismatch {
+            expected: s!("fa1fe775"),
+            actual: s!("14b05267")
+        });
+        assert_eq!(
+            err.to_string(),
+            "armored data checksum 14b05267 doesn't match the checksum fa1fe775 from the header; \
+             the data are corrupted."
+        );
+        assert!(matches!(
+            parse(&ARMORED.replace("BmZv", "Bm-v")).unwrap_err(),
+            ArmorError::Body(TextError::InvalidChar {
+                ch: '-',
+                pos: 2,
+                ..
+            })
+        ));
+
+        // valid armor with invalid strict encoding
+        let armor = Armor::new("HEADER", vec![7, b'f']).to_string();
+        assert!(matches!(
+            parse(&armor).unwrap_err(),
+            ArmorError::Decode(DeserializeError::Decode(_))
+        ));
+        let mut data = crate::test::encode(&header());
+        data.push(0);
+        let armor = Armor::new("HEADER", data).to_string();
+        assert_eq!(
+            parse(&armor).unwrap_err(),
+            ArmorError::Decode(DeserializeError::DataNotEntirelyConsumed(1))
+        );
+    }
+}
@@ -0,0 +1,434 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding and decoding over tokio [`AsyncRead`] and [`AsyncWrite`].
+//!
+//! Values are encoded and decoded by exactly the same code as in the blocking
+//! case, thus producing identical bytes and the same errors. The confinement
+//! limit of the async reader and writer applies to each of the values
+//! separately, rather than to the whole stream, which is usually the desired
+//! behaviour for long-living connections.
+//!
+//! # Cancellation safety
+//!
+//! [`AsyncStrictReader::decode`] is cancellation safe: the data read from the
+//! underlying reader are kept in the internal buffer of [`AsyncStrictReader`]
+//! and not in the future, so if the future is dropped at any point, no data
+//! are lost and the next call to [`AsyncStrictReader::decode`] resumes
+//! decoding from the start of the same value.
+//!
+//! # Performance
+//!
+//! Strict decoding is not resumable, so each time the buffered data are not
+//! enough to decode a value, [`AsyncStrictReader::decode`] reads more data
+//! and decodes the value again from its start. A new attempt is made only
+//! once all the data requested by the read which failed the previous attempt
+//! are buffered, so a value made of a few large parts read at once, like
+//! strings, is decoded in a few attempts regardless of the pieces in which
+//! its data arrive. In addition, the reader takes all
+//! the data immediately available from the underlying reader, up to the size
+//! of the data already buffered, such that the number of attempts is
+//! logarithmic in the value size when the data are available without
+//! waiting. Only a value made of many small parts, including collections
+//! decoded item by item, whose data arrive slowly in many small pieces
+//! separated by waiting, may be re-decoded after each of the pieces.
+//!
+//! [`AsyncStrictWriter::encode`] is not cancellation safe: the value may be
+//! partially written to the underlying writer when the future is dropped.
+
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::reader::SliceReader;
+use crate::writer::ConfinedWriter;
+use crate::{
+    io, DecodeError, StreamWriter, StrictDecode, StrictEncode, StrictReader, StrictWriter,
+};
+
+/// Minimal number of bytes read ahead from the underlying reader each time the
+/// buffered data are not enough to decode a value.
+const READ_CHUNK: usize = 1024;
+
+/// Reader decoding strict-encoded values from [`AsyncRead`].
+///
+/// The reader buffers the data which are read from the underlying reader but
+/// not yet consumed by the decoded values; they can be inspected with
+/// [`AsyncStrictReader::buffered`].
+#[derive(Debug)]
+pub struct AsyncStrictReader<R: AsyncRead + Unpin> {
+    reader: R,
+    buf: Vec<u8>,
+    limit: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncStrictReader<R> {
+    /// Constructs reader which fails decoding values longer than `MAX` bytes.
+    pub fn new<const MAX: usize>(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![],
+            limit: MAX,
+        }
+    }
+
+    /// Data read from the underlying reader but not consumed yet.
+    pub fn buffered(&self) -> &[u8] { &self.buf }
+
+    /// Releases the underlying reader, together with the data which are read
+    /// from it but not consumed yet.
+    pub fn unconfine(self) -> (R, Vec<u8>) { (self.reader, self.buf) }
+
+    /// Decodes next value from the underlying reader.
+    ///
+    /// The future is cancellation safe: the data read from the underlying
+    /// reader are kept in the reader buffer, so if the future is dropped, the
+    /// next call resumes decoding of the same value without data loss.
+    ///
+    /// When the buffered data are not enough, the value is decoded again from
+    /// its start, but only once the data missed by the failed attempt arrive;
+    /// see the [module documentation](self) for the cost of this.
+    pub async fn decode<T: StrictDecode>(&mut self) -> Result<T, DecodeError> {
+        loop {
+            let mut reader = StrictReader::with(SliceReader::with(self.limit, &self.buf));
+            let res = T::strict_decode(&mut reader);
+            let reader = reader.unbox();
+            let err = match res {
+                Ok(val) => {
+                    let pos = reader.pos();
+                    self.buf.drain(..pos);
+                    return Ok(val);
+                }
+                Err(err) if err.is_eof() => err,
+                Err(err) => return Err(err),
+            };
+            // data beyond the limit fail the decoding anyway
+            let required = reader.required().min(self.limit.saturating_add(1));
+            while self.buf.len() < required {
+                if self.read_ahead().await? == 0 {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Waits for more data from the underlying reader and then reads all the
+    /// data immediately available, up to the size of the data already
+    /// buffered (but at least [`READ_CHUNK`] bytes). Returns the number of
+    /// bytes read, which is zero only at the end of the stream.
+    async fn read_ahead(&mut self) -> io::Result<usize> {
+        let target = READ_CHUNK.max(self.buf.len());
+        self.buf.reserve(target);
+        let mut read = self.reader.read_buf(&mut self.buf).await?;
+        if read == 0 {
+            return Ok(0);
+        }
+        while read < target {
+            let next = poll_fn(|cx| match pin!(self.reader.read_buf(&mut self.buf)).poll(cx) {
+                Poll::Ready(res) => Poll::Ready(Some(res)),
+                Poll::Pending => Poll::Ready(None),
+            });
+            match next.await {
+                Some(Ok(0)) | None => break,
+                Some(res) => read += res?,
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Writer encoding strict-encoded values into [`AsyncWrite`].
+#[derive(Debug)]
+pub struct AsyncStrictWriter<W: AsyncWrite + Unpin> {
+    writer: W,
+    limit: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncStrictWriter<W> {
+    /// Constructs writer which fails encoding values longer than `MAX` bytes.
+    pub fn new<const MAX: usize>(writer: W) -> Self { Self { writer, limit: MAX } }
+
+    /// Releases the underlying writer.
+    pub fn unconfine(self) -> W { self.writer }
+
+    /// Encodes the value and writes it to the underlying writer, returning the
+    /// number of written bytes.
+    ///
+    /// The value is written only if it is entirely encoded without errors. The
+    /// future is not cancellation safe: if it is dropped, the value may be
+    /// partially written.
+    pub async fn encode<T: StrictEncode>(&mut self, value: &T) -> io::Result<usize> {
+        let writer = ConfinedWriter::with(self.limit, vec![]);
+        let writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(writer));
+        let data = value.strict_encode(writer)?.unbox().unconfine().unconfine();
+        self.writer.write_all(&data).await?;
+        Ok(data.len())
+    }
+
+    /// Flushes the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> { self.writer.flush().await }
+}
+
+/// Extension trait for encoding values into [`AsyncStrictWriter`].
+pub trait StrictEncodeAsync: StrictEncode {
+    fn strict_encode_async<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut AsyncStrictWriter<W>,
+    ) -> impl Future<Output = io::Result<usize>>
+    where
+        Self: Sized,
+    {
+        writer.encode(self)
+    }
+}
+
+impl<T: StrictEncode> StrictEncodeAsync for T {}
+
+/// Extension trait for decoding values from [`AsyncStrictReader`].
+pub trait StrictDecodeAsync: StrictDecode {
+    fn strict_decode_async<R: AsyncRead + Unpin>(
+        reader: &mut AsyncStrictReader<R>,
+    ) -> impl Future<Output = Result<Self, DecodeError>> {
+        reader.decode()
+    }
+}
+
+impl<T: StrictDecode> StrictDecodeAsync for T {}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::Context;
+
+    use amplify::confinement::{SmallString, TinyVec};
+    use tokio::io::ReadBuf;
+
+    use super::*;
+    use crate::{ReadTuple, StrictDumb, StrictType, TypedRead};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    #[strict_type(dumb = Message::Ping)]
+    enum Message {
+        Ping,
+        Text(SmallString),
+        Batch { id: u64, items: TinyVec<u16> },
+    }
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::Ping,
+            Message::Text(SmallString::try_from(s!("hello async")).unwrap()),
+            Message::Batch {
+                id: 0xDEADBEEF,
+                items: TinyVec::try_from_iter(0..100u16).unwrap(),
+            },
+        ]
+    }
+
+    /// Reader returning only the data which have arrived so far, and
+    /// [`Poll::Pending`] when all of them are already read.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        arrived: Rc<Cell<usize>>,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let end = self.arrived.get().min(self.data.len());
+            if self.pos == end && end < self.data.len() {
+                return Poll::Pending;
+            }
+            let end = end.min(self.pos + buf.remaining());
+            buf.put_slice(&self.data[self.pos..end]);
+            self.pos = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Reader which returns the data by three bytes per call, never waiting.
+    struct Dribble {
+        data: Vec<u8>,
+        pos: usize,
+    }
+
+    impl AsyncRead for Dribble {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let end = (self.pos + 3).min(self.data.len()).min(self.pos + buf.remaining());
+            buf.put_slice(&self.data[self.pos..end]);
+            self.pos = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    thread_local! {
+        static ATTEMPTS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// String counting the attempts to decode it.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Large(SmallString);
+
+    impl StrictDecode for Large {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            ATTEMPTS.with(|a| a.set(a.get() + 1));
+            reader.read_tuple(|r| Ok(Self(r.read_field()?)))
+        }
+    }
+
+    #[tokio::test]
+    async fn same_as_sync() {
+        let mut writer = AsyncStrictWriter::new::<{ u16::MAX as usize }>(vec![]);
+        let mut sync = vec![];
+        for msg in messages() {
+            let len = msg.strict_encode_async(&mut writer).await.unwrap();
+            let data = crate::test::encode(&msg);
+            assert_eq!(len, data.len());
+            sync.extend(data);
+        }
+        let data = writer.unconfine();
+        assert_eq!(data, sync);
+
+        let mut reader = AsyncStrictReader::new::<{ u16::MAX as usize }>(data.as_slice());
+        for msg in messages() {
+            assert_eq!(Message::strict_decode_async(&mut reader).await.unwrap(), msg);
+        }
+        assert!(reader.buffered().is_empty());
+        let err = reader.decode::<Message>().await.unwrap_err();
+        assert!(matches!(err, DecodeError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof));
+    }
+
+    #[tokio::test]
+    async fn confinement() {
+        let msg = &messages()[2];
+        let len = crate::test::encode(msg).len();
+
+        let mut writer = AsyncStrictWriter::new::<16>(vec![]);
+        let err = writer.encode(msg).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.unconfine().is_empty());
+
+        let mut writer = AsyncStrictWriter::new::<{ u16::MAX as usize }>(vec![]);
+        writer.encode(msg).await.unwrap();
+        let data = writer.unconfine();
+
+        let mut reader = AsyncStrictReader::new::<16>(data.as_slice());
+        let err = reader.decode::<Message>().await.unwrap_err();
+        assert!(matches!(err, DecodeError::Io(err) if err.kind() == io::ErrorKind::InvalidInput));
+
+        // the limit applies to each value and not to the whole stream
+        assert!(len < u8::MAX as usize && len * 3 > u8::MAX as usize);
+        let mut writer = AsyncStrictWriter::new::<{ u8::MAX as usize }>(vec![]);
+        for _ in 0..3 {
+            writer.encode(msg).await.unwrap();
+        }
+        let data = writer.unconfine();
+        let mut reader = AsyncStrictReader::new::<{ u8::MAX as usize }>(data.as_slice());
+        for _ in 0..3 {
+            assert_eq!(&reader.decode::<Message>().await.unwrap(), msg);
+        }
+    }
+
+    #[tokio::test]
+    async fn cancellation() {
+        let msg = &messages()[2];
+        let data = crate::test::encode(msg);
+
+        for polls in 0..=data.len() {
+            let arrived = Rc::new(Cell::new(0));
+            let mut reader = AsyncStrictReader::new::<{ u16::MAX as usize }>(Trickle {
+                data: data.clone(),
+                pos: 0,
+                arrived: arrived.clone(),
+            });
+            {
+                let mut fut = pin!(reader.decode::<Message>());
+                for _ in 0..polls {
+                    let poll = poll_fn(|cx| Poll::Ready(fut.as_mut().poll(cx))).await;
+                    assert!(poll.is_pending());
+                    arrived.set(arrived.get() + 1);
+                }
+                // the future is dropped here
+            }
+            // the first poll doesn't read anything, each next one reads a byte
+            assert_eq!(reader.buffered(), &data[..polls.saturating_sub(1)]);
+            arrived.set(data.len());
+            assert_eq!(&reader.decode::<Message>().await.unwrap(), msg);
+            assert!(reader.buffered().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn large_value() {
+        let value = Large(SmallString::try_from("a".repeat(60_000)).unwrap());
+        let data = crate::test::encode(&value);
+
+        let mut reader = AsyncStrictReader::new::<{ u16::MAX as usize }>(Dribble { data, pos: 0 });
+        ATTEMPTS.with(|a| a.set(0));
+        assert_eq!(reader.decode::<Large>().await.unwrap(), value);
+        assert!(reader.buffered().is_empty());
+        // with no data, with the first 1024 bytes giving the string length and
+        // with the whole string
+        assert_eq!(ATTEMPTS.with(Cell::get), 3);
+    }
+
+    #[tokio::test]
+    async fn large_value_trickle() {
+        let value = Large(SmallString::try_from("a".repeat(20_000)).unwrap());
+        let data = crate::test::encode(&value);
+        let len = data.len();
+
+        let arrived = Rc::new(Cell::new(0));
+        let mut reader = AsyncStrictReader::new::<{ u16::MAX as usize }>(Trickle {
+            data,
+            pos: 0,
+            arrived: arrived.clone(),
+        });
+        ATTEMPTS.with(|a| a.set(0));
+        let mut fut = pin!(reader.decode::<Large>());
+        let decoded = loop {
+            match poll_fn(|cx| Poll::Ready(fut.as_mut().poll(cx))).await {
+                Poll::Ready(res) => break res.unwrap(),
+                Poll::Pending => arrived.set(arrived.get() + 100),
+            }
+        };
+        assert_eq!(decoded, value);
+        // the string is not decoded again after each of the 200 pieces of data
+        assert_eq!(ATTEMPTS.with(Cell::get), 3);
+        assert!(arrived.get() >= len);
+    }
+}
@@ -0,0 +1,263 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handshake of the peers agreeing on the encoding rules before exchanging
+//! data.
+//!
+//! Each peer sends its [`EncodingBanner`], listing the version of the wire
+//! format rules, [`STRICT_ENCODING_VERSION`], and the ids of the type
+//! libraries it expects the data to use, like the semantic ids of their
+//! types. The received banner is compared with the local one by
+//! [`check_compatibility`], which reports all the mismatches at once.
+//!
+//! The banner is encoded as `u16` version followed by the list of up to 255
+//! 32-byte ids, prefixed with a single byte of their count. The order of the
+//! ids is preserved, but doesn't matter for the compatibility.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use amplify::confinement::{self, Confined, U8};
+
+use crate::{
+    ByteArray, StrictDecode, StrictEncode, StrictType, LIB_NAME_STD, STRICT_ENCODING_VERSION,
+};
+
+/// Version of the wire format rules and the type libraries expected by a peer.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, crate = crate)]
+pub struct EncodingBanner {
+    /// Version of the wire format rules; see [`STRICT_ENCODING_VERSION`].
+    pub version: u16,
+    /// Ids of the type libraries expected by the peer.
+    pub lib_ids: Confined<Vec<[u8; 32]>, 0, U8>,
+}
+
+impl Default for EncodingBanner {
+    fn default() -> Self {
+        EncodingBanner {
+            version: STRICT_ENCODING_VERSION,
+            lib_ids: none!(),
+        }
+    }
+}
+
+impl EncodingBanner {
+    /// Constructs the banner of the current [`STRICT_ENCODING_VERSION`] with
+    /// the ids of the expected type libraries, failing if there are more than
+    /// 255 of them.
+    pub fn new(lib_ids: impl IntoIterator<Item = [u8; 32]>) -> Result<Self, confinement::Error> {
+        Ok(EncodingBanner {
+            version: STRICT_ENCODING_VERSION,
+            lib_ids: Confined::try_from_iter(lib_ids)?,
+        })
+    }
+}
+
+/// Encoding versions of the peers, which differ.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("the remote peer uses encoding version {remote}, while the local one uses {local}")]
+pub struct VersionMismatch {
+    /// Version of the local peer.
+    pub local: u16,
+    /// Version received from the remote peer.
+    pub remote: u16,
+}
+
+/// Mismatches of the local and remote [`EncodingBanner`]s found by
+/// [`check_compatibility`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct IncompatibilityReport {
+    /// Versions of the wire format rules, if they differ.
+    pub version: Option<VersionMismatch>,
+    /// Ids of the libraries expected by the local peer, which are not listed
+    /// by the remote one, in the local order.
+    pub missing_libs: Vec<[u8; 32]>,
+    /// Ids of the libraries expected by the remote peer, which are not listed
+    /// by the local one, in the remote order.
+    pub unknown_libs: Vec<[u8; 32]>,
+}
+
+impl IncompatibilityReport {
+    /// Detects whether the banners have no mismatches.
+    pub fn is_empty(&self) -> bool {
+        self.version.is_none() && self.missing_libs.is_empty() && self.unknown_libs.is_empty()
+    }
+}
+
+impl Display for IncompatibilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn ids(f: &mut Formatter<'_>, ids: &[[u8; 32]]) -> fmt::Result {
+            for (index, id) in ids.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", ByteArray::from(*id))?;
+            }
+            Ok(())
+        }
+
+        f.write_str("incompatible encodings")?;
+        let mut sep = ": ";
+        if let Some(version) = self.version {
+            write!(f, "{sep}{version}")?;
+            sep = "; ";
+        }
+        if !self.missing_libs.is_empty() {
+            write!(f, "{sep}the remote peer lacks type libraries ")?;
+            ids(f, &self.missing_libs)?;
+            sep = "; ";
+        }
+        if !self.unknown_libs.is_empty() {
+            write!(f, "{sep}the remote peer expects unknown type libraries ")?;
+            ids(f, &self.unknown_libs)?;
+        }
+        f.write_str(".")
+    }
+}
+
+impl_error!(IncompatibilityReport);
+
+/// Compares the banner of the `local` peer with the one received from the
+/// `remote` peer. The peers are compatible if they use the same version of the
+/// wire format rules and expect the same type libraries, regardless of their
+/// order; otherwise all the mismatches are reported.
+pub fn check_compatibility(
+    local: &EncodingBanner,
+    remote: &EncodingBanner,
+) -> Result<(), IncompatibilityReport> {
+    fn absent(ids: &[[u8; 32]], other: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        let other = other.iter().collect::<BTreeSet<_>>();
+        ids.iter().filter(|id| !other.contains(id)).copied().collect()
+    }
+
+    let report = IncompatibilityReport {
+        version: (local.version != remote.version).then_some(VersionMismatch {
+            local: local.version,
+            remote: remote.version,
+        }),
+        missing_libs: absent(&local.lib_ids, &remote.lib_ids),
+        unknown_libs: absent(&remote.lib_ids, &local.lib_ids),
+    };
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{decode, encode, encoding};
+    use crate::{DecodeError, StrictReader};
+
+    const LIB_A: [u8; 32] = [0xAA; 32];
+    const LIB_B: [u8; 32] = [0xBB; 32];
+    const LIB_C: [u8; 32] = [0xCC; 32];
+
+    fn banner(version: u16, lib_ids: &[[u8; 32]]) -> EncodingBanner {
+        EncodingBanner {
+            version,
+            lib_ids: Confined::try_from(lib_ids.to_vec()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn banner_encoding() {
+        assert_eq!(STRICT_ENCODING_VERSION, 1);
+        assert_eq!(EncodingBanner::default(), EncodingBanner::new([]).unwrap());
+        encoding(&EncodingBanner::default(), [1, 0, 0]);
+
+        let banner = EncodingBanner::new([LIB_A, LIB_B]).unwrap();
+        let mut data = vec![1, 0, 2];
+        data.extend(LIB_A);
+        data.extend(LIB_B);
+        encoding(&banner, &data);
+        assert_eq!(decode::<EncodingBanner>(encode(&banner)), banner);
+
+        assert!(EncodingBanner::new([LIB_A; 256]).is_err());
+        let mut reader = StrictReader::in_memory::<16>([1, 0, 1, 0xAA]);
+        assert!(matches!(EncodingBanner::strict_decode(&mut reader), Err(DecodeError::Io(_))));
+    }
+
+    #[test]
+    fn matching() {
+        let local = EncodingBanner::new([LIB_A, LIB_B]).unwrap();
+        assert_eq!(check_compatibility(&local, &local), Ok(()));
+        assert_eq!(check_compatibility(&local, &banner(1, &[LIB_B, LIB_A])), Ok(()));
+        let empty = EncodingBanner::default();
+        assert_eq!(check_compatibility(&empty, &empty), Ok(()));
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let local = banner(1, &[LIB_A]);
+        let report = check_compatibility(&local, &banner(2, &[LIB_A])).unwrap_err();
+        assert_eq!(report, IncompatibilityReport {
+            version: Some(VersionMismatch {
+                local: 1,
+                remote: 2
+            }),
+            missing_libs: vec![],
+            unknown_libs: vec![],
+        });
+        assert_eq!(
+            report.to_string(),
+            "incompatible encodings: the remote peer uses encoding version 2, while the local one \
+             uses 1."
+        );
+    }
+
+    #[test]
+    fn lib_mismatch() {
+        let local = banner(1, &[LIB_A, LIB_B]);
+        let report = check_compatibility(&local, &banner(1, &[LIB_C, LIB_A])).unwrap_err();
+        assert_eq!(report, IncompatibilityReport {
+            version: None,
+            missing_libs: vec![LIB_B],
+            unknown_libs: vec![LIB_C],
+        });
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "incompatible encodings: the remote peer lacks type libraries {}; the remote peer \
+                 expects unknown type libraries {}.",
+                "bb".repeat(32),
+                "cc".repeat(32)
+            )
+        );
+
+        let report = check_compatibility(&banner(1, &[]), &banner(3, &[LIB_A])).unwrap_err();
+        assert_eq!(
+            report.version,
+            Some(VersionMismatch {
+                local: 1,
+                remote: 3
+            })
+        );
+        assert!(report.missing_libs.is_empty());
+        assert_eq!(report.unknown_libs, vec![LIB_A]);
+        assert!(!report.is_empty());
+    }
+}
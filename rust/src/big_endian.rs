@@ -0,0 +1,151 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integers encoded in big-endian byte order, for the protocols which require
+//! it for some of their fields.
+//!
+//! Strict encoding always writes integers in little-endian byte order;
+//! [`BigEndian`] is encoded as a named newtype of the byte array with the
+//! integer bytes in big-endian order. The name of the newtype is the name of
+//! the integer with `Be` suffix, like `U32Be`, such that the byte order is
+//! visible in the type schema and the type is distinct from the integer.
+
+use alloc::format;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::hash::Hash;
+
+use crate::{
+    io, DecodeError, ReadTuple, StrictDecode, StrictDumb, StrictEncode, StrictProduct, StrictTuple,
+    StrictType, TypeName, TypedRead, TypedWrite, LIB_NAME_STD,
+};
+
+/// Integer types which can be encoded in big-endian byte order with
+/// [`BigEndian`].
+///
+/// The trait is implemented for all the primitive integers from 16 to 128
+/// bits.
+pub trait BigEndianInt: Copy + Ord + Hash + Debug + Display + Default + StrictType {
+    /// Byte array of the size of the integer.
+    type Bytes: StrictEncode + StrictDecode + StrictDumb;
+
+    fn to_be_bytes(self) -> Self::Bytes;
+
+    fn from_be_bytes(bytes: Self::Bytes) -> Self;
+}
+
+macro_rules! impl_big_endian_int {
+    ($($ty:ty),+) => {
+        $( impl BigEndianInt for $ty {
+            type Bytes = [u8; <$ty>::BITS as usize / 8];
+            fn to_be_bytes(self) -> Self::Bytes { <$ty>::to_be_bytes(self) }
+            fn from_be_bytes(bytes: Self::Bytes) -> Self { <$ty>::from_be_bytes(bytes) }
+        }
+
+        impl From<$ty> for BigEndian<$ty> {
+            fn from(value: $ty) -> Self { BigEndian(value) }
+        }
+
+        impl From<BigEndian<$ty>> for $ty {
+            fn from(value: BigEndian<$ty>) -> Self { value.0 }
+        } )+
+    };
+}
+
+impl_big_endian_int!(u16, u32, u64, u128, i16, i32, i64, i128);
+
+/// Integer of type `T` encoded in big-endian byte order.
+///
+/// The value is kept in native byte order; only its strict encoding differs
+/// from the one of `T`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct BigEndian<T: BigEndianInt>(pub T);
+
+impl<T: BigEndianInt> BigEndian<T> {
+    /// Returns the integer value.
+    pub fn get(&self) -> T { self.0 }
+}
+
+impl<T: BigEndianInt> Display for BigEndian<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.0, f) }
+}
+
+impl<T: BigEndianInt> StrictType for BigEndian<T> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> {
+        let int = T::strict_name().expect("primitive integers always have a name");
+        Some(tn!(format!("{int}Be")))
+    }
+}
+impl<T: BigEndianInt> StrictProduct for BigEndian<T> {}
+impl<T: BigEndianInt> StrictTuple for BigEndian<T> {
+    const FIELD_COUNT: u8 = 1;
+}
+impl<T: BigEndianInt> StrictEncode for BigEndian<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_newtype::<Self>(&self.0.to_be_bytes())
+    }
+}
+impl<T: BigEndianInt> StrictDecode for BigEndian<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| r.read_field().map(T::from_be_bytes).map(Self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_strict_layout;
+    use crate::test::{decode, encoding};
+
+    #[test]
+    fn byte_order() {
+        encoding(&BigEndian(0x01020304u32), [0x01, 0x02, 0x03, 0x04]);
+        encoding(&0x01020304u32, [0x04, 0x03, 0x02, 0x01]);
+        encoding(&BigEndian(0x0102u16), [0x01, 0x02]);
+        encoding(&BigEndian(0x0102030405060708u64), [1, 2, 3, 4, 5, 6, 7, 8]);
+        encoding(&BigEndian(-2i16), [0xFF, 0xFE]);
+        encoding(&BigEndian(1u128), {
+            let mut data = [0u8; 16];
+            data[15] = 1;
+            data
+        });
+        assert_eq!(decode::<BigEndian<u32>>([0, 0, 1, 0]), BigEndian(256));
+        assert_eq!(BigEndian::<u64>::strict_dumb(), BigEndian(0));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(BigEndian::<u16>::strict_name(), Some(tn!("U16Be")));
+        assert_eq!(BigEndian::<u32>::strict_name(), Some(tn!("U32Be")));
+        assert_eq!(BigEndian::<i64>::strict_name(), Some(tn!("I64Be")));
+        assert_strict_layout!(BigEndian<u32>, "Std.U32Be: tuple([Byte ^ 4])");
+    }
+
+    #[test]
+    fn conversions() {
+        let be = BigEndian::from(0xCAFEu16);
+        assert_eq!(u16::from(be), 0xCAFE);
+        assert_eq!(be.get(), 0xCAFE);
+        assert_eq!(be.to_string(), "51966");
+        let value: u64 = BigEndian(7u64).into();
+        assert_eq!(value, 7);
+    }
+}
@@ -0,0 +1,317 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bit sets packing eight boolean values into each byte.
+//!
+//! [`ConfinedBitset`] is encoded as the number of its bits, with the length
+//! prefix which width is defined by the maximal number of bits, like for the
+//! collections, followed by `ceil(len / 8)` bytes. Bit `i` is stored in byte
+//! `i / 8` at position `i % 8`, counting from the least significant bit.
+//!
+//! The unused bits of the last byte are padding, which must be zero: decoding
+//! fails with [`DecodeError::NonZeroPadding`] error otherwise, thus each bit
+//! set has a single encoding.
+//!
+//! Bit sets register [`RawEncoding::Bitset`] with their bounds in place of
+//! the layout, since the packing isn't a part of the strict type system.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Formatter};
+
+use amplify::confinement::{self, U16, U24, U32, U8};
+
+use crate::reader::decoded_name;
+use crate::{
+    io, ConfinedDumb, DecodeError, InvalidLength, RawEncoding, ReadRaw, Sizing, StrictDecode,
+    StrictEncode, StrictType, TypeName, TypedRead, TypedWrite, WriteRaw, LIB_NAME_STD,
+};
+
+pub type TinyBitset = ConfinedBitset<0, U8>;
+pub type SmallBitset = ConfinedBitset<0, U16>;
+pub type MediumBitset = ConfinedBitset<0, U24>;
+pub type LargeBitset = ConfinedBitset<0, U32>;
+
+/// Set of bits which number is confined to be between `MIN_LEN` and
+/// `MAX_LEN`, stored and encoded packed eight bits per byte.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConfinedBitset<const MIN_LEN: usize, const MAX_LEN: usize> {
+    len: usize,
+    // padding bits are always kept zero, thus the derived comparisons are
+    // correct
+    bytes: Vec<u8>,
+}
+
+pub(crate) const fn byte_len(len: usize) -> usize { len.div_ceil(8) }
+
+/// Checks that the padding bits of the last of the `bytes` packing `len` bits
+/// are zero, reporting the error for the type named `type_name`.
+pub(crate) fn check_padding(
+    bytes: &[u8],
+    len: usize,
+    type_name: impl FnOnce() -> String,
+) -> Result<(), DecodeError> {
+    if len % 8 != 0 && bytes[len / 8] >> (len % 8) != 0 {
+        return Err(DecodeError::NonZeroPadding {
+            type_name: type_name(),
+        });
+    }
+    Ok(())
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ConfinedBitset<MIN_LEN, MAX_LEN> {
+    /// Constructs the bit set of `len` unset bits.
+    pub fn with_len(len: usize) -> Result<Self, confinement::Error> {
+        Self::check(len)?;
+        Ok(ConfinedBitset {
+            len,
+            bytes: vec![0; byte_len(len)],
+        })
+    }
+
+    fn check(len: usize) -> Result<(), confinement::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(len)
+    }
+
+    /// Number of bits in the set.
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns the bit at `index`, or `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+        Some(self.bytes[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    /// Sets the bit at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "bit index {index} is out of bounds of {} bits", self.len);
+        let mask = 1 << (index % 8);
+        if value {
+            self.bytes[index / 8] |= mask;
+        } else {
+            self.bytes[index / 8] &= !mask;
+        }
+    }
+
+    /// Appends the bit, failing if the set already has `MAX_LEN` bits.
+    pub fn push(&mut self, value: bool) -> Result<(), confinement::Error> {
+        Self::check(self.len + 1)?;
+        if self.len % 8 == 0 {
+            self.bytes.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, value);
+        Ok(())
+    }
+
+    /// Iterates over the bits in their order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = bool> + '_ {
+        (0..self.len).map(|index| self.bytes[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    /// Packed bytes of the set, with zero padding bits in the last byte.
+    pub fn as_bytes(&self) -> &[u8] { &self.bytes }
+}
+
+impl<const MAX_LEN: usize> Default for ConfinedBitset<0, MAX_LEN> {
+    fn default() -> Self {
+        ConfinedBitset {
+            len: 0,
+            bytes: vec![],
+        }
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb for ConfinedBitset<MIN_LEN, MAX_LEN> {
+    fn confined_dumb() -> Self { Self::with_len(MIN_LEN).expect("MIN_LEN is within the bounds") }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<&[bool]>
+    for ConfinedBitset<MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+
+    fn try_from(bits: &[bool]) -> Result<Self, Self::Error> {
+        let mut set = Self::with_len(bits.len())?;
+        for (index, bit) in bits.iter().enumerate() {
+            set.set(index, *bit);
+        }
+        Ok(set)
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<&ConfinedBitset<MIN_LEN, MAX_LEN>>
+    for Vec<bool>
+{
+    fn from(set: &ConfinedBitset<MIN_LEN, MAX_LEN>) -> Self { set.iter().collect() }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Debug for ConfinedBitset<MIN_LEN, MAX_LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ConfinedBitset(")?;
+        for bit in self.iter() {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for ConfinedBitset<MIN_LEN, MAX_LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Bitset")) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode for ConfinedBitset<MIN_LEN, MAX_LEN> {
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len)?;
+        let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        unsafe {
+            let raw = writer.raw_writer();
+            raw.write_raw_len::<MAX_LEN>(self.len)?;
+            raw.write_raw::<MAX_LEN>(&self.bytes)?;
+            Ok(writer.register_raw::<Self>(RawEncoding::Bitset(sizing)))
+        }
+    }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode for ConfinedBitset<MIN_LEN, MAX_LEN> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        reader.alloc(byte_len(len))?;
+        let bytes = unsafe { reader.raw_reader() }.read_raw::<MAX_LEN>(byte_len(len))?;
+        check_padding(&bytes, len, decoded_name::<Self>)?;
+        Ok(ConfinedBitset { len, bytes })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+
+    use super::*;
+    use crate::test::{encode, encoding};
+    use crate::StrictReader;
+
+    fn bits(len: usize) -> Vec<bool> { (0..len).map(|i| i % 3 == 0 || i % 7 == 0).collect() }
+
+    fn check(len: usize) {
+        let set = SmallBitset::try_from(bits(len).as_slice()).unwrap();
+        assert_eq!(set.len(), len);
+        assert_eq!(Vec::from(&set), bits(len));
+
+        let mut data = (len as u16).to_le_bytes().to_vec();
+        data.extend(set.as_bytes());
+        assert_eq!(data.len(), 2 + len.div_ceil(8));
+        encoding(&set, data);
+    }
+
+    #[test]
+    fn roundtrip() {
+        for len in [0, 1, 7, 8, 9, 65535] {
+            check(len);
+        }
+        let set = TinyBitset::try_from(
+            [true, false, false, true, true, false, true, false, true].as_slice(),
+        )
+        .unwrap();
+        encoding(&set, [9, 0b0101_1001, 0b0000_0001]);
+    }
+
+    #[test]
+    fn access() {
+        let mut set = TinyBitset::with_len(10).unwrap();
+        set.set(0, true);
+        set.set(9, true);
+        set.set(3, true);
+        set.set(3, false);
+        assert_eq!(set.get(0), Some(true));
+        assert_eq!(set.get(3), Some(false));
+        assert_eq!(set.get(9), Some(true));
+        assert_eq!(set.get(10), None);
+        assert_eq!(set.iter().filter(|bit| *bit).count(), 2);
+        assert_eq!(set.as_bytes(), [0b0000_0001, 0b0000_0010]);
+
+        set.push(true).unwrap();
+        assert_eq!(set.len(), 11);
+        assert_eq!(set.as_bytes(), [0b0000_0001, 0b0000_0110]);
+        assert_eq!(format!("{set:?}"), "ConfinedBitset(10000000011)");
+    }
+
+    #[test]
+    fn confinement() {
+        assert!(ConfinedBitset::<2, 4>::try_from([true].as_slice()).is_err());
+        assert!(ConfinedBitset::<2, 4>::try_from([true; 5].as_slice()).is_err());
+        let mut set = ConfinedBitset::<2, 4>::confined_dumb();
+        assert_eq!(Vec::from(&set), [false, false]);
+        set.push(true).unwrap();
+        set.push(true).unwrap();
+        assert!(set.push(true).is_err());
+
+        let mut reader = StrictReader::in_memory::<8>([5, 0xFF]);
+        assert!(matches!(
+            ConfinedBitset::<2, 4>::strict_decode(&mut reader),
+            Err(DecodeError::Oversize {
+                declared_len: 5,
+                max_len: 4,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "bit index 8 is out of bounds of 8 bits")]
+    fn set_out_of_bounds() { TinyBitset::with_len(8).unwrap().set(8, true); }
+
+    #[test]
+    fn padding() {
+        for data in [[3, 0b0000_1000], [3, 0b1000_0101], [1, 0b0000_0010]] {
+            let mut reader = StrictReader::in_memory::<8>(data);
+            assert_eq!(
+                TinyBitset::strict_decode(&mut reader).unwrap_err(),
+                DecodeError::NonZeroPadding {
+                    type_name: s!("Bitset"),
+                }
+            );
+        }
+        // all bits of the last byte are significant
+        let mut reader = StrictReader::in_memory::<8>([8, 0xFF]);
+        assert_eq!(
+            TinyBitset::strict_decode(&mut reader).unwrap(),
+            TinyBitset::try_from([true; 8].as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn packed_size() {
+        let flags = bits(50_000);
+        let unpacked = Confined::<Vec<bool>, 0, U16>::try_from(flags.clone()).unwrap();
+        let packed = SmallBitset::try_from(flags.as_slice()).unwrap();
+        assert_eq!(encode(&unpacked).len(), 2 + 50_000);
+        assert_eq!(encode(&packed).len(), 2 + 6_250);
+        assert!(encode(&unpacked).len() > 7 * encode(&packed).len());
+    }
+}
@@ -0,0 +1,362 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of values borrowing their data from the source buffer.
+//!
+//! Decoding from an in-memory slice doesn't require byte arrays and byte
+//! strings to be copied: they may be represented by references into the
+//! source data. [`StrictDecodeBorrowed`] is the decoding trait for such types;
+//! it is implemented for `&'a [u8; LEN]` and [`BlobRef`], and for all types
+//! implementing [`StrictDecode`], which are decoded as usual.
+//!
+//! In the same way, references to slices and strings may be encoded without
+//! copying them into owned collections with [`ConfinedRef`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use amplify::confinement::{self, Confined, U16, U24, U32, U8};
+
+use crate::layout::layout_dumb;
+use crate::reader::{list_name, unicode_name};
+use crate::{
+    io, Byte, DecodeError, DeserializeError, InvalidLength, Sizing, SliceReader, StrictDecode,
+    StrictDumb, StrictEncode, StrictReader, StrictType, TypeName, TypedRead, TypedWrite, WriteRaw,
+    LIB_EMBEDDED,
+};
+
+pub type TinyBlobRef<'a> = BlobRef<'a, 0, U8>;
+pub type SmallBlobRef<'a> = BlobRef<'a, 0, U16>;
+pub type MediumBlobRef<'a> = BlobRef<'a, 0, U24>;
+pub type LargeBlobRef<'a> = BlobRef<'a, 0, U32>;
+
+/// Decodes a value from an in-memory slice, allowing the value to borrow
+/// parts of the source data.
+///
+/// The trait can be derived for structures with
+/// `#[derive(StrictDecodeBorrowed)]`; fields of the types not supporting
+/// borrowing are decoded with [`StrictDecode`].
+pub trait StrictDecodeBorrowed<'a>: Sized {
+    fn strict_decode_borrowed(
+        reader: &mut StrictReader<SliceReader<'a>>,
+    ) -> Result<Self, DecodeError>;
+
+    /// Decodes the value from the `data`, which must be entirely consumed and
+    /// must not exceed `MAX` bytes.
+    fn from_strict_slice_borrowed<const MAX: usize>(
+        data: &'a [u8],
+    ) -> Result<Self, DeserializeError> {
+        let mut reader = StrictReader::from_slice::<MAX>(data);
+        let me = Self::strict_decode_borrowed(&mut reader)?;
+        let left = reader.remaining().len();
+        if left > 0 {
+            return Err(DeserializeError::DataNotEntirelyConsumed(left as u64));
+        }
+        Ok(me)
+    }
+}
+
+impl<'a, T: StrictDecode> StrictDecodeBorrowed<'a> for T {
+    fn strict_decode_borrowed(
+        reader: &mut StrictReader<SliceReader<'a>>,
+    ) -> Result<Self, DecodeError> {
+        T::strict_decode(reader)
+    }
+}
+
+impl<'a, const LEN: usize> StrictDecodeBorrowed<'a> for &'a [u8; LEN] {
+    fn strict_decode_borrowed(
+        reader: &mut StrictReader<SliceReader<'a>>,
+    ) -> Result<Self, DecodeError> {
+        let data = reader.read_borrowed(LEN)?;
+        Ok(data.try_into().expect("slice of LEN bytes"))
+    }
+}
+
+/// Byte string borrowed from the decoded data, which has the same encoding as
+/// `Confined<Vec<u8>, MIN_LEN, MAX_LEN>` and may be used in place of it for
+/// the decoding without copying the data.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct BlobRef<'a, const MIN_LEN: usize, const MAX_LEN: usize>(&'a [u8]);
+
+impl<'a, const MIN_LEN: usize, const MAX_LEN: usize> BlobRef<'a, MIN_LEN, MAX_LEN> {
+    pub fn as_slice(&self) -> &'a [u8] { self.0 }
+
+    /// Copies the data into an owned confined byte string.
+    pub fn to_confined(&self) -> Confined<Vec<u8>, MIN_LEN, MAX_LEN> {
+        Confined::try_from(self.0.to_vec()).expect("blob reference length is already checked")
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Deref for BlobRef<'_, MIN_LEN, MAX_LEN> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target { self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> AsRef<[u8]> for BlobRef<'_, MIN_LEN, MAX_LEN> {
+    fn as_ref(&self) -> &[u8] { self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Borrow<[u8]> for BlobRef<'_, MIN_LEN, MAX_LEN> {
+    fn borrow(&self) -> &[u8] { self.0 }
+}
+
+impl<'a, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeBorrowed<'a>
+    for BlobRef<'a, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode_borrowed(
+        reader: &mut StrictReader<SliceReader<'a>>,
+    ) -> Result<Self, DecodeError> {
+        let len =
+            unsafe { reader.read_len::<Confined<Vec<u8>, MIN_LEN, MAX_LEN>, MIN_LEN, MAX_LEN>()? };
+        reader.read_borrowed(len).map(BlobRef).map_err(DecodeError::from)
+    }
+}
+
+/// Slice or string borrowed by reference, which is encoded in the same way as
+/// the owned `Confined<Vec<T>, MIN_LEN, MAX_LEN>` or `Confined<String,
+/// MIN_LEN, MAX_LEN>`, such that the data don't have to be copied for the
+/// encoding.
+///
+/// The length of the data is checked against the confinement when the
+/// reference is constructed.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ConfinedRef<'a, T: ?Sized, const MIN_LEN: usize, const MAX_LEN: usize>(&'a T);
+
+impl<T: ?Sized, const MIN_LEN: usize, const MAX_LEN: usize> Copy
+    for ConfinedRef<'_, T, MIN_LEN, MAX_LEN>
+{
+}
+impl<T: ?Sized, const MIN_LEN: usize, const MAX_LEN: usize> Clone
+    for ConfinedRef<'_, T, MIN_LEN, MAX_LEN>
+{
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, T: ?Sized, const MIN_LEN: usize, const MAX_LEN: usize>
+    ConfinedRef<'a, T, MIN_LEN, MAX_LEN>
+{
+    pub fn as_inner(&self) -> &'a T { self.0 }
+
+    fn with_len(data: &'a T, len: usize) -> Result<Self, confinement::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(len)?;
+        Ok(ConfinedRef(data))
+    }
+}
+
+impl<T: ?Sized, const MIN_LEN: usize, const MAX_LEN: usize> Deref
+    for ConfinedRef<'_, T, MIN_LEN, MAX_LEN>
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target { self.0 }
+}
+
+impl<'a, T, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<&'a [T]>
+    for ConfinedRef<'a, [T], MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(slice: &'a [T]) -> Result<Self, Self::Error> { Self::with_len(slice, slice.len()) }
+}
+
+impl<'a, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<&'a str>
+    for ConfinedRef<'a, str, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> { Self::with_len(s, s.len()) }
+}
+
+impl<'a, T, const MIN_LEN: usize, const MAX_LEN: usize> From<&'a Confined<Vec<T>, MIN_LEN, MAX_LEN>>
+    for ConfinedRef<'a, [T], MIN_LEN, MAX_LEN>
+{
+    fn from(vec: &'a Confined<Vec<T>, MIN_LEN, MAX_LEN>) -> Self { ConfinedRef(vec.as_slice()) }
+}
+
+impl<'a, const MIN_LEN: usize, const MAX_LEN: usize> From<&'a Confined<String, MIN_LEN, MAX_LEN>>
+    for ConfinedRef<'a, str, MIN_LEN, MAX_LEN>
+{
+    fn from(s: &'a Confined<String, MIN_LEN, MAX_LEN>) -> Self { ConfinedRef(s.as_str()) }
+}
+
+impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedRef<'_, [T], MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(MIN_LEN, MAX_LEN) }
+}
+impl<T: StrictEncode + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for ConfinedRef<'_, [T], MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.0.len())?;
+        writer = unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(self.0.len())?;
+            writer = writer.write_items(self.0)?;
+            if T::strict_name() == u8::strict_name() {
+                writer.register_list(&Byte::strict_dumb(), sizing)
+            } else {
+                writer.register_list(&layout_dumb::<T>(), sizing)
+            }
+        };
+        Ok(writer)
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedRef<'_, str, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { unicode_name(MIN_LEN, MAX_LEN) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for ConfinedRef<'_, str, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.0.len())?;
+        unsafe {
+            writer
+                .register_unicode(Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
+                .write_string::<MAX_LEN>(self.0.as_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{NonEmptyBlob, SmallBlob, SmallVec, TinyBlob, TinyString};
+
+    use super::*;
+    use crate::io;
+    use crate::test::encode;
+
+    #[test]
+    fn blob_ref() {
+        let blob = SmallBlob::try_from(vec![0xCA, 0xFE, 0xBA, 0xBE]).unwrap();
+        let data = encode(&blob);
+        let blob_ref = SmallBlobRef::from_strict_slice_borrowed::<16>(&data).unwrap();
+        assert_eq!(blob_ref.as_slice(), &data[2..]);
+        assert_eq!(blob_ref.as_slice().as_ptr(), data[2..].as_ptr());
+        assert_eq!(blob_ref.to_confined(), blob);
+
+        let data = encode(&TinyBlob::default());
+        assert!(TinyBlobRef::from_strict_slice_borrowed::<16>(&data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn blob_ref_confinement() {
+        let data = encode(&TinyBlob::default());
+        let err = BlobRef::<1, { U8 }>::from_strict_slice_borrowed::<16>(&data).unwrap_err();
+        assert!(matches!(
+            err,
+            DeserializeError::Decode(DecodeError::Undersize {
+                declared_len: 0,
+                min_len: 1,
+                max_len: U8,
+                ..
+            })
+        ));
+
+        let data = encode(&NonEmptyBlob::<{ U8 }>::try_from(vec![1, 2, 3]).unwrap());
+        let err = BlobRef::<0, 2>::from_strict_slice_borrowed::<16>(&data).unwrap_err();
+        assert!(matches!(
+            err,
+            DeserializeError::Decode(DecodeError::Oversize {
+                declared_len: 3,
+                max_len: 2,
+                ..
+            })
+        ));
+
+        let err = TinyBlobRef::from_strict_slice_borrowed::<3>(&data).unwrap_err();
+        let DeserializeError::Decode(DecodeError::Io(err)) = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = TinyBlobRef::from_strict_slice_borrowed::<16>(&data[..3]).unwrap_err();
+        let DeserializeError::Decode(DecodeError::Io(err)) = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn array_ref() {
+        let data = [1u8, 2, 3, 4, 5];
+        let array = <&[u8; 4]>::from_strict_slice_borrowed::<16>(&data[..4]).unwrap();
+        assert_eq!(array, &[1, 2, 3, 4]);
+        assert_eq!(array.as_ptr(), data.as_ptr());
+
+        let err = <&[u8; 4]>::from_strict_slice_borrowed::<16>(&data).unwrap_err();
+        assert!(matches!(err, DeserializeError::DataNotEntirelyConsumed(1)));
+    }
+
+    #[test]
+    fn confined_ref() {
+        let ints = [1u16, 0x0203, 0xFFFF];
+        let owned = SmallVec::try_from(ints.to_vec()).unwrap();
+        let slice = ConfinedRef::<_, 0, { U16 }>::try_from(&ints[..]).unwrap();
+        assert_eq!(encode(&slice), encode(&owned));
+        assert_eq!(encode(&ConfinedRef::from(&owned)), encode(&owned));
+
+        let bytes = b"data".as_slice();
+        let blob = TinyBlob::try_from(bytes.to_vec()).unwrap();
+        let blob_ref = ConfinedRef::<[u8], 0, { U8 }>::try_from(bytes).unwrap();
+        assert_eq!(encode(&blob_ref), encode(&blob));
+
+        let text = "строка";
+        let string = TinyString::try_from(s!("строка")).unwrap();
+        let str_ref = ConfinedRef::<str, 0, { U8 }>::try_from(text).unwrap();
+        assert_eq!(encode(&str_ref), encode(&string));
+        assert_eq!(encode(&ConfinedRef::from(&string)), encode(&string));
+        assert_eq!(&*str_ref, text);
+
+        // references are encoded as the values they refer to
+        assert_eq!(encode(&&string), encode(&string));
+        assert_eq!(<&TinyString>::strict_name(), TinyString::strict_name());
+    }
+
+    #[test]
+    fn confined_ref_bounds() {
+        assert_eq!(
+            ConfinedRef::<str, 1, 4>::try_from("").unwrap_err(),
+            confinement::Error::Undersize { len: 0, min_len: 1 }
+        );
+        assert_eq!(ConfinedRef::<str, 1, 4>::try_from("long").unwrap().as_inner(), "long");
+        assert_eq!(
+            ConfinedRef::<[u8], 0, 4>::try_from([0u8; 5].as_slice()).unwrap_err(),
+            confinement::Error::Oversize { len: 5, max_len: 4 }
+        );
+    }
+
+    #[test]
+    fn owned_fallback() {
+        let data = [0x34, 0x12, 7];
+        let mut reader = StrictReader::from_slice::<16>(&data);
+        assert_eq!(u16::strict_decode_borrowed(&mut reader).unwrap(), 0x1234);
+        assert_eq!(<&[u8; 1]>::strict_decode_borrowed(&mut reader).unwrap(), &[7]);
+        assert!(reader.remaining().is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integers confined to a range of values, like percentages or month numbers.
+//!
+//! [`Bounded`] is encoded as a named newtype of the integer, i.e. with the same
+//! bytes as the integer itself. The name of the newtype includes the integer
+//! type and the bounds, like `BoundedU8From0To100` or `BoundedI16FromNeg40To85`,
+//! such that the bounds become the part of the type schema. Values out of the
+//! bounds are rejected on decoding with [`OutOfBounds`] error.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Formatter};
+use core::ops::{Deref, RangeInclusive};
+
+use crate::{
+    io, DecodeError, OutOfBounds, ReadTuple, StrictDecode, StrictDumb, StrictEncode, StrictProduct,
+    StrictTuple, StrictType, TypeName, TypedRead, TypedWrite, LIB_NAME_STD,
+};
+
+/// Integer types which can be confined with [`Bounded`].
+///
+/// The trait is implemented for all the primitive integers up to 64 bits.
+pub trait BoundedInt:
+    Copy + Ord + Display + Into<i128> + TryFrom<i128> + StrictEncode + StrictDecode
+{
+    /// Minimal value of the integer type.
+    const MIN_VALUE: i128;
+    /// Maximal value of the integer type.
+    const MAX_VALUE: i128;
+}
+
+macro_rules! impl_bounded_int {
+    ($($ty:ty),+) => {
+        $( impl BoundedInt for $ty {
+            const MIN_VALUE: i128 = <$ty>::MIN as i128;
+            const MAX_VALUE: i128 = <$ty>::MAX as i128;
+        } )+
+    };
+}
+
+impl_bounded_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Integer of type `T` with a value within `MIN..=MAX` range.
+///
+/// The bounds are checked at compile time to be valid for the integer type:
+/// using a type with `MIN > MAX` or bounds out of the range of `T` fails to
+/// compile.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Bounded<T: BoundedInt, const MIN: i128, const MAX: i128>(T);
+
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> Bounded<T, MIN, MAX> {
+    const VALID_BOUNDS: () = assert!(
+        MIN <= MAX && MIN >= T::MIN_VALUE && MAX <= T::MAX_VALUE,
+        "bounds of Bounded type must be ordered and fit the range of its integer type"
+    );
+
+    /// Constructs the value, returning an error if `value` is out of the
+    /// bounds.
+    pub fn new(value: T) -> Result<Self, OutOfBounds> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_BOUNDS;
+        if Self::bounds().contains(&value.into()) {
+            Ok(Bounded(value))
+        } else {
+            Err(OutOfBounds {
+                type_name: Self::type_name().to_string(),
+                value: value.into(),
+                min: MIN,
+                max: MAX,
+            })
+        }
+    }
+
+    /// Minimal value of the type.
+    pub fn min_value() -> Self { Self::from_bound(MIN) }
+
+    /// Maximal value of the type.
+    pub fn max_value() -> Self { Self::from_bound(MAX) }
+
+    /// Range of the values allowed by the type.
+    pub const fn bounds() -> RangeInclusive<i128> { MIN..=MAX }
+
+    /// Returns the integer value.
+    pub fn get(&self) -> T { self.0 }
+
+    fn from_bound(bound: i128) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_BOUNDS;
+        match T::try_from(bound) {
+            Ok(value) => Bounded(value),
+            Err(_) => unreachable!("bounds are checked to fit the integer type"),
+        }
+    }
+
+    fn type_name() -> TypeName {
+        fn bound(value: i128) -> String {
+            if value < 0 {
+                format!("Neg{}", value.unsigned_abs())
+            } else {
+                value.to_string()
+            }
+        }
+        let int = T::strict_name().expect("primitive integers always have a name");
+        tn!(format!("Bounded{int}From{}To{}", bound(MIN), bound(MAX)))
+    }
+}
+
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> Deref for Bounded<T, MIN, MAX> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> Display for Bounded<T, MIN, MAX> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.0, f) }
+}
+
+macro_rules! impl_from_bounded {
+    ($($ty:ty),+) => {
+        $( impl<const MIN: i128, const MAX: i128> From<Bounded<$ty, MIN, MAX>> for $ty {
+            fn from(value: Bounded<$ty, MIN, MAX>) -> Self { value.0 }
+        }
+        impl<const MIN: i128, const MAX: i128> TryFrom<$ty> for Bounded<$ty, MIN, MAX> {
+            type Error = OutOfBounds;
+            fn try_from(value: $ty) -> Result<Self, Self::Error> { Self::new(value) }
+        } )+
+    };
+}
+
+impl_from_bounded!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictDumb for Bounded<T, MIN, MAX> {
+    fn strict_dumb() -> Self { Self::min_value() }
+}
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictType for Bounded<T, MIN, MAX> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(Self::type_name()) }
+}
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictProduct for Bounded<T, MIN, MAX> {}
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictTuple for Bounded<T, MIN, MAX> {
+    const FIELD_COUNT: u8 = 1;
+}
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictEncode for Bounded<T, MIN, MAX> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_newtype::<Self>(&self.0)
+    }
+}
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictDecode for Bounded<T, MIN, MAX> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| Ok(Self::new(r.read_field()?)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encoding, try_decode};
+
+    type Percent = Bounded<u8, 0, 100>;
+    type Month = Bounded<u8, 1, 12>;
+    type Port = Bounded<u16, 1025, 65535>;
+    type Temperature = Bounded<i16, -40, 85>;
+
+    #[test]
+    fn names() {
+        assert_eq!(Percent::strict_name(), Some(tn!("BoundedU8From0To100")));
+        assert_eq!(Port::strict_name(), Some(tn!("BoundedU16From1025To65535")));
+        assert_eq!(Temperature::strict_name(), Some(tn!("BoundedI16FromNeg40To85")));
+        assert_eq!(
+            Bounded::<i64, { i64::MIN as i128 }, { i64::MAX as i128 }>::strict_name(),
+            Some(tn!("BoundedI64FromNeg9223372036854775808To9223372036854775807"))
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        encoding(&Percent::new(0).unwrap(), [0]);
+        encoding(&Percent::new(100).unwrap(), [100]);
+        encoding(&Month::new(1).unwrap(), [1]);
+        encoding(&Month::new(12).unwrap(), [12]);
+        encoding(&Port::new(1025).unwrap(), [0x01, 0x04]);
+        encoding(&Port::new(u16::MAX).unwrap(), [0xFF, 0xFF]);
+        encoding(&Temperature::new(-40).unwrap(), [0xD8, 0xFF]);
+        encoding(&Temperature::new(85).unwrap(), [85, 0]);
+
+        assert_eq!(Month::strict_dumb(), Month::min_value());
+        assert_eq!(*Month::strict_dumb(), 1);
+        assert_eq!(Temperature::max_value().get(), 85);
+        assert_eq!(u8::from(Percent::new(42).unwrap()), 42);
+        assert_eq!(Temperature::new(-3).unwrap().to_string(), "-3");
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        assert_eq!(
+            try_decode::<Month>(&[0]).unwrap_err(),
+            DecodeError::OutOfBounds(Box::new(OutOfBounds {
+                type_name: s!("BoundedU8From1To12"),
+                value: 0,
+                min: 1,
+                max: 12,
+            }))
+        );
+        assert_eq!(
+            try_decode::<Month>(&[13]).unwrap_err().to_string(),
+            "value 13 of `BoundedU8From1To12` is out of its bounds 1..=12"
+        );
+        assert!(try_decode::<Percent>(&[101]).is_err());
+        assert!(try_decode::<Port>(&[0x00, 0x04]).is_err());
+        assert!(try_decode::<Temperature>(&[0xD7, 0xFF]).is_err());
+        assert!(try_decode::<Temperature>(&[86, 0]).is_err());
+    }
+
+    #[test]
+    fn constructor_consistency() {
+        for value in i16::MIN..=i16::MAX {
+            let constructed = Temperature::new(value);
+            let decoded = try_decode::<Temperature>(&value.to_le_bytes());
+            assert_eq!(constructed.is_ok(), (-40..=85).contains(&value));
+            assert_eq!(constructed.clone().map_err(DecodeError::from), decoded);
+            assert_eq!(Temperature::try_from(value), constructed);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixed-size byte arrays, like 32-byte hashes and identifiers.
+//!
+//! [`ByteArray`] is encoded as `N` raw bytes without a length prefix, as a
+//! named newtype `BytesN` (for instance, `Bytes32`) of the `[u8; N]` array.
+//! It is displayed and parsed as a lowercase hex string of `2 * N`
+//! characters.
+
+use alloc::format;
+use alloc::string::String;
+use core::array::TryFromSliceError;
+use core::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::text::Base16;
+use crate::{
+    io, DecodeError, ReadTuple, StrictDecode, StrictEncode, StrictProduct, StrictTuple, StrictType,
+    TypeName, TypedRead, TypedWrite, LIB_NAME_STD,
+};
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum HexError {
+    /// hex string must be {expected} characters long, while it has {found}
+    /// characters.
+    InvalidLength { expected: usize, found: usize },
+
+    /// invalid hex character '{0}' at position {1}.
+    InvalidChar(char, usize),
+
+    /// hex string has odd number of {0} digits.
+    OddLength(usize),
+}
+
+impl_error!(HexError);
+
+/// Array of `N` bytes.
+///
+/// The comparison for equality takes the same time for all values of the same
+/// size, such that secrets can be compared without leaking their content.
+#[derive(Clone, Copy, PartialOrd, Ord)]
+pub struct ByteArray<const N: usize>([u8; N]);
+
+impl<const N: usize> ByteArray<N> {
+    pub const fn from_byte_array(bytes: [u8; N]) -> Self { ByteArray(bytes) }
+
+    pub const fn to_byte_array(&self) -> [u8; N] { self.0 }
+
+    pub const fn as_slice(&self) -> &[u8] { &self.0 }
+}
+
+impl<const N: usize> Default for ByteArray<N> {
+    fn default() -> Self { ByteArray([0u8; N]) }
+}
+
+impl<const N: usize> PartialEq for ByteArray<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+impl<const N: usize> Eq for ByteArray<N> {}
+
+impl<const N: usize> Hash for ByteArray<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+
+impl<const N: usize> From<[u8; N]> for ByteArray<N> {
+    fn from(bytes: [u8; N]) -> Self { ByteArray(bytes) }
+}
+
+impl<const N: usize> From<ByteArray<N>> for [u8; N] {
+    fn from(array: ByteArray<N>) -> Self { array.0 }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for ByteArray<N> {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> { <[u8; N]>::try_from(bytes).map(Self) }
+}
+
+impl<const N: usize> Deref for ByteArray<N> {
+    type Target = [u8; N];
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const N: usize> AsRef<[u8]> for ByteArray<N> {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl<const N: usize> AsRef<[u8; N]> for ByteArray<N> {
+    fn as_ref(&self) -> &[u8; N] { &self.0 }
+}
+
+impl<const N: usize> LowerHex for ByteArray<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl<const N: usize> UpperHex for ByteArray<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+    }
+}
+
+impl<const N: usize> Display for ByteArray<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { LowerHex::fmt(self, f) }
+}
+
+impl<const N: usize> Debug for ByteArray<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { write!(f, "ByteArray<{N}>({self:x})") }
+}
+
+impl<const N: usize> FromStr for ByteArray<N> {
+    type Err = HexError;
+
+    /// Parses hex string in either lower or upper case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let found = s.chars().count();
+        if found != N * 2 {
+            return Err(HexError::InvalidLength {
+                expected: N * 2,
+                found,
+            });
+        }
+        let mut bytes = [0u8; N];
+        let mut len = 0;
+        Base16::decode_digits(s.chars().enumerate(), |byte| {
+            bytes[len] = byte;
+            len += 1;
+        })?;
+        Ok(ByteArray(bytes))
+    }
+}
+
+impl<const N: usize> StrictType for ByteArray<N> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!(format!("Bytes{N}"))) }
+}
+impl<const N: usize> StrictProduct for ByteArray<N> {}
+impl<const N: usize> StrictTuple for ByteArray<N> {
+    const FIELD_COUNT: u8 = 1;
+}
+impl<const N: usize> StrictEncode for ByteArray<N> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_newtype::<Self>(&self.0)
+    }
+}
+impl<const N: usize> StrictDecode for ByteArray<N> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| r.read_field().map(Self))
+    }
+
+    fn strict_display(&self) -> Option<String> { Some(format!("{self}")) }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::test::{decode, encoding};
+    use crate::StrictDumb;
+
+    const ID: &str = "0123456789abcdef00ff10ee20dd30cc40bb50aa6099708880779966a55ab44b";
+
+    #[test]
+    fn encoding_raw() {
+        let id = ByteArray::<32>::from_str(ID).unwrap();
+        let bytes = id.to_byte_array();
+        assert_eq!(bytes[..3], [0x01, 0x23, 0x45]);
+        encoding(&id, bytes);
+        encoding(&ByteArray::from([0xA5u8, 0x5A]), [0xA5, 0x5A]);
+        encoding(&ByteArray::<0>::default(), []);
+        assert_eq!(ByteArray::<4>::strict_dumb(), ByteArray::from([0u8; 4]));
+        assert_eq!(decode::<ByteArray<2>>([0x00, 0xff]), ByteArray::from([0x00, 0xff]));
+
+        assert_eq!(ByteArray::<32>::strict_name(), Some(tn!("Bytes32")));
+        assert_eq!(ByteArray::<20>::strict_name(), Some(tn!("Bytes20")));
+    }
+
+    #[test]
+    fn conversions() {
+        let slice = [1u8, 2, 3, 4].as_slice();
+        let array = ByteArray::<4>::try_from(slice).unwrap();
+        assert_eq!(array.as_slice(), slice);
+        assert_eq!(<[u8; 4]>::from(array), [1, 2, 3, 4]);
+        assert_eq!(array[3], 4);
+        assert!(ByteArray::<4>::try_from(&slice[1..]).is_err());
+        assert!(ByteArray::<3>::try_from(slice).is_err());
+        assert_ne!(array, ByteArray::from([1, 2, 3, 5]));
+    }
+
+    #[test]
+    fn hex() {
+        let id = ByteArray::<32>::from_str(ID).unwrap();
+        assert_eq!(id.to_string(), ID);
+        assert_eq!(format!("{id:X}"), ID.to_uppercase());
+        assert_eq!(ByteArray::<32>::from_str(&ID.to_uppercase()).unwrap(), id);
+        assert_eq!(format!("{:?}", ByteArray::from([0xAB, 0x01])), "ByteArray<2>(ab01)");
+
+        assert_eq!(ByteArray::<31>::from_str(ID).unwrap_err(), HexError::InvalidLength {
+            expected: 62,
+            found: 64
+        });
+        assert_eq!(ByteArray::<32>::from_str(&ID[1..]).unwrap_err(), HexError::InvalidLength {
+            expected: 64,
+            found: 63
+        });
+        assert_eq!(ByteArray::<2>::from_str("0x12").unwrap_err(), HexError::InvalidChar('x', 1));
+        assert_eq!(ByteArray::<2>::from_str("12ю").unwrap_err(), HexError::InvalidLength {
+            expected: 4,
+            found: 3
+        });
+        assert_eq!(ByteArray::<2>::from_str("12ю4").unwrap_err(), HexError::InvalidChar('ю', 2));
+        assert_eq!(
+            ByteArray::<2>::from_str("123").unwrap_err().to_string(),
+            "hex string must be 4 characters long, while it has 3 characters."
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn display_parse_inverse(bytes: [u8; 24]) {
+            let array = ByteArray::from(bytes);
+            prop_assert_eq!(ByteArray::from_str(&array.to_string()), Ok(array));
+            prop_assert_eq!(ByteArray::from_str(&format!("{array:X}")), Ok(array));
+        }
+    }
+}
@@ -0,0 +1,358 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding from and into reference-counted [`Bytes`] buffers.
+//!
+//! [`BytesReader`] reads the data from a [`Bytes`] buffer, such that byte
+//! strings decoded as [`Bytes`] and [`SharedBlob`] are slices of that buffer
+//! sharing its memory, and not copies of the data. With other readers the
+//! byte strings are copied into newly allocated buffers.
+//!
+//! [`Bytes`] and [`BytesMut`] are encoded as `LargeBlob`, while
+//! [`SharedBlob`] has the same encoding as `Confined<Vec<u8>, MIN_LEN,
+//! MAX_LEN>`.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use ::bytes::{Bytes, BytesMut};
+use amplify::confinement::{self, Confined, U16, U24, U32, U8};
+
+use crate::reader::list_name;
+use crate::shared::write_blob;
+use crate::{
+    io, DecodeError, ReadRaw, SliceReader, StrictDecode, StrictEncode, StrictType, TypeName,
+    TypedRead, TypedWrite, LIB_EMBEDDED,
+};
+
+pub type TinySharedBlob = SharedBlob<0, U8>;
+pub type SmallSharedBlob = SharedBlob<0, U16>;
+pub type MediumSharedBlob = SharedBlob<0, U24>;
+pub type LargeSharedBlob = SharedBlob<0, U32>;
+
+/// Reader over a [`Bytes`] buffer, which returns the byte strings as slices
+/// of the buffer without copying them.
+///
+/// The reader is confined in the same way as [`SliceReader`].
+#[derive(Clone, Debug)]
+pub struct BytesReader {
+    data: Bytes,
+    pos: usize,
+    limit: usize,
+}
+
+impl BytesReader {
+    pub fn new<const MAX: usize>(data: Bytes) -> Self {
+        Self {
+            data,
+            pos: 0,
+            limit: MAX,
+        }
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// Data which are not consumed yet.
+    pub fn remaining(&self) -> Bytes { self.data.slice(self.pos..) }
+
+    fn consume(&mut self, len: usize) -> io::Result<&[u8]> {
+        let limit = self.limit.saturating_sub(self.pos);
+        let chunk = SliceReader::with(limit, &self.data[self.pos..]).consume(len)?;
+        self.pos += len;
+        Ok(chunk)
+    }
+}
+
+impl ReadRaw for BytesReader {
+    fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.consume(len).map(<[u8]>::to_vec)
+    }
+
+    fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        let mut buf = [0u8; LEN];
+        buf.copy_from_slice(self.consume(LEN)?);
+        Ok(buf)
+    }
+
+    fn read_raw_bytes<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Bytes> {
+        let start = self.pos;
+        self.consume(len)?;
+        Ok(self.data.slice(start..start + len))
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        buf.extend_from_slice(self.consume(len)?);
+        Ok(())
+    }
+
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        self.consume(len).map(Arc::from)
+    }
+
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> { self.consume(len).map(|_| ()) }
+
+    fn offset(&self) -> Option<usize> { Some(self.pos) }
+
+    fn peek_byte(&mut self) -> io::Result<u8> {
+        let limit = self.limit.saturating_sub(self.pos);
+        SliceReader::with(limit, &self.data[self.pos..]).consume(1).map(|chunk| chunk[0])
+    }
+
+    /// Bytes reader can be rewound up to the beginning of the data.
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        self.pos = self.pos.checked_sub(len).ok_or(io::ErrorKind::InvalidInput)?;
+        Ok(())
+    }
+
+    fn drain(&mut self) -> io::Result<usize> {
+        let len = self.data.len() - self.pos;
+        self.pos = self.data.len();
+        Ok(len)
+    }
+}
+
+/// Byte string in a reference-counted buffer, which has the same encoding as
+/// `Confined<Vec<u8>, MIN_LEN, MAX_LEN>`. Decoded by [`BytesReader`], it
+/// shares the memory of the source buffer.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SharedBlob<const MIN_LEN: usize, const MAX_LEN: usize>(Bytes);
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> SharedBlob<MIN_LEN, MAX_LEN> {
+    pub fn as_slice(&self) -> &[u8] { &self.0 }
+
+    pub fn as_bytes(&self) -> &Bytes { &self.0 }
+
+    pub fn into_bytes(self) -> Bytes { self.0 }
+
+    /// Copies the data into an owned confined byte string.
+    pub fn to_confined(&self) -> Confined<Vec<u8>, MIN_LEN, MAX_LEN> {
+        Confined::try_from(self.0.to_vec()).expect("shared blob length is already checked")
+    }
+}
+
+impl<const MAX_LEN: usize> Default for SharedBlob<0, MAX_LEN> {
+    fn default() -> Self { SharedBlob(Bytes::new()) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<Bytes> for SharedBlob<MIN_LEN, MAX_LEN> {
+    type Error = confinement::Error;
+
+    fn try_from(data: Bytes) -> Result<Self, Self::Error> {
+        let len = data.len();
+        if len < MIN_LEN {
+            return Err(confinement::Error::Undersize {
+                len,
+                min_len: MIN_LEN,
+            });
+        }
+        if len > MAX_LEN {
+            return Err(confinement::Error::Oversize {
+                len,
+                max_len: MAX_LEN,
+            });
+        }
+        Ok(SharedBlob(data))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<Confined<Vec<u8>, MIN_LEN, MAX_LEN>>
+    for SharedBlob<MIN_LEN, MAX_LEN>
+{
+    fn from(data: Confined<Vec<u8>, MIN_LEN, MAX_LEN>) -> Self {
+        SharedBlob(Bytes::from(data.release()))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<SharedBlob<MIN_LEN, MAX_LEN>> for Bytes {
+    fn from(blob: SharedBlob<MIN_LEN, MAX_LEN>) -> Self { blob.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Deref for SharedBlob<MIN_LEN, MAX_LEN> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> AsRef<[u8]> for SharedBlob<MIN_LEN, MAX_LEN> {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Borrow<[u8]> for SharedBlob<MIN_LEN, MAX_LEN> {
+    fn borrow(&self) -> &[u8] { &self.0 }
+}
+
+impl StrictType for Bytes {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<u8>(0, U32) }
+}
+impl StrictEncode for Bytes {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        write_blob::<W, 0, U32>(writer, self)
+    }
+}
+impl StrictDecode for Bytes {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        unsafe { reader.read_bytes::<Self, 0, U32>() }
+    }
+}
+
+impl StrictType for BytesMut {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<u8>(0, U32) }
+}
+impl StrictEncode for BytesMut {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        write_blob::<W, 0, U32>(writer, self)
+    }
+}
+impl StrictDecode for BytesMut {
+    /// Since the mutable buffer can't share memory with the source data, the
+    /// decoded byte string is always copied.
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        Bytes::strict_decode(reader).map(BytesMut::from)
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for SharedBlob<MIN_LEN, MAX_LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<u8>(MIN_LEN, MAX_LEN) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode for SharedBlob<MIN_LEN, MAX_LEN> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        write_blob::<W, MIN_LEN, MAX_LEN>(writer, self)
+    }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode for SharedBlob<MIN_LEN, MAX_LEN> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        unsafe { reader.read_bytes::<Self, MIN_LEN, MAX_LEN>() }.map(SharedBlob)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{LargeBlob, TinyBlob};
+
+    use super::*;
+    use crate::test::{decode, encode};
+    use crate::{StrictReader, StrictSize};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Packet {
+        id: u32,
+        payload: Bytes,
+        tag: TinySharedBlob,
+    }
+
+    fn packet() -> Packet {
+        Packet {
+            id: 0xCAFE,
+            payload: Bytes::from((0..0x8000u32).map(|i| i as u8).collect::<Vec<_>>()),
+            tag: TinySharedBlob::try_from(Bytes::from_static(b"tag")).unwrap(),
+        }
+    }
+
+    fn contains(buf: &[u8], slice: &[u8]) -> bool {
+        let (buf, slice) = (buf.as_ptr_range(), slice.as_ptr_range());
+        buf.start <= slice.start && slice.end <= buf.end
+    }
+
+    #[test]
+    fn zero_copy() {
+        let packet = packet();
+        let data = Bytes::from(encode(&packet));
+        let mut reader = StrictReader::from_bytes::<{ usize::MAX }>(data.clone());
+        let decoded = Packet::strict_decode(&mut reader).unwrap();
+        assert!(reader.remaining().is_empty());
+        assert_eq!(reader.pos(), data.len());
+        assert_eq!(decoded, packet);
+        assert!(contains(&data, &decoded.payload));
+        assert!(contains(&data, &decoded.tag));
+
+        let copied = decode::<Packet>(&data);
+        assert_eq!(copied, packet);
+        assert!(!contains(&data, &copied.payload));
+    }
+
+    #[test]
+    fn encoding() {
+        let blob = LargeBlob::try_from(vec![1u8, 2, 3]).unwrap();
+        assert_eq!(encode(&Bytes::from_static(&[1, 2, 3])), encode(&blob));
+        assert_eq!(encode(&BytesMut::from(&[1u8, 2, 3][..])), encode(&blob));
+        assert_eq!(decode::<BytesMut>(encode(&blob)), &[1, 2, 3][..]);
+
+        let blob = TinyBlob::try_from(vec![4u8, 5]).unwrap();
+        let shared = TinySharedBlob::from(blob.clone());
+        assert_eq!(encode(&shared), encode(&blob));
+        assert_eq!(shared.to_confined(), blob);
+        assert_eq!(TinySharedBlob::strict_size(), TinyBlob::strict_size());
+    }
+
+    #[test]
+    fn confinement() {
+        let data = Bytes::from(encode(&TinyBlob::try_from(vec![1u8, 2, 3]).unwrap()));
+        let err =
+            SharedBlob::<0, 2>::strict_decode(&mut StrictReader::from_bytes::<16>(data.clone()))
+                .unwrap_err();
+        assert!(matches!(err, DecodeError::Oversize {
+            declared_len: 3,
+            max_len: 2,
+            ..
+        }));
+        let err = SharedBlob::<4, { U8 }>::strict_decode(&mut StrictReader::from_bytes::<16>(
+            data.clone(),
+        ))
+        .unwrap_err();
+        assert!(matches!(err, DecodeError::Undersize {
+            declared_len: 3,
+            min_len: 4,
+            ..
+        }));
+
+        let err = TinySharedBlob::strict_decode(&mut StrictReader::from_bytes::<3>(data.clone()))
+            .unwrap_err();
+        let DecodeError::Io(err) = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err =
+            TinySharedBlob::strict_decode(&mut StrictReader::from_bytes::<16>(data.slice(..3)))
+                .unwrap_err();
+        let DecodeError::Io(err) = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        assert!(SharedBlob::<0, 2>::try_from(data.clone()).is_err());
+        assert!(SharedBlob::<5, 8>::try_from(data).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of calendar types from `chrono` and `time` crates.
+//!
+//! Both crates share the same type names and wire layout, such that a value
+//! encoded using one of them can be decoded with the other:
+//! - `Timestamp` (`chrono::DateTime<Utc>`, `time::OffsetDateTime`) is a
+//!   structure of `i64` seconds since the UNIX epoch and `u32` nanoseconds,
+//!   which must be less than `1_000_000_000`. The offset of
+//!   `OffsetDateTime` is not encoded, and the value is decoded in UTC;
+//! - `Date` (`chrono::NaiveDate`, `time::Date`) is a tuple of `i32` days since
+//!   1970-01-01.
+//!
+//! Values which can't be represented by the decoded type result in
+//! [`DecodeError::TimeOutOfRange`] error.
+//!
+//! The types from `time` crate do not implement `Default` and thus are not
+//! [`crate::StrictDumb`]; when they are used as fields of a type deriving it,
+//! the field must provide its dumb value explicitly with
+//! `#[strict_type(dumb = ...)]` attribute.
+
+use alloc::borrow::ToOwned;
+
+use crate::time::check_nanos;
+use crate::{
+    io, DecodeError, StrictDecode, StrictEncode, StrictType, TypeName, TypedRead, TypedWrite,
+    LIB_NAME_STD,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, crate = crate)]
+pub(crate) struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, crate = crate)]
+pub(crate) struct Date(i32);
+
+fn out_of_range<T>(value: impl Into<i64>) -> DecodeError {
+    DecodeError::TimeOutOfRange(core::any::type_name::<T>().to_owned(), value.into())
+}
+
+macro_rules! impl_calendar {
+    ($ty:ty, $mirror:ident) => {
+        impl StrictType for $ty {
+            const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+            fn strict_name() -> Option<TypeName> { Some(tn!(stringify!($mirror))) }
+        }
+        impl StrictEncode for $ty {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                $mirror::try_from(self)?.strict_encode(writer)
+            }
+        }
+        impl StrictDecode for $ty {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                $mirror::strict_decode(reader).and_then(<$ty>::try_from)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "chrono")]
+mod chrono {
+    use ::chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+    use super::*;
+
+    // Number of days from 0001-01-01 to 1970-01-01.
+    const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+    impl TryFrom<&DateTime<Utc>> for Timestamp {
+        type Error = io::Error;
+
+        fn try_from(time: &DateTime<Utc>) -> Result<Self, Self::Error> {
+            let nanos = time.timestamp_subsec_nanos();
+            // Leap seconds are not representable by the strict timestamp
+            check_nanos::<DateTime<Utc>>(nanos)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            Ok(Timestamp {
+                secs: time.timestamp(),
+                nanos,
+            })
+        }
+    }
+
+    impl TryFrom<Timestamp> for DateTime<Utc> {
+        type Error = DecodeError;
+
+        fn try_from(time: Timestamp) -> Result<Self, Self::Error> {
+            let nanos = check_nanos::<Self>(time.nanos)?;
+            DateTime::from_timestamp(time.secs, nanos)
+                .ok_or_else(|| out_of_range::<Self>(time.secs))
+        }
+    }
+
+    impl TryFrom<&NaiveDate> for Date {
+        type Error = io::Error;
+
+        fn try_from(date: &NaiveDate) -> Result<Self, Self::Error> {
+            Ok(Date(date.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE))
+        }
+    }
+
+    impl TryFrom<Date> for NaiveDate {
+        type Error = DecodeError;
+
+        fn try_from(date: Date) -> Result<Self, Self::Error> {
+            date.0
+                .checked_add(UNIX_EPOCH_DAYS_FROM_CE)
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .ok_or_else(|| out_of_range::<Self>(date.0))
+        }
+    }
+
+    impl_calendar!(DateTime<Utc>, Timestamp);
+    impl_calendar!(NaiveDate, Date);
+}
+
+#[cfg(feature = "time")]
+mod time {
+    use ::time::{Date as TimeDate, OffsetDateTime};
+
+    use super::*;
+
+    // Julian day number of 1970-01-01.
+    const UNIX_EPOCH_JULIAN_DAY: i32 = 2_440_588;
+
+    impl TryFrom<&OffsetDateTime> for Timestamp {
+        type Error = io::Error;
+
+        fn try_from(time: &OffsetDateTime) -> Result<Self, Self::Error> {
+            Ok(Timestamp {
+                secs: time.unix_timestamp(),
+                nanos: time.nanosecond(),
+            })
+        }
+    }
+
+    impl TryFrom<Timestamp> for OffsetDateTime {
+        type Error = DecodeError;
+
+        fn try_from(time: Timestamp) -> Result<Self, Self::Error> {
+            let nanos = check_nanos::<Self>(time.nanos)?;
+            OffsetDateTime::from_unix_timestamp(time.secs)
+                .and_then(|t| t.replace_nanosecond(nanos))
+                .map_err(|_| out_of_range::<Self>(time.secs))
+        }
+    }
+
+    impl TryFrom<&TimeDate> for Date {
+        type Error = io::Error;
+
+        fn try_from(date: &TimeDate) -> Result<Self, Self::Error> {
+            Ok(Date(date.to_julian_day() - UNIX_EPOCH_JULIAN_DAY))
+        }
+    }
+
+    impl TryFrom<Date> for TimeDate {
+        type Error = DecodeError;
+
+        fn try_from(date: Date) -> Result<Self, Self::Error> {
+            date.0
+                .checked_add(UNIX_EPOCH_JULIAN_DAY)
+                .and_then(|day| TimeDate::from_julian_day(day).ok())
+                .ok_or_else(|| out_of_range::<Self>(date.0))
+        }
+    }
+
+    impl_calendar!(OffsetDateTime, Timestamp);
+    impl_calendar!(TimeDate, Date);
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+
+    use super::*;
+    use crate::test::{decode, decode_err, encode, encoding};
+    use crate::StrictDumb;
+
+    // 2024-02-29T12:30:15.25Z
+    const TIMESTAMP: [u8; 12] = [0xD7, 0x78, 0xE0, 0x65, 0, 0, 0, 0, 0x80, 0xB2, 0xE6, 0x0E];
+    // 1969-12-31T23:59:59Z
+    const BEFORE_EPOCH: [u8; 12] = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+    // 2024-02-29
+    const DATE: [u8; 4] = [0x46, 0x4D, 0, 0];
+    // 1969-12-31
+    const DATE_BEFORE_EPOCH: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+    const INVALID_NANOS: [u8; 12] = [0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0xCA, 0x9A, 0x3B];
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono() {
+        use ::chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+        assert_eq!(DateTime::<Utc>::strict_name(), Some(tn!("Timestamp")));
+        assert_eq!(NaiveDate::strict_name(), Some(tn!("Date")));
+        assert_eq!(DateTime::<Utc>::strict_dumb(), DateTime::<Utc>::UNIX_EPOCH);
+        assert_eq!(NaiveDate::strict_dumb(), NaiveDate::default());
+
+        let time = Utc.with_ymd_and_hms(2024, 2, 29, 12, 30, 15).unwrap()
+            + ::chrono::Duration::milliseconds(250);
+        encoding(&time, TIMESTAMP);
+        encoding(&DateTime::<Utc>::UNIX_EPOCH, [0u8; 12]);
+        encoding(&Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 59).unwrap(), BEFORE_EPOCH);
+
+        encoding(&NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), DATE);
+        encoding(&NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(), [0u8; 4]);
+        encoding(&NaiveDate::from_ymd_opt(1969, 12, 31).unwrap(), DATE_BEFORE_EPOCH);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_out_of_range() {
+        use ::chrono::{DateTime, NaiveDate, Utc};
+
+        let max = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0, 0, 0, 0];
+        assert_eq!(
+            decode_err::<DateTime<Utc>>(&max),
+            DecodeError::TimeOutOfRange(
+                core::any::type_name::<DateTime<Utc>>().to_owned(),
+                i64::MAX
+            )
+        );
+        assert!(matches!(
+            decode_err::<DateTime<Utc>>(&INVALID_NANOS),
+            DecodeError::ValueOutOfRange(_, _, 1_000_000_000)
+        ));
+        assert!(matches!(
+            decode_err::<NaiveDate>(&[0xFF, 0xFF, 0xFF, 0x7F]),
+            DecodeError::TimeOutOfRange(_, 0x7FFF_FFFF)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time() {
+        use ::time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset};
+
+        assert_eq!(OffsetDateTime::strict_name(), Some(tn!("Timestamp")));
+        assert_eq!(Date::strict_name(), Some(tn!("Date")));
+
+        let date = Date::from_calendar_date(2024, Month::February, 29).unwrap();
+        let time = date
+            .with_time(Time::from_hms_milli(12, 30, 15, 250).unwrap())
+            .assume_utc();
+        encoding(&time, TIMESTAMP);
+        encoding(&OffsetDateTime::UNIX_EPOCH, [0u8; 12]);
+        encoding(&(OffsetDateTime::UNIX_EPOCH - Duration::SECOND), BEFORE_EPOCH);
+
+        // offset is not preserved
+        let offset = time.to_offset(UtcOffset::from_hms(3, 0, 0).unwrap());
+        assert_eq!(encode(&offset), TIMESTAMP);
+        assert_eq!(decode::<OffsetDateTime>(encode(&offset)).offset(), UtcOffset::UTC);
+
+        encoding(&date, DATE);
+        encoding(&OffsetDateTime::UNIX_EPOCH.date(), [0u8; 4]);
+        let before = Date::from_calendar_date(1969, Month::December, 31).unwrap();
+        encoding(&before, DATE_BEFORE_EPOCH);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_out_of_range() {
+        use ::time::{Date, OffsetDateTime};
+
+        let max = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0, 0, 0, 0];
+        assert_eq!(
+            decode_err::<OffsetDateTime>(&max),
+            DecodeError::TimeOutOfRange(
+                core::any::type_name::<OffsetDateTime>().to_owned(),
+                i64::MAX
+            )
+        );
+        assert!(matches!(
+            decode_err::<OffsetDateTime>(&INVALID_NANOS),
+            DecodeError::ValueOutOfRange(_, _, 1_000_000_000)
+        ));
+        assert!(matches!(
+            decode_err::<Date>(&[0xFF, 0xFF, 0xFF, 0x7F]),
+            DecodeError::TimeOutOfRange(_, 0x7FFF_FFFF)
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "chrono", feature = "time"))]
+    fn cross_compatibility() {
+        use ::chrono::{DateTime, NaiveDate, Utc};
+        use ::time::{Date, OffsetDateTime};
+
+        for data in [TIMESTAMP, BEFORE_EPOCH, [0u8; 12]] {
+            let chrono = decode::<DateTime<Utc>>(data);
+            let time = decode::<OffsetDateTime>(data);
+            assert_eq!(encode(&chrono), encode(&time));
+            assert_eq!(chrono.timestamp_nanos_opt().unwrap() as i128, time.unix_timestamp_nanos());
+        }
+        for data in [DATE, DATE_BEFORE_EPOCH, [0u8; 4]] {
+            let chrono = decode::<NaiveDate>(data);
+            let time = decode::<Date>(data);
+            assert_eq!(encode(&chrono), encode(&time));
+            assert_eq!(chrono.to_string(), time.to_string());
+        }
+    }
+}
@@ -0,0 +1,227 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Unicode strings confined by the number of characters instead of bytes.
+//!
+//! [`ConfinedCharString`] is encoded exactly as a UTF-8 string confined to
+//! `MIN..=MAX * 4` bytes, i.e. with the length prefix of the width derived from
+//! `MAX * 4`, which is the maximal number of bytes taken by `MAX` characters.
+//! On decoding, the data are checked to be a valid UTF-8 string, failing with
+//! [`DecodeError::Utf8`], and then the number of its characters (unicode
+//! scalar values) is checked to be within the bounds, failing with
+//! [`DecodeError::CharCount`].
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::reader::{decoded_name, unicode_name};
+use crate::{
+    io, DecodeError, InvalidCharCount, ReadRaw, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictType, TypeName, TypedRead, TypedWrite, Utf8Policy, WriteRaw, LIB_EMBEDDED,
+};
+
+/// Unicode string with `MIN..=MAX` characters (unicode scalar values).
+///
+/// The bounds are checked at compile time to be ordered: using a type with
+/// `MIN > MAX` fails to compile.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ConfinedCharString<const MIN: usize, const MAX: usize>(String);
+
+impl<const MIN: usize, const MAX: usize> ConfinedCharString<MIN, MAX> {
+    /// Maximal number of bytes taken by the string, which defines the width of
+    /// its length prefix.
+    pub const MAX_BYTES: usize = MAX.saturating_mul(4);
+
+    const VALID_BOUNDS: () =
+        assert!(MIN <= MAX, "bounds of ConfinedCharString type must be ordered");
+
+    /// Constructs the string, returning an error if the number of its
+    /// characters is out of the bounds.
+    pub fn try_from_string(s: String) -> Result<Self, InvalidCharCount> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_BOUNDS;
+        let count = s.chars().count();
+        if !(MIN..=MAX).contains(&count) {
+            return Err(InvalidCharCount {
+                type_name: decoded_name::<Self>(),
+                count,
+                min_len: MIN,
+                max_len: MAX,
+            });
+        }
+        Ok(Self(s))
+    }
+
+    /// Number of characters in the string.
+    pub fn char_count(&self) -> usize { self.0.chars().count() }
+
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    pub fn into_string(self) -> String { self.0 }
+
+    // The string is laid out as a string confined to `MIN..=MAX_BYTES` bytes,
+    // which defines the width of its length prefix.
+    const fn sizing() -> Sizing { Sizing::new(MIN as u64, Self::MAX_BYTES as u64) }
+}
+
+impl<const MIN: usize, const MAX: usize> Deref for ConfinedCharString<MIN, MAX> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const MIN: usize, const MAX: usize> AsRef<str> for ConfinedCharString<MIN, MAX> {
+    fn as_ref(&self) -> &str { &self.0 }
+}
+
+impl<const MIN: usize, const MAX: usize> Display for ConfinedCharString<MIN, MAX> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+impl<const MIN: usize, const MAX: usize> FromStr for ConfinedCharString<MIN, MAX> {
+    type Err = InvalidCharCount;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::try_from_string(s.to_owned()) }
+}
+
+impl<const MIN: usize, const MAX: usize> TryFrom<String> for ConfinedCharString<MIN, MAX> {
+    type Error = InvalidCharCount;
+    fn try_from(s: String) -> Result<Self, Self::Error> { Self::try_from_string(s) }
+}
+
+impl<const MIN: usize, const MAX: usize> From<ConfinedCharString<MIN, MAX>> for String {
+    fn from(s: ConfinedCharString<MIN, MAX>) -> Self { s.0 }
+}
+
+impl<const MIN: usize, const MAX: usize> StrictDumb for ConfinedCharString<MIN, MAX> {
+    fn strict_dumb() -> Self {
+        Self::try_from_string("\0".repeat(MIN)).expect("dumb string has MIN characters")
+    }
+}
+impl<const MIN: usize, const MAX: usize> StrictType for ConfinedCharString<MIN, MAX> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { unicode_name(MIN, Self::MAX_BYTES) }
+}
+impl<const MIN: usize, const MAX: usize> StrictEncode for ConfinedCharString<MIN, MAX> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        let bytes = self.0.as_bytes();
+        unsafe {
+            let mut writer = writer.register_unicode(Self::sizing());
+            Self::sizing().write_len(writer.raw_writer(), bytes.len())?;
+            writer.raw_writer().write_raw::<{ usize::MAX }>(bytes)?;
+            Ok(writer)
+        }
+    }
+}
+impl<const MIN: usize, const MAX: usize> StrictDecode for ConfinedCharString<MIN, MAX> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let bytes = unsafe {
+            let len = Self::sizing().read_len(reader.raw_reader(), decoded_name::<Self>)?;
+            reader.alloc(len)?;
+            reader.raw_reader().read_raw::<{ usize::MAX }>(len)?
+        };
+        let s = match reader.utf8_policy() {
+            Utf8Policy::Strict => String::from_utf8(bytes)?,
+            Utf8Policy::Lossy => String::from_utf8(bytes)
+                .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        };
+        Self::try_from_string(s).map_err(DecodeError::from)
+    }
+
+    fn strict_display(&self) -> Option<String> { Some(self.0.clone()) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encoding, try_decode};
+    use crate::{StrictReader, StrictWriter};
+
+    type Name = ConfinedCharString<1, 32>;
+    type Wide = ConfinedCharString<0, 64>;
+
+    fn data(prefix: &[u8], s: &str) -> Vec<u8> {
+        let mut data = prefix.to_vec();
+        data.extend(s.as_bytes());
+        data
+    }
+
+    #[test]
+    fn prefix_width() {
+        assert_eq!(Name::MAX_BYTES, 128);
+        assert_eq!(Wide::MAX_BYTES, 256);
+        encoding(&Name::from_str("日本").unwrap(), [6, 0xE6, 0x97, 0xA5, 0xE6, 0x9C, 0xAC]);
+        encoding(&Wide::from_str("日本").unwrap(), [6, 0, 0xE6, 0x97, 0xA5, 0xE6, 0x9C, 0xAC]);
+        encoding(&Wide::strict_dumb(), [0, 0]);
+        assert_eq!(Name::strict_dumb().as_str(), "\0");
+    }
+
+    #[test]
+    fn char_bounds() {
+        let cjk = "漢".repeat(32);
+        assert_eq!(cjk.len(), 96);
+        let name = Name::try_from(cjk.clone()).unwrap();
+        assert_eq!(name.char_count(), 32);
+        encoding(&name, data(&[96], &cjk));
+
+        let ascii = "a".repeat(33);
+        let err = InvalidCharCount {
+            type_name: s!("[Unicode ^ 1..0x80]"),
+            count: 33,
+            min_len: 1,
+            max_len: 32,
+        };
+        assert_eq!(Name::from_str(&ascii), Err(err.clone()));
+        assert_eq!(try_decode::<Name>(&data(&[33], &ascii)), Err(DecodeError::from(err)));
+        assert!(Name::from_str("").is_err());
+        assert!(ConfinedCharString::<0, 100>::from_str(&ascii).is_ok());
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert!(matches!(try_decode::<Name>(&[2, 0xC3, 0x28]), Err(DecodeError::Utf8(_))));
+        assert_eq!(
+            try_decode::<ConfinedCharString<0, 2>>(&data(&[9], "abcdefghi")),
+            Err(DecodeError::Oversize {
+                type_name: s!("[Unicode ^ ..0x8]"),
+                declared_len: 9,
+                max_len: 8,
+            })
+        );
+        assert!(matches!(try_decode::<Name>(&[0]), Err(DecodeError::Undersize { .. })));
+
+        let lossy = Name::strict_decode(
+            &mut StrictReader::in_memory::<16>([2, 0xC3, 0x28]).with_utf8_policy(Utf8Policy::Lossy),
+        );
+        assert_eq!(lossy.unwrap().as_str(), "\u{FFFD}(");
+    }
+
+    #[test]
+    fn same_as_byte_string() {
+        let name = Name::from_str("Ʒupa").unwrap();
+        let writer = StrictWriter::in_memory::<1024>();
+        let data = name.strict_encode(writer).unwrap().unbox().unconfine();
+        let s = try_decode::<amplify::confinement::Confined<String, 1, 128>>(&data).unwrap();
+        assert_eq!(s.as_str(), name.as_str());
+    }
+}
@@ -0,0 +1,415 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Values protected by a checksum of their strict encoding.
+//!
+//! [`Checked`] value is encoded as the strict encoding of the wrapped value,
+//! followed by the checksum of these encoded bytes. The checksum is computed
+//! while the value is written and verified while it is read, without
+//! buffering the encoded data.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "bytes")]
+use ::bytes::Bytes;
+
+#[cfg(feature = "trace")]
+use crate::ScopeKind;
+use crate::{
+    io, DecodeError, ReadRaw, StrictDecode, StrictDumb, StrictEncode, StrictReader, StrictType,
+    StrictWriter, TypeName, TypedRead, TypedWrite, WriteRaw, LIB_EMBEDDED,
+};
+
+/// Checksum algorithm used by [`Checked`] values.
+pub trait Checksum: Default {
+    /// Checksum value, which is strict-encoded after the checked data.
+    type Output: StrictEncode + StrictDecode + AsRef<[u8]> + Eq + Debug;
+
+    fn update(&mut self, data: &[u8]);
+
+    fn finalize(self) -> Self::Output;
+}
+
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 checksum (as used by zip, PNG and Ethernet), encoded as 4 bytes in
+/// little-endian order.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self { Crc32(0xFFFF_FFFF) }
+}
+
+impl Checksum for Crc32 {
+    type Output = [u8; 4];
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.0 = CRC32_TABLE[((self.0 ^ *byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finalize(self) -> Self::Output { (!self.0).to_le_bytes() }
+}
+
+/// SHA-256 checksum.
+#[cfg(feature = "sha2")]
+#[derive(Clone, Debug, Default)]
+pub struct Sha256(sha2::Sha256);
+
+#[cfg(feature = "sha2")]
+impl Checksum for Sha256 {
+    type Output = [u8; 32];
+
+    fn update(&mut self, data: &[u8]) { sha2::Digest::update(&mut self.0, data) }
+
+    fn finalize(self) -> Self::Output { sha2::Digest::finalize(self.0).into() }
+}
+
+/// Value which is strict-encoded together with a checksum of its encoding,
+/// such that the data corruption is detected during the decoding.
+///
+/// Decoding fails with [`DecodeError::ChecksumMismatch`] if the checksum
+/// doesn't match the data, without returning the decoded value.
+pub struct Checked<T, C: Checksum = Crc32> {
+    value: T,
+    checksum: PhantomData<C>,
+}
+
+// Manual implementations don't require checksum type to implement the traits
+impl<T: Clone, C: Checksum> Clone for Checked<T, C> {
+    fn clone(&self) -> Self { Checked::new(self.value.clone()) }
+}
+
+impl<T: PartialEq, C: Checksum> PartialEq for Checked<T, C> {
+    fn eq(&self, other: &Self) -> bool { self.value == other.value }
+}
+
+impl<T: Eq, C: Checksum> Eq for Checked<T, C> {}
+
+impl<T: Hash, C: Checksum> Hash for Checked<T, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.value.hash(state) }
+}
+
+impl<T: Debug, C: Checksum> Debug for Checked<T, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Checked").field(&self.value).finish()
+    }
+}
+
+impl<T, C: Checksum> Checked<T, C> {
+    pub fn new(value: T) -> Self {
+        Checked {
+            value,
+            checksum: PhantomData,
+        }
+    }
+
+    pub fn as_inner(&self) -> &T { &self.value }
+
+    pub fn into_inner(self) -> T { self.value }
+}
+
+impl<T, C: Checksum> From<T> for Checked<T, C> {
+    fn from(value: T) -> Self { Checked::new(value) }
+}
+
+impl<T, C: Checksum> Deref for Checked<T, C> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.value }
+}
+
+impl<T, C: Checksum> DerefMut for Checked<T, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.value }
+}
+
+impl<T, C: Checksum> AsRef<T> for Checked<T, C> {
+    fn as_ref(&self) -> &T { &self.value }
+}
+
+impl<T: StrictType, C: Checksum> StrictType for Checked<T, C> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl<T: StrictDumb, C: Checksum> StrictDumb for Checked<T, C> {
+    fn strict_dumb() -> Self { Checked::new(T::strict_dumb()) }
+}
+
+impl<T: StrictEncode, C: Checksum> StrictEncode for Checked<T, C> {
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let checksum = {
+            let tee = ChecksumWriter {
+                inner: unsafe { writer.raw_writer() },
+                checksum: C::default(),
+            };
+            self.value.strict_encode(StrictWriter::with(tee))?.unbox().checksum.finalize()
+        };
+        checksum.strict_encode(writer)
+    }
+}
+
+impl<T: StrictDecode, C: Checksum> StrictDecode for Checked<T, C> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let tee = ChecksumReader::<_, C>::new(unsafe { reader.raw_reader() });
+        let mut tee_reader = StrictReader::with(tee);
+        let value = T::strict_decode(&mut tee_reader)?;
+        let actual = tee_reader.unbox().checksum.finalize();
+        let expected = C::Output::strict_decode(reader)?;
+        if expected != actual {
+            return Err(DecodeError::ChecksumMismatch {
+                expected: expected.as_ref().to_vec(),
+                actual: actual.as_ref().to_vec(),
+            });
+        }
+        Ok(Checked::new(value))
+    }
+}
+
+/// Writer passing the data to the inner writer and to the checksum.
+struct ChecksumWriter<W: WriteRaw, C: Checksum> {
+    inner: W,
+    checksum: C,
+}
+
+impl<W: WriteRaw, C: Checksum> WriteRaw for ChecksumWriter<W, C> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        self.inner.write_raw::<MAX_LEN>(bytes)?;
+        self.checksum.update(bytes);
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { self.inner.offset() }
+
+    #[cfg(feature = "trace")]
+    fn enter_scope(&mut self, kind: ScopeKind, lib: &'static str, name: Option<TypeName>) {
+        self.inner.enter_scope(kind, lib, name)
+    }
+
+    #[cfg(feature = "trace")]
+    fn exit_scope(&mut self, kind: ScopeKind) { self.inner.exit_scope(kind) }
+}
+
+/// Reader passing the data read from the inner reader to the checksum.
+///
+/// The reader can be rewound as far as the inner reader; the rewound data are
+/// already added to the checksum, so they are not added again once re-read.
+struct ChecksumReader<R: ReadRaw, C: Checksum> {
+    inner: R,
+    checksum: C,
+    /// Number of the rewound bytes, which are added to the checksum already.
+    rewound: usize,
+}
+
+impl<R: ReadRaw, C: Checksum> ChecksumReader<R, C> {
+    fn new(inner: R) -> Self {
+        ChecksumReader {
+            inner,
+            checksum: C::default(),
+            rewound: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let reread = self.rewound.min(data.len());
+        self.rewound -= reread;
+        self.checksum.update(&data[reread..]);
+    }
+}
+
+impl<R: ReadRaw, C: Checksum> ReadRaw for ChecksumReader<R, C> {
+    fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let data = self.inner.read_raw::<MAX_LEN>(len)?;
+        self.update(&data);
+        Ok(data)
+    }
+
+    fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        let data = self.inner.read_raw_array::<LEN>()?;
+        self.update(&data);
+        Ok(data)
+    }
+
+    #[cfg(feature = "bytes")]
+    fn read_raw_bytes<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Bytes> {
+        let data = self.inner.read_raw_bytes::<MAX_LEN>(len)?;
+        self.update(&data);
+        Ok(data)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let start = buf.len();
+        self.inner.read_raw_into::<MAX_LEN>(len, buf)?;
+        self.update(&buf[start..]);
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { self.inner.offset() }
+
+    fn peek_byte(&mut self) -> io::Result<u8> { self.inner.peek_byte() }
+
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        self.inner.rewind(len)?;
+        self.rewound += len;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallString;
+
+    use super::*;
+    use crate::test::{decode, decode_err, encode};
+    use crate::SliceReader;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        id: u32,
+        name: SmallString,
+    }
+
+    fn record() -> Record {
+        Record {
+            id: 0xCAFE,
+            name: SmallString::try_from(s!("123456789")).unwrap(),
+        }
+    }
+
+    #[test]
+    fn crc32() {
+        let mut crc = Crc32::default();
+        crc.update(b"123456789");
+        assert_eq!(crc.finalize(), 0xCBF43926u32.to_le_bytes());
+        assert_eq!(Crc32::default().finalize(), [0; 4]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let checked = Checked::<Record>::from(record());
+        let data = encode(&checked);
+        let inner = encode(&record());
+        assert_eq!(data[..inner.len()], inner);
+        let mut crc = Crc32::default();
+        crc.update(&inner);
+        assert_eq!(data[inner.len()..], crc.finalize());
+
+        let decoded: Checked<Record> = decode(&data);
+        assert_eq!(decoded.id, 0xCAFE);
+        assert_eq!(decoded.into_inner(), record());
+    }
+
+    #[test]
+    fn rewind() {
+        let data = *b"123456789";
+        let mut reader = ChecksumReader::<_, Crc32>::new(SliceReader::new::<9>(&data));
+        assert_eq!(reader.read_raw::<9>(4).unwrap(), b"1234");
+        reader.rewind(3).unwrap();
+        assert_eq!(reader.read_raw_array::<2>().unwrap(), *b"23");
+        let mut buf = vec![];
+        reader.read_raw_into::<9>(6, &mut buf).unwrap();
+        assert_eq!(buf, b"456789");
+        assert!(reader.rewind(10).is_err());
+        assert_eq!(reader.checksum.finalize(), 0xCBF43926u32.to_le_bytes());
+    }
+
+    #[test]
+    fn payload_corruption() {
+        let data = encode(&Checked::<Record>::from(record()));
+        let checksum = &data[data.len() - 4..];
+        for bit in 0..(data.len() - 4) * 8 {
+            let mut corrupted = data.clone();
+            corrupted[bit / 8] ^= 1 << (bit % 8);
+            match (bit / 8, decode_err::<Checked<Record>>(&corrupted)) {
+                (_, DecodeError::ChecksumMismatch { expected, actual }) => {
+                    assert_ne!(actual, expected);
+                    if !(4..6).contains(&(bit / 8)) {
+                        assert_eq!(expected, checksum);
+                    }
+                }
+                // corrupted string length or string data
+                (4 | 5, DecodeError::Io(_) | DecodeError::Utf8(_))
+                | (6.., DecodeError::Utf8(_)) => {}
+                (_, err) => panic!("unexpected error {err:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn checksum_corruption() {
+        let data = encode(&Checked::<Record>::from(record()));
+        let mut corrupted = data.clone();
+        *corrupted.last_mut().unwrap() ^= 0x80;
+        let err = decode_err::<Checked<Record>>(&corrupted);
+        assert_eq!(err, DecodeError::ChecksumMismatch {
+            expected: corrupted[data.len() - 4..].to_vec(),
+            actual: data[data.len() - 4..].to_vec(),
+        });
+
+        let err = decode_err::<Checked<Record>>(&data[..data.len() - 1]);
+        assert!(matches!(err, DecodeError::Io(_)));
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn sha256() {
+        use sha2::Digest;
+
+        let checked = Checked::<_, Sha256>::new(record());
+        let data = encode(&checked);
+        let inner = encode(&record());
+        assert_eq!(data[inner.len()..], sha2::Sha256::digest(&inner)[..]);
+        assert_eq!(decode::<Checked<Record, Sha256>>(&data), checked);
+
+        let mut corrupted = data.clone();
+        corrupted[0] ^= 1;
+        let err = decode_err::<Checked<Record, Sha256>>(&corrupted);
+        assert!(matches!(err, DecodeError::ChecksumMismatch { .. }));
+    }
+}
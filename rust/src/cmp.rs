@@ -0,0 +1,289 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of the comparison types from [`core::cmp`].
+//!
+//! [`Reverse`] only inverts the ordering of the values and is encoded exactly
+//! as the wrapped value, having the same type name, such that the in-memory
+//! ordering doesn't leak into the wire format. It must be noted that ordered
+//! collections, like `Confined<BTreeMap<Reverse<K>, V>>`, are encoded in the
+//! order of their keys, which is the descending order of `K`, and are decoded
+//! only from the data in that order. Thus, while the type layout of such a
+//! collection is the same as of the collection over `K`, their encodings are
+//! not interchangeable: the data of one are rejected by the decoder of the
+//! other as having a broken key order, unless they have less than two keys.
+//!
+//! [`Ordering`] is encoded as an enum named `Ordering` with the tags following
+//! the order of the values: `0` for `less`, `1` for `equal` and `2` for
+//! `greater`; other tags are rejected during decoding. Since [`Ordering`]
+//! doesn't implement [`crate::StrictDumb`], when it is used as a field of a
+//! type deriving it, the field must provide its dumb value explicitly with
+//! `#[strict_type(dumb = ...)]` attribute, and it can't be an item of a
+//! collection or an option.
+//!
+//! The strings used as the keys of ordered collections are ordered byte-wise,
+//! by the lexicographic order of their UTF-8 representations, which is the
+//! order of their characters' code points; see [`canonical_cmp`]. This is
+//! the `Ord` order of [`str`] and of all the string types of this crate,
+//! including restricted strings and identifiers, thus the maps and sets keyed
+//! by them are encoded in this order and are decoded only from the data in
+//! this order. Neither case folding nor locale-specific collation is ever
+//! applied.
+
+use alloc::string::String;
+use core::cmp::{Ordering, Reverse};
+
+use crate::{
+    io, DecodeError, StrictDecode, StrictEncode, StrictType, TypeName, TypedRead, TypedWrite,
+    LIB_NAME_STD,
+};
+
+/// Compares the strings in the canonical order of the string keys of maps and
+/// sets: byte-wise, such that `"Z"` goes before `"a"` and ASCII strings go
+/// before the strings starting with non-ASCII characters. Application code
+/// sorting string keys before encoding must use this order.
+///
+/// The lengths of the strings are not taken into account, unlike with the
+/// encodings of the strings, which are prefixed by their length: `"b"` goes
+/// after `"aa"`.
+pub fn canonical_cmp(a: &str, b: &str) -> Ordering { a.as_bytes().cmp(b.as_bytes()) }
+
+impl<T: StrictType> StrictType for Reverse<T> {
+    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+}
+impl<T: StrictEncode> StrictEncode for Reverse<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.strict_encode(writer)
+    }
+}
+impl<T: StrictDecode> StrictDecode for Reverse<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        T::strict_decode(reader).map(Reverse)
+    }
+
+    fn strict_display(&self) -> Option<String> { self.0.strict_display() }
+}
+
+// `Ordering` doesn't implement `Default` and can't be `StrictDumb`, thus it is
+// encoded through this mirror type.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, tags = repr, into_u8, try_from_u8, rename = "Ordering")]
+#[strict_type(crate = crate)]
+#[repr(u8)]
+pub(crate) enum Order {
+    #[default]
+    Less = 0,
+    Equal = 1,
+    Greater = 2,
+}
+
+impl From<Ordering> for Order {
+    fn from(ordering: Ordering) -> Self {
+        match ordering {
+            Ordering::Less => Order::Less,
+            Ordering::Equal => Order::Equal,
+            Ordering::Greater => Order::Greater,
+        }
+    }
+}
+
+impl From<Order> for Ordering {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Less => Ordering::Less,
+            Order::Equal => Ordering::Equal,
+            Order::Greater => Ordering::Greater,
+        }
+    }
+}
+
+impl StrictType for Ordering {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Ordering")) }
+}
+impl StrictEncode for Ordering {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_enum::<Order>(Order::from(*self))
+    }
+}
+impl StrictDecode for Ordering {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_enum::<Order>().map(Ordering::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::fmt::Debug;
+
+    use amplify::confinement::{TinyOrdMap, TinyOrdSet, TinyString};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::test::{decode, encode, encoding};
+    use crate::test_helpers::assert_key_order_consistency;
+    use crate::{
+        ConfinedCharString, KnownVariants, StrictDumb, StrictReader, TinyArcString, TypeName,
+    };
+
+    const KEYS: [&str; 7] = ["a", "Z", "\u{e9}", "z", "\u{df}", "ab", "\u{1F600}"];
+
+    /// Sorts the keys converted into `K`, checking that `Ord` of `K` agrees
+    /// with [`canonical_cmp`].
+    fn check_ord<K: Ord + Debug>(keys: &[&str], convert: impl Fn(&str) -> K) {
+        let mut sorted = keys.to_vec();
+        sorted.sort_by(|a, b| canonical_cmp(a, b));
+        let mut converted = keys.iter().map(|key| convert(key)).collect::<Vec<_>>();
+        converted.sort();
+        assert_eq!(converted, sorted.iter().map(|key| convert(key)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn canonical_order() {
+        assert_eq!(canonical_cmp("Z", "a"), Ordering::Less);
+        assert_eq!(canonical_cmp("z", "\u{e9}"), Ordering::Less);
+        assert_eq!(canonical_cmp("\u{df}", "\u{e9}"), Ordering::Less);
+        assert_eq!(canonical_cmp("aa", "b"), Ordering::Less);
+        assert_eq!(canonical_cmp("ab", "a"), Ordering::Greater);
+        assert_eq!(canonical_cmp("a", "a"), Ordering::Equal);
+
+        check_ord(&KEYS, |key| String::from(key));
+        check_ord(&KEYS, |key| TinyString::try_from(String::from(key)).unwrap());
+        check_ord(&KEYS, |key| TinyArcString::try_from(Arc::<str>::from(key)).unwrap());
+        check_ord(&KEYS, |key| ConfinedCharString::<1, 4>::try_from(String::from(key)).unwrap());
+        check_ord(&["Aa", "AZ", "A_", "A1", "B", "Ab"], |key| TypeName::try_from_str(key).unwrap());
+    }
+
+    #[test]
+    fn string_keys() {
+        let map = TinyOrdMap::from_iter_checked(
+            KEYS.iter()
+                .take(5)
+                .enumerate()
+                .map(|(no, key)| (TinyString::try_from(String::from(*key)).unwrap(), no as u8)),
+        );
+        #[rustfmt::skip]
+        encoding(&map, [
+            5,
+            1, b'Z', 1,
+            1, b'a', 0,
+            1, b'z', 3,
+            2, 0xC3, 0x9F, 4,
+            2, 0xC3, 0xA9, 2,
+        ]);
+
+        // Case-insensitive and length-first orders are rejected
+        let mut reader = StrictReader::in_memory::<16>([2, 1, b'a', 0, 1, b'Z', 1]);
+        let err = TinyOrdMap::<TinyString, u8>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::BrokenMapOrder);
+        let mut reader = StrictReader::in_memory::<16>([2, 1, b'b', 2, b'a', b'a']);
+        let err = TinyOrdSet::<TinyString>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::BrokenSetOrder);
+        let mut reader = StrictReader::in_memory::<16>([2, 2, 0xC3, 0xA9, 1, b'z']);
+        let err = TinyOrdSet::<ConfinedCharString<1, 4>>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::BrokenSetOrder);
+        let mut reader = StrictReader::in_memory::<16>([2, 2, b'A', b'a', 2, b'A', b'Z']);
+        let err = TinyOrdSet::<TypeName>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::BrokenSetOrder);
+
+        let set = TinyOrdSet::from_iter_checked([tn!("Aa"), tn!("AZ")]);
+        encoding(&set, [2, 2, b'A', b'Z', 2, b'A', b'a']);
+        let set: TinyOrdSet<TinyArcString> = decode([2, 1, b'z', 2, 0xC3, 0xA9]);
+        assert_eq!(set.iter().map(|s| s.as_str()).collect::<Vec<_>>(), ["z", "\u{e9}"]);
+    }
+
+    proptest! {
+        #[test]
+        fn canonical_order_matches_code_points(a: String, b: String) {
+            prop_assert_eq!(canonical_cmp(&a, &b), a.chars().cmp(b.chars()));
+            prop_assert_eq!(canonical_cmp(&a, &b), a.cmp(&b));
+        }
+    }
+
+    #[test]
+    fn reverse() {
+        encoding(&Reverse(0x0102u16), [0x02, 0x01]);
+        assert_eq!(encode(&Reverse(7u64)), encode(&7u64));
+        assert_eq!(decode::<Reverse<u32>>([5, 0, 0, 0]), Reverse(5));
+        assert_eq!(Reverse::<u32>::strict_name(), u32::strict_name());
+        assert_eq!(Reverse::<Ordering>::strict_name(), Some(tn!("Ordering")));
+    }
+
+    #[test]
+    fn reverse_map_keys() {
+        let map = TinyOrdMap::from_iter_checked([(Reverse(1u8), 1u8), (Reverse(2), 2)]);
+        // keys are written in the descending order of the wrapped values
+        encoding(&map, [2, 2, 2, 1, 1]);
+        let mut reader = StrictReader::in_memory::<16>([2, 2, 2, 1, 1]);
+        let err = TinyOrdMap::<u8, u8>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::BrokenMapOrder);
+    }
+
+    #[test]
+    fn ordering() {
+        encoding(&Ordering::Less, [0]);
+        encoding(&Ordering::Equal, [1]);
+        encoding(&Ordering::Greater, [2]);
+        assert_eq!(Ordering::strict_name(), Some(tn!("Ordering")));
+        assert_key_order_consistency([Ordering::Greater, Ordering::Equal, Ordering::Less]);
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Audit {
+        id: u8,
+        #[strict_type(dumb = Ordering::Equal)]
+        result: Ordering,
+    }
+
+    #[test]
+    fn ordering_field() {
+        let audit = Audit {
+            id: 7,
+            result: Ordering::Greater,
+        };
+        encoding(&audit, [7, 2]);
+        assert_eq!(Audit::strict_dumb().result, Ordering::Equal);
+    }
+
+    #[test]
+    fn ordering_invalid_tag() {
+        for tag in [3u8, 0xFF] {
+            let mut reader = StrictReader::in_memory::<1>([tag]);
+            let err = Ordering::strict_decode(&mut reader).unwrap_err();
+            assert_eq!(err, DecodeError::EnumTagNotKnown {
+                type_name: s!("Ordering"),
+                tag,
+                variants: KnownVariants(vec![
+                    (0, vname!("less")),
+                    (1, vname!("equal")),
+                    (2, vname!("greater")),
+                ]),
+            });
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`LinkedList`] and [`BinaryHeap`], which can't be
+//! wrapped into [`Confined`](amplify::confinement::Confined) and are confined
+//! by [`ConfinedLinkedList`] and [`ConfinedBinaryHeap`] instead.
+//!
+//! Both are encoded exactly as `Confined<Vec<T>, MIN_LEN, MAX_LEN>` with the
+//! same items:
+//! - [`ConfinedLinkedList`] writes its items in the iteration order;
+//! - [`ConfinedBinaryHeap`] writes its items sorted in ascending order, not in the internal order
+//!   of the heap, thus heaps with the same items have the same encoding regardless of the order of
+//!   their insertion. Decoding fails with [`DecodeError::BrokenSetOrder`] error if the items don't
+//!   follow in ascending order; equal items are allowed.
+
+use alloc::collections::{BinaryHeap, LinkedList};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::iter;
+use core::ops::Deref;
+
+use amplify::confinement;
+
+use crate::embedded::{alloc_items, decode_item, prealloc};
+use crate::reader::list_name;
+use crate::{
+    io, ConfinedDumb, DecodeError, InvalidLength, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictType, TypeName, TypedRead, TypedWrite, LIB_EMBEDDED,
+};
+
+/// [`LinkedList`] which number of items is confined to be between `MIN_LEN`
+/// and `MAX_LEN`.
+///
+/// The number of items is checked when the list is constructed.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ConfinedLinkedList<T, const MIN_LEN: usize, const MAX_LEN: usize>(LinkedList<T>);
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedLinkedList<T, MIN_LEN, MAX_LEN> {
+    /// Returns a reference to the wrapped list.
+    pub fn as_inner(&self) -> &LinkedList<T> { &self.0 }
+
+    /// Returns the wrapped list, releasing it from the confinement.
+    pub fn release(self) -> LinkedList<T> { self.0 }
+}
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> Deref
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    type Target = LinkedList<T>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T, const MAX_LEN: usize> Default for ConfinedLinkedList<T, 0, MAX_LEN> {
+    fn default() -> Self { ConfinedLinkedList(LinkedList::new()) }
+}
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<LinkedList<T>>
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(list: LinkedList<T>) -> Result<Self, Self::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(list.len())?;
+        Ok(ConfinedLinkedList(list))
+    }
+}
+
+impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(MIN_LEN, MAX_LEN) }
+}
+impl<T: StrictEncode + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
+        writer.write_collection_from_iter::<T, MIN_LEN, MAX_LEN>(self.iter())
+    }
+}
+impl<T: StrictDecode, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut list = LinkedList::new();
+        for index in 0..len {
+            list.push_back(decode_item(reader, index)?);
+        }
+        Self::try_from(list).map_err(DecodeError::from)
+    }
+}
+
+impl<T: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for ConfinedLinkedList<T, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self {
+        Self::try_from(LinkedList::from_iter(iter::repeat_with(T::strict_dumb).take(MIN_LEN)))
+            .expect("minimal length exceeds maximal length")
+    }
+}
+
+/// [`BinaryHeap`] which number of items is confined to be between `MIN_LEN`
+/// and `MAX_LEN`.
+///
+/// The number of items is checked when the heap is constructed. Heaps are
+/// equal if they have the same items, regardless of their internal order; the
+/// comparison doesn't allocate, but takes quadratic time.
+#[derive(Clone, Debug)]
+pub struct ConfinedBinaryHeap<T, const MIN_LEN: usize, const MAX_LEN: usize>(BinaryHeap<T>);
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN> {
+    /// Returns a reference to the wrapped heap.
+    pub fn as_inner(&self) -> &BinaryHeap<T> { &self.0 }
+
+    /// Returns the wrapped heap, releasing it from the confinement.
+    pub fn release(self) -> BinaryHeap<T> { self.0 }
+}
+
+impl<T: Ord, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN> {
+    /// Number of the items equal to `item`.
+    fn count(&self, item: &T) -> usize { self.0.iter().filter(|other| *other == item).count() }
+
+    /// Items of the heap in ascending order, in which they are encoded.
+    fn sorted(&self) -> Vec<&T> {
+        let mut items = self.0.iter().collect::<Vec<_>>();
+        items.sort();
+        items
+    }
+}
+
+impl<T: Ord, const MIN_LEN: usize, const MAX_LEN: usize> PartialEq
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().all(|item| self.count(item) == other.count(item))
+    }
+}
+impl<T: Ord, const MIN_LEN: usize, const MAX_LEN: usize> Eq
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+}
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> Deref
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    type Target = BinaryHeap<T>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T: Ord, const MAX_LEN: usize> Default for ConfinedBinaryHeap<T, 0, MAX_LEN> {
+    fn default() -> Self { ConfinedBinaryHeap(BinaryHeap::new()) }
+}
+
+impl<T, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<BinaryHeap<T>>
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(heap: BinaryHeap<T>) -> Result<Self, Self::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(heap.len())?;
+        Ok(ConfinedBinaryHeap(heap))
+    }
+}
+
+impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(MIN_LEN, MAX_LEN) }
+}
+impl<T: StrictEncode + StrictDumb + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
+        writer.write_collection_from_iter::<T, MIN_LEN, MAX_LEN>(self.sorted().into_iter())
+    }
+}
+impl<T: StrictDecode + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut items = Vec::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            let item = decode_item(reader, index)?;
+            if matches!(items.last(), Some(last) if last > &item) {
+                return Err(DecodeError::BrokenSetOrder);
+            }
+            items.push(item);
+        }
+        Self::try_from(BinaryHeap::from(items)).map_err(DecodeError::from)
+    }
+}
+
+impl<T: StrictDumb + Ord, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for ConfinedBinaryHeap<T, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self {
+        Self::try_from(BinaryHeap::from_iter(iter::repeat_with(T::strict_dumb).take(MIN_LEN)))
+            .expect("minimal length exceeds maximal length")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+
+    use super::*;
+    use crate::test::{encode, encoding, encoding_roundtrip};
+    use crate::StrictReader;
+
+    #[test]
+    fn linked_list() {
+        let list = ConfinedLinkedList::<u16, 1, 4>::try_from(LinkedList::from([3, 1, 2])).unwrap();
+        let vec = Confined::<Vec<u16>, 1, 4>::try_from(vec![3, 1, 2]).unwrap();
+        assert_eq!(encode(&list), encode(&vec));
+        encoding(&list, [3, 3, 0, 1, 0, 2, 0]);
+        encoding_roundtrip(&ConfinedLinkedList::<u8, 0, 300>::default());
+
+        let dumb = ConfinedLinkedList::<u8, 1, 4>::confined_dumb();
+        assert_eq!(dumb.release(), LinkedList::from([0]));
+        assert_eq!(
+            ConfinedLinkedList::<u8, 1, 2>::try_from(LinkedList::from([1, 2, 3])).unwrap_err(),
+            confinement::Error::Oversize { len: 3, max_len: 2 }
+        );
+        let mut reader = StrictReader::in_memory::<8>([0]);
+        assert!(matches!(
+            ConfinedLinkedList::<u8, 1, 4>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Undersize {
+                declared_len: 0,
+                min_len: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn binary_heap() {
+        let mut ascending = BinaryHeap::new();
+        let mut descending = BinaryHeap::new();
+        for item in [1u16, 2, 2, 7, 9] {
+            ascending.push(item);
+        }
+        for item in [9u16, 7, 2, 2, 1] {
+            descending.push(item);
+        }
+        assert_ne!(ascending.as_slice(), descending.as_slice());
+        let ascending = ConfinedBinaryHeap::<u16, 0, 8>::try_from(ascending).unwrap();
+        let descending = ConfinedBinaryHeap::<u16, 0, 8>::try_from(descending).unwrap();
+        assert_eq!(encode(&ascending), encode(&descending));
+        assert_eq!(ascending, descending);
+        let other = BinaryHeap::from([1u16, 1, 2, 7, 9]);
+        assert_ne!(ascending, ConfinedBinaryHeap::try_from(other).unwrap());
+        encoding(&ascending, [5, 1, 0, 2, 0, 2, 0, 7, 0, 9, 0]);
+        let vec = Confined::<Vec<u16>, 0, 8>::try_from(vec![1, 2, 2, 7, 9]).unwrap();
+        assert_eq!(encode(&ascending), encode(&vec));
+
+        let dumb = ConfinedBinaryHeap::<u8, 2, 4>::confined_dumb();
+        assert_eq!(dumb.release().into_sorted_vec(), [0, 0]);
+        assert_eq!(
+            ConfinedBinaryHeap::<u8, 1, 4>::try_from(BinaryHeap::new()).unwrap_err(),
+            confinement::Error::Undersize { len: 0, min_len: 1 }
+        );
+    }
+
+    #[test]
+    fn binary_heap_order() {
+        let mut reader = StrictReader::in_memory::<8>([3, 1, 7, 2]);
+        assert_eq!(
+            ConfinedBinaryHeap::<u8, 0, 8>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::BrokenSetOrder
+        );
+    }
+}
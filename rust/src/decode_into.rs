@@ -0,0 +1,399 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding into existing values, which reuses the memory they have already
+//! allocated.
+//!
+//! Decoding many values of the same type, like the messages of a protocol,
+//! allocates new collections and strings for each of the values.
+//! [`StrictDecodeInto::strict_decode_into`] decodes the data into an existing
+//! value instead: its vectors and strings are refilled within their existing
+//! capacity, the items which are already present are decoded into
+//! recursively, and integers are overwritten. The decoded value is always
+//! equal to the one returned by [`StrictDecode::strict_decode`].
+//!
+//! Types which can't reuse their memory, like maps, sets and enums, implement
+//! the trait with its default method, which decodes a new value and replaces
+//! the existing one. The trait can be derived for structures, which fields are
+//! decoded into one by one, with `#[derive(StrictDecodeInto)]`; for other
+//! data types the derived implementation uses the default method.
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+    NonZeroU32, NonZeroU64, NonZeroU8,
+};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use amplify::ascii::AsciiString;
+use amplify::confinement::Confined;
+use amplify::num::{i1024, i256, i512, u1024, u24, u256, u40, u48, u512, u56};
+use amplify::Array;
+
+use crate::embedded::{alloc_items, decode_item};
+use crate::stl::AsciiSym;
+use crate::{
+    i24, i40, i48, i56, Bounded, BoundedInt, Byte, CharsetError, ConfinedDumb, DecodeError,
+    PathSegment, RString, RestrictedCharSet, StrictDecode, StrictDumb, TypedRead, Utf8Policy,
+};
+
+/// Decoding of the value into an existing one, reusing the memory allocated
+/// by it.
+///
+/// The trait can be derived with `#[derive(StrictDecodeInto)]`; the derived
+/// implementation of a structure decodes into the fields implementing the
+/// trait and replaces the other fields with their newly decoded values. Types
+/// which don't reuse their memory implement it with an empty
+/// `impl StrictDecodeInto for Type {}` block.
+pub trait StrictDecodeInto: StrictDecode {
+    /// Decodes the value into `self`, reusing its collections and strings.
+    ///
+    /// On failure `self` is left in an unspecified, but valid state: it may
+    /// keep the old value, get some of the decoded parts or the dumb values of
+    /// some of its parts. The default implementation decodes a new value and
+    /// replaces `self` with it, thus leaving `self` unchanged on failure.
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        *self = Self::strict_decode(reader)?;
+        Ok(())
+    }
+}
+
+/// Field of a structure deriving `StrictDecodeInto`, which is decoded into if
+/// its type implements [`StrictDecodeInto`], and is replaced with a newly
+/// decoded value otherwise; see [`DecodeFieldInto`] and
+/// [`DecodeFieldReplacing`].
+pub struct FieldInto<'a, T>(pub &'a mut T);
+
+/// Decoding of a [`FieldInto`] of a [`StrictDecodeInto`] type, which is
+/// selected by the method resolution before [`DecodeFieldReplacing`], since it
+/// doesn't require auto-referencing of the receiver.
+pub trait DecodeFieldInto {
+    fn decode_field_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError>;
+}
+
+impl<T: StrictDecodeInto> DecodeFieldInto for FieldInto<'_, T> {
+    fn decode_field_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        self.0.strict_decode_into(reader)
+    }
+}
+
+/// Decoding of a [`FieldInto`] of a type which doesn't implement
+/// [`StrictDecodeInto`], replacing the field with a newly decoded value.
+pub trait DecodeFieldReplacing {
+    fn decode_field_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError>;
+}
+
+impl<T: StrictDecode> DecodeFieldReplacing for &mut FieldInto<'_, T> {
+    fn decode_field_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        *self.0 = T::strict_decode(reader)?;
+        Ok(())
+    }
+}
+
+/// Decodes an item of a collection or an array at a given `index` into the
+/// existing `item`.
+fn decode_item_into<T: StrictDecodeInto>(
+    reader: &mut impl TypedRead,
+    index: usize,
+    item: &mut T,
+) -> Result<(), DecodeError> {
+    reader.in_context(|| PathSegment::Item(index), |r| r.in_depth(|r| item.strict_decode_into(r)))
+}
+
+macro_rules! decode_replacing {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl StrictDecodeInto for $ty {} )+
+    };
+}
+
+decode_replacing!(
+    u8,
+    u16,
+    u24,
+    u32,
+    u40,
+    u48,
+    u56,
+    u64,
+    u128,
+    u256,
+    u512,
+    u1024,
+    i8,
+    i16,
+    i24,
+    i32,
+    i40,
+    i48,
+    i56,
+    i64,
+    i128,
+    i256,
+    i512,
+    i1024,
+    f32,
+    f64,
+    bool,
+    (),
+    Byte
+);
+decode_replacing!(
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128
+);
+
+impl<T: BoundedInt, const MIN: i128, const MAX: i128> StrictDecodeInto for Bounded<T, MIN, MAX> {}
+
+impl<T: StrictDecode> StrictDecodeInto for Option<T> {}
+
+impl<T: StrictDecodeInto> StrictDecodeInto for Box<T> {
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        (**self).strict_decode_into(reader)
+    }
+}
+
+impl<T: StrictDecodeInto + StrictDumb, const LEN: usize> StrictDecodeInto for [T; LEN] {
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        for (index, item) in self.iter_mut().enumerate() {
+            decode_item_into(reader, index, item)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: StrictDecode + StrictDumb + Copy, const LEN: usize, const REVERSE_STR: bool>
+    StrictDecodeInto for Array<T, LEN, REVERSE_STR>
+{
+}
+
+/// The items present in the vector are decoded into, while the missing ones
+/// are decoded and pushed; the vector length always stays within the bounds.
+/// Items dropped when the vector shrinks don't keep their memory, thus values
+/// of the same shape reuse the memory best.
+impl<T: StrictDecodeInto, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<Vec<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        while self.len() > len {
+            self.remove(self.len() - 1)?;
+        }
+        for (index, item) in self.iter_mut().enumerate() {
+            decode_item_into(reader, index, item)?;
+        }
+        for index in self.len()..len {
+            self.push(decode_item(reader, index)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: StrictDecodeInto, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        while self.len() > len {
+            self.remove(self.len() - 1)?;
+        }
+        for (index, item) in self.iter_mut().enumerate() {
+            decode_item_into(reader, index, item)?;
+        }
+        for index in self.len()..len {
+            self.push_back(decode_item(reader, index)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// The string is taken out for the time of decoding, leaving the dumb value in
+/// its place, which remains there on failure.
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<String, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        let mut bytes = mem::replace(self, Self::confined_dumb()).release().into_bytes();
+        unsafe { reader.read_string_into::<Self, MIN_LEN, MAX_LEN>(&mut bytes)? };
+        let s = match reader.utf8_policy() {
+            Utf8Policy::Strict => String::from_utf8(bytes)?,
+            Utf8Policy::Lossy => String::from_utf8(bytes)
+                .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        };
+        *self = Confined::try_from(s)?;
+        Ok(())
+    }
+}
+
+/// The string is taken out for the time of decoding, leaving the dumb value in
+/// its place, which remains there on failure.
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<AsciiString, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode_into(&mut self, reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        let mut bytes: Vec<u8> = mem::replace(self, Self::confined_dumb()).release().into();
+        unsafe { reader.read_string_into::<Self, MIN_LEN, MAX_LEN>(&mut bytes)? };
+        if let Some(pos) = bytes.iter().position(|byte| !byte.is_ascii()) {
+            return Err(CharsetError::with::<AsciiSym>(bytes[pos], pos).into());
+        }
+        // SAFETY: all the bytes are checked to be ASCII characters above
+        let s = unsafe { AsciiString::from_ascii_unchecked(bytes) };
+        *self = Confined::try_from(s)?;
+        Ok(())
+    }
+}
+
+impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MAX_LEN: usize>
+    StrictDecodeInto for RString<C, C1, MIN_LEN, MAX_LEN>
+{
+}
+
+impl<T: StrictDecode + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
+{
+}
+
+impl<
+        K: StrictDecode + Ord + Hash + StrictDumb,
+        V: StrictDecode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictDecodeInto for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+{
+}
+
+#[cfg(feature = "std")]
+impl<T: StrictDecode + Ord + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecodeInto
+    for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+}
+
+#[cfg(feature = "std")]
+impl<
+        K: StrictDecode + Ord + Hash + StrictDumb,
+        V: StrictDecode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictDecodeInto for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{self, TinyAscii, TinyString, TinyVec};
+
+    use super::*;
+    use crate::test::{decode, encode};
+    use crate::StrictReader;
+
+    fn decode_into<T: StrictDecodeInto>(value: &mut T, data: &[u8]) -> Result<(), DecodeError> {
+        let mut reader = StrictReader::from_slice::<{ usize::MAX }>(data);
+        value.strict_decode_into(&mut reader)?;
+        assert!(reader.remaining().is_empty());
+        Ok(())
+    }
+
+    fn strings(items: &[&str]) -> TinyVec<TinyString> {
+        TinyVec::try_from_iter(items.iter().map(|s| TinyString::try_from(s.to_string()).unwrap()))
+            .unwrap()
+    }
+
+    #[test]
+    fn vec_reuse() {
+        let mut value = strings(&["first item", "second item", "third item"]);
+        let ptrs = value.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+        let vec_ptr = value.as_ptr();
+
+        let shorter = strings(&["one", "two"]);
+        decode_into(&mut value, &encode(&shorter)).unwrap();
+        assert_eq!(value, shorter);
+        assert_eq!(value.as_ptr(), vec_ptr);
+        assert_eq!(value.iter().map(|s| s.as_ptr()).collect::<Vec<_>>(), ptrs[..2]);
+
+        let longer = strings(&["1", "2", "3", "4"]);
+        decode_into(&mut value, &encode(&longer)).unwrap();
+        assert_eq!(value, longer);
+        assert_eq!(value[0].as_ptr(), ptrs[0]);
+        assert_eq!(value[1].as_ptr(), ptrs[1]);
+
+        let mut value = TinyVec::<u16>::with_capacity(8);
+        let vec_ptr = value.as_ptr();
+        decode_into(&mut value, &[3, 1, 0, 2, 0, 3, 0]).unwrap();
+        assert_eq!(value.as_slice(), &[1, 2, 3]);
+        assert_eq!(value.as_ptr(), vec_ptr);
+    }
+
+    #[test]
+    fn same_as_decode() {
+        let deque = Confined::<VecDeque<u8>, 0, 8>::try_from(VecDeque::from(vec![4, 5])).unwrap();
+        let data = encode(&deque);
+        let mut value = Confined::<VecDeque<u8>, 0, 8>::try_from_iter(0..6).unwrap();
+        decode_into(&mut value, &data).unwrap();
+        assert_eq!(value, decode::<Confined<VecDeque<u8>, 0, 8>>(&data));
+
+        let ascii = TinyAscii::try_from(AsciiString::from_ascii("strict").unwrap()).unwrap();
+        let mut value = TinyAscii::default();
+        decode_into(&mut value, &encode(&ascii)).unwrap();
+        assert_eq!(value, ascii);
+
+        let mut value = [7u32; 3];
+        decode_into(&mut value, &encode(&[1u32, 2, 3])).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+
+        let map = confinement::TinyOrdMap::try_from_iter([(1u8, 2u8)]).unwrap();
+        let mut value = confinement::TinyOrdMap::try_from_iter([(3u8, 4u8)]).unwrap();
+        decode_into(&mut value, &encode(&map)).unwrap();
+        assert_eq!(value, map);
+    }
+
+    #[test]
+    fn failure_state() {
+        // the failure happens on the third item, when the vector is truncated
+        let mut value = TinyVec::<u16>::try_from_iter([1, 2, 3, 4]).unwrap();
+        assert!(decode_into(&mut value, &[3, 9, 0, 8, 0]).is_err());
+        assert_eq!(value.as_slice(), &[9, 8, 3]);
+
+        type Name = Confined<String, 2, 8>;
+        let mut value = Name::try_from(s!("name")).unwrap();
+        let err = decode_into(&mut value, &[2, 0xFF, 0xFF]).unwrap_err();
+        assert!(matches!(err, DecodeError::Utf8(_)));
+        assert_eq!(value, Name::confined_dumb());
+
+        let mut value = Name::try_from(s!("name")).unwrap();
+        decode_into(&mut value, &[2, b'o', b'k']).unwrap();
+        assert_eq!(value.as_str(), "ok");
+    }
+}
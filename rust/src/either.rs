@@ -0,0 +1,185 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`Either`], available with `either` feature.
+//!
+//! `Either<L, R>` is encoded as a union with `left` newtype variant having tag
+//! `0` and `right` newtype variant having tag `1`, i.e. exactly as an enum
+//! with two single-field variants deriving the strict encoding. Other tags are
+//! rejected on decoding. Its type name is derived from the names of both type
+//! parameters.
+//!
+//! Like [`Result`], [`Either`] doesn't implement [`Default`], thus it can't be
+//! [`StrictDumb`] and [`StrictUnion`]. Its dumb value within the derived types
+//! is given with `#[strict_type(dumb = Either::Left(strict_dumb!()))]`
+//! attribute; it can't be a field of an enum variant.
+
+use ::either::Either;
+
+use crate::{
+    io, DecodeError, DefineUnion, ReadTuple, ReadUnion, StrictDecode, StrictDumb, StrictEncode,
+    StrictSum, StrictType, StrictUnion, TypeName, TypedRead, TypedWrite, WriteUnion, LIB_NAME_STD,
+};
+
+// The union is read and written via this mirror type, which implements
+// `StrictDumb` and shares the type name with the `Either`.
+enum EitherUnion<L, R> {
+    Left(L),
+    Right(R),
+}
+impl<L: StrictDumb, R> StrictDumb for EitherUnion<L, R> {
+    fn strict_dumb() -> Self { EitherUnion::Left(L::strict_dumb()) }
+}
+impl<L: StrictType, R: StrictType> StrictType for EitherUnion<L, R> {
+    const STRICT_LIB_NAME: &'static str = <Either<L, R> as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { Either::<L, R>::strict_name() }
+}
+impl<L: StrictType, R: StrictType> StrictSum for EitherUnion<L, R> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = Either::<L, R>::ALL_VARIANTS;
+    fn variant_name(&self) -> &'static str {
+        match self {
+            EitherUnion::Left(_) => "left",
+            EitherUnion::Right(_) => "right",
+        }
+    }
+}
+impl<L: StrictType + StrictDumb, R: StrictType> StrictUnion for EitherUnion<L, R> {}
+impl<L, R> From<EitherUnion<L, R>> for Either<L, R> {
+    fn from(value: EitherUnion<L, R>) -> Self {
+        match value {
+            EitherUnion::Left(val) => Either::Left(val),
+            EitherUnion::Right(val) => Either::Right(val),
+        }
+    }
+}
+
+impl<L: StrictType, R: StrictType> StrictType for Either<L, R> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+}
+impl<L: StrictType, R: StrictType> StrictSum for Either<L, R> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(0u8, "left"), (1u8, "right")];
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Either::Left(_) => "left",
+            Either::Right(_) => "right",
+        }
+    }
+}
+impl<L, R> StrictEncode for Either<L, R>
+where
+    L: StrictEncode + StrictDumb,
+    R: StrictEncode + StrictDumb,
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_union::<EitherUnion<L, R>>(|u| {
+            let u = u
+                .define_newtype::<L>(vname!("left"))
+                .define_newtype::<R>(vname!("right"))
+                .complete();
+
+            Ok(match self {
+                Either::Left(val) => u.write_newtype(vname!("left"), val),
+                Either::Right(val) => u.write_newtype(vname!("right"), val),
+            }?
+            .complete())
+        })
+    }
+}
+impl<L, R> StrictDecode for Either<L, R>
+where
+    L: StrictDecode + StrictDumb,
+    R: StrictDecode,
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader
+            .read_union(|field_name, u| match field_name.as_str() {
+                "left" => u.read_tuple(|r| r.read_field().map(EitherUnion::Left)),
+                "right" => u.read_tuple(|r| r.read_field().map(EitherUnion::Right)),
+                _ => unreachable!("unknown either field"),
+            })
+            .map(Either::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::test::{encode, encoding};
+    use crate::{KnownVariants, StrictReader};
+
+    // hand-written equivalent of `Either<u16, TinyString>`
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Choice {
+        Left(u16),
+        Right(TinyString),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Entry {
+        id: u8,
+        #[strict_type(dumb = Either::Left(strict_dumb!()))]
+        value: Either<u16, TinyString>,
+    }
+
+    fn text(s: &str) -> TinyString { TinyString::try_from(s.to_owned()).unwrap() }
+
+    #[test]
+    fn roundtrip() {
+        encoding(&Either::<u16, TinyString>::Left(0x0102), [0x00, 0x02, 0x01]);
+        encoding(&Either::<u16, TinyString>::Right(text("ab")), [0x01, 0x02, b'a', b'b']);
+        encoding(
+            &Entry {
+                id: 7,
+                value: Either::Right(text("")),
+            },
+            [0x07, 0x01, 0x00],
+        );
+        assert_eq!(Entry::strict_dumb().value, Either::Left(0));
+        assert_eq!(Either::<u16, u8>::strict_name(), Some(tn!("Eitheru16u8")));
+        assert_eq!(Either::<u8, u16>::strict_name(), Some(tn!("Eitheru8u16")));
+    }
+
+    #[test]
+    fn equivalence() {
+        assert_eq!(encode(&Either::<u16, TinyString>::Left(0xCAFE)), encode(&Choice::Left(0xCAFE)));
+        assert_eq!(
+            encode(&Either::<u16, TinyString>::Right(text("either"))),
+            encode(&Choice::Right(text("either")))
+        );
+        assert_eq!(Either::<u16, TinyString>::ALL_VARIANTS, Choice::ALL_VARIANTS);
+    }
+
+    #[test]
+    fn unknown_tag() {
+        for tag in [2u8, 0xFF] {
+            let mut reader = StrictReader::in_memory::<4>([tag, 0, 0]);
+            let err = Either::<u16, TinyString>::strict_decode(&mut reader).unwrap_err();
+            assert!(matches!(err, DecodeError::UnionTagNotKnown { tag: t, variants, .. }
+                if t == tag && variants == KnownVariants(vec![(0, vname!("left")), (1, vname!("right"))])));
+        }
+    }
+}
@@ -19,26 +19,71 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeMap, BTreeSet};
-use std::hash::Hash;
-use std::io;
-use std::num::{NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::hash::Hash;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+    NonZeroU32, NonZeroU64, NonZeroU8, Saturating, Wrapping,
+};
+use core::{iter, mem};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
 
-use amplify::ascii::AsciiString;
-use amplify::confinement::Confined;
+use amplify::ascii::{AsciiChar, AsciiString};
+use amplify::confinement::{Collection, Confined, SmallString, SmallVec, U16};
 #[cfg(feature = "float")]
 use amplify::num::apfloat::{ieee, Float};
 use amplify::num::{i1024, i256, i512, u1024, u24, u256, u40, u48, u512, u56};
 use amplify::{Array, Wrapper};
 
+use crate::layout::layout_dumb;
+use crate::reader::{array_name, decoded_name, list_name, map_name, set_name, unicode_name};
 use crate::stl::AsciiSym;
 use crate::{
-    DecodeError, DefineUnion, Primitive, RString, ReadRaw, ReadTuple, ReadUnion, RestrictedCharSet,
-    Sizing, StrictDecode, StrictDumb, StrictEncode, StrictProduct, StrictStruct, StrictSum,
-    StrictTuple, StrictType, StrictUnion, TypeName, TypedRead, TypedWrite, WriteRaw, WriteTuple,
-    WriteUnion, LIB_EMBEDDED,
+    i24, i40, i48, i56, io, CharsetError, ConfinedDumb, DecodeError, DefineUnion, InvalidLength,
+    PathSegment, Primitive, RString, ReadRaw, ReadTuple, ReadUnion, RestrictedCharSet, Sizing,
+    StrictDecode, StrictDumb, StrictEncode, StrictMaybeDumb, StrictProduct, StrictStruct,
+    StrictSum, StrictTuple, StrictType, StrictUnion, TypeName, TypedRead, TypedWrite, Utf8Policy,
+    WriteRaw, WriteTuple, WriteUnion, LIB_EMBEDDED, LIB_NAME_STD,
 };
 
+/// Decodes an item of a collection or an array at a given `index`.
+pub(crate) fn decode_item<T: StrictDecode>(
+    reader: &mut impl TypedRead,
+    index: usize,
+) -> Result<T, DecodeError> {
+    reader.in_context(|| PathSegment::Item(index), |r| r.in_depth(|r| T::strict_decode(r)))
+}
+
+/// Accounts the memory of `len` items of type `T` of a decoded collection; see
+/// [`TypedRead::alloc`].
+pub(crate) fn alloc_items<T>(reader: &mut impl TypedRead, len: usize) -> Result<(), DecodeError> {
+    reader.alloc(len.saturating_mul(mem::size_of::<T>()))
+}
+
+/// Maximal number of bytes preallocated for a decoded collection or a string
+/// before its items are read.
+///
+/// A collection is allocated with the capacity for all the items declared by
+/// its length prefix, unless they occupy more than this number of bytes (for
+/// strings, the bytes are read in chunks of this size). Longer collections
+/// grow while their items are decoded, such that a corrupted or malicious
+/// length prefix can't cause a large allocation not backed by the actual data.
+pub const PREALLOC_LIMIT: usize = 0x10_0000;
+
+/// Number of items of type `T` for which a collection of `len` items is
+/// preallocated; see [`PREALLOC_LIMIT`].
+pub(crate) fn prealloc<T>(len: usize) -> usize {
+    len.min(PREALLOC_LIMIT / mem::size_of::<T>().max(1))
+}
+
 pub trait DecodeRawLe: Sized {
     fn decode_raw_le(reader: &mut (impl ReadRaw + ?Sized)) -> Result<Self, DecodeError>;
 }
@@ -62,8 +107,46 @@ impl StrictEncode for Byte {
     }
 }
 
+/// Little-endian bytes of the items, collected into a buffer.
+fn le_bytes<T, const N: usize>(items: &[T], to_le: impl Fn(&T) -> [u8; N]) -> Cow<'_, [u8]> {
+    let mut buf = Vec::with_capacity(items.len() * N);
+    for item in items {
+        buf.extend_from_slice(&to_le(item));
+    }
+    Cow::Owned(buf)
+}
+
+/// Little-endian bytes of the primitive integers, which on little-endian
+/// targets are the memory of the items themselves.
+///
+/// # Safety
+///
+/// `T` must be a primitive integer type of `N` bytes.
+#[cfg(target_endian = "little")]
+unsafe fn native_le_bytes<T, const N: usize>(
+    items: &[T],
+    _to_le: impl Fn(&T) -> [u8; N],
+) -> Cow<'_, [u8]> {
+    debug_assert_eq!(mem::size_of::<T>(), N);
+    Cow::Borrowed(core::slice::from_raw_parts(items.as_ptr().cast(), mem::size_of_val(items)))
+}
+
+#[cfg(target_endian = "big")]
+unsafe fn native_le_bytes<T, const N: usize>(
+    items: &[T],
+    to_le: impl Fn(&T) -> [u8; N],
+) -> Cow<'_, [u8]> {
+    le_bytes(items, to_le)
+}
+
 macro_rules! encode_num {
     ($ty:ty, $id:ident) => {
+        encode_num!($ty, $id, le_bytes);
+    };
+    ($ty:ty, $id:ident,native) => {
+        encode_num!($ty, $id, native_le_bytes);
+    };
+    ($ty:ty, $id:ident, $le_bytes:ident) => {
         impl $crate::StrictType for $ty {
             const STRICT_LIB_NAME: &'static str = $crate::LIB_EMBEDDED;
             fn strict_name() -> Option<TypeName> { Some(tn!(stringify!($id))) }
@@ -76,6 +159,15 @@ macro_rules! encode_num {
                 }
                 Ok(writer)
             }
+            fn strict_encode_items<W: TypedWrite>(items: &[Self], mut writer: W) -> io::Result<W> {
+                #[allow(unused_unsafe)]
+                unsafe {
+                    writer = writer.register_primitive(Primitive::$id);
+                    let bytes = $le_bytes(items, |item| item.to_le_bytes());
+                    writer.raw_writer().write_raw::<{ usize::MAX }>(bytes)?;
+                }
+                Ok(writer)
+            }
         }
         impl $crate::DecodeRawLe for $ty {
             fn decode_raw_le(reader: &mut (impl ReadRaw + ?Sized)) -> Result<Self, DecodeError> {
@@ -87,12 +179,17 @@ macro_rules! encode_num {
             fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
                 Self::decode_raw_le(unsafe { reader.raw_reader() })
             }
+
+            fn strict_display(&self) -> Option<String> { Some(self.to_string()) }
         }
     };
 }
 
 macro_rules! encode_nonzero {
     ($ty:ty, $p:ty, $id:ident) => {
+        impl $crate::ForeignDumb for $ty {
+            fn foreign_dumb() -> Self { Self::new(1).expect("one is not zero") }
+        }
         impl $crate::StrictType for $ty {
             const STRICT_LIB_NAME: &'static str = $crate::LIB_EMBEDDED;
             fn strict_name() -> Option<TypeName> { Some(tn!(stringify!($id))) }
@@ -113,6 +210,8 @@ macro_rules! encode_nonzero {
                 let v = <$p>::from_le_bytes(buf);
                 Self::new(v).ok_or(DecodeError::ZeroNatural)
             }
+
+            fn strict_display(&self) -> Option<String> { Some(self.to_string()) }
         }
     };
 }
@@ -150,33 +249,86 @@ macro_rules! encode_float {
     };
 }
 
-encode_num!(u8, U8);
-encode_num!(u16, U16);
+// Native floats are encoded as little-endian IEEE-754 bits. To keep encoding
+// deterministic, all NaN values (regardless of their sign and payload) are
+// encoded as a single canonical quiet NaN; during decoding any NaN is accepted
+// and converted into the canonical one. Negative zero is preserved. Thus, unlike
+// the other built-in types, floats may be decoded from non-canonical data,
+// which is exempted from `test_helpers::assert_mutation_resilience` checks.
+macro_rules! encode_native_float {
+    ($ty:ty, $bits:ty, $nan:literal, $id:ident) => {
+        impl $crate::StrictType for $ty {
+            const STRICT_LIB_NAME: &'static str = $crate::LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> { Some(tn!(stringify!($id))) }
+        }
+        impl $crate::StrictEncode for $ty {
+            fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+                let bits: $bits = if self.is_nan() { $nan } else { self.to_bits() };
+                unsafe {
+                    writer = writer.register_primitive(Primitive::$id);
+                    writer.raw_writer().write_raw_array(bits.to_le_bytes())?;
+                }
+                Ok(writer)
+            }
+        }
+        impl $crate::StrictDecode for $ty {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                let buf = unsafe {
+                    reader.raw_reader().read_raw_array::<{ <$bits>::BITS as usize / 8 }>()?
+                };
+                let val = <$ty>::from_bits(<$bits>::from_le_bytes(buf));
+                Ok(if val.is_nan() { <$ty>::from_bits($nan) } else { val })
+            }
+        }
+    };
+}
+
+encode_num!(u8, U8, native);
+encode_num!(u16, U16, native);
 encode_num!(u24, U24);
-encode_num!(u32, U32);
+encode_num!(u32, U32, native);
 encode_num!(u40, U40);
 encode_num!(u48, U48);
 encode_num!(u56, U56);
-encode_num!(u64, U64);
-encode_num!(u128, U128);
+encode_num!(u64, U64, native);
+encode_num!(u128, U128, native);
 encode_num!(u256, U256);
 encode_num!(u512, U512);
 encode_num!(u1024, U1024);
 
-encode_num!(i8, I8);
-encode_num!(i16, I16);
-encode_num!(i32, I32);
-encode_num!(i64, I64);
-encode_num!(i128, I128);
+encode_num!(i8, I8, native);
+encode_num!(i16, I16, native);
+encode_num!(i24, I24);
+encode_num!(i32, I32, native);
+encode_num!(i40, I40);
+encode_num!(i48, I48);
+encode_num!(i56, I56);
+encode_num!(i64, I64, native);
+encode_num!(i128, I128, native);
 encode_num!(i256, I256);
 encode_num!(i512, I512);
 encode_num!(i1024, I1024);
 
 encode_nonzero!(NonZeroU8, u8, N8);
 encode_nonzero!(NonZeroU16, u16, N16);
-encode_nonzero!(NonZeroU32, u32, U32);
-encode_nonzero!(NonZeroU64, u64, U64);
-encode_nonzero!(NonZeroU128, u128, U128);
+encode_nonzero!(NonZeroU32, u32, N32);
+encode_nonzero!(NonZeroU64, u64, N64);
+encode_nonzero!(NonZeroU128, u128, N128);
+
+// There are no signed non-zero primitives, so these are encoded as signed
+// integers which are checked for not being zero during decoding.
+encode_nonzero!(NonZeroI8, i8, I8);
+encode_nonzero!(NonZeroI16, i16, I16);
+encode_nonzero!(NonZeroI32, i32, I32);
+encode_nonzero!(NonZeroI64, i64, I64);
+encode_nonzero!(NonZeroI128, i128, I128);
+
+encode_native_float!(f32, u32, 0x7FC0_0000, F32);
+encode_native_float!(f64, u64, 0x7FF8_0000_0000_0000, F64);
+#[cfg(feature = "half")]
+encode_native_float!(half::f16, u16, 0x7E00, F16);
+#[cfg(feature = "half")]
+encode_native_float!(half::bf16, u16, 0x7FC0, F16B);
 
 encode_float!(ieee::Half, 2, F16);
 encode_float!(ieee::Single, 4, F32);
@@ -185,49 +337,166 @@ encode_float!(ieee::X87DoubleExtended, 10, F80);
 encode_float!(ieee::Quad, 16, F128);
 encode_float!(ieee::Oct, 32, F256);
 
-impl<T> StrictType for Box<T>
-where T: StrictType
-{
-    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
-}
-impl<T> StrictSum for Box<T>
-where T: StrictSum
-{
-    const ALL_VARIANTS: &'static [(u8, &'static str)] = T::ALL_VARIANTS;
-    fn variant_name(&self) -> &'static str { self.as_ref().variant_name() }
+// Smart pointers are invisible both on the wire and in the type system: they
+// are encoded exactly as the value they point to.
+macro_rules! encode_pointer {
+    ($ptr:ident) => {
+        impl<T> StrictType for $ptr<T>
+        where T: StrictType
+        {
+            const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+            fn strict_name() -> Option<TypeName> { T::strict_name() }
+            fn strict_anonymous_name() -> String { T::strict_anonymous_name() }
+        }
+        impl<T> StrictSum for $ptr<T>
+        where T: StrictSum
+        {
+            const ALL_VARIANTS: &'static [(u8, &'static str)] = T::ALL_VARIANTS;
+            fn variant_name(&self) -> &'static str { self.as_ref().variant_name() }
+        }
+        impl<T> StrictProduct for $ptr<T> where T: Default + StrictProduct {}
+        impl<T> StrictUnion for $ptr<T> where T: Default + StrictUnion {}
+        impl<T> StrictTuple for $ptr<T>
+        where T: Default + StrictTuple
+        {
+            const FIELD_COUNT: u8 = T::FIELD_COUNT;
+        }
+        impl<T> StrictStruct for $ptr<T>
+        where T: Default + StrictStruct
+        {
+            const ALL_FIELDS: &'static [&'static str] = T::ALL_FIELDS;
+        }
+        impl<T> StrictEncode for $ptr<T>
+        where T: StrictEncode
+        {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                self.as_ref().strict_encode(writer)
+            }
+        }
+        impl<T> StrictDecode for $ptr<T>
+        where T: StrictDecode
+        {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                T::strict_decode(reader).map($ptr::new)
+            }
+        }
+    };
 }
-impl<T> StrictProduct for Box<T> where T: Default + StrictProduct {}
-impl<T> StrictUnion for Box<T> where T: Default + StrictUnion {}
-impl<T> StrictTuple for Box<T>
-where T: Default + StrictTuple
-{
-    const FIELD_COUNT: u8 = T::FIELD_COUNT;
+
+encode_pointer!(Box);
+encode_pointer!(Rc);
+encode_pointer!(Arc);
+
+// Arithmetic-mode wrappers only change the behaviour of operations and are
+// encoded exactly as the wrapped value. Their dumb value comes from `Default`,
+// which matches the dumb value of the integer types.
+macro_rules! encode_arithmetic {
+    ($wrapper:ident) => {
+        impl<T> StrictType for $wrapper<T>
+        where T: StrictType
+        {
+            const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+            fn strict_name() -> Option<TypeName> { T::strict_name() }
+            fn strict_anonymous_name() -> String { T::strict_anonymous_name() }
+        }
+        impl<T> StrictEncode for $wrapper<T>
+        where T: StrictEncode
+        {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                self.0.strict_encode(writer)
+            }
+        }
+        impl<T> StrictDecode for $wrapper<T>
+        where T: StrictDecode
+        {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                T::strict_decode(reader).map($wrapper)
+            }
+        }
+    };
 }
-impl<T> StrictStruct for Box<T>
-where T: Default + StrictStruct
+
+encode_arithmetic!(Wrapping);
+encode_arithmetic!(Saturating);
+
+// Copy-on-write values are encoded as the owned value: the type system sees no
+// difference between borrowed and owned data, and encoding never clones the
+// borrowed value. Decoding always produces the owned variant.
+impl<B> StrictType for Cow<'_, B>
+where B: StrictType + Clone
 {
-    const ALL_FIELDS: &'static [&'static str] = T::ALL_FIELDS;
+    const STRICT_LIB_NAME: &'static str = B::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { B::strict_name() }
+    fn strict_anonymous_name() -> String { B::strict_anonymous_name() }
 }
-impl<T> StrictEncode for Box<T>
-where T: StrictEncode
+impl<B> StrictEncode for Cow<'_, B>
+where B: StrictEncode + Clone
 {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
         self.as_ref().strict_encode(writer)
     }
 }
-impl<T> StrictDecode for Box<T>
-where T: StrictDecode
+impl<B> StrictDecode for Cow<'_, B>
+where B: StrictDecode + Clone
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        T::strict_decode(reader).map(Box::new)
+        B::strict_decode(reader).map(Cow::Owned)
+    }
+}
+
+// Unconfined copy-on-write strings and slices are encoded as the owned
+// `SmallString` and `SmallVec<T>`, i.e. with up to `u16::MAX` bytes or items;
+// the borrowed data are written in place. Longer data fail to encode, and the
+// data for other limits must be wrapped into `Cow<Confined<..>>` instead.
+impl StrictType for Cow<'_, str> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { SmallString::strict_name() }
+    fn strict_anonymous_name() -> String { SmallString::strict_anonymous_name() }
+}
+impl StrictEncode for Cow<'_, str> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<SmallString, 0, U16>(self.len())?;
+        unsafe {
+            writer.register_unicode(Sizing::new(0, U16 as u64)).write_string::<U16>(self.as_bytes())
+        }
+    }
+}
+impl StrictDecode for Cow<'_, str> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        SmallString::strict_decode(reader).map(|s| Cow::Owned(s.release()))
+    }
+
+    fn strict_display(&self) -> Option<String> { Some(self.to_string()) }
+}
+
+impl<T: StrictType + Clone> StrictType for Cow<'_, [T]> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { SmallVec::<T>::strict_name() }
+    fn strict_anonymous_name() -> String { SmallVec::<T>::strict_anonymous_name() }
+}
+impl<T: StrictEncode + StrictDumb + Clone> StrictEncode for Cow<'_, [T]> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_collection_from_iter::<T, 0, U16>(self.iter())
+    }
+}
+impl<T: StrictDecode + Clone> StrictDecode for Cow<'_, [T]> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        SmallVec::<T>::strict_decode(reader).map(|v| Cow::Owned(v.release()))
     }
 }
 
+// `Option` is encoded as a union with `none` unit variant having tag `0` and
+// `some` newtype variant having tag `1`. Each layer of nested options writes
+// its own tag, so `Option<Option<u8>>` values `None`, `Some(None)` and
+// `Some(Some(7))` are encoded as `00`, `01 00` and `01 01 07`; any other tag
+// byte is rejected when decoding at any layer. The same holds for options
+// within union variants, which follow the variant tag.
 impl<T> StrictType for Option<T>
 where T: StrictType
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { format!("{}?", decoded_name::<T>()) }
 }
 impl<T> StrictSum for Option<T>
 where T: StrictType
@@ -264,102 +533,245 @@ impl<T: StrictDecode> StrictDecode for Option<T> {
     }
 }
 
-impl StrictType for () {
-    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
-    fn strict_name() -> Option<TypeName> { None }
+// `Result` doesn't implement `Default` and can't be `StrictDumb`, which is
+// required from unions; thus the union is read and written via this mirror
+// type, which shares the type name with the `Result`.
+enum ResultUnion<T, E> {
+    Ok(T),
+    Err(E),
 }
-impl StrictEncode for () {
-    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
-        Ok(unsafe { writer.register_primitive(Primitive::UNIT) })
+impl<T: StrictDumb, E> StrictDumb for ResultUnion<T, E> {
+    fn strict_dumb() -> Self { ResultUnion::Ok(T::strict_dumb()) }
+}
+impl<T: StrictType, E: StrictType> StrictType for ResultUnion<T, E> {
+    const STRICT_LIB_NAME: &'static str = <Result<T, E> as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { Result::<T, E>::strict_name() }
+}
+impl<T: StrictType, E: StrictType> StrictSum for ResultUnion<T, E> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = Result::<T, E>::ALL_VARIANTS;
+    fn variant_name(&self) -> &'static str {
+        match self {
+            ResultUnion::Ok(_) => "ok",
+            ResultUnion::Err(_) => "err",
+        }
     }
 }
-impl StrictDecode for () {
-    fn strict_decode(_reader: &mut impl TypedRead) -> Result<Self, DecodeError> { Ok(()) }
+impl<T: StrictType + StrictDumb, E: StrictType> StrictUnion for ResultUnion<T, E> {}
+impl<T, E> From<ResultUnion<T, E>> for Result<T, E> {
+    fn from(value: ResultUnion<T, E>) -> Self {
+        match value {
+            ResultUnion::Ok(val) => Ok(val),
+            ResultUnion::Err(err) => Err(err),
+        }
+    }
 }
 
-impl<A: StrictType, B: StrictType> StrictType for (A, B) {
-    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
-    fn strict_name() -> Option<TypeName> { None }
+// `Result` is encoded as a union with `ok` variant having tag `0` and `err`
+// variant having tag `1`. Its type name is derived from the names of both type
+// parameters.
+impl<T, E> StrictType for Result<T, E>
+where
+    T: StrictType,
+    E: StrictType,
+{
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
 }
-impl<A: StrictType + Default, B: StrictType + Default> StrictProduct for (A, B) {}
-impl<A: StrictType + Default, B: StrictType + Default> StrictTuple for (A, B) {
-    const FIELD_COUNT: u8 = 2;
+impl<T, E> StrictSum for Result<T, E>
+where
+    T: StrictType,
+    E: StrictType,
+{
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(0u8, "ok"), (1u8, "err")];
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Ok(_) => "ok",
+            Err(_) => "err",
+        }
+    }
 }
-impl<A: StrictEncode + Default, B: StrictEncode + Default> StrictEncode for (A, B) {
+impl<T, E> StrictEncode for Result<T, E>
+where
+    T: StrictEncode + StrictDumb,
+    E: StrictEncode + StrictMaybeDumb,
+{
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
-        writer.write_tuple::<Self>(|w| Ok(w.write_field(&self.0)?.write_field(&self.1)?.complete()))
+        writer.write_union::<ResultUnion<T, E>>(|u| {
+            let u = u
+                .define_newtype::<T>(vname!("ok"))
+                .define_newtype::<E>(vname!("err"))
+                .complete();
+
+            Ok(match self {
+                Ok(val) => u.write_newtype(vname!("ok"), val),
+                Err(err) => u.write_newtype(vname!("err"), err),
+            }?
+            .complete())
+        })
     }
 }
-impl<A: StrictDecode + Default, B: StrictDecode + Default> StrictDecode for (A, B) {
+impl<T, E> StrictDecode for Result<T, E>
+where
+    T: StrictDecode + StrictDumb,
+    E: StrictDecode,
+{
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        reader.read_tuple(|r| {
-            let a = r.read_field()?;
-            let b = r.read_field()?;
-            Ok((a, b))
-        })
+        reader
+            .read_union(|field_name, u| match field_name.as_str() {
+                "ok" => u.read_tuple(|r| r.read_field().map(ResultUnion::Ok)),
+                "err" => u.read_tuple(|r| r.read_field().map(ResultUnion::Err)),
+                _ => unreachable!("unknown result field"),
+            })
+            .map(Result::from)
     }
 }
 
-impl<A: StrictType, B: StrictType, C: StrictType> StrictType for (A, B, C) {
-    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
-    fn strict_name() -> Option<TypeName> { None }
+/// Type without values, which is a placeholder for the impossible cases of
+/// generic types, like `Result<T, Void>` of an operation which can't fail.
+///
+/// Unlike [`Infallible`], which also has strict encoding, `Void` implements
+/// [`StrictMaybeDumb`], as required from the fields of tuples, structs and
+/// union variants, thus it can be used in `Result` and in derived unions. Since
+/// no dumb value exists, `Void` is not [`StrictDumb`], and the discovery of
+/// type layouts describes it as a union without variants. Decoding of `Void`
+/// always fails with [`DecodeError::Uninhabited`] error.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Void {}
+
+impl From<Infallible> for Void {
+    fn from(never: Infallible) -> Self { match never {} }
 }
-impl<A: StrictType + Default, B: StrictType + Default, C: StrictType + Default> StrictProduct
-    for (A, B, C)
-{
+impl StrictMaybeDumb for Void {
+    fn try_strict_dumb() -> Option<Self> { None }
 }
-impl<A: StrictType + Default, B: StrictType + Default, C: StrictType + Default> StrictTuple
-    for (A, B, C)
-{
-    const FIELD_COUNT: u8 = 3;
+
+// Uninhabited types can't be encoded, since they have no values, and fail to
+// be decoded.
+macro_rules! encode_uninhabited {
+    ($ty:ty) => {
+        impl StrictType for $ty {
+            const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+        }
+        impl StrictEncode for $ty {
+            fn strict_encode<W: TypedWrite>(&self, _writer: W) -> io::Result<W> { match *self {} }
+        }
+        impl StrictDecode for $ty {
+            fn strict_decode(_reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                Err(DecodeError::Uninhabited(String::from(core::any::type_name::<Self>())))
+            }
+        }
+    };
 }
-impl<A: StrictEncode + Default, B: StrictEncode + Default, C: StrictEncode + Default> StrictEncode
-    for (A, B, C)
-{
+
+encode_uninhabited!(Void);
+encode_uninhabited!(Infallible);
+
+impl StrictType for () {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { Primitive::UNIT.to_string() }
+}
+impl StrictEncode for () {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
-        writer.write_tuple::<Self>(|w| {
-            Ok(w.write_field(&self.0)?.write_field(&self.1)?.write_field(&self.2)?.complete())
-        })
+        Ok(unsafe { writer.register_primitive(Primitive::UNIT) })
     }
 }
-impl<A: StrictDecode + Default, B: StrictDecode + Default, C: StrictDecode + Default> StrictDecode
-    for (A, B, C)
-{
-    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        reader.read_tuple(|r| {
-            let a = r.read_field()?;
-            let b = r.read_field()?;
-            let c = r.read_field()?;
-            Ok((a, b, c))
-        })
+impl StrictDecode for () {
+    fn strict_decode(_reader: &mut impl TypedRead) -> Result<Self, DecodeError> { Ok(()) }
+}
+
+/// Name of a tuple with the given element types: `Tuple` followed by the names
+/// of the elements, like `TupleU16U8` for `(u16, u8)`. Anonymous elements are
+/// named by the letters and digits of their strict type notation, such that
+/// `[Byte ^ 4]` becomes `Byte4`. Tuples which names don't fit into a type name
+/// stay anonymous.
+fn tuple_name(elements: &[String]) -> Option<TypeName> {
+    let mut name = String::from("Tuple");
+    for element in elements {
+        name.extend(element.chars().filter(char::is_ascii_alphanumeric));
     }
+    TypeName::try_from(name).ok()
+}
+
+// Tuples are embedded types named after their elements; see `tuple_name`. The
+// implementations cover all tuple arities for which the standard library
+// provides `Default`, which is required for `StrictDumb`.
+macro_rules! encode_tuple {
+    ($count:literal: $($ty:ident $field:ident $idx:tt),+) => {
+        impl<$($ty: StrictType),+> StrictType for ($($ty,)+) {
+            const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> {
+                tuple_name(&[$(decoded_name::<$ty>()),+])
+            }
+            fn strict_anonymous_name() -> String {
+                format!("({})", [$(decoded_name::<$ty>()),+].join(", "))
+            }
+        }
+        impl<$($ty: StrictType + Default),+> StrictProduct for ($($ty,)+) {}
+        impl<$($ty: StrictType + Default),+> StrictTuple for ($($ty,)+) {
+            const FIELD_COUNT: u8 = $count;
+        }
+        impl<$($ty: StrictEncode + Default),+> StrictEncode for ($($ty,)+) {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                writer.write_tuple::<Self>(|w| Ok(w$(.write_field(&self.$idx)?)+.complete()))
+            }
+        }
+        impl<$($ty: StrictDecode + Default),+> StrictDecode for ($($ty,)+) {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_tuple(|r| {
+                    $(let $field = r.read_field()?;)+
+                    Ok(($($field,)+))
+                })
+            }
+        }
+    };
 }
 
-impl<T: StrictType + Copy + StrictDumb, const LEN: usize> StrictType for [T; LEN] {
+encode_tuple!(2: A a 0, B b 1);
+encode_tuple!(3: A a 0, B b 1, C c 2);
+encode_tuple!(4: A a 0, B b 1, C c 2, D d 3);
+encode_tuple!(5: A a 0, B b 1, C c 2, D d 3, E e 4);
+encode_tuple!(6: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5);
+encode_tuple!(7: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6);
+encode_tuple!(8: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7);
+encode_tuple!(9: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7, I i 8);
+encode_tuple!(10: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7, I i 8, J j 9);
+encode_tuple!(11: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7, I i 8, J j 9, K k 10);
+encode_tuple!(
+    12: A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7, I i 8, J j 9, K k 10, L l 11
+);
+
+// Arrays of any length are supported, however the type system can't represent
+// arrays longer than `u16::MAX` elements, so their encoding fails. Decoding
+// doesn't require `T: Copy` or `T: Default`; elements decoded before a failure
+// are dropped normally.
+impl<T: StrictType + StrictDumb, const LEN: usize> StrictType for [T; LEN] {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { array_name::<T>(LEN) }
 }
-impl<T: StrictEncode + Copy + StrictDumb, const LEN: usize> StrictEncode for [T; LEN] {
+impl<T: StrictEncode + StrictDumb, const LEN: usize> StrictEncode for [T; LEN] {
     fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
-        for item in self {
-            writer = item.strict_encode(writer)?;
-        }
+        let len = u16::try_from(LEN).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        writer = unsafe { writer.write_items(self)? };
         Ok(unsafe {
             if T::strict_name() == u8::strict_name() {
-                writer.register_array(&Byte::strict_dumb(), LEN as u16)
+                writer.register_array(&Byte::strict_dumb(), len)
             } else {
-                writer.register_array(&T::strict_dumb(), LEN as u16)
+                writer.register_array(&layout_dumb::<T>(), len)
             }
         })
     }
 }
-impl<T: StrictDecode + Copy + StrictDumb, const LEN: usize> StrictDecode for [T; LEN] {
+impl<T: StrictDecode + StrictDumb, const LEN: usize> StrictDecode for [T; LEN] {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let mut ar = [T::strict_dumb(); LEN];
-        for c in ar.iter_mut() {
-            *c = T::strict_decode(reader)?;
+        let mut items = Vec::with_capacity(LEN);
+        for index in 0..LEN {
+            items.push(decode_item(reader, index)?);
+        }
+        match <[T; LEN]>::try_from(items) {
+            Ok(ar) => Ok(ar),
+            Err(_) => unreachable!("vector length always matches array length"),
         }
-        Ok(ar)
     }
 }
 
@@ -368,6 +780,7 @@ impl<T: StrictType + StrictDumb + Copy, const LEN: usize, const REVERSE_STR: boo
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { array_name::<T>(LEN) }
 }
 impl<T: StrictEncode + StrictDumb + Copy, const LEN: usize, const REVERSE_STR: bool> StrictEncode
     for Array<T, LEN, REVERSE_STR>
@@ -387,11 +800,13 @@ impl<T: StrictDecode + StrictDumb + Copy, const LEN: usize, const REVERSE_STR: b
 impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for Confined<String, MIN_LEN, MAX_LEN> {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { unicode_name(MIN_LEN, MAX_LEN) }
 }
 impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
     for Confined<String, MIN_LEN, MAX_LEN>
 {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
         unsafe {
             writer
                 .register_unicode(Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
@@ -403,10 +818,16 @@ impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
     for Confined<String, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let bytes = unsafe { reader.read_string::<MAX_LEN>()? };
-        let s = String::from_utf8(bytes)?;
+        let bytes = unsafe { reader.read_string::<Self, MIN_LEN, MAX_LEN>()? };
+        let s = match reader.utf8_policy() {
+            Utf8Policy::Strict => String::from_utf8(bytes)?,
+            Utf8Policy::Lossy => String::from_utf8(bytes)
+                .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        };
         Confined::try_from(s).map_err(DecodeError::from)
     }
+
+    fn strict_display(&self) -> Option<String> { Some(String::from(self.as_str())) }
 }
 
 impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType
@@ -414,11 +835,13 @@ impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<AsciiSym>(MIN_LEN, MAX_LEN) }
 }
 impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
     for Confined<AsciiString, MIN_LEN, MAX_LEN>
 {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
         unsafe {
             writer
                 .register_list(
@@ -433,10 +856,17 @@ impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
     for Confined<AsciiString, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let bytes = unsafe { reader.read_string::<MAX_LEN>()? };
-        let s = AsciiString::from_ascii(bytes).map_err(|err| err.ascii_error())?;
+        let bytes = unsafe { reader.read_string::<Self, MIN_LEN, MAX_LEN>()? };
+        if !bytes.is_ascii() {
+            let pos = bytes.iter().position(|byte| !byte.is_ascii()).unwrap_or_default();
+            return Err(CharsetError::with::<AsciiSym>(bytes[pos], pos).into());
+        }
+        // SAFETY: all the bytes are checked to be ASCII characters above
+        let s = unsafe { AsciiString::from_ascii_unchecked(bytes) };
         Confined::try_from(s).map_err(DecodeError::from)
     }
+
+    fn strict_display(&self) -> Option<String> { Some(String::from(self.as_str())) }
 }
 
 impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MAX_LEN: usize>
@@ -444,6 +874,10 @@ impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MA
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String {
+        let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        format!("[{}, {}{sizing}]", decoded_name::<C>(), decoded_name::<C1>())
+    }
 }
 impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MAX_LEN: usize>
     StrictDumb for RString<C, C1, MIN_LEN, MAX_LEN>
@@ -451,8 +885,9 @@ impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MA
     fn strict_dumb() -> Self {
         Self::try_from(format!(
             "{}{}",
-            C1::strict_dumb(),
-            String::from_utf8(vec![C::strict_dumb().into(); MIN_LEN]).expect("dumb")
+            C::strict_dumb(),
+            String::from_utf8(vec![C1::strict_dumb().into(); MIN_LEN.saturating_sub(1)])
+                .expect("dumb")
         ))
         .expect("dumb")
     }
@@ -465,6 +900,7 @@ impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MA
             MIN_LEN, 0,
             "Restricted string type can't have minimum length equal to zero"
         );
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.as_bytes().len())?;
         let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
         unsafe {
             writer
@@ -477,9 +913,15 @@ impl<C: RestrictedCharSet, C1: RestrictedCharSet, const MIN_LEN: usize, const MA
     StrictDecode for RString<C, C1, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let bytes = unsafe { reader.read_string::<MAX_LEN>()? };
-        RString::try_from(bytes).map_err(|e| DecodeError::DataIntegrityError(e.to_string()))
+        let bytes = unsafe { reader.read_string::<Self, MIN_LEN, MAX_LEN>()? };
+        if let Some((first, rest)) = bytes.split_first() {
+            CharsetError::check::<C>(&[*first], 0)?;
+            CharsetError::check::<C1>(rest, 1)?;
+        }
+        RString::try_from(bytes).map_err(DecodeError::from)
     }
+
+    fn strict_display(&self) -> Option<String> { Some(String::from(self.as_str())) }
 }
 
 impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
@@ -487,18 +929,21 @@ impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(MIN_LEN, MAX_LEN) }
 }
 impl<T: StrictEncode + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
     for Confined<Vec<T>, MIN_LEN, MAX_LEN>
 {
     fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
         let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
         writer = unsafe {
-            writer = writer.write_collection::<Vec<T>, MIN_LEN, MAX_LEN>(self)?;
+            writer.raw_writer().write_raw_len::<MAX_LEN>(self.len())?;
+            writer = writer.write_items(self)?;
             if T::strict_name() == u8::strict_name() {
                 writer.register_list(&Byte::strict_dumb(), sizing)
             } else {
-                writer.register_list(&T::strict_dumb(), sizing)
+                writer.register_list(&layout_dumb::<T>(), sizing)
             }
         };
         Ok(writer)
@@ -508,20 +953,69 @@ impl<T: StrictDecode, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
     for Confined<Vec<T>, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let len = unsafe { reader.raw_reader().read_raw_len::<MAX_LEN>()? };
-        let mut col = Vec::<T>::with_capacity(len);
-        for _ in 0..len {
-            col.push(StrictDecode::strict_decode(reader)?);
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut col = Vec::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            col.push(decode_item(reader, index)?);
         }
         Confined::try_from(col).map_err(DecodeError::from)
     }
 }
 
+impl<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(MIN_LEN, MAX_LEN) }
+}
+impl<T: StrictEncode + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
+        writer = unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(self.len())?;
+            let (front, back) = self.as_slices();
+            writer = writer.write_items(front)?.write_items(back)?;
+            if T::strict_name() == u8::strict_name() {
+                writer.register_list(&Byte::strict_dumb(), sizing)
+            } else {
+                writer.register_list(&layout_dumb::<T>(), sizing)
+            }
+        };
+        Ok(writer)
+    }
+}
+impl<T: StrictDecode, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut col = VecDeque::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            col.push_back(decode_item(reader, index)?);
+        }
+        Confined::try_from(col).map_err(DecodeError::from)
+    }
+}
+
+// B-tree collections are encoded in the `Ord` order of their elements or keys,
+// and during decoding the decoded elements are required to follow in strictly
+// ascending `Ord` order. It may differ from the lexicographic order of the
+// encodings, like for the little-endian integers; the keys for which the two
+// orders must agree can be checked with
+// `test_helpers::assert_key_order_consistency`. String keys are ordered
+// byte-wise, as by `canonical_cmp`, regardless of their length prefixes.
 impl<T: StrictType + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
     for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { set_name::<T>(MIN_LEN, MAX_LEN) }
 }
 impl<T: StrictEncode + Ord + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
     for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
@@ -531,7 +1025,7 @@ impl<T: StrictEncode + Ord + StrictDumb, const MIN_LEN: usize, const MAX_LEN: us
             writer = writer.write_collection::<BTreeSet<T>, MIN_LEN, MAX_LEN>(self)?;
         }
         Ok(unsafe {
-            writer.register_set(&T::strict_dumb(), Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
+            writer.register_set(&layout_dumb::<T>(), Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
         })
     }
 }
@@ -539,18 +1033,22 @@ impl<T: StrictDecode + Ord, const MIN_LEN: usize, const MAX_LEN: usize> StrictDe
     for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let len = unsafe { reader.raw_reader().read_raw_len::<MAX_LEN>()? };
-        let mut col = BTreeSet::<T>::new();
-        for _ in 0..len {
-            let item = StrictDecode::strict_decode(reader)?;
-            if matches!(col.last(), Some(last) if last > &item) {
-                return Err(DecodeError::BrokenSetOrder);
-            }
-            if !col.insert(item) {
-                return Err(DecodeError::RepeatedSetValue);
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        // The items are collected into a sorted vector first, from which the
+        // tree is built in bulk
+        let mut items = Vec::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            let item = decode_item(reader, index)?;
+            match items.last() {
+                Some(last) if last > &item => return Err(DecodeError::BrokenSetOrder),
+                Some(last) if last == &item => {
+                    return Err(DecodeError::repeated_value::<Self>(&item, index))
+                }
+                _ => items.push(item),
             }
         }
-        Confined::try_from(col).map_err(DecodeError::from)
+        Confined::try_from(BTreeSet::from_iter(items)).map_err(DecodeError::from)
     }
 }
 
@@ -559,15 +1057,17 @@ impl<K: StrictType + Ord + Hash, V: StrictType, const MIN_LEN: usize, const MAX_
 {
     const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
     fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { map_name::<K, V>(MIN_LEN, MAX_LEN) }
 }
 impl<
-    K: StrictEncode + Ord + Hash + StrictDumb,
-    V: StrictEncode + StrictDumb,
-    const MIN_LEN: usize,
-    const MAX_LEN: usize,
-> StrictEncode for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+        K: StrictEncode + Ord + Hash + StrictDumb,
+        V: StrictEncode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictEncode for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
 {
     fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
         unsafe {
             writer.raw_writer().write_raw_len::<MAX_LEN>(self.len())?;
         }
@@ -577,33 +1077,1339 @@ impl<
         }
         Ok(unsafe {
             writer.register_map(
-                &K::strict_dumb(),
-                &V::strict_dumb(),
+                &layout_dumb::<K>(),
+                &layout_dumb::<V>(),
                 Sizing::new(MIN_LEN as u64, MAX_LEN as u64),
             )
         })
     }
 }
 impl<
-    K: StrictDecode + Ord + Hash + StrictDumb,
-    V: StrictDecode + StrictDumb,
-    const MIN_LEN: usize,
-    const MAX_LEN: usize,
-> StrictDecode for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+        K: StrictDecode + Ord + Hash + StrictDumb,
+        V: StrictDecode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictDecode for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
 {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
-        let len = unsafe { reader.raw_reader().read_raw_len::<MAX_LEN>()? };
-        let mut col = BTreeMap::new();
-        for _ in 0..len {
-            let key = StrictDecode::strict_decode(reader)?;
-            let val = StrictDecode::strict_decode(reader)?;
-            if matches!(col.last_key_value(), Some((last, _)) if last > &key) {
-                return Err(DecodeError::BrokenMapOrder);
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<(K, V)>(reader, len)?;
+        // The entries are collected into a sorted vector first, from which the
+        // tree is built in bulk
+        let mut items = Vec::<(K, V)>::with_capacity(prealloc::<(K, V)>(len));
+        for index in 0..len {
+            let key = decode_item(reader, index)?;
+            let val = decode_item(reader, index)?;
+            match items.last() {
+                Some((last, _)) if last > &key => return Err(DecodeError::BrokenMapOrder),
+                Some((last, _)) if last == &key => {
+                    return Err(DecodeError::repeated_key::<Self>(&key, index))
+                }
+                _ => items.push((key, val)),
             }
-            if col.insert(key, val).is_some() {
-                return Err(DecodeError::RepeatedMapValue);
+        }
+        Confined::try_from(BTreeMap::from_iter(items)).map_err(DecodeError::from)
+    }
+}
+
+// Hash collections are encoded exactly as the corresponding B-tree collections:
+// their elements are sorted by the key before encoding, so the randomized hash
+// iteration order never affects the encoding. During decoding, the elements
+// are required to follow in strictly ascending order.
+#[cfg(feature = "std")]
+impl<T: StrictType + Ord + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { set_name::<T>(MIN_LEN, MAX_LEN) }
+}
+#[cfg(feature = "std")]
+impl<T: StrictEncode + Ord + Hash + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize>
+    StrictEncode for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let mut items = self.iter().collect::<Vec<_>>();
+        items.sort_unstable();
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(items.len())?;
+        unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(items.len())?;
+        }
+        for item in items {
+            writer = item.strict_encode(writer)?;
+        }
+        Ok(unsafe {
+            writer.register_set(&layout_dumb::<T>(), Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
+        })
+    }
+}
+#[cfg(feature = "std")]
+impl<T: StrictDecode + Ord + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut items = Vec::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            let item = decode_item(reader, index)?;
+            match items.last() {
+                Some(last) if last > &item => return Err(DecodeError::BrokenSetOrder),
+                Some(last) if last == &item => {
+                    return Err(DecodeError::repeated_value::<Self>(&item, index))
+                }
+                _ => items.push(item),
             }
         }
-        Confined::try_from(col).map_err(DecodeError::from)
+        Confined::try_from(items.into_iter().collect::<HashSet<_>>()).map_err(DecodeError::from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: StrictType + Ord + Hash, V: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>
+    StrictType for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { map_name::<K, V>(MIN_LEN, MAX_LEN) }
+}
+#[cfg(feature = "std")]
+impl<
+        K: StrictEncode + Ord + Hash + StrictDumb,
+        V: StrictEncode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictEncode for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let mut items = self.iter().collect::<Vec<_>>();
+        items.sort_unstable_by_key(|(k, _)| *k);
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(items.len())?;
+        unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(items.len())?;
+        }
+        for (k, v) in items {
+            writer = k.strict_encode(writer)?;
+            writer = v.strict_encode(writer)?
+        }
+        Ok(unsafe {
+            writer.register_map(
+                &layout_dumb::<K>(),
+                &layout_dumb::<V>(),
+                Sizing::new(MIN_LEN as u64, MAX_LEN as u64),
+            )
+        })
+    }
+}
+#[cfg(feature = "std")]
+impl<
+        K: StrictDecode + Ord + Hash + StrictDumb,
+        V: StrictDecode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictDecode for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<(K, V)>(reader, len)?;
+        let mut items = Vec::<(K, V)>::with_capacity(prealloc::<(K, V)>(len));
+        for index in 0..len {
+            let key = decode_item(reader, index)?;
+            let val = decode_item(reader, index)?;
+            match items.last() {
+                Some((last, _)) if last > &key => return Err(DecodeError::BrokenMapOrder),
+                Some((last, _)) if last == &key => {
+                    return Err(DecodeError::repeated_key::<Self>(&key, index))
+                }
+                _ => items.push((key, val)),
+            }
+        }
+        Confined::try_from(items.into_iter().collect::<HashMap<_, _>>())
+            .map_err(DecodeError::from)
+    }
+}
+
+/// Constructs a confined collection filled with `MIN_LEN` dumb items.
+fn confined_dumb<C: Collection, const MIN_LEN: usize, const MAX_LEN: usize>(
+    item: impl FnMut() -> C::Item,
+) -> Confined<C, MIN_LEN, MAX_LEN> {
+    Confined::try_from_iter(iter::repeat_with(item).take(MIN_LEN))
+        .expect("dumb items of a set or a map can't fill more than a single item")
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<String, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(|| '\0') }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<AsciiString, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(|| AsciiChar::Null) }
+}
+impl<T: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<Vec<T>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(T::strict_dumb) }
+}
+impl<T: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<VecDeque<T>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(T::strict_dumb) }
+}
+impl<T: StrictDumb + Ord, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(T::strict_dumb) }
+}
+impl<K: StrictDumb + Ord + Hash, V: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize>
+    ConfinedDumb for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(|| (K::strict_dumb(), V::strict_dumb())) }
+}
+#[cfg(feature = "std")]
+impl<T: StrictDumb + Eq + Hash, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for Confined<HashSet<T>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(T::strict_dumb) }
+}
+#[cfg(feature = "std")]
+impl<K: StrictDumb + Eq + Hash, V: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize>
+    ConfinedDumb for Confined<HashMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self { confined_dumb(|| (K::strict_dumb(), V::strict_dumb())) }
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt::Debug;
+    use core::str::FromStr;
+
+    use amplify::confinement::{TinyBlob, TinyString};
+
+    use super::*;
+    use crate::stl::{AlphaCaps, AlphaNumDash, AlphaSmall};
+    use crate::test::{decode, decode_err, encode, encoding, encoding_roundtrip};
+    use crate::{ForeignDumb, KnownVariants, RepeatedItem, StrictReader, StrictSize, StrictWriter};
+
+    #[test]
+    fn int128() {
+        encoding(&u128::MAX, [0xFF; 16]);
+        encoding(&0x0102030405060708090A0B0C0D0E0F10u128, [
+            0x10, 0x0F, 0x0E, 0x0D, 0x0C, 0x0B, 0x0A, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03,
+            0x02, 0x01,
+        ]);
+        let mut min = [0u8; 16];
+        min[15] = 0x80;
+        encoding(&i128::MIN, min);
+        let mut minus_two = [0xFF; 16];
+        minus_two[0] = 0xFE;
+        encoding(&-2i128, minus_two);
+
+        assert_eq!(u128::strict_name(), Some(tn!("U128")));
+        assert_eq!(i128::strict_name(), Some(tn!("I128")));
+        assert_eq!(u128::strict_dumb(), 0);
+        assert_eq!(i128::strict_dumb(), 0);
+    }
+
+    #[test]
+    fn odd_width_int() {
+        encoding(&u24::ZERO, [0x00; 3]);
+        encoding(&u24::with(0x123456), [0x56, 0x34, 0x12]);
+        encoding(&u24::MAX, [0xFF; 3]);
+        encoding(&u40::ZERO, [0x00; 5]);
+        encoding(&u40::try_from(0x12_3456_789A).unwrap(), [0x9A, 0x78, 0x56, 0x34, 0x12]);
+        encoding(&u40::MAX, [0xFF; 5]);
+        encoding(&u48::ZERO, [0x00; 6]);
+        encoding(&u48::try_from(0x1234_5678_9ABC).unwrap(), [0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12]);
+        encoding(&u48::MAX, [0xFF; 6]);
+        encoding(&u56::ZERO, [0x00; 7]);
+        encoding(&u56::try_from(0x12_3456_789A_BCDE).unwrap(), [
+            0xDE, 0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12,
+        ]);
+        encoding(&u56::MAX, [0xFF; 7]);
+
+        encoding(&i24::ZERO, [0x00; 3]);
+        encoding(&i24::with(-0x123456), [0xAA, 0xCB, 0xED]);
+        encoding(&i24::with(-1), [0xFF; 3]);
+        encoding(&i24::MIN, [0x00, 0x00, 0x80]);
+        encoding(&i24::MAX, [0xFF, 0xFF, 0x7F]);
+        encoding(&i40::ZERO, [0x00; 5]);
+        encoding(&i40::with(0x12_3456_789A), [0x9A, 0x78, 0x56, 0x34, 0x12]);
+        encoding(&i40::MIN, [0x00, 0x00, 0x00, 0x00, 0x80]);
+        encoding(&i40::MAX, [0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+        encoding(&i48::ZERO, [0x00; 6]);
+        encoding(&i48::with(-2), [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        encoding(&i48::MIN, [0x00, 0x00, 0x00, 0x00, 0x00, 0x80]);
+        encoding(&i48::MAX, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+        encoding(&i56::ZERO, [0x00; 7]);
+        encoding(&i56::with(0x12_3456_789A_BCDE), [0xDE, 0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12]);
+        encoding(&i56::MIN, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80]);
+        encoding(&i56::MAX, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+
+        assert_eq!(u24::strict_name(), Some(tn!("U24")));
+        assert_eq!(u40::strict_name(), Some(tn!("U40")));
+        assert_eq!(u48::strict_name(), Some(tn!("U48")));
+        assert_eq!(u56::strict_name(), Some(tn!("U56")));
+        assert_eq!(i24::strict_name(), Some(tn!("I24")));
+        assert_eq!(i40::strict_name(), Some(tn!("I40")));
+        assert_eq!(i48::strict_name(), Some(tn!("I48")));
+        assert_eq!(i56::strict_name(), Some(tn!("I56")));
+        assert_eq!(i56::strict_dumb(), i56::ZERO);
+    }
+
+    #[test]
+    fn wide_int() {
+        encoding(&u256::MAX, [0xFF; 32]);
+        encoding(&u512::MAX, [0xFF; 64]);
+        encoding(&u1024::MAX, [0xFF; 128]);
+
+        let mut high = [0u8; 32];
+        high[24] = 0x01;
+        encoding(&(u256::ONE << 192), high);
+        let mut high = [0u8; 64];
+        high[56] = 0x01;
+        encoding(&(u512::ONE << 448), high);
+        let mut high = [0u8; 128];
+        high[120] = 0x01;
+        encoding(&(u1024::ONE << 960), high);
+
+        let mut min = [0u8; 32];
+        min[31] = 0x80;
+        encoding(&i256::MIN, min);
+        encoding(&i512::from(-1i64), [0xFF; 64]);
+        encoding(&i1024::ONE, {
+            let mut one = [0u8; 128];
+            one[0] = 1;
+            one
+        });
+
+        assert_eq!(u256::strict_name(), Some(tn!("U256")));
+        assert_eq!(u512::strict_name(), Some(tn!("U512")));
+        assert_eq!(u1024::strict_name(), Some(tn!("U1024")));
+        assert_eq!(i256::strict_name(), Some(tn!("I256")));
+        assert_eq!(i512::strict_name(), Some(tn!("I512")));
+        assert_eq!(i1024::strict_name(), Some(tn!("I1024")));
+        assert_eq!(u256::strict_dumb(), u256::ZERO);
+    }
+
+    #[test]
+    fn float() {
+        encoding(&0.0f32, [0, 0, 0, 0]);
+        encoding(&-0.0f32, [0, 0, 0, 0x80]);
+        encoding(&1.5f32, [0, 0, 0xC0, 0x3F]);
+        encoding(&f32::from_bits(1), [1, 0, 0, 0]);
+        encoding(&f32::INFINITY, [0, 0, 0x80, 0x7F]);
+        encoding(&f32::NEG_INFINITY, [0, 0, 0x80, 0xFF]);
+
+        encoding(&0.0f64, [0, 0, 0, 0, 0, 0, 0, 0]);
+        encoding(&-0.0f64, [0, 0, 0, 0, 0, 0, 0, 0x80]);
+        encoding(&f64::MIN_POSITIVE, [0, 0, 0, 0, 0, 0, 0x10, 0]);
+        encoding(&f64::from_bits(1), [1, 0, 0, 0, 0, 0, 0, 0]);
+        encoding(&f64::INFINITY, [0, 0, 0, 0, 0, 0, 0xF0, 0x7F]);
+        encoding(&f64::NEG_INFINITY, [0, 0, 0, 0, 0, 0, 0xF0, 0xFF]);
+
+        let decoded: f64 = decode([0, 0, 0, 0, 0, 0, 0xF0, 0x7F]);
+        assert_eq!(decoded.to_bits(), f64::INFINITY.to_bits());
+        let decoded: f32 = decode([0, 0, 0, 0x80]);
+        assert_eq!(decoded.to_bits(), (-0.0f32).to_bits());
+    }
+
+    #[test]
+    fn float_nan() {
+        for nan in [f32::NAN, -f32::NAN, f32::from_bits(0x7F80_0001), f32::from_bits(0xFFC0_1234)] {
+            assert_eq!(encode(&nan), [0, 0, 0xC0, 0x7F]);
+        }
+        for nan in [f64::NAN, -f64::NAN, f64::from_bits(0x7FF0_0000_0000_0001)] {
+            assert_eq!(encode(&nan), [0, 0, 0, 0, 0, 0, 0xF8, 0x7F]);
+        }
+
+        let decoded: f32 = decode([0x01, 0, 0x80, 0xFF]);
+        assert_eq!(decoded.to_bits(), 0x7FC0_0000);
+        let decoded: f64 = decode([0x34, 0x12, 0, 0, 0, 0, 0xF8, 0xFF]);
+        assert_eq!(decoded.to_bits(), 0x7FF8_0000_0000_0000);
+    }
+
+    #[test]
+    #[cfg(feature = "half")]
+    fn float_half() {
+        use half::{bf16, f16};
+
+        encoding(&f16::ZERO, [0, 0]);
+        encoding(&f16::NEG_ZERO, [0, 0x80]);
+        encoding(&f16::ONE, [0x00, 0x3C]);
+        encoding(&f16::from_bits(1), [1, 0]);
+        encoding(&f16::INFINITY, [0x00, 0x7C]);
+        encoding(&f16::NEG_INFINITY, [0x00, 0xFC]);
+        assert_eq!(encode(&f16::from_bits(0xFE01)), [0x00, 0x7E]);
+        assert_eq!(decode::<f16>([0x01, 0x7C]).to_bits(), 0x7E00);
+
+        encoding(&bf16::ZERO, [0, 0]);
+        encoding(&bf16::NEG_ZERO, [0, 0x80]);
+        encoding(&bf16::ONE, [0x80, 0x3F]);
+        encoding(&bf16::from_bits(1), [1, 0]);
+        encoding(&bf16::INFINITY, [0x80, 0x7F]);
+        encoding(&bf16::NEG_INFINITY, [0x80, 0xFF]);
+        assert_eq!(encode(&bf16::from_bits(0xFFC1)), [0xC0, 0x7F]);
+        assert_eq!(decode::<bf16>([0x81, 0x7F]).to_bits(), 0x7FC0);
+    }
+
+    #[test]
+    fn option() {
+        encoding(&None::<u16>, [0]);
+        encoding(&Some(0x0102u16), [1, 2, 1]);
+        encoding(&Some(None::<u8>), [1, 0]);
+        for tag in 2..=u8::MAX {
+            let mut reader = StrictReader::in_memory::<3>([tag, 2, 1]);
+            assert_eq!(
+                Option::<u16>::strict_decode(&mut reader).unwrap_err(),
+                DecodeError::UnionTagNotKnown {
+                    type_name: s!("U16?"),
+                    tag,
+                    variants: KnownVariants(vec![(0, vname!("none")), (1, vname!("some"))]),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn option_nested() {
+        encoding(&None::<Option<u8>>, [0x00]);
+        encoding(&Some(None::<u8>), [0x01, 0x00]);
+        encoding(&Some(Some(7u8)), [0x01, 0x01, 0x07]);
+        encoding(&Some(Some(None::<u8>)), [0x01, 0x01, 0x00]);
+
+        let mut reader = StrictReader::in_memory::<3>([0x01, 0x02, 0x07]);
+        assert_eq!(
+            Option::<Option<u8>>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::UnionTagNotKnown {
+                type_name: s!("U8?"),
+                tag: 2,
+                variants: KnownVariants(vec![(0, vname!("none")), (1, vname!("some"))]),
+            }
+        );
+    }
+
+    #[test]
+    fn option_in_union() {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        enum Setting {
+            #[default]
+            Unset,
+            Value(Option<u8>),
+            Limits {
+                min: Option<u8>,
+                max: Option<Option<u8>>,
+            },
+        }
+
+        encoding(&Setting::Unset, [0x00]);
+        encoding(&Setting::Value(None), [0x01, 0x00]);
+        encoding(&Setting::Value(Some(7)), [0x01, 0x01, 0x07]);
+        encoding(
+            &Setting::Limits {
+                min: None,
+                max: Some(None),
+            },
+            [0x02, 0x00, 0x01, 0x00],
+        );
+        encoding(
+            &Setting::Limits {
+                min: Some(1),
+                max: Some(Some(9)),
+            },
+            [0x02, 0x01, 0x01, 0x01, 0x01, 0x09],
+        );
+
+        let mut reader = StrictReader::in_memory::<3>([0x01, 0x02, 0x07]);
+        assert!(matches!(
+            Setting::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::UnionTagNotKnown { tag: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn result() {
+        encoding(&Result::<u16, u8>::Ok(0x0102), [0x00, 0x02, 0x01]);
+        encoding(&Result::<u16, u8>::Err(0xFF), [0x01, 0xFF]);
+        assert_eq!(Result::<u16, u8>::strict_name(), Some(tn!("Resultu16u8")));
+
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = LIB_NAME_STD, tags = order, crate = crate, dumb = Outcome::Ok(u16::MIN))]
+        enum Outcome {
+            Ok(u16),
+            Err(u8),
+        }
+        let ok = Outcome::Ok(0x0102);
+        let err = Outcome::Err(0xFF);
+        assert_eq!(encode(&Result::<u16, u8>::Ok(0x0102)), encode(&ok));
+        assert_eq!(encode(&Result::<u16, u8>::Err(0xFF)), encode(&err));
+        assert_eq!(decode::<Result<u16, u8>>(encode(&ok)), Ok(0x0102));
+        assert_eq!(decode::<Result<u16, u8>>(encode(&err)), Err(0xFF));
+        for tag in 2..=u8::MAX {
+            let mut reader = StrictReader::in_memory::<3>([tag, 2, 1]);
+            assert_eq!(
+                Result::<u16, u8>::strict_decode(&mut reader).unwrap_err(),
+                DecodeError::UnionTagNotKnown {
+                    type_name: s!("Resultu16u8"),
+                    tag,
+                    variants: KnownVariants(vec![(0, vname!("ok")), (1, vname!("err"))]),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn uninhabited() {
+        encoding(&Result::<u8, Void>::Ok(7), [0x00, 0x07]);
+        encoding(&Result::<u16, Void>::Ok(0x0102), [0x00, 0x02, 0x01]);
+        assert_eq!(decode::<Result<u8, Infallible>>([0x00, 0x07]), Ok(7));
+
+        let mut reader = StrictReader::in_memory::<2>([0x01, 0x07]);
+        assert_eq!(
+            Result::<u8, Void>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Uninhabited(s!("strict_encoding::embedded::Void"))
+        );
+        let mut reader = StrictReader::in_memory::<2>([0x01, 0x07]);
+        assert_eq!(
+            Result::<u8, Infallible>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Uninhabited(s!("core::convert::Infallible"))
+        );
+        let mut reader = StrictReader::in_memory::<0>([]);
+        assert!(Void::strict_decode(&mut reader).is_err());
+
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        enum Reply {
+            Done(u8),
+            Never(Void),
+        }
+        encoding(&Reply::Done(1), [0x00, 0x01]);
+        assert_eq!(encode(&Reply::Done(1)), encode(&Result::<u8, Void>::Ok(1)));
+    }
+
+    #[test]
+    fn void_layout() {
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        enum Reply {
+            Done(u8),
+            Never(Void),
+        }
+        assert_eq!(Reply::strict_size(), (2, Some(2)));
+        let info = <Reply as crate::StrictTypeInfo>::type_info();
+        let crate::TypeClassOwned::Union { variants } = info.class else {
+            panic!("unions must be described as unions")
+        };
+        assert_eq!(variants[1].fields[0].ty.as_ref().unwrap().to_string(), "Std.Void");
+    }
+
+    #[test]
+    fn tuple() {
+        encoding(&(0x0102u16, 3u8), [2, 1, 3]);
+        encoding(&(1u8, 2u8, 3u8), [1, 2, 3]);
+
+        let tuple = (
+            1u8,
+            0x0302u16,
+            0x07060504u32,
+            -1i8,
+            true,
+            Some(9u8),
+            None::<u8>,
+            [0xAAu8; 2],
+            0x0F0E0D0C0B0A0908u64,
+            (),
+            (0x10u8, 0x11u8),
+            -2i16,
+        );
+        assert_eq!(<(u16, u8)>::strict_name(), Some(tn!("TupleU16U8")));
+        assert_eq!(
+            <(Option<u8>, [u8; 4], (u8, bool))>::strict_name(),
+            Some(tn!("TupleU8Byte4TupleU8Bool"))
+        );
+        assert_eq!(decoded_name::<(u16, u8)>(), "TupleU16U8");
+        // tuples which names are too long are anonymous
+        type Long = Confined<Vec<TinyString>, 1, 8>;
+        type LongTuple = (Long, Long, Long, Long, Long, Long, Long);
+        assert_eq!(LongTuple::strict_name(), None);
+        assert_eq!(
+            decoded_name::<LongTuple>(),
+            format!("({})", ["[[Unicode ^ ..0xff] ^ 1..0x8]"; 7].join(", "))
+        );
+        encoding(&tuple, [
+            1, 2, 3, 4, 5, 6, 7, 0xFF, 1, 1, 9, 0, 0xAA, 0xAA, 8, 9, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+            0x0F, 0x10, 0x11, 0xFE, 0xFF,
+        ]);
+    }
+
+    #[test]
+    fn array_long() {
+        let ar: [Box<u32>; 1000] = core::array::from_fn(|i| Box::new(i as u32));
+        let data = encode(&ar);
+        assert_eq!(data.len(), 4000);
+        assert_eq!(&data[4..8], &[1, 0, 0, 0]);
+        assert_eq!(&data[3996..], &[0xE7, 0x03, 0, 0]);
+        encoding_roundtrip(&ar);
+
+        let page = [0xA5u8; 4096];
+        encoding(&page, page);
+    }
+
+    #[test]
+    fn array_partial_decode() {
+        use core::cell::Cell;
+
+        thread_local! {
+            static DROPPED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Default, Debug)]
+        struct Counted(u8);
+        impl Drop for Counted {
+            fn drop(&mut self) { DROPPED.with(|d| d.set(d.get() + 1)) }
+        }
+        impl StrictType for Counted {
+            const STRICT_LIB_NAME: &'static str = "Test";
+        }
+        impl StrictDecode for Counted {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                u8::strict_decode(reader).map(Counted)
+            }
+        }
+
+        let mut reader = StrictReader::in_memory::<8>([1u8, 2, 3, 4, 5]);
+        let err = <[Counted; 8]>::strict_decode(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::Io(_)));
+        assert_eq!(DROPPED.with(Cell::get), 5);
+
+        let mut reader = StrictReader::in_memory::<3>([1u8, 2, 3]);
+        let ar = <[Counted; 3]>::strict_decode(&mut reader).unwrap();
+        assert_eq!(ar.iter().map(|c| c.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(DROPPED.with(Cell::get), 5);
+        drop(ar);
+        assert_eq!(DROPPED.with(Cell::get), 8);
+    }
+
+    #[test]
+    fn pointers() {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Pair {
+            a: u8,
+            b: u16,
+        }
+
+        let pair = Pair { a: 1, b: 0x0203 };
+        let data = encode(&pair);
+        assert_eq!(data, [1, 3, 2]);
+        encoding(&Box::new(pair.clone()), &data);
+        encoding(&Rc::new(pair.clone()), &data);
+        encoding(&Arc::new(pair.clone()), &data);
+        encoding(&Box::new(Rc::new(Arc::new(pair))), &data);
+
+        assert_eq!(Box::<Pair>::strict_name(), Pair::strict_name());
+        assert_eq!(Rc::<Pair>::strict_name(), Pair::strict_name());
+        assert_eq!(Arc::<Pair>::strict_name(), Pair::strict_name());
+        assert_eq!(Box::<Pair>::STRICT_LIB_NAME, "Test");
+        assert_eq!(Arc::<u8>::strict_name(), Some(tn!("U8")));
+        assert_eq!(Rc::<Pair>::strict_dumb(), Rc::new(Pair::default()));
+    }
+
+    #[test]
+    fn arithmetic_wrappers() {
+        assert_eq!(encode(&Wrapping(42u32)), encode(&42u32));
+        assert_eq!(encode(&Saturating(42u32)), encode(&42u32));
+        assert_eq!(Wrapping::<u32>::strict_name(), u32::strict_name());
+        assert_eq!(Saturating::<i64>::strict_name(), i64::strict_name());
+        assert_eq!(Wrapping::<u16>::strict_dumb(), Wrapping(u16::strict_dumb()));
+
+        let max = Wrapping(u8::MAX);
+        encoding(&max, [0xFF]);
+        encoding(&(max + Wrapping(1)), [0x00]);
+        encoding(&(Wrapping(i16::MIN) - Wrapping(1)), [0xFF, 0x7F]);
+        encoding(&Wrapping(u128::MAX), [0xFF; 16]);
+
+        let min = Saturating(i8::MIN);
+        encoding(&min, [0x80]);
+        encoding(&(min - Saturating(1)), [0x80]);
+        encoding(&(Saturating(u64::MAX) + Saturating(1)), [0xFF; 8]);
+    }
+
+    #[test]
+    fn cow() {
+        use amplify::confinement::{SmallString, TinyVec};
+
+        use crate::test::count_allocations;
+        use crate::{StreamWriter, StrictWriter};
+
+        fn encode_counting(val: &impl StrictEncode, buf: &mut [u8]) -> usize {
+            let writer = StrictWriter::with(StreamWriter::new::<256>(&mut buf[..]));
+            count_allocations(|| val.strict_encode(writer).unwrap()).1
+        }
+
+        let s = SmallString::try_from(s!("borrowed")).unwrap();
+        let owned = Cow::<SmallString>::Owned(s.clone());
+        let borrowed = Cow::Borrowed(&s);
+        assert_eq!(encode(&borrowed), encode(&s));
+        assert_eq!(encode(&owned), encode(&s));
+        encoding(&owned, encode(&s));
+
+        // Encoding of the borrowed value must not allocate on top of what the
+        // encoding of the value itself requires
+        let mut buf = [0u8; 10];
+        let allocations = encode_counting(&s, &mut buf);
+        assert_eq!(encode_counting(&borrowed, &mut buf), allocations);
+        assert_eq!(buf[..], encode(&s));
+
+        let v = TinyVec::try_from(vec![1u16, 2, 3]).unwrap();
+        let borrowed = Cow::Borrowed(&v);
+        assert_eq!(encode(&borrowed), [3, 1, 0, 2, 0, 3, 0]);
+        let mut buf = [0u8; 7];
+        let allocations = encode_counting(&v, &mut buf);
+        assert_eq!(encode_counting(&borrowed, &mut buf), allocations);
+        assert_eq!(buf, [3, 1, 0, 2, 0, 3, 0]);
+
+        assert!(matches!(decode::<Cow<TinyVec<u16>>>(buf), Cow::Owned(vec) if vec == v));
+        assert_eq!(Cow::<SmallString>::strict_name(), SmallString::strict_name());
+    }
+
+    #[test]
+    fn cow_unconfined() {
+        use amplify::confinement::{SmallString, SmallVec, U16};
+
+        use crate::test::count_allocations;
+        use crate::{StreamWriter, StrictWriter};
+
+        fn encode_counting(val: &impl StrictEncode, buf: &mut [u8]) -> usize {
+            let writer = StrictWriter::with(StreamWriter::new::<256>(&mut buf[..]));
+            count_allocations(|| val.strict_encode(writer).unwrap()).1
+        }
+
+        let owned = SmallString::try_from(s!("borrowed")).unwrap();
+        let borrowed = Cow::Borrowed("borrowed");
+        assert_eq!(encode(&borrowed), encode(&owned));
+        assert_eq!(encode(&Cow::<str>::Owned(s!("borrowed"))), encode(&owned));
+        // Borrowed data must be written in place, without any allocation on top
+        // of what the encoding of the owned value requires
+        let mut buf = [0u8; 10];
+        let allocations = encode_counting(&owned, &mut buf);
+        assert_eq!(encode_counting(&borrowed, &mut buf), allocations);
+        assert_eq!(buf[..], encode(&owned));
+        assert!(matches!(decode::<Cow<str>>(buf), Cow::Owned(s) if s == "borrowed"));
+        assert_eq!(Cow::<str>::strict_name(), SmallString::strict_name());
+        assert_eq!(decoded_name::<Cow<str>>(), decoded_name::<SmallString>());
+
+        let owned = SmallVec::try_from(vec![1u16, 2, 3]).unwrap();
+        let borrowed = Cow::Borrowed(&[1u16, 2, 3][..]);
+        assert_eq!(encode(&borrowed), encode(&owned));
+        let mut buf = [0u8; 8];
+        let allocations = encode_counting(&owned, &mut buf);
+        assert_eq!(encode_counting(&borrowed, &mut buf), allocations);
+        assert_eq!(buf, [3, 0, 1, 0, 2, 0, 3, 0]);
+        assert!(matches!(decode::<Cow<[u16]>>(buf), Cow::Owned(v) if v == [1, 2, 3]));
+        assert_eq!(decoded_name::<Cow<[u16]>>(), decoded_name::<SmallVec<u16>>());
+
+        let long = "x".repeat(U16 + 1);
+        let err = Cow::Borrowed(long.as_str())
+            .strict_encode(StrictWriter::in_memory::<{ usize::MAX }>())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let long = vec![0u8; U16 + 1];
+        let err = Cow::Borrowed(&long[..])
+            .strict_encode(StrictWriter::in_memory::<{ usize::MAX }>())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn vec_deque() {
+        let mut ring = VecDeque::with_capacity(4);
+        ring.extend([1u16, 2, 3, 4]);
+        ring.pop_front();
+        ring.push_back(5);
+        let ring = Confined::<VecDeque<u16>, 1, 4>::try_from(ring).unwrap();
+        let list = Confined::<Vec<u16>, 1, 4>::try_from(vec![2, 3, 4, 5]).unwrap();
+        assert_eq!(encode(&ring), encode(&list));
+        encoding(&ring, [4, 2, 0, 3, 0, 4, 0, 5, 0]);
+
+        let mut reader = StrictReader::in_memory::<1>([0u8]);
+        assert!(matches!(
+            Confined::<VecDeque<u16>, 1, 4>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Undersize { .. }
+        ));
+    }
+
+    #[test]
+    fn hash_collections() {
+        let mut set = HashSet::new();
+        let mut btree_set = BTreeSet::new();
+        let mut map = HashMap::new();
+        let mut btree_map = BTreeMap::new();
+        for i in (0..200u16).rev() {
+            set.insert(i * 7);
+            btree_set.insert(i * 7);
+            map.insert(i * 7, i as u8);
+            btree_map.insert(i * 7, i as u8);
+        }
+        let set = Confined::<HashSet<u16>, 0, 255>::try_from(set).unwrap();
+        let btree_set = Confined::<BTreeSet<u16>, 0, 255>::try_from(btree_set).unwrap();
+        let map = Confined::<HashMap<u16, u8>, 0, 255>::try_from(map).unwrap();
+        let btree_map = Confined::<BTreeMap<u16, u8>, 0, 255>::try_from(btree_map).unwrap();
+        assert_eq!(encode(&set), encode(&btree_set));
+        assert_eq!(encode(&map), encode(&btree_map));
+        encoding_roundtrip(&set);
+        encoding_roundtrip(&map);
+
+        let set = Confined::<HashSet<u8>, 0, 4>::try_from(HashSet::from([3, 1, 2])).unwrap();
+        encoding(&set, [3, 1, 2, 3]);
+        let map = Confined::<HashMap<u8, u8>, 0, 4>::try_from(HashMap::from([(2, 0), (1, 9)]))
+            .unwrap();
+        encoding(&map, [2, 1, 9, 2, 0]);
+    }
+
+    #[test]
+    fn hash_collections_order() {
+        type Set = Confined<HashSet<u8>, 0, 4>;
+        type Map = Confined<HashMap<u8, u8>, 0, 4>;
+
+        let mut reader = StrictReader::in_memory::<3>([2, 2, 1]);
+        assert_eq!(Set::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenSetOrder);
+        let mut reader = StrictReader::in_memory::<3>([2, 1, 1]);
+        let err = Set::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::repeated_value::<Set>(&1u8, 1));
+        let mut reader = StrictReader::in_memory::<5>([2, 2, 0, 1, 0]);
+        assert_eq!(Map::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenMapOrder);
+        let mut reader = StrictReader::in_memory::<5>([2, 1, 0, 1, 1]);
+        let err = Map::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::repeated_key::<Map>(&1u8, 1));
+    }
+
+    #[test]
+    fn btree_collections_order() {
+        type Set = Confined<BTreeSet<u8>, 0, 4>;
+        type Map = Confined<BTreeMap<u8, u8>, 0, 4>;
+
+        let mut reader = StrictReader::in_memory::<4>([3, 1, 3, 2]);
+        assert_eq!(Set::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenSetOrder);
+        let mut reader = StrictReader::in_memory::<4>([3, 1, 2, 2]);
+        let err = Set::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::repeated_value::<Set>(&2u8, 2));
+        let set: Set = decode([1, 7]);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![7]);
+
+        let mut reader = StrictReader::in_memory::<5>([2, 5, 0, 4, 0]);
+        assert_eq!(Map::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenMapOrder);
+        let mut reader = StrictReader::in_memory::<5>([2, 4, 0, 4, 1]);
+        let err = Map::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::repeated_key::<Map>(&4u8, 1));
+        let map: Map = decode([1, 7, 1]);
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![(7, 1)]);
+    }
+
+    #[test]
+    fn repeated_items() {
+        type StrMap = Confined<BTreeMap<TinyString, u8>, 0, 4>;
+        let err = decode_err::<StrMap>(&[2, 1, b'a', 0, 1, b'a', 1]);
+        assert_eq!(err, DecodeError::RepeatedKey {
+            type_name: s!("{[Unicode ^ ..0xff] -> U8 ^ ..0x4}"),
+            key: RepeatedItem::Value(s!("a")),
+        });
+        assert_eq!(
+            err.to_string(),
+            "key `a` occurs multiple times in map `{[Unicode ^ ..0xff] -> U8 ^ ..0x4}`."
+        );
+        assert!(err.is_invalid_data());
+
+        // Values which can't be rendered are reported by their index
+        type PairSet = Confined<HashSet<(u8, u8)>, 0, 4>;
+        let err = decode_err::<PairSet>(&[3, 1, 2, 1, 3, 1, 3]);
+        assert_eq!(err, DecodeError::RepeatedValue {
+            type_name: s!("{TupleU8U8 ^ ..0x4}"),
+            value: RepeatedItem::Index(2),
+        });
+        assert_eq!(err.to_string(), "value #2 occurs multiple times in set `{TupleU8U8 ^ ..0x4}`.");
+
+        // The last key of a map filled up to its maximal length
+        type FullMap = Confined<BTreeMap<u16, u8>, 0, 4>;
+        let err = decode_err::<FullMap>(&[4, 1, 0, 0, 2, 0, 0, 3, 0, 0, 3, 0, 1]);
+        assert_eq!(err, DecodeError::RepeatedKey {
+            type_name: s!("{U16 -> U8 ^ ..0x4}"),
+            key: RepeatedItem::Value(s!("3")),
+        });
+        type FullHashMap = Confined<HashMap<i8, u8>, 0, 4>;
+        let err = decode_err::<FullHashMap>(&[4, 0xFE, 0, 0xFF, 0, 0, 0, 0, 1]);
+        assert_eq!(err, DecodeError::RepeatedKey {
+            type_name: s!("{I8 -> U8 ^ ..0x4}"),
+            key: RepeatedItem::Value(s!("0")),
+        });
+    }
+
+    #[test]
+    fn repeated_rendering() {
+        #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Id(u16);
+
+        assert_eq!(RepeatedItem::of(&tn!("Name"), 1), RepeatedItem::Value(s!("Name")));
+        assert_eq!(RepeatedItem::of(&fname!("name"), 1), RepeatedItem::Value(s!("name")));
+        assert_eq!(RepeatedItem::of(&Id(7), 1), RepeatedItem::Value(s!("7")));
+
+        let escaped = TinyString::try_from(s!("a`\n\u{7}")).unwrap();
+        assert_eq!(RepeatedItem::of(&escaped, 1), RepeatedItem::Value(s!("a`\\n\\u{7}")));
+        let long = TinyString::try_from("x".repeat(RepeatedItem::MAX_LEN + 1)).unwrap();
+        assert_eq!(
+            RepeatedItem::of(&long, 1),
+            RepeatedItem::Value(format!("{}…", "x".repeat(RepeatedItem::MAX_LEN)))
+        );
+
+        type IdSet = Confined<BTreeSet<Id>, 0, 4>;
+        let err = decode_err::<IdSet>(&[2, 7, 0, 7, 0]);
+        assert_eq!(err.to_string(), "value `7` occurs multiple times in set `{Id ^ ..0x4}`.");
+    }
+
+    #[test]
+    fn anonymous_names() {
+        assert_eq!(decoded_name::<Option<u8>>(), "U8?");
+        assert_eq!(decoded_name::<[u8; 4]>(), "[Byte ^ 4]");
+        assert_eq!(decoded_name::<[u16; 4]>(), "[U16 ^ 4]");
+        assert_eq!(decoded_name::<()>(), "()");
+        assert_eq!(decoded_name::<TinyBlob>(), "[Byte ^ ..0xff]");
+        assert_eq!(
+            decoded_name::<Confined<Vec<TinyString>, 1, 8>>(),
+            "[[Unicode ^ ..0xff] ^ 1..0x8]"
+        );
+        assert_eq!(decoded_name::<Box<Option<u8>>>(), "U8?");
+    }
+
+    #[test]
+    fn nonzero() {
+        encoding(&NonZeroU8::new(1).unwrap(), [1]);
+        encoding(&NonZeroU16::new(0x0102).unwrap(), [0x02, 0x01]);
+        encoding(&NonZeroU32::MAX, [0xFF; 4]);
+        encoding(&NonZeroU64::new(0x0102).unwrap(), [0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        encoding(&NonZeroU128::MIN, [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        encoding(&NonZeroI8::new(-1).unwrap(), [0xFF]);
+        encoding(&NonZeroI16::new(-2).unwrap(), [0xFE, 0xFF]);
+        encoding(&NonZeroI32::MIN, [0, 0, 0, 0x80]);
+        encoding(&NonZeroI64::new(1).unwrap(), [1, 0, 0, 0, 0, 0, 0, 0]);
+        let mut max = [0xFF; 16];
+        max[15] = 0x7F;
+        encoding(&NonZeroI128::MAX, max);
+
+        assert_eq!(NonZeroU16::foreign_dumb().get(), 1);
+        assert_eq!(NonZeroI64::foreign_dumb().get(), 1);
+    }
+
+    #[test]
+    fn nonzero_zero() {
+        let mut reader = StrictReader::in_memory::<1>([0u8]);
+        assert_eq!(NonZeroU8::strict_decode(&mut reader).unwrap_err(), DecodeError::ZeroNatural);
+
+        let mut reader = StrictReader::in_memory::<2>([0u8; 2]);
+        assert_eq!(NonZeroI16::strict_decode(&mut reader).unwrap_err(), DecodeError::ZeroNatural);
+    }
+
+    #[test]
+    fn len_prefix_width() {
+        const U24: usize = u24::MAX.into_usize();
+        const U32: usize = u32::MAX as usize;
+        const LIMIT: usize = 0x100_0000 + 0x100;
+
+        fn prefix<T: StrictEncode + StrictDecode + PartialEq + core::fmt::Debug>(
+            val: T,
+            width: usize,
+        ) -> Vec<u8> {
+            let writer = StrictWriter::in_memory::<LIMIT>();
+            let data = val.strict_encode(writer).unwrap().unbox().unconfine();
+            let mut reader = StrictReader::in_memory::<LIMIT>(&data);
+            assert_eq!(T::strict_decode(&mut reader).unwrap(), val);
+            data[..width].to_vec()
+        }
+
+        let blob = |len: usize| vec![0xA5u8; len];
+
+        let small = Confined::<Vec<u8>, 0, { u16::MAX as usize }>::try_from(blob(0xFFFF));
+        assert_eq!(prefix(small.unwrap(), 2), [0xFF, 0xFF]);
+        let medium = Confined::<Vec<u8>, 0, U24>::try_from(blob(0xFFFF));
+        assert_eq!(prefix(medium.unwrap(), 3), [0xFF, 0xFF, 0x00]);
+        let medium = Confined::<Vec<u8>, 0, U24>::try_from(blob(0x1_0000));
+        assert_eq!(prefix(medium.unwrap(), 3), [0x00, 0x00, 0x01]);
+        let medium = Confined::<Vec<u8>, 0, 0x1_0000>::try_from(blob(0x1_0000));
+        assert_eq!(prefix(medium.unwrap(), 3), [0x00, 0x00, 0x01]);
+        let large = Confined::<Vec<u8>, 0, U32>::try_from(blob(0x100_0000));
+        assert_eq!(prefix(large.unwrap(), 4), [0x00, 0x00, 0x00, 0x01]);
+        let large = Confined::<Vec<u8>, 0, { U24 + 1 }>::try_from(blob(0xFF));
+        assert_eq!(prefix(large.unwrap(), 4), [0xFF, 0x00, 0x00, 0x00]);
+
+        let set = Confined::<BTreeSet<u32>, 0, U24>::try_from_iter(0..0x1_0000).unwrap();
+        assert_eq!(prefix(set, 3), [0x00, 0x00, 0x01]);
+        let list = Confined::<Vec<u16>, 0, U32>::try_from(vec![7u16; 0x1_0001]).unwrap();
+        assert_eq!(prefix(list, 6), [0x01, 0x00, 0x01, 0x00, 0x07, 0x00]);
+
+        // the prefix is checked against the bound before anything is allocated
+        let mut reader = StrictReader::in_memory::<LIMIT>([0xFF, 0xFF, 0xFF]);
+        let err = Confined::<Vec<u8>, 0, 0x1_0000>::strict_decode(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::Oversize {
+            declared_len: 0xFF_FFFF,
+            max_len: 0x1_0000,
+            ..
+        }));
+
+        // multi-megabyte blob
+        let data = (0..5 * 1024 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let large = Confined::<Vec<u8>, 0, U32>::try_from(data.clone()).unwrap();
+        assert_eq!(prefix(large, 4), (data.len() as u32).to_le_bytes());
+    }
+
+    #[test]
+    fn oversize_prefix() {
+        fn oversize<T: StrictDecode + core::fmt::Debug>(data: &[u8]) -> (String, usize, usize) {
+            let mut reader = StrictReader::in_memory::<{ u16::MAX as usize }>(data);
+            match T::strict_decode(&mut reader).unwrap_err() {
+                DecodeError::Oversize {
+                    type_name,
+                    declared_len,
+                    max_len,
+                } => (type_name, declared_len, max_len),
+                err => panic!("unexpected error {err:?}"),
+            }
+        }
+
+        // only the length prefix is present: no elements must be read
+        let (type_name, declared, max) = oversize::<Confined<Vec<u16>, 0, 1000>>(&[0xFF, 0xFF]);
+        assert_eq!(type_name, "[U16 ^ ..0x3e8]");
+        assert_eq!((declared, max), (0xFFFF, 1000));
+
+        let (type_name, declared, max) = oversize::<Confined<BTreeMap<u8, u16>, 0, 10>>(&[11]);
+        assert_eq!(type_name, "{U8 -> U16 ^ ..0xa}");
+        assert_eq!((declared, max), (11, 10));
+
+        let (type_name, declared, max) = oversize::<Confined<String, 0, 100>>(&[200]);
+        assert_eq!(type_name, "[Unicode ^ ..0x64]");
+        assert_eq!((declared, max), (200, 100));
+        let err = DecodeError::Oversize {
+            type_name,
+            declared_len: declared,
+            max_len: max,
+        };
+        assert_eq!(
+            err.to_string(),
+            "length prefix of `[Unicode ^ ..0x64]` declares 200 elements, while the type is \
+             confined to at most 100 elements."
+        );
+
+        let (_, declared, max) = oversize::<Confined<AsciiString, 1, 300>>(&[0x2D, 0x01]);
+        assert_eq!((declared, max), (301, 300));
+
+        #[cfg(feature = "std")]
+        {
+            let (_, declared, max) = oversize::<Confined<HashMap<u8, u8>, 0, 1>>(&[2, 1, 1, 2, 2]);
+            assert_eq!((declared, max), (2, 1));
+        }
+
+        // lengths within the limit are still checked for the minimum
+        let mut reader = StrictReader::in_memory::<4>([0u8]);
+        assert!(matches!(
+            Confined::<String, 1, 100>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Undersize {
+                declared_len: 0,
+                min_len: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn undersize_prefix() {
+        fn undersize<T: StrictDecode + core::fmt::Debug>(data: &[u8]) -> (usize, usize, usize) {
+            let mut reader = StrictReader::in_memory::<{ u16::MAX as usize }>(data);
+            match T::strict_decode(&mut reader).unwrap_err() {
+                DecodeError::Undersize {
+                    type_name,
+                    declared_len,
+                    min_len,
+                    max_len,
+                } => {
+                    assert_eq!(type_name, decoded_name::<T>());
+                    (declared_len, min_len, max_len)
+                }
+                err => panic!("unexpected error {err:?}"),
+            }
+        }
+
+        assert_eq!(undersize::<Confined<Vec<u16>, 1, 100>>(&[0]), (0, 1, 100));
+        assert_eq!(undersize::<Confined<VecDeque<u16>, 1, 100>>(&[0]), (0, 1, 100));
+        assert_eq!(undersize::<Confined<BTreeSet<u8>, 1, 100>>(&[0]), (0, 1, 100));
+        assert_eq!(undersize::<Confined<BTreeMap<u8, u8>, 1, 100>>(&[0]), (0, 1, 100));
+        assert_eq!(undersize::<Confined<String, 1, 1000>>(&[0, 0]), (0, 1, 1000));
+        assert_eq!(undersize::<Confined<AsciiString, 1, 100>>(&[0]), (0, 1, 100));
+        // the elements are not read
+        assert_eq!(undersize::<Confined<Vec<u8>, 3, 100>>(&[2]), (2, 3, 100));
+        assert_eq!(undersize::<RString<AlphaCaps, AlphaNumDash, 2, 10>>(&[1, b'A']), (1, 2, 10));
+
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(undersize::<Confined<HashSet<u8>, 1, 100>>(&[0]), (0, 1, 100));
+            assert_eq!(undersize::<Confined<HashMap<u8, u8>, 1, 100>>(&[0]), (0, 1, 100));
+        }
+
+        let err = DecodeError::Undersize {
+            type_name: s!("List"),
+            declared_len: 0,
+            min_len: 1,
+            max_len: 10,
+        };
+        assert_eq!(
+            err.to_string(),
+            "length prefix of `List` declares 0 elements, while the type is confined to 1..=10 \
+             elements."
+        );
+
+        assert_eq!(decode::<Confined<Vec<u8>, 1, 100>>([1, 7]).release(), vec![7]);
+    }
+
+    #[test]
+    fn bulk_items() {
+        fn per_item<T: StrictEncode>(items: &[T]) -> Vec<u8> {
+            let mut writer = StrictWriter::in_memory::<{ usize::MAX }>();
+            for item in items {
+                writer = item.strict_encode(writer).unwrap();
+            }
+            writer.unbox().unconfine()
+        }
+        fn check<T: StrictEncode + StrictDecode + StrictDumb + Clone + core::fmt::Debug + Eq>(
+            items: impl IntoIterator<Item = T>,
+        ) {
+            let items = items.into_iter().collect::<Vec<_>>();
+            let reference = per_item(&items);
+            assert_eq!(
+                reference.len(),
+                items.len() * T::strict_dumb().strict_encoded_len::<256>().unwrap()
+            );
+
+            let list =
+                Confined::<Vec<T>, 0, { u16::MAX as usize }>::try_from(items.clone()).unwrap();
+            assert_eq!(encode(&list)[2..], reference);
+            assert_eq!(decode::<Confined<Vec<T>, 0, { u16::MAX as usize }>>(encode(&list)), list);
+
+            // deque with the items wrapped around the end of its buffer
+            let mut deque = VecDeque::with_capacity(items.len());
+            deque.extend(items.iter().skip(items.len() / 2).cloned());
+            for item in items.iter().take(items.len() / 2).rev() {
+                deque.push_front(item.clone());
+            }
+            let deque = Confined::<VecDeque<T>, 0, { u16::MAX as usize }>::try_from(deque).unwrap();
+            assert_eq!(encode(&deque)[2..], reference);
+
+            let array: [T; 5] = core::array::from_fn(|i| items[i].clone());
+            assert_eq!(encode(&array), per_item(&array));
+        }
+
+        check(0..=u8::MAX);
+        check((0..1000u16).map(|i| i.wrapping_mul(0x9E37)));
+        check((0..1000u32).map(|i| i.wrapping_mul(0x9E37_79B9)));
+        check((0..1000u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+        check((0..1000u128).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15) << 64 | i));
+        check((-128..128i16).map(|i| i as i8));
+        check((-500..500i16).map(|i| i.wrapping_mul(-0x1E37)));
+        check((-500..500i32).map(|i| i.wrapping_mul(-0x1E37_79B9)));
+        check((-500..500i64).map(|i| i.wrapping_mul(-0x1E37_79B9_7F4A_7C15)));
+        check((-500..500i128).map(|i| i << 100 | i));
+        check((0..1000u32).map(|i| u24::with(i * 0x3001)));
+        check((0..100u64).map(|i| u256::from(i) << 200 | u256::from(i)));
+        check((0..100i64).map(|i| i1024::from(-i) << 1000));
+        check((0..100u32).map(|i| Byte(i as u8)));
+    }
+
+    #[test]
+    fn preallocation() {
+        use amplify::confinement::U32;
+
+        let list = Confined::<Vec<u32>, 0, 10000>::try_from_iter(0..1000).unwrap();
+        assert_eq!(decode::<Confined<Vec<u32>, 0, 10000>>(encode(&list)).capacity(), 1000);
+        let list = Confined::<VecDeque<u32>, 0, 10000>::try_from_iter(0..1000).unwrap();
+        assert_eq!(decode::<Confined<VecDeque<u32>, 0, 10000>>(encode(&list)).capacity(), 1000);
+
+        assert_eq!(prealloc::<u8>(1000), 1000);
+        assert_eq!(prealloc::<u8>(usize::MAX), PREALLOC_LIMIT);
+        assert_eq!(prealloc::<(u64, u64)>(usize::MAX), PREALLOC_LIMIT / 16);
+        assert_eq!(prealloc::<()>(usize::MAX), PREALLOC_LIMIT);
+
+        // length prefixes declaring gigabytes of missing data
+        fn truncated<T: StrictDecode + core::fmt::Debug>(data: &[u8]) -> io::ErrorKind {
+            let mut reader = StrictReader::in_memory::<{ usize::MAX }>(data);
+            match T::strict_decode(&mut reader).unwrap_err() {
+                DecodeError::Io(err) => err.kind(),
+                err => panic!("unexpected error {err:?}"),
+            }
+        }
+        let eof = io::ErrorKind::UnexpectedEof;
+        assert_eq!(truncated::<Confined<Vec<u64>, 0, U32>>(&[0xFF; 6]), eof);
+        assert_eq!(truncated::<Confined<VecDeque<u64>, 0, U32>>(&[0xFF; 6]), eof);
+        assert_eq!(truncated::<Confined<BTreeSet<u64>, 0, U32>>(&[0xFF; 6]), eof);
+        assert_eq!(truncated::<Confined<BTreeMap<u64, u64>, 0, U32>>(&[0xFF; 6]), eof);
+        assert_eq!(truncated::<Confined<String, 0, U32>>(&[0xFF; 6]), eof);
+        assert_eq!(truncated::<Confined<Vec<u8>, 0, { u64::MAX as usize }>>(&[0xFF; 9]), eof);
+    }
+
+    #[test]
+    fn confined_dumb() {
+        fn check<T: ConfinedDumb + StrictEncode + StrictDecode + PartialEq + core::fmt::Debug>(
+            len: usize,
+        ) -> T {
+            let dumb = T::confined_dumb();
+            assert_eq!(encode(&dumb)[0] as usize, len);
+            encoding_roundtrip(&dumb);
+            dumb
+        }
+
+        assert_eq!(check::<Confined<Vec<u16>, 1, 100>>(1).release(), vec![0]);
+        assert_eq!(check::<Confined<Vec<u16>, 3, 3>>(3).release(), vec![0; 3]);
+        assert_eq!(check::<Confined<VecDeque<u8>, 2, 100>>(2).len(), 2);
+        assert_eq!(check::<Confined<BTreeSet<u8>, 1, 100>>(1).len(), 1);
+        assert_eq!(check::<Confined<BTreeMap<u8, u8>, 1, 100>>(1).len(), 1);
+        assert_eq!(check::<Confined<String, 2, 100>>(2).as_str(), "\0\0");
+        assert_eq!(check::<Confined<AsciiString, 1, 100>>(1).len(), 1);
+        assert!(check::<Confined<Vec<u8>, 0, 100>>(0).is_empty());
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(check::<Confined<HashSet<u8>, 1, 100>>(1).len(), 1);
+            assert_eq!(check::<Confined<HashMap<u8, u8>, 1, 100>>(1).len(), 1);
+        }
+
+        let ident = RString::<AlphaCaps, AlphaNumDash, 1, 10>::strict_dumb();
+        assert_eq!(ident.as_str(), "A");
+        encoding_roundtrip(&ident);
+        let ident = RString::<AlphaSmall, AlphaCaps, 3, 3>::strict_dumb();
+        assert_eq!(ident.as_str(), "aAA");
+        encoding_roundtrip(&ident);
+    }
+
+    #[test]
+    fn ascii_invalid_byte() {
+        fn invalid_pos(data: &[u8]) -> usize {
+            let mut reader = StrictReader::in_memory::<{ u16::MAX as usize }>(data);
+            match Confined::<AsciiString, 0, 1000>::strict_decode(&mut reader).unwrap_err() {
+                DecodeError::Charset(CharsetError { byte, pos, charset }) => {
+                    assert_eq!(charset, "Ascii");
+                    assert_eq!(byte, data[pos + 2]);
+                    pos
+                }
+                err => panic!("unexpected error {err:?}"),
+            }
+        }
+
+        assert_eq!(invalid_pos(b"\x05\x00\x80bcde"), 0);
+        assert_eq!(invalid_pos(b"\x05\x00ab\xFFde"), 2);
+        assert_eq!(invalid_pos(b"\x05\x00abcd\xC3"), 4);
+
+        let mut data = vec![0xE8, 0x03];
+        data.extend([b'a'; 999]);
+        data.push(0x80);
+        assert_eq!(invalid_pos(&data), 999);
+
+        let mut reader = StrictReader::in_memory::<8>(b"\x03\x00a\x80c");
+        assert_eq!(
+            Confined::<AsciiString, 0, 1000>::strict_decode(&mut reader).unwrap_err().to_string(),
+            "string contains byte 0x80 at position 1, which is not a valid character of `Ascii` \
+             set."
+        );
+        assert_eq!(decode::<Confined<AsciiString, 0, 1000>>(b"\x03\x00abc").as_str(), "abc");
+    }
+
+    #[test]
+    fn string_prefix_width() {
+        type Ascii<const MAX: usize> = Confined<AsciiString, 0, MAX>;
+        type Name<const MAX: usize> = RString<AlphaSmall, AlphaSmall, 1, MAX>;
+        type Bytes<const MAX: usize> = Confined<Vec<u8>, 0, MAX>;
+
+        fn text<T>(data: &[u8])
+        where T: StrictEncode + StrictDecode + FromStr + PartialEq + core::fmt::Debug {
+            encoding(&T::from_str("abc").ok().unwrap(), data);
+        }
+
+        text::<Confined<String, 0, 255>>(b"\x03abc");
+        text::<Confined<String, 0, 256>>(b"\x03\x00abc");
+        text::<Confined<String, 0, 65535>>(b"\x03\x00abc");
+        text::<Confined<String, 0, 65536>>(b"\x03\x00\x00abc");
+        text::<Confined<String, 0, { u32::MAX as usize }>>(b"\x03\x00\x00\x00abc");
+
+        text::<Ascii<255>>(b"\x03abc");
+        text::<Ascii<256>>(b"\x03\x00abc");
+        text::<Ascii<65535>>(b"\x03\x00abc");
+        text::<Ascii<65536>>(b"\x03\x00\x00abc");
+
+        text::<Name<255>>(b"\x03abc");
+        text::<Name<256>>(b"\x03\x00abc");
+        text::<Name<65535>>(b"\x03\x00abc");
+        text::<Name<65536>>(b"\x03\x00\x00abc");
+
+        let abc = Bytes::<{ u32::MAX as usize }>::try_from(b"abc".to_vec()).unwrap();
+        encoding(&abc, b"\x03\x00\x00\x00abc");
+        let abc = abc.release();
+        encoding(&Bytes::<255>::try_from(abc.clone()).unwrap(), b"\x03abc");
+        encoding(&Bytes::<256>::try_from(abc.clone()).unwrap(), b"\x03\x00abc");
+        encoding(&Bytes::<65535>::try_from(abc.clone()).unwrap(), b"\x03\x00abc");
+        encoding(&Bytes::<65536>::try_from(abc).unwrap(), b"\x03\x00\x00abc");
+
+        // the prefix is checked against the bound before the content is read
+        let mut reader = StrictReader::in_memory::<8>(b"\x2C\x01");
+        assert!(matches!(
+            Confined::<String, 0, 256>::strict_decode(&mut reader),
+            Err(DecodeError::Oversize {
+                declared_len: 300,
+                max_len: 256,
+                ..
+            })
+        ));
+        let mut reader = StrictReader::in_memory::<8>(b"\xFF\xFF\x01");
+        assert!(matches!(
+            Ascii::<65536>::strict_decode(&mut reader),
+            Err(DecodeError::Oversize {
+                declared_len: 0x1_FFFF,
+                max_len: 65536,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn utf8_policy() {
+        type Text = Confined<String, 0, 1000>;
+
+        let data = b"\x06\x00a\xC3\xA9bcd";
+        assert_eq!(decode::<Text>(data).as_str(), "a\u{e9}bcd");
+        let mut reader = StrictReader::in_memory::<64>(data).with_utf8_policy(Utf8Policy::Lossy);
+        assert_eq!(Text::strict_decode(&mut reader).unwrap().as_str(), "a\u{e9}bcd");
+
+        // overlong encoding of `/`
+        let data = b"\x04\x00ab\xC0\xAF";
+        let mut reader = StrictReader::in_memory::<64>(data);
+        assert_eq!(reader.utf8_policy(), Utf8Policy::Strict);
+        match Text::strict_decode(&mut reader).unwrap_err() {
+            DecodeError::Utf8(err) => assert_eq!(err.utf8_error().valid_up_to(), 2),
+            err => panic!("unexpected error {err:?}"),
+        }
+
+        let mut reader = StrictReader::in_memory::<64>(data).with_utf8_policy(Utf8Policy::Lossy);
+        let text = Text::strict_decode(&mut reader).unwrap();
+        assert_eq!(text.as_str(), "ab\u{FFFD}\u{FFFD}");
+        assert_eq!(encode(&text), b"\x08\x00ab\xEF\xBF\xBD\xEF\xBF\xBD");
+
+        let mut data = vec![0x2C, 0x01];
+        data.extend([0xFF; 300]);
+        let mut reader = StrictReader::in_memory::<1024>(&data).with_utf8_policy(Utf8Policy::Lossy);
+        assert!(matches!(
+            Confined::<String, 0, 500>::strict_decode(&mut reader),
+            Err(DecodeError::Confinement(_))
+        ));
     }
 }
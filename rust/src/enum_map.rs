@@ -0,0 +1,258 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maps keyed by all the variants of a fieldless enum, like per-network
+//! configuration tables.
+//!
+//! [`EnumMap`] holds a value for each of the variants of the key enum and is
+//! encoded as a tuple of these values in the ascending order of the variant
+//! tags, without encoding the keys themselves. Since the number of the values
+//! is defined by the enum, the map can't miss a key, and its encoding is
+//! shorter than the one of a `BTreeMap` with the same keys.
+//!
+//! Sparse maps of enum keys, like `Confined<BTreeMap<K, V>, ..>`, decode their
+//! keys with the [`StrictEnum`] tag conversion, thus a key byte which doesn't
+//! match any of the variants fails with [`DecodeError::EnumTagNotKnown`] right
+//! away.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+
+use crate::{
+    io, DecodeError, ReadTuple, StrictDecode, StrictDumb, StrictEncode, StrictEnum, StrictProduct,
+    StrictTuple, StrictType, TypedRead, TypedWrite, WriteTuple, LIB_NAME_STD,
+};
+
+/// Map containing a value for each of the variants of enum `K`.
+///
+/// Variants of the enum which are not listed in [`StrictSum::ALL_VARIANTS`],
+/// like the ones of the catch-all `other` variant, are not the keys of the map,
+/// and accessing them panics.
+///
+/// [`StrictSum::ALL_VARIANTS`]: crate::StrictSum::ALL_VARIANTS
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct EnumMap<K, V> {
+    /// Values in the ascending order of the variant tags.
+    values: Vec<V>,
+    _key: PhantomData<K>,
+}
+
+impl<K: StrictEnum, V> EnumMap<K, V>
+where u8: From<K>
+{
+    /// Constructs the map with the values returned by `f` for each of the
+    /// variants, called in the ascending order of the variant tags.
+    pub fn from_fn(f: impl FnMut(K) -> V) -> Self {
+        EnumMap {
+            values: Self::keys().map(f).collect(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Variants of the enum in the ascending order of their tags.
+    pub fn keys() -> impl Iterator<Item = K> {
+        let mut tags = K::ALL_VARIANTS.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+        tags.sort_unstable();
+        tags.into_iter()
+            .map(|tag| K::try_from(tag).expect("variant tags are convertible into the enum"))
+    }
+
+    fn index(key: K) -> usize {
+        let tag = u8::from(key);
+        assert!(
+            K::ALL_VARIANTS.iter().any(|(t, _)| *t == tag),
+            "variant with tag {tag} is not a key of the enum map"
+        );
+        K::ALL_VARIANTS.iter().filter(|(t, _)| *t < tag).count()
+    }
+
+    pub fn get(&self, key: K) -> &V { &self.values[Self::index(key)] }
+
+    pub fn get_mut(&mut self, key: K) -> &mut V { &mut self.values[Self::index(key)] }
+
+    /// Iterates over the entries in the ascending order of the variant tags.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> { Self::keys().zip(&self.values) }
+
+    /// Values in the ascending order of the variant tags.
+    pub fn values(&self) -> &[V] { &self.values }
+
+    pub fn into_values(self) -> Vec<V> { self.values }
+}
+
+impl<K: StrictEnum, V> Index<K> for EnumMap<K, V>
+where u8: From<K>
+{
+    type Output = V;
+    fn index(&self, key: K) -> &Self::Output { self.get(key) }
+}
+
+impl<K: StrictEnum, V> IndexMut<K> for EnumMap<K, V>
+where u8: From<K>
+{
+    fn index_mut(&mut self, key: K) -> &mut Self::Output { self.get_mut(key) }
+}
+
+impl<K: StrictEnum + Ord, V> From<EnumMap<K, V>> for BTreeMap<K, V>
+where u8: From<K>
+{
+    fn from(map: EnumMap<K, V>) -> Self { EnumMap::<K, V>::keys().zip(map.values).collect() }
+}
+
+// The map doesn't implement `Default`, such that its dumb value is made of the
+// dumb values, which don't require `V: Default`.
+impl<K: StrictEnum, V: StrictDumb> StrictDumb for EnumMap<K, V>
+where u8: From<K>
+{
+    fn strict_dumb() -> Self { Self::from_fn(|_| V::strict_dumb()) }
+}
+impl<K: StrictEnum, V: StrictType> StrictType for EnumMap<K, V>
+where u8: From<K>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+}
+impl<K: StrictEnum, V: StrictType + StrictDumb> StrictProduct for EnumMap<K, V> where u8: From<K> {}
+impl<K: StrictEnum, V: StrictType + StrictDumb> StrictTuple for EnumMap<K, V>
+where u8: From<K>
+{
+    const FIELD_COUNT: u8 = K::ALL_VARIANTS.len() as u8;
+}
+impl<K: StrictEnum, V: StrictEncode + StrictDumb> StrictEncode for EnumMap<K, V>
+where u8: From<K>
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_tuple::<Self>(|mut w| {
+            for value in &self.values {
+                w = w.write_field(value)?;
+            }
+            Ok(w.complete())
+        })
+    }
+}
+impl<K: StrictEnum, V: StrictDecode + StrictDumb> StrictDecode for EnumMap<K, V>
+where u8: From<K>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| {
+            let mut values = Vec::with_capacity(K::ALL_VARIANTS.len());
+            for _ in K::ALL_VARIANTS {
+                values.push(r.read_field()?);
+            }
+            Ok(EnumMap {
+                values,
+                _key: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{TinyOrdMap, TinyString};
+
+    use super::*;
+    use crate::test::{encode, encoding, try_decode};
+    use crate::KnownVariants;
+
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8, crate = crate)]
+    #[repr(u8)]
+    enum Network {
+        #[default]
+        Mainnet = 0,
+        Testnet = 3,
+        Signet = 2,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Config {
+        port: u16,
+        seed: TinyString,
+    }
+
+    fn config(network: Network) -> Config {
+        let port = 8333 + u8::from(network) as u16;
+        Config {
+            port,
+            seed: TinyString::try_from(format!("seed{port}")).unwrap(),
+        }
+    }
+
+    #[test]
+    fn sparse_invalid_key() {
+        let map = TinyOrdMap::from_iter_checked([(Network::Mainnet, 1u8), (Network::Testnet, 2)]);
+        let mut data = encode(&map);
+        assert_eq!(data, [2, 0, 1, 3, 2]);
+        assert_eq!(try_decode::<TinyOrdMap<Network, u8>>(&data).unwrap(), map);
+
+        data[3] = 1;
+        assert_eq!(
+            try_decode::<TinyOrdMap<Network, u8>>(&data).unwrap_err(),
+            DecodeError::EnumTagNotKnown {
+                type_name: s!("Network"),
+                tag: 1,
+                variants: KnownVariants::of::<Network>(),
+            }
+        );
+    }
+
+    #[test]
+    fn dense() {
+        let map = EnumMap::<Network, Config>::from_fn(config);
+        assert_eq!(EnumMap::<Network, Config>::keys().collect::<Vec<_>>(), [
+            Network::Mainnet,
+            Network::Signet,
+            Network::Testnet
+        ]);
+        assert_eq!(map[Network::Testnet], config(Network::Testnet));
+        assert_eq!(map.values()[1].port, 8335);
+
+        let mut data = vec![];
+        for port in [8333u16, 8335, 8336] {
+            let port = port.to_le_bytes();
+            data.extend([port[0], port[1], 8, b's', b'e', b'e', b'd']);
+            data.extend(format!("{}", u16::from_le_bytes(port)).as_bytes());
+        }
+        encoding(&map, data.clone());
+        // no key bytes and no length prefix, as opposed to a sparse map
+        let sparse = TinyOrdMap::try_from(BTreeMap::from(map.clone())).unwrap();
+        assert_eq!(encode(&sparse).len(), data.len() + 1 + 3);
+        assert_eq!(data.len(), 3 * 11);
+
+        let err = try_decode::<EnumMap<Network, Config>>(&data[..22]).unwrap_err();
+        assert!(matches!(err, DecodeError::Io(_)));
+
+        let mut map = EnumMap::<Network, u8>::from_fn(|_| 0);
+        map[Network::Signet] = 7;
+        assert_eq!(map.iter().collect::<Vec<_>>(), [
+            (Network::Mainnet, &0),
+            (Network::Signet, &7),
+            (Network::Testnet, &0)
+        ]);
+        encoding(&map, [0, 7, 0]);
+        assert_eq!(EnumMap::<Network, u8>::strict_dumb().into_values(), [0, 0, 0]);
+        assert_eq!(EnumMap::<Network, u8>::FIELD_COUNT, 3);
+    }
+}
@@ -0,0 +1,266 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Containers identifying the type and format version of the encoded data.
+//!
+//! [`Envelope`] is encoded as the magic bytes of the contained type, followed
+//! by `u16` format version and the strict encoding of the value. Decoding
+//! checks the magic bytes and the version before decoding the value, such that
+//! data of a different kind are rejected with [`DecodeError::WrongMagic`]
+//! rather than failing somewhere in the middle of the decoding.
+
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+use crate::{
+    io, DecodeError, ReadRaw, StrictDecode, StrictDumb, StrictEncode, StrictType, TypeName,
+    TypedRead, TypedWrite, WriteRaw, LIB_EMBEDDED,
+};
+
+/// Type which can be put into an [`Envelope`].
+pub trait StrictContainer: StrictType {
+    /// Magic bytes identifying the type; must be 4 to 8 bytes long.
+    const MAGIC: &'static [u8];
+
+    /// Version of the format used for the encoding.
+    const VERSION: u16;
+
+    /// Detects whether the data of the given format version can be decoded.
+    /// By default, only the current [`Self::VERSION`] is supported.
+    fn is_version_supported(version: u16) -> bool { version == Self::VERSION }
+}
+
+/// Magic bytes and format version at the start of the [`Envelope`] data.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct EnvelopeHeader {
+    pub magic: Vec<u8>,
+    pub version: u16,
+}
+
+impl EnvelopeHeader {
+    /// Reads the header of the envelope with `magic_len` bytes of magic and
+    /// moves the reader back, such that the envelope can be decoded from the
+    /// same reader afterwards.
+    ///
+    /// The header is not checked; the reader must support
+    /// [`ReadRaw::rewind`].
+    pub fn sniff(reader: &mut impl TypedRead, magic_len: usize) -> Result<Self, DecodeError> {
+        let magic = unsafe { reader.raw_reader().read_raw::<8>(magic_len)? };
+        let version = u16::strict_decode(reader)?;
+        reader.rewind(magic_len + 2)?;
+        Ok(EnvelopeHeader { magic, version })
+    }
+}
+
+/// Value prefixed with magic bytes and format version of its type.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Envelope<T: StrictContainer>(T);
+
+impl<T: StrictContainer> Envelope<T> {
+    const MAGIC_LEN: usize = {
+        let len = T::MAGIC.len();
+        assert!(len >= 4 && len <= 8, "container magic must be 4 to 8 bytes long");
+        len
+    };
+
+    pub fn new(value: T) -> Self { Envelope(value) }
+
+    pub fn as_inner(&self) -> &T { &self.0 }
+
+    pub fn into_inner(self) -> T { self.0 }
+
+    /// Reads the header of the envelope for type `T` and moves the reader
+    /// back; see [`EnvelopeHeader::sniff`].
+    pub fn sniff(reader: &mut impl TypedRead) -> Result<EnvelopeHeader, DecodeError> {
+        EnvelopeHeader::sniff(reader, Self::MAGIC_LEN)
+    }
+}
+
+impl<T: StrictContainer> From<T> for Envelope<T> {
+    fn from(value: T) -> Self { Envelope(value) }
+}
+
+impl<T: StrictContainer> Deref for Envelope<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T: StrictContainer> DerefMut for Envelope<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
+
+impl<T: StrictContainer> AsRef<T> for Envelope<T> {
+    fn as_ref(&self) -> &T { &self.0 }
+}
+
+impl<T: StrictContainer> StrictType for Envelope<T> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl<T: StrictContainer + StrictEncode> StrictEncode for Envelope<T> {
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        unsafe { writer.raw_writer().write_raw::<8>(&T::MAGIC[..Self::MAGIC_LEN])? };
+        writer = T::VERSION.strict_encode(writer)?;
+        self.0.strict_encode(writer)
+    }
+}
+
+impl<T: StrictContainer + StrictDecode> StrictDecode for Envelope<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let magic = unsafe { reader.raw_reader().read_raw::<8>(Self::MAGIC_LEN)? };
+        if magic != T::MAGIC {
+            return Err(DecodeError::WrongMagic { found: magic });
+        }
+        let version = u16::strict_decode(reader)?;
+        if !T::is_version_supported(version) {
+            return Err(DecodeError::UnsupportedVersion {
+                found: version,
+                supported: T::VERSION,
+            });
+        }
+        T::strict_decode(reader).map(Envelope)
+    }
+}
+
+impl<T: StrictContainer + StrictDumb> StrictDumb for Envelope<T> {
+    fn strict_dumb() -> Self { Envelope(T::strict_dumb()) }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::test::{decode, decode_err, encode};
+    use crate::{SliceReader, StrictReader};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Config {
+        name: TinyString,
+        threads: u8,
+    }
+
+    impl StrictDumb for Config {
+        fn strict_dumb() -> Self { config() }
+    }
+
+    impl StrictContainer for Config {
+        const MAGIC: &'static [u8] = b"CONFIG";
+        const VERSION: u16 = 2;
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Wallet(u64);
+
+    impl StrictContainer for Wallet {
+        const MAGIC: &'static [u8] = b"WLT\0";
+        const VERSION: u16 = 1;
+        fn is_version_supported(version: u16) -> bool { version <= Self::VERSION }
+    }
+
+    fn config() -> Config {
+        Config {
+            name: TinyString::try_from(s!("node")).unwrap(),
+            threads: 4,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let envelope = Envelope::from(config());
+        let data = encode(&envelope);
+        assert_eq!(&data[..8], b"CONFIG\x02\x00");
+        assert_eq!(data[8..], encode(&config()));
+        assert_eq!(decode::<Envelope<Config>>(&data), envelope);
+        assert_eq!(envelope.threads, 4);
+
+        let wallet = Envelope::from(Wallet(0x0102));
+        assert_eq!(encode(&wallet), b"WLT\0\x01\x00\x02\x01\0\0\0\0\0\0");
+        assert_eq!(decode::<Envelope<Wallet>>(b"WLT\0\x00\x00\x03\0\0\0\0\0\0\0").0, Wallet(3));
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let data = encode(&Envelope::from(Wallet(7)));
+        assert_eq!(decode_err::<Envelope<Config>>(&data), DecodeError::WrongMagic {
+            found: b"WLT\0\x01\x00".to_vec()
+        });
+
+        let data = encode(&Envelope::from(config()));
+        assert_eq!(decode_err::<Envelope<Wallet>>(&data), DecodeError::WrongMagic {
+            found: b"CONF".to_vec()
+        });
+    }
+
+    #[test]
+    fn future_version() {
+        let mut data = encode(&Envelope::from(config()));
+        data[6] = 3;
+        assert_eq!(decode_err::<Envelope<Config>>(&data), DecodeError::UnsupportedVersion {
+            found: 3,
+            supported: 2
+        });
+        data[6] = 1;
+        assert_eq!(decode_err::<Envelope<Config>>(&data), DecodeError::UnsupportedVersion {
+            found: 1,
+            supported: 2
+        });
+
+        let mut data = encode(&Envelope::from(Wallet(7)));
+        data[5] = 1;
+        assert_eq!(decode_err::<Envelope<Wallet>>(&data), DecodeError::UnsupportedVersion {
+            found: 0x0101,
+            supported: 1
+        });
+    }
+
+    #[test]
+    fn sniff() {
+        let data = encode(&Envelope::from(config()));
+
+        let mut reader = StrictReader::with(SliceReader::new::<64>(&data));
+        let header = Envelope::<Config>::sniff(&mut reader).unwrap();
+        assert_eq!(header, EnvelopeHeader {
+            magic: b"CONFIG".to_vec(),
+            version: 2
+        });
+        assert_eq!(reader.pos(), 0);
+        // different type with the magic of other length is detected as well
+        let header = Envelope::<Wallet>::sniff(&mut reader).unwrap();
+        assert_eq!(header, EnvelopeHeader {
+            magic: b"CONF".to_vec(),
+            version: u16::from_le_bytes(*b"IG")
+        });
+        assert_eq!(Envelope::<Config>::strict_decode(&mut reader).unwrap().into_inner(), config());
+
+        let mut reader = StrictReader::in_memory::<64>(&data);
+        assert_eq!(EnvelopeHeader::sniff(&mut reader, 6).unwrap().version, 2);
+        assert_eq!(Envelope::<Config>::strict_decode(&mut reader).unwrap().into_inner(), config());
+
+        let mut reader = StrictReader::with(SliceReader::new::<64>(&data[..7]));
+        assert!(matches!(Envelope::<Config>::sniff(&mut reader), Err(DecodeError::Io(_))));
+    }
+}
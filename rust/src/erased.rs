@@ -0,0 +1,136 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Object-safe strict encoding, used to encode values of different types
+//! stored behind `dyn` pointers in the same container.
+//!
+//! [`StrictEncode`] is generic over the writer, thus it can't be used as a
+//! trait object; [`StrictEncodeErased`] erases the writer type by encoding
+//! into `&mut dyn io::Write`. It is implemented for all [`StrictEncode`]
+//! types and produces exactly the same bytes. Decoding requires the concrete
+//! type to be known and remains typed.
+
+use alloc::vec::Vec;
+
+use amplify::confinement::Confined;
+
+use crate::{io, SerializeError, StreamWriter, StrictEncode, StrictWriter};
+
+/// Object-safe version of [`StrictEncode`], which is implemented for all
+/// strict-encodable types.
+pub trait StrictEncodeErased {
+    /// Strict-encodes the value into the writer. Fails with
+    /// [`io::ErrorKind::InvalidInput`] error if the encoding exceeds `max_len`
+    /// bytes, in which case the part of the encoding up to `max_len` bytes may
+    /// be already written.
+    fn strict_encode_erased(&self, writer: &mut dyn io::Write, max_len: usize) -> io::Result<()>;
+}
+
+impl<T: StrictEncode> StrictEncodeErased for T {
+    fn strict_encode_erased(&self, writer: &mut dyn io::Write, max_len: usize) -> io::Result<()> {
+        let writer = StrictWriter::with(StreamWriter::with(max_len, writer));
+        self.strict_encode(writer)?;
+        Ok(())
+    }
+}
+
+/// Serializes a type-erased value into a byte vector of at most `MAX` bytes,
+/// like [`StrictSerialize::to_strict_serialized`](crate::StrictSerialize::to_strict_serialized)
+/// does for the typed values.
+pub fn encode_dyn<const MAX: usize>(
+    value: &dyn StrictEncodeErased,
+) -> Result<Confined<Vec<u8>, 0, MAX>, SerializeError> {
+    let mut data = vec![];
+    value.strict_encode_erased(&mut data, MAX)?;
+    Confined::try_from(data).map_err(SerializeError::from)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+
+    use amplify::confinement::{TinyString, TinyVec};
+
+    use super::*;
+    use crate::test::encode;
+    use crate::{StrictDecode, StrictType};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Ping {
+        nonce: u64,
+        payload: TinyVec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Status {
+        #[default]
+        Ok,
+        Failed(TinyString),
+    }
+
+    #[test]
+    fn heterogeneous() {
+        let ping = Ping {
+            nonce: 0x0102,
+            payload: tiny_vec![1, 2, 3],
+        };
+        let status = Status::Failed(tiny_s!("timeout"));
+        let expected = [
+            encode(&ping),
+            encode(&status),
+            encode(&Some(7u16)),
+            encode(&tiny_s!("text")),
+            encode(&0xABCDu32),
+        ];
+        let values: Vec<Box<dyn StrictEncodeErased>> = vec![
+            Box::new(ping),
+            Box::new(status),
+            Box::new(Some(7u16)),
+            Box::new(tiny_s!("text")),
+            Box::new(0xABCDu32),
+        ];
+        for (value, expected) in values.iter().zip(expected) {
+            assert_eq!(encode_dyn::<1024>(value.as_ref()).unwrap().as_slice(), expected);
+        }
+
+        let mut stream = vec![];
+        for value in &values {
+            value.strict_encode_erased(&mut stream, 1024).unwrap();
+        }
+        let mut reader = crate::StrictReader::in_memory::<1024>(stream);
+        assert_eq!(Ping::strict_decode(&mut reader).unwrap().nonce, 0x0102);
+        assert_eq!(Status::strict_decode(&mut reader).unwrap(), Status::Failed(tiny_s!("timeout")));
+    }
+
+    #[test]
+    fn max_len() {
+        let value: Box<dyn StrictEncodeErased> = Box::new(0xABCDu32);
+        assert_eq!(encode_dyn::<4>(value.as_ref()).unwrap().as_slice(), &[0xCD, 0xAB, 0, 0]);
+        let SerializeError::Io(err) = encode_dyn::<3>(value.as_ref()).unwrap_err() else {
+            panic!("encoding must fail")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -19,12 +19,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io;
-use std::ops::Range;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Range;
 
-use amplify::{confinement, IoError};
+use amplify::confinement;
+#[cfg(feature = "std")]
+use amplify::IoError;
 
-#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[cfg(not(feature = "std"))]
+use crate::io::Error as IoError;
+use crate::reader::decoded_name;
+use crate::{
+    io, type_name, FieldName, HexError, InvalidRString, StrictDecode, StrictSum, StrictType,
+    VariantName,
+};
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
 #[display(doc_comments)]
 pub enum DecodeError {
     #[display(inner)]
@@ -35,35 +48,86 @@ pub enum DecodeError {
     #[from]
     Confinement(confinement::Error),
 
-    /// non-zero natural number can't have a value equal to zero.
+    /// length prefix of `{type_name}` declares {declared_len} elements, while
+    /// the type is confined to at most {max_len} elements.
+    Oversize {
+        type_name: String,
+        declared_len: usize,
+        max_len: usize,
+    },
+
+    /// length prefix of `{type_name}` declares {declared_len} elements, while
+    /// the type is confined to {min_len}..={max_len} elements.
+    Undersize {
+        type_name: String,
+        declared_len: usize,
+        min_len: usize,
+        max_len: usize,
+    },
+
+    /// non-zero number can't have a value equal to zero.
     ZeroNatural,
 
+    /// value {0:#06x} is not a valid unicode scalar value.
+    InvalidChar(u32),
+
     /// string data are not in valid UTF-8 encoding.\nDetails: {0}
     #[from]
-    Utf8(std::string::FromUtf8Error),
+    Utf8(alloc::string::FromUtf8Error),
 
     /// string data are not in valid UTF-8 encoding.\nDetails: {0}
     #[from]
     Ascii(amplify::ascii::AsAsciiStrError),
 
-    /// value occurs multiple times in a set
-    RepeatedSetValue,
+    #[display(inner)]
+    #[from]
+    Charset(CharsetError),
+
+    /// restricted string data are invalid: {0}
+    #[from]
+    RString(InvalidRString),
 
-    /// key occurs multiple times in a map
-    RepeatedMapValue,
+    /// value {value} occurs multiple times in set `{type_name}`.
+    RepeatedValue {
+        type_name: String,
+        value: RepeatedItem,
+    },
 
-    /// unsupported value `{1}` for enum `{0}` encountered during decode
-    /// operation
-    EnumTagNotKnown(String, u8),
+    /// key {key} occurs multiple times in map `{type_name}`.
+    RepeatedKey {
+        type_name: String,
+        key: RepeatedItem,
+    },
 
-    /// unsupported value `{1}` for union `{0}` encountered during decode
-    /// operation
-    UnionTagNotKnown(String, u8),
+    /// unexpected variant {tag} for enum `{type_name}`; known variants:
+    /// {variants}
+    EnumTagNotKnown {
+        type_name: String,
+        tag: u8,
+        variants: KnownVariants,
+    },
+
+    /// unexpected variant {tag} for union `{type_name}`; known variants:
+    /// {variants}
+    UnionTagNotKnown {
+        type_name: String,
+        tag: u8,
+        variants: KnownVariants,
+    },
 
     /// decoding resulted in value `{2}` for type `{0}` that exceeds the
     /// supported range {1:#?}
     ValueOutOfRange(String, Range<u128>, u128),
 
+    #[display(inner)]
+    OutOfBounds(Box<OutOfBounds>),
+
+    #[display(inner)]
+    CharCount(Box<InvalidCharCount>),
+
+    /// decoded time value `{1}` can't be represented by type `{0}`
+    TimeOutOfRange(String, i64),
+
     /// encoded values are not deterministically ordered within a set
     BrokenSetOrder,
 
@@ -72,9 +136,417 @@ pub enum DecodeError {
 
     /// data integrity problem during strict decoding operation.\nDetails: {0}
     DataIntegrityError(String),
+
+    /// decoded value of type `{from}` can't be converted into `{into}`:
+    /// {message}
+    Conversion {
+        from: String,
+        into: String,
+        message: String,
+    },
+
+    /// decoded value of type `{type_name}` is invalid: {message}
+    InvalidValue { type_name: String, message: String },
+
+    /// flags `{type_name}` have bits {unknown:#x} set, which don't belong to
+    /// any of the declared flags.
+    UnknownFlags { type_name: String, unknown: u128 },
+
+    /// variable-length integer `{type_name}` is not encoded in its minimal
+    /// form.
+    NonMinimalVarInt { type_name: String },
+
+    /// padding bits in the last byte of `{type_name}` are set, while they must
+    /// be zero.
+    NonZeroPadding { type_name: String },
+
+    /// type `{0}` has no values, thus the data can't be decoded as it.
+    Uninhabited(String),
+
+    /// checksum of the decoded data {actual:02x?} doesn't match the expected
+    /// checksum {expected:02x?}.
+    ChecksumMismatch { expected: Vec<u8>, actual: Vec<u8> },
+
+    /// data start with bytes {found:02x?}, which don't match the magic of the
+    /// expected container type.
+    WrongMagic { found: Vec<u8> },
+
+    /// container format version {found} is not supported; the supported
+    /// version is {supported}.
+    UnsupportedVersion { found: u16, supported: u16 },
+
+    /// data are typed with semantic id {found}, while type with semantic id
+    /// {expected} is expected.
+    TypeMismatch {
+        expected: crate::SemId,
+        found: crate::SemId,
+    },
+
+    /// nesting of the decoded data exceeds the limit of {limit} levels.
+    MaxRecursionDepth { limit: usize },
+
+    /// decoded data require allocation of {attempted} bytes, exceeding the
+    /// budget of {budget} bytes.
+    AllocationBudgetExceeded { budget: usize, attempted: usize },
+
+    /// type `{0}`: {1} of {2} fields read. This indicates a bug in the
+    /// implementation of the type decoding.
+    FieldsNotRead(String, usize, usize),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(err) => Some(err),
+            DecodeError::Confinement(err) => Some(err),
+            DecodeError::Utf8(err) => Some(err),
+            DecodeError::Ascii(err) => Some(err),
+            DecodeError::Charset(err) => Some(err),
+            DecodeError::RString(err) => Some(err),
+            DecodeError::OutOfBounds(err) => Some(err.as_ref()),
+            DecodeError::CharCount(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for String {
+    fn from(err: DecodeError) -> Self { err.to_string() }
+}
+
+/// Category of a [`DecodeError`], telling how the failure may be handled.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum DecodeErrorKind {
+    /// data end before the value is complete; the decoding may succeed once
+    /// more data are available.
+    UnexpectedEof,
+
+    /// input or output operation has failed for the reasons not related to
+    /// the decoded data.
+    Io,
+
+    /// data are not a valid strict encoding of the type: they have an unknown
+    /// enum or union tag, invalid characters, non-canonical value, wrong order
+    /// of the collection items etc.
+    InvalidData,
+
+    /// data exceed a limit of the reader or a length confinement of the type.
+    Limit,
+
+    /// decoding has failed due to a bug in the implementation of the type
+    /// decoding.
+    Internal,
+}
+
+/// Limit which is exceeded by the decoded data, reported by
+/// [`DecodeError::limit_violation`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum LimitViolation {
+    /// collection or string has {len} elements, exceeding the limit of
+    /// {max_len} elements of its type.
+    TooLong { len: usize, max_len: usize },
+
+    /// collection or string has {len} elements, which is less than the minimum
+    /// of {min_len} elements of its type.
+    TooShort { len: usize, min_len: usize },
+
+    /// data exceed the limit on the number of bytes the reader may consume.
+    ReadLimit,
+
+    /// nesting of the data exceeds the limit of {limit} levels.
+    RecursionDepth { limit: usize },
+
+    /// data require allocation of {attempted} bytes, exceeding the budget of
+    /// {budget} bytes.
+    AllocationBudget { budget: usize, attempted: usize },
+}
+
+impl DecodeError {
+    /// Category of the error.
+    ///
+    /// I/O errors of [`io::ErrorKind::UnexpectedEof`] kind are
+    /// [`DecodeErrorKind::UnexpectedEof`], while the ones of
+    /// [`io::ErrorKind::InvalidInput`] and [`io::ErrorKind::OutOfMemory`] kinds,
+    /// returned by the readers once their limit is reached, are
+    /// [`DecodeErrorKind::Limit`]. The errors of the `Limit` kind are those
+    /// which report a [`Self::limit_violation`].
+    pub fn kind(&self) -> DecodeErrorKind {
+        match self {
+            DecodeError::Io(err) => match err.kind() {
+                io::ErrorKind::UnexpectedEof => DecodeErrorKind::UnexpectedEof,
+                io::ErrorKind::InvalidInput | io::ErrorKind::OutOfMemory => DecodeErrorKind::Limit,
+                _ => DecodeErrorKind::Io,
+            },
+
+            DecodeError::Confinement(confinement::Error::OutOfBoundary { .. }) => {
+                DecodeErrorKind::InvalidData
+            }
+            DecodeError::Confinement(_)
+            | DecodeError::Oversize { .. }
+            | DecodeError::Undersize { .. }
+            | DecodeError::CharCount(_)
+            | DecodeError::MaxRecursionDepth { .. }
+            | DecodeError::AllocationBudgetExceeded { .. } => DecodeErrorKind::Limit,
+
+            DecodeError::ZeroNatural
+            | DecodeError::InvalidChar(_)
+            | DecodeError::Utf8(_)
+            | DecodeError::Ascii(_)
+            | DecodeError::Charset(_)
+            | DecodeError::RString(_)
+            | DecodeError::RepeatedValue { .. }
+            | DecodeError::RepeatedKey { .. }
+            | DecodeError::EnumTagNotKnown { .. }
+            | DecodeError::UnionTagNotKnown { .. }
+            | DecodeError::ValueOutOfRange(..)
+            | DecodeError::OutOfBounds(_)
+            | DecodeError::TimeOutOfRange(..)
+            | DecodeError::BrokenSetOrder
+            | DecodeError::BrokenMapOrder
+            | DecodeError::DataIntegrityError(_)
+            | DecodeError::Conversion { .. }
+            | DecodeError::InvalidValue { .. }
+            | DecodeError::UnknownFlags { .. }
+            | DecodeError::NonMinimalVarInt { .. }
+            | DecodeError::NonZeroPadding { .. }
+            | DecodeError::Uninhabited(_)
+            | DecodeError::ChecksumMismatch { .. }
+            | DecodeError::WrongMagic { .. }
+            | DecodeError::UnsupportedVersion { .. }
+            | DecodeError::TypeMismatch { .. } => DecodeErrorKind::InvalidData,
+
+            DecodeError::FieldsNotRead(..) => DecodeErrorKind::Internal,
+        }
+    }
+
+    /// Detects whether the data have ended before the value is complete, such
+    /// that the decoding may be retried once more data are available.
+    pub fn is_eof(&self) -> bool { self.kind() == DecodeErrorKind::UnexpectedEof }
+
+    /// Detects whether the data are not a valid encoding of the type, such
+    /// that the decoding can't succeed with any more data.
+    pub fn is_invalid_data(&self) -> bool { self.kind() == DecodeErrorKind::InvalidData }
+
+    /// Returns the limit exceeded by the data, if the error is caused by the
+    /// reader limits or the length confinement of the decoded type.
+    pub fn limit_violation(&self) -> Option<LimitViolation> {
+        Some(match *self {
+            DecodeError::Io(ref err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::InvalidInput | io::ErrorKind::OutOfMemory
+                ) =>
+            {
+                LimitViolation::ReadLimit
+            }
+            DecodeError::Confinement(confinement::Error::Oversize { len, max_len })
+            | DecodeError::Oversize {
+                declared_len: len,
+                max_len,
+                ..
+            } => LimitViolation::TooLong { len, max_len },
+            DecodeError::Confinement(confinement::Error::Undersize { len, min_len })
+            | DecodeError::Undersize {
+                declared_len: len,
+                min_len,
+                ..
+            } => LimitViolation::TooShort { len, min_len },
+            DecodeError::CharCount(ref err) if err.count < err.min_len => {
+                LimitViolation::TooShort {
+                    len: err.count,
+                    min_len: err.min_len,
+                }
+            }
+            DecodeError::CharCount(ref err) => LimitViolation::TooLong {
+                len: err.count,
+                max_len: err.max_len,
+            },
+            DecodeError::MaxRecursionDepth { limit } => LimitViolation::RecursionDepth { limit },
+            DecodeError::AllocationBudgetExceeded { budget, attempted } => {
+                LimitViolation::AllocationBudget { budget, attempted }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Error of converting a decoded value of type `W` into type `T`, which
+    /// keeps the message of the conversion error `err`.
+    pub fn conversion<W, T>(err: impl Display) -> Self {
+        DecodeError::Conversion {
+            from: type_name::<W>(),
+            into: type_name::<T>(),
+            message: err.to_string(),
+        }
+    }
+
+    /// Error of a set of type `T`, in which the value `item` at `index` repeats
+    /// the preceding one.
+    pub fn repeated_value<T: StrictType>(item: &impl StrictDecode, index: usize) -> Self {
+        DecodeError::RepeatedValue {
+            type_name: decoded_name::<T>(),
+            value: RepeatedItem::of(item, index),
+        }
+    }
+
+    /// Error of a map of type `T`, in which the key `key` at `index` repeats
+    /// the preceding one.
+    pub fn repeated_key<T: StrictType>(key: &impl StrictDecode, index: usize) -> Self {
+        DecodeError::RepeatedKey {
+            type_name: decoded_name::<T>(),
+            key: RepeatedItem::of(key, index),
+        }
+    }
+
+    /// Error of a decoded value of type `T` which violates the invariants of
+    /// the type, as reported by the validation error `err`.
+    pub fn invalid<T>(err: impl Display) -> Self {
+        DecodeError::InvalidValue {
+            type_name: type_name::<T>(),
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Value of a set or key of a map which repeats the preceding one in the
+/// decoded data.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum RepeatedItem {
+    /// Rendering of the value, for the types which can be rendered, like
+    /// integers and strings.
+    #[display("`{0}`")]
+    Value(String),
+
+    /// Index of the value in the collection, for the other types.
+    #[display("#{0}")]
+    Index(usize),
+}
+
+impl RepeatedItem {
+    /// Maximal number of characters of the rendered value kept in the error.
+    pub const MAX_LEN: usize = 64;
+
+    /// Reports the item at `index`, rendering it if its type supports that.
+    /// Since the rendering comes from the decoded data, it is escaped and
+    /// truncated to [`RepeatedItem::MAX_LEN`] characters.
+    pub fn of<T: StrictDecode>(item: &T, index: usize) -> Self {
+        let Some(rendered) = item.strict_display() else {
+            return RepeatedItem::Index(index);
+        };
+        let mut value =
+            rendered.chars().take(Self::MAX_LEN).flat_map(char::escape_debug).collect::<String>();
+        if rendered.chars().nth(Self::MAX_LEN).is_some() {
+            value.push('…');
+        }
+        RepeatedItem::Value(value)
+    }
+}
+
+/// Tags and names of the variants of an enum or union, reported when an
+/// unknown variant tag is decoded.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct KnownVariants(pub Vec<(u8, VariantName)>);
+
+impl KnownVariants {
+    /// Variants declared by the type `T`.
+    pub fn of<T: StrictSum>() -> Self {
+        KnownVariants(T::ALL_VARIANTS.iter().map(|(tag, name)| (*tag, vname!(*name))).collect())
+    }
+}
+
+impl Display for KnownVariants {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, (tag, name)) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{name}={tag}")?;
+        }
+        Ok(())
+    }
+}
+
+/// string contains byte {byte:#04x} at position {pos}, which is not a valid
+/// character of `{charset}` set.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub struct CharsetError {
+    /// Value of the invalid byte.
+    pub byte: u8,
+    /// Zero-based position of the byte within the string.
+    pub pos: usize,
+    /// Name of the character set the string must consist of.
+    pub charset: String,
+}
+
+impl CharsetError {
+    pub fn with<C: StrictType>(byte: u8, pos: usize) -> Self {
+        CharsetError {
+            byte,
+            pos,
+            charset: decoded_name::<C>(),
+        }
+    }
+
+    /// Checks that all the `bytes`, located in a string starting from the
+    /// position `offset`, are characters of the set `C`.
+    pub(crate) fn check<C: StrictType + TryFrom<u8>>(
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(), Self> {
+        match bytes.iter().position(|byte| C::try_from(*byte).is_err()) {
+            None => Ok(()),
+            Some(pos) => Err(CharsetError::with::<C>(bytes[pos], offset + pos)),
+        }
+    }
+}
+
+impl_error!(CharsetError);
+
+/// value {value} of `{type_name}` is out of its bounds {min}..={max}
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub struct OutOfBounds {
+    /// Name of the bounded type.
+    pub type_name: String,
+    /// Actual value.
+    pub value: i128,
+    /// Minimal value allowed by the type.
+    pub min: i128,
+    /// Maximal value allowed by the type.
+    pub max: i128,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+impl_error!(OutOfBounds);
+
+impl From<OutOfBounds> for DecodeError {
+    fn from(err: OutOfBounds) -> Self { DecodeError::OutOfBounds(Box::new(err)) }
+}
+
+impl From<InvalidCharCount> for DecodeError {
+    fn from(err: InvalidCharCount) -> Self { DecodeError::CharCount(Box::new(err)) }
+}
+
+/// `{type_name}` has {count} characters, which is out of its bounds
+/// {min_len}..={max_len}.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub struct InvalidCharCount {
+    /// Name of the string type.
+    pub type_name: String,
+    /// Actual number of the characters.
+    pub count: usize,
+    /// Minimal number of the characters allowed by the type.
+    pub min_len: usize,
+    /// Maximal number of the characters allowed by the type.
+    pub max_len: usize,
+}
+
+impl_error!(InvalidCharCount);
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
 #[display(doc_comments)]
 pub enum SerializeError {
     #[display(inner)]
@@ -86,7 +558,9 @@ pub enum SerializeError {
     Confinement(confinement::Error),
 }
 
-#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+impl_error!(SerializeError);
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
 #[display(doc_comments)]
 pub enum DeserializeError {
     #[display(inner)]
@@ -94,6 +568,317 @@ pub enum DeserializeError {
     #[from(io::Error)]
     Decode(DecodeError),
 
-    /// data are not entirely consumed during strict deserialize operation
-    DataNotEntirelyConsumed,
+    /// data are not entirely consumed during strict deserialize operation: {0}
+    /// bytes are left.
+    DataNotEntirelyConsumed(u64),
+}
+
+impl_error!(DeserializeError);
+
+/// Errors of deserializing a value from the hex string of its strict
+/// encoding.
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum HexDeserializeError {
+    #[display(inner)]
+    #[from]
+    Hex(HexError),
+
+    /// hex string encodes {len} bytes, exceeding the limit of {max_len} bytes.
+    Oversize { len: usize, max_len: usize },
+
+    #[display(inner)]
+    #[from]
+    #[from(DecodeError)]
+    Deserialize(DeserializeError),
+}
+
+impl_error!(HexDeserializeError);
+
+/// Error of writing to a fixed-size buffer which doesn't have enough space for
+/// the data. It is returned wrapped into [`io::Error`] of
+/// [`io::ErrorKind::WriteZero`] kind, and can be extracted from it with
+/// [`BufferOverflow::from_io`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display)]
+#[display("buffer overflow at byte {pos}: {len} bytes don't fit into a buffer of {capacity} bytes")]
+pub struct BufferOverflow {
+    /// Position in the buffer at which the failed write has started.
+    pub pos: usize,
+    /// Length of the data which were attempted to be written.
+    pub len: usize,
+    /// Capacity of the buffer.
+    pub capacity: usize,
+}
+
+impl_error!(BufferOverflow);
+
+impl BufferOverflow {
+    /// Extracts buffer overflow details from an I/O error, if the error was
+    /// caused by the buffer overflow.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<Self>().cloned()
+    }
+
+    /// Extracts buffer overflow details from an I/O error, if the error was
+    /// caused by the buffer overflow.
+    #[cfg(not(feature = "std"))]
+    pub fn from_io(err: &io::Error) -> Option<Self> { err.overflow }
+}
+
+#[cfg(feature = "std")]
+impl From<BufferOverflow> for io::Error {
+    fn from(err: BufferOverflow) -> Self { io::Error::new(io::ErrorKind::WriteZero, err) }
+}
+
+/// Error of encoding a collection or a string which length is out of the
+/// bounds of its type, for instance due to a bug bypassing the confinement
+/// checks. It is returned wrapped into [`io::Error`] of
+/// [`io::ErrorKind::InvalidInput`] kind, and can be extracted from it with
+/// [`InvalidLength::from_io`]. Nothing is written for the value with an invalid
+/// length.
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+#[display("`{type_name}` has {len} elements, which is out of its bounds {min_len}..={max_len}")]
+pub struct InvalidLength {
+    /// Name of the encoded type.
+    pub type_name: String,
+    /// Actual number of the elements.
+    pub len: usize,
+    /// Minimal number of the elements allowed by the type.
+    pub min_len: usize,
+    /// Maximal number of the elements allowed by the type.
+    pub max_len: usize,
+}
+
+impl_error!(InvalidLength);
+
+impl InvalidLength {
+    /// Checks that a value of type `T` with `len` elements fits into the
+    /// `MIN_LEN..=MAX_LEN` bounds.
+    pub fn check<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        len: usize,
+    ) -> Result<(), Self> {
+        Self::check_with::<MIN_LEN, MAX_LEN>(len, decoded_name::<T>)
+    }
+
+    /// Checks that a value named `type_name` with `len` elements fits into the
+    /// `MIN_LEN..=MAX_LEN` bounds.
+    pub(crate) fn check_with<const MIN_LEN: usize, const MAX_LEN: usize>(
+        len: usize,
+        type_name: impl FnOnce() -> String,
+    ) -> Result<(), Self> {
+        if (MIN_LEN..=MAX_LEN).contains(&len) {
+            return Ok(());
+        }
+        Err(InvalidLength {
+            type_name: type_name(),
+            len,
+            min_len: MIN_LEN,
+            max_len: MAX_LEN,
+        })
+    }
+
+    /// Extracts invalid length details from an I/O error, if the error was
+    /// caused by encoding a value with an invalid length.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<Self>().cloned()
+    }
+
+    /// Extracts invalid length details from an I/O error, if the error was
+    /// caused by encoding a value with an invalid length.
+    #[cfg(not(feature = "std"))]
+    pub fn from_io(err: &io::Error) -> Option<Self> { err.length.as_deref().cloned() }
+}
+
+#[cfg(feature = "std")]
+impl From<InvalidLength> for io::Error {
+    fn from(err: InvalidLength) -> Self { io::Error::new(io::ErrorKind::InvalidInput, err) }
+}
+
+/// Error of writing more bytes than the writer is limited to. It is returned
+/// wrapped into [`io::Error`] of [`io::ErrorKind::InvalidInput`] kind, and can
+/// be extracted from it with [`WriteLimitExceeded::from_io`]. The limit is
+/// checked before the data are written, thus none of the bytes of the failed
+/// write reach the underlying writer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display)]
+#[display("writing {len} bytes after {total} bytes exceeds the limit of {limit} bytes")]
+pub struct WriteLimitExceeded {
+    /// Maximal number of bytes the writer may write.
+    pub limit: usize,
+    /// Number of bytes written before the failed write.
+    pub total: usize,
+    /// Number of bytes which were attempted to be written.
+    pub len: usize,
+}
+
+impl_error!(WriteLimitExceeded);
+
+impl WriteLimitExceeded {
+    /// Checks that writing `len` more bytes after `total` bytes doesn't exceed
+    /// the `limit`.
+    pub(crate) fn check(limit: usize, total: usize, len: usize) -> Result<(), Self> {
+        match total.checked_add(len) {
+            Some(end) if end <= limit => Ok(()),
+            _ => Err(WriteLimitExceeded { limit, total, len }),
+        }
+    }
+
+    /// Extracts write limit details from an I/O error, if the error was caused
+    /// by exceeding the limit of a writer.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<Self>().cloned()
+    }
+
+    /// Extracts write limit details from an I/O error, if the error was caused
+    /// by exceeding the limit of a writer.
+    #[cfg(not(feature = "std"))]
+    pub fn from_io(err: &io::Error) -> Option<Self> { err.limit.as_deref().copied() }
+}
+
+#[cfg(feature = "std")]
+impl From<WriteLimitExceeded> for io::Error {
+    fn from(err: WriteLimitExceeded) -> Self { io::Error::new(io::ErrorKind::InvalidInput, err) }
+}
+
+/// Segment of a path to a value within the decoded data.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum PathSegment {
+    /// Top-level type being decoded.
+    #[display(inner)]
+    Type(String),
+    /// Named field of a structure.
+    #[display(".{0}")]
+    Field(FieldName),
+    /// Field of a tuple, by its index.
+    #[display(".{0}")]
+    Index(u8),
+    /// Variant of a union.
+    #[display("::{0}")]
+    Variant(VariantName),
+    /// Item of a collection or an array, by its position.
+    #[display("[{0}]")]
+    Item(usize),
+}
+
+/// Decoding error reported together with the location in the data at which it
+/// has happened.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ContextError {
+    /// The original decoding error.
+    pub error: DecodeError,
+    /// Byte offset at which decoding of the innermost failed value has started.
+    pub offset: u64,
+    /// Path to the innermost value which has failed to decode.
+    pub path: Vec<PathSegment>,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { Some(&self.error) }
+}
+
+impl From<ContextError> for String {
+    fn from(err: ContextError) -> Self { err.to_string() }
+}
+
+impl Display for ContextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for segment in &self.path {
+            Display::fmt(segment, f)?;
+        }
+        write!(f, " at byte {}: {}", self.offset, self.error)
+    }
+}
+
+impl From<ContextError> for DecodeError {
+    fn from(err: ContextError) -> Self { err.error }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, TinyOrdSet, TinyString};
+
+    use super::*;
+    use crate::test::decode_err;
+    use crate::{StrictDecode, StrictReader};
+
+    #[test]
+    fn eof() {
+        let err = decode_err::<u32>(&[1, 2]);
+        assert_eq!(err.kind(), DecodeErrorKind::UnexpectedEof);
+        assert!(err.is_eof());
+        assert!(!err.is_invalid_data());
+        assert_eq!(err.limit_violation(), None);
+
+        let err = decode_err::<TinyString>(&[3, b'a']);
+        assert!(err.is_eof());
+    }
+
+    #[test]
+    fn invalid_data() {
+        let err = decode_err::<Option<u8>>(&[2, 0]);
+        assert!(matches!(err, DecodeError::UnionTagNotKnown { tag: 2, .. }));
+        assert_eq!(err.kind(), DecodeErrorKind::InvalidData);
+
+        let err = decode_err::<TinyString>(&[1, 0xFF]);
+        assert!(matches!(err, DecodeError::Utf8(_)));
+        assert!(err.is_invalid_data());
+
+        let err = decode_err::<TinyOrdSet<u8>>(&[2, 2, 1]);
+        assert_eq!(err, DecodeError::BrokenSetOrder);
+        assert!(err.is_invalid_data());
+        assert!(!err.is_eof());
+        assert_eq!(err.limit_violation(), None);
+
+        let err = decode_err::<char>(&[0x00, 0xD8, 0x00, 0x00]);
+        assert_eq!(err, DecodeError::InvalidChar(0xD800));
+        assert!(err.is_invalid_data());
+    }
+
+    #[test]
+    fn limits() {
+        let err = decode_err::<Confined<Vec<u8>, 0, 2>>(&[3, 1, 2, 3]);
+        assert!(matches!(err, DecodeError::Oversize {
+            declared_len: 3,
+            max_len: 2,
+            ..
+        }));
+        assert_eq!(err.kind(), DecodeErrorKind::Limit);
+        assert_eq!(err.limit_violation(), Some(LimitViolation::TooLong { len: 3, max_len: 2 }));
+
+        let err = decode_err::<Confined<Vec<u8>, 2, 4>>(&[1, 1]);
+        assert_eq!(err.limit_violation(), Some(LimitViolation::TooShort { len: 1, min_len: 2 }));
+        assert!(!err.is_invalid_data());
+
+        let mut reader = StrictReader::in_memory::<2>([1, 2, 3, 4]);
+        let err = u32::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), DecodeErrorKind::Limit);
+        assert_eq!(err.limit_violation(), Some(LimitViolation::ReadLimit));
+        assert!(!err.is_eof());
+
+        let mut reader = StrictReader::in_memory::<64>([1, 1, 5]).with_depth_limit(1);
+        let err = Option::<Option<u8>>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err.limit_violation(), Some(LimitViolation::RecursionDepth { limit: 1 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn source() {
+        use std::error::Error;
+
+        let err = decode_err::<u32>(&[1, 2]);
+        let source = err.source().expect("I/O errors have source");
+        let io = source.downcast_ref::<IoError>().expect("source is the I/O error");
+        assert_eq!(io.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = decode_err::<TinyString>(&[1, 0xFF]);
+        assert!(err.source().unwrap().is::<alloc::string::FromUtf8Error>());
+        assert!(DecodeError::BrokenSetOrder.source().is_none());
+
+        let mut reader = StrictReader::in_memory::<64>([1, 2]);
+        let err = reader.decode_with_context::<u32>().unwrap_err();
+        assert_eq!(err.source().unwrap().source().unwrap().downcast_ref::<IoError>(), Some(io));
+    }
 }
@@ -0,0 +1,154 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of the flag sets defined with `bitflags!` macro, available
+//! with `bitflags` feature.
+//!
+//! A flag set is encoded with [`FlagsOf`] wrapper as its underlying unsigned
+//! integer, i.e. as the value returned by [`Flags::bits`], such that the
+//! encoding depends only on the values of the declared flags and not on the
+//! version of `bitflags` crate. An empty set is encoded as zero.
+//!
+//! Decoding fails with [`DecodeError::UnknownFlags`] error if any of the
+//! decoded bits doesn't belong to a declared flag, thus the decoded flags are
+//! always equal to the encoded ones.
+
+use core::ops::Deref;
+
+use bitflags::{Bits, Flags};
+
+use crate::{
+    io, type_name, DecodeError, StrictDecode, StrictEncode, StrictType, TypeName, TypedRead,
+    TypedWrite,
+};
+
+/// Wrapper for the flag sets defined with `bitflags!` macro, providing their
+/// strict encoding.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FlagsOf<F: Flags>(F);
+
+impl<F: Flags> From<F> for FlagsOf<F> {
+    fn from(flags: F) -> Self { FlagsOf(flags) }
+}
+
+impl<F: Flags> Deref for FlagsOf<F> {
+    type Target = F;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<F: Flags> FlagsOf<F> {
+    pub fn into_inner(self) -> F { self.0 }
+}
+
+impl<F: Flags> Default for FlagsOf<F> {
+    fn default() -> Self { FlagsOf(F::empty()) }
+}
+
+impl<F: Flags> StrictType for FlagsOf<F>
+where F::Bits: StrictType
+{
+    const STRICT_LIB_NAME: &'static str = F::Bits::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { F::Bits::strict_name() }
+}
+impl<F: Flags> StrictEncode for FlagsOf<F>
+where F::Bits: StrictEncode
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.bits().strict_encode(writer)
+    }
+}
+impl<F: Flags> StrictDecode for FlagsOf<F>
+where F::Bits: StrictDecode + Into<u128>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let bits = F::Bits::strict_decode(reader)?;
+        let unknown = bits & !F::all().bits();
+        if unknown != F::Bits::EMPTY {
+            return Err(DecodeError::UnknownFlags {
+                type_name: type_name::<F>(),
+                unknown: unknown.into(),
+            });
+        }
+        Ok(FlagsOf(F::from_bits_retain(bits)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bitflags::bitflags;
+
+    use super::*;
+    use crate::test::{encode, encoding};
+    use crate::StrictReader;
+
+    bitflags! {
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        struct Access: u8 {
+            const READ = 0b0001;
+            const WRITE = 0b0010;
+            const EXEC = 0b0100;
+        }
+
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        struct Caps: u32 {
+            const NET = 1;
+            const DISK = 1 << 9;
+            const GPU = 1 << 30;
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        encoding(&FlagsOf::from(Access::empty()), [0]);
+        encoding(&FlagsOf::from(Access::READ | Access::EXEC), [0b101]);
+        encoding(&FlagsOf::from(Access::all()), [0b111]);
+        encoding(&FlagsOf::from(Caps::NET | Caps::DISK | Caps::GPU), [0x01, 0x02, 0x00, 0x40]);
+        encoding(&FlagsOf::<Caps>::default(), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn stable_bytes() {
+        // Flags are encoded as their bits, like integers
+        let flags = FlagsOf::from(Caps::DISK | Caps::GPU);
+        assert_eq!(encode(&flags), encode(&flags.bits()));
+        assert_eq!(encode(&flags), 0x4000_0200u32.to_le_bytes());
+    }
+
+    #[test]
+    fn unknown_bits() {
+        let mut reader = StrictReader::in_memory::<1>([0b1000_0011]);
+        assert_eq!(
+            FlagsOf::<Access>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::UnknownFlags {
+                type_name: s!("Access"),
+                unknown: 0x80,
+            }
+        );
+
+        let mut reader = StrictReader::in_memory::<4>([0x01, 0x00, 0x00, 0x80]);
+        let err = FlagsOf::<Caps>::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "flags `Caps` have bits 0x80000000 set, which don't belong to any of the declared \
+             flags."
+        );
+    }
+}
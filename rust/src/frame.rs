@@ -0,0 +1,419 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Framing of strict-encoded messages sent over a stream transport.
+//!
+//! Each message is preceded by its length in bytes, encoded as a little-endian
+//! integer of the same width as the length of a confined collection which may
+//! contain up to `MAX` elements: a frame limited to 64 kB has `u16` prefix,
+//! 16 MB - `u24` prefix, 4 GB - `u32` prefix.
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use amplify::IoError;
+
+#[cfg(not(feature = "std"))]
+use crate::io::Error as IoError;
+use crate::{
+    io, DeserializeError, SliceReader, StreamWriter, StrictDecode, StrictEncode, StrictReader,
+    StrictWriter, WriteLimitExceeded, PREALLOC_LIMIT,
+};
+
+/// Errors of sending and receiving strict-encoded frames.
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum FrameError {
+    #[display(inner)]
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// the stream is closed.
+    Closed,
+
+    /// the stream has ended in the middle of a frame, after {read} of
+    /// {expected} bytes.
+    Truncated { read: usize, expected: usize },
+
+    /// frame of {len} bytes exceeds the maximum frame size of {max} bytes.
+    Oversize { len: usize, max: usize },
+
+    /// invalid frame data. {0}
+    #[from]
+    #[from(crate::DecodeError)]
+    Deserialize(DeserializeError),
+}
+
+impl_error!(FrameError);
+
+/// Width of the frame length prefix in bytes.
+fn prefix_len(max: usize) -> usize {
+    match max as u64 {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        0x100_0000..=0xFFFF_FFFF => 4,
+        _ => 8,
+    }
+}
+
+/// Writes strict-encoded messages as frames into a stream.
+#[derive(Clone, Debug)]
+pub struct FrameWriter<W: io::Write> {
+    inner: W,
+    max: usize,
+}
+
+impl<W: io::Write> FrameWriter<W> {
+    /// Constructs the writer which frames messages of up to `MAX` bytes.
+    pub fn new<const MAX: usize>(inner: W) -> Self { Self { inner, max: MAX } }
+
+    /// Maximum size of a message in the frame.
+    pub fn max_frame_len(&self) -> usize { self.max }
+
+    pub fn get_ref(&self) -> &W { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut W { &mut self.inner }
+
+    pub fn into_inner(self) -> W { self.inner }
+
+    /// Encodes the message and writes it as a single frame, flushing the
+    /// underlying writer afterwards.
+    ///
+    /// Messages exceeding the maximum frame size are not written and are
+    /// reported with [`FrameError::Oversize`]. The encoding of such messages
+    /// is stopped once it exceeds the maximum frame size, thus the memory
+    /// used by the message data is bounded by the frame size.
+    pub fn send<T: StrictEncode>(&mut self, message: &T) -> Result<(), FrameError> {
+        let writer = StrictWriter::with(StreamWriter::with(self.max, vec![]));
+        let data = match message.strict_encode(writer) {
+            Ok(writer) => writer.unbox().unconfine(),
+            Err(err) if WriteLimitExceeded::from_io(&err).is_some() => {
+                // the size of the message is counted without storing its data
+                let counter = StrictWriter::counter::<{ usize::MAX }>();
+                return Err(FrameError::Oversize {
+                    len: message.strict_encode(counter)?.unbox().unconfine().count,
+                    max: self.max,
+                });
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let prefix = (data.len() as u64).to_le_bytes();
+        self.inner.write_all(&prefix[..prefix_len(self.max)])?;
+        self.inner.write_all(&data)?;
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads strict-encoded messages from the frames in a stream.
+#[derive(Clone, Debug)]
+pub struct FrameReader<R: io::Read> {
+    inner: R,
+    max: usize,
+}
+
+impl<R: io::Read> FrameReader<R> {
+    /// Constructs the reader which accepts frames of up to `MAX` bytes.
+    pub fn new<const MAX: usize>(inner: R) -> Self { Self { inner, max: MAX } }
+
+    /// Maximum size of a message in the frame.
+    pub fn max_frame_len(&self) -> usize { self.max }
+
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut R { &mut self.inner }
+
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Reads the next frame and decodes the message from it. The message must
+    /// occupy the whole frame.
+    ///
+    /// If the stream ends before the frame starts, the method returns
+    /// [`FrameError::Closed`]; when it ends in the middle of the frame -
+    /// [`FrameError::Truncated`]. Frames exceeding the maximum frame size are
+    /// rejected with [`FrameError::Oversize`] before their data are read.
+    pub fn recv<T: StrictDecode>(&mut self) -> Result<T, FrameError> {
+        let width = prefix_len(self.max);
+        let mut prefix = [0u8; 8];
+        match self.fill(&mut prefix[..width])? {
+            0 => return Err(FrameError::Closed),
+            read if read < width => {
+                return Err(FrameError::Truncated {
+                    read,
+                    expected: width,
+                })
+            }
+            _ => {}
+        }
+        let len = u64::from_le_bytes(prefix);
+        if len > self.max as u64 {
+            return Err(FrameError::Oversize {
+                len: len as usize,
+                max: self.max,
+            });
+        }
+        let len = len as usize;
+
+        // Since the frame data may not be present in the stream, they are read
+        // in chunks of at most `PREALLOC_LIMIT` bytes, and the buffer grows
+        // only when the previous chunk is read.
+        let mut data = Vec::with_capacity(len.min(PREALLOC_LIMIT));
+        while data.len() < len {
+            let start = data.len();
+            data.resize(start + (len - start).min(PREALLOC_LIMIT), 0);
+            let read = self.fill(&mut data[start..])?;
+            if start + read < data.len() {
+                return Err(FrameError::Truncated {
+                    read: width + start + read,
+                    expected: width + len,
+                });
+            }
+        }
+
+        let mut reader = StrictReader::with(SliceReader::with(len, &data));
+        let message = T::strict_decode(&mut reader)?;
+        let left = reader.remaining().len();
+        if left > 0 {
+            return Err(DeserializeError::DataNotEntirelyConsumed(left as u64).into());
+        }
+        Ok(message)
+    }
+
+    /// Reads into the buffer until it is full or the stream ends, returning
+    /// the number of the bytes read.
+    fn fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.inner.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(len) => read += len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::collections::VecDeque;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use amplify::confinement::{Confined, SmallVec, TinyString};
+
+    use super::*;
+    use crate::test::largest_allocation;
+    use crate::{DecodeError, StrictType};
+
+    /// One end of an in-memory duplex pipe, which returns at most three bytes
+    /// per read.
+    #[derive(Clone, Default)]
+    struct Pipe {
+        incoming: Rc<RefCell<VecDeque<u8>>>,
+        outgoing: Rc<RefCell<VecDeque<u8>>>,
+    }
+
+    impl Pipe {
+        fn duplex() -> (Pipe, Pipe) {
+            let a = Pipe::default();
+            let b = Pipe {
+                incoming: a.outgoing.clone(),
+                outgoing: a.incoming.clone(),
+            };
+            (a, b)
+        }
+
+        fn push(&self, data: &[u8]) { self.outgoing.borrow_mut().extend(data) }
+    }
+
+    impl io::Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut incoming = self.incoming.borrow_mut();
+            let len = buf.len().min(incoming.len()).min(3);
+            for (byte, src) in buf.iter_mut().zip(incoming.drain(..len)) {
+                *byte = src;
+            }
+            Ok(len)
+        }
+    }
+
+    impl io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.outgoing.borrow_mut().extend(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Message {
+        id: u16,
+        text: TinyString,
+    }
+
+    fn message(id: u16, text: &str) -> Message {
+        Message {
+            id,
+            text: TinyString::try_from(text.to_owned()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn duplex() {
+        let (a, b) = Pipe::duplex();
+        let mut a_writer = FrameWriter::new::<0xFFFFFF>(a.clone());
+        let mut a_reader = FrameReader::new::<0xFFFFFF>(a);
+        let mut b_writer = FrameWriter::new::<0xFFFFFF>(b.clone());
+        let mut b_reader = FrameReader::new::<0xFFFFFF>(b.clone());
+
+        a_writer.send(&message(1, "ping")).unwrap();
+        a_writer.send(&message(2, "")).unwrap();
+        assert_eq!(b.incoming.borrow().iter().take(10).copied().collect::<Vec<_>>(), [
+            7, 0, 0, 1, 0, 4, b'p', b'i', b'n', b'g'
+        ]);
+        assert_eq!(b_reader.recv::<Message>().unwrap(), message(1, "ping"));
+        assert_eq!(b_reader.recv::<Message>().unwrap(), message(2, ""));
+        assert_eq!(b_reader.recv::<Message>().unwrap_err(), FrameError::Closed);
+
+        b_writer.send(&message(3, "pong")).unwrap();
+        b_writer.send(&0xABCDu16).unwrap();
+        assert_eq!(a_reader.recv::<Message>().unwrap(), message(3, "pong"));
+        assert_eq!(a_reader.recv::<u16>().unwrap(), 0xABCD);
+    }
+
+    #[test]
+    fn prefix_width() {
+        let (a, b) = Pipe::duplex();
+        let mut writer = FrameWriter::new::<0xFFFF>(a.clone());
+        writer.send(&message(1, "")).unwrap();
+        assert_eq!(b.incoming.borrow().len(), 2 + 3);
+
+        let mut writer = FrameWriter::new::<{ u32::MAX as usize }>(a);
+        writer.send(&message(1, "")).unwrap();
+        assert_eq!(b.incoming.borrow().len(), 5 + 4 + 3);
+
+        let mut reader = FrameReader::new::<0xFFFF>(b.clone());
+        assert_eq!(reader.recv::<Message>().unwrap(), message(1, ""));
+        let mut reader = FrameReader::new::<{ u32::MAX as usize }>(b);
+        assert_eq!(reader.recv::<Message>().unwrap(), message(1, ""));
+    }
+
+    #[test]
+    fn oversize() {
+        let (a, b) = Pipe::duplex();
+        let list = SmallVec::try_from(vec![0u8; 300]).unwrap();
+        let mut writer = FrameWriter::new::<0xFF>(a.clone());
+        assert_eq!(writer.send(&list).unwrap_err(), FrameError::Oversize {
+            len: 302,
+            max: 0xFF
+        });
+        assert!(b.incoming.borrow().is_empty());
+
+        // oversize message is not buffered beyond the frame size
+        let blob = Confined::<Vec<u8>, 0, 0x10_0000>::try_from(vec![0u8; 0x10_0000]).unwrap();
+        let (err, size) = largest_allocation(|| writer.send(&blob).unwrap_err());
+        assert_eq!(err, FrameError::Oversize {
+            len: 0x10_0003,
+            max: 0xFF
+        });
+        assert!(size < 0x1000);
+
+        let mut writer = FrameWriter::new::<0xFFFF>(a);
+        writer.send(&list).unwrap();
+        let mut reader = FrameReader::new::<0x100>(b.clone());
+        assert_eq!(reader.recv::<SmallVec<u8>>().unwrap_err(), FrameError::Oversize {
+            len: 302,
+            max: 0x100
+        });
+        // frame data are not consumed
+        assert_eq!(b.incoming.borrow().len(), 302);
+    }
+
+    #[test]
+    fn truncated() {
+        let (a, b) = Pipe::duplex();
+        let mut reader = FrameReader::new::<0xFFFFFF>(b);
+
+        a.push(&[7, 0]);
+        assert_eq!(reader.recv::<Message>().unwrap_err(), FrameError::Truncated {
+            read: 2,
+            expected: 3
+        });
+
+        a.push(&[7, 0, 0, 1, 0, 4, b'p']);
+        let err = reader.recv::<Message>().unwrap_err();
+        assert_eq!(err, FrameError::Truncated {
+            read: 7,
+            expected: 10
+        });
+        assert_eq!(
+            err.to_string(),
+            "the stream has ended in the middle of a frame, after 7 of 10 bytes."
+        );
+        assert_eq!(reader.recv::<Message>().unwrap_err(), FrameError::Closed);
+    }
+
+    #[test]
+    fn huge_prefix() {
+        let (a, b) = Pipe::duplex();
+        let mut reader = FrameReader::new::<{ u32::MAX as usize }>(b);
+
+        // frame of 4 GB declared without the data: the buffer for them must
+        // not be allocated before the data arrive
+        a.push(&[0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3]);
+        let (err, size) = largest_allocation(|| reader.recv::<Message>().unwrap_err());
+        assert_eq!(err, FrameError::Truncated {
+            read: 7,
+            expected: 4 + u32::MAX as usize
+        });
+        assert!(size <= PREALLOC_LIMIT);
+    }
+
+    #[test]
+    fn poisoned() {
+        let (a, b) = Pipe::duplex();
+        let mut reader = FrameReader::new::<0xFFFFFF>(b);
+
+        // frame has two extra bytes after the message
+        a.push(&[5, 0, 0, 1, 0, 0, 0xDE, 0xAD]);
+        // frame is shorter than the message it contains
+        a.push(&[4, 0, 0, 1, 0, 4, b'p']);
+        a.push(&[3, 0, 0, 1, 0, 0]);
+
+        assert_eq!(
+            reader.recv::<Message>().unwrap_err(),
+            FrameError::Deserialize(DeserializeError::DataNotEntirelyConsumed(2))
+        );
+        let FrameError::Deserialize(DeserializeError::Decode(DecodeError::Io(err))) =
+            reader.recv::<Message>().unwrap_err()
+        else {
+            panic!("short frame must fail with I/O error");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        // the reader stays at the frame boundary
+        assert_eq!(reader.recv::<Message>().unwrap(), message(1, ""));
+    }
+}
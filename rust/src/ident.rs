@@ -19,15 +19,139 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::str::FromStr;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
 
 use amplify::Wrapper;
 
 use crate::stl::{AlphaCapsLodash, AlphaLodash, AlphaNumLodash, AlphaSmallLodash};
-use crate::{RString, STRICT_TYPES_LIB};
+use crate::{RString, RestrictedCharSet, STRICT_TYPES_LIB};
 
 pub const IDENT_MAX_LEN: usize = 100;
 
+/// Identifiers which are valid according to their character sets, but can't
+/// be used as names since they clash with the keywords of the Rust language
+/// (including the ones reserved for the future use), in which the code for the
+/// types is generated. The comparison is case-sensitive.
+pub const RESERVED_IDENTS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum InvalidIdent {
+    /// identifier must contain at least one character.
+    Empty,
+
+    /// identifier '{0}' is {1} characters long, exceeding the limit of
+    /// {IDENT_MAX_LEN} characters.
+    TooLong(String, usize),
+
+    /// identifier '{0}' must not start with character '{1}'.
+    DisallowedFirst(String, char),
+
+    /// identifier '{0}' contains invalid character '{1}' at position {2}.
+    InvalidChar(String, char, usize),
+}
+
+impl_error!(InvalidIdent);
+
+/// Checks whether `ident` is a valid identifier which starts with a character
+/// from `C1` set, followed by characters from `C` set, and is not longer than
+/// [`IDENT_MAX_LEN`]. These are the same rules as applied by [`FromStr`] of
+/// the identifier types, and thus by `tn!`, `fname!` and other macros used in
+/// the derived code, but the error tells the exact reason of the failure.
+///
+/// Positions in the error are positions of characters, not bytes.
+pub fn check_ident<C1: RestrictedCharSet, C: RestrictedCharSet>(
+    ident: &str,
+) -> Result<(), InvalidIdent> {
+    fn is_in<C: RestrictedCharSet>(ch: char) -> bool {
+        ch.is_ascii() && C::try_from(ch as u8).is_ok()
+    }
+
+    let mut chars = ident.chars();
+    let Some(first) = chars.next() else {
+        return Err(InvalidIdent::Empty);
+    };
+    if !is_in::<C1>(first) {
+        return Err(InvalidIdent::DisallowedFirst(ident.to_owned(), first));
+    }
+    if let Some((pos, ch)) = chars.enumerate().find(|(_, ch)| !is_in::<C>(*ch)) {
+        return Err(InvalidIdent::InvalidChar(ident.to_owned(), ch, pos + 1));
+    }
+    // all the characters are ASCII, so the byte length matches the number of
+    // characters
+    if ident.len() > IDENT_MAX_LEN {
+        return Err(InvalidIdent::TooLong(ident.to_owned(), ident.len()));
+    }
+    Ok(())
+}
+
+/// Splits identifier into words on underscores and case changes, such that
+/// `prevTxOut`, `PrevTxOut` and `prev_tx_out` all consist of `prev`, `tx` and
+/// `out` words, and `HTTPServer` consists of `HTTP` and `Server`.
+fn ident_words(ident: &str) -> Vec<&str> {
+    let bytes = ident.as_bytes();
+    let mut words = Vec::new();
+    let mut start = 0;
+    for pos in 0..bytes.len() {
+        let ch = bytes[pos];
+        if ch == b'_' {
+            if start < pos {
+                words.push(&ident[start..pos]);
+            }
+            start = pos + 1;
+            continue;
+        }
+        if pos == start || !ch.is_ascii_uppercase() {
+            continue;
+        }
+        let prev = bytes[pos - 1];
+        let next_small = bytes.get(pos + 1).is_some_and(u8::is_ascii_lowercase);
+        if !prev.is_ascii_uppercase() || next_small {
+            words.push(&ident[start..pos]);
+            start = pos;
+        }
+    }
+    if start < bytes.len() {
+        words.push(&ident[start..]);
+    }
+    words
+}
+
+/// Converts identifier into the upper camel case used for type names, like
+/// `PrevTxOut`.
+fn to_camel_case(ident: &str) -> String {
+    let mut s = String::with_capacity(ident.len());
+    for word in ident_words(ident) {
+        let (first, rest) = word.split_at(1);
+        s.push_str(&first.to_ascii_uppercase());
+        s.push_str(&rest.to_ascii_lowercase());
+    }
+    s
+}
+
+/// Converts identifier into the snake case used for Rust field names, like
+/// `prev_tx_out`.
+fn to_snake_case(ident: &str) -> String {
+    let words = ident_words(ident);
+    let mut s = String::with_capacity(ident.len() + words.len());
+    for word in words {
+        if !s.is_empty() {
+            s.push('_');
+        }
+        s.push_str(&word.to_ascii_lowercase());
+    }
+    s
+}
+
 #[macro_export]
 macro_rules! impl_ident_type {
     ($ty:ty) => {
@@ -72,6 +196,40 @@ macro_rules! impl_ident_type {
     };
 }
 
+macro_rules! impl_ident_check {
+    ($ty:ty, $first:ty) => {
+        impl $ty {
+            /// Checks whether the string is a valid name; see [`check_ident`].
+            #[inline]
+            pub fn check(s: &str) -> Result<(), InvalidIdent> {
+                check_ident::<$first, AlphaNumLodash>(s)
+            }
+
+            /// Constructs the name from a string, reporting the exact reason
+            /// if the string is not a valid name.
+            pub fn try_from_str(s: &str) -> Result<Self, InvalidIdent> {
+                Self::check(s)?;
+                Ok(Self(RString::try_from(s.as_bytes()).expect("checked identifier")))
+            }
+
+            /// Converts the name into upper camel case, like `PrevTxOut`.
+            ///
+            /// The result is a string since it may not be a valid name of the
+            /// same kind.
+            pub fn to_camel_case(&self) -> String { to_camel_case(self.as_str()) }
+
+            /// Converts the name into snake case, like `prev_tx_out`.
+            ///
+            /// The result is a string since it may not be a valid name of the
+            /// same kind.
+            pub fn to_snake_case(&self) -> String { to_snake_case(self.as_str()) }
+
+            /// Detects whether the name is one of [`RESERVED_IDENTS`].
+            pub fn is_reserved(&self) -> bool { RESERVED_IDENTS.contains(&self.as_str()) }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_ident_subtype {
     ($ty:ty) => {
@@ -103,6 +261,7 @@ macro_rules! impl_ident_subtype {
 pub struct Ident(RString<AlphaLodash, AlphaNumLodash, 1, IDENT_MAX_LEN>);
 
 impl_ident_type!(Ident);
+impl_ident_check!(Ident, AlphaLodash);
 impl_strict_newtype!(Ident, STRICT_TYPES_LIB);
 
 #[derive(Wrapper, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From)]
@@ -115,6 +274,7 @@ impl_strict_newtype!(Ident, STRICT_TYPES_LIB);
 pub struct TypeName(RString<AlphaCapsLodash, AlphaNumLodash, 1, IDENT_MAX_LEN>);
 
 impl_ident_type!(TypeName);
+impl_ident_check!(TypeName, AlphaCapsLodash);
 impl_ident_subtype!(TypeName);
 impl_strict_newtype!(TypeName, STRICT_TYPES_LIB);
 
@@ -128,6 +288,7 @@ impl_strict_newtype!(TypeName, STRICT_TYPES_LIB);
 pub struct FieldName(RString<AlphaSmallLodash, AlphaNumLodash, 1, IDENT_MAX_LEN>);
 
 impl_ident_type!(FieldName);
+impl_ident_check!(FieldName, AlphaSmallLodash);
 impl_ident_subtype!(FieldName);
 impl_strict_newtype!(FieldName, STRICT_TYPES_LIB);
 
@@ -141,6 +302,7 @@ impl_strict_newtype!(FieldName, STRICT_TYPES_LIB);
 pub struct VariantName(RString<AlphaSmallLodash, AlphaNumLodash, 1, IDENT_MAX_LEN>);
 
 impl_ident_type!(VariantName);
+impl_ident_check!(VariantName, AlphaSmallLodash);
 impl_ident_subtype!(VariantName);
 impl_strict_newtype!(VariantName, STRICT_TYPES_LIB);
 
@@ -154,5 +316,94 @@ impl_strict_newtype!(VariantName, STRICT_TYPES_LIB);
 pub struct LibName(RString<AlphaCapsLodash, AlphaNumLodash, 1, IDENT_MAX_LEN>);
 
 impl_ident_type!(LibName);
+impl_ident_check!(LibName, AlphaCapsLodash);
 impl_ident_subtype!(LibName);
 impl_strict_newtype!(LibName, STRICT_TYPES_LIB);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn invalid_ident() {
+        assert_eq!(TypeName::try_from_str("").unwrap_err(), InvalidIdent::Empty);
+        assert_eq!(
+            TypeName::try_from_str("txOut").unwrap_err(),
+            InvalidIdent::DisallowedFirst(s!("txOut"), 't')
+        );
+        assert_eq!(
+            FieldName::try_from_str("Ünicode").unwrap_err(),
+            InvalidIdent::DisallowedFirst(s!("Ünicode"), 'Ü')
+        );
+        assert_eq!(
+            FieldName::try_from_str("prev-out").unwrap_err(),
+            InvalidIdent::InvalidChar(s!("prev-out"), '-', 4)
+        );
+        assert_eq!(
+            LibName::try_from_str("Bäz8").unwrap_err(),
+            InvalidIdent::InvalidChar(s!("Bäz8"), 'ä', 1)
+        );
+        let long = "A".repeat(IDENT_MAX_LEN + 1);
+        assert_eq!(
+            TypeName::try_from_str(&long).unwrap_err(),
+            InvalidIdent::TooLong(long.clone(), IDENT_MAX_LEN + 1)
+        );
+        assert_eq!(
+            TypeName::try_from_str(&long).unwrap_err().to_string(),
+            format!(
+                "identifier '{long}' is 101 characters long, exceeding the limit of 100 \
+                 characters."
+            )
+        );
+        assert_eq!(
+            TypeName::try_from_str(&long[1..]).unwrap(),
+            TypeName::from_str(&long[1..]).unwrap()
+        );
+
+        assert_eq!(Ident::try_from_str("_tx").unwrap(), Ident::from("_tx"));
+        assert_eq!(VariantName::try_from_str("some_2").unwrap(), vname!("some_2"));
+        assert_eq!(TypeName::try_from_str("TxOut").unwrap(), tn!("TxOut"));
+        assert_eq!(FieldName::check("txOut"), Ok(()));
+    }
+
+    proptest! {
+        #[test]
+        fn check_matches_macros(s in "[aZ_09.-]{0,5}") {
+            // `tn!`, `fname!` and other macros construct names with `RString` conversions
+            prop_assert_eq!(TypeName::check(&s).is_ok(), TypeName::from_str(&s).is_ok());
+            prop_assert_eq!(FieldName::check(&s).is_ok(), FieldName::from_str(&s).is_ok());
+            prop_assert_eq!(VariantName::check(&s).is_ok(), VariantName::from_str(&s).is_ok());
+            prop_assert_eq!(LibName::check(&s).is_ok(), LibName::from_str(&s).is_ok());
+            prop_assert_eq!(Ident::check(&s).is_ok(), Ident::from_str(&s).is_ok());
+        }
+    }
+
+    #[test]
+    fn case() {
+        assert_eq!(fname!("prevTxOut").to_camel_case(), "PrevTxOut");
+        assert_eq!(fname!("prevTxOut").to_snake_case(), "prev_tx_out");
+        assert_eq!(fname!("prev_tx_out").to_camel_case(), "PrevTxOut");
+        assert_eq!(tn!("PrevTxOut").to_snake_case(), "prev_tx_out");
+        assert_eq!(tn!("HTTPServer").to_snake_case(), "http_server");
+        assert_eq!(tn!("HTTPServer").to_camel_case(), "HttpServer");
+        assert_eq!(tn!("Utf8String").to_snake_case(), "utf8_string");
+        assert_eq!(Ident::from("__private__name").to_camel_case(), "PrivateName");
+        assert_eq!(Ident::from("__private__name").to_snake_case(), "private_name");
+        assert_eq!(Ident::from("_").to_snake_case(), "");
+        assert_eq!(libname!("U8").to_snake_case(), "u8");
+
+        let name = fname!("prev_tx_out");
+        assert_eq!(TypeName::try_from_str(&name.to_camel_case()).unwrap(), tn!("PrevTxOut"));
+    }
+
+    #[test]
+    fn reserved() {
+        assert!(fname!("type").is_reserved());
+        assert!(tn!("Self").is_reserved());
+        assert!(!tn!("Type").is_reserved());
+        assert!(!fname!("types").is_reserved());
+        assert!(RESERVED_IDENTS.iter().all(|kw| Ident::check(kw).is_ok()));
+    }
+}
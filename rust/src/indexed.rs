@@ -0,0 +1,311 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`IndexMap`] and [`IndexSet`] collections, available
+//! with `indexmap` feature.
+//!
+//! Like all other collections, index maps and sets must be confined to be
+//! encoded; since [`Confined`](amplify::confinement::Confined) can't wrap them,
+//! [`ConfinedIndex`] is used instead.
+//!
+//! The encoding is canonical and byte-identical to the encoding of the
+//! `Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>` and
+//! `Confined<BTreeSet<T>, MIN_LEN, MAX_LEN>` with the same contents: the
+//! entries are sorted by their keys during the encoding, and must follow in
+//! strictly ascending order on the wire during the decoding. Thus, the
+//! insertion order is not preserved across a round-trip: collections with
+//! different insertion orders of the same entries have the same encoding, and
+//! the decoded collection always iterates in the ascending order of its keys.
+
+use core::iter;
+use core::ops::Deref;
+use std::hash::Hash;
+
+use amplify::confinement;
+use indexmap::{IndexMap, IndexSet};
+
+use crate::embedded::{alloc_items, decode_item, prealloc};
+use crate::layout::layout_dumb;
+use crate::reader::{map_name, set_name};
+use crate::{
+    io, ConfinedDumb, DecodeError, InvalidLength, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictType, TypeName, TypedRead, TypedWrite, WriteRaw, LIB_EMBEDDED,
+};
+
+pub type ConfinedIndexMap<K, V, const MIN_LEN: usize, const MAX_LEN: usize> =
+    ConfinedIndex<IndexMap<K, V>, MIN_LEN, MAX_LEN>;
+pub type ConfinedIndexSet<T, const MIN_LEN: usize, const MAX_LEN: usize> =
+    ConfinedIndex<IndexSet<T>, MIN_LEN, MAX_LEN>;
+
+/// [`IndexMap`] or [`IndexSet`] which number of items is confined to be
+/// between `MIN_LEN` and `MAX_LEN`.
+///
+/// The number of items is checked when the collection is constructed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConfinedIndex<C, const MIN_LEN: usize, const MAX_LEN: usize>(C);
+
+impl<C, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedIndex<C, MIN_LEN, MAX_LEN> {
+    pub fn as_inner(&self) -> &C { &self.0 }
+
+    pub fn release(self) -> C { self.0 }
+
+    fn with_len(col: C, len: usize) -> Result<Self, confinement::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(len)?;
+        Ok(ConfinedIndex(col))
+    }
+}
+
+impl<C, const MIN_LEN: usize, const MAX_LEN: usize> Deref for ConfinedIndex<C, MIN_LEN, MAX_LEN> {
+    type Target = C;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<C: Default, const MAX_LEN: usize> Default for ConfinedIndex<C, 0, MAX_LEN> {
+    fn default() -> Self { ConfinedIndex(C::default()) }
+}
+
+impl<K: Hash + Eq, V, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<IndexMap<K, V>>
+    for ConfinedIndexMap<K, V, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(map: IndexMap<K, V>) -> Result<Self, Self::Error> {
+        let len = map.len();
+        Self::with_len(map, len)
+    }
+}
+
+impl<T: Hash + Eq, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<IndexSet<T>>
+    for ConfinedIndexSet<T, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(set: IndexSet<T>) -> Result<Self, Self::Error> {
+        let len = set.len();
+        Self::with_len(set, len)
+    }
+}
+
+impl<T: StrictType + Ord + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedIndexSet<T, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { set_name::<T>(MIN_LEN, MAX_LEN) }
+}
+impl<T: StrictEncode + Ord + Hash + StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize>
+    StrictEncode for ConfinedIndexSet<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let mut items = self.iter().collect::<Vec<_>>();
+        items.sort_unstable();
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(items.len())?;
+        unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(items.len())?;
+        }
+        for item in items {
+            writer = item.strict_encode(writer)?;
+        }
+        Ok(unsafe {
+            writer.register_set(&layout_dumb::<T>(), Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
+        })
+    }
+}
+impl<T: StrictDecode + Ord + Hash, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for ConfinedIndexSet<T, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<T>(reader, len)?;
+        let mut items = IndexSet::<T>::with_capacity(prealloc::<T>(len));
+        for index in 0..len {
+            let item = decode_item(reader, index)?;
+            match items.last() {
+                Some(last) if last > &item => return Err(DecodeError::BrokenSetOrder),
+                Some(last) if last == &item => {
+                    return Err(DecodeError::repeated_value::<Self>(&item, index))
+                }
+                _ => items.insert(item),
+            };
+        }
+        Self::try_from(items).map_err(DecodeError::from)
+    }
+}
+
+impl<K: StrictType + Ord + Hash, V: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>
+    StrictType for ConfinedIndexMap<K, V, MIN_LEN, MAX_LEN>
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { map_name::<K, V>(MIN_LEN, MAX_LEN) }
+}
+impl<
+        K: StrictEncode + Ord + Hash + StrictDumb,
+        V: StrictEncode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictEncode for ConfinedIndexMap<K, V, MIN_LEN, MAX_LEN>
+{
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        let mut items = self.iter().collect::<Vec<_>>();
+        items.sort_unstable_by_key(|(k, _)| *k);
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(items.len())?;
+        unsafe {
+            writer.raw_writer().write_raw_len::<MAX_LEN>(items.len())?;
+        }
+        for (k, v) in items {
+            writer = k.strict_encode(writer)?;
+            writer = v.strict_encode(writer)?
+        }
+        Ok(unsafe {
+            writer.register_map(
+                &layout_dumb::<K>(),
+                &layout_dumb::<V>(),
+                Sizing::new(MIN_LEN as u64, MAX_LEN as u64),
+            )
+        })
+    }
+}
+impl<
+        K: StrictDecode + Ord + Hash + StrictDumb,
+        V: StrictDecode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    > StrictDecode for ConfinedIndexMap<K, V, MIN_LEN, MAX_LEN>
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<(K, V)>(reader, len)?;
+        let mut items = IndexMap::<K, V>::with_capacity(prealloc::<(K, V)>(len));
+        for index in 0..len {
+            let key = decode_item(reader, index)?;
+            let val = decode_item(reader, index)?;
+            match items.last() {
+                Some((last, _)) if last > &key => return Err(DecodeError::BrokenMapOrder),
+                Some((last, _)) if last == &key => {
+                    return Err(DecodeError::repeated_key::<Self>(&key, index))
+                }
+                _ => items.insert(key, val),
+            };
+        }
+        Self::try_from(items).map_err(DecodeError::from)
+    }
+}
+
+impl<T: StrictDumb + Eq + Hash, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for ConfinedIndexSet<T, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self {
+        Self::try_from(IndexSet::from_iter(iter::repeat_with(T::strict_dumb).take(MIN_LEN)))
+            .expect("dumb items of a set or a map can't fill more than a single item")
+    }
+}
+impl<K: StrictDumb + Eq + Hash, V: StrictDumb, const MIN_LEN: usize, const MAX_LEN: usize>
+    ConfinedDumb for ConfinedIndexMap<K, V, MIN_LEN, MAX_LEN>
+{
+    fn confined_dumb() -> Self {
+        let item = || (K::strict_dumb(), V::strict_dumb());
+        Self::try_from(IndexMap::from_iter(iter::repeat_with(item).take(MIN_LEN)))
+            .expect("dumb items of a set or a map can't fill more than a single item")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use amplify::confinement::{Confined, U8};
+
+    use super::*;
+    use crate::test::{encode, encoding_roundtrip};
+    use crate::StrictReader;
+
+    type Map = ConfinedIndexMap<u16, u8, 0, U8>;
+    type Set = ConfinedIndexSet<u16, 0, U8>;
+
+    const ENTRIES: [(u16, u8); 3] = [(0x300, 1), (0x100, 2), (0x200, 3)];
+
+    fn map(entries: impl IntoIterator<Item = (u16, u8)>) -> Map {
+        Map::try_from(IndexMap::from_iter(entries)).unwrap()
+    }
+
+    fn set(items: impl IntoIterator<Item = u16>) -> Set {
+        Set::try_from(IndexSet::from_iter(items)).unwrap()
+    }
+
+    #[test]
+    fn btree_identical() {
+        let tree = Confined::<BTreeMap<_, _>, 0, U8>::try_from(BTreeMap::from(ENTRIES)).unwrap();
+        assert_eq!(encode(&map(ENTRIES)), encode(&tree));
+
+        let keys = ENTRIES.map(|(k, _)| k);
+        let tree = Confined::<BTreeSet<_>, 0, U8>::try_from(BTreeSet::from(keys)).unwrap();
+        assert_eq!(encode(&set(keys)), encode(&tree));
+
+        assert_eq!(
+            encode(&Map::default()),
+            encode(&Confined::<BTreeMap<u16, u8>, 0, U8>::default())
+        );
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut reversed = ENTRIES;
+        reversed.reverse();
+        assert_eq!(encode(&map(ENTRIES)), encode(&map(reversed)));
+        assert_eq!(encode(&set(ENTRIES.map(|(k, _)| k))), encode(&set(reversed.map(|(k, _)| k))));
+    }
+
+    #[test]
+    fn roundtrip() {
+        encoding_roundtrip(&map(ENTRIES));
+        encoding_roundtrip(&set(ENTRIES.map(|(k, _)| k)));
+        encoding_roundtrip(&Map::default());
+
+        // The decoded collection iterates in the order of its keys
+        let data = encode(&map(ENTRIES));
+        let decoded = Map::strict_decode(&mut StrictReader::in_memory::<256>(data)).unwrap();
+        assert_eq!(decoded.keys().copied().collect::<Vec<_>>(), vec![0x100, 0x200, 0x300]);
+    }
+
+    #[test]
+    fn broken_order() {
+        let data = [2u8, 0x00, 0x02, 3, 0x00, 0x01, 2];
+        let mut reader = StrictReader::in_memory::<256>(data);
+        assert_eq!(Map::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenMapOrder);
+
+        let data = [2u8, 0x00, 0x01, 1, 0x00, 0x01, 2];
+        let mut reader = StrictReader::in_memory::<256>(data);
+        let err = Map::strict_decode(&mut reader).unwrap_err();
+        assert_eq!(err, DecodeError::repeated_key::<Map>(&0x100u16, 1));
+
+        let data = [2u8, 0x00, 0x02, 0x00, 0x01];
+        let mut reader = StrictReader::in_memory::<256>(data);
+        assert_eq!(Set::strict_decode(&mut reader).unwrap_err(), DecodeError::BrokenSetOrder);
+    }
+
+    #[test]
+    fn confinement() {
+        assert_eq!(
+            ConfinedIndexSet::<u8, 1, 2>::try_from(IndexSet::new()).unwrap_err(),
+            confinement::Error::Undersize { len: 0, min_len: 1 }
+        );
+        assert_eq!(ConfinedIndexSet::<u8, 1, 2>::confined_dumb().as_inner(), &IndexSet::from([0]));
+    }
+}
@@ -0,0 +1,98 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deduplication of the shared buffers decoded by [`StrictReader`]; see
+//! [`StrictReader::with_interner`].
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+#[cfg(doc)]
+use crate::StrictReader;
+use crate::{io, ReadRaw, PREALLOC_LIMIT};
+
+/// Table of the distinct contents of the shared buffers decoded so far.
+///
+/// The data are read into a scratch buffer reused by all reads and are copied
+/// into a new shared buffer only if the same contents were not seen before.
+/// Buffers longer than [`PREALLOC_LIMIT`] are not deduplicated.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Interner {
+    table: BTreeSet<Arc<[u8]>>,
+    capacity: usize,
+    scratch: Vec<u8>,
+}
+
+impl Interner {
+    /// Constructs the table which keeps up to `capacity` distinct contents;
+    /// once it is full, the data not seen before are still decoded, but are
+    /// not deduplicated with the subsequent ones.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Interner {
+            table: BTreeSet::new(),
+            capacity,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Returns a shared buffer with `data`, which is the buffer seen before if
+    /// there is one.
+    pub fn intern(&mut self, data: &[u8]) -> Arc<[u8]> {
+        if let Some(shared) = self.table.get(data) {
+            return shared.clone();
+        }
+        let shared = Arc::<[u8]>::from(data);
+        if self.table.len() < self.capacity {
+            self.table.insert(shared.clone());
+        }
+        shared
+    }
+
+    /// Reads `len` bytes into a shared buffer, which is the buffer seen before
+    /// if there is one with the same contents.
+    pub fn read<R: ReadRaw, const MAX_LEN: usize>(
+        &mut self,
+        reader: &mut R,
+        len: usize,
+    ) -> io::Result<Arc<[u8]>> {
+        if len > PREALLOC_LIMIT {
+            return reader.read_raw_shared::<MAX_LEN>(len);
+        }
+        let mut scratch = core::mem::take(&mut self.scratch);
+        scratch.clear();
+        let res = reader.read_raw_into::<MAX_LEN>(len, &mut scratch);
+        let shared = res.map(|_| self.intern(&scratch));
+        self.scratch = scratch;
+        shared
+    }
+
+    /// Reads an array of `LEN` bytes into a shared buffer, which is the buffer
+    /// seen before if there is one with the same contents.
+    pub fn read_array<R: ReadRaw, const LEN: usize>(
+        &mut self,
+        reader: &mut R,
+    ) -> io::Result<Arc<[u8; LEN]>> {
+        let data = reader.read_raw_array::<LEN>()?;
+        let shared = self.intern(&data);
+        Ok(Arc::try_from(shared).expect("interned buffer has the length of the array"))
+    }
+}
@@ -0,0 +1,231 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! I/O primitives used by strict encoding.
+//!
+//! With `std` feature these are re-exports from `std::io`. Without it, the
+//! module provides a minimal `no_std` replacement, covering just the part of
+//! `std::io` API which is required for the strict encoding: [`Read`] and
+//! [`Write`] traits, implemented for byte slices, vectors, [`Cursor`] and
+//! [`Sink`], and an [`Error`](struct@Error) carrying only its [`ErrorKind`].
+
+#[cfg(feature = "std")]
+pub use std::io::{sink, Cursor, Error, ErrorKind, Read, Result, Sink, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::*;
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::fmt::{self, Display, Formatter};
+
+    use crate::{BufferOverflow, InvalidLength, WriteLimitExceeded};
+
+    /// A list specifying general categories of I/O error, matching the
+    /// respective variants of `std::io::ErrorKind`.
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+    #[display(doc_comments)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        /// invalid input parameter
+        InvalidInput,
+        /// invalid data
+        InvalidData,
+        /// write zero
+        WriteZero,
+        /// out of memory
+        OutOfMemory,
+        /// unexpected end of file
+        UnexpectedEof,
+        /// unsupported operation
+        Unsupported,
+        /// operation interrupted
+        Interrupted,
+        /// other error
+        Other,
+    }
+
+    /// I/O error, which consists of an [`ErrorKind`] and may include details
+    /// of [`BufferOverflow`], [`InvalidLength`] or [`WriteLimitExceeded`].
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        pub(crate) overflow: Option<BufferOverflow>,
+        pub(crate) length: Option<Box<InvalidLength>>,
+        pub(crate) limit: Option<Box<WriteLimitExceeded>>,
+    }
+
+    impl Error {
+        pub fn kind(&self) -> ErrorKind { self.kind }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Error {
+                kind,
+                overflow: None,
+                length: None,
+                limit: None,
+            }
+        }
+    }
+
+    impl From<BufferOverflow> for Error {
+        fn from(overflow: BufferOverflow) -> Self {
+            Error {
+                kind: ErrorKind::WriteZero,
+                overflow: Some(overflow),
+                length: None,
+                limit: None,
+            }
+        }
+    }
+
+    impl From<InvalidLength> for Error {
+        fn from(length: InvalidLength) -> Self {
+            Error {
+                kind: ErrorKind::InvalidInput,
+                overflow: None,
+                length: Some(Box::new(length)),
+                limit: None,
+            }
+        }
+    }
+
+    impl From<WriteLimitExceeded> for Error {
+        fn from(limit: WriteLimitExceeded) -> Self {
+            Error {
+                kind: ErrorKind::InvalidInput,
+                overflow: None,
+                length: None,
+                limit: Some(Box::new(limit)),
+            }
+        }
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match (self.overflow, &self.length, &self.limit) {
+                (Some(overflow), ..) => Display::fmt(&overflow, f),
+                (None, Some(length), _) => Display::fmt(length, f),
+                (None, None, Some(limit)) => Display::fmt(limit, f),
+                (None, None, None) => Display::fmt(&self.kind, f),
+            }
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    pub trait Read {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf)? {
+                    0 => return Err(ErrorKind::UnexpectedEof.into()),
+                    n => buf = &mut buf[n..],
+                }
+            }
+            Ok(())
+        }
+    }
+
+    pub trait Write {
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        fn flush(&mut self) -> Result<()>;
+
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf)? {
+                    0 => return Err(ErrorKind::WriteZero.into()),
+                    n => buf = &buf[n..],
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> { (**self).read(buf) }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> { (**self).write(buf) }
+        fn flush(&mut self) -> Result<()> { (**self).flush() }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let len = buf.len().min(self.len());
+            let (data, rest) = self.split_at(len);
+            buf[..len].copy_from_slice(data);
+            *self = rest;
+            Ok(len)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    /// In-memory reader over a byte buffer, tracking the current position.
+    #[derive(Clone, Eq, PartialEq, Debug, Default)]
+    pub struct Cursor<T> {
+        inner: T,
+        pos: u64,
+    }
+
+    impl<T> Cursor<T> {
+        pub fn new(inner: T) -> Self { Cursor { inner, pos: 0 } }
+        pub fn into_inner(self) -> T { self.inner }
+        pub fn get_ref(&self) -> &T { &self.inner }
+        pub fn position(&self) -> u64 { self.pos }
+        pub fn set_position(&mut self, pos: u64) { self.pos = pos }
+    }
+
+    impl<T: AsRef<[u8]>> Read for Cursor<T> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let data = self.inner.as_ref();
+            let start = (self.pos as usize).min(data.len());
+            let len = (&data[start..]).read(buf)?;
+            self.pos += len as u64;
+            Ok(len)
+        }
+    }
+
+    /// Writer which discards all the data.
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+    pub struct Sink;
+
+    pub fn sink() -> Sink { Sink }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> { Ok(buf.len()) }
+        fn flush(&mut self) -> Result<()> { Ok(()) }
+    }
+}
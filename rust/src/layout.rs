@@ -0,0 +1,490 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Discovery of type layouts from the strict encoding of dumb values, which
+//! is used to compute type properties like semantic ids and encoded sizes.
+
+use alloc::vec::Vec;
+
+use amplify::confinement::{Collection, Confined};
+
+use crate::{
+    io, DefineStruct, DefineTuple, DefineUnion, FieldName, Primitive, RawEncoding, Sizing,
+    StrictDumb, StrictEncode, StrictEnum, StrictMaybeDumb, StrictStruct, StrictSum, StrictTuple,
+    StrictType, StrictUnion, TypeDocs, TypeName, TypedParent, TypedWrite, VariantName, WriteRaw,
+    WriteStruct, WriteTuple, WriteUnion,
+};
+
+/// Fields of a tuple or a struct in the order of serialization; tuple fields
+/// have no names.
+pub(crate) type Fields<N> = Vec<(Option<FieldName>, N)>;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct TypeHeader {
+    pub lib: &'static str,
+    pub name: Option<TypeName>,
+    pub docs: TypeDocs,
+}
+
+impl TypeHeader {
+    fn of<T: StrictType>() -> Self {
+        TypeHeader {
+            lib: T::STRICT_LIB_NAME,
+            name: T::strict_name(),
+            docs: T::strict_docs(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum VariantKind {
+    Unit,
+    Tuple,
+    Struct,
+}
+
+#[cfg_attr(not(feature = "sha2"), allow(dead_code))]
+pub(crate) struct LayoutVariant<N> {
+    pub name: VariantName,
+    pub tag: u8,
+    pub kind: VariantKind,
+    pub fields: Fields<N>,
+}
+
+/// Property of a type computed from its layout nodes.
+pub(crate) trait LayoutNode: Clone {
+    fn primitive(prim: Primitive) -> Self;
+    fn unicode(sizing: Sizing) -> Self;
+    fn rstring(first: Self, rest: Self, sizing: Sizing) -> Self;
+    fn array(item: Self, len: u16) -> Self;
+    fn list(item: Self, sizing: Sizing) -> Self;
+    fn set(item: Self, sizing: Sizing) -> Self;
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self;
+    /// Values which encode several inner values without a type wrapping them.
+    fn sequence(items: Vec<Self>) -> Self;
+    fn enumeration(ty: &TypeHeader, variants: &'static [(u8, &'static str)]) -> Self;
+    fn union(ty: &TypeHeader, variants: Vec<LayoutVariant<Self>>) -> Self;
+    fn tuple(ty: &TypeHeader, fields: Fields<Self>) -> Self;
+    fn structure(ty: &TypeHeader, fields: Fields<Self>) -> Self;
+    /// Named type nested in itself.
+    fn recursive(ty: &TypeHeader) -> Self;
+    /// Type which data are written raw in an encoding external to the strict
+    /// type system.
+    fn raw(ty: &TypeHeader, encoding: RawEncoding) -> Self;
+}
+
+/// Computes the layout property of the type of the value.
+pub(crate) fn layout_of<N: LayoutNode>(value: &impl StrictEncode) -> N {
+    LayoutWriter::with_stack(vec![]).layout(value)
+}
+
+/// Value registering the layout of type `T`: either the dumb value of the
+/// type, or a union without variants for the uninhabited types, which have no
+/// dumb value.
+pub(crate) enum LayoutDumb<T> {
+    Dumb(T),
+    Uninhabited,
+}
+
+/// Returns the value registering the layout of type `T`; see [`LayoutDumb`].
+pub(crate) fn layout_dumb<T: StrictMaybeDumb>() -> LayoutDumb<T> {
+    T::try_strict_dumb().map_or(LayoutDumb::Uninhabited, LayoutDumb::Dumb)
+}
+
+impl<T> StrictDumb for LayoutDumb<T> {
+    fn strict_dumb() -> Self { LayoutDumb::Uninhabited }
+}
+impl<T: StrictType> StrictType for LayoutDumb<T> {
+    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+    fn strict_docs() -> TypeDocs { T::strict_docs() }
+}
+impl<T: StrictType> StrictSum for LayoutDumb<T> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = &[];
+    fn variant_name(&self) -> &'static str { unreachable!("uninhabited type has no variants") }
+}
+impl<T: StrictType> StrictUnion for LayoutDumb<T> {}
+impl<T: StrictEncode> StrictEncode for LayoutDumb<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        match self {
+            LayoutDumb::Dumb(dumb) => dumb.strict_encode(writer),
+            LayoutDumb::Uninhabited => writer.write_union::<Self>(|u| Ok(u.complete().complete())),
+        }
+    }
+}
+
+#[derive(Default)]
+struct LayoutSink;
+
+impl WriteRaw for LayoutSink {
+    fn write_raw<const MAX_LEN: usize>(&mut self, _bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer discovering the layout of a single value. Each inner value is
+/// processed by a separate writer, so all nodes collected by a writer belong
+/// to the value it processes.
+struct LayoutWriter<N: LayoutNode> {
+    nodes: Vec<N>,
+    fields: Fields<N>,
+    /// Named types which contain the value.
+    stack: Vec<TypeHeader>,
+    sink: LayoutSink,
+}
+
+impl<N: LayoutNode> TypedParent for LayoutWriter<N> {}
+
+impl<N: LayoutNode> LayoutWriter<N> {
+    fn with_stack(stack: Vec<TypeHeader>) -> Self {
+        LayoutWriter {
+            nodes: vec![],
+            fields: vec![],
+            stack,
+            sink: LayoutSink,
+        }
+    }
+
+    fn layout(&self, value: &impl StrictEncode) -> N {
+        let mut writer = value
+            .strict_encode(LayoutWriter::with_stack(self.stack.clone()))
+            .expect("layout writer doesn't fail");
+        match writer.nodes.len() {
+            1 => writer.nodes.remove(0),
+            _ => N::sequence(writer.nodes),
+        }
+    }
+
+    /// Returns writer for the fields of a type, or the type header if the type
+    /// is already being processed.
+    fn enter<T: StrictType>(&self) -> Result<Self, TypeHeader> {
+        let ty = TypeHeader::of::<T>();
+        if ty.name.is_some() && self.stack.contains(&ty) {
+            return Err(ty);
+        }
+        let mut stack = self.stack.clone();
+        stack.push(ty);
+        Ok(LayoutWriter::with_stack(stack))
+    }
+
+    // Collections write their items before registering themselves; the nodes
+    // of the items are replaced with the node of the collection.
+    fn register(mut self, node: N) -> Self {
+        self.nodes.clear();
+        self.nodes.push(node);
+        self
+    }
+
+    fn push(mut self, node: N) -> Self {
+        self.nodes.push(node);
+        self
+    }
+}
+
+impl<N: LayoutNode> TypedWrite for LayoutWriter<N> {
+    type TupleWriter = LayoutFields<N, Self>;
+    type StructWriter = LayoutFields<N, Self>;
+    type UnionDefiner = LayoutUnion<N>;
+    type RawWriter = LayoutSink;
+
+    unsafe fn raw_writer(&mut self) -> &mut Self::RawWriter { &mut self.sink }
+
+    fn write_union<T: StrictUnion>(
+        self,
+        inner: impl FnOnce(Self::UnionDefiner) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        match self.enter::<T>() {
+            Ok(writer) => inner(LayoutUnion::new::<T>(self, writer.stack)),
+            Err(ty) => Ok(self.push(N::recursive(&ty))),
+        }
+    }
+
+    fn write_enum<T: StrictEnum>(self, _value: T) -> io::Result<Self>
+    where u8: From<T> {
+        Ok(self.push(N::enumeration(&TypeHeader::of::<T>(), T::ALL_VARIANTS)))
+    }
+
+    fn write_tuple<T: StrictTuple>(
+        self,
+        inner: impl FnOnce(Self::TupleWriter) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        match self.enter::<T>() {
+            Ok(writer) => {
+                let fields = inner(LayoutFields::new(writer))?.fields;
+                Ok(self.push(N::tuple(&TypeHeader::of::<T>(), fields)))
+            }
+            Err(ty) => Ok(self.push(N::recursive(&ty))),
+        }
+    }
+
+    fn write_struct<T: StrictStruct>(
+        self,
+        inner: impl FnOnce(Self::StructWriter) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        match self.enter::<T>() {
+            Ok(writer) => {
+                let fields = inner(LayoutFields::new(writer))?.fields;
+                Ok(self.push(N::structure(&TypeHeader::of::<T>(), fields)))
+            }
+            Err(ty) => Ok(self.push(N::recursive(&ty))),
+        }
+    }
+
+    unsafe fn register_primitive(self, prim: Primitive) -> Self {
+        self.register(N::primitive(prim))
+    }
+
+    unsafe fn register_array(self, ty: &impl StrictEncode, len: u16) -> Self {
+        let item = self.layout(ty);
+        self.register(N::array(item, len))
+    }
+
+    unsafe fn register_unicode(self, sizing: Sizing) -> Self { self.register(N::unicode(sizing)) }
+
+    unsafe fn register_rstring(
+        self,
+        c: &impl StrictEncode,
+        c1: &impl StrictEncode,
+        sizing: Sizing,
+    ) -> Self {
+        let (first, rest) = (self.layout(c), self.layout(c1));
+        self.register(N::rstring(first, rest, sizing))
+    }
+
+    unsafe fn register_list(self, ty: &impl StrictEncode, sizing: Sizing) -> Self {
+        let item = self.layout(ty);
+        self.register(N::list(item, sizing))
+    }
+
+    unsafe fn register_set(self, ty: &impl StrictEncode, sizing: Sizing) -> Self {
+        let item = self.layout(ty);
+        self.register(N::set(item, sizing))
+    }
+
+    unsafe fn register_map(
+        self,
+        key: &impl StrictEncode,
+        ty: &impl StrictEncode,
+        sizing: Sizing,
+    ) -> Self {
+        let (key, value) = (self.layout(key), self.layout(ty));
+        self.register(N::map(key, value, sizing))
+    }
+
+    unsafe fn register_raw<T: StrictType>(self, encoding: RawEncoding) -> Self {
+        self.register(N::raw(&TypeHeader::of::<T>(), encoding))
+    }
+
+    unsafe fn write_string<const MAX_LEN: usize>(
+        self,
+        _bytes: impl AsRef<[u8]>,
+    ) -> io::Result<Self> {
+        Ok(self)
+    }
+
+    unsafe fn write_items<T: StrictEncode>(self, _items: &[T]) -> io::Result<Self> { Ok(self) }
+
+    unsafe fn write_collection<C: Collection, const MIN_LEN: usize, const MAX_LEN: usize>(
+        self,
+        _col: &Confined<C, MIN_LEN, MAX_LEN>,
+    ) -> io::Result<Self>
+    where
+        for<'a> &'a C: IntoIterator,
+        for<'a> <&'a C as IntoIterator>::Item: StrictEncode,
+    {
+        Ok(self)
+    }
+}
+
+trait LayoutParent<N: LayoutNode>: TypedParent {
+    fn stack(&self) -> &[TypeHeader];
+    fn set_fields(&mut self, fields: Fields<N>);
+
+    fn layout(&self, value: &impl StrictEncode) -> N {
+        LayoutWriter::<N>::with_stack(self.stack().to_vec()).layout(value)
+    }
+}
+
+impl<N: LayoutNode> LayoutParent<N> for LayoutWriter<N> {
+    fn stack(&self) -> &[TypeHeader] { &self.stack }
+    fn set_fields(&mut self, fields: Fields<N>) { self.fields = fields; }
+}
+
+impl<N: LayoutNode> LayoutParent<N> for LayoutUnion<N> {
+    fn stack(&self) -> &[TypeHeader] { &self.stack }
+    fn set_fields(&mut self, fields: Fields<N>) { self.fields = fields; }
+}
+
+struct LayoutFields<N: LayoutNode, P: LayoutParent<N>> {
+    parent: P,
+    fields: Fields<N>,
+}
+
+impl<N: LayoutNode, P: LayoutParent<N>> LayoutFields<N, P> {
+    fn new(parent: P) -> Self {
+        LayoutFields {
+            parent,
+            fields: vec![],
+        }
+    }
+
+    fn push(mut self, name: Option<FieldName>, value: &impl StrictEncode) -> Self {
+        let node = self.parent.layout(value);
+        self.fields.push((name, node));
+        self
+    }
+
+    fn into_parent(mut self) -> P {
+        self.parent.set_fields(self.fields);
+        self.parent
+    }
+}
+
+impl<N: LayoutNode, P: LayoutParent<N>> DefineTuple for LayoutFields<N, P> {
+    type Parent = P;
+    fn define_field<T: StrictEncode + StrictMaybeDumb>(self) -> Self {
+        self.push(None, &layout_dumb::<T>())
+    }
+    fn complete(self) -> P { self.into_parent() }
+}
+
+impl<N: LayoutNode, P: LayoutParent<N>> WriteTuple for LayoutFields<N, P> {
+    type Parent = P;
+    fn write_field(self, value: &impl StrictEncode) -> io::Result<Self> {
+        Ok(self.push(None, value))
+    }
+    fn complete(self) -> P { self.into_parent() }
+}
+
+impl<N: LayoutNode, P: LayoutParent<N>> DefineStruct for LayoutFields<N, P> {
+    type Parent = P;
+    fn define_field<T: StrictEncode + StrictMaybeDumb>(self, name: FieldName) -> Self {
+        self.push(Some(name), &layout_dumb::<T>())
+    }
+    fn complete(self) -> P { self.into_parent() }
+}
+
+impl<N: LayoutNode, P: LayoutParent<N>> WriteStruct for LayoutFields<N, P> {
+    type Parent = P;
+    fn write_field(self, name: FieldName, value: &impl StrictEncode) -> io::Result<Self> {
+        Ok(self.push(Some(name), value))
+    }
+    fn complete(self) -> P { self.into_parent() }
+}
+
+/// Collects the layout of the union variants during the definition; the
+/// written variant doesn't affect the layout and is ignored.
+struct LayoutUnion<N: LayoutNode> {
+    parent: LayoutWriter<N>,
+    ty: TypeHeader,
+    stack: Vec<TypeHeader>,
+    all_variants: &'static [(u8, &'static str)],
+    variants: Vec<LayoutVariant<N>>,
+    fields: Fields<N>,
+}
+
+impl<N: LayoutNode> TypedParent for LayoutUnion<N> {}
+
+impl<N: LayoutNode> LayoutUnion<N> {
+    fn new<T: StrictSum>(parent: LayoutWriter<N>, stack: Vec<TypeHeader>) -> Self {
+        LayoutUnion {
+            parent,
+            ty: TypeHeader::of::<T>(),
+            stack,
+            all_variants: T::ALL_VARIANTS,
+            variants: vec![],
+            fields: vec![],
+        }
+    }
+
+    fn define_variant(mut self, name: VariantName, kind: VariantKind) -> Self {
+        let tag = self
+            .all_variants
+            .iter()
+            .find(|(_, n)| *n == name.as_str())
+            .map(|(tag, _)| *tag)
+            .expect("union variant is not listed in ALL_VARIANTS");
+        let fields = core::mem::take(&mut self.fields);
+        self.variants.push(LayoutVariant {
+            name,
+            tag,
+            kind,
+            fields,
+        });
+        self
+    }
+}
+
+impl<N: LayoutNode> DefineUnion for LayoutUnion<N> {
+    type Parent = LayoutWriter<N>;
+    type TupleDefiner = LayoutFields<N, Self>;
+    type StructDefiner = LayoutFields<N, Self>;
+    type UnionWriter = Self;
+
+    fn define_unit(self, name: VariantName) -> Self { self.define_variant(name, VariantKind::Unit) }
+
+    fn define_tuple(
+        self,
+        name: VariantName,
+        inner: impl FnOnce(Self::TupleDefiner) -> Self,
+    ) -> Self {
+        inner(LayoutFields::new(self)).define_variant(name, VariantKind::Tuple)
+    }
+
+    fn define_struct(
+        self,
+        name: VariantName,
+        inner: impl FnOnce(Self::StructDefiner) -> Self,
+    ) -> Self {
+        inner(LayoutFields::new(self)).define_variant(name, VariantKind::Struct)
+    }
+
+    fn complete(self) -> Self::UnionWriter { self }
+}
+
+impl<N: LayoutNode> WriteUnion for LayoutUnion<N> {
+    type Parent = LayoutWriter<N>;
+    type TupleWriter = LayoutFields<N, Self>;
+    type StructWriter = LayoutFields<N, Self>;
+
+    fn write_unit(self, _name: VariantName) -> io::Result<Self> { Ok(self) }
+
+    fn write_tuple(
+        self,
+        _name: VariantName,
+        inner: impl FnOnce(Self::TupleWriter) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        inner(LayoutFields::new(self))
+    }
+
+    fn write_struct(
+        self,
+        _name: VariantName,
+        inner: impl FnOnce(Self::StructWriter) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        inner(LayoutFields::new(self))
+    }
+
+    fn complete(self) -> Self::Parent {
+        let node = N::union(&self.ty, self.variants);
+        self.parent.push(node)
+    }
+}
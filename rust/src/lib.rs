@@ -29,9 +29,15 @@
     //missing_docs
 )]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("either `std` or `alloc` feature must be enabled");
 
 #[cfg(feature = "derive")]
-pub use derive::{StrictDecode, StrictDumb, StrictEncode, StrictType};
+pub use derive::{
+    StrictDecode, StrictDecodeBorrowed, StrictDecodeInto, StrictDumb, StrictEncode, StrictType,
+};
 #[cfg(not(feature = "derive"))]
 use derive::{StrictDecode, StrictDumb, StrictEncode, StrictType};
 #[cfg(feature = "derive")]
@@ -39,6 +45,8 @@ pub use strict_encoding_derive as derive;
 #[cfg(not(feature = "derive"))]
 use strict_encoding_derive as derive;
 
+#[macro_use]
+extern crate alloc;
 #[macro_use]
 extern crate amplify;
 #[cfg(feature = "serde")]
@@ -47,6 +55,7 @@ extern crate serde_crate as serde;
 
 #[macro_use]
 mod macros;
+pub mod io;
 mod types;
 mod traits;
 #[macro_use]
@@ -57,23 +66,193 @@ mod writer;
 mod util;
 mod primitives;
 mod embedded;
+mod borrowed;
+mod decode_into;
+#[cfg(feature = "bytes")]
+mod bytes;
+mod byte_array;
+mod bitset;
+mod bounded;
+mod big_endian;
+mod char_string;
+mod enum_map;
+mod checked;
+mod collections;
+mod layout;
+mod notation;
+mod banner;
+mod envelope;
+mod erased;
+#[cfg(feature = "armor")]
+mod armor;
+mod frame;
+#[cfg(feature = "std")]
+mod net;
+#[cfg(feature = "std")]
+mod path;
+mod time;
+mod cmp;
+mod range;
+mod scoped;
+mod intern;
+mod shared;
+mod size;
+mod skip;
+mod type_info;
+mod stream;
+mod varint;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "std")]
+mod observer;
+mod sem_id;
+#[cfg(feature = "sha2")]
+mod typed_blob;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod calendar;
+#[cfg(feature = "uuid")]
+mod uuid;
+#[cfg(feature = "url")]
+mod url;
+#[cfg(feature = "semver")]
+mod semver;
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "either")]
+mod either;
+#[cfg(feature = "indexmap")]
+mod indexed;
+#[cfg(any(feature = "smallvec", feature = "arrayvec"))]
+mod stackvec;
+#[cfg(feature = "bitflags")]
+mod flags;
+#[cfg(feature = "async")]
+mod async_io;
+mod type_lib;
 pub mod stl;
+pub mod text;
+pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "serde")]
+pub mod serde_strict;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(any(test, feature = "test_helpers"))]
+pub mod test_helpers;
 #[cfg(test)]
 pub(crate) mod test;
 
-pub use embedded::{Byte, DecodeRawLe};
-pub use error::{DecodeError, DeserializeError, SerializeError};
-pub use ident::{FieldName, Ident, LibName, TypeName, VariantName, IDENT_MAX_LEN};
-pub use primitives::{NumCls, NumInfo, NumSize, Primitive};
-pub use reader::{ConfinedReader, StreamReader, StrictReader};
+#[cfg(feature = "arbitrary")]
+pub use arbitrary::StrictArbitrary;
+#[cfg(feature = "armor")]
+pub use armor::{Armor, ArmorError, StrictArmor, ARMOR_LINE_WIDTH};
+#[cfg(feature = "async")]
+pub use async_io::{AsyncStrictReader, AsyncStrictWriter, StrictDecodeAsync, StrictEncodeAsync};
+pub use banner::{check_compatibility, EncodingBanner, IncompatibilityReport, VersionMismatch};
+pub use big_endian::{BigEndian, BigEndianInt};
+pub use bitset::{ConfinedBitset, LargeBitset, MediumBitset, SmallBitset, TinyBitset};
+pub use borrowed::{
+    BlobRef, ConfinedRef, LargeBlobRef, MediumBlobRef, SmallBlobRef, StrictDecodeBorrowed,
+    TinyBlobRef,
+};
+pub use bounded::{Bounded, BoundedInt};
+pub use byte_array::{ByteArray, HexError};
+#[cfg(feature = "bytes")]
+pub use bytes::{
+    BytesReader, LargeSharedBlob, MediumSharedBlob, SharedBlob, SmallSharedBlob, TinySharedBlob,
+};
+pub use char_string::ConfinedCharString;
+#[cfg(feature = "sha2")]
+pub use checked::Sha256;
+pub use checked::{Checked, Checksum, Crc32};
+pub use cmp::canonical_cmp;
+pub use collections::{ConfinedBinaryHeap, ConfinedLinkedList};
+pub use decode_into::StrictDecodeInto;
+pub use embedded::{Byte, DecodeRawLe, Void, PREALLOC_LIMIT};
+pub use enum_map::EnumMap;
+pub use envelope::{Envelope, EnvelopeHeader, StrictContainer};
+pub use erased::{encode_dyn, StrictEncodeErased};
+pub use error::{
+    BufferOverflow, CharsetError, ContextError, DecodeError, DecodeErrorKind, DeserializeError,
+    HexDeserializeError, InvalidCharCount, InvalidLength, KnownVariants, LimitViolation,
+    OutOfBounds, PathSegment, RepeatedItem, SerializeError, WriteLimitExceeded,
+};
+#[cfg(feature = "bitflags")]
+pub use flags::FlagsOf;
+pub use frame::{FrameError, FrameReader, FrameWriter};
+pub use ident::{
+    check_ident, FieldName, Ident, InvalidIdent, LibName, TypeName, VariantName, IDENT_MAX_LEN,
+    RESERVED_IDENTS,
+};
+#[cfg(feature = "indexmap")]
+pub use indexed::{ConfinedIndex, ConfinedIndexMap, ConfinedIndexSet};
+#[cfg(feature = "std")]
+pub use observer::{CodecObserver, Stats, StatsObserver};
+#[cfg(feature = "std")]
+pub use path::{NonUtf8Path, PATH_MAX_LEN};
+pub use primitives::{i24, i40, i48, i56, IntOverflow, NumCls, NumInfo, NumSize, Primitive};
+#[cfg(feature = "std")]
+pub use reader::BufStreamReader;
+pub use reader::{
+    ChainReader, ConfinedReader, SliceReader, StreamReader, StrictReader, Utf8Policy,
+    RECURSION_DEPTH_LIMIT,
+};
+pub use scoped::{ScopedStruct, ScopedTuple, ScopedWriter};
+#[cfg(feature = "zeroize")]
+pub use secret::{Secret, ZeroizingBlob};
+#[cfg(feature = "sha2")]
+pub use sem_id::StrictSemId;
+pub use sem_id::{SemId, SEM_ID_TAG};
+#[cfg(feature = "semver")]
+pub use semver::{SEMVER_META_MAX_LEN, SEMVER_REQ_MAX_LEN};
+#[cfg(feature = "serde")]
+pub use serde_strict::AsStrict;
+pub use shared::{
+    ArcArray, ArcBlob, ArcString, LargeArcBlob, LargeArcString, MediumArcBlob, MediumArcString,
+    SmallArcBlob, SmallArcString, TinyArcBlob, TinyArcString,
+};
+pub use size::StrictSize;
+pub use skip::StrictSkip;
+#[cfg(feature = "smallvec")]
+pub use stackvec::ConfinedSmallVec;
 pub use stl::{Bool, InvalidRString, RString, RestrictedCharSet, U1, U2, U3, U4, U5, U6, U7};
+pub use stream::{StreamDecoder, StreamEncoder};
+#[cfg(all(feature = "trace", feature = "std"))]
+pub use trace::hex_trace_to_stderr;
+#[cfg(feature = "trace")]
+pub use trace::{hex_trace, TraceEvent, TraceWriter};
 pub use traits::*;
+pub use type_info::StrictTypeInfo;
+pub use type_lib::{TypeLib, TypeLibError};
+#[cfg(feature = "sha2")]
+pub use typed_blob::{peek_type_id, TypedBlob};
 pub use types::*;
-pub use util::{Sizing, Variant};
+#[cfg(feature = "url")]
+pub use url::URL_MAX_LEN;
+pub use util::{RawEncoding, Sizing, Variant};
+pub use varint::{CompactSizeU64, VarIntU64};
+#[cfg(feature = "digest")]
+pub use writer::HashingSink;
+#[cfg(not(feature = "std"))]
+pub use writer::WriteCounter;
+#[cfg(feature = "zeroize")]
+pub use writer::ZeroizingWriter;
 pub use writer::{
-    SplitParent, StreamWriter, StrictParent, StrictWriter, StructWriter, UnionWriter,
+    BufferedWriter, DeclaredField, FieldsWriter, FlattenedFields, LimitedWriter, ScopeKind,
+    SliceWriter, SplitParent, StreamWriter, StrictParent, StrictWriter, StructWriter, UnionWriter,
 };
 
+// Not a public API: the items are used by the exported macros and the derived
+// code, which can't rely on the `alloc` crate being in scope.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::String;
+
+    pub use crate::decode_into::{DecodeFieldInto, DecodeFieldReplacing, FieldInto};
+    pub use crate::types::sanity::{has_unique_fields, has_unique_tags, has_unique_variant_names};
+}
+
 #[deprecated(since = "2.2.0", note = "use LIB_EMBEDDED")]
 pub const NO_LIB: &str = LIB_EMBEDDED;
 #[deprecated(since = "2.2.0", note = "use LIB_NAME_STD")]
@@ -81,3 +260,12 @@ pub const STD_LIB: &str = "StdLib";
 pub const LIB_EMBEDDED: &str = "_";
 pub const LIB_NAME_STD: &str = "Std";
 pub const STRICT_TYPES_LIB: &str = "StrictTypes";
+
+/// Limit on the size of the strict-encoded data used by default by the text
+/// representations of the values in [`text`] module and by the serde adapter.
+pub const DEFAULT_MAX_LEN: usize = u16::MAX as usize;
+
+/// Version of the byte-level rules of the strict encoding, exchanged by the
+/// peers in [`EncodingBanner`]. It is bumped only when the encoding of some
+/// values changes, independently of the crate version.
+pub const STRICT_ENCODING_VERSION: u16 = 1;
@@ -19,6 +19,84 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// Replacement for `amplify::Error` derive macro, which always implements
+// `std::error::Error` and thus can't be used without `std`.
+macro_rules! impl_error {
+    ($ty:ty) => {
+        #[cfg(feature = "std")]
+        impl std::error::Error for $ty {}
+
+        impl From<$ty> for alloc::string::String {
+            fn from(err: $ty) -> Self { alloc::string::ToString::to_string(&err) }
+        }
+    };
+}
+
+/// Checks at compile time that a strict struct or sum type defines fields or
+/// variants and that their names and tags are unique, failing the build
+/// otherwise. Tuples may have no fields, being unit types.
+///
+/// The kind of the type - `struct`, `tuple`, `enum` or `union` - precedes its
+/// name, and defines which of [`StrictStruct`](crate::StrictStruct),
+/// [`StrictTuple`](crate::StrictTuple) or [`StrictSum`](crate::StrictSum)
+/// is checked. The derive macros do this for all non-generic types, so the
+/// macro is required only for the manual implementations:
+///
+/// ```
+/// # use strict_encoding::{StrictSum, StrictType};
+/// # #[derive(Default)] struct Method;
+/// # impl StrictType for Method { const STRICT_LIB_NAME: &'static str = "Http"; }
+/// # impl StrictSum for Method {
+/// #     const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(1, "get"), (2, "post")];
+/// #     fn variant_name(&self) -> &'static str { "get" }
+/// # }
+/// strict_encoding::assert_type_sanity!(enum Method);
+/// ```
+#[macro_export]
+macro_rules! assert_type_sanity {
+    (struct $ty:ty) => {
+        const _: () = {
+            let fields = <$ty as $crate::StrictStruct>::ALL_FIELDS;
+            assert!(
+                !fields.is_empty(),
+                concat!("struct type `", stringify!($ty), "` does not contain a single field defined")
+            );
+            assert!(
+                $crate::__private::has_unique_fields(fields),
+                concat!("struct type `", stringify!($ty), "` contains repeated field names")
+            );
+        };
+    };
+    // tuples without fields are unit types, thus there is nothing to check
+    // besides the type being a tuple
+    (tuple $ty:ty) => {
+        const _: u8 = <$ty as $crate::StrictTuple>::FIELD_COUNT;
+    };
+    (enum $ty:ty) => {
+        $crate::assert_type_sanity!(@sum $ty);
+    };
+    (union $ty:ty) => {
+        $crate::assert_type_sanity!(@sum $ty);
+    };
+    (@sum $ty:ty) => {
+        const _: () = {
+            let variants = <$ty as $crate::StrictSum>::ALL_VARIANTS;
+            assert!(
+                !variants.is_empty(),
+                concat!("type `", stringify!($ty), "` does not contain a single variant defined")
+            );
+            assert!(
+                $crate::__private::has_unique_tags(variants),
+                concat!("type `", stringify!($ty), "` contains repeated variant tags")
+            );
+            assert!(
+                $crate::__private::has_unique_variant_names(variants),
+                concat!("type `", stringify!($ty), "` contains repeated variant names")
+            );
+        };
+    };
+}
+
 #[macro_export]
 macro_rules! strict_dumb {
     () => {
@@ -43,7 +121,7 @@ macro_rules! impl_strict_newtype {
             const FIELD_COUNT: u8 = 1;
         }
         impl $crate::StrictEncode for $ty {
-            fn strict_encode<W: $crate::TypedWrite>(&self, writer: W) -> ::std::io::Result<W> {
+            fn strict_encode<W: $crate::TypedWrite>(&self, writer: W) -> $crate::io::Result<W> {
                 writer.write_newtype::<Self>(&self.0)
             }
         }
@@ -54,6 +132,10 @@ macro_rules! impl_strict_newtype {
                 use $crate::ReadTuple;
                 reader.read_tuple(|r| Ok(Self(r.read_field()?)))
             }
+
+            fn strict_display(&self) -> Option<$crate::__private::String> {
+                $crate::StrictDecode::strict_display(&self.0)
+            }
         }
     };
 }
@@ -79,7 +161,7 @@ macro_rules! impl_strict_struct {
             const ALL_FIELDS: &'static [&'static str] = &[$(stringify!($field)),+];
         }
         impl $crate::StrictEncode for $ty {
-            fn strict_encode<W: $crate::TypedWrite>(&self, writer: W) -> io::Result<W> {
+            fn strict_encode<W: $crate::TypedWrite>(&self, writer: W) -> $crate::io::Result<W> {
                 writer.write_struct::<Self>(|w| {
                     Ok(w
                         $(.write_field(fname!(stringify!($field)), &self.$field)?)+
@@ -98,6 +180,103 @@ macro_rules! impl_strict_struct {
     };
 }
 
+/// Implements [`StrictSum`](crate::StrictSum) for a sum type from a single
+/// list of variant patterns with their tags and names, such that
+/// [`StrictSum::ALL_VARIANTS`](crate::StrictSum::ALL_VARIANTS) and
+/// [`StrictSum::variant_name`](crate::StrictSum::variant_name) can't disagree,
+/// and `variant_ord` never panics. The type sanity is checked at compile time,
+/// as by [`assert_type_sanity!`](crate::assert_type_sanity), thus the kind of
+/// the type - `enum` or `union` - precedes its name.
+///
+/// ```
+/// # use strict_encoding::{StrictSum, StrictType};
+/// #[derive(Default)]
+/// enum Shape {
+///     #[default]
+///     Dot,
+///     Circle(u16),
+///     Rect { width: u16, height: u16 },
+/// }
+/// # impl StrictType for Shape { const STRICT_LIB_NAME: &'static str = "Geometry"; }
+/// strict_encoding::impl_strict_sum!(union Shape {
+///     Self::Dot => (0, "dot"),
+///     Self::Circle(_) => (1, "circle"),
+///     Self::Rect { .. } => (2, "rect"),
+/// });
+///
+/// assert_eq!(Shape::ALL_VARIANTS, &[(0, "dot"), (1, "circle"), (2, "rect")]);
+/// assert_eq!(Shape::Circle(5).variant_name(), "circle");
+/// assert_eq!(Shape::Circle(5).variant_ord(), 1);
+/// ```
+#[macro_export]
+macro_rules! impl_strict_sum {
+    ($kind:tt $ty:ty { $($pat:pat => ($tag:expr, $name:literal)),+ $(,)? }) => {
+        impl $crate::StrictSum for $ty {
+            const ALL_VARIANTS: &'static [(u8, &'static str)] = &[$(($tag, $name)),+];
+
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    $($pat => $name),+
+                }
+            }
+
+            fn variant_ord(&self) -> u8 {
+                match self {
+                    $($pat => $tag),+
+                }
+            }
+        }
+        $crate::assert_type_sanity!($kind $ty);
+    };
+}
+
+/// Writes a struct in a manual [`StrictEncode`](crate::StrictEncode)
+/// implementation, checking at compile time that the fields are the ones
+/// declared in [`StrictStruct::ALL_FIELDS`](crate::StrictStruct::ALL_FIELDS)
+/// of the type, written in the same order. Missing, unknown and reordered
+/// fields fail the build, while the writer itself checks this only at runtime.
+///
+/// ```
+/// # use strict_encoding::{fname, io, StrictEncode, StrictStruct, StrictType, TypedWrite};
+/// # #[derive(Default)]
+/// # struct Rate { base: u32, quote: u32 }
+/// # impl StrictType for Rate { const STRICT_LIB_NAME: &'static str = "Test"; }
+/// # impl strict_encoding::StrictProduct for Rate {}
+/// # impl StrictStruct for Rate { const ALL_FIELDS: &'static [&'static str] = &["base", "quote"]; }
+/// impl StrictEncode for Rate {
+///     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+///         strict_encoding::write_struct!(writer, Self {
+///             "base" => &self.base,
+///             "quote" => &self.quote,
+///         })
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! write_struct {
+    ($writer:expr, $ty:ty { $($field:literal => $value:expr),* $(,)? }) => {{
+        // generic types can't be named in a constant item, thus the check is
+        // done by an associated constant evaluated when the type is known
+        struct FieldsCheck<T>(::core::marker::PhantomData<T>);
+        impl<T: $crate::StrictStruct> FieldsCheck<T> {
+            const COMPLETE: () = assert!(
+                $crate::has_same_fields(T::ALL_FIELDS, &[$($field),*]),
+                "fields written by `write_struct!` are not the declared fields of the type \
+                 in their order"
+            );
+        }
+        #[allow(clippy::let_unit_value)]
+        let () = FieldsCheck::<$ty>::COMPLETE;
+        $crate::TypedWrite::write_struct::<$ty>($writer, |writer| {
+            $(
+                let field = $crate::fname!($field);
+                let writer = $crate::WriteStruct::write_field(writer, field, $value)?;
+            )*
+            Ok($crate::WriteStruct::complete(writer))
+        })
+    }};
+}
+
 #[macro_export]
 macro_rules! ident {
     ($name:literal) => {
@@ -147,6 +326,29 @@ macro_rules! fname {
     };
 }
 
+/// Constructs the [`DeclaredField`](crate::DeclaredField) marker written into
+/// the [`FieldsWriter`](crate::FieldsWriter) typestate.
+#[macro_export]
+macro_rules! field {
+    ($name:literal) => {
+        $crate::DeclaredField::<{ $crate::field_id($name) }>::new($crate::fname!($name))
+    };
+}
+
+/// Constructs the type of the fields list of the
+/// [`FieldsWriter`](crate::FieldsWriter) typestate from the field names in
+/// their order, where `..` stands for the fields of a flattened structure.
+#[macro_export]
+macro_rules! fields {
+    () => { () };
+    (.. $(, $($rest:tt)*)?) => {
+        ($crate::FlattenedFields, $crate::fields!($($($rest)*)?))
+    };
+    ($name:literal $(, $($rest:tt)*)?) => {
+        ($crate::DeclaredField<{ $crate::field_id($name) }>, $crate::fields!($($($rest)*)?))
+    };
+}
+
 #[macro_export]
 macro_rules! libname {
     ($name:literal) => {
@@ -0,0 +1,254 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of the network address types from [`std::net`].
+//!
+//! IP addresses are encoded as tuples over their octets in network byte order
+//! (i.e. the order returned by `octets()`), while ports and IPv6 flow
+//! information and scope identifiers are little-endian integers, like any
+//! other integer in strict encoding. [`IpAddr`] and [`SocketAddr`] are unions
+//! with stable tags: `0` for the `v4` variant and `1` for the `v6` variant.
+//!
+//! Socket addresses are encoded as structures of the IP address and port;
+//! [`SocketAddrV6`] also includes its flow information and scope id fields,
+//! such that the decoded value is always equal to the encoded one.
+//!
+//! The std network types do not implement [`crate::StrictDumb`], so when they
+//! are used as fields of a type deriving it, the field must provide its dumb
+//! value explicitly with `#[strict_type(dumb = ...)]` attribute.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use crate::{
+    io, DecodeError, StrictDecode, StrictDumb, StrictEncode, StrictType, TypeName, TypedRead,
+    TypedWrite, LIB_NAME_STD,
+};
+
+// Since the std network types do not implement `Default`, they can't be
+// `StrictDumb` (and thus can't be products or unions) without conflicting with
+// the blanket implementation. Thus, the actual encoding is performed by the
+// local mirror types below, which define the wire layout and type names.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "Ipv4Addr", crate = crate)]
+pub(crate) struct Ipv4([u8; 4]);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "Ipv6Addr", crate = crate)]
+pub(crate) struct Ipv6([u8; 16]);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, tags = custom, rename = "IpAddr", crate = crate)]
+#[strict_type(dumb = Ip::V4(strict_dumb!()))]
+pub(crate) enum Ip {
+    #[strict_type(tag = 0)]
+    V4(Ipv4),
+    #[strict_type(tag = 1)]
+    V6(Ipv6),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "SocketAddrV4", crate = crate)]
+pub(crate) struct SocketV4 {
+    ip: Ipv4,
+    port: u16,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "SocketAddrV6", crate = crate)]
+pub(crate) struct SocketV6 {
+    ip: Ipv6,
+    port: u16,
+    flow_info: u32,
+    scope_id: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, tags = custom, rename = "SocketAddr", crate = crate)]
+#[strict_type(dumb = Socket::V4(strict_dumb!()))]
+pub(crate) enum Socket {
+    #[strict_type(tag = 0)]
+    V4(SocketV4),
+    #[strict_type(tag = 1)]
+    V6(SocketV6),
+}
+
+impl From<&Ipv4Addr> for Ipv4 {
+    fn from(addr: &Ipv4Addr) -> Self { Ipv4(addr.octets()) }
+}
+impl From<Ipv4> for Ipv4Addr {
+    fn from(addr: Ipv4) -> Self { Ipv4Addr::from(addr.0) }
+}
+
+impl From<&Ipv6Addr> for Ipv6 {
+    fn from(addr: &Ipv6Addr) -> Self { Ipv6(addr.octets()) }
+}
+impl From<Ipv6> for Ipv6Addr {
+    fn from(addr: Ipv6) -> Self { Ipv6Addr::from(addr.0) }
+}
+
+impl From<&IpAddr> for Ip {
+    fn from(addr: &IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(addr) => Ip::V4(addr.into()),
+            IpAddr::V6(addr) => Ip::V6(addr.into()),
+        }
+    }
+}
+impl From<Ip> for IpAddr {
+    fn from(addr: Ip) -> Self {
+        match addr {
+            Ip::V4(addr) => IpAddr::V4(addr.into()),
+            Ip::V6(addr) => IpAddr::V6(addr.into()),
+        }
+    }
+}
+
+impl From<&SocketAddrV4> for SocketV4 {
+    fn from(addr: &SocketAddrV4) -> Self {
+        SocketV4 {
+            ip: addr.ip().into(),
+            port: addr.port(),
+        }
+    }
+}
+impl From<SocketV4> for SocketAddrV4 {
+    fn from(addr: SocketV4) -> Self { SocketAddrV4::new(addr.ip.into(), addr.port) }
+}
+
+impl From<&SocketAddrV6> for SocketV6 {
+    fn from(addr: &SocketAddrV6) -> Self {
+        SocketV6 {
+            ip: addr.ip().into(),
+            port: addr.port(),
+            flow_info: addr.flowinfo(),
+            scope_id: addr.scope_id(),
+        }
+    }
+}
+impl From<SocketV6> for SocketAddrV6 {
+    fn from(addr: SocketV6) -> Self {
+        SocketAddrV6::new(addr.ip.into(), addr.port, addr.flow_info, addr.scope_id)
+    }
+}
+
+impl From<&SocketAddr> for Socket {
+    fn from(addr: &SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(addr) => Socket::V4(addr.into()),
+            SocketAddr::V6(addr) => Socket::V6(addr.into()),
+        }
+    }
+}
+impl From<Socket> for SocketAddr {
+    fn from(addr: Socket) -> Self {
+        match addr {
+            Socket::V4(addr) => SocketAddr::V4(addr.into()),
+            Socket::V6(addr) => SocketAddr::V6(addr.into()),
+        }
+    }
+}
+
+macro_rules! impl_net {
+    ($ty:ident, $mirror:ident) => {
+        impl StrictType for $ty {
+            const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+            fn strict_name() -> Option<TypeName> { Some(tn!(stringify!($ty))) }
+        }
+        impl StrictEncode for $ty {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                $mirror::from(self).strict_encode(writer)
+            }
+        }
+        impl StrictDecode for $ty {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                $mirror::strict_decode(reader).map($ty::from)
+            }
+        }
+    };
+}
+
+impl_net!(Ipv4Addr, Ipv4);
+impl_net!(Ipv6Addr, Ipv6);
+impl_net!(IpAddr, Ip);
+impl_net!(SocketAddrV4, SocketV4);
+impl_net!(SocketAddrV6, SocketV6);
+impl_net!(SocketAddr, Socket);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::encoding;
+
+    #[test]
+    fn ipv4() {
+        encoding(&Ipv4Addr::new(192, 168, 0, 1), [192, 168, 0, 1]);
+        encoding(&Ipv4Addr::UNSPECIFIED, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn ipv6() {
+        encoding(&Ipv6Addr::LOCALHOST, [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        encoding(&Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x1234), [
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x12, 0x34,
+        ]);
+    }
+
+    #[test]
+    fn ip_addr() {
+        encoding(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), [0, 10, 0, 0, 1]);
+        encoding(&IpAddr::V6(Ipv6Addr::LOCALHOST), [
+            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        ]);
+    }
+
+    #[test]
+    fn socket_addr_v4() {
+        let addr = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8333);
+        encoding(&addr, [127, 0, 0, 1, 0x8D, 0x20]);
+    }
+
+    #[test]
+    fn socket_addr_v6() {
+        let addr = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 18333, 0x0A0B0C0D, 7);
+        encoding(&addr, [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x9D, 0x47, 0x0D, 0x0C, 0x0B, 0x0A, 7,
+            0, 0, 0,
+        ]);
+    }
+
+    #[test]
+    fn socket_addr() {
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 0x0102));
+        encoding(&addr, [0, 1, 2, 3, 4, 0x02, 0x01]);
+
+        let addr = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0x0102, 3, 4));
+        encoding(&addr, [
+            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x02, 0x01, 3, 0, 0, 0, 4, 0, 0, 0,
+        ]);
+    }
+}
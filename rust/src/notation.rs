@@ -0,0 +1,909 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Editable text notation of strict values, which is converted to and from the
+//! strict encoding through the [reflected values](crate::value).
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::layout::{
+    layout_dumb, layout_of, Fields, LayoutNode, LayoutVariant, TypeHeader, VariantKind,
+};
+use crate::text::TextError;
+use crate::value::{decode_value, encode_value, Resolver, StrictVal, Ty, TypeSystem, ValueError};
+use crate::{
+    DeserializeError, LibName, NumCls, Primitive, RawEncoding, Sizing, StrictDecode, StrictDumb,
+    StrictEncode, StrictReader, StrictWriter, TypeName, VariantInfo, VariantName,
+};
+
+/// Type of a value in the notation together with the named types it refers
+/// to.
+#[derive(Clone)]
+struct TextTy {
+    ty: Ty,
+    sys: TypeSystem,
+}
+
+impl TextTy {
+    fn embedded(ty: Ty) -> Self {
+        TextTy {
+            ty,
+            sys: TypeSystem::new(),
+        }
+    }
+
+    fn inner(item: TextTy, ty: impl FnOnce(Ty) -> Ty) -> Self {
+        TextTy {
+            ty: ty(item.ty),
+            sys: item.sys,
+        }
+    }
+
+    /// Named types are defined in the type system and referenced by their
+    /// names qualified with the library names, such that recursive types can
+    /// refer to themselves.
+    fn named(header: &TypeHeader, ty: Ty, mut sys: TypeSystem) -> Self {
+        match &header.name {
+            Some(name) => {
+                sys.insert(libname!(header.lib), name.clone(), ty);
+                TextTy {
+                    ty: Ty::named(libname!(header.lib), name.clone()),
+                    sys,
+                }
+            }
+            None => TextTy { ty, sys },
+        }
+    }
+}
+
+fn merge(items: impl IntoIterator<Item = TextTy>, sys: &mut TypeSystem) -> Vec<Ty> {
+    items
+        .into_iter()
+        .map(|item| {
+            sys.extend(item.sys);
+            item.ty
+        })
+        .collect()
+}
+
+fn fields_ty(kind: VariantKind, fields: Fields<TextTy>, sys: &mut TypeSystem) -> Ty {
+    let (names, items): (Vec<_>, Vec<_>) = fields.into_iter().unzip();
+    let items = merge(items, sys);
+    match kind {
+        VariantKind::Unit => Ty::unit(),
+        VariantKind::Tuple => Ty::Tuple(items),
+        VariantKind::Struct => Ty::Struct(
+            names
+                .into_iter()
+                .zip(items)
+                .map(|(name, ty)| (name.expect("struct fields are named"), ty))
+                .collect(),
+        ),
+    }
+}
+
+impl LayoutNode for TextTy {
+    fn primitive(prim: Primitive) -> Self { Self::embedded(Ty::Primitive(prim)) }
+
+    fn unicode(sizing: Sizing) -> Self { Self::embedded(Ty::Unicode(sizing)) }
+
+    // restricted strings are encoded as unicode strings; the characters are
+    // checked when the value is decoded from the encoding of the notation
+    fn rstring(_first: Self, _rest: Self, sizing: Sizing) -> Self { Self::unicode(sizing) }
+
+    fn array(item: Self, len: u16) -> Self { Self::inner(item, |ty| Ty::array(ty, len)) }
+
+    fn list(item: Self, sizing: Sizing) -> Self { Self::inner(item, |ty| Ty::list(ty, sizing)) }
+
+    fn set(item: Self, sizing: Sizing) -> Self { Self::inner(item, |ty| Ty::set(ty, sizing)) }
+
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self {
+        let mut sys = key.sys;
+        sys.extend(value.sys);
+        TextTy {
+            ty: Ty::map(key.ty, value.ty, sizing),
+            sys,
+        }
+    }
+
+    fn sequence(items: Vec<Self>) -> Self {
+        let mut sys = TypeSystem::new();
+        let items = merge(items, &mut sys);
+        TextTy {
+            ty: Ty::Tuple(items),
+            sys,
+        }
+    }
+
+    fn enumeration(ty: &TypeHeader, variants: &'static [(u8, &'static str)]) -> Self {
+        let variants =
+            variants.iter().map(|(tag, name)| VariantInfo::new(vname!(*name), *tag)).collect();
+        Self::named(ty, Ty::Enum(variants), TypeSystem::new())
+    }
+
+    fn union(ty: &TypeHeader, variants: Vec<LayoutVariant<Self>>) -> Self {
+        let mut sys = TypeSystem::new();
+        let variants = variants
+            .into_iter()
+            .map(|variant| {
+                let info = VariantInfo::new(variant.name, variant.tag);
+                (info, fields_ty(variant.kind, variant.fields, &mut sys))
+            })
+            .collect();
+        Self::named(ty, Ty::Union(variants), sys)
+    }
+
+    fn tuple(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        let mut sys = TypeSystem::new();
+        let tuple = fields_ty(VariantKind::Tuple, fields, &mut sys);
+        Self::named(ty, tuple, sys)
+    }
+
+    fn structure(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        let mut sys = TypeSystem::new();
+        let structure = fields_ty(VariantKind::Struct, fields, &mut sys);
+        Self::named(ty, structure, sys)
+    }
+
+    // the type is defined by the outer node of the same type
+    fn recursive(ty: &TypeHeader) -> Self {
+        let name = ty.name.clone().expect("recursive types are named");
+        Self::embedded(Ty::named(libname!(ty.lib), name))
+    }
+
+    fn raw(ty: &TypeHeader, encoding: RawEncoding) -> Self {
+        Self::named(ty, Ty::Raw(encoding), TypeSystem::new())
+    }
+}
+
+/// Renders the value in the text notation, written on a single line:
+/// - integers, including variable-length ones, are decimal, and the unit value is `()`;
+/// - strings are quoted, escaping quotes, backslashes and control characters as `\"`, `\\`, `\n`,
+///   `\r`, `\t` and `\u{7f}`;
+/// - byte strings and byte arrays are lowercase hex with `0x` prefix;
+/// - lists and arrays of other items are `[a, b]`, sets are `{a, b}` and maps are `{key: value}`;
+/// - bit sets are lists of `0` and `1` bits, like `[1, 0, 1]`;
+/// - tuples are `(a, b)` and structs are `(field: a, other: b)`;
+/// - enum variants are their names, and union variants are their names followed by the fields in
+///   the tuple or struct form, if any.
+///
+/// The notation covers the types supported by the reflected values; in
+/// particular, floats and integers above 128 bits are not supported.
+///
+/// # Errors
+///
+/// If the value contains types not supported by the notation.
+pub fn to_text<T: StrictEncode>(value: &T) -> Result<String, TextError> {
+    let TextTy { ty, sys } = layout_of::<TextTy>(value);
+    let writer = value
+        .strict_encode(StrictWriter::in_memory::<{ usize::MAX }>())
+        .map_err(DeserializeError::from)?;
+    let data = writer.unbox().unconfine();
+    let mut reader = StrictReader::from_slice::<{ usize::MAX }>(&data);
+    let value = decode_value(&mut reader, &ty, &sys).map_err(DeserializeError::from)?;
+    let mut text = String::new();
+    render(&value, &mut text);
+    Ok(text)
+}
+
+/// Parses a value of type `T` from the [text notation](to_text).
+///
+/// Parsing is driven by the type of the value, accepting any whitespace and
+/// trailing commas. Values may nest the named types up to
+/// [`RECURSION_DEPTH_LIMIT`](crate::RECURSION_DEPTH_LIMIT) levels deep.
+///
+/// # Errors
+///
+/// If the text is not a valid notation of a value of the type, reporting the
+/// position of syntax errors, or if the value is not valid for the type.
+pub fn from_text<T>(text: &str) -> Result<T, TextError>
+where T: StrictEncode + StrictDecode + StrictDumb {
+    let TextTy { ty, sys } = layout_of::<TextTy>(&layout_dumb::<T>());
+    let mut parser = Parser::new(text, Resolver::new(&sys));
+    let value = parser.value(&ty)?;
+    parser.skip_ws();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    let writer = encode_value(StrictWriter::in_memory::<{ usize::MAX }>(), &value, &ty, &sys)?;
+    let data = writer.unbox().unconfine();
+    let mut reader = StrictReader::from_slice::<{ usize::MAX }>(&data);
+    let value = T::strict_decode(&mut reader).map_err(DeserializeError::from)?;
+    let left = reader.remaining().len();
+    if left > 0 {
+        return Err(DeserializeError::DataNotEntirelyConsumed(left as u64).into());
+    }
+    Ok(value)
+}
+
+fn render_items<'v>(
+    open: char,
+    items: impl IntoIterator<Item = &'v StrictVal>,
+    close: char,
+    text: &mut String,
+) {
+    text.push(open);
+    for (no, item) in items.into_iter().enumerate() {
+        if no > 0 {
+            text.push_str(", ");
+        }
+        render(item, text);
+    }
+    text.push(close);
+}
+
+fn render(value: &StrictVal, text: &mut String) {
+    match value {
+        StrictVal::Unit => text.push_str("()"),
+        StrictVal::Uint(v) => write!(text, "{v}").expect("writing to string"),
+        StrictVal::Int(v) => write!(text, "{v}").expect("writing to string"),
+        StrictVal::Bytes(bytes) => {
+            text.push_str("0x");
+            for byte in bytes {
+                write!(text, "{byte:02x}").expect("writing to string");
+            }
+        }
+        StrictVal::String(s) => {
+            text.push('"');
+            for ch in s.chars() {
+                match ch {
+                    '"' => text.push_str("\\\""),
+                    '\\' => text.push_str("\\\\"),
+                    '\n' => text.push_str("\\n"),
+                    '\r' => text.push_str("\\r"),
+                    '\t' => text.push_str("\\t"),
+                    ch if ch.is_control() => {
+                        write!(text, "\\u{{{:x}}}", ch as u32).expect("writing to string")
+                    }
+                    ch => text.push(ch),
+                }
+            }
+            text.push('"');
+        }
+        StrictVal::List(items) => render_items('[', items, ']', text),
+        StrictVal::Set(items) => render_items('{', items, '}', text),
+        StrictVal::Map(items) => {
+            text.push('{');
+            for (no, (key, value)) in items.iter().enumerate() {
+                if no > 0 {
+                    text.push_str(", ");
+                }
+                render(key, text);
+                text.push_str(": ");
+                render(value, text);
+            }
+            text.push('}');
+        }
+        StrictVal::Tuple(items) => render_items('(', items, ')', text),
+        StrictVal::Struct(fields) => {
+            text.push('(');
+            for (no, (name, value)) in fields.iter().enumerate() {
+                if no > 0 {
+                    text.push_str(", ");
+                }
+                text.push_str(name.as_str());
+                text.push_str(": ");
+                render(value, text);
+            }
+            text.push(')');
+        }
+        StrictVal::Union(name, fields) => {
+            text.push_str(name.as_str());
+            if **fields != StrictVal::Unit {
+                render(fields, text);
+            }
+        }
+        StrictVal::Enum(name) => text.push_str(name.as_str()),
+    }
+}
+
+/// Parser of the notation of values, which is driven by their types.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    types: Resolver<'a>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, types: Resolver<'a>) -> Self {
+        Parser {
+            text,
+            pos: 0,
+            types,
+        }
+    }
+
+    fn error(&self, msg: impl ToString) -> TextError {
+        let parsed = &self.text[..self.pos];
+        let line_start = parsed.rfind('\n').map(|pos| pos + 1).unwrap_or_default();
+        TextError::Syntax {
+            line: parsed.matches('\n').count() + 1,
+            column: parsed[line_start..].chars().count() + 1,
+            msg: msg.to_string(),
+        }
+    }
+
+    fn rest(&self) -> &'a str { &self.text[self.pos..] }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), TextError> {
+        if self.eat(ch) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{ch}`")))
+        }
+    }
+
+    /// Takes the longest non-empty prefix of characters matching `f`.
+    fn take(&mut self, what: &str, f: impl Fn(char) -> bool) -> Result<&'a str, TextError> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest.find(|ch| !f(ch)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(format!("expected {what}")));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn ident(&mut self) -> Result<&'a str, TextError> {
+        self.take("a name", |ch| ch.is_ascii_alphanumeric() || ch == '_')
+    }
+
+    /// Parses comma-separated items up to the closing character, allowing a
+    /// trailing comma.
+    fn items<T>(
+        &mut self,
+        open: char,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, TextError>,
+    ) -> Result<Vec<T>, TextError> {
+        self.expect(open)?;
+        let mut items = vec![];
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn integer(&mut self, prim: Primitive) -> Result<StrictVal, TextError> {
+        let (cls, size) = match prim {
+            Primitive::BYTE => (NumCls::Unsigned, 1),
+            _ => (prim.info().ty, prim.info().byte_size()),
+        };
+        if size > 16 || cls == NumCls::Float {
+            return Err(ValueError::UnsupportedPrimitive(prim).into());
+        }
+        let start = self.pos;
+        let digits = self.take("an integer", |ch| ch.is_ascii_digit() || ch == '-')?;
+        let value = match cls {
+            NumCls::Signed => digits.parse().map(StrictVal::Int).ok(),
+            _ => digits.parse().map(StrictVal::Uint).ok(),
+        };
+        value.ok_or_else(|| {
+            self.pos = start;
+            self.error(format!("invalid {prim} integer `{digits}`"))
+        })
+    }
+
+    fn string(&mut self) -> Result<String, TextError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let Some(ch) = self.rest().chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += ch.len_utf8();
+            match ch {
+                '"' => return Ok(s),
+                '\\' => s.push(self.escape()?),
+                ch => s.push(ch),
+            }
+        }
+    }
+
+    /// Parses the escape sequence following a backslash.
+    fn escape(&mut self) -> Result<char, TextError> {
+        let rest = self.rest();
+        let escaped = match rest.chars().next() {
+            Some('"') => Some(('"', 1)),
+            Some('\\') => Some(('\\', 1)),
+            Some('n') => Some(('\n', 1)),
+            Some('r') => Some(('\r', 1)),
+            Some('t') => Some(('\t', 1)),
+            Some('u') => rest[1..]
+                .strip_prefix('{')
+                .and_then(|code| code.split_once('}'))
+                .and_then(|(hex, _)| {
+                    let ch = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+                    Some((ch, hex.len() + 3))
+                }),
+            _ => None,
+        };
+        let Some((ch, len)) = escaped else {
+            // points to the backslash
+            self.pos -= 1;
+            return Err(self.error("invalid escape sequence"));
+        };
+        self.pos += len;
+        Ok(ch)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, TextError> {
+        self.skip_ws();
+        if !self.rest().starts_with("0x") {
+            return Err(self.error("expected hex string starting with `0x`"));
+        }
+        self.pos += 2;
+        let rest = self.rest();
+        let hex = &rest[..rest.find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(rest.len())];
+        if hex.len() % 2 != 0 {
+            self.pos += hex.len();
+            return Err(self.error("hex string has odd number of digits"));
+        }
+        if hex.bytes().any(|ch| ch.is_ascii_uppercase()) {
+            return Err(self.error("hex string must be lowercase"));
+        }
+        self.pos += hex.len();
+        Ok((0..hex.len())
+            .step_by(2)
+            .map(|pos| u8::from_str_radix(&hex[pos..pos + 2], 16).expect("hex digits"))
+            .collect())
+    }
+
+    fn variant<'v, T>(
+        &mut self,
+        variants: &'v [T],
+        name: impl Fn(&T) -> &VariantName,
+    ) -> Result<&'v T, TextError> {
+        let start = self.pos;
+        let found = self.ident()?;
+        variants.iter().find(|variant| name(variant).as_str() == found).ok_or_else(|| {
+            self.pos = start;
+            self.skip_ws();
+            self.error(format!("unknown variant `{found}`"))
+        })
+    }
+
+    fn fields(&mut self, fields: &'a [Ty]) -> Result<Vec<StrictVal>, TextError> {
+        self.expect('(')?;
+        let mut values = Vec::with_capacity(fields.len());
+        for (no, ty) in fields.iter().enumerate() {
+            if no > 0 {
+                self.expect(',')?;
+            }
+            values.push(self.value(ty)?);
+        }
+        if !fields.is_empty() {
+            self.eat(',');
+        }
+        self.expect(')')?;
+        Ok(values)
+    }
+
+    fn named_fields(
+        &mut self,
+        fields: &'a [(crate::FieldName, Ty)],
+    ) -> Result<Vec<(crate::FieldName, StrictVal)>, TextError> {
+        self.expect('(')?;
+        let mut values = Vec::with_capacity(fields.len());
+        for (no, (name, ty)) in fields.iter().enumerate() {
+            if no > 0 {
+                self.expect(',')?;
+            }
+            let start = self.pos;
+            if self.ident()? != name.as_str() {
+                self.pos = start;
+                self.skip_ws();
+                return Err(self.error(format!("expected field `{name}`")));
+            }
+            self.expect(':')?;
+            values.push((name.clone(), self.value(ty)?));
+        }
+        if !fields.is_empty() {
+            self.eat(',');
+        }
+        self.expect(')')?;
+        Ok(values)
+    }
+
+    /// Parses the values of the types which don't contain other types.
+    fn leaf(&mut self, ty: &'a Ty) -> Result<StrictVal, TextError> {
+        Ok(match ty {
+            Ty::Primitive(Primitive::UNIT) => {
+                self.expect('(')?;
+                self.expect(')')?;
+                StrictVal::Unit
+            }
+            Ty::Primitive(prim) => self.integer(*prim)?,
+            Ty::Unicode(_) => StrictVal::String(self.string()?),
+            Ty::Array(..) | Ty::List(..) => StrictVal::Bytes(self.bytes()?),
+            Ty::Enum(variants) => {
+                StrictVal::Enum(self.variant(variants, |variant| &variant.name)?.name.clone())
+            }
+            Ty::Raw(RawEncoding::Leb128 | RawEncoding::CompactSize) => {
+                self.integer(Primitive::U64)?
+            }
+            Ty::Raw(RawEncoding::Bitset(_)) => {
+                StrictVal::List(self.items('[', ']', |p| p.integer(Primitive::U8))?)
+            }
+            Ty::Set(..) | Ty::Map(..) | Ty::Union(..) | Ty::Tuple(..) | Ty::Struct(..) => {
+                unreachable!("composite types are parsed by Parser::value")
+            }
+            Ty::Ref { .. } => unreachable!("named types are parsed by Parser::value"),
+        })
+    }
+
+    fn map(&mut self, key: &'a Ty, value: &'a Ty) -> Result<StrictVal, TextError> {
+        let items = self.items('{', '}', |p| {
+            let key = p.value(key)?;
+            p.expect(':')?;
+            Ok((key, p.value(value)?))
+        })?;
+        Ok(StrictVal::Map(items))
+    }
+
+    fn union(&mut self, variants: &'a [(VariantInfo, Ty)]) -> Result<StrictVal, TextError> {
+        let (variant, fields) = self.variant(variants, |(variant, _)| &variant.name)?;
+        let fields = match fields {
+            Ty::Tuple(fields) => StrictVal::Tuple(self.fields(fields)?),
+            Ty::Struct(fields) => StrictVal::Struct(self.named_fields(fields)?),
+            _ => StrictVal::Unit,
+        };
+        Ok(StrictVal::Union(variant.name.clone(), fields.into()))
+    }
+
+    fn named(&mut self, lib: &LibName, name: &TypeName) -> Result<StrictVal, TextError> {
+        let ty = self.types.enter(lib, name)?;
+        let value = self.value(ty)?;
+        self.types.leave();
+        Ok(value)
+    }
+
+    // Each kind of composite type is parsed by its own method, keeping the
+    // frames of this recursive dispatch small.
+    fn value(&mut self, ty: &'a Ty) -> Result<StrictVal, TextError> {
+        match ty {
+            Ty::Array(item, _) | Ty::List(item, _) if !item.is_byte() => {
+                self.items('[', ']', |p| p.value(item)).map(StrictVal::List)
+            }
+            Ty::Set(item, _) => self.items('{', '}', |p| p.value(item)).map(StrictVal::Set),
+            Ty::Map(key, value, _) => self.map(key, value),
+            Ty::Union(variants) => self.union(variants),
+            Ty::Tuple(fields) => self.fields(fields).map(StrictVal::Tuple),
+            Ty::Struct(fields) => self.named_fields(fields).map(StrictVal::Struct),
+            Ty::Ref { lib, name } => self.named(lib, name),
+            _ => self.leaf(ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use amplify::confinement::{
+        Confined, SmallString, TinyBlob, TinyOrdMap, TinyOrdSet, TinyString, TinyVec,
+    };
+    use proptest::collection::{btree_map, vec};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        CompactSizeU64, DecodeError, StrictType, TinyBitset, VarIntU64, RECURSION_DEPTH_LIMIT,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        version: u16,
+        offset: i32,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8, crate = crate)]
+    #[repr(u8)]
+    enum Kind {
+        #[default]
+        Plain = 0,
+        Nested = 0x10,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Payload {
+        #[default]
+        Empty,
+        Point(u8, i8),
+        Named {
+            id: u64,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        header: Header,
+        kind: Kind,
+        payloads: TinyVec<Payload>,
+        name: TinyString,
+        data: TinyBlob,
+        id: [u8; 4],
+        tags: TinyOrdSet<u16>,
+        index: TinyOrdMap<u8, TinyString>,
+        pair: (u8, i16),
+        parent: Option<Header>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Tree {
+        value: u8,
+        children: TinyVec<Tree>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Other", rename = "Header", crate = crate)]
+    struct OtherHeader(TinyString);
+
+    fn record() -> Record {
+        Record {
+            header: Header {
+                version: 2,
+                offset: -70000,
+            },
+            kind: Kind::Nested,
+            payloads: TinyVec::try_from(vec![
+                Payload::Empty,
+                Payload::Point(7, -1),
+                Payload::Named { id: u64::MAX },
+            ])
+            .unwrap(),
+            name: TinyString::try_from(s!("say \"hi\"\\\n\u{7f}")).unwrap(),
+            data: TinyBlob::try_from(vec![0xAB, 0x0D]).unwrap(),
+            id: [1, 2, 3, 4],
+            tags: TinyOrdSet::try_from_iter([5u16, 0x100]).unwrap(),
+            index: TinyOrdMap::try_from_iter([(1u8, TinyString::try_from(s!("a")).unwrap())])
+                .unwrap(),
+            pair: (255, -2),
+            parent: Some(Header::default()),
+        }
+    }
+
+    const RECORD: &str = "(header: (version: 2, offset: -70000), kind: nested, payloads: [empty, \
+                          point(7, -1), named(id: 18446744073709551615)], name: \"say \
+                          \\\"hi\\\"\\\\\\n\\u{7f}\", data: 0xab0d, id: 0x01020304, tags: {5, \
+                          256}, index: {1: \"a\"}, pair: (255, -2), parent: some((version: 0, \
+                          offset: 0)))";
+
+    #[test]
+    fn golden() {
+        assert_eq!(to_text(&record()).unwrap(), RECORD);
+        assert_eq!(from_text::<Record>(RECORD).unwrap(), record());
+        assert_eq!(
+            to_text(&Record::default()).unwrap(),
+            "(header: (version: 0, offset: 0), kind: plain, payloads: [], name: \"\", data: 0x, \
+             id: 0x00000000, tags: {}, index: {}, pair: (0, 0), parent: none)"
+        );
+    }
+
+    #[test]
+    fn editable() {
+        let text = r#"(
+            header: (version: 2, offset: -70000),
+            kind: nested,
+            payloads: [
+                empty,
+                point(7, -1),
+                named(id: 18446744073709551615),
+            ],
+            name: "say \"hi\"\\
+\u{7f}",
+            data: 0xab0d,
+            id: 0x01020304,
+            tags: {5, 256},
+            index: {1: "a",},
+            pair: (255, -2,),
+            parent: some((version: 0, offset: 0)),
+        )"#;
+        assert_eq!(from_text::<Record>(text).unwrap(), record());
+    }
+
+    #[test]
+    fn raw() {
+        let value = (
+            VarIntU64::from(300),
+            CompactSizeU64::from(u64::MAX),
+            TinyBitset::try_from([true, false, true].as_slice()).unwrap(),
+        );
+        let text = "(300, 18446744073709551615, [1, 0, 1])";
+        assert_eq!(to_text(&value).unwrap(), text);
+        assert_eq!(from_text::<(VarIntU64, CompactSizeU64, TinyBitset)>(text).unwrap(), value);
+        assert_eq!(
+            from_text::<VarIntU64>("18446744073709551616").unwrap_err().to_string(),
+            "value 18446744073709551616 is out of range of U64 type."
+        );
+        assert!(from_text::<TinyBitset>("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn recursive() {
+        let leaf = |value| Tree {
+            value,
+            children: none!(),
+        };
+        let tree = Tree {
+            value: 1,
+            children: TinyVec::try_from(vec![leaf(2), Tree {
+                value: 3,
+                children: TinyVec::try_from(vec![leaf(4)]).unwrap(),
+            }])
+            .unwrap(),
+        };
+        let text = "(value: 1, children: [(value: 2, children: []), (value: 3, children: [(value: \
+                    4, children: [])])])";
+        assert_eq!(to_text(&tree).unwrap(), text);
+        assert_eq!(from_text::<Tree>(text).unwrap(), tree);
+
+        let deep = format!(
+            "{}(value: 0, children: []){}",
+            "(value: 0, children: [".repeat(RECURSION_DEPTH_LIMIT),
+            "])".repeat(RECURSION_DEPTH_LIMIT)
+        );
+        assert_eq!(
+            from_text::<Tree>(&deep).unwrap_err(),
+            TextError::Value(ValueError::MaxRecursionDepth {
+                limit: RECURSION_DEPTH_LIMIT
+            })
+        );
+    }
+
+    #[test]
+    fn qualified_names() {
+        let value = (Header::default(), OtherHeader(TinyString::try_from(s!("a")).unwrap()));
+        let text = "((version: 0, offset: 0), (\"a\"))";
+        assert_eq!(to_text(&value).unwrap(), text);
+        assert_eq!(from_text::<(Header, OtherHeader)>(text).unwrap(), value);
+    }
+
+    fn roundtrip<T>(value: T)
+    where T: StrictEncode + StrictDecode + StrictDumb + PartialEq + core::fmt::Debug {
+        let text = to_text(&value).unwrap();
+        assert_eq!(from_text::<T>(&text).unwrap(), value, "{text}");
+    }
+
+    proptest! {
+        #[test]
+        fn primitives(a: u8, b: u16, c: u32, d: u64, e: u128, f: i8, g: i16, h: i32, i: i64) {
+            roundtrip((a, b, c, d, e));
+            roundtrip((f, g, h, i));
+        }
+
+        #[test]
+        fn misc(a: i128, b: bool, c: char, d: Option<u16>, e: [u8; 3], f: [i16; 2]) {
+            roundtrip((a, b, c, ()));
+            roundtrip((d, e, f));
+        }
+
+        #[test]
+        fn collections(
+            s in any::<String>(),
+            blob in vec(any::<u8>(), 0..64),
+            list in vec(any::<Option<i32>>(), 0..16),
+            map in btree_map(any::<u8>(), any::<String>(), 0..8),
+        ) {
+            roundtrip(SmallString::try_from(s).unwrap());
+            roundtrip(Confined::<Vec<u8>, 0, 64>::try_from(blob).unwrap());
+            roundtrip(TinyVec::try_from(list).unwrap());
+            let map = map
+                .into_iter()
+                .map(|(key, value)| (key, SmallString::try_from(value).unwrap()))
+                .collect::<BTreeMap<_, _>>();
+            roundtrip(TinyOrdMap::try_from(map).unwrap());
+        }
+
+        #[test]
+        fn records(version: u16, offset: i32, point: (u8, i8), id: u64, name in any::<String>()) {
+            let mut record = record();
+            record.header = Header { version, offset };
+            record.payloads = TinyVec::try_from(vec![
+                Payload::Named { id },
+                Payload::Point(point.0, point.1),
+            ])
+            .unwrap();
+            record.name = TinyString::try_from(name.chars().take(20).collect::<String>()).unwrap();
+            record.parent = None;
+            roundtrip(record);
+        }
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let err = |text: &str| from_text::<Record>(text).unwrap_err().to_string();
+        let text = "(\n  header: (version: 2, offset: 0),\n  kind: wrong,\n)";
+        assert_eq!(err(text), "unknown variant `wrong` at line 3, column 9.");
+        let text = "(\n  header: (version: 2 offset: 0)";
+        assert_eq!(err(text), "expected `,` at line 2, column 23.");
+        let text = "(header: (version: 2, offst: 0))";
+        assert_eq!(err(text), "expected field `offset` at line 1, column 23.");
+        assert_eq!(
+            from_text::<TinyString>("\"ab\\q\"").unwrap_err().to_string(),
+            "invalid escape sequence at line 1, column 4."
+        );
+        assert_eq!(
+            from_text::<TinyString>("  \"ab").unwrap_err().to_string(),
+            "unterminated string at line 1, column 6."
+        );
+        assert_eq!(
+            from_text::<TinyBlob>("0xABCD").unwrap_err().to_string(),
+            "hex string must be lowercase at line 1, column 3."
+        );
+        assert_eq!(
+            from_text::<u8>("-1").unwrap_err().to_string(),
+            "invalid U8 integer `-1` at line 1, column 1."
+        );
+        assert_eq!(
+            from_text::<u8>("1 2").unwrap_err().to_string(),
+            "unexpected text after the value at line 1, column 3."
+        );
+    }
+
+    #[test]
+    fn value_errors() {
+        // syntactically valid text which doesn't fit the type
+        assert_eq!(
+            from_text::<u8>("256").unwrap_err().to_string(),
+            "value 256 is out of range of U8 type."
+        );
+        let err = from_text::<[u8; 4]>("0x0102").unwrap_err();
+        assert!(matches!(err, TextError::Value(_)), "{err}");
+        let err = from_text::<TinyOrdSet<u8>>("{2, 1, 1}").unwrap_err();
+        assert_eq!(err, TextError::Decode(DeserializeError::Decode(DecodeError::BrokenSetOrder)));
+        #[cfg(feature = "float")]
+        assert!(matches!(to_text(&1.0f32), Err(TextError::Decode(DeserializeError::Decode(_)))));
+    }
+}
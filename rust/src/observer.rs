@@ -0,0 +1,390 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumentation of the encoding and decoding for collecting metrics,
+//! available with `std` feature; see [`CodecObserver`].
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{io, DecodeError, ReadRaw, WriteRaw};
+
+/// Callbacks notified about the encoding and decoding of values, for
+/// collecting metrics. All the methods do nothing by default.
+///
+/// An observer installed into a writer with
+/// [`StrictWriter::with_observer`](crate::StrictWriter::with_observer) or into
+/// a reader with [`StrictReader::with_observer`](crate::StrictReader::with_observer)
+/// is notified about the encoding and decoding of each value of a structure,
+/// tuple, union or enum type, including the values nested into other values.
+/// The types are identified by their strict names, or by their rust type names
+/// if they don't have strict names (like `Option`). Values failed to encode or
+/// decode are reported together with all the values containing them.
+///
+/// The numbers of bytes are known only for the writers and readers tracking
+/// their offsets ([`WriteRaw::offset`] and [`ReadRaw::offset`]), which all
+/// the writers and readers of the library do; for the others zero is reported.
+/// On `wasm32-unknown-unknown` target, which has no clock, the elapsed time is
+/// always reported as zero.
+///
+/// Without an installed observer, the only overhead of the instrumentation is
+/// a check whether the observer is present for each encoded or decoded value;
+/// see `observer` benchmark.
+///
+/// [`StatsObserver`] is a ready-made observer aggregating [`Stats`] per type.
+#[allow(unused_variables)]
+pub trait CodecObserver: Send + Sync {
+    /// Encoding of a value of the type starts.
+    fn on_encode_start(&self, type_name: &str) {}
+    /// Value of the type is encoded into `bytes` bytes, taking `elapsed` time.
+    fn on_encode_end(&self, type_name: &str, bytes: usize, elapsed: Duration) {}
+    /// Value of the type has failed to encode.
+    fn on_encode_error(&self, type_name: &str, err: &io::Error) {}
+    /// Decoding of a value of the type starts.
+    fn on_decode_start(&self, type_name: &str) {}
+    /// Value of the type is decoded from `bytes` bytes, taking `elapsed` time.
+    fn on_decode_end(&self, type_name: &str, bytes: usize, elapsed: Duration) {}
+    /// Value of the type has failed to decode.
+    fn on_decode_error(&self, type_name: &str, err: &DecodeError) {}
+}
+
+/// Observer installed into a writer or a reader.
+#[derive(Clone)]
+pub(crate) struct Observer(Arc<dyn CodecObserver>);
+
+impl Debug for Observer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("CodecObserver") }
+}
+
+impl From<Arc<dyn CodecObserver>> for Observer {
+    fn from(observer: Arc<dyn CodecObserver>) -> Self { Observer(observer) }
+}
+
+/// Detects targets where [`Instant::now`] panics.
+const NO_CLOCK: bool = cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+/// Encoding or decoding of a single value reported to an observer.
+pub(crate) struct Observation {
+    observer: Arc<dyn CodecObserver>,
+    type_name: String,
+    offset: Option<usize>,
+    start: Option<Instant>,
+}
+
+impl Observation {
+    pub fn encode(observer: &Observer, type_name: String, writer: &impl WriteRaw) -> Self {
+        observer.0.on_encode_start(&type_name);
+        Self::start(observer, type_name, writer.offset())
+    }
+
+    pub fn decode(observer: &Observer, type_name: String, reader: &impl ReadRaw) -> Self {
+        observer.0.on_decode_start(&type_name);
+        Self::start(observer, type_name, reader.offset())
+    }
+
+    fn start(observer: &Observer, type_name: String, offset: Option<usize>) -> Self {
+        Observation {
+            observer: observer.0.clone(),
+            type_name,
+            offset,
+            start: (!NO_CLOCK).then(Instant::now),
+        }
+    }
+
+    fn elapsed(&self) -> Duration { self.start.map(|start| start.elapsed()).unwrap_or_default() }
+
+    fn bytes(&self, offset: Option<usize>) -> usize {
+        match (self.offset, offset) {
+            (Some(start), Some(end)) => end.saturating_sub(start),
+            _ => 0,
+        }
+    }
+
+    /// Reports the result of the encoding, given as the offset of the writer
+    /// after the value is written or the encoding error.
+    pub fn encoded(self, res: Result<Option<usize>, &io::Error>) {
+        match res {
+            Ok(offset) => {
+                let bytes = self.bytes(offset);
+                self.observer.on_encode_end(&self.type_name, bytes, self.elapsed());
+            }
+            Err(err) => self.observer.on_encode_error(&self.type_name, err),
+        }
+    }
+
+    /// Reports the result of the decoding, given as the offset of the reader
+    /// after the value is read or the decoding error.
+    pub fn decoded(self, res: Result<Option<usize>, &DecodeError>) {
+        match res {
+            Ok(offset) => {
+                let bytes = self.bytes(offset);
+                self.observer.on_decode_end(&self.type_name, bytes, self.elapsed());
+            }
+            Err(err) => self.observer.on_decode_error(&self.type_name, err),
+        }
+    }
+}
+
+/// Metrics of the encoding and decoding of the values of a type collected by
+/// [`StatsObserver`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Stats {
+    /// Number of the encoded values.
+    pub encoded: u64,
+    /// Total number of bytes the values are encoded into.
+    pub encoded_bytes: u64,
+    /// Total time spent on encoding the values.
+    pub encode_time: Duration,
+    /// Number of the values failed to encode.
+    pub encode_failures: u64,
+    /// Number of the decoded values.
+    pub decoded: u64,
+    /// Total number of bytes the values are decoded from.
+    pub decoded_bytes: u64,
+    /// Total time spent on decoding the values.
+    pub decode_time: Duration,
+    /// Number of the values failed to decode.
+    pub decode_failures: u64,
+}
+
+/// Observer aggregating [`Stats`] of each type into a shared map; the clones
+/// of the observer share the same map.
+#[derive(Clone, Debug, Default)]
+pub struct StatsObserver(Arc<Mutex<HashMap<String, Stats>>>);
+
+impl StatsObserver {
+    pub fn new() -> Self { default!() }
+
+    /// Map of the collected stats by the type names.
+    pub fn stats(&self) -> Arc<Mutex<HashMap<String, Stats>>> { self.0.clone() }
+
+    /// Returns the stats collected for the type, if any.
+    pub fn get(&self, type_name: &str) -> Option<Stats> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).get(type_name).copied()
+    }
+
+    fn update(&self, type_name: &str, f: impl FnOnce(&mut Stats)) {
+        let mut stats = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match stats.get_mut(type_name) {
+            Some(entry) => f(entry),
+            None => f(stats.entry(type_name.to_owned()).or_default()),
+        }
+    }
+}
+
+impl CodecObserver for StatsObserver {
+    fn on_encode_end(&self, type_name: &str, bytes: usize, elapsed: Duration) {
+        self.update(type_name, |stats| {
+            stats.encoded += 1;
+            stats.encoded_bytes += bytes as u64;
+            stats.encode_time += elapsed;
+        })
+    }
+
+    fn on_encode_error(&self, type_name: &str, _: &io::Error) {
+        self.update(type_name, |stats| stats.encode_failures += 1)
+    }
+
+    fn on_decode_end(&self, type_name: &str, bytes: usize, elapsed: Duration) {
+        self.update(type_name, |stats| {
+            stats.decoded += 1;
+            stats.decoded_bytes += bytes as u64;
+            stats.decode_time += elapsed;
+        })
+    }
+
+    fn on_decode_error(&self, type_name: &str, _: &DecodeError) {
+        self.update(type_name, |stats| stats.decode_failures += 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{TinyString, TinyVec};
+
+    use super::*;
+    use crate::{
+        StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictReader, StrictSerialize,
+        StrictType, StrictWriter,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Shape {
+        #[default]
+        Point,
+        Circle(u16),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        id: u32,
+        shape: Shape,
+        label: Option<u8>,
+        name: TinyString,
+    }
+    impl StrictSerialize for Record {}
+    impl StrictDeserialize for Record {}
+
+    /// Observer recording the events as strings.
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Recorder {
+        fn events(&self) -> Vec<String> { self.0.lock().unwrap().clone() }
+        fn push(&self, event: String) { self.0.lock().unwrap().push(event) }
+    }
+
+    impl CodecObserver for Recorder {
+        fn on_encode_start(&self, type_name: &str) { self.push(format!("encode {type_name}")) }
+        fn on_encode_end(&self, type_name: &str, bytes: usize, _: Duration) {
+            self.push(format!("encoded {type_name} {bytes}"))
+        }
+        fn on_encode_error(&self, type_name: &str, err: &io::Error) {
+            self.push(format!("encode error {type_name} {:?}", err.kind()))
+        }
+        fn on_decode_start(&self, type_name: &str) { self.push(format!("decode {type_name}")) }
+        fn on_decode_end(&self, type_name: &str, bytes: usize, _: Duration) {
+            self.push(format!("decoded {type_name} {bytes}"))
+        }
+        fn on_decode_error(&self, type_name: &str, err: &DecodeError) {
+            self.push(format!("decode error {type_name} {err}"))
+        }
+    }
+
+    const OPTION: &str = "U8?";
+
+    fn record() -> Record {
+        Record {
+            id: 7,
+            shape: Shape::Circle(300),
+            label: Some(1),
+            name: TinyString::try_from(s!("observed")).unwrap(),
+        }
+    }
+
+    fn encode<T: StrictEncode>(val: &T) -> Vec<u8> {
+        val.strict_encode(StrictWriter::in_memory::<1024>()).unwrap().unbox().unconfine()
+    }
+
+    #[test]
+    fn events() {
+        let recorder = Arc::new(Recorder::default());
+        let data = record().to_strict_serialized_observed::<1024>(recorder.clone()).unwrap();
+        assert_eq!(recorder.events(), [
+            s!("encode Record"),
+            s!("encode Shape"),
+            s!("encoded Shape 3"),
+            format!("encode {OPTION}"),
+            format!("encoded {OPTION} 2"),
+            format!("encoded Record {}", data.len()),
+        ]);
+
+        let recorder = Arc::new(Recorder::default());
+        let decoded = Record::from_strict_slice_observed::<1024>(&data, recorder.clone()).unwrap();
+        assert_eq!(decoded, record());
+        assert_eq!(recorder.events(), [
+            s!("decode Record"),
+            s!("decode Shape"),
+            s!("decoded Shape 3"),
+            format!("decode {OPTION}"),
+            format!("decoded {OPTION} 2"),
+            format!("decoded Record {}", data.len()),
+        ]);
+    }
+
+    #[test]
+    fn stats() {
+        let observer = StatsObserver::new();
+        let records = [record(), Record::strict_dumb(), Record {
+            shape: Shape::Point,
+            label: None,
+            ..record()
+        }];
+        let mut total = 0;
+        for record in &records {
+            let data = record.to_strict_serialized_observed::<1024>(Arc::new(observer.clone()));
+            let data = data.unwrap();
+            assert_eq!(data.as_slice(), encode(record));
+            total += data.len() as u64;
+
+            let mut reader = StrictReader::in_memory::<1024>(data.as_slice())
+                .with_observer(Arc::new(observer.clone()));
+            assert_eq!(&Record::strict_decode(&mut reader).unwrap(), record);
+        }
+
+        let stats = observer.get("Record").unwrap();
+        assert_eq!(stats.encoded, 3);
+        assert_eq!(stats.encoded_bytes, total);
+        assert_eq!(stats.decoded, 3);
+        assert_eq!(stats.decoded_bytes, total);
+        assert_eq!((stats.encode_failures, stats.decode_failures), (0, 0));
+
+        let shapes = records.iter().map(|record| encode(&record.shape).len() as u64).sum::<u64>();
+        let stats = observer.get("Shape").unwrap();
+        assert_eq!((stats.encoded, stats.encoded_bytes), (3, shapes));
+        assert_eq!((stats.decoded, stats.decoded_bytes), (3, shapes));
+        assert_eq!(observer.get(OPTION).unwrap().encoded_bytes, 2 + 1 + 1);
+        assert_eq!(observer.get("TinyString"), None);
+        assert_eq!(observer.stats().lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn failures() {
+        let observer = StatsObserver::new();
+        let data = encode(&record());
+
+        let res =
+            Record::from_strict_slice_observed::<1024>(&data[..6], Arc::new(observer.clone()));
+        assert!(res.is_err());
+        assert_eq!(observer.get("Record").unwrap().decode_failures, 1);
+        assert_eq!(observer.get("Shape").unwrap().decode_failures, 1);
+        assert_eq!(observer.get("Shape").unwrap().decoded, 0);
+
+        let writer = StrictWriter::in_memory::<8>().with_observer(Arc::new(observer.clone()));
+        assert!(record().strict_encode(writer).is_err());
+        let stats = observer.get("Record").unwrap();
+        assert_eq!((stats.encoded, stats.encode_failures), (0, 1));
+        assert_eq!(observer.get("Shape").unwrap().encoded, 1);
+        assert_eq!(observer.get(OPTION).unwrap().encode_failures, 1);
+    }
+
+    #[test]
+    fn anonymous_names() {
+        let observer = StatsObserver::new();
+        let list = Some(TinyVec::try_from(vec![1u16, 2, 3]).unwrap());
+        let writer = StrictWriter::in_memory::<64>().with_observer(Arc::new(observer.clone()));
+        let data = list.strict_encode(writer).unwrap().unbox().unconfine();
+
+        let mut reader = StrictReader::in_memory::<64>(data.as_slice())
+            .with_observer(Arc::new(observer.clone()));
+        assert_eq!(Option::<TinyVec<u16>>::strict_decode(&mut reader).unwrap(), list);
+        // anonymous types are named in the strict type notation
+        let stats = observer.get("[U16 ^ ..0xff]?").unwrap();
+        assert_eq!((stats.encoded_bytes, stats.decoded_bytes), (8, 8));
+        assert_eq!(observer.stats().lock().unwrap().len(), 1);
+    }
+}
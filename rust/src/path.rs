@@ -0,0 +1,176 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of the file system paths from [`std::path`].
+//!
+//! [`PathBuf`] and `&`[`Path`] are encoded as a newtype `Path` over a UTF-8
+//! string of up to [`PATH_MAX_LEN`] (`u16::MAX`) bytes, such that the encoding
+//! is the same on all platforms:
+//! - the components of the path are separated with forward slashes; on Windows backslash separators
+//!   are replaced with them during encoding (on other platforms backslash is a valid character of a
+//!   file name and is kept);
+//! - paths which are not valid UTF-8 strings can't be encoded and fail with [`NonUtf8Path`] error
+//!   wrapped into [`io::Error`] of [`io::ErrorKind::InvalidInput`] kind; no lossy conversion is
+//!   performed;
+//! - paths longer than [`PATH_MAX_LEN`] bytes fail to encode with [`io::ErrorKind::InvalidInput`]
+//!   error.
+//!
+//! The path is kept as is otherwise: absolute and relative paths are encoded
+//! without modification and are decoded without canonicalization.
+
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use amplify::confinement::{Confined, U16};
+
+use crate::{
+    io, DecodeError, StrictDecode, StrictEncode, StrictType, TypeName, TypedRead, TypedWrite,
+    LIB_NAME_STD,
+};
+
+/// Maximal length of the UTF-8 string representation of a strict-encoded path,
+/// in bytes.
+pub const PATH_MAX_LEN: usize = U16;
+
+/// Error of encoding a path which is not a valid UTF-8 string.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct NonUtf8Path(pub PathBuf);
+
+impl Display for NonUtf8Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "path `{}` is not a valid UTF-8 string and can't be encoded", self.0.display())
+    }
+}
+
+impl_error!(NonUtf8Path);
+
+impl From<NonUtf8Path> for io::Error {
+    fn from(err: NonUtf8Path) -> Self { io::Error::new(io::ErrorKind::InvalidInput, err) }
+}
+
+// `PathBuf` and `Path` are encoded through this mirror type, which defines the
+// wire layout and type name.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "Path", crate = crate)]
+struct PathString(Confined<String, 0, PATH_MAX_LEN>);
+
+impl TryFrom<&Path> for PathString {
+    type Error = io::Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let s = path.to_str().ok_or_else(|| NonUtf8Path(path.to_owned()))?;
+        #[cfg(windows)]
+        let s = s.replace('\\', "/");
+        let s = Confined::try_from(s.to_owned())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(PathString(s))
+    }
+}
+
+impl From<PathString> for PathBuf {
+    fn from(path: PathString) -> Self { PathBuf::from(path.0.release()) }
+}
+
+impl StrictType for PathBuf {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Path")) }
+}
+impl StrictEncode for PathBuf {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.as_path().strict_encode(writer)
+    }
+}
+impl StrictDecode for PathBuf {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        PathString::strict_decode(reader).map(PathBuf::from)
+    }
+}
+
+impl StrictType for &Path {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Path")) }
+}
+impl StrictEncode for &Path {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        PathString::try_from(*self)?.strict_encode(writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encode, encoding};
+    use crate::{assert_strict_layout, StrictWriter};
+
+    fn check(path: &str) {
+        let mut data = (path.len() as u16).to_le_bytes().to_vec();
+        data.extend(path.as_bytes());
+        encoding(&PathBuf::from(path), data.clone());
+        assert_eq!(encode(&Path::new(path)), data);
+    }
+
+    #[test]
+    fn layout() {
+        assert_strict_layout!(PathString, "Std.Path: tuple([Unicode])");
+    }
+
+    #[test]
+    fn relative() {
+        check("");
+        check("docs/manifest.toml");
+        check("../workspace/./src");
+    }
+
+    #[test]
+    fn absolute() {
+        check("/");
+        check("/home/user/.config/workspace.toml");
+    }
+
+    #[test]
+    fn spaces_unicode() {
+        check("My Documents/проекты/日本語 ファイル.txt");
+        check("/tmp/emoji 🦀/ä ö ü");
+    }
+
+    #[test]
+    fn oversize() {
+        let path = PathBuf::from("a".repeat(PATH_MAX_LEN + 1));
+        let err = path.strict_encode(StrictWriter::in_memory::<{ U16 + 2 }>()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"data/\xFF\xFE.bin"));
+        let err = path.strict_encode(StrictWriter::in_memory::<256>()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.get_ref().and_then(|err| err.downcast_ref::<NonUtf8Path>()),
+            Some(&NonUtf8Path(path))
+        );
+    }
+}
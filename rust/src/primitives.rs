@@ -19,8 +19,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fmt::{self, Display, Formatter, Write};
-use std::hash::Hash;
+use core::fmt::{self, Display, Formatter, Write};
+use core::hash::Hash;
 
 use amplify::Wrapper;
 
@@ -304,8 +304,106 @@ impl NumCls {
     pub const fn into_code(self) -> u8 { self as u8 }
 }
 
+/// Error converting an integer into a signed integer of non-standard bit
+/// width, like [`i24`], which range doesn't include the value.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{value} is out of the range {min}..={max} of `{type_name}` integer")]
+pub struct IntOverflow {
+    /// Name of the integer type.
+    pub type_name: &'static str,
+    /// The value which doesn't fit into the type.
+    pub value: i64,
+    /// Minimal value of the type.
+    pub min: i64,
+    /// Maximal value of the type.
+    pub max: i64,
+}
+
+impl_error!(IntOverflow);
+
+macro_rules! signed_int {
+    ($ty:ident, $inner:ty, $bytes:literal, $doc:literal; $($into:ident => $into_ty:ty),+) => {
+        #[doc = $doc]
+        ///
+        /// All bit patterns of the width are valid values, which are
+        /// strict-encoded as little-endian bytes in two's complement form.
+        #[allow(non_camel_case_types)]
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+        #[display(inner)]
+        pub struct $ty($inner);
+
+        impl $ty {
+            pub const BITS: u32 = $bytes * 8;
+            pub const MIN: Self = $ty(-(1 << (Self::BITS - 1)));
+            pub const MAX: Self = $ty((1 << (Self::BITS - 1)) - 1);
+            pub const ZERO: Self = $ty(0);
+
+            /// Constructs the value, panicking if it is out of the type range.
+            pub const fn with(value: $inner) -> Self {
+                assert!(
+                    value >= Self::MIN.0 && value <= Self::MAX.0,
+                    concat!("value is out of ", stringify!($ty), " range")
+                );
+                $ty(value)
+            }
+
+            pub const fn to_le_bytes(self) -> [u8; $bytes] {
+                let full = self.0.to_le_bytes();
+                let mut bytes = [0u8; $bytes];
+                let mut i = 0;
+                while i < $bytes {
+                    bytes[i] = full[i];
+                    i += 1;
+                }
+                bytes
+            }
+
+            /// Reads the value from little-endian bytes, extending the sign
+            /// bit; any bytes make a valid value.
+            pub const fn from_le_bytes(bytes: [u8; $bytes]) -> Self {
+                const SHIFT: u32 = <$inner>::BITS - $ty::BITS;
+                let mut full = [0u8; <$inner>::BITS as usize / 8];
+                let mut i = 0;
+                while i < $bytes {
+                    full[i] = bytes[i];
+                    i += 1;
+                }
+                $ty(<$inner>::from_le_bytes(full) << SHIFT >> SHIFT)
+            }
+
+            $( pub const fn $into(self) -> $into_ty { self.0 as $into_ty } )+
+        }
+
+        impl TryFrom<$inner> for $ty {
+            type Error = IntOverflow;
+
+            fn try_from(value: $inner) -> Result<Self, Self::Error> {
+                if !(Self::MIN.0..=Self::MAX.0).contains(&value) {
+                    return Err(IntOverflow {
+                        type_name: stringify!($ty),
+                        value: value as i64,
+                        min: Self::MIN.0 as i64,
+                        max: Self::MAX.0 as i64,
+                    });
+                }
+                Ok($ty(value))
+            }
+        }
+
+        $( impl From<$ty> for $into_ty {
+            fn from(value: $ty) -> Self { value.$into() }
+        } )+
+    };
+}
+
+signed_int!(i24, i32, 3, "24-bit signed integer."; into_i32 => i32, into_i64 => i64);
+signed_int!(i40, i64, 5, "40-bit signed integer."; into_i64 => i64);
+signed_int!(i48, i64, 6, "48-bit signed integer."; into_i64 => i64);
+signed_int!(i56, i64, 7, "56-bit signed integer."; into_i64 => i64);
+
 #[cfg(test)]
 mod test {
+    use super::*;
     use crate::Primitive;
 
     #[test]
@@ -368,4 +466,49 @@ mod test {
         assert_eq!(Primitive::UNIT.byte_size(), 0);
         assert_eq!(Primitive::BYTE.byte_size(), 1);
     }
+
+    #[test]
+    fn signed_ranges() {
+        assert_eq!(i24::MIN.into_i32(), -0x80_0000);
+        assert_eq!(i24::MAX.into_i64(), 0x7F_FFFF);
+        assert_eq!(i40::MIN.into_i64(), -0x80_0000_0000);
+        assert_eq!(i48::MAX.into_i64(), 0x7FFF_FFFF_FFFF);
+        assert_eq!(i56::MIN.into_i64(), -0x80_0000_0000_0000);
+        assert_eq!(i64::from(i56::MAX), 0x7F_FFFF_FFFF_FFFF);
+
+        assert_eq!(i24::try_from(-0x80_0000), Ok(i24::MIN));
+        assert_eq!(
+            i24::try_from(0x80_0000),
+            Err(IntOverflow {
+                type_name: "i24",
+                value: 0x80_0000,
+                min: -0x80_0000,
+                max: 0x7F_FFFF,
+            })
+        );
+        assert!(i40::try_from(-0x80_0000_0001).is_err());
+        assert!(i48::try_from(0x8000_0000_0000).is_err());
+        let err = i56::try_from(i64::MIN).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "-9223372036854775808 is out of the range -36028797018963968..=36028797018963967 of \
+             `i56` integer"
+        );
+    }
+
+    #[test]
+    fn sign_extension() {
+        assert_eq!(i24::from_le_bytes([0xFF, 0xFF, 0xFF]), i24::with(-1));
+        assert_eq!(i40::from_le_bytes([0x00, 0x00, 0x00, 0x00, 0xC0]).into_i64(), -0x40_0000_0000);
+        assert_eq!(
+            i48::from_le_bytes([0x01, 0x00, 0x00, 0x00, 0x00, 0x80]),
+            i48::with(i48::MIN.into_i64() + 1)
+        );
+        assert_eq!(i56::from_le_bytes([0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]), i56::MAX);
+        assert_eq!(i24::with(-5).to_string(), "-5");
+    }
+
+    #[test]
+    #[should_panic(expected = "value is out of i24 range")]
+    fn signed_with_overflow() { i24::with(0x80_0000); }
 }
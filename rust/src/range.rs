@@ -0,0 +1,279 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of ranges and range bounds from [`std::ops`].
+//!
+//! Ranges are encoded as structures of their `start` and/or `end` fields.
+//! Ranges with `start > end` are valid in Rust and thus are accepted on
+//! decoding. [`RangeInclusive`] is always reconstructed as `start..=end`, so
+//! the exhaustion state of a range used as an iterator is not preserved.
+//!
+//! [`Bound`] is encoded as a union with `included` (tag `0`), `excluded` (tag
+//! `1`) and `unbounded` (tag `2`) variants.
+
+use core::ops::{Bound, Range, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive};
+
+use crate::{
+    io, DecodeError, DefineUnion, ReadStruct, ReadTuple, ReadUnion, StrictDecode, StrictDumb,
+    StrictEncode, StrictProduct, StrictStruct, StrictSum, StrictType, StrictUnion, TypeName,
+    TypedRead, TypedWrite, WriteStruct, WriteUnion, LIB_NAME_STD,
+};
+
+// Most of the range types do not implement `Default` and can't be
+// `StrictDumb`, so they are read and written using these mirror structures,
+// which share the name with the range type.
+macro_rules! range_mirror {
+    ($mirror:ident, $range:ident, $( $field:ident ),+) => {
+        struct $mirror<T> {
+            $( $field: T ),+
+        }
+        impl<T: StrictDumb> StrictDumb for $mirror<T> {
+            fn strict_dumb() -> Self {
+                $mirror { $( $field: T::strict_dumb() ),+ }
+            }
+        }
+        impl<T: StrictType> StrictType for $mirror<T> {
+            const STRICT_LIB_NAME: &'static str = <$range<T> as StrictType>::STRICT_LIB_NAME;
+            fn strict_name() -> Option<TypeName> { $range::<T>::strict_name() }
+        }
+        impl<T: StrictType + StrictDumb> StrictProduct for $mirror<T> {}
+        impl<T: StrictType + StrictDumb> StrictStruct for $mirror<T> {
+            const ALL_FIELDS: &'static [&'static str] = &[$( stringify!($field) ),+];
+        }
+        impl<T: StrictDecode + StrictDumb> $mirror<T> {
+            fn read(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_struct(|r| {
+                    Ok($mirror { $( $field: r.read_field(fname!(stringify!($field)))? ),+ })
+                })
+            }
+        }
+
+        impl<T: StrictType> StrictType for $range<T> {
+            const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+        }
+    };
+}
+
+range_mirror!(RangeDef, Range, start, end);
+range_mirror!(RangeInclusiveDef, RangeInclusive, start, end);
+range_mirror!(RangeFromDef, RangeFrom, start);
+range_mirror!(RangeToDef, RangeTo, end);
+range_mirror!(RangeToInclusiveDef, RangeToInclusive, end);
+
+impl<T: StrictEncode + StrictDumb> StrictEncode for Range<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<RangeDef<T>>(|w| {
+            Ok(w.write_field(fname!("start"), &self.start)?
+                .write_field(fname!("end"), &self.end)?
+                .complete())
+        })
+    }
+}
+impl<T: StrictDecode + StrictDumb> StrictDecode for Range<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        RangeDef::read(reader).map(|r| r.start..r.end)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictEncode for RangeInclusive<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<RangeInclusiveDef<T>>(|w| {
+            Ok(w.write_field(fname!("start"), self.start())?
+                .write_field(fname!("end"), self.end())?
+                .complete())
+        })
+    }
+}
+impl<T: StrictDecode + StrictDumb> StrictDecode for RangeInclusive<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        RangeInclusiveDef::read(reader).map(|r| r.start..=r.end)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictEncode for RangeFrom<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<RangeFromDef<T>>(|w| {
+            Ok(w.write_field(fname!("start"), &self.start)?.complete())
+        })
+    }
+}
+impl<T: StrictDecode + StrictDumb> StrictDecode for RangeFrom<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        RangeFromDef::read(reader).map(|r| r.start..)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictEncode for RangeTo<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<RangeToDef<T>>(|w| {
+            Ok(w.write_field(fname!("end"), &self.end)?.complete())
+        })
+    }
+}
+impl<T: StrictDecode + StrictDumb> StrictDecode for RangeTo<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        RangeToDef::read(reader).map(|r| ..r.end)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictEncode for RangeToInclusive<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<RangeToInclusiveDef<T>>(|w| {
+            Ok(w.write_field(fname!("end"), &self.end)?.complete())
+        })
+    }
+}
+impl<T: StrictDecode + StrictDumb> StrictDecode for RangeToInclusive<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        RangeToInclusiveDef::read(reader).map(|r| ..=r.end)
+    }
+}
+
+const BOUND_VARIANTS: &[(u8, &str)] = &[(0u8, "included"), (1u8, "excluded"), (2u8, "unbounded")];
+
+enum BoundDef<T> {
+    Included(T),
+    Excluded(T),
+    Unbounded,
+}
+impl<T> StrictDumb for BoundDef<T> {
+    fn strict_dumb() -> Self { BoundDef::Unbounded }
+}
+impl<T: StrictType> StrictType for BoundDef<T> {
+    const STRICT_LIB_NAME: &'static str = <Bound<T> as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { Bound::<T>::strict_name() }
+}
+impl<T: StrictType> StrictSum for BoundDef<T> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = BOUND_VARIANTS;
+    fn variant_name(&self) -> &'static str {
+        match self {
+            BoundDef::Included(_) => "included",
+            BoundDef::Excluded(_) => "excluded",
+            BoundDef::Unbounded => "unbounded",
+        }
+    }
+}
+impl<T: StrictType> StrictUnion for BoundDef<T> {}
+
+impl<T: StrictType> StrictType for Bound<T> {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+}
+impl<T: StrictType> StrictSum for Bound<T> {
+    const ALL_VARIANTS: &'static [(u8, &'static str)] = BOUND_VARIANTS;
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Bound::Included(_) => "included",
+            Bound::Excluded(_) => "excluded",
+            Bound::Unbounded => "unbounded",
+        }
+    }
+}
+impl<T: StrictEncode + StrictDumb> StrictEncode for Bound<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_union::<BoundDef<T>>(|u| {
+            let u = u
+                .define_newtype::<T>(vname!("included"))
+                .define_newtype::<T>(vname!("excluded"))
+                .define_unit(vname!("unbounded"))
+                .complete();
+
+            Ok(match self {
+                Bound::Included(val) => u.write_newtype(vname!("included"), val),
+                Bound::Excluded(val) => u.write_newtype(vname!("excluded"), val),
+                Bound::Unbounded => u.write_unit(vname!("unbounded")),
+            }?
+            .complete())
+        })
+    }
+}
+impl<T: StrictDecode> StrictDecode for Bound<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let bound = reader.read_union(|field_name, u| match field_name.as_str() {
+            "included" => u.read_tuple(|r| r.read_field().map(BoundDef::Included)),
+            "excluded" => u.read_tuple(|r| r.read_field().map(BoundDef::Excluded)),
+            "unbounded" => Ok(BoundDef::Unbounded),
+            _ => unreachable!("unknown bound variant"),
+        })?;
+        Ok(match bound {
+            BoundDef::Included(val) => Bound::Included(val),
+            BoundDef::Excluded(val) => Bound::Excluded(val),
+            BoundDef::Unbounded => Bound::Unbounded,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::encoding;
+    use crate::test_helpers::assert_layout_of;
+
+    #[test]
+    fn layout() {
+        assert_layout_of(&(0u8..1), "Std.Rangeu8: struct { start:0: U8, end:1: U8 }");
+        assert_layout_of(&(..1u8), "Std.RangeTou8: struct { end:0: U8 }");
+        assert_layout_of(
+            &Bound::Included(0u8),
+            "Std.Boundu8: union { included = 0(U8), excluded = 1(U8), unbounded = 2 }",
+        );
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn range() {
+        encoding(&(1u16..0x0203), [1, 0, 3, 2]);
+        encoding(&(5u8..5), [5, 5]);
+        // ranges with start greater than end are accepted
+        encoding(&(9u8..2), [9, 2]);
+        assert!(Range::<u8>::strict_name().is_some());
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn range_inclusive() {
+        encoding(&(1u64..=u64::MAX), [
+            1, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        // ranges with start greater than end are accepted
+        encoding(&(9u8..=2), [9, 2]);
+
+        // exhausted range is decoded back as a non-empty one
+        let mut exhausted = 1u8..=2;
+        exhausted.by_ref().for_each(drop);
+        assert!(exhausted.is_empty());
+        assert_eq!(crate::test::encode(&exhausted), [2, 2]);
+        assert!(!crate::test::decode::<RangeInclusive<u8>>([2, 2]).is_empty());
+    }
+
+    #[test]
+    fn range_open() {
+        encoding(&(0x0102u16..), [2, 1]);
+        encoding(&(..0x0102u16), [2, 1]);
+        encoding(&(..=0x0102u16), [2, 1]);
+    }
+
+    #[test]
+    fn bound() {
+        encoding(&Bound::Included(0x0102u16), [0, 2, 1]);
+        encoding(&Bound::Excluded(7u8), [1, 7]);
+        encoding(&Bound::<u8>::Unbounded, [2]);
+    }
+}
@@ -19,13 +19,84 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
+#[cfg(feature = "bytes")]
+use ::bytes::Bytes;
+
+use crate::intern::Interner;
+#[cfg(feature = "std")]
+use crate::observer::{CodecObserver, Observation, Observer};
+#[cfg(feature = "bytes")]
+use crate::BytesReader;
 use crate::{
-    DecodeError, FieldName, ReadRaw, ReadStruct, ReadTuple, ReadUnion, StrictDecode, StrictEnum,
-    StrictStruct, StrictSum, StrictTuple, StrictUnion, TypedRead, VariantName,
+    io, Byte, ContextError, DecodeError, DeserializeError, FieldName, KnownVariants, PathSegment,
+    ReadRaw, ReadStruct, ReadTuple, ReadUnion, Sizing, StrictDecode, StrictDumb, StrictEnum,
+    StrictStruct, StrictSum, StrictTuple, StrictType, StrictUnion, TypedRead, VariantName,
+    PREALLOC_LIMIT,
 };
 
+/// Name of the type used in decoding errors: anonymous types (like `Option`)
+/// are reported by their [`StrictType::strict_anonymous_name`].
+pub(crate) fn decoded_name<T: StrictType>() -> String {
+    T::strict_name().map(|name| name.to_string()).unwrap_or_else(T::strict_anonymous_name)
+}
+
+/// Decodes a value from in-memory data of at most `MAX` bytes, which must be
+/// entirely consumed by the value.
+pub(crate) fn decode_slice<T: StrictDecode, const MAX: usize>(
+    data: &[u8],
+) -> Result<T, DeserializeError> {
+    let mut reader = StrictReader::from_slice::<MAX>(data);
+    let value = T::strict_decode(&mut reader)?;
+    reader.finish()?;
+    Ok(value)
+}
+
+/// Strict type notation of an anonymous unicode string.
+pub(crate) fn unicode_name(min_len: usize, max_len: usize) -> String {
+    format!("[Unicode{}]", Sizing::new(min_len as u64, max_len as u64))
+}
+
+// Items of byte arrays and lists are laid out as `Byte`.
+fn item_name<T: StrictType>() -> String {
+    match T::strict_name() == u8::strict_name() {
+        true => decoded_name::<Byte>(),
+        false => decoded_name::<T>(),
+    }
+}
+
+/// Strict type notation of an anonymous array of `LEN` items.
+pub(crate) fn array_name<T: StrictType>(len: usize) -> String {
+    format!("[{} ^ {len}]", item_name::<T>())
+}
+
+/// Strict type notation of an anonymous list of `T` items.
+pub(crate) fn list_name<T: StrictType>(min_len: usize, max_len: usize) -> String {
+    format!("[{}{}]", item_name::<T>(), Sizing::new(min_len as u64, max_len as u64))
+}
+
+/// Strict type notation of an anonymous set of `T` items.
+pub(crate) fn set_name<T: StrictType>(min_len: usize, max_len: usize) -> String {
+    format!("{{{}{}}}", decoded_name::<T>(), Sizing::new(min_len as u64, max_len as u64))
+}
+
+/// Strict type notation of an anonymous map from `K` keys to `V` values.
+pub(crate) fn map_name<K: StrictType, V: StrictType>(min_len: usize, max_len: usize) -> String {
+    format!(
+        "{{{} -> {}{}}}",
+        decoded_name::<K>(),
+        decoded_name::<V>(),
+        Sizing::new(min_len as u64, max_len as u64)
+    )
+}
+
+/// Allocates a zero-filled shared buffer, which is uniquely owned and can be
+/// filled in place by taking it with [`Arc::get_mut`].
+fn zeroed_shared(len: usize) -> Arc<[u8]> { core::iter::repeat(0).take(len).collect() }
+
 // TODO: Move to amplify crate
 /// A simple way to count bytes read through [`io::Read`].
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug)]
@@ -86,63 +157,939 @@ impl<R: io::Read> io::Read for ConfinedReader<R> {
     }
 }
 
+/// Reader decoding data from [`io::Read`].
+///
+/// The reader keeps the last [`StreamReader::MAX_REWIND`] consumed bytes, such
+/// that it can be [rewound](ReadRaw::rewind) by up to that number of bytes.
 #[derive(Clone, Debug)]
-pub struct StreamReader<R: io::Read>(ConfinedReader<R>);
+pub struct StreamReader<R: io::Read> {
+    reader: ConfinedReader<R>,
+    /// Ring buffer with the last consumed bytes.
+    history: [u8; MAX_REWIND],
+    /// Position in the history where the next consumed byte goes.
+    head: usize,
+    /// Number of bytes in the history.
+    filled: usize,
+    /// Number of bytes at the end of the history which are rewound and must
+    /// be read again.
+    rewound: usize,
+}
+
+const MAX_REWIND: usize = 64;
 
 impl<R: io::Read> StreamReader<R> {
-    pub fn new<const MAX: usize>(inner: R) -> Self { Self(ConfinedReader::with(MAX, inner)) }
-    pub fn unconfine(self) -> R { self.0.unconfine() }
+    /// Maximal number of bytes by which the reader can be rewound.
+    pub const MAX_REWIND: usize = MAX_REWIND;
+
+    pub fn new<const MAX: usize>(inner: R) -> Self { Self::with(MAX, inner) }
+
+    pub(crate) fn with(limit: usize, inner: R) -> Self {
+        Self {
+            reader: ConfinedReader::with(limit, inner),
+            history: [0u8; MAX_REWIND],
+            head: 0,
+            filled: 0,
+            rewound: 0,
+        }
+    }
+    /// Number of bytes consumed so far.
+    pub fn count(&self) -> usize { self.reader.count() - self.rewound }
+    /// Releases the underlying reader. The bytes which were rewound and not
+    /// yet read again are lost.
+    pub fn unconfine(self) -> R { self.reader.unconfine() }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        use io::Read;
+
+        let replay = self.rewound.min(buf.len());
+        if replay > 0 {
+            let start = (self.head + MAX_REWIND - self.rewound) % MAX_REWIND;
+            let first = replay.min(MAX_REWIND - start);
+            buf[..first].copy_from_slice(&self.history[start..start + first]);
+            buf[first..replay].copy_from_slice(&self.history[..replay - first]);
+            self.rewound -= replay;
+        }
+
+        let fresh = &mut buf[replay..];
+        self.reader.read_exact(fresh)?;
+        let fresh = &fresh[fresh.len().saturating_sub(MAX_REWIND)..];
+        let first = fresh.len().min(MAX_REWIND - self.head);
+        self.history[self.head..self.head + first].copy_from_slice(&fresh[..first]);
+        self.history[..fresh.len() - first].copy_from_slice(&fresh[first..]);
+        self.head = (self.head + fresh.len()) % MAX_REWIND;
+        self.filled = (self.filled + fresh.len()).min(MAX_REWIND);
+        Ok(())
+    }
 }
 
 impl<T: AsRef<[u8]>> StreamReader<io::Cursor<T>> {
-    pub fn cursor<const MAX: usize>(inner: T) -> Self {
-        Self(ConfinedReader::with(MAX, io::Cursor::new(inner)))
-    }
+    pub fn cursor<const MAX: usize>(inner: T) -> Self { Self::new::<MAX>(io::Cursor::new(inner)) }
 }
 
 impl<R: io::Read> ReadRaw for StreamReader<R> {
+    /// Since the data may not be present in the stream, the bytes are read in
+    /// chunks of at most [`PREALLOC_LIMIT`] bytes, and the buffer grows only
+    /// when the previous chunk is read.
     fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
-        use io::Read;
-        let mut buf = vec![0u8; len];
-        self.0.read_exact(&mut buf)?;
+        let mut buf = Vec::with_capacity(len.min(PREALLOC_LIMIT));
+        while buf.len() < len {
+            let start = buf.len();
+            buf.resize(start + (len - start).min(PREALLOC_LIMIT), 0);
+            self.read_into(&mut buf[start..])?;
+        }
         Ok(buf)
     }
 
     fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
-        use io::Read;
         let mut buf = [0u8; LEN];
-        self.0.read_exact(&mut buf)?;
+        self.read_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let end = buf.len() + len;
+        while buf.len() < end {
+            let start = buf.len();
+            buf.resize(start + (end - start).min(PREALLOC_LIMIT), 0);
+            self.read_into(&mut buf[start..])?;
+        }
+        Ok(())
+    }
+
+    /// Buffers of up to [`PREALLOC_LIMIT`] bytes are allocated upfront and
+    /// read right from the stream; longer data are read in chunks, as by
+    /// [`ReadRaw::read_raw`], and copied.
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        if len > PREALLOC_LIMIT {
+            return self.read_raw::<MAX_LEN>(len).map(Arc::from);
+        }
+        let mut buf = zeroed_shared(len);
+        self.read_into(Arc::get_mut(&mut buf).expect("new buffer is not shared"))?;
         Ok(buf)
     }
+
+    /// Skipping beyond the confinement limit fails before any data are read.
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> {
+        match self.count().checked_add(len) {
+            None => return Err(io::ErrorKind::OutOfMemory.into()),
+            Some(end) if end > self.reader.limit => return Err(io::ErrorKind::InvalidInput.into()),
+            Some(_) => {}
+        }
+        let mut buf = [0u8; 4096];
+        let mut left = len;
+        while left > 0 {
+            let chunk = left.min(buf.len());
+            self.read_into(&mut buf[..chunk])?;
+            left -= chunk;
+        }
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.count()) }
+
+    fn peek_byte(&mut self) -> io::Result<u8> {
+        let [byte] = self.read_raw_array::<1>()?;
+        self.rewind(1)?;
+        Ok(byte)
+    }
+
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        if self.rewound + len > self.filled {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        self.rewound += len;
+        Ok(())
+    }
+
+    /// Reads the underlying reader up to its end or the first byte beyond the
+    /// confinement limit; the drained data can't be rewound.
+    fn drain(&mut self) -> io::Result<usize> {
+        let cap = self.reader.limit.saturating_sub(self.reader.count).saturating_add(1);
+        let mut fresh = 0usize;
+        let mut buf = [0u8; 4096];
+        while fresh < cap {
+            let chunk = buf.len().min(cap - fresh);
+            match self.reader.reader.read(&mut buf[..chunk]) {
+                Ok(0) => break,
+                Ok(read) => fresh += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.reader.count = self.reader.count.saturating_add(fresh);
+        let len = self.rewound.saturating_add(fresh);
+        self.rewound = 0;
+        self.filled = 0;
+        Ok(len)
+    }
 }
 
 impl<T: AsRef<[u8]>> StreamReader<io::Cursor<T>> {
     pub fn in_memory<const MAX: usize>(data: T) -> Self { Self::new::<MAX>(io::Cursor::new(data)) }
-    pub fn into_cursor(self) -> io::Cursor<T> { self.0.unconfine() }
+    /// Releases the cursor, positioned right after the consumed data.
+    pub fn into_cursor(self) -> io::Cursor<T> {
+        let rewound = self.rewound as u64;
+        let mut cursor = self.unconfine();
+        cursor.set_position(cursor.position() - rewound);
+        cursor
+    }
 }
 
 impl StreamReader<ReadCounter> {
     pub fn counter<const MAX: usize>() -> Self { Self::new::<MAX>(ReadCounter::default()) }
 }
 
-#[derive(Clone, Debug, From)]
-pub struct StrictReader<R: ReadRaw>(R);
+/// Reader over an in-memory byte slice, which doesn't go through [`io::Read`]
+/// and keeps track of the current read position.
+///
+/// The reader is confined in the same way as [`StreamReader`]: an attempt to
+/// read beyond the limit fails with [`io::ErrorKind::InvalidInput`] error,
+/// while reading past the end of data fails with
+/// [`io::ErrorKind::UnexpectedEof`].
+#[derive(Clone, Debug)]
+pub struct SliceReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    limit: usize,
+    required: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new<const MAX: usize>(data: &'a [u8]) -> Self { Self::with(MAX, data) }
+
+    pub(crate) fn with(limit: usize, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            limit,
+            required: 0,
+        }
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// Data which are not consumed yet.
+    pub fn remaining(&self) -> &'a [u8] { &self.data[self.pos..] }
+
+    /// Length of the data which would be enough for the last read failed with
+    /// [`io::ErrorKind::UnexpectedEof`] error, or zero if there was no such
+    /// read.
+    #[cfg(feature = "async")]
+    pub(crate) fn required(&self) -> usize { self.required }
+
+    pub(crate) fn consume(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let available = len.min(self.data.len() - self.pos);
+        match self.pos.checked_add(available) {
+            None => return Err(io::ErrorKind::OutOfMemory.into()),
+            Some(end) if end > self.limit => return Err(io::ErrorKind::InvalidInput.into()),
+            Some(_) if available < len => {
+                self.required = self.pos.saturating_add(len);
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Some(_) => {}
+        }
+        let chunk = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(chunk)
+    }
+}
+
+impl ReadRaw for SliceReader<'_> {
+    fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.consume(len).map(<[u8]>::to_vec)
+    }
+
+    fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        let mut buf = [0u8; LEN];
+        buf.copy_from_slice(self.consume(LEN)?);
+        Ok(buf)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        buf.extend_from_slice(self.consume(len)?);
+        Ok(())
+    }
+
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        self.consume(len).map(Arc::from)
+    }
+
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> { self.consume(len).map(|_| ()) }
+
+    fn offset(&self) -> Option<usize> { Some(self.pos) }
+
+    fn peek_byte(&mut self) -> io::Result<u8> { self.clone().consume(1).map(|chunk| chunk[0]) }
+
+    /// Slice reader can be rewound up to the beginning of the data.
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        self.pos = self.pos.checked_sub(len).ok_or(io::ErrorKind::InvalidInput)?;
+        Ok(())
+    }
+
+    fn drain(&mut self) -> io::Result<usize> {
+        let len = self.data.len() - self.pos;
+        self.pos = self.data.len();
+        Ok(len)
+    }
+}
+
+/// Reader over a sequence of in-memory byte slices, like the buffers of a
+/// scatter/gather I/O, which decodes them as if they were a single contiguous
+/// slice, without copying them into one buffer.
+///
+/// Values may straddle the boundaries of the slices. The reader is confined and
+/// reports its position in the same way as [`SliceReader`], counting the bytes
+/// from the start of the first slice. `std::io::IoSlice` buffers are passed as
+/// `slices.iter().map(|slice| &**slice)`.
+#[derive(Clone, Debug)]
+pub struct ChainReader<'a> {
+    /// Non-empty slices of the data.
+    bufs: Vec<&'a [u8]>,
+    /// Index of the slice containing the next byte; equals to the number of
+    /// the slices when all the data are consumed.
+    index: usize,
+    /// Position of the next byte in the slice at `index`.
+    offset: usize,
+    pos: usize,
+    len: usize,
+    limit: usize,
+}
+
+impl<'a> ChainReader<'a> {
+    pub fn new<const MAX: usize>(bufs: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let bufs = bufs.into_iter().filter(|buf| !buf.is_empty()).collect::<Vec<_>>();
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        Self {
+            bufs,
+            index: 0,
+            offset: 0,
+            pos: 0,
+            len,
+            limit: MAX,
+        }
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// Number of bytes which are not consumed yet.
+    pub fn remaining_len(&self) -> usize { self.len - self.pos }
+
+    /// Checks that `len` bytes can be consumed, failing with the same errors as
+    /// [`SliceReader`] does.
+    fn check(&self, len: usize) -> io::Result<()> {
+        let available = len.min(self.len - self.pos);
+        match self.pos.checked_add(available) {
+            None => Err(io::ErrorKind::OutOfMemory.into()),
+            Some(end) if end > self.limit => Err(io::ErrorKind::InvalidInput.into()),
+            Some(_) if available < len => Err(io::ErrorKind::UnexpectedEof.into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Fills the buffer with the next bytes, which must be checked with
+    /// [`Self::check`] before.
+    fn copy_into(&mut self, buf: &mut [u8]) {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let chunk = &self.bufs[self.index][self.offset..];
+            let len = chunk.len().min(buf.len() - filled);
+            buf[filled..filled + len].copy_from_slice(&chunk[..len]);
+            filled += len;
+            self.offset += len;
+            if self.offset == self.bufs[self.index].len() {
+                self.index += 1;
+                self.offset = 0;
+            }
+        }
+        self.pos += buf.len();
+    }
+
+    /// Consumes the next `len` bytes, which must be checked with
+    /// [`Self::check`] before, without copying them.
+    fn advance(&mut self, len: usize) {
+        let mut left = len;
+        while left > 0 {
+            let step = left.min(self.bufs[self.index].len() - self.offset);
+            left -= step;
+            self.offset += step;
+            if self.offset == self.bufs[self.index].len() {
+                self.index += 1;
+                self.offset = 0;
+            }
+        }
+        self.pos += len;
+    }
+}
+
+impl ReadRaw for ChainReader<'_> {
+    fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.check(len)?;
+        let mut buf = vec![0u8; len];
+        self.copy_into(&mut buf);
+        Ok(buf)
+    }
+
+    fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        self.check(LEN)?;
+        let mut buf = [0u8; LEN];
+        self.copy_into(&mut buf);
+        Ok(buf)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        self.check(len)?;
+        let start = buf.len();
+        buf.resize(start + len, 0);
+        self.copy_into(&mut buf[start..]);
+        Ok(())
+    }
+
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        self.check(len)?;
+        let mut buf = zeroed_shared(len);
+        self.copy_into(Arc::get_mut(&mut buf).expect("new buffer is not shared"));
+        Ok(buf)
+    }
+
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> {
+        self.check(len)?;
+        self.advance(len);
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.pos) }
+
+    fn peek_byte(&mut self) -> io::Result<u8> {
+        let [byte] = self.read_raw_array::<1>()?;
+        self.rewind(1)?;
+        Ok(byte)
+    }
+
+    /// Chain reader can be rewound up to the beginning of the first slice.
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        self.pos = self.pos.checked_sub(len).ok_or(io::ErrorKind::InvalidInput)?;
+        let mut left = len;
+        while left > 0 {
+            if self.offset == 0 {
+                self.index -= 1;
+                self.offset = self.bufs[self.index].len();
+            }
+            let step = left.min(self.offset);
+            self.offset -= step;
+            left -= step;
+        }
+        Ok(())
+    }
+
+    fn drain(&mut self) -> io::Result<usize> {
+        let len = self.remaining_len();
+        self.index = self.bufs.len();
+        self.offset = 0;
+        self.pos = self.len;
+        Ok(len)
+    }
+}
+
+/// Reader decoding data from [`std::io::BufRead`], which takes byte arrays,
+/// byte strings and strings directly out of the buffer of the underlying
+/// reader, without copying them through the intermediate buffers used by
+/// [`StreamReader`]. Data straddling the end of the buffered data are read with
+/// [`std::io::Read::read_exact`] instead.
+///
+/// The reader is confined and counts the consumed bytes in the same way as
+/// [`StreamReader`]. It can't be [rewound](ReadRaw::rewind), but peeks the next
+/// byte out of the buffer.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct BufStreamReader<R: std::io::BufRead> {
+    reader: R,
+    count: usize,
+    limit: usize,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> BufStreamReader<R> {
+    pub fn new<const MAX: usize>(inner: R) -> Self {
+        Self {
+            reader: inner,
+            count: 0,
+            limit: MAX,
+        }
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Releases the underlying reader, positioned right after the consumed
+    /// data.
+    pub fn unconfine(self) -> R { self.reader }
+
+    fn check(&self, len: usize) -> io::Result<()> {
+        match self.count.checked_add(len) {
+            None => Err(io::ErrorKind::OutOfMemory.into()),
+            Some(end) if end > self.limit => Err(io::ErrorKind::InvalidInput.into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Consumes the next `len` bytes, passing them to `f`, if all of them are
+    /// in the buffer of the reader; returns `None` otherwise, consuming
+    /// nothing.
+    fn consume_buffered<T>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&[u8]) -> T,
+    ) -> io::Result<Option<T>> {
+        self.check(len)?;
+        let buf = self.reader.fill_buf()?;
+        if buf.len() < len {
+            return Ok(None);
+        }
+        let value = f(&buf[..len]);
+        self.reader.consume(len);
+        self.count += len;
+        Ok(Some(value))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.reader.read_exact(buf)?;
+        self.count += buf.len();
+        Ok(())
+    }
+
+    /// Since the data may not be present in the stream, the bytes are read in
+    /// chunks of at most [`PREALLOC_LIMIT`] bytes, as [`StreamReader`] does.
+    fn read_chunked(&mut self, len: usize, buf: &mut Vec<u8>) -> io::Result<()> {
+        let end = buf.len() + len;
+        while buf.len() < end {
+            let start = buf.len();
+            buf.resize(start + (end - start).min(PREALLOC_LIMIT), 0);
+            self.read_exact(&mut buf[start..])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> ReadRaw for BufStreamReader<R> {
+    fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        if let Some(buf) = self.consume_buffered(len, <[u8]>::to_vec)? {
+            return Ok(buf);
+        }
+        let mut buf = Vec::with_capacity(len.min(PREALLOC_LIMIT));
+        self.read_chunked(len, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        let mut buf = [0u8; LEN];
+        if self.consume_buffered(LEN, |data| buf.copy_from_slice(data))?.is_none() {
+            self.read_exact(&mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        if self.consume_buffered(len, |data| buf.extend_from_slice(data))?.is_none() {
+            self.read_chunked(len, buf)?;
+        }
+        Ok(())
+    }
+
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        if let Some(buf) = self.consume_buffered(len, |data| Arc::from(data))? {
+            return Ok(buf);
+        }
+        let mut buf = Vec::with_capacity(len.min(PREALLOC_LIMIT));
+        self.read_chunked(len, &mut buf)?;
+        Ok(Arc::from(buf))
+    }
+
+    /// Consumes the data right out of the buffer of the underlying reader.
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> {
+        self.check(len)?;
+        let mut left = len;
+        while left > 0 {
+            let available = match self.reader.fill_buf() {
+                Ok(buf) => buf.len().min(left),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if available == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.reader.consume(available);
+            self.count += available;
+            left -= available;
+        }
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.count) }
+
+    fn peek_byte(&mut self) -> io::Result<u8> {
+        self.check(1)?;
+        match self.reader.fill_buf()?.first() {
+            Some(byte) => Ok(*byte),
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    /// Reads the underlying reader up to its end or the first byte beyond the
+    /// confinement limit.
+    fn drain(&mut self) -> io::Result<usize> {
+        let cap = self.limit.saturating_sub(self.count).saturating_add(1);
+        let mut len = 0usize;
+        while len < cap {
+            let read = match self.reader.fill_buf() {
+                Ok(buf) => buf.len().min(cap - len),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if read == 0 {
+                break;
+            }
+            self.reader.consume(read);
+            len += read;
+        }
+        self.count = self.count.saturating_add(len);
+        Ok(len)
+    }
+}
+
+/// Default maximal depth of nesting of the values decoded by [`StrictReader`],
+/// which protects from stack overflows on decoding of maliciously nested
+/// data of recursive types.
+pub const RECURSION_DEPTH_LIMIT: usize = 128;
+
+/// Handling of invalid UTF-8 sequences in the decoded strings. The policy
+/// affects only decoding: the encoding of strings is always canonical.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Utf8Policy {
+    /// Strings with invalid UTF-8 sequences are rejected with
+    /// [`DecodeError::Utf8`], which reports the offset of the first invalid
+    /// sequence.
+    #[default]
+    Strict,
+
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD` replacement
+    /// character. Since the replacement character may be longer than the
+    /// sequence it replaces, the string may exceed the length limit of its
+    /// type and fail with [`DecodeError::Confinement`].
+    Lossy,
+}
+
+#[derive(Clone, Debug)]
+pub struct StrictReader<R: ReadRaw> {
+    reader: R,
+    utf8: Utf8Policy,
+    depth: usize,
+    depth_limit: usize,
+    allocated: usize,
+    alloc_limit: usize,
+    #[cfg(feature = "debug-context")]
+    trace: DecodeTrace,
+    #[cfg(feature = "std")]
+    observer: Option<Observer>,
+    interner: Option<Interner>,
+}
+
+/// Decoding of a value reported to the observer; without `std` feature there
+/// are no observers.
+#[cfg(not(feature = "std"))]
+enum Observation {}
+
+impl<R: ReadRaw> From<R> for StrictReader<R> {
+    fn from(reader: R) -> Self { Self::with(reader) }
+}
+
+/// Depth of the currently decoded value and the location of the first decoding
+/// failure.
+///
+/// The path to the failed value is collected while the values enclosing it
+/// complete, from the innermost one outwards, thus the decoding which doesn't
+/// fail doesn't allocate any memory for the path.
+#[cfg(feature = "debug-context")]
+#[derive(Clone, Debug, Default)]
+struct DecodeTrace {
+    depth: usize,
+    failure: Option<DecodeFailure>,
+}
+
+/// Location of the first decoding failure, with the path collected up to the
+/// value at `depth`, in reverse order.
+#[cfg(feature = "debug-context")]
+#[derive(Clone, Debug)]
+struct DecodeFailure {
+    offset: u64,
+    depth: usize,
+    rev_path: Vec<PathSegment>,
+}
 
 impl<T: AsRef<[u8]>> StrictReader<StreamReader<io::Cursor<T>>> {
     pub fn in_memory<const MAX: usize>(data: T) -> Self {
-        Self(StreamReader::in_memory::<MAX>(data))
+        Self::with(StreamReader::in_memory::<MAX>(data))
+    }
+    pub fn into_cursor(self) -> io::Cursor<T> { self.reader.into_cursor() }
+}
+
+impl<'a> StrictReader<SliceReader<'a>> {
+    pub fn from_slice<const MAX: usize>(data: &'a [u8]) -> Self {
+        Self::with(SliceReader::new::<MAX>(data))
+    }
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.reader.pos() }
+    /// Data which are not consumed yet.
+    pub fn remaining(&self) -> &'a [u8] { self.reader.remaining() }
+    /// Consumes `len` bytes, returning them without copying.
+    pub(crate) fn read_borrowed(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        self.reader.consume(len)
+    }
+}
+
+impl<'a> StrictReader<ChainReader<'a>> {
+    /// Constructs the reader decoding the slices as if they were concatenated.
+    pub fn from_chain<const MAX: usize>(bufs: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        Self::with(ChainReader::new::<MAX>(bufs))
     }
-    pub fn into_cursor(self) -> io::Cursor<T> { self.0.into_cursor() }
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.reader.pos() }
+}
+
+#[cfg(feature = "bytes")]
+impl StrictReader<BytesReader> {
+    pub fn from_bytes<const MAX: usize>(data: Bytes) -> Self {
+        Self::with(BytesReader::new::<MAX>(data))
+    }
+    /// Number of bytes consumed so far.
+    pub fn pos(&self) -> usize { self.reader.pos() }
+    /// Data which are not consumed yet.
+    pub fn remaining(&self) -> Bytes { self.reader.remaining() }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> StrictReader<BufStreamReader<R>> {
+    /// Constructs the reader taking the data out of the buffer of `reader`;
+    /// see [`BufStreamReader`].
+    pub fn from_buf_read<const MAX: usize>(reader: R) -> Self {
+        Self::with(BufStreamReader::new::<MAX>(reader))
+    }
+    /// Number of bytes consumed so far.
+    pub fn count(&self) -> usize { self.reader.count() }
 }
 
 impl StrictReader<StreamReader<ReadCounter>> {
-    pub fn counter<const MAX: usize>() -> Self { Self(StreamReader::counter::<MAX>()) }
+    pub fn counter<const MAX: usize>() -> Self { Self::with(StreamReader::counter::<MAX>()) }
+}
+
+impl<R: io::Read> StrictReader<StreamReader<R>> {
+    /// Number of bytes consumed so far.
+    pub fn count(&self) -> usize { self.reader.count() }
 }
 
 impl<R: ReadRaw> StrictReader<R> {
-    pub fn with(reader: R) -> Self { Self(reader) }
+    pub fn with(reader: R) -> Self {
+        Self {
+            reader,
+            utf8: default!(),
+            depth: 0,
+            depth_limit: RECURSION_DEPTH_LIMIT,
+            allocated: 0,
+            alloc_limit: usize::MAX,
+            #[cfg(feature = "debug-context")]
+            trace: default!(),
+            #[cfg(feature = "std")]
+            observer: None,
+            interner: None,
+        }
+    }
+
+    /// Installs the observer notified about the decoding of each value of a
+    /// structure, tuple, union or enum type; see [`CodecObserver`].
+    #[cfg(feature = "std")]
+    pub fn with_observer(mut self, observer: Arc<dyn CodecObserver>) -> Self {
+        self.observer = Some(observer.into());
+        self
+    }
+
+    /// Sets the policy of handling invalid UTF-8 sequences in the decoded
+    /// strings.
+    pub fn with_utf8_policy(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
 
-    pub fn unbox(self) -> R { self.0 }
+    /// Sets the maximal depth of nesting of the decoded values, which is
+    /// [`RECURSION_DEPTH_LIMIT`] by default; see [`TypedRead::in_depth`].
+    pub fn with_depth_limit(mut self, limit: usize) -> Self {
+        self.depth_limit = limit;
+        self
+    }
+
+    /// Sets the budget of memory, in bytes, which may be allocated for all the
+    /// collections and strings decoded by the reader; see
+    /// [`TypedRead::alloc`]. The budget is unlimited by default.
+    ///
+    /// The items of a collection are accounted by their size in memory, such
+    /// that nested collections are accounted both as the items of the outer
+    /// collection and by their own items.
+    pub fn with_alloc_limit(mut self, limit: usize) -> Self {
+        self.alloc_limit = limit;
+        self
+    }
+
+    /// Deduplicates the shared buffers decoded by the reader, such that the
+    /// [`ArcString`](crate::ArcString), [`ArcBlob`](crate::ArcBlob) and
+    /// [`ArcArray`](crate::ArcArray) values with the contents seen before
+    /// share the buffer decoded first instead of allocating a new one. Up to
+    /// `capacity` distinct contents are remembered; the data are still decoded
+    /// once the table is full, but aren't deduplicated anymore.
+    ///
+    /// Interning changes neither the decoded values nor the accepted data, and
+    /// the deduplicated buffers still count towards the allocation budget.
+    pub fn with_interner(mut self, capacity: usize) -> Self {
+        self.interner = Some(Interner::with_capacity(capacity));
+        self
+    }
+
+    /// Enters a nested value, failing if the depth limit is reached. Must be
+    /// paired with [`Self::leave`].
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        if self.depth >= self.depth_limit {
+            return Err(DecodeError::MaxRecursionDepth {
+                limit: self.depth_limit,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self) { self.depth -= 1; }
+
+    /// Starts reporting the decoding of a value of type `T` to the observer,
+    /// if there is one. Must be paired with [`Self::observed`].
+    #[allow(clippy::extra_unused_type_parameters)]
+    fn observation<T: StrictType>(&self) -> Option<Observation> {
+        #[cfg(feature = "std")]
+        {
+            self.observer
+                .as_ref()
+                .map(|observer| Observation::decode(observer, decoded_name::<T>(), &self.reader))
+        }
+        #[cfg(not(feature = "std"))]
+        None
+    }
+
+    /// Reports the result of the decoding to the observer.
+    fn observed<T>(
+        &self,
+        observation: Option<Observation>,
+        res: Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        #[cfg(feature = "std")]
+        if let Some(observation) = observation {
+            observation.decoded(res.as_ref().map(|_| self.reader.offset()));
+        }
+        #[cfg(not(feature = "std"))]
+        if let Some(observation) = observation {
+            match observation {}
+        }
+        res
+    }
+
+    /// Decodes a value of type `T`, reporting it to the observer.
+    fn observe<T: StrictType, V>(
+        &mut self,
+        decode: impl FnOnce(&mut Self) -> Result<V, DecodeError>,
+    ) -> Result<V, DecodeError> {
+        let observation = self.observation::<T>();
+        let res = decode(self);
+        self.observed(observation, res)
+    }
+
+    pub fn unbox(self) -> R { self.reader }
+
+    /// Completes decoding of a sequence of values, checking that the data are
+    /// entirely consumed; fails with [`DeserializeError::DataNotEntirelyConsumed`]
+    /// reporting the number of the bytes left otherwise.
+    ///
+    /// The same reader decodes any number of values going one after another
+    /// with their [`StrictDecode::strict_decode`], which share the confinement
+    /// limit, the allocation budget and the byte count of the reader. Streams
+    /// are read no further than the first byte beyond the limit; see
+    /// [`ReadRaw::drain`].
+    pub fn finish(mut self) -> Result<(), DeserializeError> {
+        match self.reader.drain()? {
+            0 => Ok(()),
+            left => Err(DeserializeError::DataNotEntirelyConsumed(left as u64)),
+        }
+    }
+
+    /// Detects whether all the data are consumed, without consuming anything.
+    fn is_eof(&mut self) -> io::Result<bool> {
+        match self.reader.peek_byte() {
+            Ok(_) => Ok(false),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Decodes a value, reporting a failure together with the location in the
+    /// data where it has happened.
+    ///
+    /// With `debug-context` feature the reader keeps track of the path to the
+    /// decoded value, and the error contains the full path to the innermost
+    /// field which has failed to decode, with the byte offset of that field.
+    /// Otherwise, the path contains only the name of the decoded type and the
+    /// offset is the position at which the reading has stopped.
+    pub fn decode_with_context<T: StrictDecode>(&mut self) -> Result<T, ContextError> {
+        let root = || PathSegment::Type(decoded_name::<T>());
+
+        #[cfg(feature = "debug-context")]
+        {
+            self.trace = default!();
+            self.in_context(root, |r| T::strict_decode(r)).map_err(|error| {
+                let (offset, path) = self
+                    .trace
+                    .failure
+                    .take()
+                    .map(|failure| (failure.offset, failure.rev_path.into_iter().rev().collect()))
+                    .unwrap_or_default();
+                ContextError {
+                    error,
+                    offset,
+                    path,
+                }
+            })
+        }
+
+        #[cfg(not(feature = "debug-context"))]
+        {
+            T::strict_decode(self).map_err(|error| ContextError {
+                error,
+                offset: self.reader.offset().unwrap_or_default() as u64,
+                path: vec![root()],
+            })
+        }
+    }
 }
 
 impl<R: ReadRaw> TypedRead for StrictReader<R> {
@@ -151,24 +1098,114 @@ impl<R: ReadRaw> TypedRead for StrictReader<R> {
     type UnionReader = Self;
     type RawReader = R;
 
-    unsafe fn raw_reader(&mut self) -> &mut Self::RawReader { &mut self.0 }
+    unsafe fn raw_reader(&mut self) -> &mut Self::RawReader { &mut self.reader }
+
+    fn utf8_policy(&self) -> Utf8Policy { self.utf8 }
+
+    fn in_depth<T>(
+        &mut self,
+        decode: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        self.enter()?;
+        let res = decode(self);
+        self.leave();
+        res
+    }
+
+    fn alloc(&mut self, len: usize) -> Result<(), DecodeError> {
+        let attempted = self.allocated.saturating_add(len);
+        if attempted > self.alloc_limit {
+            return Err(DecodeError::AllocationBudgetExceeded {
+                budget: self.alloc_limit,
+                attempted,
+            });
+        }
+        self.allocated = attempted;
+        Ok(())
+    }
+
+    unsafe fn read_shared<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+    ) -> Result<Arc<[u8]>, DecodeError> {
+        let len = self.read_len::<T, MIN_LEN, MAX_LEN>()?;
+        self.alloc(len)?;
+        match &mut self.interner {
+            Some(interner) => interner.read::<R, MAX_LEN>(&mut self.reader, len),
+            None => self.reader.read_raw_shared::<MAX_LEN>(len),
+        }
+        .map_err(DecodeError::from)
+    }
+
+    unsafe fn read_shared_array<const LEN: usize>(
+        &mut self,
+    ) -> Result<Arc<[u8; LEN]>, DecodeError> {
+        self.alloc(LEN)?;
+        match &mut self.interner {
+            Some(interner) => interner.read_array::<R, LEN>(&mut self.reader),
+            None => self.reader.read_raw_array::<LEN>().map(Arc::new),
+        }
+        .map_err(DecodeError::from)
+    }
+
+    #[cfg(feature = "debug-context")]
+    fn in_context<T>(
+        &mut self,
+        segment: impl FnOnce() -> PathSegment,
+        decode: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        let offset = self.reader.offset().unwrap_or_default() as u64;
+        self.trace.depth += 1;
+        let depth = self.trace.depth;
+        let res = decode(self);
+        self.trace.depth -= 1;
+        match &mut self.trace.failure {
+            None if res.is_err() => {
+                self.trace.failure = Some(DecodeFailure {
+                    offset,
+                    depth,
+                    rev_path: vec![segment()],
+                })
+            }
+            // The value encloses the failed one, even if it has recovered from
+            // the failure
+            Some(failure) if failure.depth == depth + 1 => {
+                failure.rev_path.push(segment());
+                failure.depth = depth;
+            }
+            _ => {}
+        }
+        res
+    }
 
     fn read_union<T: StrictUnion>(
         &mut self,
         inner: impl FnOnce(VariantName, &mut Self::UnionReader) -> Result<T, DecodeError>,
     ) -> Result<T, DecodeError> {
-        let name = T::strict_name().unwrap_or_else(|| tn!("__unnamed"));
-        let tag = u8::strict_decode(self)?;
-        let variant_name = T::variant_name_by_tag(tag)
-            .ok_or(DecodeError::UnionTagNotKnown(name.to_string(), tag))?;
-        inner(variant_name, self)
+        self.observe::<T, _>(|reader| {
+            let tag = u8::strict_decode(reader)?;
+            let variant_name =
+                T::variant_name_by_tag(tag).ok_or_else(|| DecodeError::UnionTagNotKnown {
+                    type_name: decoded_name::<T>(),
+                    tag,
+                    variants: KnownVariants::of::<T>(),
+                })?;
+            let segment = || {
+                PathSegment::Variant(T::variant_name_by_tag(tag).expect("variant is already known"))
+            };
+            reader.in_depth(|r| r.in_context(segment, |r| inner(variant_name, r)))
+        })
     }
 
     fn read_enum<T: StrictEnum>(&mut self) -> Result<T, DecodeError>
     where u8: From<T> {
-        let name = T::strict_name().unwrap_or_else(|| tn!("__unnamed"));
-        let tag = u8::strict_decode(self)?;
-        T::try_from(tag).map_err(|_| DecodeError::EnumTagNotKnown(name.to_string(), tag))
+        self.observe::<T, _>(|reader| {
+            let tag = u8::strict_decode(reader)?;
+            T::try_from(tag).map_err(|_| DecodeError::EnumTagNotKnown {
+                type_name: decoded_name::<T>(),
+                tag,
+                variants: KnownVariants::of::<T>(),
+            })
+        })
     }
 
     fn read_tuple<'parent, 'me, T: StrictTuple>(
@@ -179,20 +1216,18 @@ impl<R: ReadRaw> TypedRead for StrictReader<R> {
         Self: 'parent,
         'me: 'parent,
     {
-        let name = T::strict_name().unwrap_or_else(|| tn!("__unnamed"));
+        let observation = self.observation::<T>();
+        if let Err(err) = self.enter() {
+            return self.observed(observation, Err(err));
+        }
         let mut reader = TupleReader {
             read_fields: 0,
             parent: self,
         };
-        let res = inner(&mut reader)?;
-        assert_ne!(reader.read_fields, 0, "you forget to read fields for a tuple {}", name);
-        assert_eq!(
-            reader.read_fields,
-            T::FIELD_COUNT,
-            "the number of fields read for a tuple {} doesn't match type declaration",
-            name
-        );
-        Ok(res)
+        let res = inner(&mut reader);
+        reader.parent.leave();
+        let res = res.and_then(|res| reader.check_fields::<T>(T::FIELD_COUNT).map(|_| res));
+        reader.parent.observed(observation, res)
     }
 
     fn read_struct<'parent, 'me, T: StrictStruct>(
@@ -203,24 +1238,18 @@ impl<R: ReadRaw> TypedRead for StrictReader<R> {
         Self: 'parent,
         'me: 'parent,
     {
-        let name = T::strict_name().unwrap_or_else(|| tn!("__unnamed"));
+        let observation = self.observation::<T>();
+        if let Err(err) = self.enter() {
+            return self.observed(observation, Err(err));
+        }
         let mut reader = StructReader {
             named_fields: empty!(),
             parent: self,
         };
-        let res = inner(&mut reader)?;
-        assert!(!reader.named_fields.is_empty(), "you forget to read fields for a tuple {}", name);
-
-        for field in T::ALL_FIELDS {
-            let pos = reader
-                .named_fields
-                .iter()
-                .position(|f| f.as_str() == *field)
-                .unwrap_or_else(|| panic!("field {} is not read for {}", field, name));
-            reader.named_fields.remove(pos);
-        }
-        assert!(reader.named_fields.is_empty(), "excessive fields are read for {}", name);
-        Ok(res)
+        let res = inner(&mut reader);
+        reader.parent.leave();
+        let res = res.and_then(|res| reader.check_fields::<T>(T::ALL_FIELDS).map(|_| res));
+        reader.parent.observed(observation, res)
     }
 }
 
@@ -230,10 +1259,34 @@ pub struct TupleReader<'parent, R: ReadRaw> {
     parent: &'parent mut StrictReader<R>,
 }
 
+impl<'parent, R: ReadRaw> TupleReader<'parent, R> {
+    /// Checks that exactly `count` fields of the tuple (or of the tuple variant
+    /// of union `T`) are read.
+    fn check_fields<T: StrictType>(&self, count: u8) -> Result<(), DecodeError> {
+        if self.read_fields != count {
+            return Err(DecodeError::FieldsNotRead(
+                decoded_name::<T>(),
+                self.read_fields as usize,
+                count as usize,
+            ));
+        }
+        Ok(())
+    }
+}
+
 impl<'parent, R: ReadRaw> ReadTuple for TupleReader<'parent, R> {
     fn read_field<T: StrictDecode>(&mut self) -> Result<T, DecodeError> {
+        let index = self.read_fields;
         self.read_fields += 1;
-        T::strict_decode(self.parent)
+        self.parent.in_context(|| PathSegment::Index(index), |r| T::strict_decode(r))
+    }
+
+    fn read_optional_field<T: StrictDecode + StrictDumb>(&mut self) -> Result<T, DecodeError> {
+        if self.parent.is_eof()? {
+            self.read_fields += 1;
+            return Ok(T::strict_dumb());
+        }
+        self.read_field()
     }
 }
 
@@ -243,10 +1296,45 @@ pub struct StructReader<'parent, R: ReadRaw> {
     parent: &'parent mut StrictReader<R>,
 }
 
+impl<'parent, R: ReadRaw> StructReader<'parent, R> {
+    /// Checks that exactly the `all_fields` of the struct (or of the struct
+    /// variant of union `T`) are read.
+    fn check_fields<T: StrictType>(&mut self, all_fields: &[&str]) -> Result<(), DecodeError> {
+        let named_fields = &mut self.named_fields;
+        let read_fields = named_fields.len();
+        let declared_read = all_fields
+            .iter()
+            .filter(|field| {
+                let pos = named_fields.iter().position(|f| f.as_str() == **field);
+                pos.map(|pos| named_fields.remove(pos)).is_some()
+            })
+            .count();
+        if declared_read < all_fields.len() || !named_fields.is_empty() {
+            // excessive fields are reported only if all declared fields are read
+            let read = if declared_read < all_fields.len() { declared_read } else { read_fields };
+            return Err(DecodeError::FieldsNotRead(decoded_name::<T>(), read, all_fields.len()));
+        }
+        Ok(())
+    }
+}
+
 impl<'parent, R: ReadRaw> ReadStruct for StructReader<'parent, R> {
     fn read_field<T: StrictDecode>(&mut self, field: FieldName) -> Result<T, DecodeError> {
+        let segment = || PathSegment::Field(field.clone());
+        let res = self.parent.in_context(segment, |r| T::strict_decode(r));
         self.named_fields.push(field);
-        T::strict_decode(self.parent)
+        res
+    }
+
+    fn read_optional_field<T: StrictDecode + StrictDumb>(
+        &mut self,
+        field: FieldName,
+    ) -> Result<T, DecodeError> {
+        if self.parent.is_eof()? {
+            self.named_fields.push(field);
+            return Ok(T::strict_dumb());
+        }
+        self.read_field(field)
     }
 }
 
@@ -283,4 +1371,773 @@ impl<R: ReadRaw> ReadUnion for StrictReader<R> {
         };
         inner(&mut reader)
     }
+
+    fn read_tuple_checked<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        field_count: u8,
+        inner: impl FnOnce(&mut Self::TupleReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        let mut reader = TupleReader {
+            read_fields: 0,
+            parent: self,
+        };
+        let res = inner(&mut reader)?;
+        reader.check_fields::<T>(field_count)?;
+        Ok(res)
+    }
+
+    fn read_struct_checked<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        all_fields: &'static [&'static str],
+        inner: impl FnOnce(&mut Self::StructReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        let mut reader = StructReader {
+            named_fields: empty!(),
+            parent: self,
+        };
+        let res = inner(&mut reader)?;
+        reader.check_fields::<T>(all_fields)?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slice_reader() {
+        let data = [1u8, 2, 3, 4, 5];
+        let mut reader = StrictReader::from_slice::<5>(&data);
+        assert_eq!(u16::strict_decode(&mut reader).unwrap(), 0x0201);
+        assert_eq!(reader.pos(), 2);
+        assert_eq!(reader.remaining(), &[3, 4, 5]);
+        assert_eq!(<[u8; 3]>::strict_decode(&mut reader).unwrap(), [3, 4, 5]);
+        assert_eq!(reader.pos(), 5);
+        assert!(reader.remaining().is_empty());
+        assert_eq!(reader.unbox().pos(), 5);
+    }
+
+    #[test]
+    fn slice_reader_errors() {
+        fn decode_u32<R: ReadRaw>(mut reader: StrictReader<R>) -> io::ErrorKind {
+            match u32::strict_decode(&mut reader).unwrap_err() {
+                DecodeError::Io(err) => err.kind(),
+                err => panic!("unexpected error {err}"),
+            }
+        }
+
+        // Truncated data
+        let data = [1u8, 2, 3];
+        let slice = decode_u32(StrictReader::from_slice::<16>(&data));
+        assert_eq!(slice, io::ErrorKind::UnexpectedEof);
+        assert_eq!(slice, decode_u32(StrictReader::in_memory::<16>(data)));
+
+        // Confinement limit exceeded
+        let data = [1u8, 2, 3, 4];
+        let slice = decode_u32(StrictReader::from_slice::<3>(&data));
+        assert_eq!(slice, io::ErrorKind::InvalidInput);
+        assert_eq!(slice, decode_u32(StrictReader::in_memory::<3>(data)));
+
+        // Both truncated and confined
+        let data = [1u8, 2];
+        let slice = decode_u32(StrictReader::from_slice::<1>(&data));
+        assert_eq!(slice, io::ErrorKind::InvalidInput);
+        assert_eq!(slice, decode_u32(StrictReader::in_memory::<1>(data)));
+    }
+
+    #[test]
+    fn sequential_values() {
+        use amplify::confinement::TinyString;
+
+        let header = (7u16, 0xFFu8);
+        let body = TinyString::try_from(s!("body")).unwrap();
+        let mut data = crate::test::encode(&header);
+        data.extend(crate::test::encode(&body));
+        let len = data.len();
+
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        assert_eq!(<(u16, u8)>::strict_decode(&mut reader).unwrap(), header);
+        assert_eq!(reader.pos(), 3);
+        assert_eq!(TinyString::strict_decode(&mut reader).unwrap(), body);
+        assert_eq!(reader.pos(), len);
+        reader.finish().unwrap();
+
+        let mut reader = StrictReader::with(StreamReader::new::<1024>(Chunked(&data)));
+        assert_eq!(<(u16, u8)>::strict_decode(&mut reader).unwrap(), header);
+        assert_eq!(TinyString::strict_decode(&mut reader).unwrap(), body);
+        assert_eq!(reader.count(), len);
+        reader.finish().unwrap();
+
+        // Bytes of the third value are left
+        data.extend(crate::test::encode(&0xDEADBEEFu32));
+        let left = Err(DeserializeError::DataNotEntirelyConsumed(4));
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        <(u16, u8)>::strict_decode(&mut reader).unwrap();
+        TinyString::strict_decode(&mut reader).unwrap();
+        assert_eq!(reader.finish(), left);
+        let mut reader = StrictReader::with(StreamReader::new::<1024>(Chunked(&data)));
+        assert_eq!(u16::strict_decode(&mut reader).unwrap(), 7);
+        assert_eq!(reader.peek_byte().unwrap(), 0xFF);
+        u8::strict_decode(&mut reader).unwrap();
+        TinyString::strict_decode(&mut reader).unwrap();
+        assert_eq!(reader.finish(), left);
+        let mut reader = StrictReader::from_chain::<1024>([&data[..5], &data[5..]]);
+        <(u16, u8)>::strict_decode(&mut reader).unwrap();
+        assert_eq!(reader.finish(), Err(DeserializeError::DataNotEntirelyConsumed(9)));
+
+        // The limit is shared by the values: each of them fits it alone, but
+        // not both together
+        fn second<R: ReadRaw>(mut reader: StrictReader<R>) -> Result<TinyString, DecodeError> {
+            <(u16, u8)>::strict_decode(&mut reader)?;
+            TinyString::strict_decode(&mut reader)
+        }
+        let limit = |res: Result<TinyString, DecodeError>| {
+            let Err(DecodeError::Io(err)) = res else {
+                panic!("confinement must be enforced")
+            };
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        };
+        limit(second(StrictReader::from_slice::<6>(&data)));
+        limit(second(StrictReader::with(StreamReader::new::<6>(Chunked(&data)))));
+        assert!(TinyString::strict_decode(&mut StrictReader::from_slice::<6>(&data[3..])).is_ok());
+
+        // Endless streams are not read beyond the limit
+        #[cfg(feature = "std")]
+        {
+            use std::io::{repeat, BufReader};
+
+            let left = Err(DeserializeError::DataNotEntirelyConsumed(1023));
+            let mut reader = StrictReader::with(StreamReader::new::<1024>(repeat(0xFF)));
+            u16::strict_decode(&mut reader).unwrap();
+            assert_eq!(reader.finish(), left);
+            let mut reader =
+                StrictReader::with(BufStreamReader::new::<1024>(BufReader::new(repeat(0xFF))));
+            u16::strict_decode(&mut reader).unwrap();
+            assert_eq!(reader.finish(), left);
+        }
+    }
+
+    /// Reader returning at most three bytes per each read call.
+    struct Chunked<'a>(&'a [u8]);
+
+    impl io::Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn peek_union_tag() {
+        use fixture::Seal;
+
+        // Legacy encoding of the UTXO seal used `0xFF` tag followed by an u64
+        fn bridge_decode(reader: &mut impl TypedRead) -> Result<Seal, DecodeError> {
+            if reader.peek_byte()? == 0xFF {
+                let _ = u8::strict_decode(reader)?;
+                return Ok(Seal::Utxo(u64::strict_decode(reader)? as u32));
+            }
+            Seal::strict_decode(reader)
+        }
+
+        let data = [1u8, 5, 0xFF, 7, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0];
+        let mut reader = StrictReader::from_slice::<16>(&data);
+        assert_eq!(reader.peek_byte().unwrap(), 1);
+        assert_eq!(reader.pos(), 0);
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Address(5));
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Utxo(7));
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Utxo(3));
+
+        let mut reader = StrictReader::with(StreamReader::new::<16>(Chunked(&data)));
+        assert_eq!(reader.peek_byte().unwrap(), 1);
+        assert_eq!(reader.count(), 0);
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Address(5));
+        assert_eq!(reader.peek_byte().unwrap(), 0xFF);
+        assert_eq!(reader.count(), 2);
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Utxo(7));
+        assert_eq!(bridge_decode(&mut reader).unwrap(), Seal::Utxo(3));
+        let err = reader.peek_byte().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn stream_rewind() {
+        let data = (0..=255u8).cycle().take(400).collect::<Vec<_>>();
+        let mut reader = StreamReader::new::<400>(Chunked(&data));
+        assert_eq!(reader.read_raw_array::<5>().unwrap(), [0, 1, 2, 3, 4]);
+        assert_eq!(reader.read_raw_array::<5>().unwrap(), [5, 6, 7, 8, 9]);
+        // rewinding across the data returned by different reads of the
+        // underlying reader
+        reader.rewind(7).unwrap();
+        assert_eq!(reader.count(), 3);
+        assert_eq!(reader.read_raw_array::<2>().unwrap(), [3, 4]);
+        assert_eq!(reader.read_raw::<10>(10).unwrap(), data[5..15]);
+        assert_eq!(reader.count(), 15);
+
+        assert_eq!(reader.read_raw::<100>(100).unwrap(), data[15..115]);
+        let max = StreamReader::<Chunked>::MAX_REWIND;
+        let err = reader.rewind(max + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        reader.rewind(max - 10).unwrap();
+        reader.rewind(10).unwrap();
+        assert_eq!(reader.rewind(1).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.count(), 115 - max);
+        assert_eq!(reader.read_raw::<300>(300).unwrap(), data[115 - max..415 - max]);
+    }
+
+    #[test]
+    fn rewind_confinement() {
+        let data = [1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+
+        let mut reader = StreamReader::cursor::<8>(data);
+        assert_eq!(reader.read_raw::<8>(8).unwrap(), data[..8]);
+        reader.rewind(4).unwrap();
+        assert_eq!(reader.count(), 4);
+        assert_eq!(reader.offset(), Some(4));
+        assert_eq!(reader.read_raw_array::<2>().unwrap(), [5, 6]);
+        assert_eq!(reader.into_cursor().position(), 6);
+
+        let mut reader = StreamReader::cursor::<8>(data);
+        assert_eq!(reader.read_raw::<8>(8).unwrap(), data[..8]);
+        reader.rewind(4).unwrap();
+        // rewound bytes are not counted twice towards the limit
+        assert_eq!(reader.read_raw::<4>(4).unwrap(), data[4..8]);
+        assert_eq!(reader.count(), 8);
+        let err = reader.read_raw_array::<1>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut reader = SliceReader::new::<8>(&data);
+        assert_eq!(reader.read_raw::<8>(8).unwrap(), data[..8]);
+        reader.rewind(8).unwrap();
+        assert_eq!(reader.rewind(1).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.pos(), 0);
+        assert_eq!(reader.read_raw::<8>(8).unwrap(), data[..8]);
+        let err = reader.read_raw_array::<1>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn fields_not_read() {
+        use crate::{
+            ReadStruct, ReadTuple, ReadUnion, StrictProduct, StrictStruct, StrictSum, StrictTuple,
+            StrictUnion, TypeName, LIB_EMBEDDED,
+        };
+
+        #[derive(Clone, Eq, PartialEq, Debug, Default)]
+        struct Short(u8, u16);
+        impl StrictType for Short {
+            const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> { Some(tn!("Short")) }
+        }
+        impl StrictProduct for Short {}
+        impl StrictTuple for Short {
+            const FIELD_COUNT: u8 = 3;
+        }
+        impl StrictDecode for Short {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_tuple(|r| Ok(Short(r.read_field()?, r.read_field()?)))
+            }
+        }
+
+        #[derive(Clone, Eq, PartialEq, Debug, Default)]
+        struct Pair {
+            a: u8,
+            b: u16,
+        }
+        impl StrictType for Pair {
+            const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> { Some(tn!("Pair")) }
+        }
+        impl StrictProduct for Pair {}
+        impl StrictStruct for Pair {
+            const ALL_FIELDS: &'static [&'static str] = &["a", "b"];
+        }
+        impl StrictDecode for Pair {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_struct(|r| {
+                    let a = r.read_field(fname!("a"))?;
+                    Ok(Pair { a, b: 0 })
+                })
+            }
+        }
+
+        #[derive(Clone, Eq, PartialEq, Debug, Default)]
+        struct Misnamed(Pair);
+        impl StrictType for Misnamed {
+            const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> { Some(tn!("Misnamed")) }
+        }
+        impl StrictProduct for Misnamed {}
+        impl StrictStruct for Misnamed {
+            const ALL_FIELDS: &'static [&'static str] = &["a", "b"];
+        }
+        impl StrictDecode for Misnamed {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_struct(|r| {
+                    let a = r.read_field(fname!("a"))?;
+                    let b = r.read_field(fname!("c"))?;
+                    Ok(Misnamed(Pair { a, b }))
+                })
+            }
+        }
+
+        #[derive(Clone, Eq, PartialEq, Debug)]
+        enum Shape {
+            Line(u8, u8),
+            Rect { w: u8, h: u8 },
+        }
+        impl StrictDumb for Shape {
+            fn strict_dumb() -> Self { Shape::Line(0, 0) }
+        }
+        impl StrictType for Shape {
+            const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+            fn strict_name() -> Option<TypeName> { Some(tn!("Shape")) }
+        }
+        impl StrictSum for Shape {
+            const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(0, "line"), (1, "rect")];
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    Shape::Line(..) => "line",
+                    Shape::Rect { .. } => "rect",
+                }
+            }
+        }
+        impl StrictUnion for Shape {}
+        impl StrictDecode for Shape {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_union(|name, u| match name.as_str() {
+                    "line" => u.read_tuple_checked(2, |r| Ok(Shape::Line(r.read_field()?, 0))),
+                    "rect" => u.read_struct_checked(&["w", "h"], |r| {
+                        let w = r.read_field(fname!("w"))?;
+                        Ok(Shape::Rect { w, h: 0 })
+                    }),
+                    _ => unreachable!(),
+                })
+            }
+        }
+
+        let data = [1u8, 2, 3, 4, 5, 6];
+        let err = Shape::strict_decode(&mut StrictReader::from_slice::<8>(&data[..3])).unwrap_err();
+        assert_eq!(err, DecodeError::FieldsNotRead(s!("Shape"), 1, 2));
+        let err = Shape::strict_decode(&mut StrictReader::from_slice::<8>(&[0, 1, 2])).unwrap_err();
+        assert_eq!(err, DecodeError::FieldsNotRead(s!("Shape"), 1, 2));
+
+        let err = Short::strict_decode(&mut StrictReader::from_slice::<8>(&data)).unwrap_err();
+        assert_eq!(err, DecodeError::FieldsNotRead(s!("Short"), 2, 3));
+        assert!(err.to_string().starts_with("type `Short`: 2 of 3 fields read."));
+
+        let err = Pair::strict_decode(&mut StrictReader::from_slice::<8>(&data)).unwrap_err();
+        assert_eq!(err, DecodeError::FieldsNotRead(s!("Pair"), 1, 2));
+
+        let err = Misnamed::strict_decode(&mut StrictReader::from_slice::<8>(&data)).unwrap_err();
+        assert_eq!(err, DecodeError::FieldsNotRead(s!("Misnamed"), 1, 2));
+    }
+
+    mod fixture {
+        use amplify::confinement::{TinyBlob, TinyString, TinyVec};
+
+        use crate::{StrictDecode, StrictDumb, StrictEncode, StrictType};
+
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = custom, crate = crate)]
+        #[strict_type(dumb = Seal::Utxo(strict_dumb!()))]
+        pub enum Seal {
+            #[strict_type(tag = 0)]
+            Utxo(u32),
+            #[strict_type(tag = 1)]
+            Address(u8),
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Output {
+            pub value: u64,
+            pub seal: Seal,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Transfer {
+            pub id: u32,
+            pub outputs: TinyVec<Output>,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        pub enum Expr {
+            #[default]
+            Leaf,
+            Neg(Box<Expr>),
+            Maybe(Option<Box<Expr>>),
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Node {
+            pub children: TinyVec<Node>,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Record {
+            pub id: u16,
+            pub hash: [u8; 16],
+            pub name: TinyString,
+            pub blob: TinyBlob,
+            pub flag: Option<u8>,
+        }
+    }
+
+    /// Encoded transfer with five outputs, where the seal of the fourth output
+    /// has an unknown tag, returned together with the offset of that seal.
+    fn corrupted_transfer() -> (Vec<u8>, u64) {
+        use amplify::confinement::TinyVec;
+        use fixture::*;
+
+        let outputs = (0..5u8).map(|no| Output {
+            value: no as u64 * 1000,
+            seal: Seal::Utxo(no as u32),
+        });
+        let transfer = Transfer {
+            id: 0xDEAD,
+            outputs: TinyVec::try_from_iter(outputs).unwrap(),
+        };
+        let mut data = crate::test::encode(&transfer);
+        // id (4 bytes) + length (1 byte) + 3 outputs (13 bytes each) + value (8 bytes)
+        let seal_offset = 4 + 1 + 3 * 13 + 8;
+        assert_eq!(data[seal_offset], 0);
+        data[seal_offset] = 7;
+        (data, seal_offset as u64)
+    }
+
+    #[test]
+    fn decode_context() {
+        let (data, seal_offset) = corrupted_transfer();
+        let mut reader = StrictReader::from_slice::<{ u16::MAX as usize }>(&data);
+        let err = reader.decode_with_context::<fixture::Transfer>().unwrap_err();
+        assert_eq!(err.error, DecodeError::UnionTagNotKnown {
+            type_name: s!("Seal"),
+            tag: 7,
+            variants: KnownVariants(vec![(0, vname!("utxo")), (1, vname!("address"))]),
+        });
+
+        #[cfg(feature = "debug-context")]
+        {
+            assert_eq!(err.offset, seal_offset);
+            assert_eq!(err.path, vec![
+                PathSegment::Type(s!("Transfer")),
+                PathSegment::Field(fname!("outputs")),
+                PathSegment::Item(3),
+                PathSegment::Field(fname!("seal")),
+            ]);
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Transfer.outputs[3].seal at byte {seal_offset}: unexpected variant 7 for \
+                     union `Seal`; known variants: utxo=0, address=1"
+                )
+            );
+        }
+
+        #[cfg(not(feature = "debug-context"))]
+        {
+            // only the union tag is consumed
+            assert_eq!(err.offset, seal_offset + 1);
+            assert_eq!(err.path, vec![PathSegment::Type(s!("Transfer"))]);
+        }
+    }
+
+    #[test]
+    fn chain_splits() {
+        use fixture::Transfer;
+
+        let (mut data, seal_offset) = corrupted_transfer();
+        data[seal_offset as usize] = 0;
+        let mut contiguous = StrictReader::from_slice::<1024>(&data);
+        let transfer = Transfer::strict_decode(&mut contiguous).unwrap();
+
+        let len = data.len();
+        for first in 0..=len {
+            for second in first..=len {
+                let bufs = [&data[..first], &data[first..second], &data[second..]];
+                let mut reader = StrictReader::from_chain::<1024>(bufs);
+                assert_eq!(Transfer::strict_decode(&mut reader).unwrap(), transfer);
+                assert_eq!(reader.pos(), len);
+                assert_eq!(reader.unbox().remaining_len(), 0);
+
+                // the limit applies to the whole chain
+                let mut reader = StrictReader::from_chain::<1024>(bufs);
+                assert_eq!(reader.decode_with_context::<Transfer>().unwrap(), transfer);
+                let err = Transfer::strict_decode(&mut StrictReader::from_chain::<60>(bufs));
+                let Err(DecodeError::Io(err)) = err else {
+                    panic!("confinement must be enforced")
+                };
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            }
+        }
+    }
+
+    #[test]
+    fn chain_errors() {
+        let (data, seal_offset) = corrupted_transfer();
+        for split in 0..=data.len() {
+            let mut reader = StrictReader::from_chain::<1024>([&data[..split], &data[split..]]);
+            let err = reader.decode_with_context::<fixture::Transfer>().unwrap_err();
+            let mut contiguous = StrictReader::from_slice::<1024>(&data);
+            assert_eq!(err, contiguous.decode_with_context::<fixture::Transfer>().unwrap_err());
+            assert_eq!(reader.pos(), contiguous.pos());
+            assert!(err.offset >= seal_offset);
+        }
+
+        let truncated = &data[..40];
+        for split in 0..=truncated.len() {
+            let bufs = [&truncated[..split], &truncated[split..]];
+            let mut reader = StrictReader::from_chain::<1024>(bufs);
+            let Err(DecodeError::Io(err)) = fixture::Transfer::strict_decode(&mut reader) else {
+                panic!("truncated data must result in I/O error")
+            };
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn chain_rewind() {
+        let data = (0..20u8).collect::<Vec<_>>();
+        let bufs = [&data[..3], &[][..], &data[3..4], &data[4..12], &data[12..]];
+        let mut reader = ChainReader::new::<20>(bufs);
+        assert_eq!(reader.read_raw_array::<5>().unwrap(), [0, 1, 2, 3, 4]);
+        reader.rewind(4).unwrap();
+        assert_eq!(reader.pos(), 1);
+        assert_eq!(reader.read_raw::<12>(12).unwrap(), data[1..13]);
+        assert_eq!(reader.peek_byte().unwrap(), 13);
+        reader.rewind(13).unwrap();
+        assert_eq!(reader.rewind(1).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.read_raw::<20>(20).unwrap(), data);
+        assert_eq!(reader.remaining_len(), 0);
+        reader.rewind(8).unwrap();
+        assert_eq!(reader.read_raw_array::<8>().unwrap(), data[12..]);
+        let err = reader.read_raw_array::<1>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = ChainReader::new::<0>([]);
+        assert_eq!(reader.read_raw::<0>(0).unwrap(), Vec::<u8>::new());
+        let err = reader.read_raw_array::<1>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    /// Encoded records of different lengths, each containing byte arrays and
+    /// strings.
+    #[cfg(feature = "std")]
+    fn records() -> (Vec<fixture::Record>, Vec<u8>) {
+        use amplify::confinement::Confined;
+
+        let records = (0..3u8)
+            .map(|no| fixture::Record {
+                id: no as u16 * 0x101,
+                hash: [no; 16],
+                name: Confined::try_from("record".repeat(no as usize + 1)).unwrap(),
+                blob: Confined::try_from(vec![0xA0 | no; no as usize * 20]).unwrap(),
+                flag: (no % 2 == 0).then_some(no),
+            })
+            .collect::<Vec<_>>();
+        let data = records.iter().flat_map(crate::test::encode).collect();
+        (records, data)
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn buf_read_straddling() {
+        use std::io::BufReader;
+
+        use fixture::Record;
+
+        let (records, data) = records();
+        let len = data.len();
+        let mut padded = vec![0xFF; len];
+        padded.extend(&data);
+        // skipping the padding shifts the records relatively to the boundaries
+        // of the buffer, such that each of the fields straddles them at every
+        // offset
+        for capacity in 1..=len + 1 {
+            for skip in 0..capacity.min(len) {
+                let inner = BufReader::with_capacity(capacity, &padded[len - skip..]);
+                let mut reader = StrictReader::from_buf_read::<1024>(inner);
+                let skipped = unsafe { reader.raw_reader() }.read_raw::<1024>(skip).unwrap();
+                assert_eq!(skipped.len(), skip);
+                for record in &records {
+                    assert_eq!(&Record::strict_decode(&mut reader).unwrap(), record);
+                }
+                assert_eq!(reader.count(), len + skip);
+                assert!(reader.is_eof().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn buf_read_errors() {
+        use std::io::BufReader;
+
+        use fixture::Record;
+
+        fn decode_all(mut reader: StrictReader<impl ReadRaw>) -> io::ErrorKind {
+            loop {
+                match Record::strict_decode(&mut reader) {
+                    Ok(_) => continue,
+                    Err(DecodeError::Io(err)) => return err.kind(),
+                    Err(err) => panic!("unexpected error {err}"),
+                }
+            }
+        }
+
+        let (_, data) = records();
+        for capacity in [1, 7, 16, 64] {
+            let inner = BufReader::with_capacity(capacity, data.as_slice());
+            assert_eq!(
+                decode_all(StrictReader::from_buf_read::<60>(inner)),
+                decode_all(StrictReader::in_memory::<60>(&data))
+            );
+            let inner = BufReader::with_capacity(capacity, data.as_slice());
+            assert_eq!(
+                decode_all(StrictReader::from_buf_read::<1024>(inner)),
+                io::ErrorKind::UnexpectedEof
+            );
+        }
+        assert_eq!(decode_all(StrictReader::in_memory::<60>(&data)), io::ErrorKind::InvalidInput);
+
+        let mut reader = BufStreamReader::new::<20>(BufReader::with_capacity(8, data.as_slice()));
+        assert_eq!(reader.read_raw_array::<4>().unwrap(), data[..4]);
+        assert_eq!(reader.peek_byte().unwrap(), data[4]);
+        assert_eq!(reader.read_raw::<20>(16).unwrap(), data[4..20]);
+        assert_eq!(reader.offset(), Some(20));
+        assert_eq!(reader.peek_byte().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.rewind(1).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn decode_context_valid() {
+        let (mut data, seal_offset) = corrupted_transfer();
+        data[seal_offset as usize] = 0;
+
+        let mut reader = StrictReader::in_memory::<{ u16::MAX as usize }>(&data);
+        let transfer = reader.decode_with_context::<fixture::Transfer>().unwrap();
+        assert_eq!(transfer.outputs[3].seal, fixture::Seal::Utxo(3));
+        assert_eq!(reader.count(), data.len());
+    }
+
+    #[test]
+    fn depth_limit() {
+        use fixture::{Expr, Node};
+
+        let limit = DecodeError::MaxRecursionDepth {
+            limit: RECURSION_DEPTH_LIMIT,
+        };
+
+        // Without the limit these payloads overflow the stack
+        let mut data = vec![1u8; 100_000];
+        data.push(0);
+        let mut reader = StrictReader::in_memory::<{ usize::MAX }>(&data);
+        assert_eq!(Expr::strict_decode(&mut reader).unwrap_err(), limit);
+
+        let mut data = [2u8, 1].repeat(50_000);
+        data.push(0);
+        let mut reader = StrictReader::in_memory::<{ usize::MAX }>(&data);
+        assert_eq!(Expr::strict_decode(&mut reader).unwrap_err(), limit);
+
+        let mut data = vec![1u8; 100_000];
+        data.push(0);
+        let mut reader = StrictReader::in_memory::<{ usize::MAX }>(&data);
+        assert_eq!(Node::strict_decode(&mut reader).unwrap_err(), limit);
+    }
+
+    #[test]
+    fn alloc_limit() {
+        use amplify::confinement::{SmallString, SmallVec, U16};
+
+        use crate::{StrictEncode, StrictWriter};
+
+        type Strings = SmallVec<SmallString>;
+        const BUDGET: usize = 1 << 20;
+
+        fn decode(data: &[u8], limit: usize) -> Result<Strings, DecodeError> {
+            let mut reader =
+                StrictReader::in_memory::<{ usize::MAX }>(data).with_alloc_limit(limit);
+            Strings::strict_decode(&mut reader)
+        }
+
+        // Each of the strings is within its confinement, while all of them take
+        // more than the budget
+        let string = SmallString::try_from("x".repeat(U16)).unwrap();
+        let bomb = Strings::try_from(vec![string; 20]).unwrap();
+        let writer = StrictWriter::in_memory::<{ usize::MAX }>();
+        let data = bomb.strict_encode(writer).unwrap().unbox().unconfine();
+        let outer = 20 * core::mem::size_of::<SmallString>();
+        assert_eq!(decode(&data, usize::MAX).unwrap(), bomb);
+        assert_eq!(decode(&data, BUDGET).unwrap_err(), DecodeError::AllocationBudgetExceeded {
+            budget: BUDGET,
+            attempted: outer + 16 * U16,
+        });
+
+        // The budget is checked before the announced data are read
+        let mut data = 20u16.to_le_bytes().to_vec();
+        data.extend(u16::MAX.to_le_bytes());
+        assert_eq!(decode(&data, 1000).unwrap_err(), DecodeError::AllocationBudgetExceeded {
+            budget: 1000,
+            attempted: outer + U16,
+        });
+        assert!(matches!(decode(&data, usize::MAX).unwrap_err(), DecodeError::Io(_)));
+
+        let string = SmallString::try_from(s!("legit")).unwrap();
+        let strings = Strings::try_from(vec![string; 5]).unwrap();
+        let data = crate::test::encode(&strings);
+        assert_eq!(decode(&data, BUDGET).unwrap(), strings);
+        assert_eq!(decode(&data, 5 * core::mem::size_of::<SmallString>() + 25).unwrap(), strings);
+    }
+
+    #[test]
+    fn depth_under_limit() {
+        use fixture::Expr;
+
+        fn decode(data: &[u8], limit: usize) -> Result<Expr, DecodeError> {
+            let mut reader =
+                StrictReader::in_memory::<{ usize::MAX }>(data).with_depth_limit(limit);
+            Expr::strict_decode(&mut reader)
+        }
+
+        let mut expr = Expr::Leaf;
+        for _ in 1..RECURSION_DEPTH_LIMIT {
+            expr = Expr::Neg(Box::new(expr));
+        }
+        let data = crate::test::encode(&expr);
+        assert_eq!(decode(&data, RECURSION_DEPTH_LIMIT).unwrap(), expr);
+
+        // Each `maybe` level nests both the expression and the option unions
+        let deep = Expr::Maybe(Some(Box::new(expr.clone())));
+        let data = crate::test::encode(&deep);
+        assert_eq!(
+            decode(&data, RECURSION_DEPTH_LIMIT + 1).unwrap_err(),
+            DecodeError::MaxRecursionDepth {
+                limit: RECURSION_DEPTH_LIMIT + 1
+            }
+        );
+        assert_eq!(decode(&data, RECURSION_DEPTH_LIMIT + 2).unwrap(), deep);
+        assert_eq!(decode(&[1, 1, 0], 2).unwrap_err(), DecodeError::MaxRecursionDepth { limit: 2 });
+    }
 }
@@ -0,0 +1,215 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Writers borrowed mutably, which are an alternative to the chains of
+//! writers moved through each of the write calls.
+//!
+//! Each of the scoped writers holds the moved writer and replaces it with the
+//! one returned by the write call, such that the encoding logic may be split
+//! into helper functions taking `&mut` references and use early returns. The
+//! bytes written are exactly the same as with the move-based calls, which the
+//! scoped writers use under the hood. They are created with
+//! [`TypedWrite::write_scoped`], [`TypedWrite::write_struct_with`] and
+//! [`TypedWrite::write_tuple_with`] methods. Values are written with their
+//! existing [`StrictEncode`] implementations by [`ScopedWriter::encode`], and
+//! any move-based write call can be made with [`ScopedWriter::with`].
+//!
+//! A scoped writer which write call has failed doesn't hold a writer anymore,
+//! and panics if used again; the error must be returned instead.
+
+use crate::{io, FieldName, StrictEncode, TypedWrite, WriteStruct, WriteTuple};
+
+const POISONED: &str = "scoped writer used after a write error";
+
+/// Typed writer borrowed mutably, created by [`TypedWrite::write_scoped`].
+#[derive(Debug)]
+pub struct ScopedWriter<W: TypedWrite>(Option<W>);
+
+impl<W: TypedWrite> ScopedWriter<W> {
+    pub fn new(writer: W) -> Self { ScopedWriter(Some(writer)) }
+
+    /// Writes a value with its [`StrictEncode`] implementation.
+    pub fn encode(&mut self, value: &impl StrictEncode) -> io::Result<()> {
+        self.with(|writer| value.strict_encode(writer))
+    }
+
+    /// Passes the wrapped writer to a move-based write call, replacing it with
+    /// the returned one.
+    pub fn with(&mut self, write: impl FnOnce(W) -> io::Result<W>) -> io::Result<()> {
+        let writer = self.0.take().expect(POISONED);
+        self.0 = Some(write(writer)?);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W { self.0.expect(POISONED) }
+}
+
+/// Writer of the struct fields borrowed mutably, created by
+/// [`TypedWrite::write_struct_with`].
+#[derive(Debug)]
+pub struct ScopedStruct<W: WriteStruct>(Option<W>);
+
+impl<W: WriteStruct> ScopedStruct<W> {
+    pub fn new(writer: W) -> Self { ScopedStruct(Some(writer)) }
+
+    pub fn field(&mut self, name: FieldName, value: &impl StrictEncode) -> io::Result<()> {
+        let writer = self.0.take().expect(POISONED);
+        self.0 = Some(writer.write_field(name, value)?);
+        Ok(())
+    }
+
+    pub fn complete(self) -> W::Parent { self.0.expect(POISONED).complete() }
+}
+
+/// Writer of the tuple fields borrowed mutably, created by
+/// [`TypedWrite::write_tuple_with`].
+#[derive(Debug)]
+pub struct ScopedTuple<W: WriteTuple>(Option<W>);
+
+impl<W: WriteTuple> ScopedTuple<W> {
+    pub fn new(writer: W) -> Self { ScopedTuple(Some(writer)) }
+
+    pub fn field(&mut self, value: &impl StrictEncode) -> io::Result<()> {
+        let writer = self.0.take().expect(POISONED);
+        self.0 = Some(writer.write_field(value)?);
+        Ok(())
+    }
+
+    pub fn complete(self) -> W::Parent { self.0.expect(POISONED).complete() }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyVec;
+
+    use super::{ScopedStruct, ScopedWriter};
+    use crate::test::encode;
+    use crate::{
+        io, StrictDecode, StrictEncode, StrictType, StrictWriter, TypedWrite, WriteStruct,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        version: u8,
+        flags: u16,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Point(i32, i32);
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Message {
+        header: Header,
+        origin: Point,
+        points: TinyVec<Point>,
+    }
+
+    fn message() -> Message {
+        Message {
+            header: Header {
+                version: 2,
+                flags: 0x0180,
+            },
+            origin: Point(-1, 7),
+            points: tiny_vec![Point(1, 2), Point(-3, 4)],
+        }
+    }
+
+    // the helper takes the writer by reference and doesn't return it back
+    fn write_header<W: WriteStruct>(s: &mut ScopedStruct<W>, header: &Header) -> io::Result<()> {
+        if header.version == 0 {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        s.field(fname!("header"), header)
+    }
+
+    #[test]
+    fn struct_equivalence() {
+        let msg = message();
+        let chained = StrictWriter::in_memory::<64>()
+            .write_struct::<Message>(|w| {
+                Ok(w.write_field(fname!("header"), &msg.header)?
+                    .write_field(fname!("origin"), &msg.origin)?
+                    .write_field(fname!("points"), &msg.points)?
+                    .complete())
+            })
+            .unwrap();
+        let scoped = StrictWriter::in_memory::<64>()
+            .write_struct_with::<Message>(|s| {
+                write_header(s, &msg.header)?;
+                s.field(fname!("origin"), &msg.origin)?;
+                s.field(fname!("points"), &msg.points)
+            })
+            .unwrap();
+        assert_eq!(scoped.unbox().unconfine(), chained.unbox().unconfine());
+
+        let scoped = StrictWriter::in_memory::<64>()
+            .write_struct_with::<Message>(|s| write_header(s, &Header::default()))
+            .unwrap_err();
+        assert_eq!(scoped.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn tuple_equivalence() {
+        let point = Point(i32::MIN, 0x0102_0304);
+        let scoped = StrictWriter::in_memory::<8>()
+            .write_tuple_with::<Point>(|t| {
+                t.field(&point.0)?;
+                t.field(&point.1)
+            })
+            .unwrap();
+        assert_eq!(scoped.unbox().unconfine(), encode(&point));
+    }
+
+    #[test]
+    fn writer_equivalence() {
+        let msg = message();
+        let scoped = StrictWriter::in_memory::<64>()
+            .write_scoped(|w| {
+                w.encode(&msg.header)?;
+                w.encode(&msg.origin)?;
+                w.with(|writer| msg.points.strict_encode(writer))
+            })
+            .unwrap();
+        assert_eq!(scoped.unbox().unconfine(), encode(&msg));
+    }
+
+    #[test]
+    fn write_error() {
+        let mut scoped = ScopedWriter::new(StrictWriter::in_memory::<3>());
+        scoped.encode(&0x0102u16).unwrap();
+        assert!(scoped.encode(&0x0304u16).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "scoped writer used after a write error")]
+    fn poisoned() {
+        let mut scoped = ScopedWriter::new(StrictWriter::in_memory::<1>());
+        scoped.encode(&0x0102u16).unwrap_err();
+        let _ = scoped.encode(&0u8);
+    }
+}
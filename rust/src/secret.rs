@@ -0,0 +1,338 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding of secret data, like seeds and private keys, which are wiped from
+//! memory once they are not used.
+//!
+//! [`Zeroizing`] and [`Secret`] wrappers are encoded exactly as the wrapped
+//! value, both on the wire and in the type system; [`Secret`] additionally
+//! never prints its value. [`ZeroizingBlob`] is a byte string with the
+//! encoding of `Confined<Vec<u8>, MIN_LEN, MAX_LEN>`, which is decoded into a
+//! single allocation of the exact length, such that no partial copies of the
+//! data are left in memory by the decoding. Serialization of secrets should be
+//! done with [`StrictSerialize::to_strict_vec_zeroizing`] or
+//! [`ZeroizingWriter`](crate::ZeroizingWriter).
+//!
+//! [`StrictSerialize::to_strict_vec_zeroizing`]: crate::StrictSerialize::to_strict_vec_zeroizing
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::ops::Deref;
+
+use ::zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+use amplify::confinement;
+
+use crate::reader::list_name;
+use crate::{
+    io, ConfinedRef, DecodeError, ReadRaw, StrictDecode, StrictEncode, StrictType, TypeName,
+    TypedRead, TypedWrite, LIB_EMBEDDED,
+};
+
+impl<T: Zeroize + StrictType> StrictType for Zeroizing<T> {
+    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+}
+impl<T: Zeroize + StrictEncode> StrictEncode for Zeroizing<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.deref().strict_encode(writer)
+    }
+}
+impl<T: Zeroize + StrictDecode> StrictDecode for Zeroizing<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        T::strict_decode(reader).map(Zeroizing::new)
+    }
+}
+
+/// Secret value, which is zeroized on drop and is never printed.
+///
+/// Both [`Debug`] and [`Display`] output only a placeholder instead of the
+/// value, which can be accessed with [`Secret::expose_secret`].
+#[derive(Clone, Default)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(secret: T) -> Self { Secret(secret) }
+
+    pub fn expose_secret(&self) -> &T { &self.0 }
+
+    pub fn expose_secret_mut(&mut self) -> &mut T { &mut self.0 }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(secret: T) -> Self { Secret(secret) }
+}
+
+impl<T: Zeroize> Zeroize for Secret<T> {
+    fn zeroize(&mut self) { self.0.zeroize() }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) { self.0.zeroize() }
+}
+
+impl<T: Zeroize> ZeroizeOnDrop for Secret<T> {}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("Secret(<redacted>)") }
+}
+
+impl<T: Zeroize> Display for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("<redacted>") }
+}
+
+impl<T: Zeroize + StrictType> StrictType for Secret<T> {
+    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+}
+impl<T: Zeroize + StrictEncode> StrictEncode for Secret<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.strict_encode(writer)
+    }
+}
+impl<T: Zeroize + StrictDecode> StrictDecode for Secret<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        T::strict_decode(reader).map(Secret)
+    }
+}
+
+/// Byte string of `MIN_LEN` to `MAX_LEN` bytes, which is zeroized on drop.
+///
+/// The blob has the same encoding as `Confined<Vec<u8>, MIN_LEN, MAX_LEN>`.
+/// Its data are decoded into a buffer allocated for the length declared in
+/// the encoding, thus the declared length can't exceed the allocation budget
+/// of the reader.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ZeroizingBlob<const MIN_LEN: usize, const MAX_LEN: usize>(Zeroizing<Vec<u8>>);
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    pub fn as_slice(&self) -> &[u8] { &self.0 }
+
+    pub fn into_inner(self) -> Zeroizing<Vec<u8>> { self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Zeroize for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    fn zeroize(&mut self) { self.0.zeroize() }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ZeroizeOnDrop for ZeroizingBlob<MIN_LEN, MAX_LEN> {}
+
+impl<const MAX_LEN: usize> Default for ZeroizingBlob<0, MAX_LEN> {
+    fn default() -> Self { ZeroizingBlob(default!()) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<Zeroizing<Vec<u8>>>
+    for ZeroizingBlob<MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+
+    fn try_from(data: Zeroizing<Vec<u8>>) -> Result<Self, Self::Error> {
+        ConfinedRef::<[u8], MIN_LEN, MAX_LEN>::try_from(data.as_slice())?;
+        Ok(ZeroizingBlob(data))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Deref for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> AsRef<[u8]> for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Debug for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ZeroizingBlob(<{} bytes redacted>)", self.0.len())
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<u8>(MIN_LEN, MAX_LEN) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        ConfinedRef::<[u8], MIN_LEN, MAX_LEN>::try_from(self.as_slice())
+            .expect("blob length is already checked")
+            .strict_encode(writer)
+    }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode for ZeroizingBlob<MIN_LEN, MAX_LEN> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        reader.alloc(len)?;
+        let mut buf = Zeroizing::new(Vec::with_capacity(len));
+        unsafe { reader.raw_reader() }.read_raw_into::<MAX_LEN>(len, &mut buf)?;
+        Ok(ZeroizingBlob(buf))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{SmallBlob, TinyBlob, U16, U8};
+
+    use super::*;
+    use crate::test::{count_allocations, encode, encoding};
+    use crate::{Checked, StrictReader, StrictSerialize, StrictWriter, WriteRaw, ZeroizingWriter};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Seed {
+        version: u8,
+        entropy: [u8; 16],
+        label: ZeroizingBlob<0, U8>,
+    }
+
+    impl Zeroize for Seed {
+        fn zeroize(&mut self) {
+            self.version.zeroize();
+            self.entropy.zeroize();
+            self.label.zeroize();
+        }
+    }
+
+    impl StrictSerialize for Seed {}
+
+    // the same data stored without zeroization
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", rename = "Seed", crate = crate)]
+    struct PlainSeed {
+        version: u8,
+        entropy: [u8; 16],
+        label: TinyBlob,
+    }
+
+    impl StrictSerialize for PlainSeed {}
+
+    // `Secret` doesn't implement `PartialEq` required by `crate::test::encode`
+    fn bytes(value: &impl StrictEncode) -> Vec<u8> {
+        value.strict_encode(StrictWriter::in_memory::<1024>()).unwrap().unbox().unconfine()
+    }
+
+    fn seed() -> Seed {
+        Seed {
+            version: 1,
+            entropy: [0xA5; 16],
+            label: ZeroizingBlob::try_from(Zeroizing::new(b"wallet".to_vec())).unwrap(),
+        }
+    }
+
+    fn plain_seed() -> PlainSeed {
+        PlainSeed {
+            version: 1,
+            entropy: [0xA5; 16],
+            label: TinyBlob::try_from(b"wallet".to_vec()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn serialize_zeroizing() {
+        let seed = seed();
+        let data = seed.to_strict_vec_zeroizing::<1024>().unwrap();
+        assert_eq!(data.as_slice(), seed.to_strict_serialized::<1024>().unwrap().as_slice());
+        assert_eq!(
+            data.as_slice(),
+            plain_seed().to_strict_serialized::<1024>().unwrap().as_slice()
+        );
+        assert!(seed.to_strict_vec_zeroizing::<8>().is_err());
+
+        let mut writer = ZeroizingWriter::with_capacity::<1024>(1);
+        writer.write_raw::<U8>([1, 2, 3]).unwrap();
+        assert_eq!(writer.count(), 3);
+        assert_eq!(format!("{writer:?}"), "ZeroizingWriter { count: 3, limit: 1024, .. }");
+        let writer = seed.strict_encode(StrictWriter::with(writer)).unwrap().unbox();
+        assert_eq!(writer.into_inner()[3..], data[..]);
+    }
+
+    #[test]
+    fn secret() {
+        let secret = Secret::new(seed());
+        assert_eq!(format!("{secret:?}"), "Secret(<redacted>)");
+        assert_eq!(format!("{secret}"), "<redacted>");
+        assert_eq!(Secret::<Seed>::strict_name(), Seed::strict_name());
+        assert_eq!(bytes(&secret), encode(&plain_seed()));
+
+        let data = encode(&seed());
+        let decoded = Secret::<Seed>::strict_decode(&mut StrictReader::in_memory::<1024>(&data));
+        assert_eq!(decoded.unwrap().expose_secret(), &seed());
+
+        let mut secret = Secret::from([7u8; 4]);
+        secret.expose_secret_mut()[0] = 1;
+        assert_eq!(bytes(&secret), [1, 7, 7, 7]);
+    }
+
+    #[test]
+    fn zeroizing() {
+        let key = Zeroizing::new([0x42u8; 32]);
+        encoding(&key, [0x42u8; 32]);
+        assert_eq!(Zeroizing::<[u8; 32]>::strict_name(), <[u8; 32]>::strict_name());
+        assert_eq!(bytes(&Zeroizing::new(Secret::new(7u16))), [7, 0]);
+    }
+
+    #[test]
+    fn blob() {
+        type Blob = ZeroizingBlob<1, U16>;
+
+        let plain = SmallBlob::try_from(vec![0xC3; 300]).unwrap();
+        let blob = Blob::try_from(Zeroizing::new(vec![0xC3; 300])).unwrap();
+        assert_eq!(encode(&blob), encode(&plain));
+        encoding(&blob, encode(&plain));
+        assert_eq!(format!("{blob:?}"), "ZeroizingBlob(<300 bytes redacted>)");
+
+        let decoded = Blob::strict_decode(&mut StrictReader::in_memory::<1024>(encode(&plain)));
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.as_slice(), plain.as_slice());
+        assert_eq!(decoded.into_inner().capacity(), 300);
+
+        assert!(Blob::try_from(Zeroizing::new(vec![])).is_err());
+        assert!(Blob::strict_decode(&mut StrictReader::in_memory::<8>([0, 0])).is_err());
+        assert!(ZeroizingBlob::<0, U8>::default().is_empty());
+    }
+
+    #[test]
+    fn blob_single_copy() {
+        type Blob = ZeroizingBlob<1, U16>;
+
+        // any other allocation would leave a copy of the data which is not zeroized
+        fn decode_once<R: ReadRaw>(reader: &mut StrictReader<R>) -> Blob {
+            let (blob, allocations) = count_allocations(|| Blob::strict_decode(reader).unwrap());
+            assert_eq!(allocations, 1);
+            blob
+        }
+
+        let data = encode(&SmallBlob::try_from(vec![0xC3; 300]).unwrap());
+        let blob = decode_once(&mut StrictReader::in_memory::<1024>(&data));
+        assert_eq!(blob.as_slice(), [0xC3; 300]);
+        #[cfg(feature = "bytes")]
+        decode_once(&mut StrictReader::from_bytes::<1024>(data.clone().into()));
+
+        let data = encode(&Checked::<Blob>::new(blob));
+        let mut reader = StrictReader::in_memory::<1024>(&data);
+        let (checked, allocations) =
+            count_allocations(|| Checked::<Blob>::strict_decode(&mut reader).unwrap());
+        // the other allocation is made by the decoding of the checksum array
+        assert_eq!(allocations, 2);
+        assert_eq!(checked.as_slice(), [0xC3; 300]);
+    }
+}
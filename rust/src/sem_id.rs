@@ -0,0 +1,136 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Semantic type ids: hashes of the type layout, which change whenever the
+//! strict encoding of a type or its meaning change.
+//!
+//! # Scheme, version 1
+//!
+//! A semantic id is a tagged SHA-256 hash `SHA256(tag || tag || layout)`,
+//! where `tag = SHA256(`[`SEM_ID_TAG`]`)`, and `layout` is the serialization
+//! of the type layout node described below. All integers are little-endian;
+//! a `name` is a single byte with the name length followed by the name
+//! (zero length denotes an absent name); `sizing` is `min` and `max` as
+//! `u64`; `id` is the 32-byte semantic id of an inner type.
+//!
+//! | Class     | Byte   | Layout after the class byte                           |
+//! |-----------|--------|-------------------------------------------------------|
+//! | primitive | `0x00` | primitive code as `u8`                                |
+//! | unicode   | `0x01` | sizing                                                |
+//! | rstring   | `0x02` | id of the first char, id of the rest chars, sizing    |
+//! | array     | `0x03` | id of the element, length as `u16`                    |
+//! | list      | `0x04` | id of the element, sizing                             |
+//! | set       | `0x05` | id of the element, sizing                             |
+//! | map       | `0x06` | id of the key, id of the value, sizing                |
+//! | sequence  | `0x07` | number of ids as `u32`, ids                           |
+//! | recursion | `0x08` | lib, type name                                        |
+//! | enum      | `0x10` | lib, type name, `u8` count, (`u8` tag, name)*         |
+//! | union     | `0x11` | lib, type name, `u8` count, (`u8` tag, name, fields)* |
+//! | tuple     | `0x12` | lib, type name, fields                                |
+//! | struct    | `0x13` | lib, type name, fields                                |
+//! | raw       | `0x14` | lib, type name, encoding code as `u8`, sizing of bits |
+//!
+//! `fields` are a `u8` count followed by (name, id) pairs in the order of
+//! serialization, with absent names for the tuple fields. Union variant
+//! fields are prefixed with the variant kind: `0x00` for unit variants (no
+//! fields follow), `0x01` for tuples and `0x02` for structs. Sequences
+//! describe values which encode several inner values without a type
+//! wrapping them. Recursion nodes replace named types nested in themselves.
+//! Raw nodes describe the types written in a
+//! [`RawEncoding`](crate::RawEncoding) external to the strict type system,
+//! with encoding code `0x00` for LEB128 and `0x01` for `CompactSize`
+//! integers, which have no sizing, and `0x02` for bit sets.
+//!
+//! Thus, the id depends on the library and type names, field and variant
+//! names, tags and ordering, collection bounds and the ids of all inner
+//! types, but not on the names of the Rust types, which are not a part of
+//! the strict type system.
+//!
+//! The layout is discovered by encoding
+//! [`StrictDumb::strict_dumb`](crate::StrictDumb::strict_dumb) value.
+//! Computing the ids requires `sha2` feature, while [`SemId`] values are
+//! available without it.
+
+use core::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use core::str::FromStr;
+
+#[cfg(feature = "sha2")]
+pub use self::hash::StrictSemId;
+use crate::{ByteArray, HexError};
+
+#[cfg(feature = "sha2")]
+mod hash;
+
+/// Tag of the semantic id hashing scheme.
+pub const SEM_ID_TAG: &str = "urn:ubideco:strict-encoding:sem-id:v1";
+
+/// Semantic type id: tagged SHA-256 hash of the type layout, which commits to
+/// the library and type names, field and variant names, tags, collection
+/// bounds and the ids of all inner types.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SemId([u8; 32]);
+
+impl SemId {
+    pub const fn from_byte_array(bytes: [u8; 32]) -> Self { SemId(bytes) }
+
+    pub const fn to_byte_array(&self) -> [u8; 32] { self.0 }
+
+    pub const fn as_slice(&self) -> &[u8] { &self.0 }
+}
+
+impl From<[u8; 32]> for SemId {
+    fn from(bytes: [u8; 32]) -> Self { SemId(bytes) }
+}
+
+impl From<SemId> for [u8; 32] {
+    fn from(id: SemId) -> Self { id.0 }
+}
+
+impl AsRef<[u8]> for SemId {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl LowerHex for SemId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        LowerHex::fmt(&ByteArray::from_byte_array(self.0), f)
+    }
+}
+
+impl UpperHex for SemId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        UpperHex::fmt(&ByteArray::from_byte_array(self.0), f)
+    }
+}
+
+impl Display for SemId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result { LowerHex::fmt(self, f) }
+}
+
+impl Debug for SemId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result { write!(f, "SemId({self:x})") }
+}
+
+impl FromStr for SemId {
+    type Err = HexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ByteArray::<32>::from_str(s).map(|bytes| SemId(bytes.to_byte_array()))
+    }
+}
@@ -0,0 +1,369 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Computation of [`SemId`]s from the type layouts; see the [scheme](super).
+
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use super::{SemId, SEM_ID_TAG};
+use crate::layout::{
+    layout_dumb, layout_of, Fields, LayoutNode, LayoutVariant, TypeHeader, VariantKind,
+};
+use crate::{Primitive, RawEncoding, Sizing, StrictDumb, StrictEncode};
+
+const CLASS_PRIMITIVE: u8 = 0x00;
+const CLASS_UNICODE: u8 = 0x01;
+const CLASS_RSTRING: u8 = 0x02;
+const CLASS_ARRAY: u8 = 0x03;
+const CLASS_LIST: u8 = 0x04;
+const CLASS_SET: u8 = 0x05;
+const CLASS_MAP: u8 = 0x06;
+const CLASS_SEQUENCE: u8 = 0x07;
+const CLASS_RECURSIVE: u8 = 0x08;
+const CLASS_ENUM: u8 = 0x10;
+const CLASS_UNION: u8 = 0x11;
+const CLASS_TUPLE: u8 = 0x12;
+const CLASS_STRUCT: u8 = 0x13;
+const CLASS_RAW: u8 = 0x14;
+
+const VARIANT_UNIT: u8 = 0x00;
+const VARIANT_TUPLE: u8 = 0x01;
+const VARIANT_STRUCT: u8 = 0x02;
+
+impl SemId {
+    /// Computes semantic id of the type of the value.
+    pub fn of(value: &impl StrictEncode) -> Self { layout_of(value) }
+}
+
+/// Provides semantic id for all strict types which can be encoded.
+pub trait StrictSemId: StrictEncode + StrictDumb {
+    /// Computes semantic id of the type; see [`SemId`].
+    fn sem_id() -> SemId { SemId::of(&layout_dumb::<Self>()) }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictSemId for T {}
+
+struct LayoutHasher(Sha256);
+
+impl LayoutHasher {
+    fn new(class: u8) -> Self {
+        let tag = Sha256::digest(SEM_ID_TAG.as_bytes());
+        let mut engine = Sha256::new();
+        engine.update(tag);
+        engine.update(tag);
+        engine.update([class]);
+        LayoutHasher(engine)
+    }
+
+    fn with_header(class: u8, ty: &TypeHeader) -> Self {
+        let mut hasher = Self::new(class);
+        hasher.name(Some(ty.lib));
+        hasher.name(ty.name.as_ref().map(|name| name.as_str()));
+        hasher
+    }
+
+    fn bytes(&mut self, bytes: impl AsRef<[u8]>) -> &mut Self {
+        self.0.update(bytes);
+        self
+    }
+
+    fn id(&mut self, id: SemId) -> &mut Self { self.bytes(id.0) }
+
+    fn name(&mut self, name: Option<&str>) -> &mut Self {
+        let name = name.unwrap_or_default();
+        self.bytes([name.len() as u8]).bytes(name)
+    }
+
+    fn sizing(&mut self, sizing: Sizing) -> &mut Self {
+        self.bytes(sizing.min.to_le_bytes()).bytes(sizing.max.to_le_bytes())
+    }
+
+    fn fields(&mut self, fields: &Fields<SemId>) -> &mut Self {
+        self.bytes([fields.len() as u8]);
+        for (name, id) in fields {
+            self.name(name.as_ref().map(|name| name.as_str())).id(*id);
+        }
+        self
+    }
+
+    fn finish(&mut self) -> SemId { SemId(self.0.clone().finalize().into()) }
+}
+
+impl LayoutNode for SemId {
+    fn primitive(prim: Primitive) -> Self {
+        LayoutHasher::new(CLASS_PRIMITIVE).bytes([prim.into_code()]).finish()
+    }
+
+    fn unicode(sizing: Sizing) -> Self { LayoutHasher::new(CLASS_UNICODE).sizing(sizing).finish() }
+
+    fn rstring(first: Self, rest: Self, sizing: Sizing) -> Self {
+        LayoutHasher::new(CLASS_RSTRING).id(first).id(rest).sizing(sizing).finish()
+    }
+
+    fn array(item: Self, len: u16) -> Self {
+        LayoutHasher::new(CLASS_ARRAY).id(item).bytes(len.to_le_bytes()).finish()
+    }
+
+    fn list(item: Self, sizing: Sizing) -> Self {
+        LayoutHasher::new(CLASS_LIST).id(item).sizing(sizing).finish()
+    }
+
+    fn set(item: Self, sizing: Sizing) -> Self {
+        LayoutHasher::new(CLASS_SET).id(item).sizing(sizing).finish()
+    }
+
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self {
+        LayoutHasher::new(CLASS_MAP).id(key).id(value).sizing(sizing).finish()
+    }
+
+    fn sequence(items: Vec<Self>) -> Self {
+        let mut hasher = LayoutHasher::new(CLASS_SEQUENCE);
+        hasher.bytes((items.len() as u32).to_le_bytes());
+        for id in items {
+            hasher.id(id);
+        }
+        hasher.finish()
+    }
+
+    fn enumeration(ty: &TypeHeader, variants: &'static [(u8, &'static str)]) -> Self {
+        let mut hasher = LayoutHasher::with_header(CLASS_ENUM, ty);
+        hasher.bytes([variants.len() as u8]);
+        for (tag, name) in variants {
+            hasher.bytes([*tag]).name(Some(name));
+        }
+        hasher.finish()
+    }
+
+    fn union(ty: &TypeHeader, variants: Vec<LayoutVariant<Self>>) -> Self {
+        let mut hasher = LayoutHasher::with_header(CLASS_UNION, ty);
+        hasher.bytes([variants.len() as u8]);
+        for variant in variants {
+            hasher.bytes([variant.tag]).name(Some(variant.name.as_str()));
+            match variant.kind {
+                VariantKind::Unit => hasher.bytes([VARIANT_UNIT]),
+                VariantKind::Tuple => hasher.bytes([VARIANT_TUPLE]).fields(&variant.fields),
+                VariantKind::Struct => hasher.bytes([VARIANT_STRUCT]).fields(&variant.fields),
+            };
+        }
+        hasher.finish()
+    }
+
+    fn tuple(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        LayoutHasher::with_header(CLASS_TUPLE, ty).fields(&fields).finish()
+    }
+
+    fn structure(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        LayoutHasher::with_header(CLASS_STRUCT, ty).fields(&fields).finish()
+    }
+
+    fn recursive(ty: &TypeHeader) -> Self {
+        LayoutHasher::with_header(CLASS_RECURSIVE, ty).finish()
+    }
+
+    fn raw(ty: &TypeHeader, encoding: RawEncoding) -> Self {
+        let mut hasher = LayoutHasher::with_header(CLASS_RAW, ty);
+        match encoding {
+            RawEncoding::Leb128 => hasher.bytes([0x00]),
+            RawEncoding::CompactSize => hasher.bytes([0x01]),
+            RawEncoding::Bitset(sizing) => hasher.bytes([0x02]).sizing(sizing),
+        };
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, SmallVec, TinyString, TinyVec};
+
+    use super::*;
+    use crate::{CompactSizeU64, SmallBitset, StrictDecode, StrictType, TinyBitset, VarIntU64};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Payment {
+        amount: u64,
+        memo: TinyString,
+    }
+
+    mod renamed_field {
+        use super::*;
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Payment {
+            value: u64,
+            memo: TinyString,
+        }
+    }
+
+    mod reordered {
+        use super::*;
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Payment {
+            memo: TinyString,
+            amount: u64,
+        }
+    }
+
+    mod rebound {
+        use super::*;
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Payment {
+            amount: u64,
+            memo: Confined<String, 0, 0x1FF>,
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", rename = "Payment", crate = crate)]
+    struct RustOnlyName {
+        amount: u64,
+        memo: TinyString,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Method {
+        #[default]
+        Cash,
+        Card(u16),
+        Transfer {
+            from: TinyString,
+            to: TinyString,
+        },
+    }
+
+    #[test]
+    fn golden() {
+        assert_eq!(
+            u8::sem_id().to_string(),
+            "0efdea4367cad44e5d83464f677b1cbe05d961183c53010c38e6c7dbda45563d"
+        );
+        assert_eq!(
+            TinyString::sem_id().to_string(),
+            "06ba0e23c84d0bc7bd712ba14f80e1fbc8b7874441f75d11e5750b6ed727c331"
+        );
+        assert_eq!(
+            Payment::sem_id().to_string(),
+            "f3713e09c9514db349758dc97508c140b3e7817f44b06e3e5187e21680ac08c2"
+        );
+        assert_eq!(
+            Method::sem_id().to_string(),
+            "0e00795c73782dea2adab449104b8636af1d561cdd3ed6df67513c2e9c51f91b"
+        );
+    }
+
+    #[test]
+    fn mutations() {
+        let id = Payment::sem_id();
+        assert_ne!(renamed_field::Payment::sem_id(), id);
+        assert_ne!(reordered::Payment::sem_id(), id);
+        assert_ne!(rebound::Payment::sem_id(), id);
+        assert_eq!(RustOnlyName::sem_id(), id);
+
+        assert_ne!(u8::sem_id(), u16::sem_id());
+        assert_ne!(TinyVec::<u8>::sem_id(), SmallVec::<u8>::sem_id());
+        assert_ne!(TinyVec::<u8>::sem_id(), TinyVec::<u16>::sem_id());
+        assert_ne!(Option::<u8>::sem_id(), Option::<u16>::sem_id());
+        assert_ne!(<[u8; 4]>::sem_id(), <[u8; 5]>::sem_id());
+        assert_ne!(TinyVec::<Payment>::sem_id(), TinyVec::<reordered::Payment>::sem_id());
+
+        assert_ne!(VarIntU64::sem_id(), u64::sem_id());
+        assert_ne!(VarIntU64::sem_id(), CompactSizeU64::sem_id());
+        assert_ne!(TinyBitset::sem_id(), SmallBitset::sem_id());
+        assert_ne!(TinyBitset::sem_id(), TinyVec::<bool>::sem_id());
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Tree {
+        children: TinyVec<Tree>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Node {
+        #[default]
+        Leaf,
+        Branch(TinyVec<Node>),
+        Link {
+            forest: Forest,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Forest {
+        nodes: TinyVec<Node>,
+        trees: TinyVec<Tree>,
+    }
+
+    #[test]
+    fn recursive() {
+        assert_eq!(
+            Tree::sem_id().to_string(),
+            "8b15fa0db29eb14469fa36ee9346f87e4bd6b48703bee40f35836669df0ff0ac"
+        );
+        // mutually recursive types through a union and a structure
+        assert_ne!(Node::sem_id(), Forest::sem_id());
+        let node = Node::Branch(TinyVec::try_from(vec![Node::Leaf]).unwrap());
+        assert_eq!(SemId::of(&node), Node::sem_id());
+    }
+
+    #[test]
+    fn value_independent() {
+        let payment = Payment {
+            amount: 10,
+            memo: TinyString::try_from(s!("memo")).unwrap(),
+        };
+        assert_eq!(SemId::of(&payment), Payment::sem_id());
+        let method = Method::Transfer {
+            from: TinyString::try_from(s!("a")).unwrap(),
+            to: TinyString::default(),
+        };
+        assert_eq!(SemId::of(&method), Method::sem_id());
+        let list = TinyVec::try_from(vec![1u8, 2, 3]).unwrap();
+        assert_eq!(SemId::of(&list), TinyVec::<u8>::sem_id());
+    }
+
+    #[test]
+    fn display_from_str() {
+        let id = Payment::sem_id();
+        assert_eq!(id.to_string().parse::<SemId>().unwrap(), id);
+        assert_eq!(format!("{id:?}"), format!("SemId({id})"));
+        assert_eq!(format!("{id:X}"), id.to_string().to_uppercase());
+    }
+}
@@ -0,0 +1,269 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of semantic versions from [`semver`](::semver) crate.
+//!
+//! [`Version`] is encoded as a structure of its `major`, `minor` and `patch`
+//! numbers, followed by the `pre`-release and `build` metadata ASCII strings
+//! of up to [`SEMVER_META_MAX_LEN`] (`u8::MAX`) bytes each, which are empty
+//! when the version doesn't have them. The metadata are validated on decoding.
+//! The dumb value of [`Version`] is `0.0.0`, provided by [`ForeignDumb`].
+//!
+//! [`VersionReq`] is encoded as a newtype over its string representation of up
+//! to [`SEMVER_REQ_MAX_LEN`] (`u16::MAX`) bytes, as it is displayed by
+//! [`VersionReq`]. Decoding parses the string and fails if it is not a valid
+//! requirement or differs from the representation of the parsed requirement,
+//! such that each requirement has a single encoding.
+//!
+//! Values exceeding the length limits fail to encode with
+//! [`io::ErrorKind::InvalidInput`] error.
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::ToString;
+
+use ::semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use amplify::ascii::AsciiString;
+use amplify::confinement::{Confined, U16, U8};
+
+use crate::{
+    io, DecodeError, ForeignDumb, InvalidLength, StrictDecode, StrictEncode, StrictType, TypeName,
+    TypedRead, TypedWrite, LIB_NAME_STD,
+};
+
+/// Maximal length of the pre-release and build metadata of a strict-encoded
+/// [`Version`], in bytes.
+pub const SEMVER_META_MAX_LEN: usize = U8;
+
+/// Maximal length of the string representation of a strict-encoded
+/// [`VersionReq`], in bytes.
+pub const SEMVER_REQ_MAX_LEN: usize = U16;
+
+fn ascii<const MAX_LEN: usize>(s: &str) -> io::Result<Confined<AsciiString, 0, MAX_LEN>> {
+    InvalidLength::check::<Confined<AsciiString, 0, MAX_LEN>, 0, MAX_LEN>(s.len())?;
+    let s = AsciiString::from_ascii(s.to_owned()).expect("semantic versions are ASCII strings");
+    Ok(Confined::try_from(s).expect("length is already checked"))
+}
+
+// `Version` is encoded through this mirror type, which defines the wire layout
+// and type name.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "Version", crate = crate)]
+pub(crate) struct VersionDef {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Confined<AsciiString, 0, SEMVER_META_MAX_LEN>,
+    build: Confined<AsciiString, 0, SEMVER_META_MAX_LEN>,
+}
+
+impl TryFrom<&Version> for VersionDef {
+    type Error = io::Error;
+
+    fn try_from(version: &Version) -> Result<Self, Self::Error> {
+        Ok(VersionDef {
+            major: version.major,
+            minor: version.minor,
+            patch: version.patch,
+            pre: ascii::<SEMVER_META_MAX_LEN>(version.pre.as_str())?,
+            build: ascii::<SEMVER_META_MAX_LEN>(version.build.as_str())?,
+        })
+    }
+}
+
+impl TryFrom<VersionDef> for Version {
+    type Error = DecodeError;
+
+    fn try_from(def: VersionDef) -> Result<Self, Self::Error> {
+        let pre = Prerelease::new(def.pre.as_str()).map_err(|err| {
+            DecodeError::DataIntegrityError(format!("invalid pre-release `{}`: {err}", def.pre))
+        })?;
+        let build = BuildMetadata::new(def.build.as_str()).map_err(|err| {
+            DecodeError::DataIntegrityError(format!(
+                "invalid build metadata `{}`: {err}",
+                def.build
+            ))
+        })?;
+        Ok(Version {
+            major: def.major,
+            minor: def.minor,
+            patch: def.patch,
+            pre,
+            build,
+        })
+    }
+}
+
+impl ForeignDumb for Version {
+    fn foreign_dumb() -> Self { Version::new(0, 0, 0) }
+}
+
+impl StrictType for Version {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Version")) }
+}
+impl StrictEncode for Version {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        VersionDef::try_from(self)?.strict_encode(writer)
+    }
+}
+impl StrictDecode for Version {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        VersionDef::strict_decode(reader).and_then(Version::try_from)
+    }
+}
+
+// `VersionReq` is encoded through this mirror type, which defines the wire
+// layout and type name.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "VersionReq", crate = crate)]
+pub(crate) struct VersionReqString(Confined<AsciiString, 0, SEMVER_REQ_MAX_LEN>);
+
+impl StrictType for VersionReq {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("VersionReq")) }
+}
+impl StrictEncode for VersionReq {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        let s = ascii::<SEMVER_REQ_MAX_LEN>(&self.to_string())?;
+        VersionReqString(s).strict_encode(writer)
+    }
+}
+impl StrictDecode for VersionReq {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let s = VersionReqString::strict_decode(reader)?.0;
+        let req = VersionReq::parse(s.as_str()).map_err(|err| {
+            DecodeError::DataIntegrityError(format!("invalid version requirement `{s}`: {err}"))
+        })?;
+        if req.to_string() != s.as_str() {
+            return Err(DecodeError::DataIntegrityError(format!(
+                "version requirement `{s}` is not in its canonical form `{req}`"
+            )));
+        }
+        Ok(req)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encode, encoding, try_decode};
+    use crate::{assert_strict_layout, StrictDumb};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Plugin {
+        #[strict_type(dumb = ForeignDumb::foreign_dumb())]
+        version: Version,
+        requires: VersionReq,
+    }
+
+    fn req_data(s: &str) -> Vec<u8> {
+        let mut data = (s.len() as u16).to_le_bytes().to_vec();
+        data.extend(s.as_bytes());
+        data
+    }
+
+    #[test]
+    fn layout() {
+        assert_strict_layout!(
+            VersionDef,
+            "Std.Version: struct { major:0: U64, minor:1: U64, patch:2: U64, pre:3: [Std.Ascii ^ \
+             ..0xff], build:4: [Std.Ascii ^ ..0xff] }"
+        );
+        assert_strict_layout!(VersionReqString, "Std.VersionReq: tuple([Std.Ascii])");
+    }
+
+    #[test]
+    fn version() {
+        encoding(&Version::new(1, 2, 3), [
+            1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        let mut data =
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7];
+        data.extend(b"alpha.1");
+        data.push(7);
+        data.extend(b"build.5");
+        encoding(&Version::parse("1.0.0-alpha.1+build.5").unwrap(), data);
+        assert_eq!(Version::strict_name(), Some(tn!("Version")));
+    }
+
+    #[test]
+    fn version_dumb() {
+        let plugin = Plugin::strict_dumb();
+        assert_eq!(plugin.version, Version::new(0, 0, 0));
+        assert_eq!(plugin.requires, VersionReq::STAR);
+        let mut data = vec![0; 26];
+        data.extend(req_data("*"));
+        encoding(&plugin, data);
+    }
+
+    #[test]
+    fn version_order() {
+        let versions =
+            ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta.2", "1.0.0"]
+                .map(|s| Version::parse(s).unwrap());
+        let decoded = versions.clone().map(|v| try_decode::<Version>(&encode(&v)).unwrap());
+        assert_eq!(decoded, versions);
+        assert_eq!(decoded[1].pre.as_str(), "alpha.1");
+        assert!(decoded.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn version_invalid() {
+        let mut data = encode(&Version::new(1, 0, 0));
+        data.truncate(24);
+        data.push(8);
+        data.extend(b"alpha..1");
+        data.push(0);
+        let err = try_decode::<Version>(&data).unwrap_err().to_string();
+        assert!(err.contains("invalid pre-release `alpha..1`"), "{err}");
+
+        let version = Version {
+            pre: Prerelease::new(&"a".repeat(SEMVER_META_MAX_LEN + 1)).unwrap(),
+            ..Version::new(1, 0, 0)
+        };
+        let err = version.strict_encode(crate::StrictWriter::in_memory::<1024>()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn version_req() {
+        for s in ["*", "^1.2.3", ">=1.0.0, <2.0.0-alpha", "=0.9.1-rc.1", "~1"] {
+            encoding(&VersionReq::parse(s).unwrap(), req_data(s));
+        }
+        assert_eq!(VersionReq::strict_dumb(), VersionReq::STAR);
+
+        let req = VersionReq::parse(">= 1.0 ,<2").unwrap();
+        assert_eq!(encode(&req), req_data(">=1.0, <2"));
+        assert_eq!(
+            try_decode::<VersionReq>(&req_data(">= 1.0 ,<2")).unwrap_err(),
+            DecodeError::DataIntegrityError(s!(
+                "version requirement `>= 1.0 ,<2` is not in its canonical form `>=1.0, <2`"
+            ))
+        );
+        let err = try_decode::<VersionReq>(&req_data("1.x.2")).unwrap_err().to_string();
+        assert!(err.contains("invalid version requirement `1.x.2`"), "{err}");
+    }
+}
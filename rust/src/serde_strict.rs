@@ -0,0 +1,292 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Adapters representing strict-encodable types in serde by their strict
+//! encoding.
+//!
+//! Values are serialized as a byte string for binary formats, and as a text
+//! in [`TextEncoding`] `E` for human-readable formats (like JSON), which is
+//! lowercase hex ([`Base16`]) unless specified otherwise. Deserialized data
+//! must not exceed `MAX_LEN` bytes and must be entirely consumed by the strict
+//! decoding; failures of the strict decoding are reported as serde custom
+//! errors with the message of the original error.
+//!
+//! A field can either use [`AsStrict`] type, or be annotated with
+//! `#[serde(with = "strict_encoding::serde_strict")]` for hex or
+//! `#[serde(with = "strict_encoding::serde_strict::base64")]` for [`Base64`],
+//! which use [`DEFAULT_MAX_LEN`] limit:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Request {
+//!     #[serde(with = "strict_encoding::serde_strict")]
+//!     header: Header,
+//!     body: AsStrict<Body, 0xFFFFFF, Base64>,
+//! }
+//! ```
+
+use core::fmt::{self, Formatter};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use serde_crate::de::{Error, Visitor};
+use serde_crate::ser::Error as _;
+use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::reader::decode_slice;
+pub use crate::text::{Base16, Base64, TextEncoding};
+pub use crate::DEFAULT_MAX_LEN;
+use crate::{StrictDecode, StrictEncode, StrictWriter};
+
+/// Wrapper represented in serde by the strict encoding of the value, which
+/// must not exceed `MAX_LEN` bytes, in text encoding `E` for human-readable
+/// formats.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct AsStrict<T, const MAX_LEN: usize = DEFAULT_MAX_LEN, E: TextEncoding = Base16>(
+    pub T,
+    PhantomData<E>,
+);
+
+impl<T, const MAX_LEN: usize, E: TextEncoding> AsStrict<T, MAX_LEN, E> {
+    pub fn new(value: T) -> Self { AsStrict(value, PhantomData) }
+
+    pub fn into_inner(self) -> T { self.0 }
+}
+
+impl<T, const MAX_LEN: usize, E: TextEncoding> From<T> for AsStrict<T, MAX_LEN, E> {
+    fn from(value: T) -> Self { AsStrict::new(value) }
+}
+
+impl<T, const MAX_LEN: usize, E: TextEncoding> Deref for AsStrict<T, MAX_LEN, E> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T, const MAX_LEN: usize, E: TextEncoding> DerefMut for AsStrict<T, MAX_LEN, E> {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
+
+impl<T: StrictEncode, const MAX_LEN: usize, E: TextEncoding> Serialize for AsStrict<T, MAX_LEN, E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let writer = StrictWriter::in_memory::<MAX_LEN>();
+        let data = self.0.strict_encode(writer).map_err(S::Error::custom)?.unbox().unconfine();
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&E::encode(&data))
+        } else {
+            serializer.serialize_bytes(&data)
+        }
+    }
+}
+
+impl<'de, T: StrictDecode, const MAX_LEN: usize, E: TextEncoding> Deserialize<'de>
+    for AsStrict<T, MAX_LEN, E>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let visitor = StrictVisitor::<T, MAX_LEN, E>(PhantomData);
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(visitor)
+        } else {
+            deserializer.deserialize_bytes(visitor)
+        }
+    }
+}
+
+struct StrictVisitor<T, const MAX_LEN: usize, E>(PhantomData<(T, E)>);
+
+impl<T: StrictDecode, const MAX_LEN: usize, E: TextEncoding> StrictVisitor<T, MAX_LEN, E> {
+    fn decode<Er: Error>(data: &[u8]) -> Result<AsStrict<T, MAX_LEN, E>, Er> {
+        if data.len() > MAX_LEN {
+            return Err(Er::invalid_length(data.len(), &Self(PhantomData)));
+        }
+        decode_slice::<T, MAX_LEN>(data).map(AsStrict::new).map_err(Er::custom)
+    }
+}
+
+impl<T: StrictDecode, const MAX_LEN: usize, E: TextEncoding> Visitor<'_>
+    for StrictVisitor<T, MAX_LEN, E>
+{
+    type Value = AsStrict<T, MAX_LEN, E>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "strict-encoded data of at most {MAX_LEN} bytes")
+    }
+
+    fn visit_str<Er: Error>(self, text: &str) -> Result<Self::Value, Er> {
+        // the text is checked before being decoded, such that oversized data
+        // are not allocated
+        if text.len() > E::max_text_len(MAX_LEN) {
+            return Err(Er::invalid_length(text.len(), &self));
+        }
+        Self::decode(&E::decode(text).map_err(Er::custom)?)
+    }
+
+    fn visit_bytes<Er: Error>(self, data: &[u8]) -> Result<Self::Value, Er> { Self::decode(data) }
+}
+
+/// Serializes the value by its strict encoding in hex, which must not exceed
+/// [`DEFAULT_MAX_LEN`] bytes; for the use with
+/// `#[serde(with = "strict_encoding::serde_strict")]`.
+pub fn serialize<T: StrictEncode, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    AsStrict::<&T>::new(value).serialize(serializer)
+}
+
+/// Deserializes the value from its strict encoding in hex, which must not
+/// exceed [`DEFAULT_MAX_LEN`] bytes; for the use with
+/// `#[serde(with = "strict_encoding::serde_strict")]`.
+pub fn deserialize<'de, T: StrictDecode, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    AsStrict::<T>::deserialize(deserializer).map(AsStrict::into_inner)
+}
+
+/// Adapter representing the strict encoding in [`Base64`] for human-readable
+/// formats; for the use with
+/// `#[serde(with = "strict_encoding::serde_strict::base64")]`.
+pub mod base64 {
+    use super::*;
+
+    /// Serializes the value by its strict encoding in base64, which must not
+    /// exceed [`DEFAULT_MAX_LEN`] bytes.
+    pub fn serialize<T: StrictEncode, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        AsStrict::<&T, DEFAULT_MAX_LEN, Base64>::new(value).serialize(serializer)
+    }
+
+    /// Deserializes the value from its strict encoding in base64, which must
+    /// not exceed [`DEFAULT_MAX_LEN`] bytes.
+    pub fn deserialize<'de, T: StrictDecode, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        AsStrict::<T, DEFAULT_MAX_LEN, Base64>::deserialize(deserializer).map(AsStrict::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, TinyString};
+
+    use super::*;
+    use crate::StrictType;
+
+    type Body = Confined<Vec<u8>, 0, 4>;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        name: TinyString,
+        version: u16,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Request {
+        #[serde(with = "crate::serde_strict")]
+        header: Header,
+        body: AsStrict<Body, 8>,
+    }
+
+    fn request() -> Request {
+        Request {
+            header: Header {
+                name: TinyString::try_from(s!("ab")).unwrap(),
+                version: 0x0102,
+            },
+            body: AsStrict::new(Body::try_from(vec![0xAB, 0xCD]).unwrap()),
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Packed {
+        #[serde(with = "crate::serde_strict::base64")]
+        header: Header,
+        body: AsStrict<Body, 8, Base64>,
+    }
+
+    #[test]
+    fn json() {
+        let json = serde_json::to_string(&request()).unwrap();
+        assert_eq!(json, r#"{"header":"0261620201","body":"02abcd"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request());
+
+        let Request { header, body } = request();
+        let packed = Packed {
+            header,
+            body: AsStrict::new(body.into_inner()),
+        };
+        let json = serde_json::to_string(&packed).unwrap();
+        assert_eq!(json, r#"{"header":"AmFiAgE=","body":"AqvN"}"#);
+        assert_eq!(serde_json::from_str::<Packed>(&json).unwrap(), packed);
+    }
+
+    #[test]
+    fn bincode() {
+        let data = bincode::serialize(&request()).unwrap();
+        assert_eq!(data, [
+            5, 0, 0, 0, 0, 0, 0, 0, 2, 0x61, 0x62, 2, 1, 3, 0, 0, 0, 0, 0, 0, 0, 2, 0xAB, 0xCD
+        ]);
+        assert_eq!(bincode::deserialize::<Request>(&data).unwrap(), request());
+    }
+
+    #[test]
+    fn errors() {
+        let err = |json: &str| {
+            let err = serde_json::from_str::<AsStrict<Body, 8>>(json).unwrap_err().to_string();
+            err[..err.rfind(" at line").unwrap()].to_owned()
+        };
+
+        assert_eq!(
+            err(r#""05abcdef0102""#),
+            "length prefix of `[Byte ^ ..0x4]` declares 5 elements, while the type is confined to \
+             at most 4 elements."
+        );
+        assert_eq!(
+            err(r#""02abcd00""#),
+            "data are not entirely consumed during strict deserialize operation: 1 bytes are left."
+        );
+        // oversized text is rejected before being decoded
+        assert_eq!(
+            err(r#""0101010101010101fx""#),
+            "invalid length 18, expected strict-encoded data of at most 8 bytes"
+        );
+        assert_eq!(
+            err(r#""02abc""#),
+            "Hex text has invalid length or padding, or is not canonical."
+        );
+        assert_eq!(err(r#""02abcx""#), "invalid Hex character 'x' at position 5.");
+        assert_eq!(err(r#""02ABCD""#), "invalid Hex character 'A' at position 2.");
+
+        let data = [3, 0, 0, 0, 0, 0, 0, 0, 1, 0xAB, 0];
+        let err = bincode::deserialize::<AsStrict<Body, 8>>(&data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "data are not entirely consumed during strict deserialize operation: 1 bytes are left."
+        );
+    }
+}
@@ -0,0 +1,494 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Confined strings, byte strings and byte arrays in shared immutable buffers.
+//!
+//! Unconfined `Arc<str>` and `Arc<[u8]>` can't be encoded, as neither can
+//! `String` and `Vec<u8>`; [`ArcString`] and [`ArcBlob`] wrap them with the
+//! length bounds and have the same encoding as `Confined<String, MIN_LEN,
+//! MAX_LEN>` and `Confined<Vec<u8>, MIN_LEN, MAX_LEN>`. They are decoded right
+//! into a newly allocated shared buffer, without an intermediate owned copy
+//! of the data, unless the data are read from a stream in chunks.
+//! [`ArcArray`] has the same encoding as `[u8; LEN]`.
+//!
+//! Readers [interning](crate::StrictReader::with_interner) the decoded data
+//! share a single buffer among all the values with the same contents.
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Deref;
+
+use amplify::confinement::{self, Confined, U16, U24, U32, U8};
+
+use crate::reader::{array_name, list_name, unicode_name};
+use crate::{
+    io, Byte, DecodeError, InvalidLength, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictType, TypeName, TypedRead, TypedWrite, Utf8Policy, LIB_EMBEDDED,
+};
+
+pub type TinyArcString = ArcString<0, U8>;
+pub type SmallArcString = ArcString<0, U16>;
+pub type MediumArcString = ArcString<0, U24>;
+pub type LargeArcString = ArcString<0, U32>;
+
+pub type TinyArcBlob = ArcBlob<0, U8>;
+pub type SmallArcBlob = ArcBlob<0, U16>;
+pub type MediumArcBlob = ArcBlob<0, U24>;
+pub type LargeArcBlob = ArcBlob<0, U32>;
+
+/// Writes a byte string in the same way as `Confined<Vec<u8>, MIN_LEN,
+/// MAX_LEN>`, failing with [`io::ErrorKind::InvalidInput`] if its length is
+/// out of the bounds.
+pub(crate) fn write_blob<W: TypedWrite, const MIN_LEN: usize, const MAX_LEN: usize>(
+    writer: W,
+    data: &[u8],
+) -> io::Result<W> {
+    InvalidLength::check::<Confined<Vec<u8>, MIN_LEN, MAX_LEN>, MIN_LEN, MAX_LEN>(data.len())?;
+    let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+    unsafe { Ok(writer.write_string::<MAX_LEN>(data)?.register_list(&Byte::strict_dumb(), sizing)) }
+}
+
+/// Unicode string in a shared immutable buffer, which has the same encoding
+/// as `Confined<String, MIN_LEN, MAX_LEN>`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArcString<const MIN_LEN: usize, const MAX_LEN: usize>(Arc<str>);
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ArcString<MIN_LEN, MAX_LEN> {
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    pub fn as_arc(&self) -> &Arc<str> { &self.0 }
+
+    pub fn into_arc(self) -> Arc<str> { self.0 }
+
+    /// Copies the string into an owned confined string.
+    pub fn to_confined(&self) -> Confined<String, MIN_LEN, MAX_LEN> {
+        Confined::try_from(String::from(&*self.0)).expect("shared string length is already checked")
+    }
+}
+
+impl<const MAX_LEN: usize> Default for ArcString<0, MAX_LEN> {
+    fn default() -> Self { ArcString(Arc::from("")) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<Arc<str>> for ArcString<MIN_LEN, MAX_LEN> {
+    type Error = confinement::Error;
+
+    fn try_from(s: Arc<str>) -> Result<Self, Self::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(s.len())?;
+        Ok(ArcString(s))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<Confined<String, MIN_LEN, MAX_LEN>>
+    for ArcString<MIN_LEN, MAX_LEN>
+{
+    fn from(s: Confined<String, MIN_LEN, MAX_LEN>) -> Self { ArcString(Arc::from(s.release())) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<ArcString<MIN_LEN, MAX_LEN>> for Arc<str> {
+    fn from(s: ArcString<MIN_LEN, MAX_LEN>) -> Self { s.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Deref for ArcString<MIN_LEN, MAX_LEN> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> AsRef<str> for ArcString<MIN_LEN, MAX_LEN> {
+    fn as_ref(&self) -> &str { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Borrow<str> for ArcString<MIN_LEN, MAX_LEN> {
+    fn borrow(&self) -> &str { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Display for ArcString<MIN_LEN, MAX_LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+/// Byte string in a shared immutable buffer, which has the same encoding as
+/// `Confined<Vec<u8>, MIN_LEN, MAX_LEN>`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArcBlob<const MIN_LEN: usize, const MAX_LEN: usize>(Arc<[u8]>);
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> ArcBlob<MIN_LEN, MAX_LEN> {
+    pub fn as_slice(&self) -> &[u8] { &self.0 }
+
+    pub fn as_arc(&self) -> &Arc<[u8]> { &self.0 }
+
+    pub fn into_arc(self) -> Arc<[u8]> { self.0 }
+
+    /// Copies the data into an owned confined byte string.
+    pub fn to_confined(&self) -> Confined<Vec<u8>, MIN_LEN, MAX_LEN> {
+        Confined::try_from(self.0.to_vec()).expect("shared blob length is already checked")
+    }
+}
+
+impl<const MAX_LEN: usize> Default for ArcBlob<0, MAX_LEN> {
+    fn default() -> Self { ArcBlob(Arc::from([])) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<Arc<[u8]>> for ArcBlob<MIN_LEN, MAX_LEN> {
+    type Error = confinement::Error;
+
+    fn try_from(data: Arc<[u8]>) -> Result<Self, Self::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(data.len())?;
+        Ok(ArcBlob(data))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<Confined<Vec<u8>, MIN_LEN, MAX_LEN>>
+    for ArcBlob<MIN_LEN, MAX_LEN>
+{
+    fn from(data: Confined<Vec<u8>, MIN_LEN, MAX_LEN>) -> Self {
+        ArcBlob(Arc::from(data.release()))
+    }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> From<ArcBlob<MIN_LEN, MAX_LEN>> for Arc<[u8]> {
+    fn from(blob: ArcBlob<MIN_LEN, MAX_LEN>) -> Self { blob.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Deref for ArcBlob<MIN_LEN, MAX_LEN> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> AsRef<[u8]> for ArcBlob<MIN_LEN, MAX_LEN> {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> Borrow<[u8]> for ArcBlob<MIN_LEN, MAX_LEN> {
+    fn borrow(&self) -> &[u8] { &self.0 }
+}
+
+/// Byte array in a shared immutable buffer, which has the same encoding as
+/// `[u8; LEN]`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArcArray<const LEN: usize>(Arc<[u8; LEN]>);
+
+impl<const LEN: usize> ArcArray<LEN> {
+    pub fn as_slice(&self) -> &[u8] { &*self.0 }
+
+    pub fn as_arc(&self) -> &Arc<[u8; LEN]> { &self.0 }
+
+    pub fn into_arc(self) -> Arc<[u8; LEN]> { self.0 }
+
+    pub fn to_byte_array(&self) -> [u8; LEN] { *self.0 }
+}
+
+impl<const LEN: usize> Default for ArcArray<LEN> {
+    fn default() -> Self { ArcArray(Arc::new([0u8; LEN])) }
+}
+
+impl<const LEN: usize> From<[u8; LEN]> for ArcArray<LEN> {
+    fn from(bytes: [u8; LEN]) -> Self { ArcArray(Arc::new(bytes)) }
+}
+
+impl<const LEN: usize> From<Arc<[u8; LEN]>> for ArcArray<LEN> {
+    fn from(bytes: Arc<[u8; LEN]>) -> Self { ArcArray(bytes) }
+}
+
+impl<const LEN: usize> From<ArcArray<LEN>> for Arc<[u8; LEN]> {
+    fn from(array: ArcArray<LEN>) -> Self { array.0 }
+}
+
+impl<const LEN: usize> Deref for ArcArray<LEN> {
+    type Target = [u8; LEN];
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<const LEN: usize> AsRef<[u8]> for ArcArray<LEN> {
+    fn as_ref(&self) -> &[u8] { &*self.0 }
+}
+
+impl<const LEN: usize> Borrow<[u8]> for ArcArray<LEN> {
+    fn borrow(&self) -> &[u8] { &*self.0 }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for ArcString<MIN_LEN, MAX_LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { unicode_name(MIN_LEN, MAX_LEN) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode for ArcString<MIN_LEN, MAX_LEN> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
+        unsafe {
+            writer
+                .register_unicode(Sizing::new(MIN_LEN as u64, MAX_LEN as u64))
+                .write_string::<MAX_LEN>(self.as_bytes())
+        }
+    }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode for ArcString<MIN_LEN, MAX_LEN> {
+    /// Valid UTF-8 data are taken as they are read into the shared buffer;
+    /// only the data fixed by the lossy UTF-8 policy are copied.
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let bytes = unsafe { reader.read_shared::<Self, MIN_LEN, MAX_LEN>()? };
+        if core::str::from_utf8(&bytes).is_ok() {
+            // SAFETY: the bytes are checked to be valid UTF-8 above, and `str`
+            // has the same layout as `[u8]`.
+            let s = unsafe { Arc::from_raw(Arc::into_raw(bytes) as *const str) };
+            return Ok(ArcString(s));
+        }
+        let s = match reader.utf8_policy() {
+            Utf8Policy::Strict => String::from_utf8(bytes.to_vec())?,
+            Utf8Policy::Lossy => String::from_utf8_lossy(&bytes).into_owned(),
+        };
+        ArcString::try_from(Arc::from(s)).map_err(DecodeError::from)
+    }
+
+    fn strict_display(&self) -> Option<String> { Some(String::from(self.as_str())) }
+}
+
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictType for ArcBlob<MIN_LEN, MAX_LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<u8>(MIN_LEN, MAX_LEN) }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode for ArcBlob<MIN_LEN, MAX_LEN> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        write_blob::<W, MIN_LEN, MAX_LEN>(writer, self)
+    }
+}
+impl<const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode for ArcBlob<MIN_LEN, MAX_LEN> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        unsafe { reader.read_shared::<Self, MIN_LEN, MAX_LEN>() }.map(ArcBlob)
+    }
+}
+
+impl<const LEN: usize> StrictType for ArcArray<LEN> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { array_name::<u8>(LEN) }
+}
+impl<const LEN: usize> StrictEncode for ArcArray<LEN> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.strict_encode(writer)
+    }
+}
+impl<const LEN: usize> StrictDecode for ArcArray<LEN> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        unsafe { reader.read_shared_array::<LEN>() }.map(ArcArray)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{SmallBlob, TinyBlob, TinyString};
+
+    use super::*;
+    use crate::test::{count_allocations, decode, decode_err, encode, encoding};
+    use crate::{ReadRaw, StrictReader, StrictSize, StrictWriter};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        id: u32,
+        name: TinyArcString,
+        payload: SmallArcBlob,
+    }
+
+    #[test]
+    fn encoding_matches_owned() {
+        let s = TinyString::try_from(s!("shared")).unwrap();
+        let shared = TinyArcString::from(s.clone());
+        assert_eq!(encode(&shared), encode(&s));
+        assert_eq!(shared.to_confined(), s);
+        assert_eq!(decode::<TinyArcString>(encode(&s)), shared);
+        assert_eq!(TinyArcString::strict_size(), TinyString::strict_size());
+        encoding(&shared, encode(&s));
+
+        let blob = SmallBlob::try_from(vec![1u8, 2, 3]).unwrap();
+        let shared = SmallArcBlob::from(blob.clone());
+        assert_eq!(encode(&shared), encode(&blob));
+        assert_eq!(shared.to_confined(), blob);
+        assert_eq!(decode::<SmallArcBlob>(encode(&blob)), shared);
+        assert_eq!(SmallArcBlob::strict_size(), SmallBlob::strict_size());
+        encoding(&shared, [3, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn dumb() {
+        assert_eq!(&*TinyArcString::strict_dumb(), "");
+        assert_eq!(&*TinyArcBlob::strict_dumb(), b"");
+        encoding(&Record::strict_dumb(), [0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn confinement() {
+        let data = encode(&TinyBlob::try_from(vec![1u8, 2, 3]).unwrap());
+        let err = decode_err::<ArcBlob<0, 2>>(&data);
+        assert!(matches!(err, DecodeError::Oversize {
+            declared_len: 3,
+            max_len: 2,
+            ..
+        }));
+        let err = decode_err::<ArcString<4, { U8 }>>(&data);
+        assert!(matches!(err, DecodeError::Undersize {
+            declared_len: 3,
+            min_len: 4,
+            ..
+        }));
+        assert!(TinyArcBlob::try_from(Arc::<[u8]>::from(vec![0u8; 256])).is_err());
+        assert!(ArcString::<1, 4>::try_from(Arc::<str>::from("")).is_err());
+    }
+
+    #[test]
+    fn utf8() {
+        let data = [4u8, b'a', b'b', 0xFF, b'c'];
+        let DecodeError::Utf8(err) = decode_err::<TinyArcString>(&data) else {
+            panic!("invalid UTF-8 accepted")
+        };
+        assert_eq!(err.utf8_error().valid_up_to(), 2);
+
+        let mut reader = StrictReader::in_memory::<16>(data).with_utf8_policy(Utf8Policy::Lossy);
+        let s = TinyArcString::strict_decode(&mut reader).unwrap();
+        assert_eq!(&*s, "ab\u{FFFD}c");
+        let mut reader = StrictReader::in_memory::<16>(data).with_utf8_policy(Utf8Policy::Lossy);
+        let err = ArcString::<0, 4>::strict_decode(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::Confinement(_)));
+    }
+
+    #[test]
+    fn allocations() {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct OwnedRecord {
+            id: u32,
+            name: TinyString,
+            payload: SmallBlob,
+        }
+
+        fn count<T: StrictDecode, R: ReadRaw>(mut reader: StrictReader<R>) -> (T, usize) {
+            count_allocations(|| T::strict_decode(&mut reader).unwrap())
+        }
+
+        // The data are read right into the shared buffers, allocating no more
+        // memory than decoding of the owned strings does
+        fn check<R: ReadRaw>(reader: impl Fn() -> StrictReader<R>, record: &Record) {
+            let (owned, owned_allocations) = count::<OwnedRecord, _>(reader());
+            let (decoded, allocations) = count::<Record, _>(reader());
+            assert_eq!(&decoded, record);
+            assert_eq!(owned.payload.as_slice(), decoded.payload.as_slice());
+            assert_eq!(allocations, owned_allocations);
+        }
+
+        let record = Record {
+            id: 7,
+            name: TinyArcString::try_from(Arc::<str>::from("record")).unwrap(),
+            payload: SmallArcBlob::try_from(Arc::<[u8]>::from(vec![0xA5; 0x1000])).unwrap(),
+        };
+        let data = encode(&record);
+        check(|| StrictReader::from_slice::<{ U16 }>(&data), &record);
+        check(|| StrictReader::from_chain::<{ U16 }>(data.chunks(5)), &record);
+        check(|| StrictReader::in_memory::<{ U16 }>(&data), &record);
+        check(|| StrictReader::from_buf_read::<{ U16 }>(&data[..]), &record);
+    }
+
+    #[test]
+    fn array() {
+        let array = ArcArray::from([0xA5u8; 32]);
+        assert_eq!(encode(&array), encode(&[0xA5u8; 32]));
+        assert_eq!(ArcArray::<32>::strict_size(), <[u8; 32]>::strict_size());
+        encoding(&array, [0xA5; 32]);
+        encoding(&ArcArray::<4>::strict_dumb(), [0; 4]);
+        assert!(matches!(decode_err::<ArcArray<4>>(&[1, 2, 3]), DecodeError::Io(_)));
+    }
+
+    #[test]
+    fn interning() {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Entry {
+            id: ArcArray<32>,
+            name: TinyArcString,
+            payload: SmallArcBlob,
+        }
+        type Entries = Confined<Vec<Entry>, 0, { U16 }>;
+
+        let entries = (0..10_000u16)
+            .map(|no| Entry {
+                id: ArcArray::from([(no % 3) as u8; 32]),
+                name: TinyArcString::try_from(Arc::<str>::from(["alice", "bob"][no as usize % 2]))
+                    .unwrap(),
+                payload: SmallArcBlob::try_from(Arc::<[u8]>::from(vec![0xA5; 0x100])).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        let entries = Entries::try_from(entries).unwrap();
+        let writer = StrictWriter::in_memory::<{ U24 }>();
+        let data = entries.strict_encode(writer).unwrap().unbox().unconfine();
+
+        let plain = StrictReader::from_slice::<{ U32 }>(&data);
+        let interning = StrictReader::from_slice::<{ U32 }>(&data).with_interner(16);
+        let (plain, plain_allocations) = count_allocations(|| {
+            let mut reader = plain;
+            Entries::strict_decode(&mut reader).unwrap()
+        });
+        let (decoded, allocations) = count_allocations(|| {
+            let mut reader = interning;
+            Entries::strict_decode(&mut reader).unwrap()
+        });
+        assert_eq!(decoded, entries);
+        assert_eq!(plain, entries);
+        // Each entry decoded without interning allocates its three buffers,
+        // while the interned ones share the buffers of the first entries
+        assert!(plain_allocations - allocations > 3 * 9_990);
+        assert_eq!(Arc::strong_count(decoded[0].id.as_arc()), 3334);
+        assert_eq!(Arc::strong_count(decoded[2].id.as_arc()), 3333);
+        assert_eq!(Arc::strong_count(decoded[0].name.as_arc()), 5000);
+        assert_eq!(Arc::strong_count(decoded[0].payload.as_arc()), 10_000);
+        assert_eq!(Arc::strong_count(plain[0].payload.as_arc()), 1);
+
+        // Once the table is full the data are still decoded, but the new
+        // contents aren't shared anymore
+        let mut reader = StrictReader::from_chain::<{ U32 }>(data.chunks(7)).with_interner(2);
+        let decoded = Entries::strict_decode(&mut reader).unwrap();
+        assert_eq!(decoded, entries);
+        drop(reader);
+        assert_eq!(Arc::strong_count(decoded[0].id.as_arc()), 3334);
+        assert_eq!(Arc::strong_count(decoded[0].name.as_arc()), 5000);
+        assert_eq!(Arc::strong_count(decoded[0].payload.as_arc()), 1);
+        assert_eq!(Arc::strong_count(decoded[1].id.as_arc()), 1);
+    }
+
+    #[test]
+    fn interning_failures() {
+        let data = [3u8, 1, 2];
+        let mut reader = StrictReader::from_slice::<16>(&data).with_interner(16);
+        assert!(matches!(SmallArcBlob::strict_decode(&mut reader), Err(DecodeError::Io(_))));
+        let mut reader =
+            StrictReader::from_slice::<16>(&data).with_interner(16).with_alloc_limit(2);
+        let err = TinyArcBlob::strict_decode(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::AllocationBudgetExceeded { .. }));
+
+        let data = [4u8, b'a', b'b', 0xFF, b'c'];
+        let mut reader = StrictReader::from_slice::<16>(&data).with_interner(16);
+        assert!(matches!(TinyArcString::strict_decode(&mut reader), Err(DecodeError::Utf8(_))));
+    }
+}
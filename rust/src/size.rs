@@ -0,0 +1,260 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use crate::layout::{layout_dumb, layout_of, Fields, LayoutNode, LayoutVariant, TypeHeader};
+use crate::{Primitive, RawEncoding, Sizing, StrictDumb, StrictEncode};
+
+/// Bounds of the size of the strict encoding, provided for all strict types
+/// which can be encoded.
+pub trait StrictSize: StrictEncode + StrictDumb {
+    /// Returns the minimal and the maximal number of bytes the strict encoding
+    /// of a value of the type may occupy.
+    ///
+    /// The maximum is `None` for recursive types and for the types whose
+    /// encoding may exceed `usize::MAX` bytes. Types of a fixed size return
+    /// equal minimum and maximum. Union variants with the fields of
+    /// uninhabited types, like [`crate::Void`], are not counted, since no
+    /// value of them can be encoded.
+    fn strict_size() -> (usize, Option<usize>) {
+        let size = layout_of::<SizeBounds>(&layout_dumb::<Self>());
+        (size.min, size.max)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictSize for T {}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct SizeBounds {
+    min: usize,
+    max: Option<usize>,
+}
+
+impl SizeBounds {
+    const fn fixed(len: usize) -> Self {
+        SizeBounds {
+            min: len,
+            max: Some(len),
+        }
+    }
+
+    fn add(self, other: SizeBounds) -> Self {
+        SizeBounds {
+            min: self.min.saturating_add(other.min),
+            max: self.max.zip(other.max).and_then(|(a, b)| a.checked_add(b)),
+        }
+    }
+
+    fn sum(items: impl IntoIterator<Item = SizeBounds>) -> Self {
+        items.into_iter().fold(SizeBounds::fixed(0), SizeBounds::add)
+    }
+
+    /// Size of a collection with `sizing` items of this size, including the
+    /// length prefix.
+    fn repeat(self, sizing: Sizing) -> Self {
+        let min = usize::try_from(sizing.min).unwrap_or(usize::MAX);
+        let max = usize::try_from(sizing.max).ok();
+        SizeBounds::fixed(sizing.prefix_len()).add(SizeBounds {
+            min: self.min.saturating_mul(min),
+            max: self.max.zip(max).and_then(|(a, b)| a.checked_mul(b)),
+        })
+    }
+
+    fn fields(fields: Fields<Self>) -> Self { SizeBounds::sum(fields.into_iter().map(|(_, s)| s)) }
+}
+
+impl LayoutNode for SizeBounds {
+    fn primitive(prim: Primitive) -> Self {
+        SizeBounds::fixed(match prim {
+            Primitive::UNIT => 0,
+            Primitive::BYTE => 1,
+            prim => prim.byte_size() as usize,
+        })
+    }
+
+    fn unicode(sizing: Sizing) -> Self { SizeBounds::fixed(1).repeat(sizing) }
+
+    fn rstring(_first: Self, _rest: Self, sizing: Sizing) -> Self {
+        SizeBounds::fixed(1).repeat(sizing)
+    }
+
+    fn array(item: Self, len: u16) -> Self {
+        SizeBounds {
+            min: item.min.saturating_mul(len as usize),
+            max: item.max.and_then(|max| max.checked_mul(len as usize)),
+        }
+    }
+
+    fn list(item: Self, sizing: Sizing) -> Self { item.repeat(sizing) }
+
+    fn set(item: Self, sizing: Sizing) -> Self { item.repeat(sizing) }
+
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self { key.add(value).repeat(sizing) }
+
+    fn sequence(items: Vec<Self>) -> Self { SizeBounds::sum(items) }
+
+    fn enumeration(_ty: &TypeHeader, _variants: &'static [(u8, &'static str)]) -> Self {
+        SizeBounds::fixed(1)
+    }
+
+    fn union(_ty: &TypeHeader, variants: Vec<LayoutVariant<Self>>) -> Self {
+        // Unions without variants describe uninhabited types, which have no
+        // values to be encoded; their bounds are neutral to the bounds of the
+        // unions having them as a variant, and to collections, which can
+        // contain only zero of their items.
+        if variants.is_empty() {
+            return SizeBounds {
+                min: usize::MAX,
+                max: Some(0),
+            };
+        }
+        let sizes = variants
+            .into_iter()
+            .map(|variant| SizeBounds::fields(variant.fields))
+            .collect::<Vec<_>>();
+        let fields = SizeBounds {
+            min: sizes.iter().map(|size| size.min).min().unwrap_or_default(),
+            max: sizes.iter().try_fold(0, |max, size| Some(max.max(size.max?))),
+        };
+        SizeBounds::fixed(1).add(fields)
+    }
+
+    fn tuple(_ty: &TypeHeader, fields: Fields<Self>) -> Self { SizeBounds::fields(fields) }
+
+    fn structure(_ty: &TypeHeader, fields: Fields<Self>) -> Self { SizeBounds::fields(fields) }
+
+    fn recursive(_ty: &TypeHeader) -> Self { SizeBounds { min: 0, max: None } }
+
+    fn raw(_ty: &TypeHeader, encoding: RawEncoding) -> Self {
+        match encoding {
+            RawEncoding::Leb128 => SizeBounds {
+                min: 1,
+                max: Some(10),
+            },
+            RawEncoding::CompactSize => SizeBounds {
+                min: 1,
+                max: Some(9),
+            },
+            RawEncoding::Bitset(sizing) => SizeBounds::fixed(sizing.prefix_len()).add(SizeBounds {
+                min: usize::try_from(sizing.min.div_ceil(8)).unwrap_or(usize::MAX),
+                max: usize::try_from(sizing.max.div_ceil(8)).ok(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, TinyString, TinyVec};
+
+    use super::*;
+    use crate::test::encode;
+    use crate::{CompactSizeU64, ConfinedBitset, StrictDecode, StrictType, VarIntU64};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Numbers {
+        a: u8,
+        b: u32,
+        c: i64,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Profile {
+        name: TinyString,
+        note: Option<Confined<String, 0, 300>>,
+        age: Option<u8>,
+        keys: TinyVec<[u8; 2]>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Message {
+        #[default]
+        Ping,
+        Data(u16, u32),
+        Text {
+            body: TinyString,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Tree {
+        value: u8,
+        children: TinyVec<Tree>,
+    }
+
+    fn check<T: StrictSize + Eq + core::fmt::Debug>(min: &T, max: &T) {
+        assert_eq!(T::strict_size(), (encode(min).len(), Some(encode(max).len())));
+    }
+
+    #[test]
+    fn fixed() {
+        assert_eq!(<[u8; 32]>::strict_size(), (32, Some(32)));
+        assert_eq!(Numbers::strict_size(), (13, Some(13)));
+        assert_eq!(<(u8, u16)>::strict_size(), (3, Some(3)));
+        assert_eq!(<()>::strict_size(), (0, Some(0)));
+        check(&Numbers::default(), &Numbers {
+            a: u8::MAX,
+            b: u32::MAX,
+            c: i64::MIN,
+        });
+    }
+
+    #[test]
+    fn bounded() {
+        let string = |len: usize| "x".repeat(len);
+        check(&TinyString::default(), &TinyString::try_from(string(255)).unwrap());
+        check(&Confined::<String, 0, 300>::default(), &Confined::try_from(string(300)).unwrap());
+        check(&None, &Some(u16::MAX));
+        check(&None, &Some(Some(u8::MAX)));
+        check(&Profile::default(), &Profile {
+            name: TinyString::try_from(string(255)).unwrap(),
+            note: Some(Confined::try_from(string(300)).unwrap()),
+            age: Some(0),
+            keys: TinyVec::try_from(vec![[0xFF; 2]; 255]).unwrap(),
+        });
+        check(&Message::Ping, &Message::Text {
+            body: TinyString::try_from(string(255)).unwrap(),
+        });
+        assert_eq!(Message::strict_size(), (1, Some(257)));
+    }
+
+    #[test]
+    fn raw() {
+        check(&VarIntU64::from(0), &VarIntU64::from(u64::MAX));
+        check(&CompactSizeU64::from(0), &CompactSizeU64::from(u64::MAX));
+        check(&ConfinedBitset::<0, 20>::default(), &ConfinedBitset::<0, 20>::with_len(20).unwrap());
+    }
+
+    #[test]
+    fn unbounded() {
+        assert_eq!(Tree::strict_size(), (2, None));
+        assert_eq!(Confined::<Vec<u64>, 0, { usize::MAX }>::strict_size(), (8, None));
+    }
+}
@@ -0,0 +1,568 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Skipping of the encoded values without decoding them, driven by the layout
+//! of their types.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::bitset::byte_len;
+use crate::layout::{layout_dumb, layout_of, Fields, LayoutNode, LayoutVariant, TypeHeader};
+use crate::reader::decoded_name;
+use crate::varint::{read_compact_size, read_leb128};
+use crate::{
+    io, DecodeError, KnownVariants, Primitive, RawEncoding, ReadRaw, Sizing, StrictDecode,
+    StrictDumb, StrictEncode, StrictType, TypeName, TypedRead, VariantName,
+};
+
+/// Skipping over the strict encoding of a value, provided for all strict types
+/// which can be encoded.
+pub trait StrictSkip: StrictEncode + StrictDumb {
+    /// Advances the reader past the encoding of a value of the type, using only
+    /// the length prefixes of the collections and the fixed sizes of the other
+    /// values. Runs of fixed-size data, like byte strings, are skipped at once
+    /// with [`ReadRaw::skip_raw`], without copying or allocating them.
+    ///
+    /// The data are checked only as much as needed to find the end of the
+    /// value: length prefixes are checked against the bounds of their
+    /// collections, union tags must be known and variable-length integers must
+    /// be minimal, while strings are not checked to be valid and enum tags,
+    /// bit set padding, set and map orders are not checked at all. Thus data
+    /// skipped successfully may still fail to decode. The skipped data count
+    /// towards the confinement limit of the reader, and nested values towards
+    /// its depth limit, as for decoding; they don't count towards the
+    /// allocation budget, since nothing is allocated for them.
+    ///
+    /// The layout of the type driving the skipping is computed on the first
+    /// call; with `std` feature it is cached for the later calls.
+    fn strict_skip(reader: &mut impl TypedRead) -> Result<(), DecodeError> {
+        let node = skip_node::<Self>();
+        Skipper {
+            type_name: decoded_name::<Self>,
+        }
+        .skip(&node, None, reader)
+    }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictSkip for T {}
+
+/// Field of type `T` which is skipped instead of being decoded; see
+/// [`crate::ReadStruct::skip_field`].
+pub(crate) struct Skipped<T>(PhantomData<T>);
+
+impl<T: StrictType> StrictType for Skipped<T> {
+    const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+}
+impl<T: StrictEncode + StrictDumb> StrictDecode for Skipped<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        T::strict_skip(reader)?;
+        Ok(Skipped(PhantomData))
+    }
+}
+
+fn layout<T: StrictEncode + StrictDumb>() -> SkipNode { layout_of(&layout_dumb::<T>()) }
+
+#[cfg(not(feature = "std"))]
+fn skip_node<T: StrictEncode + StrictDumb>() -> Cow<'static, SkipNode> { Cow::Owned(layout::<T>()) }
+
+/// Returns the layout of type `T`, computing it once per type.
+#[cfg(feature = "std")]
+fn skip_node<T: StrictEncode + StrictDumb>() -> Cow<'static, SkipNode> {
+    use std::collections::BTreeMap;
+    use std::sync::{PoisonError, RwLock};
+
+    static CACHE: RwLock<BTreeMap<usize, &'static SkipNode>> = RwLock::new(BTreeMap::new());
+
+    // `TypeId` is not available for the types with lifetimes, thus the types
+    // are told apart by the addresses of their `layout` functions. The
+    // functions of different types share the address only if they are
+    // compiled into the same code, which returns the same layout.
+    let key = layout::<T> as fn() -> SkipNode as usize;
+    if let Some(node) = CACHE.read().unwrap_or_else(PoisonError::into_inner).get(&key) {
+        return Cow::Borrowed(node);
+    }
+    let node = layout::<T>();
+    let mut cache = CACHE.write().unwrap_or_else(PoisonError::into_inner);
+    Cow::Borrowed(cache.entry(key).or_insert_with(|| Box::leak(Box::new(node))))
+}
+
+#[derive(Clone, Debug)]
+enum SkipNode {
+    /// Values of a fixed size, including fixed-size sequences of them.
+    Fixed(usize),
+    /// Collections and strings prefixed with their length.
+    Repeat(Box<SkipNode>, Sizing),
+    Array(Box<SkipNode>, u16),
+    Sequence(Vec<SkipNode>),
+    Union(TypeHeader, Vec<(u8, VariantName, SkipNode)>),
+    /// Tuples and structures, which are kept to resolve recursive nodes
+    /// nested into them.
+    Product(TypeHeader, Box<SkipNode>),
+    Recursive(TypeHeader),
+    Raw(TypeHeader, RawEncoding),
+}
+
+impl SkipNode {
+    /// Node for the sequence of `items`, merging fixed-size items.
+    fn seq(items: Vec<SkipNode>) -> Self {
+        let mut nodes = Vec::with_capacity(items.len());
+        for item in items {
+            match (nodes.last_mut(), item) {
+                (_, SkipNode::Fixed(0)) => {}
+                (Some(SkipNode::Fixed(len)), SkipNode::Fixed(next)) => {
+                    *len = len.saturating_add(next)
+                }
+                (_, SkipNode::Sequence(inner)) => nodes.extend(inner),
+                (_, item) => nodes.push(item),
+            }
+        }
+        match nodes.len() {
+            0 => SkipNode::Fixed(0),
+            1 => nodes.remove(0),
+            _ => SkipNode::Sequence(nodes),
+        }
+    }
+
+    fn fields(fields: Fields<Self>) -> Self {
+        SkipNode::seq(fields.into_iter().map(|(_, node)| node).collect())
+    }
+
+    /// Tuples and structures of a fixed size can't contain recursive nodes and
+    /// are skipped as plain data.
+    fn product(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        match SkipNode::fields(fields) {
+            SkipNode::Fixed(len) => SkipNode::Fixed(len),
+            node => SkipNode::Product(ty.clone(), Box::new(node)),
+        }
+    }
+
+    fn header(&self) -> Option<&TypeHeader> {
+        match self {
+            SkipNode::Union(ty, _) | SkipNode::Product(ty, _) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+impl LayoutNode for SkipNode {
+    fn primitive(prim: Primitive) -> Self {
+        SkipNode::Fixed(match prim {
+            Primitive::UNIT => 0,
+            Primitive::BYTE => 1,
+            prim => prim.byte_size() as usize,
+        })
+    }
+
+    fn unicode(sizing: Sizing) -> Self { SkipNode::Repeat(Box::new(SkipNode::Fixed(1)), sizing) }
+
+    fn rstring(_first: Self, _rest: Self, sizing: Sizing) -> Self {
+        SkipNode::Repeat(Box::new(SkipNode::Fixed(1)), sizing)
+    }
+
+    fn array(item: Self, len: u16) -> Self {
+        match item {
+            SkipNode::Fixed(size) => SkipNode::Fixed(size.saturating_mul(len as usize)),
+            item => SkipNode::Array(Box::new(item), len),
+        }
+    }
+
+    fn list(item: Self, sizing: Sizing) -> Self { SkipNode::Repeat(Box::new(item), sizing) }
+
+    fn set(item: Self, sizing: Sizing) -> Self { SkipNode::Repeat(Box::new(item), sizing) }
+
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self {
+        SkipNode::Repeat(Box::new(SkipNode::seq(vec![key, value])), sizing)
+    }
+
+    fn sequence(items: Vec<Self>) -> Self { SkipNode::seq(items) }
+
+    fn enumeration(_ty: &TypeHeader, _variants: &'static [(u8, &'static str)]) -> Self {
+        SkipNode::Fixed(1)
+    }
+
+    fn union(ty: &TypeHeader, variants: Vec<LayoutVariant<Self>>) -> Self {
+        let variants = variants
+            .into_iter()
+            .map(|variant| (variant.tag, variant.name, SkipNode::fields(variant.fields)))
+            .collect();
+        SkipNode::Union(ty.clone(), variants)
+    }
+
+    fn tuple(ty: &TypeHeader, fields: Fields<Self>) -> Self { SkipNode::product(ty, fields) }
+
+    fn structure(ty: &TypeHeader, fields: Fields<Self>) -> Self { SkipNode::product(ty, fields) }
+
+    fn recursive(ty: &TypeHeader) -> Self { SkipNode::Recursive(ty.clone()) }
+
+    fn raw(ty: &TypeHeader, encoding: RawEncoding) -> Self { SkipNode::Raw(ty.clone(), encoding) }
+}
+
+/// Union or product node containing the currently skipped node, linked to
+/// the nodes containing it in turn.
+struct Outer<'a> {
+    node: &'a SkipNode,
+    outer: Option<&'a Outer<'a>>,
+}
+
+/// Walks the layout of the skipped type along the data.
+struct Skipper {
+    /// Name of the skipped type, reported for the collections which don't
+    /// have names on their own.
+    type_name: fn() -> String,
+}
+
+impl Skipper {
+    fn skip(
+        &self,
+        node: &SkipNode,
+        outer: Option<&Outer>,
+        reader: &mut impl TypedRead,
+    ) -> Result<(), DecodeError> {
+        match node {
+            SkipNode::Fixed(len) => skip_raw(reader, *len),
+            SkipNode::Repeat(item, sizing) => {
+                let len = sizing.read_len(unsafe { reader.raw_reader() }, self.type_name)?;
+                if let SkipNode::Fixed(size) = **item {
+                    let total = size
+                        .checked_mul(len)
+                        .ok_or_else(|| io::Error::from(io::ErrorKind::OutOfMemory))?;
+                    return skip_raw(reader, total);
+                }
+                for _ in 0..len {
+                    reader.in_depth(|r| self.skip(item, outer, r))?;
+                }
+                Ok(())
+            }
+            SkipNode::Array(item, len) => {
+                for _ in 0..*len {
+                    self.skip(item, outer, reader)?;
+                }
+                Ok(())
+            }
+            SkipNode::Sequence(items) => {
+                for item in items {
+                    self.skip(item, outer, reader)?;
+                }
+                Ok(())
+            }
+            SkipNode::Union(ty, variants) => {
+                let [tag] = unsafe { reader.raw_reader() }.read_raw_array::<1>()?;
+                let Some((_, _, fields)) = variants.iter().find(|(t, ..)| *t == tag) else {
+                    return Err(DecodeError::UnionTagNotKnown {
+                        type_name: self.name_of(ty),
+                        tag,
+                        variants: KnownVariants(
+                            variants.iter().map(|(tag, name, _)| (*tag, name.clone())).collect(),
+                        ),
+                    });
+                };
+                self.nested(node, fields, outer, reader)
+            }
+            SkipNode::Product(_, fields) => self.nested(node, fields, outer, reader),
+            SkipNode::Recursive(ty) => {
+                let mut outer = outer;
+                while let Some(frame) = outer {
+                    if frame.node.header() == Some(ty) {
+                        return self.skip(frame.node, frame.outer, reader);
+                    }
+                    outer = frame.outer;
+                }
+                unreachable!("recursive node is nested into its type")
+            }
+            SkipNode::Raw(ty, encoding) => {
+                let raw = unsafe { reader.raw_reader() };
+                match encoding {
+                    RawEncoding::Leb128 => read_leb128(raw, || self.name_of(ty)).map(drop),
+                    RawEncoding::CompactSize => {
+                        read_compact_size(raw, || self.name_of(ty)).map(drop)
+                    }
+                    RawEncoding::Bitset(sizing) => {
+                        let len = sizing.read_len(raw, || self.name_of(ty))?;
+                        skip_raw(reader, byte_len(len))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Skips the fields of a union variant or a product `node`.
+    fn nested(
+        &self,
+        node: &SkipNode,
+        fields: &SkipNode,
+        outer: Option<&Outer>,
+        reader: &mut impl TypedRead,
+    ) -> Result<(), DecodeError> {
+        let outer = Outer { node, outer };
+        reader.in_depth(|r| self.skip(fields, Some(&outer), r))
+    }
+
+    fn name_of(&self, ty: &TypeHeader) -> String {
+        match &ty.name {
+            Some(name) => name.to_string(),
+            None => (self.type_name)(),
+        }
+    }
+}
+
+fn skip_raw(reader: &mut impl TypedRead, len: usize) -> Result<(), DecodeError> {
+    if len == 0 {
+        return Ok(());
+    }
+    unsafe { reader.raw_reader() }.skip_raw(len).map_err(DecodeError::from)
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt::Debug;
+
+    use amplify::confinement::{Confined, MediumBlob, TinyOrdMap, TinyString, TinyVec};
+
+    use super::*;
+    use crate::stl::{AlphaCaps, AlphaNumDash};
+    use crate::test::{count_allocations, decode_err, encode};
+    use crate::{
+        ChainReader, CompactSizeU64, RString, StrictReader, StrictWriter, TinyBitset, VarIntU64,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Profile {
+        id: u32,
+        name: TinyString,
+        note: Option<Confined<String, 0, 300>>,
+        keys: TinyVec<[u8; 2]>,
+        tags: TinyOrdMap<u8, TinyString>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Message {
+        #[default]
+        Ping,
+        Data(u16, u32),
+        Text {
+            body: TinyString,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Tree {
+        value: u8,
+        children: TinyVec<Tree>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        id: u64,
+        payload: MediumBlob,
+        timestamp: i64,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Packet {
+        len: VarIntU64,
+        count: CompactSizeU64,
+        flags: TinyBitset,
+        checksum: u16,
+    }
+
+    fn tree(depth: usize) -> Tree {
+        let children = (0..depth.min(2)).map(|_| tree(depth - 1)).collect::<Vec<_>>();
+        Tree {
+            value: depth as u8,
+            children: TinyVec::try_from(children).unwrap(),
+        }
+    }
+
+    fn record(len: usize) -> Record {
+        Record {
+            id: 0xCAFE,
+            payload: MediumBlob::try_from(vec![0xAB; len]).unwrap(),
+            timestamp: -1,
+        }
+    }
+
+    fn encode_large(val: &impl StrictEncode) -> Vec<u8> {
+        val.strict_encode(StrictWriter::in_memory::<{ usize::MAX }>()).unwrap().unbox().unconfine()
+    }
+
+    /// Checks that skipping the value followed by a marker byte consumes
+    /// exactly the encoding of the value with each of the readers.
+    fn check<T: StrictEncode + StrictDumb + Debug + PartialEq>(val: &T) {
+        let mut data = encode(val);
+        data.push(0xA5);
+
+        check_with::<T, _>(StrictReader::from_slice::<{ usize::MAX }>(&data));
+        check_with::<T, _>(StrictReader::in_memory::<{ usize::MAX }>(&data));
+        check_with::<T, _>(StrictReader::with(ChainReader::new::<{ usize::MAX }>(data.chunks(3))));
+        #[cfg(feature = "std")]
+        check_with::<T, _>(StrictReader::from_buf_read::<{ usize::MAX }>(
+            std::io::BufReader::with_capacity(4, data.as_slice()),
+        ));
+    }
+
+    fn check_with<T: StrictEncode + StrictDumb, R: ReadRaw>(mut reader: StrictReader<R>) {
+        reader.skip::<T>().unwrap();
+        assert_eq!(u8::strict_decode(&mut reader).unwrap(), 0xA5);
+        reader.finish().unwrap();
+    }
+
+    #[test]
+    fn consumed() {
+        check(&0xCAFEu32);
+        check(&());
+        check(&[0x1234u16; 3]);
+        check(&(7u8, tiny_s!("seven"), [tiny_s!("a"), tiny_s!("bc")]));
+        check(&None::<Option<u8>>);
+        check(&Some(Some(5u8)));
+        check(&Some(None::<u8>));
+        check(&Profile::default());
+        check(&Profile {
+            id: 1,
+            name: tiny_s!("name"),
+            note: Some(Confined::try_from("x".repeat(300)).unwrap()),
+            keys: TinyVec::try_from(vec![[1, 2], [3, 4]]).unwrap(),
+            tags: TinyOrdMap::try_from_iter([(1, tiny_s!("one")), (2, tiny_s!("two"))]).unwrap(),
+        });
+        check(&Message::Ping);
+        check(&Message::Data(1, 2));
+        check(&Message::Text {
+            body: tiny_s!("text"),
+        });
+        check(&TinyVec::try_from(vec![Message::Ping, Message::Data(3, 4)]).unwrap());
+        check(&tree(0));
+        check(&tree(4));
+        check(&RString::<AlphaCaps, AlphaNumDash, 1, 8>::from("IDENT-1"));
+        check(&record(1000));
+        check(&VarIntU64::from(300));
+        check(&Packet::default());
+        check(&Packet {
+            len: VarIntU64::from(u64::MAX),
+            count: CompactSizeU64::from(0x1_0000),
+            flags: TinyBitset::try_from([true; 11].as_slice()).unwrap(),
+            checksum: 7,
+        });
+    }
+
+    #[test]
+    fn raw() {
+        // non-minimal LEB128 integer fails as when decoding
+        let data = [0x80, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        let err = reader.skip::<Packet>().unwrap_err();
+        assert_eq!(err, decode_err::<Packet>(data));
+        assert!(matches!(err, DecodeError::NonMinimalVarInt { .. }));
+    }
+
+    #[test]
+    fn not_allocating() {
+        let data = encode(&tree(4));
+        // the first skipping computes the layout of the type
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        reader.skip::<Tree>().unwrap();
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        let (res, allocs) = count_allocations(|| reader.skip::<Tree>());
+        res.unwrap();
+        assert_eq!(allocs, 0);
+    }
+
+    #[test]
+    fn blob_not_allocated() {
+        let data = encode_large(&record(1 << 20));
+
+        let mut reader = StrictReader::from_slice::<{ usize::MAX }>(&data).with_alloc_limit(0);
+        reader.skip::<Record>().unwrap();
+        reader.finish().unwrap();
+
+        let mut reader = StrictReader::from_slice::<{ usize::MAX }>(&data).with_alloc_limit(0);
+        assert_eq!(
+            Record::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::AllocationBudgetExceeded {
+                budget: 0,
+                attempted: 1 << 20,
+            }
+        );
+    }
+
+    #[test]
+    fn hostile_len() {
+        // the prefix announces 16 MiB, which is within the bounds of the blob
+        // and the data continue beyond the confinement limit of the readers
+        let mut data = encode(&record(0));
+        data[8..11].copy_from_slice(&[0xFF; 3]);
+        data.resize(4096, 0);
+
+        // nothing is read beyond the prefix
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        let err = reader.skip::<Record>().unwrap_err();
+        assert!(
+            matches!(err, DecodeError::Io(ref err) if err.kind() == io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(reader.unbox().offset(), Some(11));
+        let mut reader = StrictReader::in_memory::<1024>(&data);
+        let err = reader.skip::<Record>().unwrap_err();
+        assert!(
+            matches!(err, DecodeError::Io(ref err) if err.kind() == io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(reader.unbox().offset(), Some(11));
+
+        // lengths beyond the bound are rejected as they are when decoding
+        type Bytes = Confined<Vec<u8>, 0, 10>;
+        let data = [11u8; 12];
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        let err = reader.skip::<Bytes>().unwrap_err();
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        assert_eq!(err, Bytes::strict_decode(&mut reader).unwrap_err());
+    }
+
+    #[test]
+    fn unknown_tag() {
+        let data = [3u8, 0, 0];
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        let err = reader.skip::<Message>().unwrap_err();
+        let mut reader = StrictReader::from_slice::<1024>(&data);
+        assert_eq!(err, Message::strict_decode(&mut reader).unwrap_err());
+    }
+
+    #[test]
+    fn depth_limit() {
+        let data = encode(&tree(6));
+        let mut reader = StrictReader::from_slice::<1024>(&data).with_depth_limit(6);
+        let err = reader.skip::<Tree>().unwrap_err();
+        let mut reader = StrictReader::from_slice::<1024>(&data).with_depth_limit(6);
+        assert_eq!(err, Tree::strict_decode(&mut reader).unwrap_err());
+        assert!(matches!(err, DecodeError::MaxRecursionDepth { limit: 6 }));
+    }
+}
@@ -0,0 +1,75 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of the stack-allocated vectors: [`SmallVec`], available
+//! with `smallvec` feature, and [`ArrayVec`], available with `arrayvec`
+//! feature.
+//!
+//! Both are encoded exactly as `Confined<Vec<T>, MIN_LEN, MAX_LEN>` with the
+//! same items, i.e. as lists with the length prefix which width is defined by
+//! the maximal length:
+//! - [`SmallVec`] must be confined to be encoded, like all other collections; since
+//!   [`Confined`](amplify::confinement::Confined) can't wrap it, [`ConfinedSmallVec`] is used
+//!   instead;
+//! - [`ArrayVec`] is confined by its capacity, thus `ArrayVec<T, CAP>` is encoded as
+//!   `Confined<Vec<T>, 0, CAP>`. Length prefixes exceeding the capacity are rejected during
+//!   decoding before any of the items are read.
+//!
+//! The dumb values of both types are empty; [`ConfinedSmallVec`] with a
+//! non-zero minimal length is filled with the minimal number of dumb items by
+//! [`ConfinedDumb`](crate::ConfinedDumb).
+//!
+//! [`SmallVec`]: ::smallvec::SmallVec
+//! [`ArrayVec`]: ::arrayvec::ArrayVec
+
+use crate::layout::layout_dumb;
+use crate::{io, Byte, Sizing, StrictDumb, StrictEncode, StrictType, TypedWrite, WriteRaw};
+
+#[cfg(feature = "arrayvec")]
+mod array_vec;
+#[cfg(feature = "smallvec")]
+mod small_vec;
+
+#[cfg(feature = "smallvec")]
+pub use self::small_vec::ConfinedSmallVec;
+
+/// Encodes the items as a list confined to `MIN_LEN..=MAX_LEN` items, like
+/// `Confined<Vec<T>, MIN_LEN, MAX_LEN>` does.
+fn encode_list<
+    T: StrictEncode + StrictDumb,
+    W: TypedWrite,
+    const MIN_LEN: usize,
+    const MAX_LEN: usize,
+>(
+    items: &[T],
+    mut writer: W,
+) -> io::Result<W> {
+    let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+    unsafe {
+        writer.raw_writer().write_raw_len::<MAX_LEN>(items.len())?;
+        writer = writer.write_items(items)?;
+        Ok(if T::strict_name() == u8::strict_name() {
+            writer.register_list(&Byte::strict_dumb(), sizing)
+        } else {
+            writer.register_list(&layout_dumb::<T>(), sizing)
+        })
+    }
+}
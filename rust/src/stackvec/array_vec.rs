@@ -0,0 +1,94 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`ArrayVec`], confined by its capacity.
+
+use arrayvec::ArrayVec;
+
+use super::encode_list;
+use crate::embedded::decode_item;
+use crate::reader::list_name;
+use crate::{
+    io, DecodeError, InvalidLength, StrictDecode, StrictDumb, StrictEncode, StrictType, TypeName,
+    TypedRead, TypedWrite, LIB_EMBEDDED,
+};
+
+impl<T: StrictType, const CAP: usize> StrictType for ArrayVec<T, CAP> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<T>(0, CAP) }
+}
+impl<T: StrictEncode + StrictDumb, const CAP: usize> StrictEncode for ArrayVec<T, CAP> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, 0, CAP>(self.len())?;
+        encode_list::<_, _, 0, CAP>(self.as_slice(), writer)
+    }
+}
+impl<T: StrictDecode, const CAP: usize> StrictDecode for ArrayVec<T, CAP> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, 0, CAP>()? };
+        let mut vec = ArrayVec::new();
+        for index in 0..len {
+            vec.push(decode_item(reader, index)?);
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, U8};
+
+    use super::*;
+    use crate::test::{encode, encoding_roundtrip};
+    use crate::StrictReader;
+
+    #[test]
+    fn arrayvec() {
+        let items = [0xAABBu16, 1, 2];
+        let vec = Confined::<Vec<u16>, 0, 4>::try_from_iter(items).unwrap();
+        let array = ArrayVec::<u16, 4>::from_iter(items);
+        assert_eq!(encode(&array), encode(&vec));
+        encoding_roundtrip(&array);
+
+        let vec = Confined::<Vec<u8>, 0, 300>::try_from_iter([7; 260]).unwrap();
+        let array = ArrayVec::<u8, 300>::from_iter([7; 260]);
+        assert_eq!(encode(&array), encode(&vec));
+        assert_eq!(encode(&array)[..2], [4, 1]);
+        encoding_roundtrip(&array);
+
+        assert_eq!(encode(&ArrayVec::<u8, { U8 }>::strict_dumb()), [0]);
+    }
+
+    #[test]
+    fn arrayvec_oversize() {
+        // No items follow the prefix: it must be rejected before they are read
+        let mut reader = StrictReader::in_memory::<8>([5]);
+        assert!(matches!(
+            ArrayVec::<u8, 4>::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::Oversize {
+                declared_len: 5,
+                max_len: 4,
+                ..
+            }
+        ));
+    }
+}
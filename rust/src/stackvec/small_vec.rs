@@ -0,0 +1,184 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`SmallVec`] confined by [`ConfinedSmallVec`].
+
+use core::fmt::{self, Debug, Formatter};
+use core::iter;
+use core::ops::Deref;
+
+use amplify::confinement;
+use smallvec::{Array, SmallVec};
+
+use super::encode_list;
+use crate::embedded::{alloc_items, decode_item, prealloc};
+use crate::reader::list_name;
+use crate::{
+    io, ConfinedDumb, DecodeError, InvalidLength, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictType, TypeName, TypedRead, TypedWrite, LIB_EMBEDDED,
+};
+
+/// [`SmallVec`] which number of items is confined to be between `MIN_LEN` and
+/// `MAX_LEN`.
+///
+/// The number of items is checked when the vector is constructed.
+pub struct ConfinedSmallVec<A: Array, const MIN_LEN: usize, const MAX_LEN: usize>(SmallVec<A>);
+
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedSmallVec<A, MIN_LEN, MAX_LEN> {
+    pub fn as_inner(&self) -> &SmallVec<A> { &self.0 }
+
+    pub fn release(self) -> SmallVec<A> { self.0 }
+}
+
+// `SmallVec` implements the traits only if the items do, thus they can't be
+// derived
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> Clone
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: Clone
+{
+    fn clone(&self) -> Self { ConfinedSmallVec(self.0.clone()) }
+}
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> PartialEq
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+}
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> Eq
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: Eq
+{
+}
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> Debug
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: Debug
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConfinedSmallVec").field(&self.0).finish()
+    }
+}
+
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> Deref
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+{
+    type Target = SmallVec<A>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<A: Array, const MAX_LEN: usize> Default for ConfinedSmallVec<A, 0, MAX_LEN> {
+    fn default() -> Self { ConfinedSmallVec(SmallVec::new()) }
+}
+
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> TryFrom<SmallVec<A>>
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+{
+    type Error = confinement::Error;
+    fn try_from(vec: SmallVec<A>) -> Result<Self, Self::Error> {
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_confined(vec.len())?;
+        Ok(ConfinedSmallVec(vec))
+    }
+}
+
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> StrictType
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: StrictType
+{
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+    fn strict_anonymous_name() -> String { list_name::<A::Item>(MIN_LEN, MAX_LEN) }
+}
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> StrictEncode
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: StrictEncode + StrictDumb
+{
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        InvalidLength::check::<Self, MIN_LEN, MAX_LEN>(self.len())?;
+        encode_list::<_, _, MIN_LEN, MAX_LEN>(self.as_slice(), writer)
+    }
+}
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> StrictDecode
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: StrictDecode
+{
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = unsafe { reader.read_len::<Self, MIN_LEN, MAX_LEN>()? };
+        alloc_items::<A::Item>(reader, len)?;
+        let mut vec = SmallVec::<A>::with_capacity(prealloc::<A::Item>(len));
+        for index in 0..len {
+            vec.push(decode_item(reader, index)?);
+        }
+        Self::try_from(vec).map_err(DecodeError::from)
+    }
+}
+
+impl<A: Array, const MIN_LEN: usize, const MAX_LEN: usize> ConfinedDumb
+    for ConfinedSmallVec<A, MIN_LEN, MAX_LEN>
+where A::Item: StrictDumb
+{
+    fn confined_dumb() -> Self {
+        Self::try_from(SmallVec::from_iter(iter::repeat_with(A::Item::strict_dumb).take(MIN_LEN)))
+            .expect("minimal length exceeds maximal length")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, U16};
+
+    use super::*;
+    use crate::test::{encode, encoding_roundtrip};
+    use crate::StrictReader;
+
+    #[test]
+    fn smallvec() {
+        fn check<A: Array>(items: impl Clone + IntoIterator<Item = A::Item>)
+        where A::Item: StrictEncode + StrictDecode + StrictDumb + PartialEq + Debug {
+            let vec = Confined::<Vec<A::Item>, 0, U16>::try_from_iter(items.clone()).unwrap();
+            let small =
+                ConfinedSmallVec::<A, 0, U16>::try_from(SmallVec::from_iter(items)).unwrap();
+            assert_eq!(encode(&small), encode(&vec));
+            encoding_roundtrip(&small);
+        }
+
+        check::<[u8; 32]>([]);
+        check::<[u8; 32]>([1, 2, 3]);
+        check::<[u8; 2]>([0xFF; 40]);
+        check::<[u32; 4]>([0xAABBCCDD, 7, 0]);
+    }
+
+    #[test]
+    fn smallvec_confinement() {
+        type Small = ConfinedSmallVec<[u8; 4], 1, 2>;
+        assert_eq!(Small::try_from(SmallVec::new()).unwrap_err(), confinement::Error::Undersize {
+            len: 0,
+            min_len: 1
+        });
+        assert_eq!(Small::confined_dumb().as_slice(), &[0]);
+        assert_eq!(ConfinedSmallVec::<[u8; 4], 0, 2>::strict_dumb().len(), 0);
+
+        let mut reader = StrictReader::in_memory::<8>([3, 1, 2, 3]);
+        assert!(matches!(Small::strict_decode(&mut reader).unwrap_err(), DecodeError::Oversize {
+            declared_len: 3,
+            max_len: 2,
+            ..
+        }));
+    }
+}
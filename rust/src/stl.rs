@@ -21,12 +21,16 @@
 
 #![allow(non_camel_case_types, unused_imports)]
 
-use std::borrow::Borrow;
-use std::fmt::{Debug, Display, Formatter};
-use std::marker::PhantomData;
-use std::ops::Deref;
-use std::str::FromStr;
-use std::{any, io};
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any;
+use core::borrow::Borrow;
+use core::fmt::{Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::str::FromStr;
 
 use amplify::ascii::{AsAsciiStrError, AsciiChar, AsciiString, FromAsciiError};
 use amplify::confinement;
@@ -34,13 +38,18 @@ use amplify::confinement::Confined;
 use amplify::num::{u1, u2, u3, u4, u5, u6, u7};
 
 use crate::{
-    type_name, DecodeError, StrictDecode, StrictDumb, StrictEncode, StrictEnum, StrictSum,
-    StrictType, TypeName, TypedRead, TypedWrite, VariantError, LIB_NAME_STD,
+    io, type_name, BigEndian, ByteArray, CompactSizeU64, DecodeError, EncodingBanner, FieldName,
+    Ident, LibName, Primitive, ReadTuple, Sizing, StrictDecode, StrictDumb, StrictEncode,
+    StrictEnum, StrictProduct, StrictSum, StrictTuple, StrictType, StrictTypeInfo, TypeLib,
+    TypeName, TypedRead, TypedWrite, VarIntU64, Variant, VariantError, VariantName, LIB_NAME_STD,
+    STRICT_TYPES_LIB,
 };
+#[cfg(feature = "sha2")]
+use crate::{SemId, StrictSemId};
 
 // TODO: Move RString and related ASCII types to amplify library
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, From)]
 #[display(doc_comments)]
 pub enum InvalidRString {
     /// must contain at least one character.
@@ -61,6 +70,8 @@ pub enum InvalidRString {
     Confinement(confinement::Error),
 }
 
+impl_error!(InvalidRString);
+
 impl<O> From<FromAsciiError<O>> for InvalidRString {
     fn from(_: FromAsciiError<O>) -> Self { InvalidRString::NonAsciiChar }
 }
@@ -204,7 +215,7 @@ impl<C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: u
 impl<C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: usize> Debug
     for RString<C1, C, MIN, MAX>
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let c = type_name::<C>();
         let c1 = type_name::<C>();
         let c = if c == c1 { c.to_owned() } else { format!("{c1}, {c}") };
@@ -215,7 +226,7 @@ impl<C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: u
 impl<C1: RestrictedCharSet, C: RestrictedCharSet, const MIN: usize, const MAX: usize> Display
     for RString<C1, C, MIN, MAX>
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { Display::fmt(&self.s, f) }
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result { Display::fmt(&self.s, f) }
 }
 
 #[cfg(feature = "serde")]
@@ -283,16 +294,45 @@ impl StrictDecode for bool {
         let v: Bool = reader.read_enum()?;
         Ok(bool::from(v))
     }
+
+    fn strict_display(&self) -> Option<String> { Some(self.to_string()) }
+}
+
+// Unicode character is encoded as a newtype around little-endian `u32` value of
+// the unicode scalar. The dumb value (as for `Default`) is `'\0'`.
+impl StrictType for char {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Char")) }
+}
+impl StrictProduct for char {}
+impl StrictTuple for char {
+    const FIELD_COUNT: u8 = 1;
+}
+impl StrictEncode for char {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_newtype::<Self>(&(*self as u32))
+    }
+}
+impl StrictDecode for char {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| {
+            let scalar: u32 = r.read_field()?;
+            char::from_u32(scalar).ok_or(DecodeError::InvalidChar(scalar))
+        })
+    }
+
+    fn strict_display(&self) -> Option<String> { Some(self.to_string()) }
 }
 
 macro_rules! impl_u {
-    ($ty:ident, $inner:ty, $( $no:ident )+) => {
+    ($ty:ident, $inner:ty, $first:ident $( $no:ident )+) => {
         #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
         #[derive(StrictType, StrictEncode, StrictDecode)]
         #[strict_type(lib = LIB_NAME_STD, tags = repr, into_u8, try_from_u8, crate = crate)]
         #[repr(u8)]
         pub enum $ty {
             #[default]
+            $first = 0,
             $( $no ),+
         }
 
@@ -530,6 +570,9 @@ impl TryFrom<u8> for AsciiPrintable {
     type Error = VariantError<u8>;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if !(b' '..=b'~').contains(&value) {
+            return Err(VariantError::with::<AsciiPrintable>(value));
+        }
         AsciiChar::from_ascii(value)
             .map_err(|_| VariantError::with::<AsciiPrintable>(value))
             .map(Self)
@@ -1720,6 +1763,213 @@ pub enum AlphaNumLodash {
     z = b'z',
 }
 
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, tags = repr, into_u8, try_from_u8, crate = crate)]
+#[display(inner)]
+#[repr(u8)]
+pub enum AlphaSmallNum {
+    #[display("0")]
+    Zero = b'0',
+    #[display("1")]
+    One = b'1',
+    #[display("2")]
+    Two = b'2',
+    #[display("3")]
+    Three = b'3',
+    #[display("4")]
+    Four = b'4',
+    #[display("5")]
+    Five = b'5',
+    #[display("6")]
+    Six = b'6',
+    #[display("7")]
+    Seven = b'7',
+    #[display("8")]
+    Eight = b'8',
+    #[display("9")]
+    Nine = b'9',
+    #[strict_type(dumb)]
+    #[display("a")]
+    A = b'a',
+    #[display("b")]
+    B = b'b',
+    #[display("c")]
+    C = b'c',
+    #[display("d")]
+    D = b'd',
+    #[display("e")]
+    E = b'e',
+    #[display("f")]
+    F = b'f',
+    #[display("g")]
+    G = b'g',
+    #[display("h")]
+    H = b'h',
+    #[display("i")]
+    I = b'i',
+    #[display("j")]
+    J = b'j',
+    #[display("k")]
+    K = b'k',
+    #[display("l")]
+    L = b'l',
+    #[display("m")]
+    M = b'm',
+    #[display("n")]
+    N = b'n',
+    #[display("o")]
+    O = b'o',
+    #[display("p")]
+    P = b'p',
+    #[display("q")]
+    Q = b'q',
+    #[display("r")]
+    R = b'r',
+    #[display("s")]
+    S = b's',
+    #[display("t")]
+    T = b't',
+    #[display("u")]
+    U = b'u',
+    #[display("v")]
+    V = b'v',
+    #[display("w")]
+    W = b'w',
+    #[display("x")]
+    X = b'x',
+    #[display("y")]
+    Y = b'y',
+    #[display("z")]
+    Z = b'z',
+}
+
+/// Characters of the Base58 alphabet, which excludes `0`, `O`, `I` and `l`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, tags = repr, into_u8, try_from_u8, crate = crate)]
+#[display(inner)]
+#[repr(u8)]
+pub enum Base58 {
+    #[strict_type(dumb)]
+    #[display("1")]
+    One = b'1',
+    #[display("2")]
+    Two = b'2',
+    #[display("3")]
+    Three = b'3',
+    #[display("4")]
+    Four = b'4',
+    #[display("5")]
+    Five = b'5',
+    #[display("6")]
+    Six = b'6',
+    #[display("7")]
+    Seven = b'7',
+    #[display("8")]
+    Eight = b'8',
+    #[display("9")]
+    Nine = b'9',
+    #[strict_type(rename = "_A")]
+    A = b'A',
+    #[strict_type(rename = "_B")]
+    B = b'B',
+    #[strict_type(rename = "_C")]
+    C = b'C',
+    #[strict_type(rename = "_D")]
+    D = b'D',
+    #[strict_type(rename = "_E")]
+    E = b'E',
+    #[strict_type(rename = "_F")]
+    F = b'F',
+    #[strict_type(rename = "_G")]
+    G = b'G',
+    #[strict_type(rename = "_H")]
+    H = b'H',
+    #[strict_type(rename = "_J")]
+    J = b'J',
+    #[strict_type(rename = "_K")]
+    K = b'K',
+    #[strict_type(rename = "_L")]
+    L = b'L',
+    #[strict_type(rename = "_M")]
+    M = b'M',
+    #[strict_type(rename = "_N")]
+    N = b'N',
+    #[strict_type(rename = "_P")]
+    P = b'P',
+    #[strict_type(rename = "_Q")]
+    Q = b'Q',
+    #[strict_type(rename = "_R")]
+    R = b'R',
+    #[strict_type(rename = "_S")]
+    S = b'S',
+    #[strict_type(rename = "_T")]
+    T = b'T',
+    #[strict_type(rename = "_U")]
+    U = b'U',
+    #[strict_type(rename = "_V")]
+    V = b'V',
+    #[strict_type(rename = "_W")]
+    W = b'W',
+    #[strict_type(rename = "_X")]
+    X = b'X',
+    #[strict_type(rename = "_Y")]
+    Y = b'Y',
+    #[strict_type(rename = "_Z")]
+    Z = b'Z',
+    #[display("a")]
+    a = b'a',
+    #[display("b")]
+    b = b'b',
+    #[display("c")]
+    c = b'c',
+    #[display("d")]
+    d = b'd',
+    #[display("e")]
+    e = b'e',
+    #[display("f")]
+    f = b'f',
+    #[display("g")]
+    g = b'g',
+    #[display("h")]
+    h = b'h',
+    #[display("i")]
+    i = b'i',
+    #[display("j")]
+    j = b'j',
+    #[display("k")]
+    k = b'k',
+    #[display("m")]
+    m = b'm',
+    #[display("n")]
+    n = b'n',
+    #[display("o")]
+    o = b'o',
+    #[display("p")]
+    p = b'p',
+    #[display("q")]
+    q = b'q',
+    #[display("r")]
+    r = b'r',
+    #[display("s")]
+    s = b's',
+    #[display("t")]
+    t = b't',
+    #[display("u")]
+    u = b'u',
+    #[display("v")]
+    v = b'v',
+    #[display("w")]
+    w = b'w',
+    #[display("x")]
+    x = b'x',
+    #[display("y")]
+    y = b'y',
+    #[display("z")]
+    z = b'z',
+}
+
 impl RestrictedCharSet for AsciiPrintable {}
 impl RestrictedCharSet for AsciiSym {}
 impl RestrictedCharSet for Alpha {}
@@ -1732,13 +1982,188 @@ impl RestrictedCharSet for AlphaNum {}
 impl RestrictedCharSet for AlphaNumDash {}
 impl RestrictedCharSet for AlphaNumLodash {}
 impl RestrictedCharSet for AlphaCapsNum {}
+impl RestrictedCharSet for AlphaSmallNum {}
+impl RestrictedCharSet for Base58 {}
 impl RestrictedCharSet for Dec {}
 impl RestrictedCharSet for HexDecCaps {}
 impl RestrictedCharSet for HexDecSmall {}
 
+macro_rules! type_lib {
+    (
+        $(#[$doc:meta])*
+        fn $lib_fn:ident, $ids_fn:ident = $lib:ident;
+        $($(#[$cfg:meta])* $ty:ty),+ $(,)?
+    ) => {
+        $(#[$doc])*
+        pub fn $lib_fn() -> TypeLib {
+            let mut lib = TypeLib::new(libname!($lib));
+            $(
+                $(#[$cfg])*
+                lib.insert(<$ty as StrictTypeInfo>::type_info())
+                    .expect("library types have unique names");
+            )+
+            lib
+        }
+
+        #[doc = concat!("Returns semantic ids of the types of [`", stringify!($lib_fn), "`] by their names.")]
+        #[cfg(feature = "sha2")]
+        pub fn $ids_fn() -> BTreeMap<TypeName, SemId> {
+            let mut ids = BTreeMap::new();
+            $(
+                $(#[$cfg])*
+                ids.insert(
+                    <$ty>::strict_name().expect("library types are named"),
+                    <$ty as StrictSemId>::sem_id(),
+                );
+            )+
+            ids
+        }
+    };
+}
+
+type_lib!(
+    /// Returns the library of the named types defined by this crate, which
+    /// belong to [`LIB_NAME_STD`] library.
+    ///
+    /// The library lists booleans, characters, small unsigned integers,
+    /// character sets of [`RString`]s, common byte arrays, big-endian
+    /// integers, variable-length integers, durations and orderings, together
+    /// with the network, path, time, calendar, UUID, semantic version and URL
+    /// types enabled by the crate features, such that type libraries using
+    /// these types may import them instead of defining their own. Generic types
+    /// with names depending on their parameters, like
+    /// [`Bounded`](crate::Bounded), and uninhabited types are not included.
+    fn std_lib, std_sem_ids = LIB_NAME_STD;
+    Bool,
+    char,
+    U1,
+    U2,
+    U3,
+    U4,
+    U5,
+    U6,
+    U7,
+    AsciiSym,
+    AsciiPrintable,
+    Alpha,
+    AlphaLodash,
+    AlphaCaps,
+    AlphaCapsLodash,
+    AlphaSmall,
+    AlphaSmallLodash,
+    AlphaNum,
+    AlphaNumDash,
+    AlphaNumLodash,
+    AlphaCapsNum,
+    AlphaSmallNum,
+    Base58,
+    Dec,
+    HexDecCaps,
+    HexDecSmall,
+    ByteArray<16>,
+    ByteArray<20>,
+    ByteArray<32>,
+    ByteArray<64>,
+    BigEndian<u16>,
+    BigEndian<u32>,
+    BigEndian<u64>,
+    BigEndian<u128>,
+    BigEndian<i16>,
+    BigEndian<i32>,
+    BigEndian<i64>,
+    BigEndian<i128>,
+    VarIntU64,
+    CompactSizeU64,
+    core::time::Duration,
+    crate::cmp::Order,
+    EncodingBanner,
+    #[cfg(feature = "std")]
+    crate::time::UnixTime,
+    #[cfg(feature = "std")]
+    crate::net::Ipv4,
+    #[cfg(feature = "std")]
+    crate::net::Ipv6,
+    #[cfg(feature = "std")]
+    crate::net::Ip,
+    #[cfg(feature = "std")]
+    crate::net::SocketV4,
+    #[cfg(feature = "std")]
+    crate::net::SocketV6,
+    #[cfg(feature = "std")]
+    crate::net::Socket,
+    #[cfg(feature = "std")]
+    std::path::PathBuf,
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    crate::calendar::Timestamp,
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    crate::calendar::Date,
+    #[cfg(feature = "uuid")]
+    ::uuid::Uuid,
+    #[cfg(feature = "semver")]
+    crate::semver::VersionDef,
+    #[cfg(feature = "semver")]
+    crate::semver::VersionReqString,
+    #[cfg(feature = "url")]
+    crate::url::UrlString,
+);
+
+type_lib!(
+    /// Returns the library of the types describing strict types themselves,
+    /// which belong to [`STRICT_TYPES_LIB`] library: identifiers, type,
+    /// field, variant and library names, primitives, sizings and variants.
+    fn strict_types_lib, strict_types_sem_ids = STRICT_TYPES_LIB;
+    Ident,
+    TypeName,
+    FieldName,
+    VariantName,
+    LibName,
+    Primitive,
+    Sizing,
+    Variant,
+);
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test::encoding;
+    use crate::{CharsetError, KnownVariants, StrictReader};
+
+    #[test]
+    fn char() {
+        encoding(&'A', [0x41, 0, 0, 0]);
+        encoding(&'Ю', [0x2E, 0x04, 0, 0]);
+        encoding(&'🦀', [0x80, 0xF9, 0x01, 0]);
+        assert_eq!(char::strict_dumb(), '\0');
+    }
+
+    #[test]
+    fn char_invalid() {
+        let mut reader = StrictReader::in_memory::<4>([0x00, 0xD8, 0, 0]);
+        assert_eq!(char::strict_decode(&mut reader).unwrap_err(), DecodeError::InvalidChar(0xD800));
+
+        let mut reader = StrictReader::in_memory::<4>([0x00, 0x00, 0x11, 0]);
+        assert_eq!(
+            char::strict_decode(&mut reader).unwrap_err(),
+            DecodeError::InvalidChar(0x110000)
+        );
+    }
+
+    #[test]
+    fn bool() {
+        encoding(&false, [0]);
+        encoding(&true, [1]);
+        for byte in 2..=u8::MAX {
+            let mut reader = StrictReader::in_memory::<1>([byte]);
+            assert_eq!(
+                bool::strict_decode(&mut reader).unwrap_err(),
+                DecodeError::EnumTagNotKnown {
+                    type_name: s!("Bool"),
+                    tag: byte,
+                    variants: KnownVariants(vec![(0, vname!("false")), (1, vname!("true"))]),
+                }
+            );
+        }
+    }
 
     #[test]
     fn rstring_utf8() {
@@ -1754,4 +2179,248 @@ mod test {
             InvalidRString::InvalidChar(s.to_owned(), 'н', 1)
         );
     }
+
+    #[test]
+    fn rstring_alphabets() {
+        type Handle = RString<AlphaSmall, AlphaSmallNum, 3, 16>;
+        type Memo = RString<AsciiPrintable, AsciiPrintable, 1, 64>;
+        type LegacyId = RString<Base58, Base58, 4, 44>;
+
+        let handle = Handle::from_str("alice42").unwrap();
+        assert_eq!(handle.to_string(), "alice42");
+        assert_eq!(Handle::try_from(s!("alice42")).unwrap(), handle);
+        assert_eq!(
+            Handle::from_str("4lice").unwrap_err(),
+            InvalidRString::DisallowedFirst(s!("4lice"), '4')
+        );
+        assert_eq!(
+            Handle::from_str("alice-42").unwrap_err(),
+            InvalidRString::InvalidChar(s!("alice-42"), '-', 5)
+        );
+        assert_eq!(
+            Handle::from_str("Alice").unwrap_err(),
+            InvalidRString::DisallowedFirst(s!("Alice"), 'A')
+        );
+        assert!(matches!(Handle::from_str("al"), Err(InvalidRString::Confinement(_))));
+        assert!(matches!(
+            Handle::from_str("aliceinwonderland"),
+            Err(InvalidRString::Confinement(_))
+        ));
+
+        let memo = Memo::from_str("Paid: 10 sats, thanks!").unwrap();
+        assert_eq!(memo.as_str(), "Paid: 10 sats, thanks!");
+        assert_eq!(
+            Memo::from_str("tab\tseparated").unwrap_err(),
+            InvalidRString::InvalidChar(s!("tab\tseparated"), '\t', 3)
+        );
+
+        let id = LegacyId::from_str("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").unwrap();
+        assert_eq!(id.len(), 34);
+        for ch in ['0', 'O', 'I', 'l'] {
+            let s = format!("3J9{ch}");
+            assert_eq!(LegacyId::from_str(&s).unwrap_err(), InvalidRString::InvalidChar(s, ch, 3));
+        }
+        assert_eq!(LegacyId::strict_dumb().as_str(), "1111");
+        assert_eq!(Handle::strict_dumb().as_str(), "aaa");
+    }
+
+    #[test]
+    fn rstring_decode() {
+        type Handle = RString<AlphaSmall, AlphaSmallNum, 3, 16>;
+
+        let handle = Handle::from_str("bob7").unwrap();
+        encoding(&handle, b"\x04bob7");
+
+        let decode = |data: &[u8]| {
+            let mut reader = StrictReader::in_memory::<32>(data);
+            Handle::strict_decode(&mut reader).unwrap_err()
+        };
+        assert_eq!(
+            decode(b"\x047bob"),
+            DecodeError::Charset(CharsetError {
+                byte: b'7',
+                pos: 0,
+                charset: s!("AlphaSmall"),
+            })
+        );
+        assert_eq!(
+            decode(b"\x04bo_b"),
+            DecodeError::Charset(CharsetError {
+                byte: b'_',
+                pos: 2,
+                charset: s!("AlphaSmallNum"),
+            })
+        );
+        assert_eq!(
+            decode(b"\x04bob\xFF"),
+            DecodeError::Charset(CharsetError {
+                byte: 0xFF,
+                pos: 3,
+                charset: s!("AlphaSmallNum"),
+            })
+        );
+        assert_eq!(
+            decode(b"\x04bo_b").to_string(),
+            "string contains byte 0x5f at position 2, which is not a valid character of \
+             `AlphaSmallNum` set."
+        );
+        assert!(matches!(decode(b"\x02bo"), DecodeError::Undersize {
+            declared_len: 2,
+            min_len: 3,
+            max_len: 16,
+            ..
+        }));
+        assert!(matches!(decode(b"\x11bobbobbobbobbobbo"), DecodeError::Oversize {
+            declared_len: 17,
+            max_len: 16,
+            ..
+        }));
+    }
+
+    #[test]
+    fn std_lib_types() {
+        let lib = std_lib();
+        assert_eq!(lib.name(), &libname!(LIB_NAME_STD));
+        let golden = std_lib_golden();
+        let names = golden.iter().map(|line| line.split(' ').next().unwrap());
+        assert!(lib.iter().map(|(name, _)| name.as_str()).eq(names));
+        assert!(lib.unresolved().is_empty());
+        assert!(lib.dependencies().is_empty());
+        assert_eq!(
+            lib.get(&tn!("Duration")).unwrap().to_string(),
+            "Std.Duration: struct { secs: U64, nanos: U32 }"
+        );
+        assert_eq!(lib.get(&tn!("U32Be")).unwrap().to_string(), "Std.U32Be: tuple(1)");
+    }
+
+    const GOLDEN: &str = "
+        Alpha 26fb616f7b01d83206096abc769a0dcce034c15be40faf368cd6742cf14d2b68
+        AlphaCaps 44356ad8b32e86221d58db399f90c252407512e709dd51014db793d7fdff4e0e
+        AlphaCapsLodash 752746c9105e811d88f85f60fd19208539bcb7ff879d93b57bc6dff3e3e0def8
+        AlphaCapsNum d6d5d66d525e680d14f5fea4657ec8481b3af4408ca39b903b78899e223a78ca
+        AlphaLodash adfa3ad14cc8290a066687fffbe14ef3ac96a0753d33232aaecd6be76fbe7605
+        AlphaNum e1654b36dbca77b11d96436486d033a6ca8f4134d3ad0e240a06274a5bb96e57
+        AlphaNumDash d1c9f4d4f164fd1916c7ed5ac94e8c5089633574c9afc9743bc0269893ab85eb
+        AlphaNumLodash 7c6e6c0914c07192e43df8c08a5589c29d716ae500a3e9f2cc80b1ce76689fd0
+        AlphaSmall 060c3eafe9214c78d282640a92ffe4af63a99a8e6f72cdb5071333cf813b3a6d
+        AlphaSmallLodash 95133be053e002935b7a3f22cda019930b85c828e500730439ef0cf678beba74
+        AlphaSmallNum bdeb303722ef38f9ce52ca39eba0dafe54adaafb384e82fd5190e62a54598dd4
+        Ascii 4de1455c1520d5e2af0aaa1e6561bf3e22a5a70d1d33389732349f27e291cf7f
+        AsciiPrintable 688bec17de6b79a3f9dd46f02b3cca5833ee672087b8e36d472453566b40ce0b
+        Base58 72267d424148798770123ac700c85cb0fff27c6aa11a6a29c917947118def658
+        Bool 456c987e09b3a64b6d27d38695228affebbca420016dce56c91e85ef814249b7
+        Bytes16 2763d3503874c9192e29d7a15caef673bb03f059811332d2cff16bc29f29d4d6
+        Bytes20 1da30503b6717e434184873868d3b1d19609dba16393e774cad16dc9d066f278
+        Bytes32 d69d5ac792f5475b3c2ac5f0420b2f6732759a7e498483dba03ce3736e6b9766
+        Bytes64 f9438f197c77f8301d5499aa402cb2f313e5f5e1dec4339819d04a5d5e62044e
+        Char 793c05fe8d6d2ce97473fe9610adf283d855c661162359ab7bef152c6a613842
+        CompactSizeU64 4143bc862a658a3e9b8a82f4e5586bbf9db391d8ed59394dcefea81c78c7c5d7
+        Dec 671fc78f6889e5591f280786ec646f5e2ee3edd407937db6ebb15a7a6d60b9e6
+        Duration f641dd038b2260b6cfc4bd85d98a89b3499f79b066a1d54162f9b2d003e37298
+        EncodingBanner 01a0eec6b50760aec5caa320e8825eb32c366bdcbba22b24ee1d7752c5ed95b5
+        HexDecCaps e47b72bd769e6d73aa7192e1362fb5fcaa4f6c915cf3cd69ac4645aea7834d9d
+        HexDecSmall bd700ed57d62480421c506b1bb7232196b6ac7943a2c3f85a5b10392674b5fc6
+        I128Be e8a2f04b2278817b77d81d6afb5a4bcf1675004b09fd4a58a20d78de45b90805
+        I16Be 008beee1376cad3f54863923f7060b1911c19b85c314751b65ab92e181c8012c
+        I32Be f4232918a16fc9ace1d1ba358f5b37cb3e7d1a1d0125a9f4533109fb3511c37c
+        I64Be d50f6a99d8b48b046f15da00a6dd757da98fdb7b181fedaf55045cd2d04125c5
+        Ordering 5347dbbe688802e8265ac296890c900ee60a2cea2a68262073564976cfd0b30f
+        U1 edcf1c2f7958e79e3a56c22ca8a812b3ce9895270e01d4296b5a74ccd72e669f
+        U128Be c9ec3fd349655ecf7e8438da18e4063fd7e3af15bd089705af05f32521aa49ed
+        U16Be ea1c3e31784597c389ec418466daea6d9908d4f23ca7cefa8a0c40a1dc950daf
+        U2 649dff2441b285e44dc6884705016f9c70edb82d193564c111f964b2c6870b24
+        U3 db060422f6bdaa1ecaf8ddc12812e8f6562df56da6815eb0f4bc676dd3293b4e
+        U32Be 08211941f79f987c231e049c591fd4b47f9086ca19c7f6d7b46c811161f4ce37
+        U4 58e8f6dd590be0dbb5b7d95f0e1f53a9ba505881bba976ef2c13ffc6ce4af698
+        U5 9225f5985ae94875b6d66e1426b258987c4031948909dbf2ee4910235a704d87
+        U6 6cb13f7b1bdb8c14014b7f598846382f3844b9f426dbc65d092df21e9403c689
+        U64Be 5a16aac30c5a419c1bd8cb4ff5c8b4c54d867b95ea574faafba0d39a0feedbef
+        U7 ea95f0079323b73c930f193edbbaa86fe3b0d06a81a3edee6cf85290dd7a1e2c
+        VarIntU64 26c03d42b00618844c94d818f1062f93c837c18bbfea2296c96f14a3894585a1
+        ";
+    const GOLDEN_STD: &str = "
+        IpAddr 2051f66abc7868d28e17ac10b4fc3f4a5ba9020cb718f4f56a392aa30445d9c7
+        Ipv4Addr b2f52296b301976109f4491ee31758b3ed08845bbd1d318bd857af5e3143d13b
+        Ipv6Addr 78cba7c78de24d561c37faf52c60d2927e0f186db51929cba91ad2e756a3ec2d
+        Path 1340920270ba867d2f156ae98a28a4841144f34be4d591ec9297bb0fc5b9ed64
+        SocketAddr 27c84255f0aa400cc9f85bfd640adbff299b671e87fa3f6bb1944e41a996f56a
+        SocketAddrV4 9ff20a5cb0fc6038306fe8e6ce87c4597f7720d82051b03ab80b0bcc863d98cf
+        SocketAddrV6 e23ffe10bed2a30dbf87b683967a21adc891e65e635f7a2bf4f7aed2defad8ed
+        SystemTime 242c125f5abf34dc54ee25345a1624f4618b2196958161f6346065bfdb588f25
+        ";
+    const GOLDEN_CALENDAR: &str = "
+        Date 2fc1a1e46423bc0ee2fe9b1a30ab5ddbf8938670c3e29921b11b117d472ec178
+        Timestamp d70c0ab6ea8ef82fb08d58157576902f61bee8cb89080e834edafebbdde9d1b6
+        ";
+    const GOLDEN_UUID: &str = "
+        Uuid 23a95942bb28b80789a7e01395f411b12b19f6770669c3cd747d81696efac345
+        ";
+    const GOLDEN_SEMVER: &str = "
+        Version 26f3f96f27dad4ff3ad84838e1a640667775758433493cfec82025e24ddde1be
+        VersionReq 9df129e49acee181d86e90bdc462a7cbd7921d1280e7f60592597256070af8ad
+        ";
+    const GOLDEN_URL: &str = "
+        Url 91e906d1cb8606cfef52e89c9965825dcfdd3ef625077dcd7d13e0baf59f4e1c
+        ";
+
+    /// Lines of the golden listing of [`std_lib`] types with their semantic
+    /// ids, for the enabled crate features.
+    fn std_lib_golden() -> Vec<String> {
+        let features = [
+            (cfg!(feature = "std"), GOLDEN_STD),
+            (cfg!(any(feature = "chrono", feature = "time")), GOLDEN_CALENDAR),
+            (cfg!(feature = "uuid"), GOLDEN_UUID),
+            (cfg!(feature = "semver"), GOLDEN_SEMVER),
+            (cfg!(feature = "url"), GOLDEN_URL),
+        ];
+        let mut golden =
+            GOLDEN.trim().lines().map(|line| format!("{}\n", line.trim())).collect::<Vec<_>>();
+        for (_, lines) in features.iter().filter(|(enabled, _)| *enabled) {
+            golden.extend(lines.trim().lines().map(|line| format!("{}\n", line.trim())));
+        }
+        golden.sort();
+        golden
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn std_lib_sem_ids() {
+        let ids = std_sem_ids();
+        assert!(ids.keys().eq(std_lib().iter().map(|(name, _)| name)));
+        let listing = ids.iter().map(|(name, id)| format!("{name} {id}\n")).collect::<String>();
+        assert_eq!(listing, std_lib_golden().concat());
+    }
+
+    #[test]
+    fn strict_types_lib_types() {
+        let lib = strict_types_lib();
+        assert_eq!(lib.name(), &libname!(STRICT_TYPES_LIB));
+        assert_eq!(lib.len(), 8);
+        assert!(lib.unresolved().is_empty());
+        assert_eq!(lib.dependencies(), bset![libname!(LIB_NAME_STD)]);
+        assert_eq!(
+            lib.get(&tn!("Variant")).unwrap().to_string(),
+            "StrictTypes.Variant: struct { name: StrictTypes.VariantName, tag: U8 }"
+        );
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn strict_types_lib_sem_ids() {
+        const GOLDEN: &str = "
+        FieldName 285c22d6e660d0c028ea73bf4f8854d88027aed4740d25ddd9a382349db7f22b
+        Ident ce6a8dfa288ba5f6437bb5288681b4396bcbf1c539212ac6ca55f2a9611fb0cf
+        LibName b59a1b50af874fb5bce7f63254c6222622e1c924c27323f23a47d169c01ab67e
+        Primitive 965042b2338f5da665e355bf5335f1e6e1839dc1d709dd140ec3e1a344ffcd26
+        Sizing 4e3f735490fc8001af01db08695bb167a6485058103e80f9e4e963073c18a08c
+        TypeName 0c95cfcd43f8ef3dbd98e85ae0a97ab0bb12fa4a773cc4a2353854ee29dbbf42
+        Variant 105cc3ae5cb420515d792bbc8b11d1a7e96ae195887ab4ad74bba86183adf401
+        VariantName 33c1e569a02944693f8e8a10e7a8a0a206cf9f81f37a6a7a87bd020c2e4a09e7
+        ";
+        let ids = strict_types_sem_ids();
+        assert!(ids.keys().eq(strict_types_lib().iter().map(|(name, _)| name)));
+        let listing = ids.iter().map(|(name, id)| format!("{name} {id}\n")).collect::<String>();
+        let golden = GOLDEN.trim().lines().map(|line| format!("{}\n", line.trim()));
+        assert_eq!(listing, golden.collect::<String>());
+    }
 }
@@ -0,0 +1,341 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streams of strict-encoded items, which are concatenated without any
+//! separators or length prefixes.
+//!
+//! [`StreamDecoder`] decodes the items one by one, reading from the stream
+//! only the bytes of the item being decoded, such that streams of any size can
+//! be processed with the memory required for a single item. [`StreamEncoder`]
+//! appends the items to a stream.
+
+use core::marker::PhantomData;
+
+use crate::{
+    io, DecodeError, StreamReader, StreamWriter, StrictDecode, StrictEncode, StrictReader,
+    StrictWriter,
+};
+
+/// Iterator over the items decoded from a stream of concatenated
+/// strict-encoded items.
+///
+/// The iteration ends when the stream ends exactly at the boundary of items;
+/// a stream ending in the middle of an item produces an error. Since the
+/// position in the stream is not known after a failure, the iteration also
+/// ends after the first error.
+#[derive(Debug)]
+pub struct StreamDecoder<T: StrictDecode, R: io::Read> {
+    inner: R,
+    item_limit: usize,
+    bytes: usize,
+    items: usize,
+    failed: bool,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: StrictDecode, R: io::Read> StreamDecoder<T, R> {
+    /// Constructs the decoder of the items of unlimited size.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            item_limit: usize::MAX,
+            bytes: 0,
+            items: 0,
+            failed: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Limits each of the items to `limit` bytes. Decoding an item which is
+    /// larger fails with [`io::ErrorKind::InvalidInput`] error.
+    pub fn with_item_limit(mut self, limit: usize) -> Self {
+        self.item_limit = limit;
+        self
+    }
+
+    /// Number of bytes consumed from the stream so far, including the bytes of
+    /// an item which has failed to decode.
+    pub fn bytes(&self) -> usize { self.bytes }
+
+    /// Number of items decoded so far.
+    pub fn items(&self) -> usize { self.items }
+
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut R { &mut self.inner }
+
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Reads the first byte of the next item, returning `None` if the stream
+    /// has ended.
+    fn first_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.inner.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<T: StrictDecode, R: io::Read> Iterator for StreamDecoder<T, R> {
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let first = match self.first_byte() {
+            Ok(None) => return None,
+            Ok(Some(byte)) => byte,
+            Err(err) => {
+                self.failed = true;
+                return Some(Err(err.into()));
+            }
+        };
+
+        let item = ItemReader {
+            first: Some(first),
+            inner: &mut self.inner,
+            count: 0,
+        };
+        let mut reader = StrictReader::with(StreamReader::with(self.item_limit, item));
+        let res = T::strict_decode(&mut reader);
+        self.bytes += reader.unbox().unconfine().count;
+        match res {
+            Ok(_) => self.items += 1,
+            Err(_) => self.failed = true,
+        }
+        Some(res)
+    }
+}
+
+/// Writer appending strict-encoded items to a stream without any separators,
+/// such that they can be read back with [`StreamDecoder`].
+#[derive(Debug)]
+pub struct StreamEncoder<W: io::Write> {
+    inner: W,
+    item_limit: usize,
+    bytes: usize,
+    items: usize,
+}
+
+impl<W: io::Write> StreamEncoder<W> {
+    /// Constructs the encoder of the items of unlimited size.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            item_limit: usize::MAX,
+            bytes: 0,
+            items: 0,
+        }
+    }
+
+    /// Limits each of the items to `limit` bytes. Encoding an item which is
+    /// larger fails with [`io::ErrorKind::InvalidInput`] error.
+    pub fn with_item_limit(mut self, limit: usize) -> Self {
+        self.item_limit = limit;
+        self
+    }
+
+    /// Encodes the item and appends it to the stream.
+    ///
+    /// The item is written directly into the stream, thus on error some of its
+    /// bytes may be already written; they are accounted in [`Self::bytes`].
+    pub fn append(&mut self, item: &impl StrictEncode) -> io::Result<()> {
+        let mut item_writer = ItemWriter {
+            inner: &mut self.inner,
+            count: 0,
+        };
+        let writer = StrictWriter::with(StreamWriter::with(self.item_limit, &mut item_writer));
+        let res = item.strict_encode(writer).map(|_| ());
+        self.bytes += item_writer.count;
+        res?;
+        self.items += 1;
+        Ok(())
+    }
+
+    /// Number of bytes written to the stream so far.
+    pub fn bytes(&self) -> usize { self.bytes }
+
+    /// Number of items appended so far.
+    pub fn items(&self) -> usize { self.items }
+
+    pub fn get_ref(&self) -> &W { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut W { &mut self.inner }
+
+    pub fn into_inner(self) -> W { self.inner }
+}
+
+/// Reader of a single item, which starts with the byte already read from the
+/// stream, counting the bytes of the item.
+struct ItemReader<'a, R: io::Read> {
+    first: Option<u8>,
+    inner: &'a mut R,
+    count: usize,
+}
+
+impl<R: io::Read> io::Read for ItemReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = match (self.first, buf.first_mut()) {
+            (_, None) => return Ok(0),
+            (Some(first), Some(byte)) => {
+                *byte = first;
+                self.first = None;
+                1
+            }
+            (None, Some(_)) => self.inner.read(buf)?,
+        };
+        self.count += len;
+        Ok(len)
+    }
+}
+
+/// Writer of a single item, counting its bytes.
+struct ItemWriter<'a, W: io::Write> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+impl<W: io::Write> io::Write for ItemWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.count += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyBlob;
+
+    use super::*;
+    use crate::StrictType;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        seq: u32,
+        payload: TinyBlob,
+    }
+
+    fn record(seq: u32) -> Record {
+        Record {
+            seq,
+            payload: TinyBlob::try_from(vec![seq as u8; seq as usize % 7]).unwrap(),
+        }
+    }
+
+    fn stream(count: u32) -> Vec<u8> {
+        let mut encoder = StreamEncoder::new(vec![]);
+        for seq in 0..count {
+            encoder.append(&record(seq)).unwrap();
+        }
+        assert_eq!(encoder.items(), count as usize);
+        assert_eq!(encoder.bytes(), encoder.get_ref().len());
+        encoder.into_inner()
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn file() {
+        use std::fs;
+        use std::io::Seek;
+
+        let path = std::env::temp_dir()
+            .join(format!("strict_encoding_{}_stream_file.dat", std::process::id()));
+        let mut encoder =
+            StreamEncoder::new(std::io::BufWriter::new(fs::File::create(&path).unwrap()));
+        for seq in 0..10_000 {
+            encoder.append(&record(seq)).unwrap();
+        }
+        let len = encoder.bytes();
+        encoder.into_inner().into_inner().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), len as u64);
+
+        // the file is read without buffering, thus its position shows that
+        // nothing is read ahead of the decoded item
+        let mut decoder = StreamDecoder::<Record, _>::new(fs::File::open(&path).unwrap());
+        for seq in 0..10_000 {
+            assert_eq!(decoder.next().unwrap().unwrap(), record(seq));
+            assert_eq!(decoder.get_mut().stream_position().unwrap(), decoder.bytes() as u64);
+        }
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.items(), 10_000);
+        assert_eq!(decoder.bytes(), len);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        let mut decoder = StreamDecoder::<Record, _>::new(io::Cursor::new(vec![]));
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.items(), 0);
+        assert_eq!(decoder.bytes(), 0);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut data = stream(3);
+        let len = data.len();
+        data.push(0x03);
+
+        let mut decoder = StreamDecoder::<Record, _>::new(io::Cursor::new(data));
+        assert_eq!(decoder.by_ref().take(3).count(), 3);
+        let Some(Err(DecodeError::Io(err))) = decoder.next() else {
+            panic!("truncated record must fail with I/O error");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.items(), 3);
+        assert_eq!(decoder.bytes(), len + 1);
+    }
+
+    #[test]
+    fn item_limit() {
+        // the record with `seq` 6 takes 4 + 1 + 6 bytes
+        let data = stream(7);
+        let decoder = StreamDecoder::<Record, _>::new(data.as_slice()).with_item_limit(11);
+        assert_eq!(
+            decoder.map(Result::unwrap).collect::<Vec<_>>(),
+            (0..7).map(record).collect::<Vec<_>>()
+        );
+
+        let mut decoder = StreamDecoder::<Record, _>::new(data.as_slice()).with_item_limit(10);
+        assert_eq!(decoder.by_ref().take(6).filter(Result::is_ok).count(), 6);
+        let Some(Err(DecodeError::Io(err))) = decoder.next() else {
+            panic!("oversized record must fail with I/O error");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut encoder = StreamEncoder::new(vec![]).with_item_limit(10);
+        encoder.append(&record(5)).unwrap();
+        assert_eq!(encoder.append(&record(6)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(encoder.items(), 1);
+    }
+}
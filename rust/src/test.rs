@@ -19,14 +19,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fmt::Debug;
+use core::fmt::Debug;
 use std::io::BufRead;
 
 use amplify::confinement::Confined;
 
-use crate::{StrictDecode, StrictEncode, StrictReader, StrictWriter};
+use crate::{test_helpers, DecodeError, StrictDecode, StrictEncode, StrictReader, StrictWriter};
 
-pub fn encode<T: StrictEncode + Debug + Eq>(val: &T) -> Vec<u8> {
+mod counting_alloc;
+
+use counting_alloc::allocations;
+
+/// Runs `f`, returning its result together with the number of allocations it
+/// has made.
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let (res, allocs) = allocations(f);
+    (res, allocs.count)
+}
+
+/// Runs `f`, returning its result together with the size of the largest
+/// allocation it has made.
+pub fn largest_allocation<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let (res, allocs) = allocations(f);
+    (res, allocs.largest)
+}
+
+pub fn encode<T: StrictEncode + Debug + PartialEq>(val: &T) -> Vec<u8> {
     const MAX: usize = u16::MAX as usize;
 
     let ast_data = StrictWriter::in_memory::<MAX>();
@@ -34,28 +52,38 @@ pub fn encode<T: StrictEncode + Debug + Eq>(val: &T) -> Vec<u8> {
     Confined::<Vec<u8>, 0, MAX>::try_from(data).unwrap().release()
 }
 
-pub fn decode<T: StrictDecode + Debug + Eq>(data: impl AsRef<[u8]>) -> T {
+pub fn decode<T: StrictDecode + Debug + PartialEq>(data: impl AsRef<[u8]>) -> T {
     const MAX: usize = u16::MAX as usize;
 
     let mut reader = StrictReader::in_memory::<MAX>(data);
     let val2 = T::strict_decode(&mut reader).unwrap();
     let mut cursor = reader.into_cursor();
-    assert!(!cursor.fill_buf().unwrap().is_empty(), "data not entirely consumed");
+    assert!(cursor.fill_buf().unwrap().is_empty(), "data not entirely consumed");
 
     val2
 }
 
+/// Decodes a value, not requiring the data to be entirely consumed.
+pub fn try_decode<T: StrictDecode>(data: impl AsRef<[u8]>) -> Result<T, DecodeError> {
+    T::strict_decode(&mut StrictReader::in_memory::<{ u16::MAX as usize }>(data))
+}
+
+/// Decodes a value which is expected to fail decoding, returning the error.
+pub fn decode_err<T: StrictDecode + Debug>(data: impl AsRef<[u8]>) -> DecodeError {
+    try_decode::<T>(data).expect_err("invalid data must not decode")
+}
+
 #[allow(dead_code)]
-pub fn encoding_roundtrip<T: StrictEncode + StrictDecode + Debug + Eq>(val: &T) {
-    let data = encode(val);
-    let val2: T = decode(data);
-    assert_eq!(val, &val2);
+pub fn encoding_roundtrip<T: StrictEncode + StrictDecode + Debug + PartialEq>(val: &T) {
+    test_helpers::roundtrip_canonical(val);
 }
 
 #[allow(dead_code)]
-pub fn encoding<T: StrictEncode + StrictDecode + Debug + Eq>(val: &T, expect: impl AsRef<[u8]>) {
-    let data = encode(val);
-    assert_eq!(&data[..], expect.as_ref());
-    let val2: T = decode(data);
-    assert_eq!(val, &val2);
+pub fn encoding<T: StrictEncode + StrictDecode + Debug + PartialEq>(
+    val: &T,
+    expect: impl AsRef<[u8]>,
+) {
+    let expect = expect.as_ref();
+    test_helpers::check_bytes("encoding of the value", expect, &test_helpers::encode(val));
+    test_helpers::check_decoded(val, expect);
 }
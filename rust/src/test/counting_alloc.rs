@@ -0,0 +1,89 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Allocator counting the allocations made by the current thread. The unit
+//! tests use it as a module of `crate::test`, while the integration tests and
+//! the benchmarks include the file with `#[path]` attribute.
+
+// each of the crates including the file uses only a part of the counters
+#![allow(dead_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Allocations made while running a closure; see [`allocations`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Allocations {
+    /// Number of allocations, including the reallocations.
+    pub count: usize,
+    /// Number of reallocations.
+    pub reallocs: usize,
+    /// Size of the largest allocation.
+    pub largest: usize,
+    /// Number of allocated bytes; reallocations add only the growth of the
+    /// allocation.
+    pub bytes: usize,
+}
+
+thread_local! {
+    static COUNTERS: Cell<Option<Allocations>> = const { Cell::new(None) };
+}
+
+fn record(size: usize, grown: usize, realloc: bool) {
+    let _ = COUNTERS.try_with(|counters| {
+        if let Some(mut allocs) = counters.get() {
+            allocs.count += 1;
+            allocs.reallocs += realloc as usize;
+            allocs.largest = allocs.largest.max(size);
+            allocs.bytes += grown;
+            counters.set(Some(allocs));
+        }
+    });
+}
+
+// Counts allocations made by the current thread while `allocations` runs, so
+// other tests running in parallel are not affected.
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size(), layout.size(), false);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr, layout) }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size, new_size.saturating_sub(layout.size()), true);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Runs `f`, returning its result together with the allocations it has made.
+pub fn allocations<R>(f: impl FnOnce() -> R) -> (R, Allocations) {
+    let outer = COUNTERS.with(|counters| counters.replace(Some(Allocations::default())));
+    let res = f();
+    let allocs = COUNTERS.with(|counters| counters.replace(outer));
+    (res, allocs.expect("allocations are counted"))
+}
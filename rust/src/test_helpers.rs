@@ -0,0 +1,1008 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Round-trip tests of strict encodings, available with `test_helpers`
+//! feature.
+//!
+//! The helpers panic like `assert_eq!` does, but instead of dumping the
+//! compared values they report the bytes: mismatching encodings are shown as
+//! a side-by-side hex diff around the first differing byte, and values which
+//! don't match after decoding are reported together with the hex of the data
+//! they were decoded from.
+//!
+//! [`assert_deterministic`] and [`assert_canonical`] encode a value several
+//! times, including in another thread, detecting the encodings which depend on
+//! the order of hash maps or other transient state.
+//!
+//! [`assert_encoding`] combines these checks with the round-trip of the value;
+//! the derive macros generate tests running it for the types marked with
+//! `#[strict_type(derive_tests)]` attribute.
+//!
+//! [`assert_mutation_resilience`] decodes truncated, bit-flipped and extended
+//! encodings of a value, detecting the decoders which accept malformed or
+//! non-canonical data.
+//!
+//! [`assert_key_order_consistency`] checks that the order of map keys agrees
+//! with the order of their encodings.
+//!
+//! [`assert_strict_layout!`](crate::assert_strict_layout) snapshots the
+//! fields and variants of a type, detecting the changes of its wire layout.
+//!
+//! Golden test vectors are kept in files, such that changes of the encodings
+//! between versions and implementations are detected: see [`vector`] and
+//! [`test_vectors!`](crate::test_vectors).
+
+use core::fmt::{Debug, Write};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use crate::layout::layout_of;
+use crate::text::{Base16, TextEncoding};
+use crate::{
+    StrictDecode, StrictEncode, StrictReader, StrictTypeInfo, StrictWriter, TypeInfoOwned,
+};
+
+/// Number of bytes in a row of a hex diff.
+const ROW_LEN: usize = 16;
+
+/// Number of rows of a hex diff shown before and after the row with the first
+/// differing byte.
+const CONTEXT_ROWS: usize = 2;
+
+/// Encodes the value, decodes it back and checks that the decoded value is
+/// equal to the original one.
+pub fn roundtrip<T: StrictEncode + StrictDecode + PartialEq + Debug>(val: &T) -> Vec<u8> {
+    let data = encode(val);
+    check_decoded(val, &data);
+    data
+}
+
+/// Performs the [`roundtrip`] and checks that the decoded value is encoded
+/// into the same bytes, detecting the decoders accepting non-canonical data.
+pub fn roundtrip_canonical<T: StrictEncode + StrictDecode + PartialEq + Debug>(val: &T) -> Vec<u8> {
+    let data = roundtrip(val);
+    let decoded = decode::<T>(&data);
+    check_bytes("re-encoding of the decoded value", &data, &encode(&decoded));
+    data
+}
+
+/// Number of times [`assert_deterministic`] encodes the value in the calling
+/// thread.
+const ENCODE_RUNS: usize = 4;
+
+/// Encodes the value several times, checking that all the encodings are the
+/// same, and returns the encoding.
+///
+/// One of the encodings is made in a newly spawned thread, which has its own
+/// hash map seeds and allocator state, such that encodings depending on the
+/// order of hash maps created during the encoding, addresses or other
+/// transient state are detected.
+pub fn assert_deterministic<T: StrictEncode + Debug + Sync>(val: &T) -> Vec<u8> {
+    let data = encode(val);
+    for _ in 1..ENCODE_RUNS {
+        check_bytes("repeated encoding of the value", &data, &encode(val));
+    }
+    let spawned = std::thread::scope(|scope| {
+        scope.spawn(|| encode(val)).join().expect("encoding thread panicked")
+    });
+    check_bytes("encoding of the value in a new thread", &data, &spawned);
+    data
+}
+
+/// Performs [`assert_deterministic`] check, decodes the encoding and checks
+/// that the decoded value is encoded into the same bytes, detecting the
+/// decoders accepting non-canonical data. Unlike [`roundtrip_canonical`], it
+/// doesn't require the values to be comparable.
+pub fn assert_canonical<T: StrictEncode + StrictDecode + Debug + Sync>(val: &T) -> Vec<u8> {
+    let data = assert_deterministic(val);
+    let decoded = decode::<T>(&data);
+    check_bytes("re-encoding of the decoded value", &data, &assert_deterministic(&decoded));
+    data
+}
+
+/// Performs [`assert_canonical`] check and checks that the decoded value is
+/// equal to the original one. This is the check run by the tests generated
+/// with `#[strict_type(derive_tests)]` attribute.
+pub fn assert_encoding<T>(val: &T) -> Vec<u8>
+where T: StrictEncode + StrictDecode + PartialEq + Debug + Sync {
+    let data = assert_canonical(val);
+    check_decoded(val, &data);
+    data
+}
+
+/// Checks that the order of the keys given by their [`Ord`] implementation is
+/// the same as the lexicographic order of their encodings, panicking on the
+/// first pair of neighbouring samples ordered differently.
+///
+/// B-tree collections are encoded in the [`Ord`] order of their keys, which is
+/// also required from the data by the decoders. Peers comparing or sorting the
+/// encoded keys without decoding them, like the databases indexing them,
+/// produce the same order only if the keys pass this check. Among the built-in
+/// types the order is consistent for `u8`, `bool`, `Ordering`, byte arrays and
+/// `Option`s of consistent types. Unit enums are consistent when their tags
+/// increase in the declaration order, which is always the case for
+/// `tags = order`. Structures and tuples with the derived `Ord` are consistent
+/// when all their fields are consistent and, except the last one, have a fixed
+/// size.
+///
+/// Integers wider than a byte are not consistent, since they are encoded in
+/// little-endian byte order (`256` is encoded as `00 01` and precedes `1`
+/// encoded as `01 00`); signed integers, including `i8`, are not consistent
+/// also because negative values are encoded with the higher bytes. Strings
+/// and collections are not consistent, since they are prefixed with their
+/// length, which places any shorter value before the longer ones.
+/// [`Reverse`](core::cmp::Reverse) keys are not consistent, since they are
+/// encoded as the wrapped values, while ordered in reverse.
+pub fn assert_key_order_consistency<K: Ord + StrictEncode + Debug>(
+    samples: impl IntoIterator<Item = K>,
+) {
+    let mut samples = samples.into_iter().collect::<Vec<_>>();
+    samples.sort();
+    let encoded = samples.iter().map(encode).collect::<Vec<_>>();
+    for (index, pair) in samples.windows(2).enumerate() {
+        let order = pair[0].cmp(&pair[1]);
+        let encoded_order = encoded[index].cmp(&encoded[index + 1]);
+        assert!(
+            order == encoded_order,
+            "order of keys {:?} and {:?} is {order:?}, while their encodings {} and {} are \
+             ordered as {encoded_order:?}",
+            pair[0],
+            pair[1],
+            to_hex(&encoded[index]),
+            to_hex(&encoded[index + 1]),
+        );
+    }
+}
+
+/// Bytes appended to the data by [`assert_mutation_resilience`].
+const TRAILING_BYTES: [&[u8]; 3] = [&[0x00], &[0xFF], &[0x00, 0x00, 0x00, 0x00]];
+
+/// Mutation of the encoded data applied by [`assert_mutation_resilience`].
+enum Mutation {
+    Truncation(usize),
+    BitFlip { offset: usize, bit: u8 },
+    Extension(&'static [u8]),
+}
+
+impl Mutation {
+    fn apply(&self, data: &[u8]) -> Vec<u8> {
+        match *self {
+            Mutation::Truncation(len) => data[..len].to_vec(),
+            Mutation::BitFlip { offset, bit } => {
+                let mut data = data.to_vec();
+                data[offset] ^= 1 << bit;
+                data
+            }
+            Mutation::Extension(bytes) => [data, bytes].concat(),
+        }
+    }
+}
+
+impl core::fmt::Display for Mutation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Mutation::Truncation(len) => write!(f, "truncation to {len} bytes"),
+            Mutation::BitFlip { offset, bit } => {
+                write!(f, "flip of bit {bit} at offset {offset:#x}")
+            }
+            Mutation::Extension(bytes) => write!(f, "extension with bytes {}", to_hex(bytes)),
+        }
+    }
+}
+
+/// Encodes the value and performs [`assert_data_resilience`] check of the
+/// encoding, which is returned.
+pub fn assert_mutation_resilience<T: StrictEncode + StrictDecode + Debug>(val: &T) -> Vec<u8> {
+    let data = encode(val);
+    assert_data_resilience::<T>(&data);
+    data
+}
+
+/// Checks that no mutation of the valid encoded data is decoded into a value
+/// which is encoded into other bytes than the mutated data, detecting the
+/// decoders which accept malformed or non-canonical input, i.e. decode two
+/// different byte strings into the same value.
+///
+/// The data are truncated at every byte boundary, each of their bits is
+/// flipped and they are extended with trailing bytes; each mutated input must
+/// either fail to decode, leave some bytes unconsumed or be decoded into a
+/// value which is encoded back into the same bytes. The data themselves must
+/// pass [`roundtrip_canonical`]-like check. A panic of the decoder is also
+/// reported as a failure, together with the mutation which caused it.
+///
+/// Floating point numbers don't pass the check, since all NaN values are
+/// decoded into the canonical NaN, which is the only non-canonical data
+/// accepted by the decoders of the built-in types.
+pub fn assert_data_resilience<T: StrictEncode + StrictDecode + Debug>(data: &[u8]) {
+    let decoded = decode::<T>(data);
+    check_bytes("re-encoding of the decoded value", data, &encode(&decoded));
+
+    let truncations = (0..data.len()).map(Mutation::Truncation);
+    let flips =
+        (0..data.len()).flat_map(|offset| (0..8).map(move |bit| Mutation::BitFlip { offset, bit }));
+    let extensions = TRAILING_BYTES.into_iter().map(Mutation::Extension);
+    for mutation in truncations.chain(flips).chain(extensions) {
+        let mutated = mutation.apply(data);
+        let result = std::panic::catch_unwind(|| {
+            let mut reader = StrictReader::from_slice::<{ usize::MAX }>(&mutated);
+            let val = T::strict_decode(&mut reader).ok()?;
+            reader.remaining().is_empty().then_some(val)
+        });
+        let Ok(result) = result else {
+            panic!("decoding of data {} after {mutation} panicked", to_hex(data));
+        };
+        let Some(val) = result else {
+            continue;
+        };
+        let encoded = try_encode(&val).unwrap_or_else(|err| {
+            panic!(
+                "data {} after {mutation} are decoded into {val:#?}, which can't be encoded: {err}",
+                to_hex(data)
+            )
+        });
+        if let Some(diff) = hex_diff(&mutated, &encoded) {
+            panic!(
+                "data {} after {mutation} are decoded into {val:#?}, which is encoded into other \
+                 data: {diff}",
+                to_hex(data)
+            );
+        }
+    }
+}
+
+/// Checks that the value is encoded into the test vector given as a hex
+/// string, which may contain whitespaces, and that the test vector is decoded
+/// into the value.
+pub fn roundtrip_vector<T: StrictEncode + StrictDecode + PartialEq + Debug>(
+    val: &T,
+    expected_hex: &str,
+) {
+    let expected = from_hex(expected_hex);
+    check_bytes("encoding of the value", &expected, &encode(val));
+    check_decoded(val, &expected);
+}
+
+/// Name of the environment variable which, being set to `1`, makes [`vector`]
+/// (re)write the test vector files from the values instead of checking them
+/// against the files.
+pub const UPDATE_VECTORS: &str = "UPDATE_VECTORS";
+
+/// Checks the test vector stored in the file, returning the decoded value.
+///
+/// Files with `.bin` extension contain the raw encoding, all other files
+/// contain its hex, which may contain whitespaces. The data must be decoded
+/// entirely, and the decoded value must be encoded back into the same bytes.
+/// The `{:#?}` rendering of the value must match the contents of the file with
+/// the same name and `.debug` extension.
+pub fn assert_vector<T: StrictEncode + StrictDecode + Debug>(path: impl AsRef<Path>) -> T {
+    let path = path.as_ref();
+    let data = read_vector(path);
+    let val = decode::<T>(&data);
+    check_bytes(&format!("re-encoding of vector {}", path.display()), &data, &encode(&val));
+
+    let debug_path = debug_path(path);
+    let expected = fs::read_to_string(&debug_path).unwrap_or_else(|err| {
+        panic!("unable to read rendering of test vector {}: {err}", debug_path.display())
+    });
+    let decoded = format!("{val:#?}");
+    assert!(
+        expected.trim_end() == decoded,
+        "value decoded from vector {} doesn't match its rendering in {}\nexpected: {}\ndecoded: \
+         {decoded}",
+        path.display(),
+        debug_path.display(),
+        expected.trim_end(),
+    );
+    val
+}
+
+/// Checks that the value is encoded into the test vector stored in the file,
+/// which is then checked with [`assert_vector`] and is decoded into the value.
+///
+/// Being run with [`UPDATE_VECTORS`] environment variable set to `1`, writes
+/// the vector and its rendering from the value first, so the changes of the
+/// encodings have to be accepted deliberately.
+pub fn vector<T: StrictEncode + StrictDecode + PartialEq + Debug>(path: impl AsRef<Path>, val: &T) {
+    let update = env::var_os(UPDATE_VECTORS).is_some_and(|var| var == "1");
+    check_vector(path.as_ref(), val, update)
+}
+
+/// Performs the [`vector`] check, (re)writing the vector first if `update` is
+/// set.
+fn check_vector<T: StrictEncode + StrictDecode + PartialEq + Debug>(
+    path: &Path,
+    val: &T,
+    update: bool,
+) {
+    if update {
+        write_vector(path, val);
+    } else if !path.exists() {
+        panic!(
+            "test vector {} is missing; run the tests with {UPDATE_VECTORS}=1 to create it",
+            path.display()
+        );
+    }
+    let data = read_vector(path);
+    check_bytes(
+        &format!("encoding of the value as vector {}", path.display()),
+        &data,
+        &encode(val),
+    );
+    assert_vector::<T>(path);
+    check_decoded(val, &data);
+}
+
+/// Declares a battery of tests, one per [`vector`] stored in the directory
+/// given relative to the crate root. Each test is named after its vector: the
+/// encoding of the value of the given type is kept in `<name>.hex` file and its
+/// rendering in `<name>.debug` file.
+///
+/// ```ignore
+/// strict_encoding::test_vectors! {
+///     "tests/vectors" => {
+///         u16_max: u16 = u16::MAX,
+///         none: Option<u8> = None,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! test_vectors {
+    ($dir:literal => { $($name:ident : $ty:ty = $val:expr),+ $(,)? }) => {
+        $(
+            #[test]
+            fn $name() {
+                $crate::test_helpers::vector::<$ty>(
+                    concat!(env!("CARGO_MANIFEST_DIR"), "/", $dir, "/", stringify!($name), ".hex"),
+                    &$val,
+                );
+            }
+        )+
+    };
+}
+
+/// Checks that the layout of the type, rendered from its [`StrictTypeInfo`]
+/// by the alternate form of [`TypeInfoOwned`] display, matches the expected
+/// one, pointing to the first difference otherwise.
+pub fn assert_layout<T: StrictTypeInfo>(expected: &str) {
+    check_layout::<T>(T::type_info(), expected)
+}
+
+/// Checks the layout of the type of the value like [`assert_layout`], for the
+/// types which have no [`StrictDumb`](crate::StrictDumb) value.
+pub fn assert_layout_of<T: StrictEncode>(value: &T, expected: &str) {
+    check_layout::<T>(layout_of(value), expected)
+}
+
+fn check_layout<T>(info: TypeInfoOwned, expected: &str) {
+    let actual = format!("{info:#}");
+    if actual == expected {
+        return;
+    }
+    let pos = expected.chars().zip(actual.chars()).take_while(|(a, b)| a == b).count();
+    panic!(
+        "layout of `{}` doesn't match the expected one\nexpected: {expected}\n  actual: \
+         {actual}\n          {:pos$}^",
+        core::any::type_name::<T>(),
+        ""
+    );
+}
+
+/// Checks that the layout of the type, rendered from its [`StrictTypeInfo`],
+/// matches the expected one, such that reordered or renamed fields and
+/// variants, changed variant tags or changed field types fail the test:
+///
+/// ```ignore
+/// strict_encoding::assert_strict_layout!(
+///     Transfer,
+///     "MyLib.Transfer: struct { amount:0: U64, memo:1: [Unicode ^ ..0xff] }"
+/// );
+/// ```
+///
+/// Named field types are listed by their names; the changes of their own
+/// layouts are detected by their snapshots or by the semantic id of the type.
+#[macro_export]
+macro_rules! assert_strict_layout {
+    ($ty:ty, $expected:expr $(,)?) => {
+        $crate::test_helpers::assert_layout::<$ty>($expected)
+    };
+}
+
+/// Path of the file with the rendering of the test vector stored at `path`.
+fn debug_path(path: &Path) -> PathBuf { path.with_extension("debug") }
+
+/// Reads the encoded data of the test vector, either raw or in hex.
+fn read_vector(path: &Path) -> Vec<u8> {
+    let data = if path.extension().is_some_and(|ext| ext == "bin") {
+        fs::read(path)
+    } else {
+        fs::read_to_string(path).map(|hex| from_hex(&hex))
+    };
+    data.unwrap_or_else(|err| panic!("unable to read test vector {}: {err}", path.display()))
+}
+
+/// Writes the encoding of the value and its rendering as a test vector.
+fn write_vector<T: StrictEncode + Debug>(path: &Path, val: &T) {
+    let failure = |err| panic!("unable to write test vector {}: {err}", path.display());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(failure);
+    }
+    let data = encode(val);
+    if path.extension().is_some_and(|ext| ext == "bin") {
+        fs::write(path, data).unwrap_or_else(failure);
+    } else {
+        let mut hex = data.chunks(ROW_LEN).map(to_hex).collect::<Vec<_>>().join("\n");
+        hex.push('\n');
+        fs::write(path, hex).unwrap_or_else(failure);
+    }
+    fs::write(debug_path(path), format!("{val:#?}\n")).unwrap_or_else(failure);
+}
+
+/// Returns the side-by-side hex diff of the two byte strings, or `None` if
+/// they are equal.
+pub fn hex_diff(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let pos = expected.iter().zip(actual).position(|(a, b)| a != b);
+    let pos = match pos {
+        None if expected.len() == actual.len() => return None,
+        None => expected.len().min(actual.len()),
+        Some(pos) => pos,
+    };
+
+    let mut diff = format!(
+        "first difference at offset {pos:#x}; expected {} bytes, actual {} bytes\n",
+        expected.len(),
+        actual.len()
+    );
+    let width = ROW_LEN * 4;
+    writeln!(diff, "offset   | {:width$}| actual", "expected").expect("writing to string");
+    let row = pos / ROW_LEN;
+    let last = expected.len().max(actual.len()).saturating_sub(1) / ROW_LEN;
+    for row in row.saturating_sub(CONTEXT_ROWS)..=last.min(row + CONTEXT_ROWS) {
+        let start = row * ROW_LEN;
+        let line = format!(
+            "{start:08x} |{}|{}",
+            hex_row(expected, start, pos),
+            hex_row(actual, start, pos)
+        );
+        writeln!(diff, "{}", line.trim_end()).expect("writing to string");
+    }
+    Some(diff)
+}
+
+/// Formats a row of bytes starting at `start`, putting the byte at `pos` into
+/// brackets.
+fn hex_row(data: &[u8], start: usize, pos: usize) -> String {
+    (start..start + ROW_LEN)
+        .map(|index| match data.get(index) {
+            None => s!("    "),
+            Some(byte) if index == pos => format!("[{byte:02x}]"),
+            Some(byte) => format!(" {byte:02x} "),
+        })
+        .collect()
+}
+
+/// Lowercase hex representation of the data.
+pub fn to_hex(data: &[u8]) -> String { Base16::encode(data) }
+
+/// Parses the hex of a test vector, ignoring whitespaces.
+fn from_hex(hex: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(hex.len() / 2);
+    let digits = hex.chars().enumerate().filter(|(_, ch)| !ch.is_whitespace());
+    Base16::decode_digits(digits, |byte| data.push(byte))
+        .unwrap_or_else(|err| panic!("test vector `{hex}` is not valid hex: {err}"));
+    data
+}
+
+/// Encodes the value, panicking on failure.
+pub(crate) fn encode<T: StrictEncode + Debug>(val: &T) -> Vec<u8> {
+    try_encode(val).unwrap_or_else(|err| panic!("unable to encode {val:#?}: {err}"))
+}
+
+fn try_encode<T: StrictEncode>(val: &T) -> crate::io::Result<Vec<u8>> {
+    let writer = val.strict_encode(StrictWriter::in_memory::<{ usize::MAX }>())?;
+    Ok(writer.unbox().unconfine())
+}
+
+/// Decodes the value, panicking unless the data are decoded entirely.
+fn decode<T: StrictDecode + Debug>(data: &[u8]) -> T {
+    let mut reader = StrictReader::from_slice::<{ usize::MAX }>(data);
+    let val = T::strict_decode(&mut reader)
+        .unwrap_or_else(|err| panic!("unable to decode data {}: {err}", to_hex(data)));
+    let left = reader.remaining();
+    assert!(
+        left.is_empty(),
+        "decoding of data {} leaves {} bytes unconsumed: {}",
+        to_hex(data),
+        left.len(),
+        to_hex(left)
+    );
+    val
+}
+
+/// Checks that the data are decoded into the value.
+pub(crate) fn check_decoded<T: StrictDecode + PartialEq + Debug>(val: &T, data: &[u8]) {
+    let decoded = decode::<T>(data);
+    assert!(
+        &decoded == val,
+        "decoded value doesn't match the original one\noriginal: {val:#?}\ndecoded: \
+         {decoded:#?}\ndata: {}",
+        to_hex(data)
+    );
+}
+
+/// Checks that the bytes are equal, panicking with their hex diff otherwise;
+/// `what` describes the origin of the actual bytes.
+pub(crate) fn check_bytes(what: &str, expected: &[u8], actual: &[u8]) {
+    if let Some(diff) = hex_diff(expected, actual) {
+        panic!("{what} doesn't match the expected data: {diff}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::{DecodeError, StrictDumb, StrictType, TypedRead, TypedWrite};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        id: u32,
+        name: TinyString,
+    }
+
+    /// Byte which is decoded inverted.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    struct Inverted(u8);
+
+    impl StrictType for Inverted {
+        const STRICT_LIB_NAME: &'static str = "Test";
+    }
+    impl StrictEncode for Inverted {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> crate::io::Result<W> {
+            self.0.strict_encode(writer)
+        }
+    }
+    impl StrictDecode for Inverted {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            u8::strict_decode(reader).map(|byte| Inverted(!byte))
+        }
+    }
+
+    /// Value with a counter which is ignored by the comparison and isn't
+    /// restored by the decoding.
+    #[derive(Copy, Clone, Eq, Debug, Default)]
+    struct Counted {
+        value: u8,
+        count: u8,
+    }
+
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Self) -> bool { self.value == other.value }
+    }
+    impl StrictType for Counted {
+        const STRICT_LIB_NAME: &'static str = "Test";
+    }
+    impl StrictEncode for Counted {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> crate::io::Result<W> {
+            (self.value, self.count).strict_encode(writer)
+        }
+    }
+    impl StrictDecode for Counted {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            let (value, _) = <(u8, u8)>::strict_decode(reader)?;
+            Ok(Counted { value, count: 0 })
+        }
+    }
+
+    /// Set of values written in the order of a hash set created during the
+    /// encoding, which differs between the hash set instances.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    struct Unordered(Vec<u16>);
+
+    impl StrictType for Unordered {
+        const STRICT_LIB_NAME: &'static str = "Test";
+    }
+    impl StrictEncode for Unordered {
+        fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> crate::io::Result<W> {
+            let set = self.0.iter().collect::<std::collections::HashSet<_>>();
+            for item in set {
+                writer = item.strict_encode(writer)?;
+            }
+            Ok(writer)
+        }
+    }
+
+    fn record() -> Record {
+        Record {
+            id: 0xCAFE,
+            name: TinyString::try_from(s!("strict")).unwrap(),
+        }
+    }
+
+    mod reordered {
+        use amplify::confinement::TinyString;
+
+        use crate::{StrictDecode, StrictEncode, StrictType};
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Record {
+            pub name: TinyString,
+            pub id: u32,
+        }
+    }
+
+    mod retyped {
+        use amplify::confinement::TinyString;
+
+        use crate::{StrictDecode, StrictEncode, StrictType};
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        pub struct Record {
+            pub id: u64,
+            pub name: TinyString,
+        }
+    }
+
+    #[test]
+    fn layout() {
+        assert_strict_layout!(
+            Record,
+            "Test.Record: struct { id:0: U32, name:1: [Unicode ^ ..0xff] }"
+        );
+        assert_strict_layout!(Option<u8>, "_: U8?");
+        assert_strict_layout!((u8, u16), "_: tuple(U8, U16)");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "layout of `strict_encoding::test_helpers::test::reordered::Record` doesn't \
+                    match the expected one\nexpected: Test.Record: struct { id:0: U32, name:1: \
+                    [Unicode ^ ..0xff] }\n  actual: Test.Record: struct { name:0: [Unicode ^ \
+                    ..0xff], id:1: U32 }\n                                ^"
+    )]
+    fn layout_reordered() {
+        assert_strict_layout!(
+            reordered::Record,
+            "Test.Record: struct { id:0: U32, name:1: [Unicode ^ ..0xff] }"
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "layout of `strict_encoding::test_helpers::test::retyped::Record` doesn't \
+                    match the expected one\nexpected: Test.Record: struct { id:0: U32, name:1: \
+                    [Unicode ^ ..0xff] }\n  actual: Test.Record: struct { id:0: U64, name:1: \
+                    [Unicode ^ ..0xff] }\n                                       ^"
+    )]
+    fn layout_retyped() {
+        assert_strict_layout!(
+            retyped::Record,
+            "Test.Record: struct { id:0: U32, name:1: [Unicode ^ ..0xff] }"
+        );
+    }
+
+    #[test]
+    fn roundtrips() {
+        assert_eq!(roundtrip(&record()), roundtrip_canonical(&record()));
+        roundtrip_vector(&record(), "feca0000 06 737472696374");
+        roundtrip_vector(&Record::strict_dumb(), "00000000 00");
+
+        let counted = Counted { value: 1, count: 7 };
+        assert_eq!(roundtrip(&counted), [1, 7]);
+        assert_eq!(roundtrip_canonical(&Counted { value: 1, count: 0 }), [1, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding of the value doesn't match the expected data: first \
+                               difference at offset 0x4")]
+    fn vector_mismatch() { roundtrip_vector(&record(), "feca0000 07 737472696374"); }
+
+    #[test]
+    #[should_panic(expected = "decoded value doesn't match the original one")]
+    fn decoded_mismatch() { roundtrip(&Inverted(0x0F)); }
+
+    #[test]
+    #[should_panic(expected = "re-encoding of the decoded value doesn't match the expected data")]
+    fn non_canonical() { roundtrip_canonical(&Counted { value: 1, count: 7 }); }
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(assert_deterministic(&record()), roundtrip(&record()));
+        assert_eq!(assert_canonical(&record()), roundtrip(&record()));
+        assert_eq!(assert_deterministic(&Unordered(vec![7])), [7, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding of the value doesn't match the expected data")]
+    fn nondeterministic() { assert_deterministic(&Unordered((0..64).collect())); }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(assert_encoding(&record()), roundtrip(&record()));
+        assert_eq!(assert_encoding(&Counted { value: 1, count: 0 }), [1, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "re-encoding of the decoded value doesn't match the expected data")]
+    fn encoding_mismatch() { assert_encoding(&Inverted(0x0F)); }
+
+    #[test]
+    #[should_panic(expected = "re-encoding of the decoded value doesn't match the expected data")]
+    fn non_canonical_incomparable() { assert_canonical(&Counted { value: 1, count: 7 }); }
+
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct AssetKey {
+        contract: [u8; 4],
+        index: [u8; 2],
+        flag: Option<bool>,
+    }
+
+    #[test]
+    fn key_order() {
+        assert_key_order_consistency(0..=u8::MAX);
+        assert_key_order_consistency([Some(3u8), None, Some(0), Some(200)]);
+        assert_key_order_consistency([[0u8, 1, 0], [1, 0, 0], [0, 0, 200], [0, 1, 0]]);
+
+        let keys = [[0xCA, 0xFE, 0, 0], [0, 0, 0, 1], [0xFF, 0xFF, 0, 0]].into_iter().flat_map(
+            |contract| {
+                [[0, 1], [1, 0], [0, 0]].into_iter().flat_map(move |index| {
+                    [None, Some(false), Some(true)].map(|flag| AssetKey {
+                        contract,
+                        index,
+                        flag,
+                    })
+                })
+            },
+        );
+        assert_key_order_consistency(keys);
+    }
+
+    #[test]
+    #[should_panic(expected = "order of keys -1 and 0 is Less, while their encodings ffffffff \
+                               and 00000000 are ordered as Greater")]
+    fn key_order_signed() { assert_key_order_consistency([1i32, -1, 0]); }
+
+    #[test]
+    #[should_panic(expected = "order of keys 255 and 256 is Less, while their encodings ff00 and \
+                               0001 are ordered as Greater")]
+    fn key_order_little_endian() { assert_key_order_consistency([1u16, 256, 255, 0]); }
+
+    #[test]
+    #[should_panic(expected = "order of keys (1, 255) and (1, 256) is Less, while their \
+                               encodings 01ff00 and 010001 are ordered as Greater")]
+    fn key_order_composite() { assert_key_order_consistency([(1u8, 256u16), (0, 512), (1, 255)]); }
+
+    #[test]
+    #[should_panic(expected = "data 0100 after flip of bit 0 at offset 0x1 are decoded into")]
+    fn mutation_non_canonical() { assert_mutation_resilience(&Counted { value: 1, count: 0 }); }
+
+    /// Byte which the decoder fails to process when it has the highest bit set.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    struct Fragile(u8);
+
+    impl StrictType for Fragile {
+        const STRICT_LIB_NAME: &'static str = "Test";
+    }
+    impl StrictEncode for Fragile {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> crate::io::Result<W> {
+            self.0.strict_encode(writer)
+        }
+    }
+    impl StrictDecode for Fragile {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            let byte = u8::strict_decode(reader)?;
+            assert!(byte < 0x80, "unsupported byte");
+            Ok(Fragile(byte))
+        }
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    #[should_panic(
+        expected = "data 0000c07f after flip of bit 0 at offset 0x0 are decoded into NaN"
+    )]
+    fn mutation_float_nan() { assert_mutation_resilience(&f32::NAN); }
+
+    #[test]
+    #[should_panic(expected = "decoding of data 01 after flip of bit 7 at offset 0x0 panicked")]
+    fn mutation_panic() { assert_mutation_resilience(&Fragile(1)); }
+
+    #[test]
+    #[should_panic(expected = "leaves 1 bytes unconsumed: ff")]
+    fn unconsumed() { decode::<Inverted>(&[0xFF, 0xFF]); }
+
+    #[test]
+    fn diff() {
+        assert_eq!(hex_diff(&[1, 2, 3], &[1, 2, 3]), None);
+
+        let expected = (0..40u8).collect::<Vec<_>>();
+        let mut actual = expected.clone();
+        actual[37] = 0xFF;
+        actual.push(40);
+        let diff = hex_diff(&expected, &actual).unwrap();
+        let lines = diff.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "first difference at offset 0x25; expected 40 bytes, actual 41 bytes");
+        assert!(lines[1].starts_with("offset   | expected "));
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with("00000000 | 00  01 "));
+        assert!(lines[4].starts_with("00000020 | 20  21  22  23  24 [25] 26  27 "));
+        assert!(lines[4].ends_with("| 20  21  22  23  24 [ff] 26  27  28"));
+
+        // the difference is in the length only
+        let diff = hex_diff(&[1, 2], &[1, 2, 3]).unwrap();
+        assert!(diff.starts_with("first difference at offset 0x2; expected 2 bytes, actual 3"));
+        assert!(diff.trim_end().ends_with("| 01  02 [03]"));
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(to_hex(&[0x00, 0xAB, 0x10]), "00ab10");
+        assert_eq!(from_hex(" 00ab\n10 "), vec![0x00, 0xAB, 0x10]);
+    }
+
+    /// Path to a unique temporary vector file with the given extension.
+    fn temp_vector(name: &str, ext: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("strict-vectors-{}", std::process::id()))
+            .join(name)
+            .with_extension(ext)
+    }
+
+    #[test]
+    fn vector_files() {
+        for ext in ["hex", "bin"] {
+            let path = temp_vector("record", ext);
+            write_vector(&path, &record());
+            assert_eq!(assert_vector::<Record>(&path), record());
+            check_vector(&path, &record(), false);
+        }
+        let hex = fs::read_to_string(temp_vector("record", "hex")).unwrap();
+        assert_eq!(hex, "feca000006737472696374\n");
+        assert_eq!(fs::read(temp_vector("record", "bin")).unwrap(), encode(&record()));
+    }
+
+    #[test]
+    #[should_panic(expected = "is missing; run the tests with UPDATE_VECTORS=1 to create it")]
+    fn vector_missing() { check_vector(&temp_vector("missing", "hex"), &record(), false); }
+
+    #[test]
+    #[should_panic(expected = "doesn't match the expected data: first difference at offset 0x4")]
+    fn vector_changed() {
+        let path = temp_vector("changed", "hex");
+        write_vector(&path, &record());
+        let changed = Record {
+            name: TinyString::try_from(s!("strict!")).unwrap(),
+            ..record()
+        };
+        check_vector(&path, &changed, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match its rendering")]
+    fn vector_rendering() {
+        let path = temp_vector("rendering", "hex");
+        write_vector(&path, &record());
+        fs::write(debug_path(&path), format!("{:#?}\n", Record::strict_dumb())).unwrap();
+        assert_vector::<Record>(&path);
+    }
+
+    mod vectors {
+        use amplify::confinement::{
+            SmallVec, TinyAscii, TinyBlob, TinyOrdMap, TinyOrdSet, TinyString,
+        };
+        use amplify::num::{u24, u256};
+
+        use super::Record;
+        use crate::stl::{AlphaCaps, AlphaCapsNum};
+        use crate::{ByteArray, RString, StrictDecode, StrictEncode, StrictType, TypeName};
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        enum Shape {
+            #[default]
+            Point,
+            Circle(u16),
+            Rect {
+                width: u16,
+                height: u16,
+            },
+        }
+
+        crate::test_vectors! {
+            "tests/vectors" => {
+                u8_max: u8 = u8::MAX,
+                u24_value: u24 = u24::with(0x123456),
+                i64_min: i64 = i64::MIN,
+                u256_value: u256 = u256::from(0xDEADBEEF_u64) << 128,
+                bool_true: bool = true,
+                char_cyrillic: char = 'Ю',
+                f64_pi: f64 = core::f64::consts::PI,
+
+                tiny_string: TinyString = tiny_s!("strict"),
+                tiny_ascii: TinyAscii = TinyAscii::try_from(
+                    amplify::ascii::AsciiString::from_ascii(b"ascii".to_vec()).unwrap()
+                ).unwrap(),
+                type_name: TypeName = tn!("StrictType"),
+                rstring: RString<AlphaCaps, AlphaCapsNum, 1, 8> = RString::from("USD1"),
+                byte_array: ByteArray<4> = ByteArray::from([0xDE, 0xAD, 0xBE, 0xEF]),
+
+                tiny_blob: TinyBlob = tiny_blob![0x00, 0x01, 0xFF],
+                small_vec: SmallVec<u16> = small_vec![1, 0x100, 0xFFFF],
+                tiny_set: TinyOrdSet<u8> = tiny_bset![3, 1, 2],
+                tiny_map: TinyOrdMap<u8, TinyString> = tiny_bmap! {
+                    2 => tiny_s!("two"),
+                    1 => tiny_s!("one"),
+                },
+
+                option_none: Option<u16> = None,
+                option_some: Option<u16> = Some(0xCAFE),
+                array: [u8; 3] = [1, 2, 3],
+                tuple: (u8, u16, bool) = (1, 2, true),
+                record: Record = super::record(),
+                union_point: Shape = Shape::Point,
+                union_circle: Shape = Shape::Circle(5),
+                union_rect: Shape = Shape::Rect { width: 3, height: 4 },
+            }
+        }
+
+        #[test]
+        fn mutation_resilience() {
+            use super::super::assert_mutation_resilience as check;
+
+            check(&u8::MAX);
+            check(&u24::with(0x123456));
+            check(&i64::MIN);
+            check(&u256::from(0xDEADBEEF_u64));
+            check(&true);
+            check(&'Ю');
+            check(&tiny_s!("strict"));
+            check(&TinyString::new());
+            check(&RString::<AlphaCaps, AlphaCapsNum, 1, 8>::from("USD1"));
+            check(&tn!("StrictType"));
+            check(&ByteArray::from([0xDE, 0xAD, 0xBE, 0xEF]));
+            check(&tiny_blob![0x00, 0x01, 0xFF]);
+            check(&small_vec![1u16, 0x100, 0xFFFF]);
+            check(&tiny_bset![3u8, 1, 2]);
+            check(&tiny_bmap! { 2u8 => tiny_s!("two"), 1 => tiny_s!("one") });
+            check(&None::<u16>);
+            check(&Some(Some(true)));
+            check(&(1u8, 2u16, true));
+            check(&super::record());
+            check(&Shape::Point);
+            check(&Shape::Circle(5));
+            check(&Shape::Rect {
+                width: 3,
+                height: 4,
+            });
+            check(&core::time::Duration::new(5, 999_999_999));
+            check(&std::time::UNIX_EPOCH);
+        }
+    }
+}
@@ -0,0 +1,621 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Text representations of strict-encoded data.
+//!
+//! [`TextOf`] wraps a value and is displayed as its strict encoding in one of
+//! the [`TextEncoding`]s: [`Base16`] (lowercase hex), [`Base64`] (RFC 4648
+//! standard alphabet with padding), [`Base32`] (RFC 4648 alphabet with padding, requires `base32`
+//! feature) or [`Base58Btc`] (Bitcoin alphabet, requires `base58` feature). The
+//! encodings are canonical: parsing rejects text which is not produced by the
+//! display of the same data, such that each value has exactly one text form.
+//!
+//! Parsing rejects text longer than any encoding of `MAX_LEN` bytes before
+//! decoding it, and data longer than `MAX_LEN` bytes before strict decoding
+//! them, and requires the decoded data to be entirely consumed.
+//!
+//! [`to_text`] and [`from_text`] convert values to and from an editable
+//! notation of their fields instead.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::str::FromStr;
+
+pub use crate::notation::{from_text, to_text};
+use crate::reader::decode_slice;
+use crate::value::ValueError;
+pub use crate::DEFAULT_MAX_LEN;
+use crate::{DeserializeError, HexError, StrictDecode, StrictEncode, StrictWriter};
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum TextError {
+    /// invalid {encoding} character '{ch}' at position {pos}.
+    InvalidChar {
+        encoding: &'static str,
+        ch: char,
+        pos: usize,
+    },
+
+    /// {encoding} text has invalid length or padding, or is not canonical.
+    InvalidLength { encoding: &'static str },
+
+    /// text encodes {len} bytes, exceeding the limit of {max_len} bytes.
+    Oversize { len: usize, max_len: usize },
+
+    /// text of {len} characters is longer than the limit of {max_len}
+    /// characters.
+    TextOversize { len: usize, max_len: usize },
+
+    /// {msg} at line {line}, column {column}.
+    Syntax {
+        line: usize,
+        column: usize,
+        msg: String,
+    },
+
+    #[display(inner)]
+    #[from]
+    Decode(DeserializeError),
+
+    #[display(inner)]
+    #[from]
+    Value(ValueError),
+}
+
+impl_error!(TextError);
+
+/// Encoding of binary data as text.
+pub trait TextEncoding {
+    /// Name of the encoding used in the errors.
+    const NAME: &'static str;
+
+    /// Maximal length of the text encoding `len` bytes.
+    fn max_text_len(len: usize) -> usize;
+
+    fn encode(data: &[u8]) -> String;
+
+    fn decode(text: &str) -> Result<Vec<u8>, TextError>;
+}
+
+/// Value represented as text of its strict encoding in encoding `E`.
+///
+/// Displaying fails with [`fmt::Error`] if the strict encoding of the value
+/// exceeds `MAX_LEN` bytes.
+pub struct TextOf<E: TextEncoding, T, const MAX_LEN: usize = DEFAULT_MAX_LEN>(
+    pub T,
+    PhantomData<E>,
+);
+
+pub type Base16Of<T, const MAX_LEN: usize = DEFAULT_MAX_LEN> = TextOf<Base16, T, MAX_LEN>;
+pub type Base64Of<T, const MAX_LEN: usize = DEFAULT_MAX_LEN> = TextOf<Base64, T, MAX_LEN>;
+#[cfg(feature = "base32")]
+pub type Base32Of<T, const MAX_LEN: usize = DEFAULT_MAX_LEN> = TextOf<Base32, T, MAX_LEN>;
+#[cfg(feature = "base58")]
+pub type Base58Of<T, const MAX_LEN: usize = DEFAULT_MAX_LEN> = TextOf<Base58Btc, T, MAX_LEN>;
+
+impl<E: TextEncoding, T, const MAX_LEN: usize> TextOf<E, T, MAX_LEN> {
+    pub fn new(value: T) -> Self { TextOf(value, PhantomData) }
+
+    pub fn into_inner(self) -> T { self.0 }
+}
+
+impl<E: TextEncoding, T, const MAX_LEN: usize> From<T> for TextOf<E, T, MAX_LEN> {
+    fn from(value: T) -> Self { TextOf::new(value) }
+}
+
+impl<E: TextEncoding, T: Clone, const MAX_LEN: usize> Clone for TextOf<E, T, MAX_LEN> {
+    fn clone(&self) -> Self { TextOf::new(self.0.clone()) }
+}
+
+impl<E: TextEncoding, T: PartialEq, const MAX_LEN: usize> PartialEq for TextOf<E, T, MAX_LEN> {
+    fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+}
+
+impl<E: TextEncoding, T: Eq, const MAX_LEN: usize> Eq for TextOf<E, T, MAX_LEN> {}
+
+impl<E: TextEncoding, T: Debug, const MAX_LEN: usize> Debug for TextOf<E, T, MAX_LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple(E::NAME).field(&self.0).finish()
+    }
+}
+
+impl<E: TextEncoding, T, const MAX_LEN: usize> Deref for TextOf<E, T, MAX_LEN> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<E: TextEncoding, T, const MAX_LEN: usize> DerefMut for TextOf<E, T, MAX_LEN> {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
+
+impl<E: TextEncoding, T: StrictEncode, const MAX_LEN: usize> Display for TextOf<E, T, MAX_LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let writer = StrictWriter::in_memory::<MAX_LEN>();
+        let data = self.0.strict_encode(writer).map_err(|_| fmt::Error)?.unbox().unconfine();
+        f.write_str(&E::encode(&data))
+    }
+}
+
+impl<E: TextEncoding, T: StrictDecode, const MAX_LEN: usize> FromStr for TextOf<E, T, MAX_LEN> {
+    type Err = TextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let max_text_len = E::max_text_len(MAX_LEN);
+        if s.len() > max_text_len {
+            return Err(TextError::TextOversize {
+                len: s.len(),
+                max_len: max_text_len,
+            });
+        }
+        let data = E::decode(s)?;
+        if data.len() > MAX_LEN {
+            return Err(TextError::Oversize {
+                len: data.len(),
+                max_len: MAX_LEN,
+            });
+        }
+        Ok(TextOf::new(decode_slice::<T, MAX_LEN>(&data)?))
+    }
+}
+
+/// Encodes data with `BITS` bits per character from `alphabet`, padding the
+/// text with `=` up to a multiple of `GROUP` characters.
+fn encode_bits<const BITS: u32, const GROUP: usize>(data: &[u8], alphabet: &[u8]) -> String {
+    let mask = (1u32 << BITS) - 1;
+    let mut text = String::with_capacity((data.len() * 8).div_ceil(BITS as usize) + GROUP);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for byte in data {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        while bits >= BITS {
+            bits -= BITS;
+            text.push(alphabet[((acc >> bits) & mask) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        text.push(alphabet[((acc << (BITS - bits)) & mask) as usize] as char);
+    }
+    while text.len() % GROUP != 0 {
+        text.push('=');
+    }
+    text
+}
+
+fn decode_bits<const BITS: u32, const GROUP: usize>(
+    text: &str,
+    alphabet: &[u8],
+    encoding: &'static str,
+) -> Result<Vec<u8>, TextError> {
+    let chars = text.trim_end_matches('=');
+    let mut data = Vec::with_capacity(chars.len() * BITS as usize / 8);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for (pos, ch) in chars.chars().enumerate() {
+        let Some(val) = alphabet.iter().position(|c| *c as char == ch) else {
+            return Err(TextError::InvalidChar { encoding, ch, pos });
+        };
+        acc = (acc << BITS) | val as u32;
+        bits += BITS;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // the remaining bits must be the zero padding of the last byte, and the
+    // text must be padded to the full group
+    if bits >= BITS || acc != 0 || text.len() != chars.len().div_ceil(GROUP) * GROUP {
+        return Err(TextError::InvalidLength { encoding });
+    }
+    Ok(data)
+}
+
+/// Hex encoding with lowercase digits.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Base16;
+
+impl Base16 {
+    const ALPHABET: &'static [u8; 16] = b"0123456789abcdef";
+
+    /// Decodes hex digits of any case, passing each decoded byte to `push`.
+    /// The digits come with their positions in the text, which are reported
+    /// in the errors.
+    pub(crate) fn decode_digits(
+        digits: impl IntoIterator<Item = (usize, char)>,
+        mut push: impl FnMut(u8),
+    ) -> Result<(), HexError> {
+        let mut high = None;
+        let mut count = 0usize;
+        for (pos, ch) in digits {
+            let nibble = ch.to_digit(16).ok_or(HexError::InvalidChar(ch, pos))? as u8;
+            count += 1;
+            match high.take() {
+                None => high = Some(nibble),
+                Some(high) => push((high << 4) | nibble),
+            }
+        }
+        if high.is_some() {
+            return Err(HexError::OddLength(count));
+        }
+        Ok(())
+    }
+}
+
+impl TextEncoding for Base16 {
+    const NAME: &'static str = "Hex";
+
+    fn max_text_len(len: usize) -> usize { len.saturating_mul(2) }
+
+    fn encode(data: &[u8]) -> String {
+        let mut text = String::with_capacity(data.len() * 2);
+        for byte in data {
+            text.push(Self::ALPHABET[(byte >> 4) as usize] as char);
+            text.push(Self::ALPHABET[(byte & 0x0F) as usize] as char);
+        }
+        text
+    }
+
+    // uppercase digits are not produced by the encoding, thus not canonical
+    fn decode(text: &str) -> Result<Vec<u8>, TextError> {
+        let encoding = Self::NAME;
+        let mut chars = text.chars().enumerate();
+        if let Some((pos, ch)) = chars.clone().find(|(_, ch)| ch.is_ascii_uppercase()) {
+            return Err(TextError::InvalidChar { encoding, ch, pos });
+        }
+        let mut data = Vec::with_capacity(text.len() / 2);
+        Self::decode_digits(&mut chars, |byte| data.push(byte)).map_err(|err| match err {
+            HexError::InvalidChar(ch, pos) => TextError::InvalidChar { encoding, ch, pos },
+            _ => TextError::InvalidLength { encoding },
+        })?;
+        Ok(data)
+    }
+}
+
+/// Base64 encoding with the standard RFC 4648 alphabet and padding.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Base64;
+
+impl Base64 {
+    const ALPHABET: &'static [u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+}
+
+impl TextEncoding for Base64 {
+    const NAME: &'static str = "Base64";
+
+    fn max_text_len(len: usize) -> usize { len.div_ceil(3).saturating_mul(4) }
+
+    fn encode(data: &[u8]) -> String { encode_bits::<6, 4>(data, Self::ALPHABET) }
+
+    fn decode(text: &str) -> Result<Vec<u8>, TextError> {
+        decode_bits::<6, 4>(text, Self::ALPHABET, Self::NAME)
+    }
+}
+
+/// Base32 encoding with the RFC 4648 alphabet and padding.
+#[cfg(feature = "base32")]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Base32;
+
+#[cfg(feature = "base32")]
+impl Base32 {
+    const ALPHABET: &'static [u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+}
+
+#[cfg(feature = "base32")]
+impl TextEncoding for Base32 {
+    const NAME: &'static str = "Base32";
+
+    fn max_text_len(len: usize) -> usize { len.div_ceil(5).saturating_mul(8) }
+
+    fn encode(data: &[u8]) -> String { encode_bits::<5, 8>(data, Self::ALPHABET) }
+
+    fn decode(text: &str) -> Result<Vec<u8>, TextError> {
+        decode_bits::<5, 8>(text, Self::ALPHABET, Self::NAME)
+    }
+}
+
+/// Base58 encoding with the Bitcoin alphabet, where each leading zero byte is
+/// represented by a `1` character.
+///
+/// The encoding takes time quadratic to the data length.
+#[cfg(feature = "base58")]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Base58Btc;
+
+#[cfg(feature = "base58")]
+impl Base58Btc {
+    const ALPHABET: &'static [u8; 58] =
+        b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    /// Converts big-endian digits in base `from` into little-endian digits in
+    /// base `to`, not including leading zeros.
+    fn convert(digits: impl Iterator<Item = u32>, from: u32, to: u32) -> Vec<u8> {
+        let mut output = Vec::<u8>::new();
+        for digit in digits {
+            let mut carry = digit;
+            for out in &mut output {
+                carry += *out as u32 * from;
+                *out = (carry % to) as u8;
+                carry /= to;
+            }
+            while carry > 0 {
+                output.push((carry % to) as u8);
+                carry /= to;
+            }
+        }
+        output
+    }
+}
+
+#[cfg(feature = "base58")]
+impl TextEncoding for Base58Btc {
+    const NAME: &'static str = "Base58";
+
+    // each leading zero byte takes a single character, and the other bytes
+    // take `log(256) / log(58) < 1.37` characters each
+    fn max_text_len(len: usize) -> usize { len.saturating_mul(137) / 100 + 1 }
+
+    fn encode(data: &[u8]) -> String {
+        let zeros = data.iter().take_while(|byte| **byte == 0).count();
+        let digits = Self::convert(data[zeros..].iter().map(|byte| *byte as u32), 256, 58);
+        let mut text = String::with_capacity(zeros + digits.len());
+        text.extend(core::iter::repeat('1').take(zeros));
+        text.extend(digits.iter().rev().map(|digit| Self::ALPHABET[*digit as usize] as char));
+        text
+    }
+
+    fn decode(text: &str) -> Result<Vec<u8>, TextError> {
+        let mut digits = Vec::with_capacity(text.len());
+        for (pos, ch) in text.chars().enumerate() {
+            let Some(val) = Self::ALPHABET.iter().position(|c| *c as char == ch) else {
+                return Err(TextError::InvalidChar {
+                    encoding: Self::NAME,
+                    ch,
+                    pos,
+                });
+            };
+            digits.push(val as u32);
+        }
+        let zeros = digits.iter().take_while(|digit| **digit == 0).count();
+        let bytes = Self::convert(digits[zeros..].iter().copied(), 58, 256);
+        let mut data = vec![0u8; zeros];
+        data.extend(bytes.iter().rev());
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, TinyString};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{DecodeError, StrictType};
+
+    type Blob = Confined<Vec<u8>, 0, 64>;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        name: TinyString,
+        version: u16,
+    }
+
+    fn header() -> Header {
+        Header {
+            name: TinyString::try_from(s!("foobar")).unwrap(),
+            version: 0x0102,
+        }
+    }
+
+    /// Byte strings up to 64 bytes, some of them starting with zero bytes.
+    fn samples() -> impl Strategy<Value = Vec<u8>> {
+        (0..4usize, vec(any::<u8>(), 0..=60)).prop_map(|(zeros, data)| {
+            let mut sample = vec![0u8; zeros];
+            sample.extend(data);
+            sample
+        })
+    }
+
+    fn roundtrip<E: TextEncoding>(data: Vec<u8>) -> Result<(), TestCaseError> {
+        let text = E::encode(&data);
+        prop_assert!(text.len() <= E::max_text_len(data.len()));
+        prop_assert_eq!(E::decode(&text).unwrap(), data.clone());
+        let blob = TextOf::<E, _>::new(Blob::try_from(data).unwrap());
+        prop_assert_eq!(TextOf::<E, Blob>::from_str(&blob.to_string()).unwrap(), blob);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn base16_roundtrip(data in samples()) { roundtrip::<Base16>(data)?; }
+
+        #[test]
+        fn base64_roundtrip(data in samples()) { roundtrip::<Base64>(data)?; }
+
+        #[test]
+        #[cfg(feature = "base32")]
+        fn base32_roundtrip(data in samples()) { roundtrip::<Base32>(data)?; }
+
+        #[test]
+        #[cfg(feature = "base58")]
+        fn base58_roundtrip(data in samples()) { roundtrip::<Base58Btc>(data)?; }
+    }
+
+    #[test]
+    fn base16() {
+        assert_eq!(Base16::encode(&[0x02, 0xAB, 0xF3]), "02abf3");
+        assert_eq!(Base16::decode("02abf3").unwrap(), [0x02, 0xAB, 0xF3]);
+        let invalid_char = |ch, pos| {
+            Err(TextError::InvalidChar {
+                encoding: "Hex",
+                ch,
+                pos,
+            })
+        };
+        assert_eq!(Base16::decode("02ABf3"), invalid_char('A', 2));
+        assert_eq!(Base16::decode("02 abf3"), invalid_char(' ', 2));
+        assert_eq!(Base16::decode("02abf"), Err(TextError::InvalidLength { encoding: "Hex" }));
+
+        let text = Base16Of::<Header>::new(header()).to_string();
+        assert_eq!(text, "06666f6f6261720201");
+        assert_eq!(Base16Of::<Header>::from_str(&text).unwrap().into_inner(), header());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(Base64::encode(b""), "");
+        assert_eq!(Base64::encode(b"f"), "Zg==");
+        assert_eq!(Base64::encode(b"fo"), "Zm8=");
+        assert_eq!(Base64::encode(b"foo"), "Zm9v");
+        assert_eq!(Base64::encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(Base64::encode(&[0xFB, 0xFF]), "+/8=");
+
+        let text = Base64Of::<Header>::new(header()).to_string();
+        assert_eq!(text, "BmZvb2JhcgIB");
+        assert_eq!(Base64Of::<Header>::from_str(&text).unwrap().into_inner(), header());
+    }
+
+    #[test]
+    fn base64_invalid() {
+        let invalid_len = Err(TextError::InvalidLength { encoding: "Base64" });
+        assert_eq!(Base64::decode("Zg"), invalid_len);
+        assert_eq!(Base64::decode("Zg="), invalid_len);
+        assert_eq!(Base64::decode("Zg==="), invalid_len);
+        assert_eq!(Base64::decode("Z==="), invalid_len);
+        assert_eq!(Base64::decode("===="), invalid_len);
+        // non-zero padding bits
+        assert_eq!(Base64::decode("Zh=="), invalid_len);
+        assert_eq!(
+            Base64::decode("Zm9=").unwrap_err().to_string(),
+            "Base64 text has invalid length or padding, or is not canonical."
+        );
+        assert_eq!(
+            Base64::decode("Zm-v"),
+            Err(TextError::InvalidChar {
+                encoding: "Base64",
+                ch: '-',
+                pos: 2
+            })
+        );
+        assert_eq!(
+            Base64::decode("Z=g="),
+            Err(TextError::InvalidChar {
+                encoding: "Base64",
+                ch: '=',
+                pos: 1
+            })
+        );
+        assert_eq!(
+            Base64::decode("Zм9v").unwrap_err().to_string(),
+            "invalid Base64 character 'м' at position 1."
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "base32")]
+    fn base32() {
+        assert_eq!(Base32::encode(b""), "");
+        assert_eq!(Base32::encode(b"f"), "MY======");
+        assert_eq!(Base32::encode(b"fo"), "MZXQ====");
+        assert_eq!(Base32::encode(b"foo"), "MZXW6===");
+        assert_eq!(Base32::encode(b"foob"), "MZXW6YQ=");
+        assert_eq!(Base32::encode(b"fooba"), "MZXW6YTB");
+        assert_eq!(Base32::encode(b"foobar"), "MZXW6YTBOI======");
+
+        let text = Base32Of::<Header>::new(header()).to_string();
+        assert_eq!(text, "AZTG633CMFZAEAI=");
+        assert_eq!(Base32Of::<Header>::from_str(&text).unwrap().into_inner(), header());
+
+        let invalid_len = Err(TextError::InvalidLength { encoding: "Base32" });
+        assert_eq!(Base32::decode("MZXW6"), invalid_len);
+        assert_eq!(Base32::decode("MZX====="), invalid_len);
+        assert_eq!(Base32::decode("MZ======"), invalid_len);
+        assert_eq!(
+            Base32::decode("mzxw6==="),
+            Err(TextError::InvalidChar {
+                encoding: "Base32",
+                ch: 'm',
+                pos: 0
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "base58")]
+    fn base58() {
+        assert_eq!(Base58Btc::encode(b""), "");
+        assert_eq!(Base58Btc::encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(Base58Btc::encode(&[0, 0, 0x28, 0x7F, 0xB4, 0xCD]), "11233QC4");
+        assert_eq!(Base58Btc::encode(&[0; 3]), "111");
+        assert_eq!(Base58Btc::decode("11233QC4").unwrap(), [0, 0, 0x28, 0x7F, 0xB4, 0xCD]);
+
+        let text = Base58Of::<Header>::new(header()).to_string();
+        assert_eq!(text, "5j3sjbsanmE8");
+        assert_eq!(Base58Of::<Header>::from_str(&text).unwrap().into_inner(), header());
+
+        assert_eq!(
+            Base58Btc::decode("2NEpo0TZ"),
+            Err(TextError::InvalidChar {
+                encoding: "Base58",
+                ch: '0',
+                pos: 5
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        // valid text, but invalid strict encoding
+        let err = Base64Of::<Header>::from_str("B2Zvb2JhcgIB").unwrap_err();
+        assert!(matches!(err, TextError::Decode(DeserializeError::Decode(DecodeError::Io(_)))));
+        let err = Base64Of::<Header>::from_str("BmZvb2JhcgIBAA==").unwrap_err();
+        assert_eq!(err, TextError::Decode(DeserializeError::DataNotEntirelyConsumed(1)));
+
+        let err = TextOf::<Base64, Header, 8>::from_str("BmZvb2JhcgIB").unwrap_err();
+        assert_eq!(err, TextError::Oversize { len: 9, max_len: 8 });
+        assert_eq!(err.to_string(), "text encodes 9 bytes, exceeding the limit of 8 bytes.");
+        // oversized text is rejected before being decoded
+        let err = TextOf::<Base64, Header, 8>::from_str("BmZvb2JhcgIBAA==").unwrap_err();
+        assert_eq!(err, TextError::TextOversize {
+            len: 16,
+            max_len: 12
+        });
+        let err = TextOf::<Base64, Header, 8>::from_str(&"-".repeat(13)).unwrap_err();
+        assert_eq!(err, TextError::TextOversize {
+            len: 13,
+            max_len: 12
+        });
+
+        let mut text = String::new();
+        let res =
+            fmt::write(&mut text, format_args!("{}", TextOf::<Base64, Header, 8>::new(header())));
+        assert_eq!(res, Err(fmt::Error));
+    }
+}
@@ -0,0 +1,217 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`Duration`] and [`SystemTime`].
+//!
+//! [`Duration`] is encoded as a structure of `u64` seconds and `u32`
+//! nanoseconds, which must be less than `1_000_000_000`.
+//!
+//! [`SystemTime`] is encoded as a structure of `i64` seconds relative to
+//! [`UNIX_EPOCH`] and `u32` nanoseconds added to them, such that the
+//! nanoseconds are always positive: one and a half seconds before the epoch is
+//! encoded as `-2` seconds and `500_000_000` nanoseconds. [`SystemTime`] is
+//! available only with `std` feature.
+
+use alloc::borrow::ToOwned;
+use core::ops::Range;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    io, DecodeError, ReadStruct, StrictDecode, StrictEncode, StrictProduct, StrictStruct,
+    StrictType, TypeName, TypedRead, TypedWrite, WriteStruct, LIB_NAME_STD,
+};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+const NANOS_RANGE: Range<u128> = 0..NANOS_PER_SEC as u128;
+
+pub(crate) fn check_nanos<T>(nanos: u32) -> Result<u32, DecodeError> {
+    if nanos >= NANOS_PER_SEC {
+        return Err(DecodeError::ValueOutOfRange(
+            core::any::type_name::<T>().to_owned(),
+            NANOS_RANGE,
+            nanos as u128,
+        ));
+    }
+    Ok(nanos)
+}
+
+impl StrictType for Duration {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Duration")) }
+}
+impl StrictProduct for Duration {}
+impl StrictStruct for Duration {
+    const ALL_FIELDS: &'static [&'static str] = &["secs", "nanos"];
+}
+impl StrictEncode for Duration {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_struct::<Self>(|w| {
+            Ok(w.write_field(fname!("secs"), &self.as_secs())?
+                .write_field(fname!("nanos"), &self.subsec_nanos())?
+                .complete())
+        })
+    }
+}
+impl StrictDecode for Duration {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let secs = r.read_field(fname!("secs"))?;
+            let nanos = check_nanos::<Self>(r.read_field(fname!("nanos"))?)?;
+            Ok(Duration::new(secs, nanos))
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+// `SystemTime` doesn't implement `Default` and can't be `StrictDumb`, thus it
+// is encoded through this mirror type.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_STD, rename = "SystemTime", crate = crate)]
+pub(crate) struct UnixTime {
+    secs: i64,
+    nanos: u32,
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&SystemTime> for UnixTime {
+    type Error = io::Error;
+
+    fn try_from(time: &SystemTime) -> Result<Self, Self::Error> {
+        let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => (i64::try_from(since.as_secs()), since.subsec_nanos()),
+            Err(err) => {
+                let before = err.duration();
+                // the negation is done in a wider type, since `i64::MIN` seconds
+                // don't have a positive counterpart
+                let secs = -(before.as_secs() as i128);
+                match before.subsec_nanos() {
+                    0 => (i64::try_from(secs), 0),
+                    nanos => (i64::try_from(secs - 1), NANOS_PER_SEC - nanos),
+                }
+            }
+        };
+        let secs = secs.map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(UnixTime { secs, nanos })
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<UnixTime> for SystemTime {
+    type Error = DecodeError;
+
+    fn try_from(time: UnixTime) -> Result<Self, Self::Error> {
+        let nanos = Duration::from_nanos(check_nanos::<SystemTime>(time.nanos)? as u64);
+        let secs = Duration::from_secs(time.secs.unsigned_abs());
+        match time.secs {
+            0.. => UNIX_EPOCH.checked_add(secs),
+            _ => UNIX_EPOCH.checked_sub(secs),
+        }
+        .and_then(|t| t.checked_add(nanos))
+        .ok_or_else(|| {
+            DecodeError::DataIntegrityError(format!(
+                "time {}s {}ns relative to the UNIX epoch can't be represented by the system",
+                time.secs, time.nanos
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl StrictType for SystemTime {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("SystemTime")) }
+}
+#[cfg(feature = "std")]
+impl StrictEncode for SystemTime {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        UnixTime::try_from(self)?.strict_encode(writer)
+    }
+}
+#[cfg(feature = "std")]
+impl StrictDecode for SystemTime {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        UnixTime::strict_decode(reader).and_then(SystemTime::try_from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{decode_err, encoding};
+
+    #[test]
+    fn duration() {
+        encoding(&Duration::ZERO, [0u8; 12]);
+        encoding(&Duration::new(1, 500_000_000), [1, 0, 0, 0, 0, 0, 0, 0, 0x00, 0x65, 0xCD, 0x1D]);
+        encoding(&Duration::MAX, [
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xC9, 0x9A, 0x3B,
+        ]);
+    }
+
+    #[test]
+    fn duration_invalid_nanos() {
+        let err = decode_err::<Duration>(&[0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0xCA, 0x9A, 0x3B]);
+        assert!(matches!(
+            err,
+            DecodeError::ValueOutOfRange(_, range, 1_000_000_000) if range == NANOS_RANGE
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time() {
+        encoding(&UNIX_EPOCH, [0u8; 12]);
+
+        let before = UNIX_EPOCH - Duration::new(86400, 250_000_000);
+        encoding(&before, [0x7F, 0xAE, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x80, 0x17, 0xB4, 0x2C]);
+
+        let before = UNIX_EPOCH - Duration::from_secs(1);
+        encoding(&before, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+
+        // 3000-01-01T00:00:00.5Z
+        let future = UNIX_EPOCH + Duration::new(32_503_680_000, 500_000_000);
+        encoding(&future, [0x00, 0xCC, 0x5E, 0x91, 0x07, 0, 0, 0, 0x00, 0x65, 0xCD, 0x1D]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_min() {
+        // the earliest encodable time is representable on the platforms with
+        // 64-bit signed seconds only
+        let Some(min) = UNIX_EPOCH.checked_sub(Duration::from_secs(1 << 63)) else {
+            return;
+        };
+        encoding(&min, [0, 0, 0, 0, 0, 0, 0, 0x80, 0, 0, 0, 0]);
+        if let Some(earlier) = min.checked_sub(Duration::from_nanos(1)) {
+            assert!(earlier.strict_encode(crate::StrictWriter::in_memory::<12>()).is_err());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_invalid_nanos() {
+        let err = decode_err::<SystemTime>(&[0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0xCA, 0x9A, 0x3B]);
+        assert!(matches!(err, DecodeError::ValueOutOfRange(_, _, 1_000_000_000)));
+    }
+}
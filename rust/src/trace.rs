@@ -0,0 +1,262 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracing of the encoding, available with `trace` feature.
+//!
+//! [`TraceWriter`] passes everything written to it to the inner writer
+//! unchanged, reporting each chunk of written bytes with its offset and the
+//! scopes of the encoded structures, tuples, unions and enums as
+//! [`TraceEvent`]s, such that it is possible to see which bytes each part of
+//! a value is encoded into. Without the feature the scopes are not reported
+//! to the writers at all.
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::{io, ScopeKind, StrictWriter, TypeName, WriteRaw};
+
+/// Event reported by [`TraceWriter`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum TraceEvent<'a> {
+    /// Encoding of a value of a type starts at the offset.
+    Enter {
+        offset: usize,
+        kind: ScopeKind,
+        lib: &'static str,
+        name: Option<TypeName>,
+    },
+    /// Encoding of the value whose scope was entered last is complete.
+    Exit { offset: usize, kind: ScopeKind },
+    /// Bytes are written at the offset; empty writes are not reported.
+    Bytes { offset: usize, data: &'a [u8] },
+}
+
+/// Raw writer tracing the encoding; see [module documentation](self).
+///
+/// Scopes of the values failed to encode are not exited.
+pub struct TraceWriter<W: WriteRaw, F: FnMut(TraceEvent)> {
+    inner: W,
+    trace: F,
+    offset: usize,
+}
+
+impl<W: WriteRaw, F: FnMut(TraceEvent)> TraceWriter<W, F> {
+    pub fn new(inner: W, trace: F) -> Self {
+        Self {
+            inner,
+            trace,
+            offset: 0,
+        }
+    }
+
+    /// Number of bytes written so far.
+    pub fn offset(&self) -> usize { self.offset }
+
+    pub fn unbox(self) -> W { self.inner }
+
+    fn trace_bytes(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        (self.trace)(TraceEvent::Bytes {
+            offset: self.offset,
+            data,
+        });
+        self.offset += data.len();
+    }
+}
+
+impl<W: WriteRaw, F: FnMut(TraceEvent)> WriteRaw for TraceWriter<W, F> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        self.inner.write_raw::<MAX_LEN>(bytes)?;
+        self.trace_bytes(bytes);
+        Ok(())
+    }
+
+    fn write_raw_array<const LEN: usize>(&mut self, raw: [u8; LEN]) -> io::Result<()> {
+        self.inner.write_raw_array(raw)?;
+        self.trace_bytes(&raw);
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.offset) }
+
+    fn enter_scope(&mut self, kind: ScopeKind, lib: &'static str, name: Option<TypeName>) {
+        self.inner.enter_scope(kind, lib, name.clone());
+        (self.trace)(TraceEvent::Enter {
+            offset: self.offset,
+            kind,
+            lib,
+            name,
+        });
+    }
+
+    fn exit_scope(&mut self, kind: ScopeKind) {
+        self.inner.exit_scope(kind);
+        (self.trace)(TraceEvent::Exit {
+            offset: self.offset,
+            kind,
+        });
+    }
+}
+
+impl<W: WriteRaw, F: FnMut(TraceEvent)> StrictWriter<TraceWriter<W, F>> {
+    /// Constructs writer passing the data to the `inner` raw writer and
+    /// reporting the encoding to the `trace` consumer.
+    pub fn with_trace(inner: W, trace: F) -> Self { Self::with(TraceWriter::new(inner, trace)) }
+}
+
+/// Constructs trace consumer formatting each event as a separate line, which
+/// is passed to `line`. The line starts with the offset, followed by the hex of
+/// the written bytes or the name of the entered scope, indented according to
+/// the depth of the scope.
+pub fn hex_trace(mut line: impl FnMut(String)) -> impl FnMut(TraceEvent) {
+    let mut depth = 0usize;
+    move |event: TraceEvent| match event {
+        TraceEvent::Enter {
+            offset,
+            kind,
+            lib,
+            name,
+        } => {
+            let name = name.as_ref().map(TypeName::as_str).unwrap_or("<unnamed>");
+            line(format!("{offset:08x} {:indent$}{kind} {lib}.{name}", "", indent = depth * 2));
+            depth += 1;
+        }
+        TraceEvent::Exit { offset, kind } => {
+            depth = depth.saturating_sub(1);
+            line(format!("{offset:08x} {:indent$}end {kind}", "", indent = depth * 2));
+        }
+        TraceEvent::Bytes { offset, data } => {
+            let hex = data.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+            line(format!("{offset:08x} {:indent$}{hex}", "", indent = depth * 2));
+        }
+    }
+}
+
+/// Constructs [`hex_trace`] consumer printing the lines to the standard error.
+#[cfg(feature = "std")]
+pub fn hex_trace_to_stderr() -> impl FnMut(TraceEvent) { hex_trace(|line| eprintln!("{line}")) }
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::{StreamWriter, StrictDecode, StrictDumb, StrictEncode, StrictType};
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8, crate = crate)]
+    #[repr(u8)]
+    enum Kind {
+        #[default]
+        Plain = 0,
+        Signed = 1,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Shape {
+        #[default]
+        Point,
+        Circle(u16),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Pair(u8, u16);
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        kind: Kind,
+        shape: Shape,
+        pair: Pair,
+        name: TinyString,
+    }
+
+    fn record() -> Record {
+        Record {
+            kind: Kind::Signed,
+            shape: Shape::Circle(0x0102),
+            pair: Pair(3, 4),
+            name: TinyString::try_from(s!("ab")).unwrap(),
+        }
+    }
+
+    #[test]
+    fn passthrough() {
+        let mut chunks = Vec::new();
+        let mut scopes = 0isize;
+        let writer = StrictWriter::with_trace(
+            StreamWriter::in_memory::<{ usize::MAX }>(),
+            |event: TraceEvent| match event {
+                TraceEvent::Enter { .. } => scopes += 1,
+                TraceEvent::Exit { .. } => scopes -= 1,
+                TraceEvent::Bytes { offset, data } => chunks.push((offset, data.to_vec())),
+            },
+        );
+        let traced = record().strict_encode(writer).unwrap().unbox();
+        assert_eq!(traced.offset(), 10);
+        let traced = traced.unbox().unconfine();
+        assert_eq!(scopes, 0);
+
+        assert_eq!(traced, crate::test::encode(&record()));
+        let mut offset = 0;
+        for (pos, data) in &chunks {
+            assert_eq!(*pos, offset);
+            offset += data.len();
+        }
+        assert_eq!(chunks.into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>(), traced);
+    }
+
+    #[test]
+    fn hex_lines() {
+        let mut lines = Vec::new();
+        let writer = StrictWriter::with_trace(
+            StreamWriter::in_memory::<{ usize::MAX }>(),
+            hex_trace(|line| lines.push(line)),
+        );
+        Record::strict_dumb().strict_encode(writer).unwrap();
+        assert_eq!(lines, vec![
+            "00000000 struct Test.Record",
+            "00000000   enum Test.Kind",
+            "00000000     00",
+            "00000001   end enum",
+            "00000001   union Test.Shape",
+            "00000001     00",
+            "00000002   end union",
+            "00000002   tuple Test.Pair",
+            "00000002     00",
+            "00000003     0000",
+            "00000005   end tuple",
+            "00000005   00",
+            "00000006 end struct",
+        ]);
+    }
+}
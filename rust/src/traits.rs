@@ -19,21 +19,41 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::{BufRead, Seek};
-use std::marker::PhantomData;
-use std::{fs, io};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::fs;
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::path::Path;
 
+#[cfg(feature = "bytes")]
+use ::bytes::Bytes;
 use amplify::confinement::{Collection, Confined};
 use amplify::num::u24;
 use amplify::Wrapper;
 
 use super::{DecodeError, DecodeRawLe, VariantName};
+use crate::layout::layout_dumb;
+#[cfg(feature = "std")]
+use crate::observer::CodecObserver;
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
 use crate::reader::StreamReader;
-use crate::writer::StreamWriter;
+use crate::reader::{decode_slice, decoded_name, list_name, map_name};
+use crate::skip::Skipped;
+use crate::text::{Base16, TextEncoding};
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use crate::writer::BufferedWriter;
 use crate::{
-    DeserializeError, FieldName, Primitive, SerializeError, Sizing, StrictDumb, StrictEnum,
-    StrictReader, StrictStruct, StrictSum, StrictTuple, StrictType, StrictUnion, StrictWriter,
+    io, Byte, DeserializeError, FieldName, HexDeserializeError, InvalidLength, PathSegment,
+    Primitive, RawEncoding, ScopedStruct, ScopedTuple, ScopedWriter, SerializeError, Sizing,
+    StrictDumb, StrictEnum, StrictMaybeDumb, StrictReader, StrictSkip, StrictStruct, StrictSum,
+    StrictTuple, StrictType, StrictUnion, StrictWriter, Utf8Policy,
 };
+#[cfg(feature = "trace")]
+use crate::{ScopeKind, TypeName};
 
 pub trait TypedParent: Sized {}
 
@@ -42,24 +62,48 @@ pub trait WriteRaw {
     fn write_raw_array<const LEN: usize>(&mut self, raw: [u8; LEN]) -> io::Result<()> {
         self.write_raw::<LEN>(raw)
     }
+    /// Writes the length prefix of a collection or a string which can't contain
+    /// more than `MAX_LEN` elements.
+    ///
+    /// The width of the little-endian prefix is the smallest of 1, 2, 3, 4 or
+    /// 8 bytes which can hold `MAX_LEN`, and thus depends only on the type and
+    /// never on the actual length: types with `MAX_LEN` up to `u8::MAX` use
+    /// `u8` prefix, up to `u16::MAX` - `u16`, up to `u24::MAX` - `u24`, up to
+    /// `u32::MAX` - `u32`, and `u64` above that. The rule is a part of the
+    /// strict encoding standard and must not change.
+    ///
+    /// Lengths exceeding `MAX_LEN` are never truncated: nothing is written and
+    /// the [`InvalidLength`] error is returned.
     fn write_raw_len<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<()> {
-        match MAX_LEN {
-            tiny if tiny <= u8::MAX as usize => self.write_raw_array((len as u8).to_le_bytes()),
-            small if small <= u16::MAX as usize => self.write_raw_array((len as u16).to_le_bytes()),
-            medium if medium <= u24::MAX.into_usize() => {
-                self.write_raw_array((u24::with(len as u32)).to_le_bytes())
-            }
-            large if large <= u32::MAX as usize => self.write_raw_array((len as u32).to_le_bytes()),
-            huge if huge <= u64::MAX as usize => self.write_raw_array((len as u64).to_le_bytes()),
-            _ => unreachable!("confined collections larger than u64::MAX must not exist"),
-        }
+        InvalidLength::check_with::<0, MAX_LEN>(len, || s!("collection"))?;
+        Sizing::new(0, MAX_LEN as u64).write_len(self, len)
     }
+    /// Number of bytes written by the writer, if the writer tracks it.
+    fn offset(&self) -> Option<usize> { None }
+    /// Notifies the writer that the encoding of a value of a named type
+    /// starts. Writers which trace the encoding report the scope; the default
+    /// implementation does nothing.
+    #[cfg(feature = "trace")]
+    fn enter_scope(&mut self, kind: ScopeKind, lib: &'static str, name: Option<TypeName>) {
+        let _ = (kind, lib, name);
+    }
+    /// Notifies the writer that the encoding of the value whose scope was
+    /// entered last is complete.
+    #[cfg(feature = "trace")]
+    fn exit_scope(&mut self, kind: ScopeKind) { let _ = kind; }
 }
 
 impl<T: WriteRaw> WriteRaw for &mut T {
     fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
         (*self).write_raw::<MAX_LEN>(bytes)
     }
+    fn offset(&self) -> Option<usize> { (**self).offset() }
+    #[cfg(feature = "trace")]
+    fn enter_scope(&mut self, kind: ScopeKind, lib: &'static str, name: Option<TypeName>) {
+        (*self).enter_scope(kind, lib, name)
+    }
+    #[cfg(feature = "trace")]
+    fn exit_scope(&mut self, kind: ScopeKind) { (*self).exit_scope(kind) }
 }
 
 #[allow(unused_variables)]
@@ -90,6 +134,40 @@ pub trait TypedWrite: Sized {
         self.write_tuple::<T>(|writer| Ok(writer.write_field(value)?.complete()))
     }
 
+    /// Writes data with the writer borrowed mutably; see [`ScopedWriter`].
+    fn write_scoped(
+        self,
+        inner: impl FnOnce(&mut ScopedWriter<Self>) -> io::Result<()>,
+    ) -> io::Result<Self> {
+        let mut writer = ScopedWriter::new(self);
+        inner(&mut writer)?;
+        Ok(writer.into_inner())
+    }
+    /// Writes a struct with its fields writer borrowed mutably, producing the
+    /// same data as [`Self::write_struct`].
+    fn write_struct_with<T: StrictStruct>(
+        self,
+        inner: impl FnOnce(&mut ScopedStruct<Self::StructWriter>) -> io::Result<()>,
+    ) -> io::Result<Self> {
+        self.write_struct::<T>(|writer| {
+            let mut writer = ScopedStruct::new(writer);
+            inner(&mut writer)?;
+            Ok(writer.complete())
+        })
+    }
+    /// Writes a tuple with its fields writer borrowed mutably, producing the
+    /// same data as [`Self::write_tuple`].
+    fn write_tuple_with<T: StrictTuple>(
+        self,
+        inner: impl FnOnce(&mut ScopedTuple<Self::TupleWriter>) -> io::Result<()>,
+    ) -> io::Result<Self> {
+        self.write_tuple::<T>(|writer| {
+            let mut writer = ScopedTuple::new(writer);
+            inner(&mut writer)?;
+            Ok(writer.complete())
+        })
+    }
+
     #[doc(hidden)]
     unsafe fn register_primitive(self, prim: Primitive) -> Self { self }
     #[doc(hidden)]
@@ -124,7 +202,14 @@ pub trait TypedWrite: Sized {
         self
     }
 
+    /// Registers type `T`, which data are written raw in the given encoding,
+    /// since they can't be described by the strict type system.
+    #[doc(hidden)]
+    unsafe fn register_raw<T: StrictType>(self, encoding: RawEncoding) -> Self { self }
+
     /// Used by unicode strings, ASCII strings and restricted char set strings.
+    /// The byte length of the string is prefixed using the same width rule as
+    /// collections; see [`WriteRaw::write_raw_len`].
     #[doc(hidden)]
     unsafe fn write_string<const MAX_LEN: usize>(
         mut self,
@@ -135,7 +220,8 @@ pub trait TypedWrite: Sized {
         Ok(self)
     }
 
-    /// Vec and sets - excluding strings, written by [`Self::write_string`].
+    /// Sets - excluding strings, written by [`Self::write_string`], and lists,
+    /// written by [`Self::write_items`].
     #[doc(hidden)]
     unsafe fn write_collection<C: Collection, const MIN_LEN: usize, const MAX_LEN: usize>(
         mut self,
@@ -145,6 +231,7 @@ pub trait TypedWrite: Sized {
         for<'a> &'a C: IntoIterator,
         for<'a> <&'a C as IntoIterator>::Item: StrictEncode,
     {
+        InvalidLength::check_with::<MIN_LEN, MAX_LEN>(col.len(), || s!("collection"))?;
         self.raw_writer().write_raw_len::<MAX_LEN>(col.len())?;
         for item in col {
             self = item.strict_encode(self)?;
@@ -152,14 +239,171 @@ pub trait TypedWrite: Sized {
         Ok(self)
     }
 
-    // TODO: Do `write_keyed_collection`
+    /// Items of arrays and lists, written one after another; see
+    /// [`StrictEncode::strict_encode_items`].
+    #[doc(hidden)]
+    unsafe fn write_items<T: StrictEncode>(self, items: &[T]) -> io::Result<Self> {
+        T::strict_encode_items(items, self)
+    }
+
+    /// Writes a list with the items produced by an iterator, without
+    /// collecting them into memory; the data are the same as of a
+    /// `Confined<Vec<T>, MIN_LEN, MAX_LEN>` with the same items.
+    ///
+    /// The length prefix is taken from [`ExactSizeIterator::len`]. A length
+    /// out of the `MIN_LEN..=MAX_LEN` bounds, as well as an iterator producing
+    /// a number of items different from the reported length, fails with
+    /// [`io::ErrorKind::InvalidInput`] error; the items written before the
+    /// failure are not reverted, thus the written data must be discarded.
+    fn write_collection_from_iter<
+        T: StrictEncode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    >(
+        mut self,
+        iter: impl ExactSizeIterator<Item = impl Borrow<T>>,
+    ) -> io::Result<Self> {
+        let len = iter.len();
+        InvalidLength::check_with::<MIN_LEN, MAX_LEN>(len, || list_name::<T>(MIN_LEN, MAX_LEN))?;
+        unsafe {
+            self.raw_writer().write_raw_len::<MAX_LEN>(len)?;
+        }
+        self = write_exact(self, iter, |w, item| item.borrow().strict_encode(w))?;
+        let sizing = Sizing::new(MIN_LEN as u64, MAX_LEN as u64);
+        Ok(unsafe {
+            if T::strict_name() == u8::strict_name() {
+                self.register_list(&Byte::strict_dumb(), sizing)
+            } else {
+                self.register_list(&layout_dumb::<T>(), sizing)
+            }
+        })
+    }
+
+    /// Writes a map with the entries produced by an iterator, without
+    /// collecting them into memory; the data are the same as of a
+    /// `Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>` with the same entries.
+    ///
+    /// The keys must follow in strictly ascending order, which is verified as
+    /// the entries are written; otherwise, and in all the cases described in
+    /// [`Self::write_collection_from_iter`], the method fails with
+    /// [`io::ErrorKind::InvalidInput`] error.
+    fn write_keyed_collection_from_iter<
+        K: StrictEncode + StrictDumb + Ord,
+        V: StrictEncode + StrictDumb,
+        const MIN_LEN: usize,
+        const MAX_LEN: usize,
+    >(
+        mut self,
+        iter: impl ExactSizeIterator<Item = (impl Borrow<K>, impl Borrow<V>)>,
+    ) -> io::Result<Self> {
+        let len = iter.len();
+        InvalidLength::check_with::<MIN_LEN, MAX_LEN>(len, || map_name::<K, V>(MIN_LEN, MAX_LEN))?;
+        unsafe {
+            self.raw_writer().write_raw_len::<MAX_LEN>(len)?;
+        }
+        let mut last = None;
+        self = write_exact(self, iter, |w, (key, val)| {
+            match last {
+                Some(ref last) if Borrow::<K>::borrow(last) >= key.borrow() => {
+                    return Err(io::ErrorKind::InvalidInput.into());
+                }
+                _ => {}
+            }
+            let w = key.borrow().strict_encode(w)?;
+            let w = val.borrow().strict_encode(w)?;
+            last = Some(key);
+            Ok(w)
+        })?;
+        Ok(unsafe {
+            self.register_map(
+                &layout_dumb::<K>(),
+                &layout_dumb::<V>(),
+                Sizing::new(MIN_LEN as u64, MAX_LEN as u64),
+            )
+        })
+    }
+}
+
+/// Writes the items of an iterator with `write`, failing with
+/// [`io::ErrorKind::InvalidInput`] error if the iterator produces a number of
+/// items different from its reported length.
+fn write_exact<W, I: ExactSizeIterator>(
+    mut writer: W,
+    iter: I,
+    mut write: impl FnMut(W, I::Item) -> io::Result<W>,
+) -> io::Result<W> {
+    let len = iter.len();
+    let mut count = 0usize;
+    for item in iter {
+        count += 1;
+        if count > len {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        writer = write(writer, item)?;
+    }
+    if count < len {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    Ok(writer)
 }
 
+/// Size of the chunks in which the skipped data are read by default; see
+/// [`ReadRaw::skip_raw`].
+const SKIP_CHUNK_LEN: usize = 256;
+
 pub trait ReadRaw {
     fn read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>>;
 
     fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]>;
 
+    /// Reads `len` bytes into a reference-counted buffer. Readers over a
+    /// [`Bytes`] buffer return a slice of it without copying the data; the
+    /// default implementation takes the data read by [`ReadRaw::read_raw`].
+    #[cfg(feature = "bytes")]
+    fn read_raw_bytes<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Bytes> {
+        self.read_raw::<MAX_LEN>(len).map(Bytes::from)
+    }
+
+    /// Reads `len` bytes, appending them to `buf` within its existing capacity
+    /// when possible; the default implementation copies the data read by
+    /// [`ReadRaw::read_raw`]. Since secrets are decoded with this method, the
+    /// readers should override it to read right into the buffer, leaving no
+    /// other copies of the data in memory.
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        buf.extend_from_slice(&self.read_raw::<MAX_LEN>(len)?);
+        Ok(())
+    }
+
+    /// Reads `len` bytes into a newly allocated shared buffer. Readers which
+    /// know that the data are present copy them right into the buffer; the
+    /// default implementation copies the data read by [`ReadRaw::read_raw`].
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        self.read_raw::<MAX_LEN>(len).map(Arc::from)
+    }
+
+    /// Consumes `len` bytes without returning them. In-memory readers just
+    /// advance their position; the default implementation reads the data in
+    /// chunks of a fixed size, without allocating memory for them.
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> {
+        let mut left = len;
+        while left >= SKIP_CHUNK_LEN {
+            self.read_raw_array::<SKIP_CHUNK_LEN>()?;
+            left -= SKIP_CHUNK_LEN;
+        }
+        for _ in 0..left {
+            self.read_raw_array::<1>()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the length prefix of a collection or a string which can't contain
+    /// more than `MAX_LEN` elements; the prefix width is selected by the rule
+    /// described in [`WriteRaw::write_raw_len`]. The returned length is not
+    /// checked against `MAX_LEN`.
     fn read_raw_len<const MAX_LEN: usize>(&mut self) -> Result<usize, DecodeError> {
         Ok(match MAX_LEN {
             tiny if tiny <= u8::MAX as usize => u8::decode_raw_le(self)? as usize,
@@ -170,6 +414,47 @@ pub trait ReadRaw {
             _ => unreachable!("confined collections larger than u64::MAX must not exist"),
         })
     }
+
+    /// Number of bytes consumed by the reader, if the reader tracks it.
+    fn offset(&self) -> Option<usize> { None }
+
+    /// Returns the next byte without consuming it.
+    ///
+    /// The default implementation fails with [`io::ErrorKind::Unsupported`]
+    /// without reading anything; readers which can look ahead or
+    /// [`ReadRaw::rewind`] override it.
+    fn peek_byte(&mut self) -> io::Result<u8> { Err(io::ErrorKind::Unsupported.into()) }
+
+    /// Moves the reader `len` bytes back, such that they are read once again,
+    /// and the confinement limit and byte count are adjusted accordingly.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `len` exceeds the number
+    /// of bytes which the reader can return back; the maximum depth depends on
+    /// the reader. The default implementation fails with
+    /// [`io::ErrorKind::Unsupported`].
+    fn rewind(&mut self, len: usize) -> io::Result<()> {
+        let _ = len;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Consumes the data left in the reader, returning their number of bytes.
+    ///
+    /// In-memory readers of this crate count all the data left, including the
+    /// data beyond the confinement limit. Streams are read no further than the
+    /// first byte beyond the limit, such that an endless stream can't keep the
+    /// reader busy: the number reported for them is at most the rest of the
+    /// limit plus one byte. The default implementation reads the data byte by
+    /// byte, failing when the limit is reached.
+    fn drain(&mut self) -> io::Result<usize> {
+        let mut len = 0usize;
+        loop {
+            match self.read_raw_array::<1>() {
+                Ok(_) => len = len.saturating_add(1),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(len),
+                Err(err) => return Err(err),
+            }
+        }
+    }
 }
 
 impl<T: ReadRaw> ReadRaw for &mut T {
@@ -180,6 +465,33 @@ impl<T: ReadRaw> ReadRaw for &mut T {
     fn read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
         (*self).read_raw_array::<LEN>()
     }
+
+    #[cfg(feature = "bytes")]
+    fn read_raw_bytes<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Bytes> {
+        (*self).read_raw_bytes::<MAX_LEN>(len)
+    }
+
+    fn read_raw_into<const MAX_LEN: usize>(
+        &mut self,
+        len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        (*self).read_raw_into::<MAX_LEN>(len, buf)
+    }
+
+    fn read_raw_shared<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Arc<[u8]>> {
+        (*self).read_raw_shared::<MAX_LEN>(len)
+    }
+
+    fn skip_raw(&mut self, len: usize) -> io::Result<()> { (**self).skip_raw(len) }
+
+    fn offset(&self) -> Option<usize> { (**self).offset() }
+
+    fn peek_byte(&mut self) -> io::Result<u8> { (**self).peek_byte() }
+
+    fn rewind(&mut self, len: usize) -> io::Result<()> { (**self).rewind(len) }
+
+    fn drain(&mut self) -> io::Result<usize> { (**self).drain() }
 }
 
 pub trait TypedRead {
@@ -193,6 +505,52 @@ pub trait TypedRead {
     #[doc(hidden)]
     unsafe fn raw_reader(&mut self) -> &mut Self::RawReader;
 
+    /// Policy of handling invalid UTF-8 sequences in the decoded strings;
+    /// [`Utf8Policy::Strict`] unless the reader is configured otherwise.
+    fn utf8_policy(&self) -> Utf8Policy { Utf8Policy::Strict }
+
+    /// Decodes a value located at the given path `segment` relative to the
+    /// value which is currently decoded. Readers which keep track of the
+    /// decoding context use the segment to report location of decoding errors;
+    /// the default implementation just runs the `decode` procedure.
+    fn in_context<T>(
+        &mut self,
+        segment: impl FnOnce() -> PathSegment,
+        decode: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where Self: Sized {
+        let _ = segment;
+        decode(self)
+    }
+
+    /// Decodes a value nested into the value which is currently decoded, like
+    /// an item of a collection. Readers limiting the depth of nesting fail with
+    /// [`DecodeError::MaxRecursionDepth`] once the limit is exceeded; the
+    /// default implementation just runs the `decode` procedure.
+    ///
+    /// Unions, tuples and structures are entered by the reader itself when
+    /// they are read.
+    fn in_depth<T>(
+        &mut self,
+        decode: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: Sized,
+    {
+        decode(self)
+    }
+
+    /// Accounts `len` bytes of memory which are going to be allocated for the
+    /// decoded collection or string, as announced by its length prefix.
+    /// Readers with an allocation budget fail with
+    /// [`DecodeError::AllocationBudgetExceeded`] once the total of the
+    /// accounted bytes exceeds the budget, before the memory is allocated; the
+    /// default implementation does nothing.
+    fn alloc(&mut self, len: usize) -> Result<(), DecodeError> {
+        let _ = len;
+        Ok(())
+    }
+
     fn read_union<T: StrictUnion>(
         &mut self,
         inner: impl FnOnce(VariantName, &mut Self::UnionReader) -> Result<T, DecodeError>,
@@ -222,16 +580,90 @@ pub trait TypedRead {
         self.read_tuple(|reader| reader.read_field().map(T::from_inner))
     }
 
+    /// Advances the reader past a value of type `T` without decoding it; see
+    /// [`StrictSkip::strict_skip`].
+    fn skip<T: StrictEncode + StrictDumb>(&mut self) -> Result<(), DecodeError>
+    where Self: Sized {
+        T::strict_skip(self)
+    }
+
+    /// Returns the next byte without consuming it, allowing to choose the way
+    /// a value is decoded, for instance by its union tag; see
+    /// [`ReadRaw::peek_byte`].
+    fn peek_byte(&mut self) -> io::Result<u8> { unsafe { self.raw_reader() }.peek_byte() }
+
+    /// Moves the reader `len` bytes back; see [`ReadRaw::rewind`].
+    fn rewind(&mut self, len: usize) -> io::Result<()> { unsafe { self.raw_reader() }.rewind(len) }
+
+    /// Reads the length prefix of a collection or a string of type `T`, which
+    /// must contain from `MIN_LEN` to `MAX_LEN` elements. Lengths outside of
+    /// the bounds are reported with [`DecodeError::Oversize`] and
+    /// [`DecodeError::Undersize`] before any of the elements are read.
     #[doc(hidden)]
-    unsafe fn read_string<const MAX_LEN: usize>(&mut self) -> Result<Vec<u8>, DecodeError> {
+    unsafe fn read_len<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+    ) -> Result<usize, DecodeError> {
         let len = self.raw_reader().read_raw_len::<MAX_LEN>()?;
+        Sizing::new(MIN_LEN as u64, MAX_LEN as u64).check_len(len as u64, decoded_name::<T>)
+    }
+
+    #[doc(hidden)]
+    unsafe fn read_string<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let len = self.read_len::<T, MIN_LEN, MAX_LEN>()?;
+        self.alloc(len)?;
         self.raw_reader().read_raw::<MAX_LEN>(len).map_err(DecodeError::from)
     }
+
+    /// Reads a string of type `T` like [`TypedRead::read_string`], replacing
+    /// the contents of `buf` while reusing its capacity.
+    #[doc(hidden)]
+    unsafe fn read_string_into<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        let len = self.read_len::<T, MIN_LEN, MAX_LEN>()?;
+        self.alloc(len)?;
+        buf.clear();
+        self.raw_reader().read_raw_into::<MAX_LEN>(len, buf).map_err(DecodeError::from)
+    }
+
+    /// Reads a byte string of type `T` into a reference-counted buffer, which
+    /// shares the data of the readers over [`Bytes`] buffers.
+    #[cfg(feature = "bytes")]
+    #[doc(hidden)]
+    unsafe fn read_bytes<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+    ) -> Result<Bytes, DecodeError> {
+        let len = self.read_len::<T, MIN_LEN, MAX_LEN>()?;
+        self.alloc(len)?;
+        self.raw_reader().read_raw_bytes::<MAX_LEN>(len).map_err(DecodeError::from)
+    }
+
+    /// Reads a byte string of type `T` into a newly allocated shared buffer.
+    #[doc(hidden)]
+    unsafe fn read_shared<T: StrictType, const MIN_LEN: usize, const MAX_LEN: usize>(
+        &mut self,
+    ) -> Result<Arc<[u8]>, DecodeError> {
+        let len = self.read_len::<T, MIN_LEN, MAX_LEN>()?;
+        self.alloc(len)?;
+        self.raw_reader().read_raw_shared::<MAX_LEN>(len).map_err(DecodeError::from)
+    }
+
+    /// Reads an array of `LEN` bytes into a newly allocated shared buffer.
+    #[doc(hidden)]
+    unsafe fn read_shared_array<const LEN: usize>(
+        &mut self,
+    ) -> Result<Arc<[u8; LEN]>, DecodeError> {
+        self.alloc(LEN)?;
+        Ok(Arc::new(self.raw_reader().read_raw_array::<LEN>()?))
+    }
 }
 
 pub trait DefineTuple: Sized {
     type Parent: TypedParent;
-    fn define_field<T: StrictEncode + StrictDumb>(self) -> Self;
+    fn define_field<T: StrictEncode + StrictMaybeDumb>(self) -> Self;
     fn complete(self) -> Self::Parent;
 }
 
@@ -243,11 +675,21 @@ pub trait WriteTuple: Sized {
 
 pub trait ReadTuple {
     fn read_field<T: StrictDecode>(&mut self) -> Result<T, DecodeError>;
+
+    /// Reads a trailing field which may be absent from the data encoded by the
+    /// earlier versions of the type. If the data end exactly before the field,
+    /// the field gets [`StrictDumb::strict_dumb`] value.
+    ///
+    /// The default implementation reads the field as a required one; readers
+    /// which can detect the end of the data override it.
+    fn read_optional_field<T: StrictDecode + StrictDumb>(&mut self) -> Result<T, DecodeError> {
+        self.read_field()
+    }
 }
 
 pub trait DefineStruct: Sized {
     type Parent: TypedParent;
-    fn define_field<T: StrictEncode + StrictDumb>(self, name: FieldName) -> Self;
+    fn define_field<T: StrictEncode + StrictMaybeDumb>(self, name: FieldName) -> Self;
     fn complete(self) -> Self::Parent;
 }
 
@@ -259,6 +701,24 @@ pub trait WriteStruct: Sized {
 
 pub trait ReadStruct {
     fn read_field<T: StrictDecode>(&mut self, field: FieldName) -> Result<T, DecodeError>;
+
+    /// Reads a trailing field which may be absent from the data encoded by the
+    /// earlier versions of the type; see [`ReadTuple::read_optional_field`].
+    fn read_optional_field<T: StrictDecode + StrictDumb>(
+        &mut self,
+        field: FieldName,
+    ) -> Result<T, DecodeError> {
+        self.read_field(field)
+    }
+
+    /// Skips a field of type `T` without decoding it, marking it as read; see
+    /// [`TypedRead::skip`].
+    fn skip_field<T: StrictEncode + StrictDumb>(
+        &mut self,
+        field: FieldName,
+    ) -> Result<(), DecodeError> {
+        self.read_field::<Skipped<T>>(field).map(|_| ())
+    }
 }
 
 pub trait DefineEnum: Sized {
@@ -281,7 +741,9 @@ pub trait DefineUnion: Sized {
     type UnionWriter: WriteUnion<Parent = Self::Parent>;
 
     fn define_unit(self, name: VariantName) -> Self;
-    fn define_newtype<T: StrictEncode + StrictDumb>(self, name: VariantName) -> Self {
+    /// Defines a tuple variant with a single field of type `T`, in the same
+    /// way as [`Self::define_tuple`] does.
+    fn define_newtype<T: StrictEncode + StrictMaybeDumb>(self, name: VariantName) -> Self {
         self.define_tuple(name, |definer| definer.define_field::<T>().complete())
     }
     fn define_tuple(
@@ -304,6 +766,8 @@ pub trait WriteUnion: Sized {
     type StructWriter: WriteStruct<Parent = Self>;
 
     fn write_unit(self, name: VariantName) -> io::Result<Self>;
+    /// Writes a tuple variant with a single field, producing the same data as
+    /// [`Self::write_tuple`] writing the field does.
     fn write_newtype(self, name: VariantName, value: &impl StrictEncode) -> io::Result<Self> {
         self.write_tuple(name, |writer| Ok(writer.write_field(value)?.complete()))
     }
@@ -343,18 +807,88 @@ pub trait ReadUnion: Sized {
         Self: 'parent,
         'me: 'parent;
 
+    /// Reads a tuple variant having `field_count` fields, like
+    /// [`Self::read_tuple`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`DecodeError::FieldsNotRead`] if `inner` reads a different
+    /// number of fields. The default implementation doesn't count the fields
+    /// and doesn't check them.
+    fn read_tuple_checked<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        field_count: u8,
+        inner: impl FnOnce(&mut Self::TupleReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        let _ = field_count;
+        self.read_tuple(inner)
+    }
+
+    /// Reads a struct variant having `all_fields` fields, like
+    /// [`Self::read_struct`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`DecodeError::FieldsNotRead`] if `inner` doesn't read all of
+    /// the fields, or reads other fields. The default implementation doesn't
+    /// track the fields and doesn't check them.
+    fn read_struct_checked<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        all_fields: &'static [&'static str],
+        inner: impl FnOnce(&mut Self::StructReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        let _ = all_fields;
+        self.read_struct(inner)
+    }
+
     fn read_newtype<T: StrictSum + From<I>, I: StrictDecode>(&mut self) -> Result<T, DecodeError> {
-        self.read_tuple(|reader| reader.read_field::<I>().map(T::from))
+        self.read_tuple_checked(1, |reader| reader.read_field::<I>().map(T::from))
+    }
+
+    /// Reads a tuple variant with a single field, constructing the value with
+    /// `variant`, like `r.read_newtype_variant(Message::Transfer)`; see
+    /// [`WriteUnion::write_newtype`].
+    fn read_newtype_variant<T: StrictSum, I: StrictDecode>(
+        &mut self,
+        variant: impl FnOnce(I) -> T,
+    ) -> Result<T, DecodeError> {
+        self.read_tuple_checked(1, |reader| reader.read_field::<I>().map(variant))
     }
 }
 
 pub trait StrictEncode: StrictType {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W>;
+    /// Encodes items of an array or a list one after another. Primitive
+    /// integers override it to write the little-endian bytes of all the items
+    /// with a single call, producing the same data.
+    #[doc(hidden)]
+    fn strict_encode_items<W: TypedWrite>(items: &[Self], mut writer: W) -> io::Result<W>
+    where Self: Sized {
+        for item in items {
+            writer = item.strict_encode(writer)?;
+        }
+        Ok(writer)
+    }
     fn strict_write(&self, writer: impl WriteRaw) -> io::Result<()> {
         let w = StrictWriter::with(writer);
         self.strict_encode(w)?;
         Ok(())
     }
+    /// Computes the length of the serialized value without allocating memory
+    /// for its data. Fails in the same way as other writers if the length
+    /// exceeds `MAX` limit.
+    fn strict_encoded_len<const MAX: usize>(&self) -> io::Result<usize> {
+        let counter = StrictWriter::counter::<MAX>();
+        Ok(self.strict_encode(counter)?.unbox().unconfine().count)
+    }
 }
 
 pub trait StrictDecode: StrictType {
@@ -363,6 +897,37 @@ pub trait StrictDecode: StrictType {
         let mut r = StrictReader::with(reader);
         Self::strict_decode(&mut r)
     }
+
+    /// Renders the decoded value for the decoding errors, like the ones
+    /// reporting repeated keys of a map. Types which can't be rendered return
+    /// `None`.
+    #[doc(hidden)]
+    fn strict_display(&self) -> Option<String> { None }
+}
+
+/// Encoding of the fields of a structure in place, as the fields of another
+/// structure, without the structure itself.
+///
+/// Derived together with [`StrictEncode`] implementation for the structures
+/// with named fields marked with `#[strict_type(flattenable)]` attribute; the
+/// fields of other structures marked with `#[strict_type(flatten)]` attribute
+/// are encoded with it. Since strict
+/// encoding doesn't frame structures, the data are the same as of the
+/// structure encoded as a field; the flattened fields are listed among
+/// [`StrictStruct::ALL_FIELDS`] of the outer structure instead.
+pub trait StrictEncodeFields: StrictStruct + StrictEncode {
+    /// Writes the fields one after another into the writer of the outer
+    /// structure.
+    fn strict_encode_fields<W: WriteStruct>(&self, writer: W) -> io::Result<W>;
+}
+
+/// Decoding of the fields of a structure flattened into another structure,
+/// which is the counterpart of [`StrictEncodeFields`], derived together with
+/// [`StrictDecode`] implementation for the same structures.
+pub trait StrictDecodeFields: StrictStruct + StrictDecode {
+    /// Reads the fields one after another from the reader of the outer
+    /// structure.
+    fn strict_decode_fields(reader: &mut impl ReadStruct) -> Result<Self, DecodeError>;
 }
 
 impl<T: StrictEncode> StrictEncode for &T {
@@ -371,18 +936,24 @@ impl<T: StrictEncode> StrictEncode for &T {
     }
 }
 
-impl<T> StrictEncode for PhantomData<T> {
+impl<T: ?Sized> StrictEncode for PhantomData<T> {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> { Ok(writer) }
 }
 
-impl<T> StrictDecode for PhantomData<T> {
+impl<T: ?Sized> StrictDecode for PhantomData<T> {
     fn strict_decode(_reader: &mut impl TypedRead) -> Result<Self, DecodeError> { Ok(default!()) }
 }
 
+/// Serialization of a value into a byte vector of at most `MAX` bytes.
+///
+/// The trait is not implemented for all the strict types: each type opts in
+/// with an empty `impl StrictSerialize for MyType {}`, which makes serializing
+/// it into a complete message (and picking the `MAX` limit at the call sites)
+/// a conscious decision. Values whose encoding exceeds `MAX` bytes fail with
+/// [`io::ErrorKind::InvalidInput`] error.
 pub trait StrictSerialize: StrictEncode {
     fn strict_serialized_len<const MAX: usize>(&self) -> io::Result<usize> {
-        let counter = StrictWriter::counter::<MAX>();
-        Ok(self.strict_encode(counter)?.unbox().unconfine().count)
+        self.strict_encoded_len::<MAX>()
     }
 
     fn to_strict_serialized<const MAX: usize>(
@@ -393,42 +964,603 @@ pub trait StrictSerialize: StrictEncode {
         Confined::<Vec<u8>, 0, MAX>::try_from(data).map_err(SerializeError::from)
     }
 
+    /// Serializes the value like [`Self::to_strict_serialized`], reporting the
+    /// encoding of the value and of the values it consists of to the observer.
+    #[cfg(feature = "std")]
+    fn to_strict_serialized_observed<const MAX: usize>(
+        &self,
+        observer: Arc<dyn CodecObserver>,
+    ) -> Result<Confined<Vec<u8>, 0, MAX>, SerializeError> {
+        let writer = StrictWriter::in_memory::<MAX>().with_observer(observer);
+        let data = self.strict_encode(writer)?.unbox().unconfine();
+        Confined::<Vec<u8>, 0, MAX>::try_from(data).map_err(SerializeError::from)
+    }
+
+    /// Serializes the value into a buffer which is zeroized on drop, such that
+    /// no copies of secret data are left in memory; see
+    /// [`ZeroizingWriter`](crate::ZeroizingWriter).
+    #[cfg(feature = "zeroize")]
+    fn to_strict_vec_zeroizing<const MAX: usize>(
+        &self,
+    ) -> Result<zeroize::Zeroizing<Vec<u8>>, SerializeError> {
+        let writer = StrictWriter::zeroizing::<MAX>();
+        Ok(self.strict_encode(writer)?.unbox().into_inner())
+    }
+
+    /// Serializes the value into a [`TypedBlob`](crate::TypedBlob) of at most
+    /// `MAX` bytes, prefixing it with the semantic id of the type.
+    #[cfg(feature = "sha2")]
+    fn to_typed_blob<const MAX: usize>(&self) -> Result<Confined<Vec<u8>, 0, MAX>, SerializeError>
+    where Self: crate::StrictSemId {
+        let writer = crate::typed_blob::encode_typed(self, StrictWriter::in_memory::<MAX>())?;
+        Confined::<Vec<u8>, 0, MAX>::try_from(writer.unbox().unconfine())
+            .map_err(SerializeError::from)
+    }
+
+    /// Serializes the value into a lowercase hex string without a prefix.
+    fn to_strict_hex<const MAX: usize>(&self) -> Result<String, SerializeError> {
+        let data = self.to_strict_serialized::<MAX>()?;
+        Ok(Base16::encode(&data))
+    }
+
+    /// Serializes the value into the file at `path`, replacing it atomically.
+    ///
+    /// The data are written to a temporary file in the same directory, which
+    /// is synced to the disk and then renamed into `path`, such that a failure
+    /// at any point never leaves a partially written file at `path`.
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
     fn strict_serialize_to_file<const MAX: usize>(
         &self,
-        path: impl AsRef<std::path::Path>,
+        path: impl AsRef<Path>,
     ) -> Result<(), SerializeError> {
-        let file = fs::File::create(path)?;
-        // TODO: Do FileReader
-        let file = StrictWriter::with(StreamWriter::new::<MAX>(file));
-        self.strict_encode(file)?;
+        let path = path.as_ref();
+        let tmp = tmp_path(path);
+        let res = fs::File::create(&tmp).map_err(SerializeError::from).and_then(|file| {
+            let writer = StrictWriter::with(BufferedWriter::new::<MAX>(file));
+            let (file, _) = self.strict_encode(writer)?.finish()?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)?;
+            Ok(())
+        });
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+            return res;
+        }
+        // Make the rename durable by syncing the directory
+        #[cfg(unix)]
+        if let Some(dir) = path.parent() {
+            let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+            fs::File::open(dir)?.sync_all()?;
+        }
         Ok(())
     }
 }
 
+/// Path of a temporary file, located in the same directory as `path`.
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+fn tmp_path(path: &Path) -> std::path::PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+    path.with_file_name(name)
+}
+
+/// Deserialization of a value from a complete message of at most `MAX` bytes,
+/// which is the counterpart of [`StrictSerialize`].
+///
+/// Each type opts in with an empty `impl StrictDeserialize for MyType {}`.
+/// The data must be entirely consumed by the value: trailing bytes are
+/// rejected with [`DeserializeError::DataNotEntirelyConsumed`].
 pub trait StrictDeserialize: StrictDecode {
     fn from_strict_serialized<const MAX: usize>(
         ast_data: Confined<Vec<u8>, 0, MAX>,
     ) -> Result<Self, DeserializeError> {
         let mut reader = StrictReader::in_memory::<MAX>(ast_data);
         let me = Self::strict_decode(&mut reader)?;
-        let mut cursor = reader.into_cursor();
-        if !cursor.fill_buf()?.is_empty() {
-            return Err(DeserializeError::DataNotEntirelyConsumed);
-        }
+        reader.finish()?;
         Ok(me)
     }
 
+    fn from_strict_slice<const MAX: usize>(data: &[u8]) -> Result<Self, DeserializeError> {
+        decode_slice::<Self, MAX>(data)
+    }
+
+    /// Deserializes the value like [`Self::from_strict_slice`], reporting the
+    /// decoding of the value and of the values it consists of to the observer.
+    #[cfg(feature = "std")]
+    fn from_strict_slice_observed<const MAX: usize>(
+        data: &[u8],
+        observer: Arc<dyn CodecObserver>,
+    ) -> Result<Self, DeserializeError> {
+        let mut reader = StrictReader::from_slice::<MAX>(data).with_observer(observer);
+        let me = Self::strict_decode(&mut reader)?;
+        reader.finish()?;
+        Ok(me)
+    }
+
+    /// Deserializes the value from a [`TypedBlob`](crate::TypedBlob), which
+    /// must be entirely consumed.
+    ///
+    /// The semantic id of the blob is checked before decoding the value; blobs
+    /// of other types are rejected with [`DecodeError::TypeMismatch`].
+    #[cfg(feature = "sha2")]
+    fn from_typed_blob<const MAX: usize>(data: &[u8]) -> Result<Self, DeserializeError>
+    where Self: crate::StrictSemId {
+        use crate::TypedBlob;
+        TypedBlob::<Self>::from_strict_slice::<MAX>(data).map(TypedBlob::into_inner)
+    }
+
+    /// Deserializes the value from a hex string of its strict encoding, which
+    /// must be entirely consumed.
+    ///
+    /// The string may have `0x` prefix and contain whitespace, which is
+    /// ignored; the digits may be of any case. Positions of the invalid
+    /// characters are counted in characters from the start of the string.
+    fn from_strict_hex<const MAX: usize>(s: &str) -> Result<Self, HexDeserializeError> {
+        let trimmed = s.trim_start();
+        let prefix = match trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")) {
+            Some(digits) => s[..s.len() - digits.len()].chars().count(),
+            None => 0,
+        };
+        let digits = || s.chars().enumerate().skip(prefix).filter(|(_, ch)| !ch.is_whitespace());
+        let len = digits().count().div_ceil(2);
+        if len > MAX {
+            return Err(HexDeserializeError::Oversize { len, max_len: MAX });
+        }
+        let mut data = Vec::with_capacity(len);
+        Base16::decode_digits(digits(), |byte| data.push(byte))?;
+        Self::from_strict_slice::<MAX>(&data).map_err(HexDeserializeError::from)
+    }
+
+    /// Deserializes the value from the file at `path`, which must be entirely
+    /// consumed.
+    ///
+    /// Files larger than `MAX` bytes are rejected with
+    /// [`amplify::confinement::Error::Oversize`] without being read.
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
     fn strict_deserialize_from_file<const MAX: usize>(
-        path: impl AsRef<std::path::Path>,
+        path: impl AsRef<Path>,
     ) -> Result<Self, DeserializeError> {
         let file = fs::File::open(path)?;
-        // TODO: Do FileReader
-        let mut reader = StrictReader::with(StreamReader::new::<MAX>(file));
+        let len = file.metadata()?.len();
+        if len > MAX as u64 {
+            return Err(DecodeError::Confinement(amplify::confinement::Error::Oversize {
+                len: usize::try_from(len).unwrap_or(usize::MAX),
+                max_len: MAX,
+            })
+            .into());
+        }
+        let mut reader =
+            StrictReader::with(StreamReader::new::<MAX>(std::io::BufReader::new(file)));
         let me = Self::strict_decode(&mut reader)?;
-        let mut file = reader.unbox().unconfine();
-        if file.stream_position()? != file.seek(io::SeekFrom::End(0))? {
-            return Err(DeserializeError::DataNotEntirelyConsumed);
+        let left = len.saturating_sub(reader.count() as u64);
+        if left > 0 {
+            return Err(DeserializeError::DataNotEntirelyConsumed(left));
         }
         Ok(me)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{self, TinyVec};
+
+    use super::*;
+    use crate::test::encoding;
+    use crate::{HexError, StrictDecode, StrictEncode, StrictReader, StrictTypeInfo, TypeName};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Message(u16, u8);
+
+    impl StrictSerialize for Message {}
+    impl StrictDeserialize for Message {}
+
+    const MAX: usize = u16::MAX as usize;
+
+    /// Union which derived encoding uses the shorthand methods for the newtype
+    /// variant.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Transfer {
+        #[default]
+        Cancel,
+        Amount(u64),
+        Batch(u16, TinyVec<u8>),
+    }
+
+    /// The same union as [`Transfer`], implemented with the long form of the
+    /// newtype variants.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    enum TransferLong {
+        #[default]
+        Cancel,
+        Amount(u64),
+        Batch(u16, TinyVec<u8>),
+    }
+
+    impl StrictType for TransferLong {
+        const STRICT_LIB_NAME: &'static str = "Test";
+        fn strict_name() -> Option<TypeName> { Some(tn!("Transfer")) }
+    }
+    impl StrictSum for TransferLong {
+        const ALL_VARIANTS: &'static [(u8, &'static str)] =
+            &[(0, "cancel"), (1, "amount"), (2, "batch")];
+        fn variant_name(&self) -> &'static str {
+            match self {
+                TransferLong::Cancel => "cancel",
+                TransferLong::Amount(_) => "amount",
+                TransferLong::Batch(..) => "batch",
+            }
+        }
+    }
+    impl StrictUnion for TransferLong {}
+    impl StrictEncode for TransferLong {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            writer.write_union::<Self>(|u| {
+                let u = u
+                    .define_unit(vname!("cancel"))
+                    .define_tuple(vname!("amount"), |d| d.define_field::<u64>().complete())
+                    .define_tuple(vname!("batch"), |d| {
+                        d.define_field::<u16>().define_field::<TinyVec<u8>>().complete()
+                    })
+                    .complete();
+                Ok(match self {
+                    TransferLong::Cancel => u.write_unit(vname!("cancel")),
+                    TransferLong::Amount(amount) => {
+                        u.write_tuple(vname!("amount"), |w| Ok(w.write_field(amount)?.complete()))
+                    }
+                    TransferLong::Batch(id, data) => u.write_tuple(vname!("batch"), |w| {
+                        Ok(w.write_field(id)?.write_field(data)?.complete())
+                    }),
+                }?
+                .complete())
+            })
+        }
+    }
+    impl StrictDecode for TransferLong {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            reader.read_union(|name, u| match name.as_str() {
+                "cancel" => Ok(TransferLong::Cancel),
+                "amount" => u.read_tuple(|r| Ok(TransferLong::Amount(r.read_field()?))),
+                "batch" => {
+                    u.read_tuple(|r| Ok(TransferLong::Batch(r.read_field()?, r.read_field()?)))
+                }
+                _ => unreachable!(),
+            })
+        }
+    }
+
+    #[test]
+    fn union_shorthands() {
+        let data = TinyVec::try_from(vec![7u8, 8]).unwrap();
+        let cases = [
+            (Transfer::Cancel, TransferLong::Cancel, vec![0]),
+            (Transfer::Amount(0x0102), TransferLong::Amount(0x0102), vec![
+                1, 2, 1, 0, 0, 0, 0, 0, 0,
+            ]),
+            (Transfer::Batch(3, data.clone()), TransferLong::Batch(3, data), vec![
+                2, 3, 0, 2, 7, 8,
+            ]),
+        ];
+        for (short, long, bytes) in cases {
+            encoding(&short, &bytes);
+            encoding(&long, &bytes);
+        }
+        assert_eq!(Transfer::type_info(), TransferLong::type_info());
+
+        let mut reader = StrictReader::in_memory::<MAX>([1, 5, 0, 0, 0, 0, 0, 0, 0]);
+        let amount = reader.read_union(|_, u| u.read_newtype_variant(Transfer::Amount));
+        assert_eq!(amount.unwrap(), Transfer::Amount(5));
+    }
+
+    #[test]
+    fn deserialize_exact() {
+        let msg = Message(0x0102, 3);
+        let data = msg.to_strict_serialized::<MAX>().unwrap();
+        assert_eq!(data.as_slice(), &[2, 1, 3]);
+        assert_eq!(Message::from_strict_slice::<MAX>(&data).unwrap(), msg);
+        assert_eq!(Message::from_strict_serialized::<MAX>(data).unwrap(), msg);
+    }
+
+    #[test]
+    fn serialize_oversize() {
+        let msg = Message(0x0102, 3);
+        assert_eq!(msg.to_strict_serialized::<3>().unwrap().as_slice(), &[2, 1, 3]);
+        let SerializeError::Io(err) = msg.to_strict_serialized::<2>().unwrap_err() else {
+            panic!("encoding must fail")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn deserialize_trailing_bytes() {
+        let data = [2u8, 1, 3, 0xFF];
+        assert_eq!(
+            Message::from_strict_slice::<MAX>(&data).unwrap_err(),
+            DeserializeError::DataNotEntirelyConsumed(1)
+        );
+        let data = Confined::<Vec<u8>, 0, MAX>::try_from(vec![2u8, 1, 3, 0, 0]).unwrap();
+        assert_eq!(
+            Message::from_strict_serialized::<MAX>(data).unwrap_err(),
+            DeserializeError::DataNotEntirelyConsumed(2)
+        );
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let msg = Message(0xAB02, 0xF3);
+        assert_eq!(msg.to_strict_hex::<MAX>().unwrap(), "02abf3");
+        assert_eq!(Message(0, 0).to_strict_hex::<MAX>().unwrap(), "000000");
+        for hex in ["02abf3", "02ABF3", "0x02aBf3", "  0X02 ab\nf3\t", "0x 02 ab f3"] {
+            assert_eq!(Message::from_strict_hex::<MAX>(hex).unwrap(), msg, "{hex}");
+        }
+        let SerializeError::Io(err) = msg.to_strict_hex::<2>().unwrap_err() else {
+            panic!("encoding must fail")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn hex_errors() {
+        assert_eq!(
+            Message::from_strict_hex::<MAX>("02ag03").unwrap_err(),
+            HexDeserializeError::Hex(HexError::InvalidChar('g', 3))
+        );
+        assert_eq!(
+            Message::from_strict_hex::<MAX>(" 0x02 ab x3").unwrap_err(),
+            HexDeserializeError::Hex(HexError::InvalidChar('x', 9))
+        );
+        assert_eq!(
+            Message::from_strict_hex::<MAX>("0x0x02ab03").unwrap_err(),
+            HexDeserializeError::Hex(HexError::InvalidChar('x', 3))
+        );
+        assert_eq!(
+            Message::from_strict_hex::<MAX>("02ab0").unwrap_err(),
+            HexDeserializeError::Hex(HexError::OddLength(5))
+        );
+        assert_eq!(
+            Message::from_strict_hex::<2>("02ab03").unwrap_err(),
+            HexDeserializeError::Oversize { len: 3, max_len: 2 }
+        );
+        // oversized strings are rejected before their digits are decoded
+        assert_eq!(
+            Message::from_strict_hex::<2>("0x02 ab 0g").unwrap_err(),
+            HexDeserializeError::Oversize { len: 3, max_len: 2 }
+        );
+        assert_eq!(
+            Message::from_strict_hex::<MAX>("02ab0300").unwrap_err(),
+            HexDeserializeError::Deserialize(DeserializeError::DataNotEntirelyConsumed(1))
+        );
+        let HexDeserializeError::Deserialize(DeserializeError::Decode(DecodeError::Io(err))) =
+            Message::from_strict_hex::<MAX>("02ab").unwrap_err()
+        else {
+            panic!("truncated data must result in I/O error")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            HexDeserializeError::Hex(HexError::InvalidChar('g', 3)).to_string(),
+            "invalid hex character 'g' at position 3."
+        );
+    }
+
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("strict_encoding_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn dir_entries(dir: &Path) -> Vec<std::ffi::OsString> {
+        fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name()).collect()
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn file_roundtrip() {
+        let dir = test_dir("file_roundtrip");
+        let path = dir.join("message.dat");
+        let msg = Message(0x0102, 3);
+        msg.strict_serialize_to_file::<MAX>(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [2, 1, 3]);
+        assert_eq!(Message::strict_deserialize_from_file::<MAX>(&path).unwrap(), msg);
+        // the existing file is replaced
+        Message(7, 8).strict_serialize_to_file::<MAX>(&path).unwrap();
+        assert_eq!(Message::strict_deserialize_from_file::<MAX>(&path).unwrap(), Message(7, 8));
+        assert_eq!(dir_entries(&dir), ["message.dat"]);
+
+        fs::write(&path, [2u8, 1, 3, 4]).unwrap();
+        assert_eq!(
+            Message::strict_deserialize_from_file::<MAX>(&path).unwrap_err(),
+            DeserializeError::DataNotEntirelyConsumed(1)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn file_errors() {
+        let dir = test_dir("file_errors");
+        let path = dir.join("message.dat");
+
+        let DeserializeError::Decode(DecodeError::Io(err)) =
+            Message::strict_deserialize_from_file::<MAX>(&path).unwrap_err()
+        else {
+            panic!("missing file must result in I/O error")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        fs::write(&path, [0u8; 10]).unwrap();
+        assert_eq!(
+            Message::strict_deserialize_from_file::<4>(&path).unwrap_err(),
+            DeserializeError::Decode(DecodeError::Confinement(confinement::Error::Oversize {
+                len: 10,
+                max_len: 4
+            }))
+        );
+
+        fs::write(&path, [0xFFu8]).unwrap();
+        let DeserializeError::Decode(DecodeError::Io(err)) =
+            Message::strict_deserialize_from_file::<MAX>(&path).unwrap_err()
+        else {
+            panic!("truncated file must result in I/O error")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn file_interrupted_write() {
+        let dir = test_dir("file_interrupted_write");
+        let path = dir.join("message.dat");
+        Message(0x0102, 3).strict_serialize_to_file::<MAX>(&path).unwrap();
+
+        // serialization fails after the first field is written
+        let SerializeError::Io(err) =
+            Message(7, 8).strict_serialize_to_file::<2>(&path).unwrap_err()
+        else {
+            panic!("encoding must fail")
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read(&path).unwrap(), [2, 1, 3]);
+        assert_eq!(dir_entries(&dir), ["message.dat"]);
+
+        let new = dir.join("new.dat");
+        Message(7, 8).strict_serialize_to_file::<2>(&new).unwrap_err();
+        assert!(!new.exists());
+        assert_eq!(dir_entries(&dir), ["message.dat"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stream_leaves_data() {
+        let data = [2u8, 1, 3, 4, 5, 6];
+        let mut reader = StrictReader::in_memory::<MAX>(data);
+        assert_eq!(Message::strict_decode(&mut reader).unwrap(), Message(0x0102, 3));
+        assert_eq!(reader.count(), 3);
+        assert_eq!(Message::strict_decode(&mut reader).unwrap(), Message(0x0504, 6));
+        assert_eq!(reader.count(), 6);
+    }
+
+    /// Iterator which reports a length different from the number of the items
+    /// it produces.
+    struct Lying {
+        reported: usize,
+        items: core::ops::Range<u16>,
+    }
+
+    impl Iterator for Lying {
+        type Item = u16;
+        fn next(&mut self) -> Option<u16> { self.items.next() }
+    }
+
+    impl ExactSizeIterator for Lying {
+        fn len(&self) -> usize { self.reported }
+    }
+
+    fn write_list<const MIN: usize>(
+        iter: impl ExactSizeIterator<Item = u16>,
+    ) -> io::Result<Vec<u8>> {
+        let writer =
+            StrictWriter::in_memory::<MAX>().write_collection_from_iter::<u16, MIN, 0xFF>(iter)?;
+        Ok(writer.unbox().unconfine())
+    }
+
+    #[test]
+    fn list_from_iter() {
+        let items = (0..100u16).map(|i| i * 0x0101);
+        let list = confinement::TinyVec::<u16>::try_from_iter(items.clone()).unwrap();
+        assert_eq!(write_list::<0>(items).unwrap(), crate::test::encode(&list));
+        assert_eq!(write_list::<0>(0..0).unwrap(), [0]);
+
+        // items may be borrowed
+        let writer = StrictWriter::in_memory::<MAX>()
+            .write_collection_from_iter::<u16, 0, 0xFF>(list.iter())
+            .unwrap();
+        assert_eq!(writer.unbox().unconfine(), crate::test::encode(&list));
+
+        let bytes = confinement::SmallBlob::try_from(vec![0xAB; 300]).unwrap();
+        let writer = StrictWriter::in_memory::<MAX>()
+            .write_collection_from_iter::<u8, 0, 0xFFFF>(bytes.iter())
+            .unwrap();
+        assert_eq!(writer.unbox().unconfine(), crate::test::encode(&bytes));
+    }
+
+    #[test]
+    fn oversized_list() {
+        let items = vec![0u8; 70_000];
+        let err = StrictWriter::in_memory::<{ usize::MAX }>()
+            .write_collection_from_iter::<u8, 0, { confinement::U16 }>(items.iter())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            InvalidLength::from_io(&err),
+            Some(InvalidLength {
+                type_name: s!("[Byte]"),
+                len: 70_000,
+                min_len: 0,
+                max_len: confinement::U16,
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "`[Byte]` has 70000 elements, which is out of its bounds 0..=65535"
+        );
+
+        // raw length prefixes are never truncated
+        let mut writer = StrictWriter::in_memory::<16>();
+        let err = unsafe { writer.raw_writer() }.write_raw_len::<0xFF>(0x100).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(InvalidLength::from_io(&err).map(|err| err.len), Some(0x100));
+        assert!(writer.unbox().unconfine().is_empty());
+    }
+
+    #[test]
+    fn list_from_lying_iter() {
+        let err = write_list::<0>(Lying {
+            reported: 3,
+            items: 0..5,
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = write_list::<0>(Lying {
+            reported: 5,
+            items: 0..3,
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = write_list::<0>(0..0x100).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = write_list::<1>(0..0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn map_from_iter() {
+        use alloc::collections::BTreeMap;
+
+        let map = BTreeMap::from_iter((0..50u16).map(|k| (k * 7, k as u8)));
+        let writer = StrictWriter::in_memory::<MAX>()
+            .write_keyed_collection_from_iter::<u16, u8, 0, 0xFF>(map.iter())
+            .unwrap();
+        let map = confinement::TinyOrdMap::try_from(map).unwrap();
+        assert_eq!(writer.unbox().unconfine(), crate::test::encode(&map));
+
+        for entries in [[(1u16, 0u8), (3, 0), (2, 0)], [(1, 0), (2, 0), (2, 1)]] {
+            let err = StrictWriter::in_memory::<MAX>()
+                .write_keyed_collection_from_iter::<u16, u8, 0, 0xFF>(entries.into_iter())
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,484 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Type information of all strict types, including primitives, strings and
+//! collections, which is discovered from the strict encoding of their dumb
+//! values.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::layout::{
+    layout_dumb, layout_of, Fields, LayoutNode, LayoutVariant, TypeHeader, VariantKind,
+};
+use crate::{
+    FieldInfo, Primitive, RawEncoding, Sizing, StrictDumb, StrictEncode, TypeClassOwned, TypeDocs,
+    TypeInfoOwned, TypeRef, VariantInfo, LIB_EMBEDDED,
+};
+
+/// Provides type information for all strict types which can be encoded.
+///
+/// Unlike `strict_type_info` of [`StrictStruct`](crate::StrictStruct),
+/// [`StrictTuple`](crate::StrictTuple), [`StrictUnion`](crate::StrictUnion)
+/// and [`StrictEnum`](crate::StrictEnum), the information lists the types of
+/// all fields, union variant fields and collection items as [`TypeRef`]s.
+/// Named types are referenced by their names, such that the information of
+/// the types of a library can be resolved with a [`TypeLib`](crate::TypeLib)
+/// containing all of them. Primitives, strings and collections have no names
+/// and belong to the embedded library; they are described by their class
+/// alone.
+pub trait StrictTypeInfo: StrictEncode + StrictDumb {
+    /// Returns the type information; see [`TypeInfoOwned`].
+    fn type_info() -> TypeInfoOwned { layout_of(&layout_dumb::<Self>()) }
+}
+
+impl<T: StrictEncode + StrictDumb> StrictTypeInfo for T {}
+
+impl TypeInfoOwned {
+    fn embedded(class: TypeClassOwned) -> Self {
+        TypeInfoOwned {
+            lib: libname!(LIB_EMBEDDED),
+            name: None,
+            doc: None,
+            class,
+        }
+    }
+
+    fn named(ty: &TypeHeader, class: TypeClassOwned) -> Self {
+        TypeInfoOwned {
+            lib: libname!(ty.lib),
+            name: ty.name.clone(),
+            doc: ty.docs.doc.map(String::from),
+            class,
+        }
+    }
+}
+
+fn fields(fields: Fields<TypeInfoOwned>, docs: TypeDocs) -> Vec<FieldInfo> {
+    fields
+        .into_iter()
+        .zip(0u8..)
+        .map(|((name, info), ord)| FieldInfo {
+            name,
+            ord,
+            doc: docs.member(ord).map(String::from),
+            ty: Some(info.into()),
+        })
+        .collect()
+}
+
+fn is_option(variants: &[LayoutVariant<TypeInfoOwned>]) -> bool {
+    match variants {
+        [none, some] => {
+            (none.name.as_str(), none.tag, none.kind) == ("none", 0, VariantKind::Unit)
+                && (some.name.as_str(), some.tag, some.kind) == ("some", 1, VariantKind::Tuple)
+                && some.fields.len() == 1
+        }
+        _ => false,
+    }
+}
+
+impl LayoutNode for TypeInfoOwned {
+    fn primitive(primitive: Primitive) -> Self {
+        Self::embedded(TypeClassOwned::Primitive { primitive })
+    }
+
+    fn unicode(sizing: Sizing) -> Self { Self::embedded(TypeClassOwned::Unicode { sizing }) }
+
+    fn rstring(first: Self, rest: Self, sizing: Sizing) -> Self {
+        Self::embedded(TypeClassOwned::Rstring {
+            first: first.into(),
+            rest: rest.into(),
+            sizing,
+        })
+    }
+
+    fn array(item: Self, len: u16) -> Self {
+        Self::embedded(TypeClassOwned::Array {
+            item: item.into(),
+            len,
+        })
+    }
+
+    fn list(item: Self, sizing: Sizing) -> Self {
+        Self::embedded(TypeClassOwned::List {
+            item: item.into(),
+            sizing,
+        })
+    }
+
+    fn set(item: Self, sizing: Sizing) -> Self {
+        Self::embedded(TypeClassOwned::Set {
+            item: item.into(),
+            sizing,
+        })
+    }
+
+    fn map(key: Self, value: Self, sizing: Sizing) -> Self {
+        Self::embedded(TypeClassOwned::Map {
+            key: key.into(),
+            value: value.into(),
+            sizing,
+        })
+    }
+
+    fn sequence(items: Vec<Self>) -> Self {
+        Self::embedded(TypeClassOwned::Sequence {
+            items: items.into_iter().map(TypeRef::from).collect(),
+        })
+    }
+
+    fn enumeration(ty: &TypeHeader, variants: &'static [(u8, &'static str)]) -> Self {
+        let variants = variants
+            .iter()
+            .zip(0u8..)
+            .map(|((tag, name), ord)| VariantInfo {
+                name: vname!(*name),
+                tag: *tag,
+                doc: ty.docs.member(ord).map(String::from),
+                fields: vec![],
+            })
+            .collect();
+        Self::named(ty, TypeClassOwned::Enum { variants })
+    }
+
+    fn union(ty: &TypeHeader, mut variants: Vec<LayoutVariant<Self>>) -> Self {
+        // `Option` is the only unnamed union of the embedded library; it is
+        // described by its item type instead of the variants
+        if ty.lib == LIB_EMBEDDED && ty.name.is_none() && is_option(&variants) {
+            let (_, item) = variants.remove(1).fields.remove(0);
+            return Self::embedded(TypeClassOwned::Option { item: item.into() });
+        }
+        let variants = variants
+            .into_iter()
+            .zip(0u8..)
+            .map(|(variant, ord)| VariantInfo {
+                name: variant.name,
+                tag: variant.tag,
+                doc: ty.docs.member(ord).map(String::from),
+                fields: fields(variant.fields, TypeDocs::default()),
+            })
+            .collect();
+        Self::named(ty, TypeClassOwned::Union { variants })
+    }
+
+    fn tuple(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        let fields = self::fields(fields, ty.docs);
+        // tuples of the embedded library are named after their elements, thus
+        // they are described by the fields alone
+        if ty.lib == LIB_EMBEDDED {
+            return Self::embedded(TypeClassOwned::Tuple { fields });
+        }
+        Self::named(ty, TypeClassOwned::Tuple { fields })
+    }
+
+    fn structure(ty: &TypeHeader, fields: Fields<Self>) -> Self {
+        let fields = self::fields(fields, ty.docs);
+        Self::named(ty, TypeClassOwned::Struct { fields })
+    }
+
+    // Recursive types are always named, thus the node is used only as a
+    // reference to the type by its name and the class is never exposed.
+    fn recursive(ty: &TypeHeader) -> Self { Self::named(ty, TypeClassOwned::Embedded) }
+
+    fn raw(ty: &TypeHeader, encoding: RawEncoding) -> Self {
+        Self::named(ty, TypeClassOwned::Raw { encoding })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+
+    use amplify::confinement::{Confined, TinyVec};
+
+    use super::*;
+    use crate::test::encoding;
+    use crate::{ByteArray, StrictDecode, StrictType, TypeLib};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header(u8, TinyVec<u16>);
+
+    /// Message with a header.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", docs, crate = crate)]
+    struct Message {
+        /// Header of the message.
+        header: Header,
+        id: [u8; 32],
+        reply: Option<u16>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Signed {
+        nonce: Option<u8>,
+        digest: Option<[u8; 32]>,
+        signature: Option<ByteArray<64>>,
+        header: Option<Header>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Tree {
+        children: TinyVec<Tree>,
+    }
+
+    fn inline(class: TypeClassOwned) -> TypeRef {
+        TypeRef::Inline(Box::new(TypeInfoOwned::embedded(class)))
+    }
+
+    fn primitive(primitive: Primitive) -> TypeRef {
+        inline(TypeClassOwned::Primitive { primitive })
+    }
+
+    fn field(name: Option<&'static str>, ord: u8, ty: TypeRef) -> FieldInfo {
+        match name {
+            Some(name) => FieldInfo::named(fname!(name), ord),
+            None => FieldInfo::unnamed(ord),
+        }
+        .with_ty(ty)
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(
+            u8::type_info(),
+            TypeInfoOwned::embedded(TypeClassOwned::Primitive {
+                primitive: Primitive::U8
+            })
+        );
+        assert_eq!(
+            u64::type_info(),
+            TypeInfoOwned::embedded(TypeClassOwned::Primitive {
+                primitive: Primitive::U64
+            })
+        );
+        assert_eq!(u64::type_info().to_string(), "_: U64");
+    }
+
+    #[test]
+    fn byte_array() {
+        let info = <[u8; 32]>::type_info();
+        assert_eq!(info.class, TypeClassOwned::Array {
+            item: primitive(Primitive::BYTE),
+            len: 32,
+        });
+        assert_eq!(info.to_string(), "_: [Byte ^ 32]");
+    }
+
+    #[test]
+    fn raw() {
+        let info = crate::VarIntU64::type_info();
+        assert_eq!(info.class, TypeClassOwned::Raw {
+            encoding: RawEncoding::Leb128
+        });
+        assert_eq!(info.to_string(), "Std.VarIntU64: raw LEB128");
+        assert_eq!(crate::TinyBitset::type_info().to_string(), "Std.Bitset: raw [Bit ^ ..0xff]");
+    }
+
+    #[test]
+    fn option() {
+        let info = Option::<u16>::type_info();
+        assert_eq!(
+            info,
+            TypeInfoOwned::embedded(TypeClassOwned::Option {
+                item: primitive(Primitive::U16)
+            })
+        );
+        assert_eq!(info.to_string(), "_: U16?");
+        assert_eq!(Option::<Option<u8>>::type_info().to_string(), "_: U8??");
+    }
+
+    #[test]
+    fn optional_fields() {
+        let option = |item| inline(TypeClassOwned::Option { item });
+        let info = Signed::type_info();
+        assert_eq!(info.class, TypeClassOwned::Struct {
+            fields: vec![
+                field(Some("nonce"), 0, option(primitive(Primitive::U8))),
+                field(
+                    Some("digest"),
+                    1,
+                    option(inline(TypeClassOwned::Array {
+                        item: primitive(Primitive::BYTE),
+                        len: 32
+                    }))
+                ),
+                field(
+                    Some("signature"),
+                    2,
+                    option(TypeRef::Named {
+                        lib: libname!("Std"),
+                        name: tn!("Bytes64"),
+                    })
+                ),
+                field(
+                    Some("header"),
+                    3,
+                    option(TypeRef::Named {
+                        lib: libname!("Test"),
+                        name: tn!("Header"),
+                    })
+                ),
+            ],
+        });
+        assert_eq!(
+            info.to_string(),
+            "Test.Signed: struct { nonce?: U8, digest?: [Byte ^ 32], signature?: Std.Bytes64, \
+             header?: Test.Header }"
+        );
+        assert_eq!(
+            format!("{info:#}"),
+            "Test.Signed: struct { nonce:0: U8?, digest:1: [Byte ^ 32]?, signature:2: \
+             Std.Bytes64?, header:3: Test.Header? }"
+        );
+        assert_eq!(info.named_refs(), bset![
+            (libname!("Std"), tn!("Bytes64")),
+            (libname!("Test"), tn!("Header"))
+        ]);
+
+        // options are still encoded as unions
+        encoding(&Signed::default(), [0, 0, 0, 0]);
+        let mut data = vec![1, 7, 1];
+        data.extend([0x5A; 32]);
+        data.push(1);
+        data.extend([0xA5; 64]);
+        data.extend([1, 2, 1, 3, 0]);
+        encoding(
+            &Signed {
+                nonce: Some(7),
+                digest: Some([0x5A; 32]),
+                signature: Some(ByteArray::from_byte_array([0xA5; 64])),
+                header: Some(Header(2, TinyVec::try_from(vec![3]).unwrap())),
+            },
+            data,
+        );
+    }
+
+    #[test]
+    fn string() {
+        let info = Confined::<String, 0, 255>::type_info();
+        assert_eq!(info, TypeInfoOwned::embedded(TypeClassOwned::Unicode { sizing: Sizing::U8 }));
+        assert_eq!(info.to_string(), "_: [Unicode ^ ..0xff]");
+    }
+
+    #[test]
+    fn nested() {
+        let header = TypeRef::Named {
+            lib: libname!("Test"),
+            name: tn!("Header"),
+        };
+        let info = Message::type_info();
+        let TypeClassOwned::Struct { fields } = &info.class else {
+            panic!("{info} is not a struct")
+        };
+        assert_eq!(
+            fields[0],
+            field(Some("header"), 0, header.clone()).with_doc("Header of the message.")
+        );
+        assert_eq!(
+            fields[1],
+            field(
+                Some("id"),
+                1,
+                inline(TypeClassOwned::Array {
+                    item: primitive(Primitive::BYTE),
+                    len: 32
+                })
+            )
+        );
+        assert_eq!(fields[2].ty, Some(Option::<u16>::type_info().into()));
+        assert_eq!(info.doc.as_deref(), Some("Message with a header."));
+        assert_eq!(
+            format!("{info:#}"),
+            "Test.Message: struct { header:0: Test.Header, id:1: [Byte ^ 32], reply:2: U16? }"
+        );
+
+        let mut lib = TypeLib::new(libname!("Test"));
+        lib.insert(info.clone()).unwrap();
+        lib.insert(Header::type_info()).unwrap();
+        let TypeRef::Named { name, .. } = header else {
+            unreachable!()
+        };
+        assert_eq!(lib.get(&name).unwrap().class, TypeClassOwned::Tuple {
+            fields: vec![
+                field(None, 0, primitive(Primitive::U8)),
+                field(
+                    None,
+                    1,
+                    inline(TypeClassOwned::List {
+                        item: primitive(Primitive::U16),
+                        sizing: Sizing::U8
+                    })
+                ),
+            ],
+        });
+        assert_eq!(lib.get(&name).unwrap().to_string(), "Test.Header: tuple(2)");
+    }
+
+    #[test]
+    fn recursive() {
+        let info = Tree::type_info();
+        assert_eq!(info.class, TypeClassOwned::Struct {
+            fields: vec![field(
+                Some("children"),
+                0,
+                inline(TypeClassOwned::List {
+                    item: TypeRef::Named {
+                        lib: libname!("Test"),
+                        name: tn!("Tree"),
+                    },
+                    sizing: Sizing::U8,
+                })
+            )],
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let json = serde_json::to_string(&Header::type_info()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"lib":"Test","name":"Header","class":"tuple","fields":[{"ord":0,"type":{"lib":"_","name":null,"class":"primitive","primitive":1}},{"ord":1,"type":{"lib":"_","name":null,"class":"list","item":{"lib":"_","name":null,"class":"primitive","primitive":2},"sizing":{"min":0,"max":255}}}]}"#
+        );
+        assert_eq!(serde_json::from_str::<TypeInfoOwned>(&json).unwrap(), Header::type_info());
+
+        let json = serde_json::to_string(&Tree::type_info()).unwrap();
+        assert!(json.contains(r#""item":{"lib":"Test","name":"Tree"}"#), "{json}");
+        assert_eq!(serde_json::from_str::<TypeInfoOwned>(&json).unwrap(), Tree::type_info());
+
+        let json = serde_json::to_string(&Signed::type_info()).unwrap();
+        assert!(
+            json.contains(
+                r#"{"name":"header","ord":3,"type":{"lib":"_","name":null,"class":"option","item":{"lib":"Test","name":"Header"}}}"#
+            ),
+            "{json}"
+        );
+        assert_eq!(serde_json::from_str::<TypeInfoOwned>(&json).unwrap(), Signed::type_info());
+    }
+}
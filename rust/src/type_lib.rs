@@ -0,0 +1,343 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::{btree_map, BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+#[cfg(feature = "serde")]
+use alloc::vec::Vec;
+
+use crate::{
+    type_name, LibName, StrictDumb, StrictEncode, StrictTypeInfo, TypeInfoOwned, TypeName,
+};
+
+/// Errors of assembling a [`TypeLib`].
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+#[display(doc_comments)]
+pub enum TypeLibError {
+    /// type {ty} belongs to library {found}, while library {expected} is being
+    /// built.
+    ForeignLib {
+        ty: String,
+        expected: LibName,
+        found: String,
+    },
+
+    /// type {0} has no strict type name and can't be a part of a library.
+    Unnamed(String),
+
+    /// type name is claimed by two different layouts: `{existing}` and
+    /// `{new}`.
+    Collision {
+        existing: Box<TypeInfoOwned>,
+        new: Box<TypeInfoOwned>,
+    },
+}
+
+impl_error!(TypeLibError);
+
+/// Library of named strict types, assembled from the [`TypeInfoOwned`] of each
+/// type.
+///
+/// Registering the same type (or another type with the same layout) twice is
+/// a no-op, while different layouts under the same type name are reported as
+/// [`TypeLibError::Collision`]. Layouts are compared by their whole
+/// [`StrictTypeInfo`], including the types of the
+/// fields and variants; named field types are compared by their names, and
+/// their own layouts are checked when they are registered, such that the named
+/// types referenced by the fields can be resolved from the library.
+///
+/// With `serde` feature the library is serialized as
+/// `{"name":"MyLib","types":[...]}`, listing the types in the order of their
+/// names; deserialization checks the types in the same way as
+/// [`TypeLib::register`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", try_from = "TypeLibDoc", into = "TypeLibDoc")
+)]
+pub struct TypeLib {
+    name: LibName,
+    types: BTreeMap<TypeName, TypeInfoOwned>,
+}
+
+impl TypeLib {
+    pub fn new(name: LibName) -> Self {
+        TypeLib {
+            name,
+            types: BTreeMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &LibName { &self.name }
+
+    pub fn len(&self) -> usize { self.types.len() }
+
+    pub fn is_empty(&self) -> bool { self.types.is_empty() }
+
+    pub fn get(&self, name: &TypeName) -> Option<&TypeInfoOwned> { self.types.get(name) }
+
+    pub fn contains(&self, name: &TypeName) -> bool { self.types.contains_key(name) }
+
+    /// Returns the libraries of the types referenced by the library types,
+    /// except the library itself; see [`TypeInfoOwned::named_refs`].
+    pub fn dependencies(&self) -> BTreeSet<LibName> {
+        self.types
+            .values()
+            .flat_map(TypeInfoOwned::named_refs)
+            .map(|(lib, _)| lib)
+            .filter(|lib| *lib != self.name)
+            .collect()
+    }
+
+    /// Returns the types of the library which are referenced by the library
+    /// types but are not registered, such that the library can't be resolved
+    /// without them.
+    pub fn unresolved(&self) -> BTreeSet<TypeName> {
+        self.types
+            .values()
+            .flat_map(TypeInfoOwned::named_refs)
+            .filter(|(lib, name)| *lib == self.name && !self.types.contains_key(name))
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// Iterates over the library types in the order of their names.
+    pub fn iter(&self) -> btree_map::Iter<'_, TypeName, TypeInfoOwned> { self.types.iter() }
+
+    /// Adds type `T` to the library, returning whether the type was not
+    /// registered before.
+    ///
+    /// The type information is [`StrictTypeInfo::type_info`], thus `T` must be
+    /// a struct, tuple, enum or union with a name belonging to the library.
+    pub fn register<T: StrictEncode + StrictDumb>(&mut self) -> Result<bool, TypeLibError> {
+        if T::STRICT_LIB_NAME != self.name.as_str() {
+            return Err(TypeLibError::ForeignLib {
+                ty: type_name::<T>(),
+                expected: self.name.clone(),
+                found: T::STRICT_LIB_NAME.to_owned(),
+            });
+        }
+        let info = T::type_info();
+        if info.name.is_none() || info.name != T::strict_name() {
+            return Err(TypeLibError::Unnamed(type_name::<T>()));
+        }
+        self.insert(info)
+    }
+
+    /// Adds type information to the library, returning whether the type was
+    /// not registered before; see [`Self::register`].
+    pub fn insert(&mut self, info: TypeInfoOwned) -> Result<bool, TypeLibError> {
+        let ty = match &info.name {
+            Some(name) => format!("{}.{name}", info.lib),
+            None => return Err(TypeLibError::Unnamed(info.lib.to_string())),
+        };
+        if info.lib != self.name {
+            return Err(TypeLibError::ForeignLib {
+                ty,
+                expected: self.name.clone(),
+                found: info.lib.to_string(),
+            });
+        }
+        let name = info.name.clone().expect("checked above");
+        match self.types.entry(name) {
+            btree_map::Entry::Occupied(entry) if entry.get() == &info => Ok(false),
+            btree_map::Entry::Occupied(entry) => Err(TypeLibError::Collision {
+                existing: Box::new(entry.get().clone()),
+                new: Box::new(info),
+            }),
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(info);
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<'lib> IntoIterator for &'lib TypeLib {
+    type Item = (&'lib TypeName, &'lib TypeInfoOwned);
+    type IntoIter = btree_map::Iter<'lib, TypeName, TypeInfoOwned>;
+
+    fn into_iter(self) -> Self::IntoIter { self.types.iter() }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+struct TypeLibDoc {
+    name: LibName,
+    types: Vec<TypeInfoOwned>,
+}
+
+#[cfg(feature = "serde")]
+impl From<TypeLib> for TypeLibDoc {
+    fn from(lib: TypeLib) -> Self {
+        TypeLibDoc {
+            name: lib.name,
+            types: lib.types.into_values().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TypeLibDoc> for TypeLib {
+    type Error = TypeLibError;
+
+    fn try_from(doc: TypeLibDoc) -> Result<Self, Self::Error> {
+        let mut lib = TypeLib::new(doc.name);
+        for info in doc.types {
+            lib.insert(info)?;
+        }
+        Ok(lib)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::{StrictDecode, StrictType, TypeClassOwned};
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Payment {
+        amount: u64,
+        memo: TinyString,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", rename = "Payment", crate = crate)]
+    struct SameLayout {
+        amount: u64,
+        memo: TinyString,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", rename = "Payment", crate = crate)]
+    struct OtherFieldType {
+        amount: u16,
+        memo: TinyString,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", rename = "Payment", crate = crate)]
+    struct OtherLayout {
+        value: u64,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Method {
+        #[default]
+        Cash,
+        Card(u16),
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Other", crate = crate)]
+    struct Foreign(u8);
+
+    fn lib() -> TypeLib {
+        let mut lib = TypeLib::new(libname!("Test"));
+        assert!(lib.register::<Payment>().unwrap());
+        assert!(lib.register::<Method>().unwrap());
+        lib
+    }
+
+    #[test]
+    fn register() {
+        let mut lib = lib();
+        assert!(!lib.register::<Payment>().unwrap());
+        assert!(!lib.register::<SameLayout>().unwrap());
+        assert_eq!(lib.len(), 2);
+        let names = lib.iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(names, ["Method", "Payment"]);
+        assert!(matches!(lib.get(&tn!("Method")).unwrap().class, TypeClassOwned::Union { .. }));
+        assert_eq!(
+            lib.get(&tn!("Payment")).unwrap().to_string(),
+            "Test.Payment: struct { amount: U64, memo: [Unicode ^ ..0xff] }"
+        );
+    }
+
+    #[test]
+    fn collision() {
+        let err = lib().register::<OtherLayout>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type name is claimed by two different layouts: `Test.Payment: struct { amount: U64, \
+             memo: [Unicode ^ ..0xff] }` and `Test.Payment: struct { value: U64 }`."
+        );
+        assert!(matches!(err, TypeLibError::Collision { .. }));
+
+        let err = lib().register::<OtherFieldType>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type name is claimed by two different layouts: `Test.Payment: struct { amount: U64, \
+             memo: [Unicode ^ ..0xff] }` and `Test.Payment: struct { amount: U16, memo: [Unicode \
+             ^ ..0xff] }`."
+        );
+    }
+
+    #[test]
+    fn foreign() {
+        let mut lib = lib();
+        assert_eq!(lib.register::<Foreign>().unwrap_err(), TypeLibError::ForeignLib {
+            ty: s!("Foreign"),
+            expected: libname!("Test"),
+            found: s!("Other"),
+        });
+        assert_eq!(
+            lib.register::<u8>().unwrap_err().to_string(),
+            "type u8 belongs to library _, while library Test is being built."
+        );
+        let mut embedded = TypeLib::new(libname!(crate::LIB_EMBEDDED));
+        assert_eq!(embedded.register::<u8>().unwrap_err(), TypeLibError::Unnamed(s!("u8")));
+        assert_eq!(lib.len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let lib = lib();
+        let json = serde_json::to_string(&lib).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"Test","types":[{"lib":"Test","name":"Method","class":"union","variants":[{"name":"cash","tag":0},{"name":"card","tag":1,"fields":[{"ord":0,"type":{"lib":"_","name":null,"class":"primitive","primitive":2}}]}]},{"lib":"Test","name":"Payment","class":"struct","fields":[{"name":"amount","ord":0,"type":{"lib":"_","name":null,"class":"primitive","primitive":8}},{"name":"memo","ord":1,"type":{"lib":"_","name":null,"class":"unicode","sizing":{"min":0,"max":255}}}]}]}"#
+        );
+        assert_eq!(serde_json::from_str::<TypeLib>(&json).unwrap(), lib);
+
+        let foreign = json.replacen(r#""lib":"Test""#, r#""lib":"Other""#, 1);
+        let err = serde_json::from_str::<TypeLib>(&foreign).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type Other.Method belongs to library Other, while library Test is being built."
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-describing containers of values of any strict type.
+//!
+//! [`TypedBlob`] is encoded as the 32-byte [`SemId`] of the contained type,
+//! followed by the strict encoding of the value. Decoding checks the id
+//! before decoding the value, such that data of a different type are rejected
+//! with [`DecodeError::TypeMismatch`] rather than being decoded into garbage.
+//! [`peek_type_id`] returns the id of the serialized blob without decoding
+//! it, which allows generic storage to route the blobs by their type.
+
+use core::ops::{Deref, DerefMut};
+
+use crate::{
+    io, DecodeError, ReadRaw, SemId, StrictDecode, StrictDeserialize, StrictEncode, StrictSemId,
+    StrictSerialize, StrictType, TypeName, TypedRead, TypedWrite, WriteRaw, LIB_EMBEDDED,
+};
+
+/// Returns the semantic id of the type of the value contained in the
+/// serialized [`TypedBlob`].
+///
+/// Fails with [`io::ErrorKind::UnexpectedEof`] error if the data are shorter
+/// than the id.
+pub fn peek_type_id(data: &[u8]) -> Result<SemId, DecodeError> {
+    let id = data.get(..32).ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(id);
+    Ok(SemId::from_byte_array(bytes))
+}
+
+pub(crate) fn encode_typed<T: StrictSemId, W: TypedWrite>(
+    value: &T,
+    mut writer: W,
+) -> io::Result<W> {
+    unsafe { writer.raw_writer().write_raw_array(T::sem_id().to_byte_array())? };
+    value.strict_encode(writer)
+}
+
+/// Value prefixed with the semantic id of its type.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TypedBlob<T: StrictSemId>(T);
+
+impl<T: StrictSemId> TypedBlob<T> {
+    pub fn new(value: T) -> Self { TypedBlob(value) }
+
+    pub fn as_inner(&self) -> &T { &self.0 }
+
+    pub fn into_inner(self) -> T { self.0 }
+}
+
+impl<T: StrictSemId> From<T> for TypedBlob<T> {
+    fn from(value: T) -> Self { TypedBlob(value) }
+}
+
+impl<T: StrictSemId> Deref for TypedBlob<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T: StrictSemId> DerefMut for TypedBlob<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
+
+impl<T: StrictSemId> AsRef<T> for TypedBlob<T> {
+    fn as_ref(&self) -> &T { &self.0 }
+}
+
+impl<T: StrictSemId> StrictType for TypedBlob<T> {
+    const STRICT_LIB_NAME: &'static str = LIB_EMBEDDED;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl<T: StrictSemId> StrictEncode for TypedBlob<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        encode_typed(&self.0, writer)
+    }
+}
+
+impl<T: StrictSemId + StrictDecode> StrictDecode for TypedBlob<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let found = unsafe { reader.raw_reader().read_raw_array::<32>()? };
+        let (expected, found) = (T::sem_id(), SemId::from_byte_array(found));
+        if found != expected {
+            return Err(DecodeError::TypeMismatch { expected, found });
+        }
+        T::strict_decode(reader).map(TypedBlob)
+    }
+}
+
+impl<T: StrictSemId> StrictSerialize for TypedBlob<T> {}
+impl<T: StrictSemId + StrictDecode> StrictDeserialize for TypedBlob<T> {}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::DeserializeError;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Account {
+        id: u32,
+        name: TinyString,
+    }
+
+    impl StrictSerialize for Account {}
+    impl StrictDeserialize for Account {}
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Order {
+        id: u32,
+        item: TinyString,
+    }
+
+    impl StrictSerialize for Order {}
+    impl StrictDeserialize for Order {}
+
+    fn account() -> Account {
+        Account {
+            id: 7,
+            name: tiny_s!("alice"),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let blob = account().to_typed_blob::<1024>().unwrap();
+        assert_eq!(&blob[..32], Account::sem_id().as_slice());
+        assert_eq!(&blob[32..], account().to_strict_serialized::<1024>().unwrap().as_slice());
+        assert_eq!(peek_type_id(&blob).unwrap(), Account::sem_id());
+        assert_eq!(Account::from_typed_blob::<1024>(&blob).unwrap(), account());
+
+        let typed = TypedBlob::new(account());
+        assert_eq!(typed.to_strict_serialized::<1024>().unwrap(), blob);
+        assert_eq!(TypedBlob::<Account>::from_strict_slice::<1024>(&blob).unwrap(), typed);
+    }
+
+    #[test]
+    fn mismatch() {
+        let blob = account().to_typed_blob::<1024>().unwrap();
+        // both types have the same encoding, but different semantic ids
+        let order = Order {
+            id: 7,
+            item: tiny_s!("alice"),
+        };
+        assert_eq!(&blob[32..], order.to_strict_serialized::<1024>().unwrap().as_slice());
+        assert_eq!(
+            Order::from_typed_blob::<1024>(&blob).unwrap_err(),
+            DeserializeError::Decode(DecodeError::TypeMismatch {
+                expected: Order::sem_id(),
+                found: Account::sem_id(),
+            })
+        );
+        assert_eq!(
+            DecodeError::TypeMismatch {
+                expected: Order::sem_id(),
+                found: Account::sem_id(),
+            }
+            .to_string(),
+            format!(
+                "data are typed with semantic id {}, while type with semantic id {} is expected.",
+                Account::sem_id(),
+                Order::sem_id()
+            )
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let blob = account().to_typed_blob::<1024>().unwrap();
+        for len in [0, 1, 31] {
+            let DecodeError::Io(err) = peek_type_id(&blob[..len]).unwrap_err() else {
+                panic!("truncated header must result in I/O error")
+            };
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            let Err(DeserializeError::Decode(DecodeError::Io(err))) =
+                Account::from_typed_blob::<1024>(&blob[..len])
+            else {
+                panic!("truncated header must result in I/O error")
+            };
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+        assert!(Account::from_typed_blob::<1024>(&blob[..40]).is_err());
+
+        let mut trailing = blob.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            Account::from_typed_blob::<1024>(&trailing).unwrap_err(),
+            DeserializeError::DataNotEntirelyConsumed(1)
+        );
+    }
+}
@@ -19,12 +19,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::any;
-use std::collections::BTreeSet;
-use std::fmt::{Debug, Display};
-use std::marker::PhantomData;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any;
+use core::fmt::{self, Debug, Display};
+use core::marker::PhantomData;
 
-use crate::{LibName, TypeName, VariantName};
+use crate::{
+    FieldName, InvalidIdent, LibName, Primitive, RawEncoding, Sizing, TypeName, VariantName,
+};
 
 pub fn type_name<T>() -> String {
     fn get_ident(path: &str) -> &str {
@@ -35,6 +40,10 @@ pub fn type_name<T>() -> String {
     let mut ident = vec![];
     for mut arg in name.split([',', '<', '>', '(', ')']) {
         arg = arg.trim();
+        // lifetimes are not a part of the type name
+        if arg.starts_with('\'') {
+            arg = arg.split_once(' ').map(|(_, ty)| ty.trim()).unwrap_or_default();
+        }
         if arg.is_empty() {
             continue;
         }
@@ -43,10 +52,53 @@ pub fn type_name<T>() -> String {
     ident.join("")
 }
 
-#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+/// Constructs the name of a type with const generic parameters, which is used
+/// by the derived [`StrictType`] implementations: the `base` name and the names
+/// of the type parameters are followed by the values of the const parameters,
+/// each prefixed with an underscore, such that different instantiations of the
+/// type have different names (like `Packet_32` and `Packet_64`). Negative
+/// values are prefixed with `Neg` instead of minus sign, which is not allowed
+/// in type names.
+///
+/// # Errors
+///
+/// If the resulting name is not a valid type name, for instance when the name
+/// of a type parameter contains characters which are not allowed in type names
+/// or the name is longer than [`IDENT_MAX_LEN`](crate::IDENT_MAX_LEN)
+/// characters. The derived implementations treat such types as unnamed.
+#[doc(hidden)]
+pub fn const_generic_name(
+    base: &str,
+    types: &[String],
+    params: &[&dyn Display],
+) -> Result<TypeName, InvalidIdent> {
+    let mut name = types.concat();
+    name.insert_str(0, base);
+    for param in params {
+        let param = param.to_string();
+        name.push('_');
+        match param.strip_prefix('-') {
+            Some(abs) => {
+                name.push_str("Neg");
+                name.push_str(abs);
+            }
+            None => name.push_str(&param),
+        }
+    }
+    TypeName::try_from_str(&name)
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
 #[display("unexpected variant {1} for enum or union {0:?}")]
 pub struct VariantError<V: Debug + Display>(pub Option<String>, pub V);
 
+#[cfg(feature = "std")]
+impl<V: Debug + Display> std::error::Error for VariantError<V> {}
+
+impl<V: Debug + Display> From<VariantError<V>> for String {
+    fn from(err: VariantError<V>) -> Self { err.to_string() }
+}
+
 impl<V: Debug + Display> VariantError<V> {
     pub fn with<T>(val: V) -> Self { VariantError(Some(type_name::<T>()), val) }
     pub fn typed(name: impl Into<String>, val: V) -> Self { VariantError(Some(name.into()), val) }
@@ -63,32 +115,96 @@ where T: StrictType + Default
     fn strict_dumb() -> T { T::default() }
 }
 
+/// Types which may be defined as fields, variants and items of other types:
+/// the types having a [`StrictDumb`] value and the uninhabited types, like
+/// [`Void`](crate::Void), which have no values at all.
+///
+/// Uninhabited types are not [`StrictDumb`]; the layout of a type defining
+/// them is discovered with a union without variants in their place.
+pub trait StrictMaybeDumb: Sized {
+    /// Returns the dumb value, or `None` if the type is uninhabited.
+    fn try_strict_dumb() -> Option<Self>;
+}
+
+impl<T: StrictDumb> StrictMaybeDumb for T {
+    fn try_strict_dumb() -> Option<Self> { Some(T::strict_dumb()) }
+}
+
+/// Dumb values of confined collections and strings, which contain the minimal
+/// number of dumb items allowed by the confinement.
+///
+/// Confined types with non-zero minimal length can't be [`StrictDumb`], since
+/// they have no [`Default`] value. Fields of such types in the types deriving
+/// `StrictDumb` should use `#[strict_type(dumb = ConfinedDumb::confined_dumb())]`
+/// attribute.
+pub trait ConfinedDumb: Sized {
+    /// Constructs the value with the minimal number of dumb items.
+    ///
+    /// # Panics
+    ///
+    /// Sets and maps panic if their minimal length is above one, since all
+    /// their dumb items are equal.
+    fn confined_dumb() -> Self;
+}
+
+/// Dumb values of the foreign types which have no [`Default`] value.
+///
+/// Such types can't be [`StrictDumb`], since its implementation for them
+/// conflicts with the blanket implementation for the [`Default`] types. Fields
+/// of such types in the types deriving `StrictDumb` should use
+/// `#[strict_type(dumb = ForeignDumb::foreign_dumb())]` attribute.
+pub trait ForeignDumb: Sized {
+    fn foreign_dumb() -> Self;
+}
+
 pub trait StrictType: Sized {
     const STRICT_LIB_NAME: &'static str;
     fn strict_name() -> Option<TypeName> { Some(tn!(type_name::<Self>())) }
+
+    /// Renders an anonymous type, which has no [`StrictType::strict_name`],
+    /// for the decoding errors. Embedded collections, options, arrays and
+    /// tuples render their strict type notation, like `{U8 -> [Unicode ^
+    /// ..0xff]}`; other types fall back to their rust type name.
+    #[doc(hidden)]
+    fn strict_anonymous_name() -> String { String::from(any::type_name::<Self>()) }
+
+    /// Documentation of the type, its fields and variants, which is empty
+    /// unless the type derives `StrictType` with `#[strict_type(docs)]`
+    /// attribute.
+    fn strict_docs() -> TypeDocs { TypeDocs::default() }
 }
 
 impl<T: StrictType> StrictType for &T {
     const STRICT_LIB_NAME: &'static str = T::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { T::strict_name() }
+    fn strict_anonymous_name() -> String { T::strict_anonymous_name() }
+    fn strict_docs() -> TypeDocs { T::strict_docs() }
 }
 
-impl<T> StrictType for PhantomData<T> {
+impl<T: ?Sized> StrictType for PhantomData<T> {
     const STRICT_LIB_NAME: &'static str = "";
 }
 
 pub trait StrictProduct: StrictType + StrictDumb {}
 
+/// Product type with unnamed fields.
+///
+/// Rust tuples implement this trait for up to 12 elements, which is the
+/// maximal arity supported by `Default`; longer tuples are not strict
+/// encodable and should be replaced with a named struct:
+///
+/// ```compile_fail
+/// use strict_encoding::StrictTuple;
+///
+/// fn assert_tuple<T: StrictTuple>() {}
+/// assert_tuple::<(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8)>();
+/// ```
+///
+/// Tuples without fields are unit types, like unit structs, which are encoded
+/// as zero bytes under their own type name.
 pub trait StrictTuple: StrictProduct {
     const FIELD_COUNT: u8;
-    fn strict_check_fields() {
-        let name = Self::strict_name().unwrap_or_else(|| tn!("__unnamed"));
-        assert_ne!(
-            Self::FIELD_COUNT,
-            0,
-            "tuple type {} does not contain a single field defined",
-            name
-        );
-    }
+    fn strict_check_fields() {}
 
     fn strict_type_info() -> TypeInfo<Self> {
         Self::strict_check_fields();
@@ -217,6 +333,145 @@ where
     }
 }
 
+/// Checks whether the fields written by [`write_struct!`](crate::write_struct)
+/// are the declared fields of the type, in the same order. The check can be
+/// done at compile time.
+pub const fn has_same_fields(declared: &[&str], written: &[&str]) -> bool {
+    if declared.len() != written.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < declared.len() {
+        if !str_eq(declared[i], written[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Identifier of a field name in the [`FieldsWriter`](crate::FieldsWriter)
+/// typestate, which is the FNV-1a hash of the name computed at compile time.
+pub const fn field_id(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut id = 0xcbf2_9ce4_8422_2325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        id ^= bytes[i] as u64;
+        id = id.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    id
+}
+
+/// Total number of the field names in `parts`, which is the length of the
+/// array returned by [`flatten_fields`].
+#[doc(hidden)]
+pub const fn fields_len(parts: &[&[&str]]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        len += parts[i].len();
+        i += 1;
+    }
+    len
+}
+
+/// Concatenates the field names in `parts`, which are the fields of a structure
+/// and the fields of the structures flattened into it, at compile time. This is
+/// how the derived [`StrictStruct::ALL_FIELDS`] of a structure with
+/// `#[strict_type(flatten)]` fields is constructed; `LEN` must be the value of
+/// [`fields_len`] for the same `parts`.
+#[doc(hidden)]
+pub const fn flatten_fields<const LEN: usize>(parts: &[&[&'static str]]) -> [&'static str; LEN] {
+    let mut fields = [""; LEN];
+    let mut pos = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let mut j = 0;
+        while j < parts[i].len() {
+            fields[pos] = parts[i][j];
+            pos += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(pos == LEN, "number of the flattened fields doesn't match the array length");
+    fields
+}
+
+// Checks of the strict types used by `assert_type_sanity!`, which are exported
+// only through `__private`.
+pub(crate) mod sanity {
+    use super::str_eq;
+
+    /// Checks whether all the field names are distinct. The check is done at
+    /// compile time by [`assert_type_sanity!`](crate::assert_type_sanity).
+    pub const fn has_unique_fields(fields: &[&str]) -> bool {
+        let mut i = 0;
+        while i < fields.len() {
+            let mut j = i + 1;
+            while j < fields.len() {
+                if str_eq(fields[i], fields[j]) {
+                    return false;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Checks whether all the variant tags are distinct. The check is done at
+    /// compile time by [`assert_type_sanity!`](crate::assert_type_sanity).
+    pub const fn has_unique_tags(variants: &[(u8, &str)]) -> bool {
+        let mut i = 0;
+        while i < variants.len() {
+            let mut j = i + 1;
+            while j < variants.len() {
+                if variants[i].0 == variants[j].0 {
+                    return false;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Checks whether all the variant names are distinct. The check is done
+    /// at compile time by [`assert_type_sanity!`](crate::assert_type_sanity).
+    pub const fn has_unique_variant_names(variants: &[(u8, &str)]) -> bool {
+        let mut i = 0;
+        while i < variants.len() {
+            let mut j = i + 1;
+            while j < variants.len() {
+                if str_eq(variants[i].1, variants[j].1) {
+                    return false;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 pub enum TypeClass {
     Embedded,
     Enum(&'static [(u8, &'static str)]),
@@ -225,6 +480,29 @@ pub enum TypeClass {
     Struct(&'static [&'static str]),
 }
 
+/// Documentation of a type collected from its doc comments, returned by
+/// [`StrictType::strict_docs`].
+///
+/// The documentation of the fields of structs and tuples and of the variants
+/// of enums and unions is listed in `members` in the order of
+/// [`StrictStruct::ALL_FIELDS`], [`StrictTuple::FIELD_COUNT`] and
+/// [`StrictSum::ALL_VARIANTS`], with `None` for the undocumented ones; skipped
+/// fields and catch-all variants are not listed. Multi-line doc comments are
+/// joined with newlines, with the leading whitespace of each line trimmed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct TypeDocs {
+    pub doc: Option<&'static str>,
+    pub members: &'static [Option<&'static str>],
+}
+
+impl TypeDocs {
+    /// Returns documentation of the field or variant with the given ordinal
+    /// number.
+    pub fn member(&self, ord: u8) -> Option<&'static str> {
+        self.members.get(ord as usize).copied().flatten()
+    }
+}
+
 pub struct TypeInfo<T: StrictType> {
     pub lib: LibName,
     pub name: Option<TypeName>,
@@ -232,12 +510,746 @@ pub struct TypeInfo<T: StrictType> {
     pub dumb: T,
 }
 
+/// Type-erased [`TypeInfo`] without the dumb value, which can be compared
+/// between releases or exported to other tools.
+///
+/// With `serde` feature it is serialized as a stable document like
+/// `{"lib":"MyLib","name":"MyStruct","class":"struct","fields":[{"name":"amount","ord":0}]}`,
+/// where the `class` is one of `embedded`, `enum`, `union`, `tuple` and
+/// `struct`; enums and unions list their `variants` with `name` and `tag`,
+/// and tuple `fields` have no names. Types, fields and variants documented
+/// with [`TypeDocs`] additionally have `doc` string.
+///
+/// Type information provided by [`StrictTypeInfo`](crate::StrictTypeInfo)
+/// also describes primitives, strings and collections, with `primitive`,
+/// `unicode`, `rstring`, `array`, `list`, `set`, `map` and `sequence` classes,
+/// and lists the `type` of each field and item as a [`TypeRef`]. Options are
+/// described by the `option` class with the `item` type, like
+/// `{"lib":"_","name":null,"class":"option","item":{"lib":"MyLib","name":"Bytes64"}}`,
+/// while being encoded as a union with `none` and `some` variants.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub struct TypeInfoOwned {
+    pub lib: LibName,
+    pub name: Option<TypeName>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub doc: Option<String>,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub class: TypeClassOwned,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", tag = "class", rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum TypeClassOwned {
+    Embedded,
+    Primitive {
+        primitive: Primitive,
+    },
+    Unicode {
+        sizing: Sizing,
+    },
+    /// Restricted string, with the types of its first and the rest chars.
+    Rstring {
+        first: TypeRef,
+        rest: TypeRef,
+        sizing: Sizing,
+    },
+    Array {
+        item: TypeRef,
+        len: u16,
+    },
+    List {
+        item: TypeRef,
+        sizing: Sizing,
+    },
+    Set {
+        item: TypeRef,
+        sizing: Sizing,
+    },
+    Map {
+        key: TypeRef,
+        value: TypeRef,
+        sizing: Sizing,
+    },
+    /// Several values encoded one after another without a type wrapping them.
+    Sequence {
+        items: Vec<TypeRef>,
+    },
+    /// `Option` of the item type, encoded as a union with `none` unit variant
+    /// having tag `0` and `some` newtype variant having tag `1`.
+    Option {
+        item: TypeRef,
+    },
+    Enum {
+        variants: Vec<VariantInfo>,
+    },
+    Union {
+        variants: Vec<VariantInfo>,
+    },
+    Tuple {
+        fields: Vec<FieldInfo>,
+    },
+    Struct {
+        fields: Vec<FieldInfo>,
+    },
+    /// Type written in an encoding which can't be described by the strict
+    /// type system.
+    Raw {
+        encoding: RawEncoding,
+    },
+}
+
+/// Reference to the type of a field or an item of a collection.
+///
+/// Named types are referenced by their library and name, such that they can
+/// be looked up in a [`TypeLib`](crate::TypeLib), while unnamed types (like
+/// primitives, collections and options) are described inline. With `serde`
+/// feature named references are serialized as `{"lib":"MyLib","name":"MyStruct"}`
+/// and inline types as [`TypeInfoOwned`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", untagged)
+)]
+pub enum TypeRef {
+    Inline(Box<TypeInfoOwned>),
+    Named { lib: LibName, name: TypeName },
+}
+
+impl From<TypeInfoOwned> for TypeRef {
+    fn from(info: TypeInfoOwned) -> Self {
+        match info.name {
+            Some(name) => TypeRef::Named {
+                lib: info.lib,
+                name,
+            },
+            None => TypeRef::Inline(Box::new(info)),
+        }
+    }
+}
+
+impl Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeRef::Inline(info) => Display::fmt(&info.class, f),
+            TypeRef::Named { lib, name } => write!(f, "{lib}.{name}"),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub struct VariantInfo {
+    pub name: VariantName,
+    pub tag: u8,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub doc: Option<String>,
+    /// Fields of the union variant, which are listed only by the type
+    /// information provided by [`StrictTypeInfo`](crate::StrictTypeInfo).
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub fields: Vec<FieldInfo>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub struct FieldInfo {
+    /// Name of the field, which is absent for tuple fields.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub name: Option<FieldName>,
+    pub ord: u8,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub doc: Option<String>,
+    /// Type of the field, which is known only for the type information
+    /// provided by [`StrictTypeInfo`](crate::StrictTypeInfo).
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "type", default, skip_serializing_if = "Option::is_none")
+    )]
+    pub ty: Option<TypeRef>,
+}
+
+impl TypeInfoOwned {
+    /// Constructs the information of an undocumented type.
+    pub fn new(lib: LibName, name: Option<TypeName>, class: TypeClassOwned) -> Self {
+        TypeInfoOwned {
+            lib,
+            name,
+            doc: None,
+            class,
+        }
+    }
+
+    /// Adds the documentation.
+    pub fn with_doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+}
+
+impl VariantInfo {
+    /// Constructs the information of an undocumented variant without fields.
+    pub fn new(name: VariantName, tag: u8) -> Self {
+        VariantInfo {
+            name,
+            tag,
+            doc: None,
+            fields: vec![],
+        }
+    }
+
+    /// Adds the fields of the union variant.
+    pub fn with_fields(mut self, fields: Vec<FieldInfo>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Adds the documentation.
+    pub fn with_doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+}
+
+impl FieldInfo {
+    /// Constructs the information of an undocumented struct field of unknown
+    /// type.
+    pub fn named(name: FieldName, ord: u8) -> Self {
+        FieldInfo {
+            name: Some(name),
+            ord,
+            doc: None,
+            ty: None,
+        }
+    }
+
+    /// Constructs the information of an undocumented tuple field of unknown
+    /// type.
+    pub fn unnamed(ord: u8) -> Self {
+        FieldInfo {
+            name: None,
+            ord,
+            doc: None,
+            ty: None,
+        }
+    }
+
+    /// Adds the type of the field.
+    pub fn with_ty(mut self, ty: impl Into<TypeRef>) -> Self {
+        self.ty = Some(ty.into());
+        self
+    }
+
+    /// Adds the documentation.
+    pub fn with_doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+}
+
+impl From<&TypeClass> for TypeClassOwned {
+    fn from(cls: &TypeClass) -> Self {
+        let variants = |variants: &'static [(u8, &'static str)]| {
+            variants
+                .iter()
+                .map(|(tag, name)| VariantInfo {
+                    name: vname!(*name),
+                    tag: *tag,
+                    doc: None,
+                    fields: vec![],
+                })
+                .collect()
+        };
+        match cls {
+            TypeClass::Embedded => TypeClassOwned::Embedded,
+            TypeClass::Enum(vars) => TypeClassOwned::Enum {
+                variants: variants(vars),
+            },
+            TypeClass::Union(vars) => TypeClassOwned::Union {
+                variants: variants(vars),
+            },
+            TypeClass::Tuple(count) => TypeClassOwned::Tuple {
+                fields: (0..*count)
+                    .map(|ord| FieldInfo {
+                        name: None,
+                        ord,
+                        doc: None,
+                        ty: None,
+                    })
+                    .collect(),
+            },
+            TypeClass::Struct(fields) => TypeClassOwned::Struct {
+                fields: fields
+                    .iter()
+                    .zip(0u8..)
+                    .map(|(name, ord)| FieldInfo {
+                        name: Some(fname!(*name)),
+                        ord,
+                        doc: None,
+                        ty: None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl TypeInfoOwned {
+    /// Returns the named types referenced by the fields, variants and items of
+    /// the type, including the ones of the nested unnamed types, as pairs of
+    /// their library and type names.
+    ///
+    /// The referenced types may belong to other libraries than the type
+    /// itself; the type information from [`TypeClass`] has no field types and
+    /// references nothing.
+    pub fn named_refs(&self) -> BTreeSet<(LibName, TypeName)> {
+        fn visit(ty: &TypeRef, refs: &mut BTreeSet<(LibName, TypeName)>) {
+            match ty {
+                TypeRef::Inline(info) => refs.extend(info.named_refs()),
+                TypeRef::Named { lib, name } => {
+                    refs.insert((lib.clone(), name.clone()));
+                }
+            }
+        }
+        let fields = |fields: &[FieldInfo], refs: &mut BTreeSet<_>| {
+            for ty in fields.iter().filter_map(|field| field.ty.as_ref()) {
+                visit(ty, refs);
+            }
+        };
+
+        let mut refs = BTreeSet::new();
+        match &self.class {
+            TypeClassOwned::Embedded
+            | TypeClassOwned::Primitive { .. }
+            | TypeClassOwned::Unicode { .. }
+            | TypeClassOwned::Enum { .. }
+            | TypeClassOwned::Raw { .. } => {}
+            TypeClassOwned::Rstring { first, rest, .. } => {
+                visit(first, &mut refs);
+                visit(rest, &mut refs);
+            }
+            TypeClassOwned::Array { item, .. }
+            | TypeClassOwned::List { item, .. }
+            | TypeClassOwned::Set { item, .. }
+            | TypeClassOwned::Option { item } => visit(item, &mut refs),
+            TypeClassOwned::Map { key, value, .. } => {
+                visit(key, &mut refs);
+                visit(value, &mut refs);
+            }
+            TypeClassOwned::Sequence { items } => {
+                for item in items {
+                    visit(item, &mut refs);
+                }
+            }
+            TypeClassOwned::Union { variants } => {
+                for variant in variants {
+                    fields(&variant.fields, &mut refs);
+                }
+            }
+            TypeClassOwned::Tuple { fields: list } | TypeClassOwned::Struct { fields: list } => {
+                fields(list, &mut refs)
+            }
+        }
+        refs
+    }
+}
+
+impl<T: StrictType> From<&TypeInfo<T>> for TypeInfoOwned {
+    fn from(info: &TypeInfo<T>) -> Self {
+        let docs = T::strict_docs();
+        let mut class = TypeClassOwned::from(&info.cls);
+        match &mut class {
+            TypeClassOwned::Enum { variants } | TypeClassOwned::Union { variants } => {
+                for (ord, variant) in (0u8..).zip(variants) {
+                    variant.doc = docs.member(ord).map(String::from);
+                }
+            }
+            TypeClassOwned::Tuple { fields } | TypeClassOwned::Struct { fields } => {
+                for field in fields {
+                    field.doc = docs.member(field.ord).map(String::from);
+                }
+            }
+            _ => {}
+        }
+        TypeInfoOwned {
+            lib: info.lib.clone(),
+            name: info.name.clone(),
+            doc: docs.doc.map(String::from),
+            class,
+        }
+    }
+}
+
+/// Formats the type as `Lib.Name: struct { amount, feeRate }`; enum and
+/// union variants are listed with their tags, and tuples with the number of
+/// fields. Primitives, strings and collections, which have no names, are
+/// formatted like `_: [Byte ^ 32]`, referencing the named item types by their
+/// library and name. Options are formatted as `Lib.Name?`, and the struct
+/// fields with known types are listed with them, like
+/// `Lib.Name: struct { amount: U64, signature?: Lib.Bytes64 }`.
+///
+/// The alternate form, `{:#}`, additionally lists the ordinals of the struct
+/// fields and the known types of all fields, including the fields of tuples
+/// and union variants, like
+/// `Lib.Name: struct { amount:0: U64, signature:1: Lib.Bytes64? }` or
+/// `Lib.Name: union { none = 0, some = 1(U8) }`. Since it renders everything
+/// defining the wire layout of the type itself, it is used by
+/// `assert_strict_layout!` snapshots.
+impl Display for TypeInfoOwned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lib)?;
+        if let Some(name) = &self.name {
+            write!(f, ".{name}")?;
+        }
+        f.write_str(": ")?;
+        Display::fmt(&self.class, f)
+    }
+}
+
+impl Display for TypeClassOwned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let alternate = f.alternate();
+        // fields in the alternate form, which lists the ordinals of named
+        // fields and the types of all fields
+        let fields = |f: &mut fmt::Formatter, fields: &[FieldInfo]| {
+            let named = fields.iter().any(|field| field.name.is_some());
+            f.write_str(if named { " {" } else { "(" })?;
+            let mut sep = if named { " " } else { "" };
+            for field in fields {
+                f.write_str(sep)?;
+                if let Some(name) = &field.name {
+                    write!(f, "{name}:{}", field.ord)?;
+                }
+                match (&field.ty, named) {
+                    (Some(ty), true) => write!(f, ": {ty}")?,
+                    (Some(ty), false) => write!(f, "{ty}")?,
+                    (None, true) => {}
+                    (None, false) => f.write_str("_")?,
+                }
+                sep = ", ";
+            }
+            f.write_str(if named { " }" } else { ")" })
+        };
+        let variants = |f: &mut fmt::Formatter, variants: &[VariantInfo]| {
+            let mut sep = " ";
+            for VariantInfo {
+                name,
+                tag,
+                fields: vars,
+                ..
+            } in variants
+            {
+                write!(f, "{sep}{name} = {tag}")?;
+                if alternate && !vars.is_empty() {
+                    fields(f, vars)?;
+                }
+                sep = ", ";
+            }
+            f.write_str(" }")
+        };
+        match self {
+            TypeClassOwned::Embedded => f.write_str("embedded"),
+            TypeClassOwned::Primitive { primitive } => Display::fmt(primitive, f),
+            TypeClassOwned::Unicode { sizing } => write!(f, "[Unicode{sizing}]"),
+            TypeClassOwned::Rstring {
+                first,
+                rest,
+                sizing,
+            } => write!(f, "[{first}, {rest}{sizing}]"),
+            TypeClassOwned::Array { item, len } => write!(f, "[{item} ^ {len}]"),
+            TypeClassOwned::List { item, sizing } => write!(f, "[{item}{sizing}]"),
+            TypeClassOwned::Set { item, sizing } => write!(f, "{{{item}{sizing}}}"),
+            TypeClassOwned::Map { key, value, sizing } => {
+                write!(f, "{{{key} -> {value}{sizing}}}")
+            }
+            TypeClassOwned::Sequence { items } => {
+                f.write_str("(")?;
+                let mut sep = "";
+                for item in items {
+                    write!(f, "{sep}{item}")?;
+                    sep = ", ";
+                }
+                f.write_str(")")
+            }
+            TypeClassOwned::Option { item } => write!(f, "{item}?"),
+            TypeClassOwned::Enum { variants: vars } => {
+                f.write_str("enum {")?;
+                variants(f, vars)
+            }
+            TypeClassOwned::Union { variants: vars } => {
+                f.write_str("union {")?;
+                variants(f, vars)
+            }
+            TypeClassOwned::Tuple { fields: items } if alternate => {
+                f.write_str("tuple")?;
+                fields(f, items)
+            }
+            TypeClassOwned::Tuple { fields } => write!(f, "tuple({})", fields.len()),
+            TypeClassOwned::Raw { encoding } => write!(f, "raw {encoding}"),
+            TypeClassOwned::Struct { fields: items } if alternate => {
+                f.write_str("struct")?;
+                fields(f, items)
+            }
+            TypeClassOwned::Struct { fields } => {
+                f.write_str("struct {")?;
+                let mut sep = " ";
+                for field in fields {
+                    let Some(name) = &field.name else { continue };
+                    write!(f, "{sep}{name}")?;
+                    sep = ", ";
+                    match &field.ty {
+                        Some(TypeRef::Inline(info)) => match &info.class {
+                            TypeClassOwned::Option { item } => write!(f, "?: {item}")?,
+                            class => write!(f, ": {class}")?,
+                        },
+                        Some(ty) => write!(f, ": {ty}")?,
+                        None => {}
+                    }
+                }
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: StrictType> serde_crate::Serialize for TypeInfo<T> {
+    /// Serializes the type information as [`TypeInfoOwned`].
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde_crate::Serializer {
+        TypeInfoOwned::from(self).serialize(serializer)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use amplify::confinement::TinyVec;
 
     use super::*;
+    use crate::StrictType;
 
     #[test]
     fn name_derivation() { assert_eq!(Option::<TinyVec<u8>>::strict_name(), None) }
+
+    #[test]
+    fn const_generic_names() {
+        assert_eq!(const_generic_name("Packet", &[], &[&32, &-1]), Ok(tn!("Packet_32_Neg1")));
+        assert_eq!(
+            const_generic_name("Frame", &[type_name::<[u8; 2]>()], &[&4]),
+            Err(InvalidIdent::InvalidChar(s!("Frame[u8; 2]_4"), '[', 5))
+        );
+        let long = "A".repeat(crate::IDENT_MAX_LEN);
+        assert!(matches!(
+            const_generic_name(&long, &[], &[&1]),
+            Err(InvalidIdent::TooLong(_, len)) if len == crate::IDENT_MAX_LEN + 2
+        ));
+    }
+
+    /// Amount transferred
+    /// with the fee.
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType)]
+    #[strict_type(lib = "Test", docs, crate = crate)]
+    struct Documented {
+        /// Amount in satoshis.
+        amount: u64,
+        #[strict_type(skip)]
+        cache: u8,
+        fee_rate: u16,
+        /**
+           Free-form note,
+             which is not validated.
+        */
+        note: u8,
+    }
+
+    /// Direction of the transfer.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType)]
+    #[strict_type(lib = "Test", tags = order, docs, crate = crate)]
+    enum Direction {
+        #[default]
+        /// Incoming transfer.
+        In,
+        Out,
+        #[strict_type(other)]
+        Unknown(u8),
+        ///   Transfer to self.
+        Loop,
+    }
+
+    #[test]
+    fn docs() {
+        assert_eq!(u8::strict_docs(), TypeDocs::default());
+        assert_eq!(Documented::strict_docs(), TypeDocs {
+            doc: Some("Amount transferred\nwith the fee."),
+            members: &[
+                Some("Amount in satoshis."),
+                None,
+                Some("Free-form note,\nwhich is not validated.")
+            ],
+        });
+        assert_eq!(
+            Documented::strict_docs().member(2),
+            Some("Free-form note,\nwhich is not validated.")
+        );
+        assert_eq!(Documented::strict_docs().member(3), None);
+        assert_eq!(Direction::strict_docs(), TypeDocs {
+            doc: Some("Direction of the transfer."),
+            members: &[Some("Incoming transfer."), None, Some("Transfer to self.")],
+        });
+    }
+
+    #[test]
+    fn name_lifetimes() {
+        struct View<'a, T>(PhantomData<&'a T>);
+        assert_eq!(type_name::<View<'static, &'static u8>>(), "Viewu8");
+        assert_eq!(type_name::<View<'_, (u8, &str)>>(), "Viewu8str");
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    enum Command {
+        Start,
+        Stop(u8),
+    }
+    impl StrictType for Command {
+        const STRICT_LIB_NAME: &'static str = "Test";
+    }
+    impl_strict_sum!(union Command {
+        Self::Start => (1, "start"),
+        Self::Stop(_) => (3, "stop"),
+    });
+
+    #[test]
+    fn variant_ord() {
+        assert_eq!(Command::ALL_VARIANTS, &[(1, "start"), (3, "stop")]);
+        assert_eq!(Command::Stop(0).variant_name(), "stop");
+        assert_eq!(Command::Start.variant_ord(), 1);
+        assert_eq!(Command::Stop(0).variant_ord(), 3);
+
+        assert_eq!(Direction::Out.variant_ord(), 1);
+        assert_eq!(Direction::Unknown(7).variant_ord(), 7);
+    }
+
+    #[cfg(feature = "serde")]
+    mod json {
+        use super::*;
+        use crate::{StrictDecode, StrictEncode, StrictType, LIB_EMBEDDED};
+
+        #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8, crate = crate)]
+        #[repr(u8)]
+        enum Network {
+            #[default]
+            Mainnet = 0,
+            Testnet = 0x10,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", tags = order, crate = crate)]
+        enum Payload {
+            #[default]
+            Unit,
+            Data(u16),
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Point(u16, u16);
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode, StrictDecode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Transfer {
+            amount: u64,
+            fee_rate: u16,
+        }
+
+        fn check(info: TypeInfoOwned, golden: &str) {
+            assert_eq!(serde_json::to_string(&info).unwrap(), golden);
+            assert_eq!(serde_json::from_str::<TypeInfoOwned>(golden).unwrap(), info);
+        }
+
+        #[test]
+        fn golden() {
+            let embedded = TypeInfo {
+                lib: libname!(LIB_EMBEDDED),
+                name: None,
+                cls: TypeClass::Embedded,
+                dumb: 0u8,
+            };
+            check((&embedded).into(), r#"{"lib":"_","name":null,"class":"embedded"}"#);
+            check(
+                (&Network::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Network","class":"enum","variants":[{"name":"mainnet","tag":0},{"name":"testnet","tag":16}]}"#,
+            );
+            check(
+                (&Payload::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Payload","class":"union","variants":[{"name":"unit","tag":0},{"name":"data","tag":1}]}"#,
+            );
+            check(
+                (&Point::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Point","class":"tuple","fields":[{"ord":0},{"ord":1}]}"#,
+            );
+            check(
+                (&Transfer::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Transfer","class":"struct","fields":[{"name":"amount","ord":0},{"name":"feeRate","ord":1}]}"#,
+            );
+            assert_eq!(
+                serde_json::to_value(Transfer::strict_type_info()).unwrap(),
+                serde_json::to_value(TypeInfoOwned::from(&Transfer::strict_type_info())).unwrap()
+            );
+        }
+
+        #[test]
+        fn documented() {
+            check(
+                (&Documented::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Documented","doc":"Amount transferred\nwith the fee.","class":"struct","fields":[{"name":"amount","ord":0,"doc":"Amount in satoshis."},{"name":"feeRate","ord":1},{"name":"note","ord":2,"doc":"Free-form note,\nwhich is not validated."}]}"#,
+            );
+            check(
+                (&Direction::strict_type_info()).into(),
+                r#"{"lib":"Test","name":"Direction","doc":"Direction of the transfer.","class":"enum","variants":[{"name":"in","tag":0,"doc":"Incoming transfer."},{"name":"out","tag":1},{"name":"loop","tag":2,"doc":"Transfer to self."}]}"#,
+            );
+        }
+
+        #[test]
+        fn invalid() {
+            let err = serde_json::from_str::<TypeInfoOwned>(
+                r#"{"lib":"Test","name":"point","class":"tuple","fields":[]}"#,
+            )
+            .unwrap_err();
+            assert!(
+                err.to_string().starts_with("string 'point' must not start with character 'p'"),
+                "{err}"
+            );
+            assert!(serde_json::from_str::<TypeInfoOwned>(
+                r#"{"lib":"Test","name":null,"class":"list"}"#
+            )
+            .is_err());
+        }
+    }
 }
@@ -19,12 +19,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::cmp::Ordering;
-use std::fmt::{self, Display, Formatter};
-use std::hash::{Hash, Hasher};
-use std::io;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter};
+use core::hash::{Hash, Hasher};
 
-use crate::{ReadStruct, VariantName, WriteStruct, STRICT_TYPES_LIB};
+use amplify::confinement;
+use amplify::num::u24;
+
+use crate::{
+    io, DecodeError, DecodeRawLe, ReadRaw, ReadStruct, VariantName, WriteRaw, WriteStruct,
+    STRICT_TYPES_LIB,
+};
 
 // TODO: Control that min > max!
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -65,10 +71,101 @@ impl Sizing {
 
     pub const fn is_fixed(&self) -> bool { self.min == self.max }
 
+    /// Width of the length prefix of a collection or a string with this sizing,
+    /// in bytes; the same rule is used for reading and, by
+    /// [`crate::WriteRaw::write_raw_len`], for writing the prefix.
+    pub const fn prefix_len(&self) -> usize {
+        match self.max {
+            0..=0xFF => 1,
+            0x100..=0xFFFF => 2,
+            0x1_0000..=0xFF_FFFF => 3,
+            0x100_0000..=0xFFFF_FFFF => 4,
+            _ => 8,
+        }
+    }
+
     pub const fn check(&self, len: usize) -> bool {
         let len = len as u64;
         len >= self.min && len <= self.max
     }
+
+    /// Checks the number of elements of a confined value being constructed
+    /// against the sizing.
+    pub(crate) fn check_confined(&self, len: usize) -> Result<(), confinement::Error> {
+        if (len as u64) < self.min {
+            return Err(confinement::Error::Undersize {
+                len,
+                min_len: self.min as usize,
+            });
+        }
+        if len as u64 > self.max {
+            return Err(confinement::Error::Oversize {
+                len,
+                max_len: usize::try_from(self.max).unwrap_or(usize::MAX),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the length of a collection or a string named `type_name`, which
+    /// is declared by its length prefix, against the sizing.
+    pub(crate) fn check_len(
+        &self,
+        len: u64,
+        type_name: impl FnOnce() -> String,
+    ) -> Result<usize, DecodeError> {
+        let declared_len = usize::try_from(len).unwrap_or(usize::MAX);
+        let max_len = usize::try_from(self.max).unwrap_or(usize::MAX);
+        if len > self.max {
+            return Err(DecodeError::Oversize {
+                type_name: type_name(),
+                declared_len,
+                max_len,
+            });
+        }
+        if len < self.min {
+            return Err(DecodeError::Undersize {
+                type_name: type_name(),
+                declared_len,
+                min_len: usize::try_from(self.min).unwrap_or(usize::MAX),
+                max_len,
+            });
+        }
+        Ok(declared_len)
+    }
+
+    /// Reads the length prefix of a collection or a string named `type_name`
+    /// and checks it against the sizing; see [`Sizing::check_len`].
+    pub(crate) fn read_len(
+        &self,
+        reader: &mut impl ReadRaw,
+        type_name: impl FnOnce() -> String,
+    ) -> Result<usize, DecodeError> {
+        let len = match self.prefix_len() {
+            1 => u8::decode_raw_le(reader)? as u64,
+            2 => u16::decode_raw_le(reader)? as u64,
+            3 => u24::decode_raw_le(reader)?.into_usize() as u64,
+            4 => u32::decode_raw_le(reader)? as u64,
+            _ => u64::decode_raw_le(reader)?,
+        };
+        self.check_len(len, type_name)
+    }
+
+    /// Writes the length prefix of a collection or a string with `len`
+    /// elements, which must be already checked against the sizing.
+    pub(crate) fn write_len(
+        &self,
+        writer: &mut (impl WriteRaw + ?Sized),
+        len: usize,
+    ) -> io::Result<()> {
+        match self.prefix_len() {
+            1 => writer.write_raw_array((len as u8).to_le_bytes()),
+            2 => writer.write_raw_array((len as u16).to_le_bytes()),
+            3 => writer.write_raw_array(u24::with(len as u32).to_le_bytes()),
+            4 => writer.write_raw_array((len as u32).to_le_bytes()),
+            _ => writer.write_raw_array((len as u64).to_le_bytes()),
+        }
+    }
 }
 
 impl Display for Sizing {
@@ -83,6 +180,38 @@ impl Display for Sizing {
     }
 }
 
+/// Encodings which can't be described by the strict type system, used by the
+/// types bridging external formats. The types register their encoding in
+/// place of the layout, such that semantic ids, size bounds, skipping and the
+/// reflected values still follow their data.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[non_exhaustive]
+pub enum RawEncoding {
+    /// Unsigned LEB128 integer of up to ten bytes; see [`crate::VarIntU64`].
+    Leb128,
+    /// Bitcoin `CompactSize` integer of one, three, five or nine bytes; see
+    /// [`crate::CompactSizeU64`].
+    CompactSize,
+    /// Bits packed eight per byte, prefixed with the number of bits confined
+    /// by the sizing; see [`crate::ConfinedBitset`].
+    Bitset(Sizing),
+}
+
+impl Display for RawEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RawEncoding::Leb128 => f.write_str("LEB128"),
+            RawEncoding::CompactSize => f.write_str("CompactSize"),
+            RawEncoding::Bitset(sizing) => write!(f, "[Bit{sizing}]"),
+        }
+    }
+}
+
 #[derive(Clone, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
 pub struct Variant {
@@ -0,0 +1,79 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict encoding of [`Uuid`] as a tuple of its 16 bytes in big-endian
+//! order, matching [`Uuid::as_bytes`]. No version or variant validation is
+//! performed on decoding, so any 128-bit value round-trips.
+
+use ::uuid::Uuid;
+
+use crate::{
+    io, DecodeError, ReadTuple, StrictDecode, StrictEncode, StrictProduct, StrictTuple, StrictType,
+    TypeName, TypedRead, TypedWrite, LIB_NAME_STD,
+};
+
+impl StrictType for Uuid {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("Uuid")) }
+}
+impl StrictProduct for Uuid {}
+impl StrictTuple for Uuid {
+    const FIELD_COUNT: u8 = 1;
+}
+impl StrictEncode for Uuid {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        writer.write_newtype::<Self>(self.as_bytes())
+    }
+}
+impl StrictDecode for Uuid {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| r.read_field().map(Uuid::from_bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encoding, encoding_roundtrip};
+    use crate::StrictDumb;
+
+    #[test]
+    fn uuid() {
+        let uuid = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        encoding(&uuid, [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ]);
+        encoding(&Uuid::nil(), [0u8; 16]);
+        encoding(&Uuid::max(), [0xFFu8; 16]);
+        // not a valid RFC 4122 UUID
+        encoding(&Uuid::from_u128(0x0102), [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(Uuid::strict_dumb(), Uuid::nil());
+        assert_eq!(Uuid::strict_name(), Some(tn!("Uuid")));
+    }
+
+    #[test]
+    fn uuid_random() {
+        for _ in 0..16 {
+            encoding_roundtrip(&Uuid::new_v4());
+        }
+    }
+}
@@ -0,0 +1,1310 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reflected values of strict types, which are decoded and encoded using
+//! type definitions known only at runtime.
+//!
+//! Types are described by [`Ty`], which may refer to the named types of a
+//! [`TypeSystem`] by their names qualified with the library names. Named types
+//! may be recursive; the values may nest the named types up to
+//! [`RECURSION_DEPTH_LIMIT`] levels deep, such that the processing of
+//! maliciously nested data can't overflow the stack. Types described by
+//! [`TypeInfoOwned`] convert into [`Ty`], and the types of a [`TypeLib`] can be
+//! added to a [`TypeSystem`] with [`TypeSystem::insert_lib`].
+//!
+//! Reflection covers the integers up to 128 bits, unicode strings, byte
+//! strings and all collections and user-defined types. Floats and larger
+//! integers are not supported. Sets and maps are not checked for the order of
+//! their items.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use amplify::IoError;
+
+use crate::bitset::{byte_len, check_padding};
+#[cfg(not(feature = "std"))]
+use crate::io::Error as IoError;
+use crate::varint::{read_compact_size, read_leb128, write_compact_size, write_leb128};
+use crate::{
+    io, DecodeError, FieldInfo, FieldName, KnownVariants, LibName, NumCls, Primitive, RawEncoding,
+    ReadRaw, Sizing, TypeClassOwned, TypeInfoOwned, TypeLib, TypeName, TypeRef, TypedRead,
+    TypedWrite, Utf8Policy, VariantInfo, VariantName, WriteRaw, RECURSION_DEPTH_LIMIT,
+};
+
+/// Runtime definition of a strict type.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Ty {
+    Primitive(Primitive),
+    Unicode(Sizing),
+    Array(Box<Ty>, u16),
+    List(Box<Ty>, Sizing),
+    Set(Box<Ty>, Sizing),
+    Map(Box<Ty>, Box<Ty>, Sizing),
+    Enum(Vec<VariantInfo>),
+    /// Union variants with their fields; unit variants have
+    /// [`Primitive::UNIT`] type.
+    Union(Vec<(VariantInfo, Ty)>),
+    Tuple(Vec<Ty>),
+    Struct(Vec<(FieldName, Ty)>),
+    /// Named type defined in a [`TypeSystem`].
+    Ref {
+        lib: LibName,
+        name: TypeName,
+    },
+    /// Type written in an encoding external to the strict type system.
+    /// Variable-length integers are reflected as [`StrictVal::Uint`] and bit
+    /// sets as [`StrictVal::List`] of `0` and `1` integers.
+    Raw(RawEncoding),
+}
+
+impl Ty {
+    pub const fn unit() -> Ty { Ty::Primitive(Primitive::UNIT) }
+
+    pub fn list(ty: Ty, sizing: Sizing) -> Ty { Ty::List(Box::new(ty), sizing) }
+
+    pub fn set(ty: Ty, sizing: Sizing) -> Ty { Ty::Set(Box::new(ty), sizing) }
+
+    pub fn map(key: Ty, ty: Ty, sizing: Sizing) -> Ty {
+        Ty::Map(Box::new(key), Box::new(ty), sizing)
+    }
+
+    pub fn array(ty: Ty, len: u16) -> Ty { Ty::Array(Box::new(ty), len) }
+
+    pub fn named(lib: LibName, name: TypeName) -> Ty { Ty::Ref { lib, name } }
+
+    pub(crate) fn is_byte(&self) -> bool { *self == Ty::Primitive(Primitive::BYTE) }
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Ty::Primitive(prim) => Display::fmt(prim, f),
+            Ty::Unicode(sizing) => write!(f, "[Unicode{sizing}]"),
+            Ty::Array(ty, len) => write!(f, "[{ty} ^ {len}]"),
+            Ty::List(ty, sizing) => write!(f, "[{ty}{sizing}]"),
+            Ty::Set(ty, sizing) => write!(f, "{{{ty}{sizing}}}"),
+            Ty::Map(key, ty, sizing) => write!(f, "{{{key} -> {ty}{sizing}}}"),
+            Ty::Enum(_) => f.write_str("enum"),
+            Ty::Union(_) => f.write_str("union"),
+            Ty::Tuple(_) => f.write_str("tuple"),
+            Ty::Struct(_) => f.write_str("struct"),
+            Ty::Ref { lib, name } => write!(f, "{lib}.{name}"),
+            Ty::Raw(encoding) => Display::fmt(encoding, f),
+        }
+    }
+}
+
+/// Named types which can be referenced by [`Ty::Ref`], identified by their
+/// names together with the names of their libraries.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct TypeSystem(BTreeMap<(LibName, TypeName), Ty>);
+
+impl TypeSystem {
+    pub fn new() -> Self { TypeSystem::default() }
+
+    /// Defines a named type, returning the previous definition, if any.
+    pub fn insert(&mut self, lib: LibName, name: TypeName, ty: Ty) -> Option<Ty> {
+        self.0.insert((lib, name), ty)
+    }
+
+    pub fn get(&self, lib: &LibName, name: &TypeName) -> Option<&Ty> {
+        self.0.get(&(lib.clone(), name.clone()))
+    }
+
+    /// Adds the definitions of the other type system, replacing the existing
+    /// definitions of the same types.
+    pub fn extend(&mut self, other: TypeSystem) { self.0.extend(other.0) }
+
+    /// Defines the types of the library, replacing the existing definitions of
+    /// the same types.
+    pub fn insert_lib(&mut self, lib: &TypeLib) -> Result<(), ValueError> {
+        for (name, info) in lib {
+            self.insert(lib.name().clone(), name.clone(), Ty::try_from(info)?);
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&TypeLib> for TypeSystem {
+    type Error = ValueError;
+
+    fn try_from(lib: &TypeLib) -> Result<Self, Self::Error> {
+        let mut sys = TypeSystem::new();
+        sys.insert_lib(lib)?;
+        Ok(sys)
+    }
+}
+
+/// Converts the layout of the type described by [`StrictTypeInfo`], which
+/// must list the types of all its fields and items. Named types referenced by
+/// the layout become [`Ty::Ref`], and must be defined in the [`TypeSystem`]
+/// used for the values, for instance by [`TypeSystem::insert_lib`]. Options
+/// become unions with `none` and `some` variants, and sequences become tuples.
+///
+/// [`StrictTypeInfo`]: crate::StrictTypeInfo
+impl TryFrom<&TypeInfoOwned> for Ty {
+    type Error = ValueError;
+
+    fn try_from(info: &TypeInfoOwned) -> Result<Self, Self::Error> {
+        let undescribed = || ValueError::Undescribed(info.to_string());
+        let ty = |ty: &TypeRef| Ty::try_from(ty);
+        let field = |field: &FieldInfo| field.ty.as_ref().ok_or_else(undescribed).and_then(ty);
+        let fields = |fields: &[FieldInfo]| -> Result<Ty, ValueError> {
+            if fields.iter().all(|field| field.name.is_none()) {
+                return fields.iter().map(field).collect::<Result<_, _>>().map(Ty::Tuple);
+            }
+            fields
+                .iter()
+                .map(|f| Ok((f.name.clone().ok_or_else(undescribed)?, field(f)?)))
+                .collect::<Result<_, _>>()
+                .map(Ty::Struct)
+        };
+        Ok(match &info.class {
+            TypeClassOwned::Embedded => return Err(undescribed()),
+            TypeClassOwned::Primitive { primitive } => Ty::Primitive(*primitive),
+            TypeClassOwned::Unicode { sizing } | TypeClassOwned::Rstring { sizing, .. } => {
+                Ty::Unicode(*sizing)
+            }
+            TypeClassOwned::Array { item, len } => Ty::array(ty(item)?, *len),
+            TypeClassOwned::List { item, sizing } => Ty::list(ty(item)?, *sizing),
+            TypeClassOwned::Set { item, sizing } => Ty::set(ty(item)?, *sizing),
+            TypeClassOwned::Map { key, value, sizing } => Ty::map(ty(key)?, ty(value)?, *sizing),
+            TypeClassOwned::Sequence { items } => {
+                Ty::Tuple(items.iter().map(ty).collect::<Result<_, _>>()?)
+            }
+            TypeClassOwned::Option { item } => Ty::Union(vec![
+                (VariantInfo::new(vname!("none"), 0), Ty::unit()),
+                (VariantInfo::new(vname!("some"), 1), Ty::Tuple(vec![ty(item)?])),
+            ]),
+            TypeClassOwned::Enum { variants } => Ty::Enum(variants.clone()),
+            TypeClassOwned::Union { variants } => Ty::Union(
+                variants
+                    .iter()
+                    .map(|variant| {
+                        let fields = match variant.fields.as_slice() {
+                            [] => Ty::unit(),
+                            list => fields(list)?,
+                        };
+                        Ok((VariantInfo::new(variant.name.clone(), variant.tag), fields))
+                    })
+                    .collect::<Result<_, ValueError>>()?,
+            ),
+            TypeClassOwned::Tuple { fields: list } | TypeClassOwned::Struct { fields: list } => {
+                fields(list)?
+            }
+            TypeClassOwned::Raw { encoding } => Ty::Raw(*encoding),
+        })
+    }
+}
+
+impl TryFrom<&TypeRef> for Ty {
+    type Error = ValueError;
+
+    fn try_from(ty: &TypeRef) -> Result<Self, Self::Error> {
+        match ty {
+            TypeRef::Inline(info) => Ty::try_from(info.as_ref()),
+            TypeRef::Named { lib, name } => Ok(Ty::named(lib.clone(), name.clone())),
+        }
+    }
+}
+
+/// Reflected value of a strict type.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum StrictVal {
+    Unit,
+    /// Unsigned and non-zero integers and bytes.
+    Uint(u128),
+    Int(i128),
+    /// Arrays and lists of bytes.
+    Bytes(Vec<u8>),
+    String(String),
+    /// Arrays and lists of other types.
+    List(Vec<StrictVal>),
+    Set(Vec<StrictVal>),
+    Map(Vec<(StrictVal, StrictVal)>),
+    Tuple(Vec<StrictVal>),
+    Struct(Vec<(FieldName, StrictVal)>),
+    /// Union variant with its fields, which are [`StrictVal::Unit`],
+    /// [`StrictVal::Tuple`] or [`StrictVal::Struct`].
+    Union(VariantName, Box<StrictVal>),
+    Enum(VariantName),
+}
+
+impl StrictVal {
+    fn kind(&self) -> &'static str {
+        match self {
+            StrictVal::Unit => "unit",
+            StrictVal::Uint(_) => "unsigned integer",
+            StrictVal::Int(_) => "signed integer",
+            StrictVal::Bytes(_) => "bytes",
+            StrictVal::String(_) => "string",
+            StrictVal::List(_) => "list",
+            StrictVal::Set(_) => "set",
+            StrictVal::Map(_) => "map",
+            StrictVal::Tuple(_) => "tuple",
+            StrictVal::Struct(_) => "struct",
+            StrictVal::Union(..) => "union",
+            StrictVal::Enum(_) => "enum",
+        }
+    }
+}
+
+/// Errors of processing reflected values.
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum ValueError {
+    #[display(inner)]
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// type {0}.{1} is not defined in the type system.
+    UnknownType(LibName, TypeName),
+
+    /// value nests named types deeper than the limit of {limit} levels.
+    MaxRecursionDepth { limit: usize },
+
+    /// primitive type {0} is not supported by reflected values.
+    UnsupportedPrimitive(Primitive),
+
+    /// {found} value can't be encoded as {expected}.
+    Mismatch {
+        expected: String,
+        found: &'static str,
+    },
+
+    /// value {value} is out of range of {prim} type.
+    OutOfRange { prim: Primitive, value: String },
+
+    /// {ty} value contains {len} items, while the type is confined to
+    /// {min}..={max} items.
+    Sizing {
+        ty: String,
+        len: usize,
+        min: u64,
+        max: u64,
+    },
+
+    /// variant {0} is not defined by the type.
+    UnknownVariant(VariantName),
+
+    /// field {found} is provided instead of {expected}.
+    FieldMismatch {
+        expected: FieldName,
+        found: FieldName,
+    },
+
+    /// value has {found} fields, while the type has {expected} fields.
+    FieldCount { expected: usize, found: usize },
+
+    /// type information `{0}` doesn't describe the types of all its fields
+    /// and items.
+    Undescribed(String),
+}
+
+impl_error!(ValueError);
+
+impl From<ValueError> for DecodeError {
+    fn from(err: ValueError) -> Self {
+        match err {
+            ValueError::Io(io) => DecodeError::Io(io),
+            ValueError::MaxRecursionDepth { limit } => DecodeError::MaxRecursionDepth { limit },
+            err => DecodeError::DataIntegrityError(err.to_string()),
+        }
+    }
+}
+
+/// Decodes a value of type `ty`, resolving named types within `sys`.
+pub fn decode_value(
+    reader: &mut impl TypedRead,
+    ty: &Ty,
+    sys: &TypeSystem,
+) -> Result<StrictVal, DecodeError> {
+    let mut resolver = Resolver::new(sys);
+    resolver.utf8 = reader.utf8_policy();
+    resolver.decode(unsafe { reader.raw_reader() }, ty)
+}
+
+/// Encodes a value of type `ty`, resolving named types within `sys`.
+pub fn encode_value<W: TypedWrite>(
+    mut writer: W,
+    value: &StrictVal,
+    ty: &Ty,
+    sys: &TypeSystem,
+) -> Result<W, ValueError> {
+    Resolver::new(sys).encode(unsafe { writer.raw_writer() }, value, ty)?;
+    Ok(writer)
+}
+
+fn read_uint(reader: &mut impl ReadRaw, size: usize) -> io::Result<u128> {
+    let bytes = reader.read_raw::<16>(size)?;
+    Ok(bytes.iter().rev().fold(0u128, |acc, byte| acc << 8 | *byte as u128))
+}
+
+fn write_uint(writer: &mut impl WriteRaw, value: u128, size: usize) -> io::Result<()> {
+    writer.write_raw::<16>(&value.to_le_bytes()[..size])
+}
+
+fn raw_u64(value: u128) -> Result<u64, ValueError> {
+    u64::try_from(value).map_err(|_| ValueError::OutOfRange {
+        prim: Primitive::U64,
+        value: value.to_string(),
+    })
+}
+
+/// Resolves the named types of the type system, tracking the depth of their
+/// nesting. Each [`Resolver::enter`] must be paired with [`Resolver::leave`].
+pub(crate) struct Resolver<'sys> {
+    sys: &'sys TypeSystem,
+    depth: usize,
+    utf8: Utf8Policy,
+}
+
+impl<'sys> Resolver<'sys> {
+    pub(crate) fn new(sys: &'sys TypeSystem) -> Self {
+        Resolver {
+            sys,
+            depth: 0,
+            utf8: Utf8Policy::Strict,
+        }
+    }
+
+    pub(crate) fn enter(&mut self, lib: &LibName, name: &TypeName) -> Result<&'sys Ty, ValueError> {
+        let ty = self
+            .sys
+            .get(lib, name)
+            .ok_or_else(|| ValueError::UnknownType(lib.clone(), name.clone()))?;
+        if self.depth >= RECURSION_DEPTH_LIMIT {
+            return Err(ValueError::MaxRecursionDepth {
+                limit: RECURSION_DEPTH_LIMIT,
+            });
+        }
+        self.depth += 1;
+        Ok(ty)
+    }
+
+    pub(crate) fn leave(&mut self) { self.depth -= 1; }
+
+    fn read_len(
+        &self,
+        reader: &mut impl ReadRaw,
+        ty: &Ty,
+        sizing: Sizing,
+    ) -> Result<usize, DecodeError> {
+        sizing.read_len(reader, || ty.to_string())
+    }
+
+    fn decode_items(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        ty: &Ty,
+        len: usize,
+    ) -> Result<Vec<StrictVal>, DecodeError> {
+        let mut items = Vec::with_capacity(len.min(u8::MAX as usize));
+        for _ in 0..len {
+            items.push(self.decode(reader, ty)?);
+        }
+        Ok(items)
+    }
+
+    fn decode_primitive(
+        &self,
+        reader: &mut impl ReadRaw,
+        prim: Primitive,
+    ) -> Result<StrictVal, DecodeError> {
+        match prim {
+            Primitive::UNIT => return Ok(StrictVal::Unit),
+            Primitive::BYTE => return Ok(StrictVal::Uint(read_uint(reader, 1)?)),
+            _ => {}
+        }
+        let info = prim.info();
+        let size = info.byte_size() as usize;
+        if size > 16 {
+            return Err(ValueError::UnsupportedPrimitive(prim).into());
+        }
+        Ok(match info.ty {
+            NumCls::Unsigned => StrictVal::Uint(read_uint(reader, size)?),
+            NumCls::NonZero => match read_uint(reader, size)? {
+                0 => return Err(DecodeError::ZeroNatural),
+                value => StrictVal::Uint(value),
+            },
+            NumCls::Signed => {
+                let shift = 128 - size * 8;
+                StrictVal::Int((read_uint(reader, size)? << shift) as i128 >> shift)
+            }
+            NumCls::Float => return Err(ValueError::UnsupportedPrimitive(prim).into()),
+        })
+    }
+
+    /// Decodes the types which don't contain other types.
+    fn decode_leaf(&self, reader: &mut impl ReadRaw, ty: &Ty) -> Result<StrictVal, DecodeError> {
+        Ok(match ty {
+            Ty::Primitive(prim) => self.decode_primitive(reader, *prim)?,
+            Ty::Unicode(sizing) => {
+                let len = self.read_len(reader, ty, *sizing)?;
+                let bytes = reader.read_raw::<{ usize::MAX }>(len)?;
+                StrictVal::String(match self.utf8 {
+                    Utf8Policy::Strict => String::from_utf8(bytes)?,
+                    Utf8Policy::Lossy => String::from_utf8(bytes)
+                        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+                })
+            }
+            Ty::Array(_, len) => {
+                StrictVal::Bytes(reader.read_raw::<{ u16::MAX as usize }>(*len as usize)?)
+            }
+            Ty::List(_, sizing) => {
+                let len = self.read_len(reader, ty, *sizing)?;
+                StrictVal::Bytes(reader.read_raw::<{ usize::MAX }>(len)?)
+            }
+            Ty::Enum(variants) => {
+                let tag = read_uint(reader, 1)? as u8;
+                let variant =
+                    variants.iter().find(|variant| variant.tag == tag).ok_or_else(|| {
+                        DecodeError::EnumTagNotKnown {
+                            type_name: ty.to_string(),
+                            tag,
+                            variants: KnownVariants(
+                                variants
+                                    .iter()
+                                    .map(|variant| (variant.tag, variant.name.clone()))
+                                    .collect(),
+                            ),
+                        }
+                    })?;
+                StrictVal::Enum(variant.name.clone())
+            }
+            Ty::Raw(RawEncoding::Leb128) => {
+                StrictVal::Uint(read_leb128(reader, || ty.to_string())? as u128)
+            }
+            Ty::Raw(RawEncoding::CompactSize) => {
+                StrictVal::Uint(read_compact_size(reader, || ty.to_string())? as u128)
+            }
+            Ty::Raw(RawEncoding::Bitset(sizing)) => {
+                let len = self.read_len(reader, ty, *sizing)?;
+                let bytes = reader.read_raw::<{ usize::MAX }>(byte_len(len))?;
+                check_padding(&bytes, len, || ty.to_string())?;
+                StrictVal::List(
+                    (0..len)
+                        .map(|index| StrictVal::Uint((bytes[index / 8] >> (index % 8) & 1) as u128))
+                        .collect(),
+                )
+            }
+            Ty::Set(..)
+            | Ty::Map(..)
+            | Ty::Union(..)
+            | Ty::Tuple(..)
+            | Ty::Struct(..)
+            | Ty::Ref { .. } => {
+                unreachable!("composite types are decoded by Resolver::decode")
+            }
+        })
+    }
+
+    fn decode_list(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        ty: &Ty,
+        item: &Ty,
+        sizing: Sizing,
+    ) -> Result<Vec<StrictVal>, DecodeError> {
+        let len = self.read_len(reader, ty, sizing)?;
+        self.decode_items(reader, item, len)
+    }
+
+    fn decode_map(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        ty: &Ty,
+        key: &Ty,
+        value: &Ty,
+        sizing: Sizing,
+    ) -> Result<StrictVal, DecodeError> {
+        let len = self.read_len(reader, ty, sizing)?;
+        let mut items = vec![];
+        for _ in 0..len {
+            items.push((self.decode(reader, key)?, self.decode(reader, value)?));
+        }
+        Ok(StrictVal::Map(items))
+    }
+
+    fn decode_union(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        ty: &Ty,
+        variants: &[(VariantInfo, Ty)],
+    ) -> Result<StrictVal, DecodeError> {
+        let tag = read_uint(reader, 1)? as u8;
+        let (variant, fields) = variants
+            .iter()
+            .find(|(variant, _)| variant.tag == tag)
+            .ok_or_else(|| DecodeError::UnionTagNotKnown {
+                type_name: ty.to_string(),
+                tag,
+                variants: KnownVariants(
+                    variants
+                        .iter()
+                        .map(|(variant, _)| (variant.tag, variant.name.clone()))
+                        .collect(),
+                ),
+            })?;
+        Ok(StrictVal::Union(variant.name.clone(), Box::new(self.decode(reader, fields)?)))
+    }
+
+    fn decode_tuple(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        fields: &[Ty],
+    ) -> Result<StrictVal, DecodeError> {
+        let mut values = Vec::with_capacity(fields.len());
+        for field in fields {
+            values.push(self.decode(reader, field)?);
+        }
+        Ok(StrictVal::Tuple(values))
+    }
+
+    fn decode_struct(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        fields: &[(FieldName, Ty)],
+    ) -> Result<StrictVal, DecodeError> {
+        let mut values = Vec::with_capacity(fields.len());
+        for (name, field) in fields {
+            values.push((name.clone(), self.decode(reader, field)?));
+        }
+        Ok(StrictVal::Struct(values))
+    }
+
+    fn decode_ref(
+        &mut self,
+        reader: &mut impl ReadRaw,
+        lib: &LibName,
+        name: &TypeName,
+    ) -> Result<StrictVal, DecodeError> {
+        let ty = self.enter(lib, name)?;
+        let value = self.decode(reader, ty)?;
+        self.leave();
+        Ok(value)
+    }
+
+    // Each kind of composite type is decoded by its own method, keeping the
+    // frames of this recursive dispatch small.
+    fn decode(&mut self, reader: &mut impl ReadRaw, ty: &Ty) -> Result<StrictVal, DecodeError> {
+        match ty {
+            Ty::Array(item, len) if !item.is_byte() => {
+                self.decode_items(reader, item, *len as usize).map(StrictVal::List)
+            }
+            Ty::List(item, sizing) if !item.is_byte() => {
+                self.decode_list(reader, ty, item, *sizing).map(StrictVal::List)
+            }
+            Ty::Set(item, sizing) => {
+                self.decode_list(reader, ty, item, *sizing).map(StrictVal::Set)
+            }
+            Ty::Map(key, value, sizing) => self.decode_map(reader, ty, key, value, *sizing),
+            Ty::Union(variants) => self.decode_union(reader, ty, variants),
+            Ty::Tuple(fields) => self.decode_tuple(reader, fields),
+            Ty::Struct(fields) => self.decode_struct(reader, fields),
+            Ty::Ref { lib, name } => self.decode_ref(reader, lib, name),
+            _ => self.decode_leaf(reader, ty),
+        }
+    }
+
+    fn write_len(
+        &self,
+        writer: &mut impl WriteRaw,
+        ty: &Ty,
+        sizing: Sizing,
+        len: usize,
+    ) -> Result<(), ValueError> {
+        if !sizing.check(len) {
+            return Err(ValueError::Sizing {
+                ty: ty.to_string(),
+                len,
+                min: sizing.min,
+                max: sizing.max,
+            });
+        }
+        write_uint(writer, len as u128, sizing.prefix_len())?;
+        Ok(())
+    }
+
+    fn encode_primitive(
+        &self,
+        writer: &mut impl WriteRaw,
+        value: &StrictVal,
+        prim: Primitive,
+    ) -> Result<(), ValueError> {
+        let out_of_range = |value: &dyn Display| ValueError::OutOfRange {
+            prim,
+            value: value.to_string(),
+        };
+        let size = match prim {
+            Primitive::UNIT => 0,
+            Primitive::BYTE => 1,
+            _ => prim.info().byte_size() as usize,
+        };
+        let cls = match prim {
+            Primitive::UNIT | Primitive::BYTE => NumCls::Unsigned,
+            _ => prim.info().ty,
+        };
+        if size > 16 || cls == NumCls::Float {
+            return Err(ValueError::UnsupportedPrimitive(prim));
+        }
+        match (value, cls) {
+            (StrictVal::Unit, _) if prim == Primitive::UNIT => {}
+            (StrictVal::Uint(0), NumCls::NonZero) => return Err(out_of_range(&0)),
+            (StrictVal::Uint(v), NumCls::Unsigned | NumCls::NonZero) if size > 0 => {
+                if size < 16 && *v >> (size * 8) != 0 {
+                    return Err(out_of_range(v));
+                }
+                write_uint(writer, *v, size)?;
+            }
+            (StrictVal::Int(v), NumCls::Signed) => {
+                let shift = 128 - size * 8;
+                if (*v << shift) >> shift != *v {
+                    return Err(out_of_range(v));
+                }
+                write_uint(writer, *v as u128, size)?;
+            }
+            (value, _) => {
+                return Err(ValueError::Mismatch {
+                    expected: prim.to_string(),
+                    found: value.kind(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_items<'v>(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        items: impl IntoIterator<Item = &'v StrictVal>,
+        ty: &Ty,
+    ) -> Result<(), ValueError> {
+        for item in items {
+            self.encode(writer, item, ty)?;
+        }
+        Ok(())
+    }
+
+    /// Encodes the types which don't contain other types.
+    fn encode_leaf(
+        &self,
+        writer: &mut impl WriteRaw,
+        value: &StrictVal,
+        ty: &Ty,
+    ) -> Result<(), ValueError> {
+        match (ty, value) {
+            (Ty::Primitive(prim), value) => self.encode_primitive(writer, value, *prim)?,
+            (Ty::Unicode(sizing), StrictVal::String(s)) => {
+                self.write_len(writer, ty, *sizing, s.len())?;
+                writer.write_raw::<{ usize::MAX }>(s)?;
+            }
+            (Ty::Array(item, len), StrictVal::Bytes(bytes)) if item.is_byte() => {
+                let sizing = Sizing::fixed(*len as u64);
+                if !sizing.check(bytes.len()) {
+                    return Err(ValueError::Sizing {
+                        ty: ty.to_string(),
+                        len: bytes.len(),
+                        min: sizing.min,
+                        max: sizing.max,
+                    });
+                }
+                writer.write_raw::<{ u16::MAX as usize }>(bytes)?;
+            }
+            (Ty::List(item, sizing), StrictVal::Bytes(bytes)) if item.is_byte() => {
+                self.write_len(writer, ty, *sizing, bytes.len())?;
+                writer.write_raw::<{ usize::MAX }>(bytes)?;
+            }
+            (Ty::Enum(variants), StrictVal::Enum(name)) => {
+                let variant = variants
+                    .iter()
+                    .find(|variant| &variant.name == name)
+                    .ok_or_else(|| ValueError::UnknownVariant(name.clone()))?;
+                writer.write_raw_array([variant.tag])?;
+            }
+            (Ty::Raw(RawEncoding::Leb128), StrictVal::Uint(value)) => {
+                write_leb128(writer, raw_u64(*value)?)?
+            }
+            (Ty::Raw(RawEncoding::CompactSize), StrictVal::Uint(value)) => {
+                write_compact_size(writer, raw_u64(*value)?)?
+            }
+            (Ty::Raw(RawEncoding::Bitset(sizing)), StrictVal::List(bits)) => {
+                self.write_len(writer, ty, *sizing, bits.len())?;
+                let mut bytes = vec![0u8; byte_len(bits.len())];
+                for (index, bit) in bits.iter().enumerate() {
+                    match bit {
+                        StrictVal::Uint(0) => {}
+                        StrictVal::Uint(1) => bytes[index / 8] |= 1 << (index % 8),
+                        bit => {
+                            return Err(ValueError::Mismatch {
+                                expected: s!("bit 0 or 1"),
+                                found: bit.kind(),
+                            })
+                        }
+                    }
+                }
+                writer.write_raw::<{ usize::MAX }>(&bytes)?;
+            }
+            (ty, value) => {
+                return Err(ValueError::Mismatch {
+                    expected: ty.to_string(),
+                    found: value.kind(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_union(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        variants: &[(VariantInfo, Ty)],
+        name: &VariantName,
+        fields: &StrictVal,
+    ) -> Result<(), ValueError> {
+        let (variant, ty) = variants
+            .iter()
+            .find(|(variant, _)| &variant.name == name)
+            .ok_or_else(|| ValueError::UnknownVariant(name.clone()))?;
+        writer.write_raw_array([variant.tag])?;
+        self.encode(writer, fields, ty)
+    }
+
+    fn encode_tuple(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        fields: &[Ty],
+        values: &[StrictVal],
+    ) -> Result<(), ValueError> {
+        if fields.len() != values.len() {
+            return Err(ValueError::FieldCount {
+                expected: fields.len(),
+                found: values.len(),
+            });
+        }
+        for (ty, value) in fields.iter().zip(values) {
+            self.encode(writer, value, ty)?;
+        }
+        Ok(())
+    }
+
+    fn encode_struct(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        fields: &[(FieldName, Ty)],
+        values: &[(FieldName, StrictVal)],
+    ) -> Result<(), ValueError> {
+        if fields.len() != values.len() {
+            return Err(ValueError::FieldCount {
+                expected: fields.len(),
+                found: values.len(),
+            });
+        }
+        for ((expected, ty), (found, value)) in fields.iter().zip(values) {
+            if expected != found {
+                return Err(ValueError::FieldMismatch {
+                    expected: expected.clone(),
+                    found: found.clone(),
+                });
+            }
+            self.encode(writer, value, ty)?;
+        }
+        Ok(())
+    }
+
+    fn encode_ref(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        value: &StrictVal,
+        lib: &LibName,
+        name: &TypeName,
+    ) -> Result<(), ValueError> {
+        let ty = self.enter(lib, name)?;
+        self.encode(writer, value, ty)?;
+        self.leave();
+        Ok(())
+    }
+
+    // Each kind of composite type is encoded by its own method, keeping the
+    // frames of this recursive dispatch small.
+    fn encode(
+        &mut self,
+        writer: &mut impl WriteRaw,
+        value: &StrictVal,
+        ty: &Ty,
+    ) -> Result<(), ValueError> {
+        match (ty, value) {
+            (Ty::Array(item, len), StrictVal::List(items)) if !item.is_byte() => {
+                if items.len() != *len as usize {
+                    return Err(ValueError::Sizing {
+                        ty: ty.to_string(),
+                        len: items.len(),
+                        min: *len as u64,
+                        max: *len as u64,
+                    });
+                }
+                self.encode_items(writer, items, item)
+            }
+            (Ty::List(item, sizing), StrictVal::List(items)) if !item.is_byte() => {
+                self.write_len(writer, ty, *sizing, items.len())?;
+                self.encode_items(writer, items, item)
+            }
+            (Ty::Set(item, sizing), StrictVal::Set(items)) => {
+                self.write_len(writer, ty, *sizing, items.len())?;
+                self.encode_items(writer, items, item)
+            }
+            (Ty::Map(key, item, sizing), StrictVal::Map(items)) => {
+                self.write_len(writer, ty, *sizing, items.len())?;
+                for (k, v) in items {
+                    self.encode(writer, k, key)?;
+                    self.encode(writer, v, item)?;
+                }
+                Ok(())
+            }
+            (Ty::Union(variants), StrictVal::Union(name, fields)) => {
+                self.encode_union(writer, variants, name, fields)
+            }
+            (Ty::Tuple(fields), StrictVal::Tuple(values)) => {
+                self.encode_tuple(writer, fields, values)
+            }
+            (Ty::Struct(fields), StrictVal::Struct(values)) => {
+                self.encode_struct(writer, fields, values)
+            }
+            (Ty::Ref { lib, name }, value) => self.encode_ref(writer, value, lib, name),
+            (ty, value) => self.encode_leaf(writer, value, ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{TinyBlob, TinyOrdMap, TinyOrdSet, TinyString, TinyVec};
+
+    use super::*;
+    use crate::{
+        test, StrictDecode, StrictEncode, StrictReader, StrictType, StrictTypeInfo, StrictWriter,
+    };
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Header {
+        version: u16,
+        offset: i32,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = repr, into_u8, try_from_u8, crate = crate)]
+    #[repr(u8)]
+    enum Kind {
+        #[default]
+        Plain = 0,
+        Nested = 0x10,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", tags = order, crate = crate)]
+    enum Payload {
+        #[default]
+        Empty,
+        Point(u8, i8),
+        Named {
+            id: u64,
+        },
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Record {
+        header: Header,
+        kind: Kind,
+        payloads: TinyVec<Payload>,
+        name: TinyString,
+        data: TinyBlob,
+        id: [u8; 4],
+        tags: TinyOrdSet<u16>,
+        index: TinyOrdMap<u8, TinyString>,
+        pair: (u8, i16),
+    }
+
+    fn sys() -> TypeSystem {
+        let mut sys = TypeSystem::new();
+        sys.insert(
+            libname!("Test"),
+            tn!("Header"),
+            Ty::Struct(vec![
+                (fname!("version"), Ty::Primitive(Primitive::U16)),
+                (fname!("offset"), Ty::Primitive(Primitive::I32)),
+            ]),
+        );
+        sys.insert(
+            libname!("Test"),
+            tn!("Kind"),
+            Ty::Enum(vec![
+                VariantInfo::new(vname!("plain"), 0),
+                VariantInfo::new(vname!("nested"), 0x10),
+            ]),
+        );
+        sys.insert(
+            libname!("Test"),
+            tn!("Payload"),
+            Ty::Union(vec![
+                (VariantInfo::new(vname!("empty"), 0), Ty::unit()),
+                (
+                    VariantInfo::new(vname!("point"), 1),
+                    Ty::Tuple(vec![Ty::Primitive(Primitive::U8), Ty::Primitive(Primitive::I8)]),
+                ),
+                (
+                    VariantInfo::new(vname!("named"), 2),
+                    Ty::Struct(vec![(fname!("id"), Ty::Primitive(Primitive::U64))]),
+                ),
+            ]),
+        );
+        let tiny_string = || Ty::Unicode(Sizing::U8);
+        sys.insert(
+            libname!("Test"),
+            tn!("Record"),
+            Ty::Struct(vec![
+                (fname!("header"), Ty::named(libname!("Test"), tn!("Header"))),
+                (fname!("kind"), Ty::named(libname!("Test"), tn!("Kind"))),
+                (
+                    fname!("payloads"),
+                    Ty::list(Ty::named(libname!("Test"), tn!("Payload")), Sizing::U8),
+                ),
+                (fname!("name"), tiny_string()),
+                (fname!("data"), Ty::list(Ty::Primitive(Primitive::BYTE), Sizing::U8)),
+                (fname!("id"), Ty::array(Ty::Primitive(Primitive::BYTE), 4)),
+                (fname!("tags"), Ty::set(Ty::Primitive(Primitive::U16), Sizing::U8)),
+                (fname!("index"), Ty::map(Ty::Primitive(Primitive::U8), tiny_string(), Sizing::U8)),
+                (
+                    fname!("pair"),
+                    Ty::Tuple(vec![Ty::Primitive(Primitive::U8), Ty::Primitive(Primitive::I16)]),
+                ),
+            ]),
+        );
+        sys
+    }
+
+    fn record() -> Record {
+        Record {
+            header: Header {
+                version: 2,
+                offset: -70000,
+            },
+            kind: Kind::Nested,
+            payloads: TinyVec::try_from(vec![
+                Payload::Empty,
+                Payload::Point(7, -1),
+                Payload::Named { id: u64::MAX },
+            ])
+            .unwrap(),
+            name: TinyString::try_from(s!("rec")).unwrap(),
+            data: TinyBlob::try_from(vec![0xAB, 0xCD]).unwrap(),
+            id: [1, 2, 3, 4],
+            tags: TinyOrdSet::try_from_iter([5u16, 0x100]).unwrap(),
+            index: TinyOrdMap::try_from_iter([(1u8, TinyString::try_from(s!("a")).unwrap())])
+                .unwrap(),
+            pair: (0xFF, i16::MIN),
+        }
+    }
+
+    fn value() -> StrictVal {
+        let string = |s: &str| StrictVal::String(s.to_owned());
+        StrictVal::Struct(vec![
+            (
+                fname!("header"),
+                StrictVal::Struct(vec![
+                    (fname!("version"), StrictVal::Uint(2)),
+                    (fname!("offset"), StrictVal::Int(-70000)),
+                ]),
+            ),
+            (fname!("kind"), StrictVal::Enum(vname!("nested"))),
+            (
+                fname!("payloads"),
+                StrictVal::List(vec![
+                    StrictVal::Union(vname!("empty"), Box::new(StrictVal::Unit)),
+                    StrictVal::Union(
+                        vname!("point"),
+                        Box::new(StrictVal::Tuple(vec![StrictVal::Uint(7), StrictVal::Int(-1)])),
+                    ),
+                    StrictVal::Union(
+                        vname!("named"),
+                        Box::new(StrictVal::Struct(vec![(
+                            fname!("id"),
+                            StrictVal::Uint(u64::MAX as u128),
+                        )])),
+                    ),
+                ]),
+            ),
+            (fname!("name"), string("rec")),
+            (fname!("data"), StrictVal::Bytes(vec![0xAB, 0xCD])),
+            (fname!("id"), StrictVal::Bytes(vec![1, 2, 3, 4])),
+            (fname!("tags"), StrictVal::Set(vec![StrictVal::Uint(5), StrictVal::Uint(0x100)])),
+            (fname!("index"), StrictVal::Map(vec![(StrictVal::Uint(1), string("a"))])),
+            (
+                fname!("pair"),
+                StrictVal::Tuple(vec![StrictVal::Uint(0xFF), StrictVal::Int(i16::MIN as i128)]),
+            ),
+        ])
+    }
+
+    fn decode(data: &[u8], ty: &Ty, sys: &TypeSystem) -> Result<StrictVal, DecodeError> {
+        let mut reader = StrictReader::from_slice::<{ usize::MAX }>(data);
+        let value = decode_value(&mut reader, ty, sys)?;
+        assert!(reader.remaining().is_empty());
+        Ok(value)
+    }
+
+    fn encode(value: &StrictVal, ty: &Ty, sys: &TypeSystem) -> Result<Vec<u8>, ValueError> {
+        let writer = StrictWriter::in_memory::<{ usize::MAX }>();
+        Ok(encode_value(writer, value, ty, sys)?.unbox().unconfine())
+    }
+
+    #[test]
+    fn roundtrip() {
+        let sys = sys();
+        let ty = Ty::named(libname!("Test"), tn!("Record"));
+        let data = test::encode(&record());
+        assert_eq!(decode(&data, &ty, &sys).unwrap(), value());
+        let data = encode(&value(), &ty, &sys).unwrap();
+        assert_eq!(test::decode::<Record>(&data), record());
+    }
+
+    #[test]
+    fn type_info() {
+        let mut lib = TypeLib::new(libname!("Test"));
+        lib.register::<Header>().unwrap();
+        lib.register::<Kind>().unwrap();
+        lib.register::<Payload>().unwrap();
+        lib.register::<Record>().unwrap();
+        let sys = TypeSystem::try_from(&lib).unwrap();
+        assert_eq!(sys, self::sys());
+
+        let ty = Ty::try_from(&Record::type_info()).unwrap();
+        assert_eq!(Some(&ty), sys.get(&libname!("Test"), &tn!("Record")));
+        assert_eq!(decode(&test::encode(&record()), &ty, &sys).unwrap(), value());
+
+        let ty = Ty::try_from(&Option::<TinyString>::type_info()).unwrap();
+        let some = StrictVal::Union(
+            vname!("some"),
+            Box::new(StrictVal::Tuple(vec![StrictVal::String(s!("a"))])),
+        );
+        assert_eq!(encode(&some, &ty, &sys).unwrap(), test::encode(&Some(tiny_s!("a"))));
+        assert_eq!(
+            decode(&[0], &ty, &sys).unwrap(),
+            StrictVal::Union(vname!("none"), Box::new(StrictVal::Unit))
+        );
+
+        let mut info = Header::type_info();
+        let TypeClassOwned::Struct { fields } = &mut info.class else {
+            unreachable!()
+        };
+        fields[0].ty = None;
+        assert_eq!(
+            Ty::try_from(&info).unwrap_err().to_string(),
+            "type information `Test.Header: struct { version, offset: I32 }` doesn't describe the \
+             types of all its fields and items."
+        );
+    }
+
+    #[test]
+    fn utf8_policy() {
+        let sys = TypeSystem::new();
+        let ty = Ty::Unicode(Sizing::U8);
+        let data = [2, 0xC3, 0x28];
+        assert!(matches!(decode(&data, &ty, &sys).unwrap_err(), DecodeError::Utf8(_)));
+        let mut reader = StrictReader::in_memory::<16>(data).with_utf8_policy(Utf8Policy::Lossy);
+        assert_eq!(
+            decode_value(&mut reader, &ty, &sys).unwrap(),
+            StrictVal::String(s!("\u{FFFD}("))
+        );
+    }
+
+    #[test]
+    fn primitives() {
+        let sys = TypeSystem::new();
+        let check = |prim: Primitive, data: &[u8], value: StrictVal| {
+            let ty = Ty::Primitive(prim);
+            assert_eq!(decode(data, &ty, &sys).unwrap(), value, "{prim}");
+            assert_eq!(encode(&value, &ty, &sys).unwrap(), data, "{prim}");
+        };
+        check(Primitive::UNIT, &[], StrictVal::Unit);
+        check(Primitive::BYTE, &[0xFE], StrictVal::Uint(0xFE));
+        check(Primitive::U24, &[1, 2, 3], StrictVal::Uint(0x030201));
+        check(Primitive::I24, &[0xFF, 0xFF, 0xFF], StrictVal::Int(-1));
+        check(Primitive::N8, &[1], StrictVal::Uint(1));
+        check(Primitive::U128, &[0xFF; 16], StrictVal::Uint(u128::MAX));
+        check(Primitive::I128, &[0xFF; 16], StrictVal::Int(-1));
+
+        let ty = Ty::Primitive(Primitive::N8);
+        assert_eq!(decode(&[0], &ty, &sys).unwrap_err(), DecodeError::ZeroNatural);
+        assert!(matches!(
+            encode(&StrictVal::Uint(0), &ty, &sys).unwrap_err(),
+            ValueError::OutOfRange { .. }
+        ));
+        let ty = Ty::Primitive(Primitive::I8);
+        assert_eq!(
+            encode(&StrictVal::Int(128), &ty, &sys).unwrap_err().to_string(),
+            "value 128 is out of range of I8 type."
+        );
+        assert_eq!(
+            encode(&StrictVal::Uint(1), &ty, &sys).unwrap_err().to_string(),
+            "unsigned integer value can't be encoded as I8."
+        );
+        let ty = Ty::Primitive(Primitive::F32);
+        assert_eq!(
+            decode(&[0; 4], &ty, &sys).unwrap_err().to_string(),
+            "data integrity problem during strict decoding operation.\nDetails: primitive type \
+             F32 is not supported by reflected values."
+        );
+    }
+
+    #[test]
+    fn sizing() {
+        let sys = TypeSystem::new();
+        let ty = Ty::Unicode(Sizing::new(1, 2));
+        assert!(matches!(decode(&[0], &ty, &sys).unwrap_err(), DecodeError::Undersize { .. }));
+        assert!(matches!(
+            decode(&[3, 0x61, 0x61, 0x61], &ty, &sys).unwrap_err(),
+            DecodeError::Oversize { .. }
+        ));
+        assert_eq!(
+            encode(&StrictVal::String(s!("abc")), &ty, &sys).unwrap_err().to_string(),
+            "[Unicode ^ 1..0x2] value contains 3 items, while the type is confined to 1..=2 items."
+        );
+        let ty = Ty::array(Ty::Primitive(Primitive::BYTE), 2);
+        assert!(matches!(
+            encode(&StrictVal::Bytes(vec![1]), &ty, &sys).unwrap_err(),
+            ValueError::Sizing { len: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn unknown_and_recursive() {
+        let mut sys = TypeSystem::new();
+        let ty = Ty::named(libname!("Test"), tn!("Missing"));
+        assert_eq!(
+            decode(&[], &ty, &sys).unwrap_err().to_string(),
+            "data integrity problem during strict decoding operation.\nDetails: type Test.Missing \
+             is not defined in the type system."
+        );
+        assert_eq!(
+            encode(&StrictVal::Unit, &ty, &sys).unwrap_err(),
+            ValueError::UnknownType(libname!("Test"), tn!("Missing"))
+        );
+
+        let looped = Ty::named(libname!("Test"), tn!("Loop"));
+        sys.insert(
+            libname!("Test"),
+            tn!("Loop"),
+            Ty::Struct(vec![(fname!("next"), looped.clone())]),
+        );
+        let tree = Ty::named(libname!("Test"), tn!("Tree"));
+        sys.insert(
+            libname!("Test"),
+            tn!("Tree"),
+            Ty::Struct(vec![(fname!("children"), Ty::list(tree.clone(), Sizing::U8))]),
+        );
+        assert_eq!(decode(&[], &looped, &sys).unwrap_err(), DecodeError::MaxRecursionDepth {
+            limit: RECURSION_DEPTH_LIMIT
+        });
+
+        let leaf = StrictVal::Struct(vec![(fname!("children"), StrictVal::List(vec![]))]);
+        assert_eq!(decode(&[0], &tree, &sys).unwrap(), leaf);
+        assert_eq!(encode(&leaf, &tree, &sys).unwrap(), [0]);
+        let nested = StrictVal::Struct(vec![(fname!("children"), StrictVal::List(vec![leaf]))]);
+        assert_eq!(decode(&[1, 0], &tree, &sys).unwrap(), nested);
+        assert_eq!(encode(&nested, &tree, &sys).unwrap(), [1, 0]);
+
+        let mut deep = StrictVal::Struct(vec![(fname!("children"), StrictVal::List(vec![]))]);
+        for _ in 0..RECURSION_DEPTH_LIMIT {
+            deep = StrictVal::Struct(vec![(fname!("children"), StrictVal::List(vec![deep]))]);
+        }
+        assert_eq!(encode(&deep, &tree, &sys).unwrap_err(), ValueError::MaxRecursionDepth {
+            limit: RECURSION_DEPTH_LIMIT
+        });
+        let mut data = vec![1u8; RECURSION_DEPTH_LIMIT];
+        data.push(0);
+        assert_eq!(decode(&data, &tree, &sys).unwrap_err(), DecodeError::MaxRecursionDepth {
+            limit: RECURSION_DEPTH_LIMIT
+        });
+    }
+
+    #[test]
+    fn qualified_names() {
+        let mut sys = TypeSystem::new();
+        sys.insert(libname!("Test"), tn!("Header"), Ty::Primitive(Primitive::U8));
+        sys.insert(libname!("Other"), tn!("Header"), Ty::Primitive(Primitive::U16));
+        let ty = Ty::Struct(vec![
+            (fname!("a"), Ty::named(libname!("Test"), tn!("Header"))),
+            (fname!("b"), Ty::named(libname!("Other"), tn!("Header"))),
+        ]);
+        let val = decode(&[1, 2, 0], &ty, &sys).unwrap();
+        assert_eq!(
+            val,
+            StrictVal::Struct(vec![
+                (fname!("a"), StrictVal::Uint(1)),
+                (fname!("b"), StrictVal::Uint(2)),
+            ])
+        );
+        assert_eq!(encode(&val, &ty, &sys).unwrap(), [1, 2, 0]);
+        assert_eq!(Ty::named(libname!("Other"), tn!("Header")).to_string(), "Other.Header");
+    }
+
+    #[test]
+    fn mismatch() {
+        let sys = sys();
+        let ty = Ty::named(libname!("Test"), tn!("Header"));
+        let value = StrictVal::Struct(vec![
+            (fname!("offset"), StrictVal::Int(0)),
+            (fname!("version"), StrictVal::Uint(0)),
+        ]);
+        assert_eq!(
+            encode(&value, &ty, &sys).unwrap_err().to_string(),
+            "field offset is provided instead of version."
+        );
+        assert!(matches!(
+            encode(&StrictVal::Tuple(vec![]), &ty, &sys).unwrap_err(),
+            ValueError::Mismatch { found: "tuple", .. }
+        ));
+        assert_eq!(
+            encode(
+                &StrictVal::Enum(vname!("other")),
+                &Ty::named(libname!("Test"), tn!("Kind")),
+                &sys
+            )
+            .unwrap_err(),
+            ValueError::UnknownVariant(vname!("other"))
+        );
+        assert!(matches!(
+            decode(&[3], &Ty::named(libname!("Test"), tn!("Payload")), &sys).unwrap_err(),
+            DecodeError::UnionTagNotKnown { tag: 3, .. }
+        ));
+    }
+}
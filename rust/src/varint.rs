@@ -0,0 +1,289 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Variable-length integers, **for the fields of the formats external to
+//! strict encoding only**.
+//!
+//! Strict encoding uses fixed-width integers; the types of this module are
+//! an explicit opt-in for the data which must be bridged with other formats
+//! using variable-length integers, like Bitcoin P2P messages or
+//! protobuf-like formats. They shouldn't be used in the data designed for
+//! strict encoding.
+//!
+//! - [`VarIntU64`] is encoded as unsigned LEB128: seven bits of the value per byte, starting from
+//!   the least significant ones, with the high bit set in all bytes except the last one; up to 10
+//!   bytes.
+//! - [`CompactSizeU64`] is encoded as Bitcoin `CompactSize`: values up to `0xFC` as a single byte,
+//!   larger values as `0xFD`, `0xFE` or `0xFF` byte followed by the value as little-endian `u16`,
+//!   `u32` or `u64`.
+//!
+//! Decoding accepts only the minimal encoding of the value and fails with
+//! [`DecodeError::NonMinimalVarInt`] error otherwise, thus each value has a
+//! single encoding, as with all other strict types.
+//!
+//! The variable-length encoding can't be described by the strict type
+//! system, thus the types register their [`RawEncoding`] in place of the
+//! layout; semantic ids, [`crate::StrictSize`] bounds, skipping and the
+//! reflected values follow it.
+
+use alloc::string::String;
+
+use crate::{
+    io, type_name, DecodeError, RawEncoding, ReadRaw, StrictDecode, StrictEncode, StrictType,
+    TypeName, TypedRead, TypedWrite, WriteRaw, LIB_NAME_STD,
+};
+
+/// Unsigned 64-bit integer encoded as LEB128, for interoperability with
+/// external formats only. Decoding accepts only the minimal encoding of the
+/// value.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
+#[derive(Display)]
+#[display(inner)]
+pub struct VarIntU64(u64);
+
+/// Unsigned 64-bit integer encoded as Bitcoin `CompactSize`, for
+/// interoperability with external formats only. Decoding accepts only the
+/// minimal encoding of the value.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
+#[derive(Display)]
+#[display(inner)]
+pub struct CompactSizeU64(u64);
+
+fn non_minimal(type_name: String) -> DecodeError { DecodeError::NonMinimalVarInt { type_name } }
+
+fn out_of_range(type_name: String, value: u128) -> DecodeError {
+    DecodeError::ValueOutOfRange(type_name, 0..u64::MAX as u128 + 1, value)
+}
+
+/// Writes the value as LEB128.
+pub(crate) fn write_leb128(writer: &mut impl WriteRaw, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        buf[len] = value as u8 & 0x7F;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+        buf[len] |= 0x80;
+        len += 1;
+    }
+    writer.write_raw::<10>(&buf[..=len])
+}
+
+/// Reads LEB128 value, reporting the errors for the type named `type_name`.
+pub(crate) fn read_leb128(
+    reader: &mut impl ReadRaw,
+    type_name: impl FnOnce() -> String,
+) -> Result<u64, DecodeError> {
+    let mut value = 0u128;
+    for index in 0..10 {
+        let [byte] = reader.read_raw_array::<1>()?;
+        value |= ((byte & 0x7F) as u128) << (index * 7);
+        if byte & 0x80 != 0 {
+            continue;
+        }
+        // the last byte must add significant bits, unless it is the only one
+        if byte == 0 && index > 0 {
+            return Err(non_minimal(type_name()));
+        }
+        return u64::try_from(value).map_err(|_| out_of_range(type_name(), value));
+    }
+    // encodings longer than 10 bytes either carry a value above `u64::MAX`, or
+    // are padded with the continuation bytes
+    match u64::try_from(value) {
+        Ok(_) => Err(non_minimal(type_name())),
+        Err(_) => Err(out_of_range(type_name(), value)),
+    }
+}
+
+/// Writes the value as Bitcoin `CompactSize`.
+pub(crate) fn write_compact_size(writer: &mut impl WriteRaw, value: u64) -> io::Result<()> {
+    match value {
+        small @ 0..=0xFC => writer.write_raw_array([small as u8]),
+        medium @ 0xFD..=0xFFFF => {
+            writer.write_raw_array([0xFD])?;
+            writer.write_raw_array((medium as u16).to_le_bytes())
+        }
+        large @ 0x1_0000..=0xFFFF_FFFF => {
+            writer.write_raw_array([0xFE])?;
+            writer.write_raw_array((large as u32).to_le_bytes())
+        }
+        huge => {
+            writer.write_raw_array([0xFF])?;
+            writer.write_raw_array(huge.to_le_bytes())
+        }
+    }
+}
+
+/// Reads Bitcoin `CompactSize` value, reporting the errors for the type named
+/// `type_name`.
+pub(crate) fn read_compact_size(
+    reader: &mut impl ReadRaw,
+    type_name: impl FnOnce() -> String,
+) -> Result<u64, DecodeError> {
+    let (value, min) = match reader.read_raw_array::<1>()? {
+        [0xFD] => (u16::from_le_bytes(reader.read_raw_array()?) as u64, 0xFD),
+        [0xFE] => (u32::from_le_bytes(reader.read_raw_array()?) as u64, 0x1_0000),
+        [0xFF] => (u64::from_le_bytes(reader.read_raw_array()?), 0x1_0000_0000),
+        [small] => (small as u64, 0),
+    };
+    if value < min {
+        return Err(non_minimal(type_name()));
+    }
+    Ok(value)
+}
+
+impl StrictType for VarIntU64 {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("VarIntU64")) }
+}
+impl StrictEncode for VarIntU64 {
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        unsafe {
+            write_leb128(writer.raw_writer(), self.0)?;
+            Ok(writer.register_raw::<Self>(RawEncoding::Leb128))
+        }
+    }
+}
+impl StrictDecode for VarIntU64 {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        read_leb128(unsafe { reader.raw_reader() }, type_name::<Self>).map(VarIntU64)
+    }
+}
+
+impl StrictType for CompactSizeU64 {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_STD;
+    fn strict_name() -> Option<TypeName> { Some(tn!("CompactSizeU64")) }
+}
+impl StrictEncode for CompactSizeU64 {
+    fn strict_encode<W: TypedWrite>(&self, mut writer: W) -> io::Result<W> {
+        unsafe {
+            write_compact_size(writer.raw_writer(), self.0)?;
+            Ok(writer.register_raw::<Self>(RawEncoding::CompactSize))
+        }
+    }
+}
+impl StrictDecode for CompactSizeU64 {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        read_compact_size(unsafe { reader.raw_reader() }, type_name::<Self>).map(CompactSizeU64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{encoding, try_decode};
+
+    #[test]
+    fn leb128() {
+        encoding(&VarIntU64(0), [0x00]);
+        encoding(&VarIntU64(1), [0x01]);
+        encoding(&VarIntU64(0x7F), [0x7F]);
+        encoding(&VarIntU64(0x80), [0x80, 0x01]);
+        encoding(&VarIntU64(300), [0xAC, 0x02]);
+        encoding(&VarIntU64(0x3FFF), [0xFF, 0x7F]);
+        encoding(&VarIntU64(0x4000), [0x80, 0x80, 0x01]);
+        encoding(&VarIntU64(0x1F_FFFF), [0xFF, 0xFF, 0x7F]);
+        encoding(&VarIntU64(0x20_0000), [0x80, 0x80, 0x80, 0x01]);
+        encoding(&VarIntU64(1 << 63), [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]);
+        encoding(&VarIntU64(u64::MAX), [
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01,
+        ]);
+    }
+
+    #[test]
+    fn leb128_non_minimal() {
+        let err = DecodeError::NonMinimalVarInt {
+            type_name: s!("VarIntU64"),
+        };
+        assert_eq!(try_decode::<VarIntU64>(&[0x80, 0x00]).unwrap_err(), err);
+        assert_eq!(try_decode::<VarIntU64>(&[0xFF, 0x80, 0x00]).unwrap_err(), err);
+        assert_eq!(try_decode::<VarIntU64>(&[0x81, 0x80, 0x80, 0x00]).unwrap_err(), err);
+        assert_eq!(try_decode::<VarIntU64>(&[0x80; 11]).unwrap_err(), err);
+
+        // values above `u64::MAX`, including the ones encoded in more than 10 bytes
+        assert_eq!(
+            try_decode::<VarIntU64>(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02])
+                .unwrap_err(),
+            DecodeError::ValueOutOfRange(s!("VarIntU64"), 0..1 << 64, 0x1_7FFF_FFFF_FFFF_FFFF)
+        );
+        assert!(matches!(
+            try_decode::<VarIntU64>(&[0xFF; 11]),
+            Err(DecodeError::ValueOutOfRange(_, _, _))
+        ));
+        assert!(matches!(try_decode::<VarIntU64>(&[0x80]), Err(DecodeError::Io(_))));
+    }
+
+    #[test]
+    fn compact_size() {
+        encoding(&CompactSizeU64(0), [0x00]);
+        encoding(&CompactSizeU64(0xFC), [0xFC]);
+        encoding(&CompactSizeU64(0xFD), [0xFD, 0xFD, 0x00]);
+        encoding(&CompactSizeU64(0xFFFF), [0xFD, 0xFF, 0xFF]);
+        encoding(&CompactSizeU64(0x1_0000), [0xFE, 0x00, 0x00, 0x01, 0x00]);
+        encoding(&CompactSizeU64(0xFFFF_FFFF), [0xFE, 0xFF, 0xFF, 0xFF, 0xFF]);
+        encoding(&CompactSizeU64(0x1_0000_0000), [
+            0xFF, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        ]);
+        encoding(&CompactSizeU64(u64::MAX), [0xFF; 9]);
+    }
+
+    #[test]
+    fn compact_size_non_minimal() {
+        let err = DecodeError::NonMinimalVarInt {
+            type_name: s!("CompactSizeU64"),
+        };
+        assert_eq!(try_decode::<CompactSizeU64>(&[0xFD, 0x00, 0x00]).unwrap_err(), err);
+        assert_eq!(try_decode::<CompactSizeU64>(&[0xFD, 0xFC, 0x00]).unwrap_err(), err);
+        assert_eq!(try_decode::<CompactSizeU64>(&[0xFE, 0xFF, 0xFF, 0x00, 0x00]).unwrap_err(), err);
+        assert_eq!(
+            try_decode::<CompactSizeU64>(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00])
+                .unwrap_err(),
+            err
+        );
+        assert_eq!(
+            err.to_string(),
+            "variable-length integer `CompactSizeU64` is not encoded in its minimal form."
+        );
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Inventory {
+        count: CompactSizeU64,
+        offset: VarIntU64,
+        flags: u8,
+    }
+
+    #[test]
+    fn derived() {
+        encoding(
+            &Inventory {
+                count: CompactSizeU64::from(0x1234),
+                offset: VarIntU64::from(0x80),
+                flags: 0x01,
+            },
+            [0xFD, 0x34, 0x12, 0x80, 0x01, 0x01],
+        );
+    }
+}
@@ -0,0 +1,142 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for passing strict-encoded data across the JavaScript boundary of
+//! WebAssembly modules, available with `wasm` feature.
+//!
+//! The helpers work with owned boxed byte slices and plain byte slices, which
+//! `wasm-bindgen` converts to and from `Uint8Array`, and their errors convert
+//! into [`String`]s, which it throws as JavaScript errors. The crate doesn't
+//! depend on `wasm-bindgen`, such that the downstream crates wrap the helpers
+//! for their own types:
+//!
+//! ```ignore
+//! #[wasm_bindgen]
+//! pub fn encode_invoice(invoice: &Invoice) -> Result<Box<[u8]>, String> {
+//!     Ok(strict_encoding::wasm::serialize::<MAX_LEN>(invoice)?)
+//! }
+//!
+//! #[wasm_bindgen]
+//! pub fn decode_invoice(data: &[u8]) -> Result<Invoice, String> {
+//!     Ok(strict_encoding::wasm::deserialize::<Invoice, MAX_LEN>(data)?)
+//! }
+//! ```
+//!
+//! The helpers are not specific to WebAssembly targets and work on any target
+//! supported by the crate.
+
+use alloc::boxed::Box;
+
+use amplify::confinement;
+
+use crate::{
+    DecodeError, DeserializeError, SerializeError, StrictDecode, StrictEncode, StrictReader,
+    StrictWriter,
+};
+
+/// Serializes the value into a boxed byte slice of at most `MAX` bytes.
+///
+/// # Errors
+///
+/// If the encoding of the value exceeds `MAX` bytes or violates confinement
+/// of the value collections.
+pub fn serialize<const MAX: usize>(value: &impl StrictEncode) -> Result<Box<[u8]>, SerializeError> {
+    let writer = value.strict_encode(StrictWriter::in_memory::<MAX>())?;
+    Ok(writer.unbox().unconfine().into_boxed_slice())
+}
+
+/// Deserializes a value of type `T` from data of at most `MAX` bytes, which
+/// must be entirely consumed.
+///
+/// # Errors
+///
+/// If the data are longer than `MAX` bytes, which is checked before decoding
+/// them, are not a valid encoding of the value, or are not entirely consumed.
+pub fn deserialize<T: StrictDecode, const MAX: usize>(data: &[u8]) -> Result<T, DeserializeError> {
+    if data.len() > MAX {
+        return Err(DecodeError::from(confinement::Error::Oversize {
+            len: data.len(),
+            max_len: MAX,
+        })
+        .into());
+    }
+    let mut reader = StrictReader::from_slice::<MAX>(data);
+    let value = T::strict_decode(&mut reader)?;
+    reader.finish()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+
+    use amplify::confinement::TinyString;
+
+    use super::*;
+    use crate::StrictType;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Invoice {
+        amount: u64,
+        memo: TinyString,
+    }
+
+    fn invoice() -> Invoice {
+        Invoice {
+            amount: 1000,
+            memo: TinyString::try_from(s!("coffee")).unwrap(),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = serialize::<15>(&invoice()).unwrap();
+        assert_eq!(&*data, b"\xe8\x03\x00\x00\x00\x00\x00\x00\x06coffee");
+        assert_eq!(deserialize::<Invoice, 15>(&data).unwrap(), invoice());
+    }
+
+    #[test]
+    fn errors() {
+        let err = String::from(serialize::<14>(&invoice()).unwrap_err());
+        assert!(!err.is_empty());
+
+        let data = serialize::<15>(&invoice()).unwrap();
+        assert_eq!(
+            deserialize::<Invoice, 14>(&data),
+            Err(DeserializeError::Decode(DecodeError::Confinement(confinement::Error::Oversize {
+                len: 15,
+                max_len: 14
+            })))
+        );
+        assert_eq!(
+            String::from(deserialize::<Invoice, 15>(&data[..14]).unwrap_err()),
+            DecodeError::Io(crate::io::ErrorKind::UnexpectedEof.into()).to_string()
+        );
+        let mut data = data.into_vec();
+        data.push(0);
+        assert_eq!(
+            deserialize::<Invoice, 16>(&data),
+            Err(DeserializeError::DataNotEntirelyConsumed(1))
+        );
+    }
+}
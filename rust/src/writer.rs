@@ -19,21 +19,51 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeMap, BTreeSet};
-use std::io;
-use std::io::Sink;
-use std::marker::PhantomData;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 use amplify::confinement::U64 as U64MAX;
+#[cfg(feature = "std")]
 use amplify::WriteCounter;
 
+use crate::io::{self, Sink};
+#[cfg(feature = "std")]
+use crate::observer::{CodecObserver, Observation, Observer};
+#[cfg(feature = "std")]
+use crate::reader::decoded_name;
 use crate::{
-    DefineEnum, DefineStruct, DefineTuple, DefineUnion, FieldName, LibName, StrictEncode,
-    StrictEnum, StrictStruct, StrictSum, StrictTuple, StrictUnion, TypeName, TypedParent,
-    TypedWrite, Variant, VariantName, WriteEnum, WriteRaw, WriteStruct, WriteTuple, WriteUnion,
-    LIB_EMBEDDED,
+    BufferOverflow, DefineEnum, DefineStruct, DefineTuple, DefineUnion, FieldName, LibName,
+    StrictEncode, StrictEncodeFields, StrictEnum, StrictStruct, StrictSum, StrictTuple, StrictType,
+    StrictUnion, TypeName, TypedParent, TypedWrite, Variant, VariantError, VariantName, WriteEnum,
+    WriteLimitExceeded, WriteRaw, WriteStruct, WriteTuple, WriteUnion, LIB_EMBEDDED,
 };
 
+/// A simple way to count bytes written through [`io::Write`].
+///
+/// With `std` feature this is `amplify::WriteCounter`.
+#[cfg(not(feature = "std"))]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug)]
+pub struct WriteCounter {
+    /// Count of bytes which passed through this writer
+    pub count: usize,
+}
+
+#[cfg(not(feature = "std"))]
+impl io::Write for WriteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = buf.len();
+        self.count += count;
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
 // TODO: Move to amplify crate
 #[derive(Clone, Debug)]
 pub struct ConfinedWriter<W: io::Write> {
@@ -66,9 +96,7 @@ impl<W: io::Write> ConfinedWriter<W> {
 
 impl<W: io::Write> io::Write for ConfinedWriter<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        if self.count + buf.len() > self.limit {
-            return Err(io::ErrorKind::InvalidInput.into());
-        }
+        WriteLimitExceeded::check(self.limit, self.count, buf.len())?;
         let count = self.writer.write(buf)?;
         self.count += count;
         Ok(count)
@@ -81,7 +109,8 @@ impl<W: io::Write> io::Write for ConfinedWriter<W> {
 pub struct StreamWriter<W: io::Write>(ConfinedWriter<W>);
 
 impl<W: io::Write> StreamWriter<W> {
-    pub fn new<const MAX: usize>(inner: W) -> Self { Self(ConfinedWriter::with(MAX, inner)) }
+    pub fn new<const MAX: usize>(inner: W) -> Self { Self::with(MAX, inner) }
+    pub(crate) fn with(limit: usize, inner: W) -> Self { Self(ConfinedWriter::with(limit, inner)) }
     pub fn unconfine(self) -> W { self.0.unconfine() }
 }
 
@@ -91,6 +120,8 @@ impl<W: io::Write> WriteRaw for StreamWriter<W> {
         self.0.write_all(bytes.as_ref())?;
         Ok(())
     }
+
+    fn offset(&self) -> Option<usize> { Some(self.0.count) }
 }
 
 impl StreamWriter<Vec<u8>> {
@@ -102,27 +133,495 @@ impl StreamWriter<WriteCounter> {
 }
 
 impl StreamWriter<Sink> {
-    pub fn sink<const MAX: usize>() -> Self { Self::new::<MAX>(Sink::default()) }
+    pub fn sink<const MAX: usize>() -> Self { Self::new::<MAX>(io::sink()) }
+}
+
+/// Writer into a caller-provided fixed-size buffer, which doesn't allocate.
+///
+/// If the data don't fit into the buffer, the write fails with
+/// [`BufferOverflow`] error (wrapped into [`io::Error`]) and the buffer is
+/// left untouched past the already written data.
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self { Self { buf, pos: 0 } }
+
+    /// Number of bytes written so far.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// Part of the buffer containing written data.
+    pub fn written(&self) -> &[u8] { &self.buf[..self.pos] }
+
+    /// Releases the buffer, returning the number of bytes written to it.
+    pub fn release(self) -> usize { self.pos }
+}
+
+impl WriteRaw for SliceWriter<'_> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        let capacity = self.buf.len();
+        let end = self.pos + bytes.len();
+        if end > capacity {
+            return Err(BufferOverflow {
+                pos: self.pos,
+                len: bytes.len(),
+                capacity,
+            }
+            .into());
+        }
+        self.buf[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.pos) }
+}
+
+/// Adapter limiting the number of bytes written to any other writer, for
+/// instance when the size of a serialized structure is capped by consensus
+/// rules.
+///
+/// Unlike the limits of the other writers, which are set by their type, the
+/// limit is given at runtime. It is checked before each write, thus the
+/// encoding fails with [`WriteLimitExceeded`] error (wrapped into
+/// [`io::Error`]) as soon as the data would exceed the limit, and none of the
+/// bytes beyond the limit reach the underlying writer.
+#[derive(Clone, Debug)]
+pub struct LimitedWriter<W: WriteRaw> {
+    inner: W,
+    count: usize,
+    limit: usize,
+}
+
+impl<W: WriteRaw> LimitedWriter<W> {
+    /// Wraps `inner` writer, allowing at most `limit` bytes to be written into
+    /// it.
+    pub fn new(limit: usize, inner: W) -> Self {
+        Self {
+            inner,
+            count: 0,
+            limit,
+        }
+    }
+
+    /// Number of bytes written so far.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Maximal number of bytes which may be written.
+    pub fn limit(&self) -> usize { self.limit }
+
+    /// Releases the underlying writer.
+    pub fn unconfine(self) -> W { self.inner }
+}
+
+impl<W: WriteRaw> WriteRaw for LimitedWriter<W> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        WriteLimitExceeded::check(self.limit, self.count, bytes.len())?;
+        self.inner.write_raw::<MAX_LEN>(bytes)?;
+        self.count += bytes.len();
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { self.inner.offset() }
+
+    #[cfg(feature = "trace")]
+    fn enter_scope(&mut self, kind: ScopeKind, lib: &'static str, name: Option<TypeName>) {
+        self.inner.enter_scope(kind, lib, name)
+    }
+
+    #[cfg(feature = "trace")]
+    fn exit_scope(&mut self, kind: ScopeKind) { self.inner.exit_scope(kind) }
+}
+
+/// Writer buffering the encoded data before passing them to the underlying
+/// [`io::Write`], such that encoding of many small fields into a file or a
+/// socket doesn't result in a write call per field.
+///
+/// The writer is confined in the same way as [`StreamWriter`]. The bytes which
+/// reach the underlying writer are always the same as they would be without
+/// buffering: once the value is encoded, [`BufferedWriter::finish`] must be
+/// called to flush the buffer and get the number of bytes written. The writer
+/// doesn't flush itself on drop, since the errors can't be reported there: if
+/// it is dropped without finishing (for instance due to an encoding error) the
+/// buffered data are discarded and only the data which didn't fit the buffer
+/// reach the underlying writer.
+///
+/// With `std` feature the writer may be configured with
+/// [`BufferedWriter::vectored`] to pass large contiguous byte regions, like the
+/// contents of byte arrays and blobs, to the underlying writer without copying
+/// them into the buffer, using [`std::io::Write::write_vectored`].
+#[derive(Debug)]
+pub struct BufferedWriter<W: io::Write> {
+    inner: W,
+    buf: Vec<u8>,
+    count: usize,
+    limit: usize,
+    #[cfg(feature = "std")]
+    vectored: Option<usize>,
+}
+
+impl<W: io::Write> BufferedWriter<W> {
+    /// Default size of the buffer, matching the one of `std::io::BufWriter`.
+    pub const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+    pub fn new<const MAX: usize>(inner: W) -> Self {
+        Self::with_capacity::<MAX>(Self::DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity<const MAX: usize>(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            count: 0,
+            limit: MAX,
+            #[cfg(feature = "std")]
+            vectored: None,
+        }
+    }
+
+    /// Makes the writer pass byte regions of at least `threshold` bytes to the
+    /// underlying writer together with the buffered data in a single
+    /// [`std::io::Write::write_vectored`] call, instead of copying them into
+    /// the buffer or writing them with a separate call.
+    ///
+    /// The bytes reaching the underlying writer are the same as without this
+    /// option. Writers which don't support vectored writes get the buffered
+    /// data and the region in separate calls, as by default.
+    #[cfg(feature = "std")]
+    pub fn vectored(mut self, threshold: usize) -> Self {
+        self.vectored = Some(threshold);
+        self
+    }
+
+    /// Number of bytes written so far, including the ones which are not yet
+    /// flushed.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Flushes the buffered data and the underlying writer, returning the
+    /// writer and the total number of bytes written to it.
+    pub fn finish(mut self) -> io::Result<(W, usize)> {
+        self.flush_buf()?;
+        self.inner.flush()?;
+        Ok((self.inner, self.count))
+    }
+
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let res = self.inner.write_all(&self.buf);
+        // Data which failed to be written are not retried, since the amount of
+        // them reaching the writer is unknown
+        self.buf.clear();
+        res
+    }
+
+    /// Writes the buffered data followed by `bytes`, flushing the buffer.
+    #[cfg(feature = "std")]
+    fn write_vectored(&mut self, bytes: &[u8]) -> io::Result<()> {
+        use std::io::IoSlice;
+
+        let inner = &mut self.inner;
+        let mut head = self.buf.as_slice();
+        let mut tail = bytes;
+        let res = loop {
+            if head.is_empty() {
+                break inner.write_all(tail);
+            }
+            match inner.write_vectored(&[IoSlice::new(head), IoSlice::new(tail)]) {
+                Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+                Ok(len) if len < head.len() => head = &head[len..],
+                Ok(len) => {
+                    tail = &tail[len - head.len()..];
+                    head = &[];
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => break Err(err),
+            }
+        };
+        // same as in `flush_buf`, failed writes are not retried
+        self.buf.clear();
+        res
+    }
+}
+
+impl<W: io::Write> WriteRaw for BufferedWriter<W> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        WriteLimitExceeded::check(self.limit, self.count, bytes.len())?;
+        #[cfg(feature = "std")]
+        if matches!(self.vectored, Some(threshold) if bytes.len() >= threshold) {
+            self.write_vectored(bytes)?;
+            self.count += bytes.len();
+            return Ok(());
+        }
+        if self.buf.len() + bytes.len() > self.buf.capacity() {
+            self.flush_buf()?;
+        }
+        if bytes.len() >= self.buf.capacity() {
+            self.inner.write_all(bytes)?;
+        } else {
+            self.buf.extend_from_slice(bytes);
+        }
+        self.count += bytes.len();
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.count) }
+}
+
+/// Writer feeding the encoded data directly into a hasher, counting the
+/// number of bytes written, such that a value can be committed to without
+/// allocating memory for its serialization.
+///
+/// The writer is confined in the same way as [`StreamWriter`].
+#[cfg(feature = "digest")]
+#[derive(Clone, Debug)]
+pub struct HashingSink<D: digest::Digest> {
+    digest: D,
+    count: u64,
+    limit: usize,
 }
 
-#[derive(Debug, From)]
-pub struct StrictWriter<W: WriteRaw>(W);
+#[cfg(feature = "digest")]
+impl<D: digest::Digest> HashingSink<D> {
+    pub fn new<const MAX: usize>() -> Self { Self::with::<MAX>(D::new()) }
+
+    pub fn with<const MAX: usize>(digest: D) -> Self {
+        Self {
+            digest,
+            count: 0,
+            limit: MAX,
+        }
+    }
+
+    /// Number of bytes written so far.
+    pub fn count(&self) -> u64 { self.count }
+
+    /// Number of bytes written so far as `usize`, saturating on the targets
+    /// where it doesn't fit, which can't be below the limit anyway.
+    fn offset_usize(&self) -> usize { usize::try_from(self.count).unwrap_or(usize::MAX) }
+
+    /// Completes hashing, returning the digest and the number of bytes
+    /// written.
+    pub fn finalize(self) -> (digest::Output<D>, u64) { (self.digest.finalize(), self.count) }
+}
+
+#[cfg(feature = "digest")]
+impl<D: digest::Digest> WriteRaw for HashingSink<D> {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        WriteLimitExceeded::check(self.limit, self.offset_usize(), bytes.len())?;
+        self.digest.update(bytes);
+        self.count += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.offset_usize()) }
+}
+
+/// In-memory writer for secret data, like private keys, which takes care that
+/// no copies of the encoded data are left in memory.
+///
+/// When the buffer has to grow, the data are moved into a new allocation and
+/// the old one is zeroized; the buffer is zeroized on drop, thus no data are
+/// left in memory when the encoding fails. Once the encoding succeeds, the
+/// data are taken with [`ZeroizingWriter::into_inner`] in a
+/// [`Zeroizing`](zeroize::Zeroizing) buffer wiping them on drop. The writer is
+/// confined in the same way as [`StreamWriter`].
+#[cfg(feature = "zeroize")]
+pub struct ZeroizingWriter {
+    buf: zeroize::Zeroizing<Vec<u8>>,
+    limit: usize,
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizingWriter {
+    pub fn new<const MAX: usize>() -> Self { Self::with_capacity::<MAX>(0) }
+
+    pub fn with_capacity<const MAX: usize>(capacity: usize) -> Self {
+        Self {
+            buf: zeroize::Zeroizing::new(Vec::with_capacity(capacity)),
+            limit: MAX,
+        }
+    }
+
+    /// Number of bytes written so far.
+    pub fn count(&self) -> usize { self.buf.len() }
+
+    /// Returns the written data.
+    pub fn into_inner(self) -> zeroize::Zeroizing<Vec<u8>> { self.buf }
+}
+
+#[cfg(feature = "zeroize")]
+impl core::fmt::Debug for ZeroizingWriter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ZeroizingWriter")
+            .field("count", &self.buf.len())
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl WriteRaw for ZeroizingWriter {
+    fn write_raw<const MAX_LEN: usize>(&mut self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let bytes = bytes.as_ref();
+        WriteLimitExceeded::check(self.limit, self.buf.len(), bytes.len())?;
+        let len = self.buf.len() + bytes.len();
+        if len > self.buf.capacity() {
+            let capacity = len.max(self.buf.capacity() * 2).min(self.limit);
+            let mut buf = zeroize::Zeroizing::new(Vec::with_capacity(capacity));
+            buf.extend_from_slice(&self.buf);
+            // the old buffer is zeroized on drop
+            self.buf = buf;
+        }
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn offset(&self) -> Option<usize> { Some(self.buf.len()) }
+}
+
+#[derive(Debug)]
+pub struct StrictWriter<W: WriteRaw> {
+    writer: W,
+    // misuse of a struct or tuple writer, reported once the value is written
+    misuse: Option<io::Error>,
+    #[cfg(feature = "std")]
+    observer: Option<Observer>,
+}
+
+impl<W: WriteRaw> From<W> for StrictWriter<W> {
+    fn from(writer: W) -> Self { Self::with(writer) }
+}
+
+#[cfg(feature = "digest")]
+impl<D: digest::Digest> StrictWriter<HashingSink<D>> {
+    pub fn hashing<const MAX: usize>() -> Self { Self::with(HashingSink::new::<MAX>()) }
+}
+
+#[cfg(feature = "zeroize")]
+impl StrictWriter<ZeroizingWriter> {
+    pub fn zeroizing<const MAX: usize>() -> Self { Self::with(ZeroizingWriter::new::<MAX>()) }
+}
+
+impl<'a> StrictWriter<SliceWriter<'a>> {
+    pub fn with_buf(buf: &'a mut [u8]) -> Self { Self::with(SliceWriter::new(buf)) }
+}
 
 impl StrictWriter<StreamWriter<Vec<u8>>> {
-    pub fn in_memory<const MAX: usize>() -> Self { Self(StreamWriter::in_memory::<MAX>()) }
+    pub fn in_memory<const MAX: usize>() -> Self { Self::with(StreamWriter::in_memory::<MAX>()) }
 }
 
 impl StrictWriter<StreamWriter<WriteCounter>> {
-    pub fn counter<const MAX: usize>() -> Self { Self(StreamWriter::counter::<MAX>()) }
+    pub fn counter<const MAX: usize>() -> Self { Self::with(StreamWriter::counter::<MAX>()) }
 }
 
 impl StrictWriter<StreamWriter<Sink>> {
-    pub fn sink<const MAX: usize>() -> Self { Self(StreamWriter::sink::<MAX>()) }
+    pub fn sink<const MAX: usize>() -> Self { Self::with(StreamWriter::sink::<MAX>()) }
+}
+
+impl<W: io::Write> StrictWriter<BufferedWriter<W>> {
+    pub fn buffered<const MAX: usize>(inner: W) -> Self {
+        Self::with(BufferedWriter::new::<MAX>(inner))
+    }
+
+    /// Flushes all the data to the underlying writer, returning it together
+    /// with the number of bytes written; see [`BufferedWriter::finish`].
+    pub fn finish(self) -> io::Result<(W, usize)> { self.writer.finish() }
+}
+
+impl<W: WriteRaw> StrictWriter<LimitedWriter<W>> {
+    /// Constructs the writer into `writer` which fails once the encoded data
+    /// exceed `limit` bytes; see [`LimitedWriter`].
+    pub fn limited(limit: usize, writer: W) -> Self {
+        Self::with(LimitedWriter::new(limit, writer))
+    }
 }
 
 impl<W: WriteRaw> StrictWriter<W> {
-    pub fn with(writer: W) -> Self { Self(writer) }
-    pub fn unbox(self) -> W { self.0 }
+    pub fn with(writer: W) -> Self {
+        Self {
+            writer,
+            misuse: None,
+            #[cfg(feature = "std")]
+            observer: None,
+        }
+    }
+
+    /// Installs the observer notified about the encoding of each value of a
+    /// structure, tuple, union or enum type; see [`CodecObserver`].
+    #[cfg(feature = "std")]
+    pub fn with_observer(mut self, observer: Arc<dyn CodecObserver>) -> Self {
+        self.observer = Some(observer.into());
+        self
+    }
+
+    pub fn unbox(self) -> W { self.writer }
+
+    /// Fails if the fields of the value written by the completed struct or
+    /// tuple writer were not the declared ones.
+    fn checked(mut self) -> io::Result<Self> {
+        match self.misuse.take() {
+            Some(err) => Err(err),
+            None => Ok(self),
+        }
+    }
+
+    /// Runs the encoding of a value of type `T`, reporting its scope to the
+    /// raw writer and to the observer.
+    #[cfg(any(feature = "trace", feature = "std"))]
+    #[cfg_attr(not(feature = "trace"), allow(unused_variables, unused_mut))]
+    fn scoped<T: StrictType>(
+        mut self,
+        kind: ScopeKind,
+        inner: impl FnOnce(Self) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        #[cfg(feature = "trace")]
+        self.writer.enter_scope(kind, T::STRICT_LIB_NAME, T::strict_name());
+        #[cfg(feature = "std")]
+        let observation = self
+            .observer
+            .as_ref()
+            .map(|observer| Observation::encode(observer, decoded_name::<T>(), &self.writer));
+        let res = inner(self);
+        #[cfg(feature = "std")]
+        if let Some(observation) = observation {
+            observation.encoded(res.as_ref().map(|writer| writer.writer.offset()));
+        }
+        let mut writer = res?;
+        #[cfg(feature = "trace")]
+        writer.writer.exit_scope(kind);
+        Ok(writer)
+    }
+
+    #[cfg(not(any(feature = "trace", feature = "std")))]
+    #[inline]
+    #[allow(clippy::extra_unused_type_parameters)]
+    fn scoped<T: StrictType>(
+        self,
+        _: ScopeKind,
+        inner: impl FnOnce(Self) -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        inner(self)
+    }
+}
+
+/// Kind of a value whose encoding is reported as a scope to the writers
+/// tracing the encoding.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum ScopeKind {
+    Struct,
+    Tuple,
+    Union,
+    Enum,
 }
 
 impl<W: WriteRaw> TypedWrite for StrictWriter<W> {
@@ -131,41 +630,46 @@ impl<W: WriteRaw> TypedWrite for StrictWriter<W> {
     type UnionDefiner = UnionWriter<W>;
     type RawWriter = W;
 
-    unsafe fn raw_writer(&mut self) -> &mut Self::RawWriter { &mut self.0 }
+    unsafe fn raw_writer(&mut self) -> &mut Self::RawWriter { &mut self.writer }
 
     fn write_union<T: StrictUnion>(
         self,
         inner: impl FnOnce(Self::UnionDefiner) -> io::Result<Self>,
     ) -> io::Result<Self> {
-        let writer = UnionWriter::with::<T>(self);
-        inner(writer)
+        self.scoped::<T>(ScopeKind::Union, |writer| {
+            inner(UnionWriter::with::<T>(writer))?.checked()
+        })
     }
 
     fn write_enum<T: StrictEnum>(self, value: T) -> io::Result<Self>
     where u8: From<T> {
-        let mut writer = UnionWriter::with::<T>(self);
-        for (_, name) in T::ALL_VARIANTS {
-            writer = writer.define_variant(vname!(*name));
-        }
-        writer = DefineEnum::complete(writer);
-        writer = writer.write_variant(vname!(value.variant_name()))?;
-        Ok(WriteEnum::complete(writer))
+        self.scoped::<T>(ScopeKind::Enum, |writer| {
+            let mut writer = UnionWriter::with::<T>(writer);
+            for (_, name) in T::ALL_VARIANTS {
+                writer = writer.define_variant(vname!(*name));
+            }
+            writer = DefineEnum::complete(writer);
+            writer = writer.write_variant(vname!(value.variant_name()))?;
+            Ok(WriteEnum::complete(writer))
+        })
     }
 
     fn write_tuple<T: StrictTuple>(
         self,
         inner: impl FnOnce(Self::TupleWriter) -> io::Result<Self>,
     ) -> io::Result<Self> {
-        let writer = StructWriter::tuple::<T>(self);
-        inner(writer)
+        self.scoped::<T>(ScopeKind::Tuple, |writer| {
+            inner(StructWriter::tuple::<T>(writer))?.checked()
+        })
     }
 
     fn write_struct<T: StrictStruct>(
         self,
         inner: impl FnOnce(Self::StructWriter) -> io::Result<Self>,
     ) -> io::Result<Self> {
-        let writer = StructWriter::structure::<T>(self);
-        inner(writer)
+        self.scoped::<T>(ScopeKind::Struct, |writer| {
+            inner(StructWriter::structure::<T>(writer))?.checked()
+        })
     }
 }
 
@@ -175,6 +679,8 @@ pub struct StructWriter<W: WriteRaw, P: StrictParent<W>> {
     name: Option<TypeName>,
     named_fields: Vec<FieldName>,
     tuple_fields: Option<u8>,
+    // whether the fields are known from the written type and are checked
+    declared: bool,
     parent: P,
     cursor: usize,
     _phantom: PhantomData<W>,
@@ -187,6 +693,7 @@ impl<W: WriteRaw, P: StrictParent<W>> StructWriter<W, P> {
             name: T::strict_name(),
             named_fields: T::ALL_FIELDS.iter().map(|name| fname!(*name)).collect(),
             tuple_fields: None,
+            declared: true,
             parent,
             cursor: 0,
             _phantom: default!(),
@@ -199,6 +706,7 @@ impl<W: WriteRaw, P: StrictParent<W>> StructWriter<W, P> {
             name: T::strict_name(),
             named_fields: empty!(),
             tuple_fields: Some(T::FIELD_COUNT),
+            declared: true,
             parent,
             cursor: 0,
             _phantom: default!(),
@@ -211,6 +719,7 @@ impl<W: WriteRaw, P: StrictParent<W>> StructWriter<W, P> {
             name: None,
             named_fields: empty!(),
             tuple_fields: if tuple { Some(0) } else { None },
+            declared: false,
             parent,
             cursor: 0,
             _phantom: default!(),
@@ -238,6 +747,18 @@ impl<W: WriteRaw, P: StrictParent<W>> StructWriter<W, P> {
         self.parent = P::from_write_split(writer, remnant);
         Ok(self)
     }
+
+    /// Completes the writing, recording the misuse in the parent writer: the
+    /// completion can't fail, thus the error is returned once the value is
+    /// written.
+    fn complete_with(self, misuse: Option<String>) -> P {
+        let Some(misuse) = misuse else {
+            return self.parent;
+        };
+        let (mut writer, remnant) = self.parent.into_write_split();
+        writer.misuse.get_or_insert_with(|| misused_writer(misuse));
+        P::from_write_split(writer, remnant)
+    }
 }
 
 impl<W: WriteRaw, P: StrictParent<W>> DefineStruct for StructWriter<W, P> {
@@ -264,35 +785,28 @@ impl<W: WriteRaw, P: StrictParent<W>> DefineStruct for StructWriter<W, P> {
 
 impl<W: WriteRaw, P: StrictParent<W>> WriteStruct for StructWriter<W, P> {
     type Parent = P;
-    fn write_field(mut self, _field: FieldName, value: &impl StrictEncode) -> io::Result<Self> {
+    fn write_field(mut self, field: FieldName, value: &impl StrictEncode) -> io::Result<Self> {
         debug_assert!(self.tuple_fields.is_none(), "using struct method on tuple");
-        /* TODO: Propagate information about the fields at the parent
-        debug_assert!(
-            !self.named_fields.is_empty(),
-            "struct without fields {} asks to write value for field '{field}'",
-            self.name()
-        );
-        assert_eq!(
-            &self.named_fields[self.cursor],
-            &field,
-            "field '{:#}' was not defined for '{}' or is written outside of the order",
-            field,
-            self.name()
-        );
-         */
+        // TODO: Propagate information about the fields of union variants
+        if self.declared && self.named_fields.get(self.cursor) != Some(&field) {
+            return Err(misused_writer(format!(
+                "field '{:#}' was not defined for '{}' or is written outside of the order",
+                field,
+                self.name()
+            )));
+        }
         self.cursor += 1;
         self.write_value(value)
     }
     fn complete(self) -> P {
-        /* TODO: Propagate information about the fields at the parent
-        assert_eq!(
-            self.cursor,
-            self.named_fields.len(),
-            "not all fields were written for '{}'",
-            self.name()
-        );
-         */
-        self.parent
+        let misuse = (self.declared && self.cursor < self.named_fields.len()).then(|| {
+            let missing = self.named_fields[self.cursor..]
+                .iter()
+                .map(|field| format!("'{field:#}'"))
+                .collect::<Vec<_>>();
+            format!("fields {} were not written for '{}'", missing.join(", "), self.name())
+        });
+        self.complete_with(misuse)
     }
 }
 
@@ -320,31 +834,115 @@ impl<W: WriteRaw, P: StrictParent<W>> DefineTuple for StructWriter<W, P> {
 impl<W: WriteRaw, P: StrictParent<W>> WriteTuple for StructWriter<W, P> {
     type Parent = P;
     fn write_field(mut self, value: &impl StrictEncode) -> io::Result<Self> {
-        /* TODO: Propagate information about number of fields at the parent
-        assert!(
-            self.tuple_fields.expect("writing tuple field to structure") as usize > self.cursor,
-            "writing more unnamed fields to the tuple {} than was defined",
-            self.name()
-        );
-         */
+        if self.declared && self.cursor >= self.tuple_fields.unwrap_or_default() as usize {
+            return Err(misused_writer(format!(
+                "writing more unnamed fields to the tuple '{}' than was defined",
+                self.name()
+            )));
+        }
         self.cursor += 1;
         self.write_value(value)
     }
     fn complete(self) -> P {
-        assert_ne!(self.cursor, 0, "tuple '{}' does not have any fields written", self.name());
-        /* TODO: Propagate information about number of fields at the parent
-        assert_eq!(
-            Some(self.cursor as u8),
-            self.unnamed_fields,
-            "not all fields were written for '{}'",
-            self.name()
-        );
-         */
         debug_assert!(self.named_fields.is_empty(), "tuple '{}' written as struct", self.name());
-        self.parent
+        let misuse = if self.declared {
+            (Some(self.cursor as u8) != self.tuple_fields)
+                .then(|| format!("not all fields were written for '{}'", self.name()))
+        } else {
+            // TODO: Propagate information about number of fields of union variants
+            (self.cursor == 0)
+                .then(|| format!("tuple '{}' does not have any fields written", self.name()))
+        };
+        self.complete_with(misuse)
     }
 }
 
+/// Marker of a declared field in the [`FieldsWriter`] typestate, identified by
+/// the [`field_id`](crate::field_id) of its name. Constructed with [`field!`](crate::field)
+/// macro.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DeclaredField<const ID: u64>(FieldName);
+
+impl<const ID: u64> DeclaredField<ID> {
+    #[doc(hidden)]
+    pub fn new(name: FieldName) -> Self { DeclaredField(name) }
+}
+
+/// Marker of the fields of a structure flattened into the structure written
+/// with the [`FieldsWriter`] typestate.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FlattenedFields;
+
+/// Typestate over a struct writer, which checks at compile time that each of
+/// the declared fields is written exactly once and in the declared order.
+///
+/// `F` lists the fields which are still to be written as nested pairs of
+/// [`DeclaredField`] and [`FlattenedFields`] markers ending with `()`; the list
+/// is constructed with [`fields!`](crate::fields) macro. Each write consumes
+/// the head of the list, and the writer is completed only once the list is
+/// empty:
+///
+/// ```
+/// # use strict_encoding::{field, fields, io, FieldsWriter, StrictEncode, TypedWrite};
+/// # #[derive(Default)]
+/// # struct Rate { base: u32, quote: u32 }
+/// # impl strict_encoding::StrictType for Rate { const STRICT_LIB_NAME: &'static str = "Test"; }
+/// # impl strict_encoding::StrictProduct for Rate {}
+/// # impl strict_encoding::StrictStruct for Rate {
+/// #     const ALL_FIELDS: &'static [&'static str] = &["base", "quote"];
+/// # }
+/// impl StrictEncode for Rate {
+///     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+///         writer.write_struct::<Self>(|w| {
+///             let w = FieldsWriter::<_, fields!["base", "quote"]>::new(w);
+///             Ok(w.write_field(field!("base"), &self.base)?
+///                 .write_field(field!("quote"), &self.quote)?
+///                 .complete())
+///         })
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FieldsWriter<S: WriteStruct, F> {
+    writer: S,
+    _fields: PhantomData<F>,
+}
+
+impl<S: WriteStruct, F> FieldsWriter<S, F> {
+    pub fn new(writer: S) -> Self {
+        FieldsWriter {
+            writer,
+            _fields: PhantomData,
+        }
+    }
+}
+
+impl<S: WriteStruct, const ID: u64, F> FieldsWriter<S, (DeclaredField<ID>, F)> {
+    pub fn write_field(
+        self,
+        field: DeclaredField<ID>,
+        value: &impl StrictEncode,
+    ) -> io::Result<FieldsWriter<S, F>> {
+        self.writer.write_field(field.0, value).map(FieldsWriter::new)
+    }
+}
+
+impl<S: WriteStruct, F> FieldsWriter<S, (FlattenedFields, F)> {
+    pub fn write_flattened(
+        self,
+        value: &impl StrictEncodeFields,
+    ) -> io::Result<FieldsWriter<S, F>> {
+        value.strict_encode_fields(self.writer).map(FieldsWriter::new)
+    }
+}
+
+impl<S: WriteStruct> FieldsWriter<S, ()> {
+    pub fn complete(self) -> S::Parent { self.writer.complete() }
+
+    /// Returns the struct writer after all the fields are written into it.
+    pub fn release(self) -> S { self.writer }
+}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub enum VariantType {
     Unit,
@@ -425,11 +1023,14 @@ impl<W: WriteRaw> UnionWriter<W> {
         self
     }
 
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the variant is not
+    /// declared by the type, as it happens when [`StrictSum::variant_name`] of
+    /// a manual implementation returns a name missing from
+    /// [`StrictSum::ALL_VARIANTS`].
     fn _write_variant(mut self, name: VariantName, variant_type: VariantType) -> io::Result<Self> {
-        let (variant, t) =
-            self.defined_variant.iter().find(|(f, _)| f.name == name).unwrap_or_else(|| {
-                panic!("variant '{:#}' was not defined in '{}'", &name, self.name())
-            });
+        let Some((variant, t)) = self.defined_variant.iter().find(|(f, _)| f.name == name) else {
+            return Err(undeclared_variant(VariantError::typed(self.name(), name)));
+        };
         assert_eq!(
             *t,
             variant_type,
@@ -545,6 +1146,24 @@ impl<W: WriteRaw> WriteEnum for UnionWriter<W> {
     fn complete(self) -> Self::Parent { self._complete_write() }
 }
 
+#[cfg(feature = "std")]
+fn undeclared_variant(err: VariantError<VariantName>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+#[cfg(not(feature = "std"))]
+fn undeclared_variant(_: VariantError<VariantName>) -> io::Error {
+    io::ErrorKind::InvalidInput.into()
+}
+
+#[cfg(feature = "std")]
+fn misused_writer(misuse: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, misuse)
+}
+
+#[cfg(not(feature = "std"))]
+fn misused_writer(_: String) -> io::Error { io::ErrorKind::InvalidInput.into() }
+
 pub trait StrictParent<W: WriteRaw>: TypedParent {
     type Remnant;
     fn from_write_split(writer: StrictWriter<W>, remnant: Self::Remnant) -> Self;
@@ -601,6 +1220,7 @@ impl<W: WriteRaw, P: StrictParent<W>> SplitParent for StructWriter<W, P> {
             name: remnant.name,
             named_fields: remnant.named_fields,
             tuple_fields: remnant.tuple_fields,
+            declared: remnant.declared,
             parent,
             cursor: remnant.cursor,
             _phantom: none!(),
@@ -612,6 +1232,7 @@ impl<W: WriteRaw, P: StrictParent<W>> SplitParent for StructWriter<W, P> {
             name: self.name,
             named_fields: self.named_fields,
             tuple_fields: self.tuple_fields,
+            declared: self.declared,
             parent: none!(),
             cursor: self.cursor,
             _phantom: none!(),
@@ -628,3 +1249,533 @@ impl<W: WriteRaw> StrictParent<W> for ParentDumb {
     fn from_write_split(_: StrictWriter<W>, _: Self::Remnant) -> Self { unreachable!() }
     fn into_write_split(self) -> (StrictWriter<W>, Self::Remnant) { unreachable!() }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use amplify::confinement::{Confined, SmallString, TinyVec};
+
+    use super::*;
+    use crate::test::encode;
+    use crate::{StrictDecode, StrictType};
+
+    fn assert_len<T: StrictEncode + core::fmt::Debug + PartialEq>(val: &T) {
+        let len = val.strict_encoded_len::<{ u16::MAX as usize }>().unwrap();
+        assert_eq!(len, encode(val).len());
+    }
+
+    #[test]
+    fn encoded_len() {
+        assert_len(&0u8);
+        assert_len(&u128::MAX);
+        assert_len(&(1u16, 2u32, [3u8; 17]));
+        assert_len(&Some(Some(7u64)));
+        assert_len(&None::<u64>);
+        assert_len(&SmallString::try_from(s!("strict encoding")).unwrap());
+        assert_len(&TinyVec::try_from(vec![Some(1u16), None, Some(3)]).unwrap());
+        let map = BTreeMap::from([(1u8, TinyVec::try_from(vec![0u32; 5]).unwrap())]);
+        assert_len(&Confined::<BTreeMap<_, _>, 0, 8>::try_from(map).unwrap());
+        assert_len(&Result::<(u8, u8), u16>::Err(0xFFFF));
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Data {
+        a: u16,
+        b: [u8; 3],
+        c: Option<u32>,
+    }
+
+    fn data() -> Data {
+        Data {
+            a: 0x0102,
+            b: [3, 4, 5],
+            c: Some(0x06070809),
+        }
+    }
+
+    #[test]
+    fn undeclared_variant() {
+        // manual implementation with the variant renamed only in the table
+        #[derive(Copy, Clone, Debug)]
+        struct Renamed;
+        impl StrictType for Renamed {
+            const STRICT_LIB_NAME: &'static str = "Test";
+        }
+        impl StrictSum for Renamed {
+            const ALL_VARIANTS: &'static [(u8, &'static str)] = &[(1, "replacement")];
+            fn variant_name(&self) -> &'static str { "original" }
+        }
+        impl StrictEnum for Renamed {}
+        impl TryFrom<u8> for Renamed {
+            type Error = VariantError<u8>;
+            fn try_from(_: u8) -> Result<Self, Self::Error> { Ok(Renamed) }
+        }
+        impl From<Renamed> for u8 {
+            fn from(_: Renamed) -> u8 { 1 }
+        }
+        impl StrictEncode for Renamed {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                writer.write_enum(*self)
+            }
+        }
+
+        let err = Renamed.strict_encode(StrictWriter::in_memory::<16>()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "unexpected variant original for enum or union Some(\"Renamed\")"
+        );
+    }
+
+    #[test]
+    fn slice_writer() {
+        let expected = encode(&data());
+        let mut buf = [0xFFu8; 10];
+        let writer = data().strict_encode(StrictWriter::with_buf(&mut buf)).unwrap();
+        assert_eq!(writer.unbox().release(), 10);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn slice_writer_overflow() {
+        let mut buf = [0xFFu8; 12];
+        let err = data().strict_encode(StrictWriter::with_buf(&mut buf[..9])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(
+            BufferOverflow::from_io(&err),
+            Some(BufferOverflow {
+                pos: 6,
+                len: 4,
+                capacity: 9
+            })
+        );
+        // the data written before the failure are kept, the rest is untouched
+        assert_eq!(buf, [2, 1, 3, 4, 5, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(BufferOverflow::from_io(&io::ErrorKind::WriteZero.into()), None);
+    }
+
+    #[test]
+    fn limited_writer() {
+        let expected = encode(&data());
+        let writer = data().strict_encode(StrictWriter::limited(
+            10,
+            StreamWriter::in_memory::<{ u16::MAX as usize }>(),
+        ));
+        let writer = writer.unwrap().unbox();
+        assert_eq!((writer.count(), writer.limit()), (10, 10));
+        assert_eq!(writer.unconfine().unconfine(), expected);
+
+        let writer = StrictWriter::limited(10, StreamWriter::counter::<{ u16::MAX as usize }>());
+        let counter = data().strict_encode(writer).unwrap().unbox().unconfine().unconfine();
+        assert_eq!(counter.count, 10);
+    }
+
+    #[test]
+    fn limited_writer_exceeded() {
+        let exceeded = WriteLimitExceeded {
+            limit: 9,
+            total: 6,
+            len: 4,
+        };
+
+        let mut recorder = Recorder::default();
+        let writer = StreamWriter::new::<{ u16::MAX as usize }>(&mut recorder);
+        let err = data().strict_encode(StrictWriter::limited(9, writer)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(WriteLimitExceeded::from_io(&err), Some(exceeded));
+        // nothing beyond the last write fitting the limit reaches the sink
+        assert_eq!(recorder.data, &encode(&data())[..6]);
+
+        let writer = StrictWriter::limited(9, StreamWriter::counter::<{ u16::MAX as usize }>());
+        let err = data().strict_encode(writer).unwrap_err();
+        assert_eq!(WriteLimitExceeded::from_io(&err), Some(exceeded));
+
+        // the limits of the writers confined by their type are reported alike
+        let err = data().strict_encode(StrictWriter::in_memory::<9>()).unwrap_err();
+        assert_eq!(WriteLimitExceeded::from_io(&err), Some(exceeded));
+        let err = data().strict_encode(StrictWriter::buffered::<9>(Recorder::default()));
+        assert_eq!(WriteLimitExceeded::from_io(&err.unwrap_err()), Some(exceeded));
+        assert_eq!(WriteLimitExceeded::from_io(&io::ErrorKind::InvalidInput.into()), None);
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        data: Vec<u8>,
+        writes: usize,
+    }
+
+    impl io::Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    fn buffered_writer() {
+        let val = TinyVec::try_from(vec![data(); 100]).unwrap();
+        let expected = encode(&val);
+
+        let mut unbuffered = Recorder::default();
+        val.strict_encode(StrictWriter::with(StreamWriter::new::<{ u16::MAX as usize }>(
+            &mut unbuffered,
+        )))
+        .unwrap();
+        assert_eq!(unbuffered.data, expected);
+        assert!(unbuffered.writes > 100);
+
+        let writer = StrictWriter::buffered::<{ u16::MAX as usize }>(Recorder::default());
+        let (recorder, count) = val.strict_encode(writer).unwrap().finish().unwrap();
+        assert_eq!(count, expected.len());
+        assert_eq!(recorder.data, expected);
+        assert_eq!(recorder.writes, 1);
+    }
+
+    #[test]
+    fn buffered_writer_large() {
+        let mut recorder = Recorder::default();
+        let mut writer = BufferedWriter::with_capacity::<100>(16, &mut recorder);
+        writer.write_raw::<32>([7u8; 32]).unwrap();
+        writer.write_raw::<1>([1u8]).unwrap();
+        writer.write_raw::<20>([8u8; 20]).unwrap();
+        writer.write_raw::<4>([9u8; 4]).unwrap();
+        assert_eq!(writer.count(), 57);
+        let (_, count) = writer.finish().unwrap();
+        assert_eq!(count, 57);
+        assert_eq!(recorder.data, [[7u8; 32].as_slice(), &[1], &[8; 20], &[9; 4]].concat());
+        // data not fitting the buffer bypass it
+        assert_eq!(recorder.writes, 4);
+    }
+
+    #[test]
+    fn buffered_writer_error() {
+        let val = TinyVec::try_from(vec![data(); 10]).unwrap();
+        let mut unbuffered = Recorder::default();
+        let writer = StrictWriter::with(StreamWriter::new::<32>(&mut unbuffered));
+        let err = val.strict_encode(writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // the buffered data are discarded if the writer is not finished
+        let mut recorder = Recorder::default();
+        let writer = StrictWriter::with(BufferedWriter::new::<32>(&mut recorder));
+        let err = val.strict_encode(writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(recorder.data.is_empty());
+        assert_eq!(recorder.writes, 0);
+        assert!(!unbuffered.data.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    enum Call {
+        Write(usize),
+        Vectored(Vec<usize>),
+    }
+
+    /// Writer recording the lengths of the buffers passed to each call, which
+    /// accepts at most `chunk` bytes per call, if given.
+    #[cfg(feature = "std")]
+    #[derive(Debug, Default)]
+    struct VectoredRecorder {
+        data: Vec<u8>,
+        calls: Vec<Call>,
+        chunk: Option<usize>,
+    }
+
+    #[cfg(feature = "std")]
+    impl VectoredRecorder {
+        fn accept(&mut self, bufs: &[&[u8]]) -> usize {
+            let mut left = self.chunk.unwrap_or(usize::MAX);
+            let mut len = 0;
+            for buf in bufs {
+                let buf = &buf[..buf.len().min(left)];
+                self.data.extend_from_slice(buf);
+                left -= buf.len();
+                len += buf.len();
+            }
+            len
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl io::Write for VectoredRecorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.calls.push(Call::Write(buf.len()));
+            Ok(self.accept(&[buf]))
+        }
+        fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> io::Result<usize> {
+            self.calls.push(Call::Vectored(bufs.iter().map(|buf| buf.len()).collect()));
+            Ok(self.accept(&bufs.iter().map(|buf| &**buf).collect::<Vec<_>>()))
+        }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[cfg(feature = "std")]
+    fn blobs() -> (u16, Confined<Vec<u8>, 0, { u16::MAX as usize }>, [u8; 32], u8) {
+        (0x0102, Confined::try_from(vec![0xA5; 100]).unwrap(), [0x5A; 32], 0xFF)
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn buffered_writer_vectored() {
+        let expected = encode(&blobs());
+
+        let writer = BufferedWriter::with_capacity::<1024>(16, VectoredRecorder::default());
+        let writer = blobs().strict_encode(StrictWriter::with(writer.vectored(32))).unwrap();
+        let (recorder, count) = writer.unbox().finish().unwrap();
+        assert_eq!(count, expected.len());
+        assert_eq!(recorder.data, expected);
+        // the integer and the blob length are written together with the blob,
+        // while the array follows the empty buffer and is written alone
+        assert_eq!(recorder.calls, [Call::Vectored(vec![4, 100]), Call::Write(32), Call::Write(1)]);
+
+        // regions below the threshold are buffered as usual
+        let writer = BufferedWriter::with_capacity::<1024>(256, VectoredRecorder::default());
+        let writer = blobs().strict_encode(StrictWriter::with(writer.vectored(128))).unwrap();
+        let (recorder, _) = writer.unbox().finish().unwrap();
+        assert_eq!(recorder.data, expected);
+        assert_eq!(recorder.calls, [Call::Write(expected.len())]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn buffered_writer_vectored_partial() {
+        let expected = encode(&blobs());
+        for chunk in [1, 3, 4, 5, 60, 103] {
+            let recorder = VectoredRecorder {
+                chunk: Some(chunk),
+                ..default!()
+            };
+            let writer = BufferedWriter::with_capacity::<1024>(16, recorder).vectored(32);
+            let writer = blobs().strict_encode(StrictWriter::with(writer)).unwrap();
+            let (recorder, count) = writer.unbox().finish().unwrap();
+            assert_eq!(count, expected.len());
+            assert_eq!(recorder.data, expected, "chunk {chunk}");
+            assert_eq!(recorder.calls[0], Call::Vectored(vec![4, 100]));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn buffered_writer_vectored_fallback() {
+        let expected = encode(&blobs());
+
+        let mut plain = Recorder::default();
+        let writer = BufferedWriter::with_capacity::<1024>(16, &mut plain);
+        blobs().strict_encode(StrictWriter::with(writer)).unwrap().unbox().finish().unwrap();
+
+        // writers without vectored writes get the same calls as without the
+        // option
+        let mut recorder = Recorder::default();
+        let writer = BufferedWriter::with_capacity::<1024>(16, &mut recorder).vectored(32);
+        blobs().strict_encode(StrictWriter::with(writer)).unwrap().unbox().finish().unwrap();
+        assert_eq!(recorder.data, expected);
+        assert_eq!(recorder.data, plain.data);
+        assert_eq!(recorder.writes, plain.writes);
+    }
+
+    #[test]
+    #[cfg(feature = "digest")]
+    fn hashing_sink() {
+        use sha2::{Digest, Sha256};
+
+        let val = (data(), TinyVec::try_from(vec![data(); 16]).unwrap());
+        let (digest, count) = val
+            .strict_encode(StrictWriter::<HashingSink<Sha256>>::hashing::<{ u16::MAX as usize }>())
+            .unwrap()
+            .unbox()
+            .finalize();
+        let data = encode(&val);
+        assert_eq!(count, data.len() as u64);
+        assert_eq!(digest, Sha256::digest(&data));
+
+        let err = val.strict_encode(StrictWriter::<HashingSink<Sha256>>::hashing::<16>());
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn encoded_len_confined() {
+        let val = [0u8; 32];
+        assert_eq!(val.strict_encoded_len::<32>().unwrap(), 32);
+        let counted = val.strict_encoded_len::<31>().unwrap_err();
+        let written = val.strict_encode(StrictWriter::in_memory::<31>()).unwrap_err();
+        assert_eq!(counted.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(counted.kind(), written.kind());
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Quote {
+        base: u32,
+        quote: u32,
+        spread: u16,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = "Test", crate = crate)]
+    struct Pair(u8, u16);
+
+    impl StrictEncode for Quote {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            write_struct!(writer, Self {
+                "base" => &self.base,
+                "quote" => &self.quote,
+                "spread" => &self.spread,
+            })
+        }
+    }
+
+    fn write_quote(fields: &[&'static str]) -> io::Result<Vec<u8>> {
+        StrictWriter::in_memory::<16>()
+            .write_struct::<Quote>(|mut w| {
+                for field in fields {
+                    w = WriteStruct::write_field(w, fname!(*field), &0u32)?;
+                }
+                Ok(WriteStruct::complete(w))
+            })
+            .map(|w| w.unbox().unconfine())
+    }
+
+    fn write_pair(fields: &[u8]) -> io::Result<Vec<u8>> {
+        StrictWriter::in_memory::<16>()
+            .write_tuple::<Pair>(|mut w| {
+                for field in fields {
+                    w = WriteTuple::write_field(w, field)?;
+                }
+                Ok(WriteTuple::complete(w))
+            })
+            .map(|w| w.unbox().unconfine())
+    }
+
+    fn misuse(res: io::Result<Vec<u8>>) -> String {
+        let err = res.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        err.to_string()
+    }
+
+    #[test]
+    fn write_struct_macro() {
+        let quote = Quote {
+            base: 1,
+            quote: 2,
+            spread: 3,
+        };
+        assert_eq!(encode(&quote), [1, 0, 0, 0, 2, 0, 0, 0, 3, 0]);
+        assert_eq!(write_quote(&["base", "quote", "spread"]).unwrap(), [0; 12]);
+    }
+
+    #[test]
+    fn struct_missing_field() {
+        assert_eq!(
+            misuse(write_quote(&["base", "quote"])),
+            "fields 'spread' were not written for 'Quote'"
+        );
+        assert_eq!(
+            misuse(write_quote(&[])),
+            "fields 'base', 'quote', 'spread' were not written for 'Quote'"
+        );
+    }
+
+    #[test]
+    fn struct_reordered_field() {
+        assert_eq!(
+            misuse(write_quote(&["base", "spread", "quote"])),
+            "field 'spread' was not defined for 'Quote' or is written outside of the order"
+        );
+    }
+
+    #[test]
+    fn tuple_missing_field() {
+        assert_eq!(misuse(write_pair(&[1])), "not all fields were written for 'Pair'");
+    }
+
+    #[test]
+    fn tuple_extra_field() {
+        assert_eq!(
+            misuse(write_pair(&[1, 2, 3])),
+            "writing more unnamed fields to the tuple 'Pair' than was defined"
+        );
+    }
+
+    #[test]
+    fn nested_misuse() {
+        // the misuse of the inner struct writer fails the encoding of the outer value
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Outer {
+            inner: Truncated,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Truncated {
+            first: u8,
+            second: u8,
+        }
+        impl StrictEncode for Truncated {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                writer.write_struct::<Self>(|w| {
+                    Ok(WriteStruct::complete(WriteStruct::write_field(
+                        w,
+                        fname!("first"),
+                        &self.first,
+                    )?))
+                })
+            }
+        }
+
+        let err = Outer::default().strict_encode(StrictWriter::in_memory::<16>()).unwrap_err();
+        assert_eq!(err.to_string(), "fields 'second' were not written for 'Truncated'");
+        let err =
+            Some(Truncated::default()).strict_encode(StrictWriter::in_memory::<16>()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn fields_typestate() {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType, StrictEncode)]
+        #[strict_type(lib = "Test", crate = crate, flattenable)]
+        struct Header {
+            version: u8,
+            flags: u16,
+        }
+
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        #[derive(StrictType)]
+        #[strict_type(lib = "Test", crate = crate)]
+        struct Message {
+            #[strict_type(flatten)]
+            header: Header,
+            body: u32,
+        }
+        impl StrictEncode for Message {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                writer.write_struct::<Self>(|w| {
+                    let w = FieldsWriter::<_, fields![.., "body"]>::new(w);
+                    Ok(w.write_flattened(&self.header)?
+                        .write_field(field!("body"), &self.body)?
+                        .complete())
+                })
+            }
+        }
+
+        let msg = Message {
+            header: Header {
+                version: 1,
+                flags: 0x0302,
+            },
+            body: 4,
+        };
+        assert_eq!(encode(&msg), [1, 2, 3, 4, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,66 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Example crate exposing strict-encoded types to JavaScript with
+//! `wasm-bindgen` on top of the helpers of strict encoding `wasm` feature.
+
+use amplify::confinement::TinyString;
+use strict_encoding::{StrictDecode, StrictEncode, StrictType};
+use wasm_bindgen::prelude::*;
+
+pub const LIB_NAME_WASM: &str = "Wasm";
+
+/// Limit on the size of the encoded invoices.
+pub const INVOICE_MAX_LEN: usize = 0x100;
+
+#[wasm_bindgen]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_WASM)]
+pub struct Invoice {
+    amount: u64,
+    memo: TinyString,
+}
+
+#[wasm_bindgen]
+impl Invoice {
+    #[wasm_bindgen(constructor)]
+    pub fn new(amount: u64, memo: String) -> Result<Invoice, String> {
+        let memo = TinyString::try_from(memo).map_err(|err| err.to_string())?;
+        Ok(Invoice { amount, memo })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn amount(&self) -> u64 { self.amount }
+
+    #[wasm_bindgen(getter)]
+    pub fn memo(&self) -> String { self.memo.to_string() }
+}
+
+#[wasm_bindgen(js_name = encodeInvoice)]
+pub fn encode_invoice(invoice: &Invoice) -> Result<Box<[u8]>, String> {
+    Ok(strict_encoding::wasm::serialize::<INVOICE_MAX_LEN>(invoice)?)
+}
+
+#[wasm_bindgen(js_name = decodeInvoice)]
+pub fn decode_invoice(data: &[u8]) -> Result<Invoice, String> {
+    Ok(strict_encoding::wasm::deserialize::<Invoice, INVOICE_MAX_LEN>(data)?)
+}
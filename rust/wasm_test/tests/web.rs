@@ -0,0 +1,57 @@
+// Strict encoding library for deterministic binary serialization.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr. Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright 2022-2024 UBIDECO Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Round-trips of the values through the exported functions, run in a browser
+//! with `wasm-pack test` or natively with `cargo test`.
+
+use strict_encoding_wasm_test::{decode_invoice, encode_invoice, Invoice};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn roundtrip() {
+    let invoice = Invoice::new(1000, "coffee".to_owned()).unwrap();
+    let data = encode_invoice(&invoice).unwrap();
+    assert_eq!(&*data, b"\xe8\x03\x00\x00\x00\x00\x00\x00\x06coffee");
+    let decoded = decode_invoice(&data).unwrap();
+    assert_eq!(decoded, invoice);
+    assert_eq!(decoded.amount(), 1000);
+    assert_eq!(decoded.memo(), "coffee");
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn errors() {
+    assert!(Invoice::new(0, "x".repeat(256)).is_err());
+    let data = encode_invoice(&Invoice::new(1, "tea".to_owned()).unwrap()).unwrap();
+    assert!(!decode_invoice(&data[..data.len() - 1]).unwrap_err().is_empty());
+    let mut data = data.into_vec();
+    data.push(0);
+    assert_eq!(
+        decode_invoice(&data).unwrap_err(),
+        "data are not entirely consumed during strict deserialize operation: 1 bytes are left."
+    );
+    assert!(decode_invoice(&[0; 0x101]).unwrap_err().contains("confinement"));
+}